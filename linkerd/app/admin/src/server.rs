@@ -1,13 +1,25 @@
 //! Serves an HTTP admin server.
 //!
 //! * `GET /metrics` -- reports prometheus-formatted metrics.
+//! * `GET /accounting` -- reports prometheus-formatted per-identity accounting
+//!   of inbound traffic.
 //! * `GET /ready` -- returns 200 when the proxy is ready to participate in meshed
-//!   traffic.
+//!   traffic, i.e. once each of the subsystems it depends on is ready, or 503
+//!   with the subsystems that are not yet ready.
 //! * `GET /live` -- returns 200 when the proxy is live.
 //! * `GET /proxy-log-level` -- returns the current proxy tracing filter.
 //! * `PUT /proxy-log-level` -- sets a new tracing filter.
+//! * `GET /endpoint-pins` -- lists backends pinned to specific endpoints.
+//! * `PUT /endpoint-pins` -- pins a backend's requests to an endpoint.
+//! * `DELETE /endpoint-pins` -- clears a backend's pin.
+//! * `POST /discovery/evict` -- evicts the cached discovery for the `addr` given
+//!   in the query string, so that it is re-resolved.
 //! * `GET /tasks` -- returns a dump of spawned Tokio tasks (when enabled by the
 //!   tracing configuration).
+//! * `GET /debug/pprof/profile` -- collects a CPU profile for the number of
+//!   `seconds` given in the query string (when built with the `pprof` feature).
+//!   Allocation profiles are not available, since the proxy uses the system
+//!   allocator, which does not record allocation samples.
 //! * `POST /shutdown` -- shuts down the proxy.
 
 use futures::future::{self, TryFutureExt};
@@ -17,7 +29,10 @@ use hyper::{
     Request, Response,
 };
 use linkerd_app_core::{
+    disco_cache::Evictions,
+    endpoint_pins::EndpointPins,
     metrics::{self as metrics, FmtMetrics},
+    profiles::LookupAddr,
     proxy::http::ClientHandle,
     trace, Error,
 };
@@ -28,16 +43,23 @@ use std::{
 };
 use tokio::sync::mpsc;
 
+mod discovery;
+mod endpoint_pins;
 mod json;
 mod log;
+#[cfg(feature = "pprof")]
+mod pprof;
 mod readiness;
 
 pub use self::readiness::{Latch, Readiness};
 
 #[derive(Clone)]
-pub struct Admin<M> {
+pub struct Admin<M, A> {
     metrics: metrics::Serve<M>,
+    accounting: metrics::Serve<A>,
     tracing: trace::Handle,
+    endpoint_pins: EndpointPins,
+    discovery_evictions: Evictions<LookupAddr>,
     ready: Readiness,
     shutdown_tx: mpsc::UnboundedSender<()>,
 }
@@ -45,18 +67,24 @@ pub struct Admin<M> {
 pub type ResponseFuture =
     Pin<Box<dyn Future<Output = Result<Response<Body>, Error>> + Send + 'static>>;
 
-impl<M> Admin<M> {
+impl<M, A> Admin<M, A> {
     pub fn new(
         metrics: M,
+        accounting: A,
         ready: Readiness,
         shutdown_tx: mpsc::UnboundedSender<()>,
         tracing: trace::Handle,
+        endpoint_pins: EndpointPins,
+        discovery_evictions: Evictions<LookupAddr>,
     ) -> Self {
         Self {
             metrics: metrics::Serve::new(metrics),
+            accounting: metrics::Serve::new(accounting),
             ready,
             shutdown_tx,
             tracing,
+            endpoint_pins,
+            discovery_evictions,
         }
     }
 
@@ -68,9 +96,15 @@ impl<M> Admin<M> {
                 .body("ready\n".into())
                 .expect("builder with known status code must not fail")
         } else {
+            let pending = self.ready.pending();
+            let body = if pending.is_empty() {
+                "not ready\n".to_string()
+            } else {
+                format!("not ready: {}\n", pending.join(", "))
+            };
             Response::builder()
                 .status(StatusCode::SERVICE_UNAVAILABLE)
-                .body("not ready\n".into())
+                .body(body.into())
                 .expect("builder with known status code must not fail")
         }
     }
@@ -183,9 +217,10 @@ impl<M> Admin<M> {
     }
 }
 
-impl<M, B> tower::Service<http::Request<B>> for Admin<M>
+impl<M, A, B> tower::Service<http::Request<B>> for Admin<M, A>
 where
     M: FmtMetrics,
+    A: FmtMetrics,
     B: HttpBody + Send + Sync + 'static,
     B::Error: Into<Error>,
     B::Data: Send,
@@ -209,6 +244,13 @@ where
                 });
                 Box::pin(future::ok(rsp))
             }
+            "/accounting" => {
+                let rsp = self.accounting.serve(req).unwrap_or_else(|error| {
+                    ::tracing::error!(%error, "Failed to format accounting metrics");
+                    Self::internal_error_rsp(error)
+                });
+                Box::pin(future::ok(rsp))
+            }
 
             "/proxy-log-level" => {
                 if !Self::client_is_localhost(&req) {
@@ -242,6 +284,41 @@ where
 
             "/env.json" => Box::pin(future::ok(Self::env_rsp(req))),
 
+            "/endpoint-pins" => {
+                if !Self::client_is_localhost(&req) {
+                    return Box::pin(future::ok(Self::forbidden_not_localhost()));
+                }
+
+                Box::pin(future::ok(endpoint_pins::serve(&self.endpoint_pins, &req)))
+            }
+
+            "/discovery/evict" => {
+                if !Self::client_is_localhost(&req) {
+                    return Box::pin(future::ok(Self::forbidden_not_localhost()));
+                }
+
+                Box::pin(future::ok(discovery::evict(
+                    &self.discovery_evictions,
+                    &req,
+                )))
+            }
+
+            #[cfg(feature = "pprof")]
+            "/debug/pprof/profile" => {
+                if !Self::client_is_localhost(&req) {
+                    return Box::pin(future::ok(Self::forbidden_not_localhost()));
+                }
+
+                if req.method() != http::Method::GET {
+                    return Box::pin(future::ok(Self::method_not_allowed()));
+                }
+
+                Box::pin(pprof::profile(req).or_else(|error| {
+                    tracing::error!(error, "Failed to collect CPU profile");
+                    future::ok(Self::internal_error_rsp(error))
+                }))
+            }
+
             "/shutdown" => {
                 if req.method() == http::Method::POST {
                     if Self::client_is_localhost(&req) {
@@ -276,7 +353,7 @@ mod tests {
 
         let (_, t) = trace::Settings::default().build();
         let (s, _) = mpsc::unbounded_channel();
-        let admin = Admin::new((), r, s, t);
+        let admin = Admin::new((), (), r, s, t, EndpointPins::default(), Default::default());
         macro_rules! call {
             () => {{
                 let r = Request::builder()
@@ -297,4 +374,32 @@ mod tests {
         drop(l1);
         assert_eq!(call!().status(), StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn ready_when_identity_obtained() {
+        let (r, [identity]) = Readiness::subsystems(["identity"]);
+
+        let (_, t) = trace::Settings::default().build();
+        let (s, _) = mpsc::unbounded_channel();
+        let admin = Admin::new((), (), r, s, t, EndpointPins::default(), Default::default());
+        macro_rules! call {
+            () => {{
+                let r = Request::builder()
+                    .method(Method::GET)
+                    .uri("http://0.0.0.0/ready")
+                    .body(Body::empty())
+                    .unwrap();
+                let f = admin.clone().oneshot(r);
+                timeout(TIMEOUT, f).await.expect("timeout").expect("call")
+            }};
+        }
+
+        let rsp = call!();
+        assert_eq!(rsp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+        assert_eq!(body, "not ready: identity\n");
+
+        identity.release();
+        assert_eq!(call!().status(), StatusCode::OK);
+    }
 }
@@ -0,0 +1,40 @@
+use super::{endpoint_pins::query_param, json};
+use http::StatusCode;
+use hyper::Body;
+use linkerd_app_core::{disco_cache::Evictions, profiles::LookupAddr, Addr};
+
+/// Evicts a destination's cached discovery, so that it is re-resolved when it
+/// is next used.
+///
+/// * `POST ?addr=<name:port|ip:port>` evicts the destination's discovery.
+pub(super) fn evict<B>(
+    evictions: &Evictions<LookupAddr>,
+    req: &http::Request<B>,
+) -> http::Response<Body> {
+    if req.method() != http::Method::POST {
+        return http::Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .header(http::header::ALLOW, "POST")
+            .body(Body::empty())
+            .expect("builder with known status code must not fail");
+    }
+
+    let addr = match query_param(req, "addr").map(str::parse::<Addr>) {
+        Some(Ok(addr)) => addr,
+        Some(Err(error)) => {
+            return json::json_error_rsp(format!("invalid addr: {error}"), StatusCode::BAD_REQUEST)
+        }
+        None => return json::json_error_rsp("an addr must be specified", StatusCode::BAD_REQUEST),
+    };
+
+    let status = if evictions.evict(&LookupAddr(addr.clone())) {
+        tracing::info!(%addr, "Evicted cached discovery");
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    };
+    http::Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .expect("builder with known status code must not fail")
+}
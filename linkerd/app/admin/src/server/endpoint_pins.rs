@@ -0,0 +1,104 @@
+use super::json;
+use http::StatusCode;
+use hyper::Body;
+use linkerd_app_core::{endpoint_pins::EndpointPins, NameAddr};
+use std::net::SocketAddr;
+
+/// Serves endpoint pins for load balanced backends.
+///
+/// * `GET` lists all pinned backends.
+/// * `PUT ?backend=<name:port>&endpoint=<ip:port>` pins all of the backend's
+///   requests to the endpoint.
+/// * `DELETE ?backend=<name:port>` clears the backend's pin.
+pub(super) fn serve<B>(pins: &EndpointPins, req: &http::Request<B>) -> http::Response<Body> {
+    match *req.method() {
+        http::Method::GET => {
+            if let Err(not_acceptable) = json::accepts_json(req) {
+                return not_acceptable;
+            }
+            let pins = pins
+                .pins()
+                .into_iter()
+                .map(|(backend, endpoint)| {
+                    serde_json::json!({
+                        "backend": backend.to_string(),
+                        "endpoint": endpoint.to_string(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            json::json_rsp(&pins)
+        }
+
+        http::Method::PUT => {
+            let backend = match backend(req) {
+                Ok(backend) => backend,
+                Err(rsp) => return rsp,
+            };
+            let endpoint = match query_param(req, "endpoint").map(str::parse::<SocketAddr>) {
+                Some(Ok(endpoint)) => endpoint,
+                Some(Err(error)) => {
+                    return json::json_error_rsp(
+                        format!("invalid endpoint: {error}"),
+                        StatusCode::BAD_REQUEST,
+                    )
+                }
+                None => {
+                    return json::json_error_rsp(
+                        "an endpoint must be specified",
+                        StatusCode::BAD_REQUEST,
+                    )
+                }
+            };
+            pins.pin(backend, endpoint);
+            mk_rsp(StatusCode::NO_CONTENT)
+        }
+
+        http::Method::DELETE => {
+            let backend = match backend(req) {
+                Ok(backend) => backend,
+                Err(rsp) => return rsp,
+            };
+            match pins.unpin(&backend) {
+                Some(_) => mk_rsp(StatusCode::NO_CONTENT),
+                None => mk_rsp(StatusCode::NOT_FOUND),
+            }
+        }
+
+        _ => http::Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .header(http::header::ALLOW, "GET")
+            .header(http::header::ALLOW, "PUT")
+            .header(http::header::ALLOW, "DELETE")
+            .body(Body::empty())
+            .expect("builder with known status code must not fail"),
+    }
+}
+
+fn backend<B>(req: &http::Request<B>) -> Result<NameAddr, http::Response<Body>> {
+    match query_param(req, "backend").map(str::parse::<NameAddr>) {
+        Some(Ok(backend)) => Ok(backend),
+        Some(Err(error)) => Err(json::json_error_rsp(
+            format!("invalid backend: {error}"),
+            StatusCode::BAD_REQUEST,
+        )),
+        None => Err(json::json_error_rsp(
+            "a backend must be specified",
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub(super) fn query_param<'r, B>(req: &'r http::Request<B>, name: &str) -> Option<&'r str> {
+    req.uri()
+        .query()?
+        .split('&')
+        .filter_map(|param| param.split_once('='))
+        .find_map(|(key, value)| (key == name).then_some(value))
+}
+
+fn mk_rsp(status: StatusCode) -> http::Response<Body> {
+    http::Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .expect("builder with known status code must not fail")
+}
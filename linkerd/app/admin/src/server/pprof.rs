@@ -0,0 +1,97 @@
+use super::json;
+use hyper::{Body, Request, Response, StatusCode};
+use linkerd_app_core::Error;
+use std::time::Duration;
+
+const DEFAULT_DURATION: Duration = Duration::from_secs(10);
+const MAX_DURATION: Duration = Duration::from_secs(60);
+const DEFAULT_FREQUENCY: i32 = 99;
+
+/// Samples the process's CPU usage for the duration specified by the `seconds`
+/// query parameter and responds with a protobuf-encoded pprof profile.
+pub(crate) async fn profile<B>(req: Request<B>) -> Result<Response<Body>, Error> {
+    let (duration, frequency) = match params(req.uri().query()) {
+        Ok(params) => params,
+        Err(error) => return Ok(json::json_error_rsp(error, StatusCode::BAD_REQUEST)),
+    };
+
+    tracing::info!(?duration, frequency, "Collecting CPU profile");
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(frequency)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()?;
+    tokio::time::sleep(duration).await;
+    let profile = guard.report().build()?.pprof()?;
+    drop(guard);
+
+    let mut body = Vec::new();
+    pprof::protos::Message::encode(&profile, &mut body)?;
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(http::header::CONTENT_TYPE, "application/octet-stream")
+        .body(body.into())
+        .expect("builder with known status code must not fail"))
+}
+
+fn params(query: Option<&str>) -> Result<(Duration, i32), String> {
+    let mut duration = DEFAULT_DURATION;
+    let mut frequency = DEFAULT_FREQUENCY;
+    for (key, val) in query
+        .into_iter()
+        .flat_map(|q| q.split('&'))
+        .filter_map(|kv| kv.split_once('='))
+    {
+        match key {
+            "seconds" => {
+                let secs = val
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid seconds: {}", val))?;
+                duration = Duration::from_secs(secs);
+                if duration.is_zero() || duration > MAX_DURATION {
+                    return Err(format!(
+                        "seconds must be between 1 and {}",
+                        MAX_DURATION.as_secs()
+                    ));
+                }
+            }
+            "hz" => {
+                frequency = val
+                    .parse::<i32>()
+                    .ok()
+                    .filter(|hz| *hz > 0)
+                    .ok_or_else(|| format!("invalid hz: {}", val))?;
+            }
+            _ => {}
+        }
+    }
+    Ok((duration, frequency))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_params() {
+        assert_eq!(params(None), Ok((DEFAULT_DURATION, DEFAULT_FREQUENCY)));
+        assert_eq!(
+            params(Some("seconds=2&hz=50")),
+            Ok((Duration::from_secs(2), 50))
+        );
+        assert!(params(Some("seconds=0")).is_err());
+        assert!(params(Some("seconds=3600")).is_err());
+        assert!(params(Some("hz=-1")).is_err());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn returns_profile() {
+        let req = Request::builder()
+            .uri("http://0.0.0.0/debug/pprof/profile?seconds=1")
+            .body(Body::empty())
+            .unwrap();
+        let rsp = profile(req).await.expect("profile must succeed");
+        assert_eq!(rsp.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+        assert!(!body.is_empty(), "profile must not be empty");
+    }
+}
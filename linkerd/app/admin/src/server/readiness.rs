@@ -2,22 +2,58 @@ use std::sync::{Arc, Weak};
 
 /// Tracks the processes's readiness to serve traffic.
 ///
-/// Once `is_ready()` returns true, it will never return false.
+/// The process may depend on several subsystems (e.g. identity), each of which
+/// holds a latch until it is ready. Once `is_ready()` returns true, it will
+/// never return false.
 #[derive(Clone, Debug)]
-pub struct Readiness(Weak<()>);
+pub struct Readiness(Arc<[Subsystem]>);
 
 /// When all latches are dropped, the process is considered ready.
 #[derive(Clone, Debug)]
 pub struct Latch(Arc<()>);
 
+#[derive(Debug)]
+struct Subsystem {
+    name: Option<&'static str>,
+    latch: Weak<()>,
+}
+
 impl Readiness {
     pub fn new() -> (Readiness, Latch) {
-        let r = Arc::new(());
-        (Readiness(Arc::downgrade(&r)), Latch(r))
+        let latch = Arc::new(());
+        let subsystem = Subsystem {
+            name: None,
+            latch: Arc::downgrade(&latch),
+        };
+        (Readiness(Arc::new([subsystem])), Latch(latch))
+    }
+
+    /// Returns a readiness that becomes ready once the latches of all of the
+    /// named subsystems have been released.
+    pub fn subsystems<const N: usize>(names: [&'static str; N]) -> (Readiness, [Latch; N]) {
+        let latches = names.map(|_| Arc::new(()));
+        let subsystems = names
+            .iter()
+            .zip(latches.iter())
+            .map(|(name, latch)| Subsystem {
+                name: Some(*name),
+                latch: Arc::downgrade(latch),
+            })
+            .collect();
+        (Readiness(subsystems), latches.map(Latch))
     }
 
     pub fn is_ready(&self) -> bool {
-        self.0.upgrade().is_none()
+        self.0.iter().all(Subsystem::is_ready)
+    }
+
+    /// Returns the names of the subsystems that are not yet ready.
+    pub fn pending(&self) -> Vec<&'static str> {
+        self.0
+            .iter()
+            .filter(|s| !s.is_ready())
+            .filter_map(|s| s.name)
+            .collect()
     }
 }
 
@@ -34,3 +70,9 @@ impl Latch {
         drop(self);
     }
 }
+
+impl Subsystem {
+    fn is_ready(&self) -> bool {
+        self.latch.upgrade().is_none()
+    }
+}
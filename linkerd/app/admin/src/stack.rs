@@ -1,8 +1,11 @@
 use linkerd_app_core::{
     classify,
     config::ServerConfig,
-    detect, drain, errors, identity,
+    detect, disco_cache, drain,
+    endpoint_pins::EndpointPins,
+    errors, identity,
     metrics::{self, FmtMetrics},
+    profiles,
     proxy::http,
     serve,
     svc::{self, ExtractParam, InsertParam, Param},
@@ -20,11 +23,15 @@ use tracing::debug;
 pub struct Config {
     pub server: ServerConfig,
     pub metrics_retain_idle: Duration,
+    /// The fraction of HTTP requests whose latencies are recorded.
+    pub metrics_latency_sample_rate: f64,
 }
 
 pub struct Task {
     pub listen_addr: Local<ServerAddr>,
-    pub latch: crate::Latch,
+    /// Released once the proxy's identity has been certified. The admin
+    /// server does not report readiness until it is released.
+    pub identity_latch: crate::Latch,
     pub serve: Pin<Box<dyn std::future::Future<Output = ()> + Send + 'static>>,
 }
 
@@ -80,6 +87,8 @@ impl Config {
         trace: trace::Handle,
         drain: drain::Watch,
         shutdown: mpsc::UnboundedSender<()>,
+        endpoint_pins: EndpointPins,
+        discovery_evictions: disco_cache::Evictions<profiles::LookupAddr>,
     ) -> Result<Task>
     where
         R: FmtMetrics + Clone + Send + Sync + Unpin + 'static,
@@ -91,8 +100,16 @@ impl Config {
         // Get the policy for the admin server.
         let policy = policy.get_policy(OrigDstAddr(listen_addr.into()));
 
-        let (ready, latch) = crate::server::Readiness::new();
-        let admin = crate::server::Admin::new(report, ready, shutdown, trace);
+        let (ready, [identity_latch]) = crate::server::Readiness::subsystems(["identity"]);
+        let admin = crate::server::Admin::new(
+            report,
+            metrics.identity_accounting.clone(),
+            ready,
+            shutdown,
+            trace,
+            endpoint_pins,
+            discovery_evictions,
+        );
         let admin = svc::stack(move |_| admin.clone())
             .push(metrics.proxy.http_endpoint.to_layer::<classify::Response, _, Permitted>())
             .push_map_target(|(permit, http)| Permitted { permit, http })
@@ -100,7 +117,12 @@ impl Config {
             .push(Rescue::layer())
             .push_on_service(http::BoxResponse::layer())
             .unlift_new()
-            .push(http::NewServeHttp::layer(Default::default(), drain.clone()))
+            .push(http::NewServeHttp::layer(
+                Default::default(),
+                Default::default(),
+                metrics.proxy.http_server.clone(),
+                drain.clone(),
+            ))
             .push_filter(
                 |(http, tcp): (
                     Result<Option<http::Version>, detect::DetectTimeoutError<_>>,
@@ -143,9 +165,12 @@ impl Config {
                 },
             )
             .lift_new_with_target()
-            .push(detect::NewDetectService::layer(svc::stack::CloneParam::from(
-                detect::Config::<http::DetectHttp>::from_timeout(DETECT_TIMEOUT),
-            )))
+            .push(detect::NewDetectService::layer(
+                svc::stack::CloneParam::from(
+                    detect::Config::<http::DetectHttp>::from_timeout(DETECT_TIMEOUT),
+                ),
+                metrics.proxy.detect.clone(),
+            ))
             .push(transport::metrics::NewServer::layer(metrics.proxy.transport))
             .push_map_target(move |(tls, addrs): (tls::ConditionalServerTls, B::Addrs)| {
                 Tcp {
@@ -163,7 +188,7 @@ impl Config {
         let serve = Box::pin(serve::serve(listen, admin, drain.signaled()));
         Ok(Task {
             listen_addr,
-            latch,
+            identity_latch,
             serve,
         })
     }
@@ -291,6 +316,20 @@ impl<T: Param<tls::ConditionalServerTls>> ExtractParam<errors::respond::EmitHead
     }
 }
 
+impl<T> ExtractParam<errors::respond::EmitJsonBody, T> for Rescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> errors::respond::EmitJsonBody {
+        errors::respond::EmitJsonBody(false)
+    }
+}
+
+impl<T> ExtractParam<errors::respond::EmitGrpcWeb, T> for Rescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> errors::respond::EmitGrpcWeb {
+        errors::respond::EmitGrpcWeb(false)
+    }
+}
+
 impl errors::HttpRescue<Error> for Rescue {
     fn rescue(&self, error: Error) -> Result<errors::SyntheticHttpResponse> {
         if let Some(cause) = errors::cause_ref::<inbound::policy::HttpRouteNotFound>(&*error) {
@@ -10,16 +10,23 @@ use std::time::Duration;
 pub struct ServerConfig {
     pub addr: ListenAddr,
     pub keepalive: Keepalive,
+    pub h1_settings: h1::ServerSettings,
     pub h2_settings: h2::Settings,
 }
 
 #[derive(Clone, Debug)]
 pub struct ConnectConfig {
     pub backoff: ExponentialBackoff,
+    /// The maximum amount of time an endpoint may spend reconnecting before it
+    /// fails. When unset, endpoints reconnect indefinitely.
+    pub backoff_max_elapsed: Option<Duration>,
     pub timeout: Duration,
     pub keepalive: Keepalive,
     pub h1_settings: h1::PoolSettings,
     pub h2_settings: h2::Settings,
+    /// Limits the number of HTTP/2 connections that may be opened to each
+    /// endpoint.
+    pub h2_pool: h2::PoolSettings,
 }
 
 #[derive(Clone, Debug)]
@@ -28,6 +35,12 @@ pub struct ProxyConfig {
     pub connect: ConnectConfig,
     pub max_in_flight_requests: usize,
     pub detect_protocol_timeout: Duration,
+    /// The capacity of each of the buffers used to copy data between
+    /// connections when forwarding opaque TCP streams.
+    pub forward_buffer_capacity: usize,
+    /// The duration after which forwarded opaque TCP streams are closed if no
+    /// data is read in either direction.
+    pub forward_idle_timeout: Option<Duration>,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -8,11 +8,19 @@ use tokio::time;
 use tokio_stream::{wrappers::IntervalStream, StreamExt};
 use tracing::{info_span, warn};
 
+mod reconnect;
+
+pub use self::reconnect::CircuitConfig;
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub addr: ControlAddr,
     pub connect: config::ConnectConfig,
     pub buffer: config::QueueConfig,
+
+    /// Staggers and caps reconnection attempts so that proxies do not all
+    /// reconnect at once when a control plane component restarts.
+    pub circuit: CircuitConfig,
 }
 
 #[derive(Clone, Debug)]
@@ -117,7 +125,11 @@ impl Config {
             // continually reconnect without checking for discovery updates.
             .push_on_service(svc::layer::mk(svc::SpawnReady::new))
             .push(svc::NewMapErr::layer_from_target::<EndpointError, _>())
-            .push_new_reconnect(self.connect.backoff)
+            // Control plane endpoints are never abandoned.
+            .push(svc::NewReconnect::layer(reconnect::Reconnect::new(
+                self.connect.backoff,
+                self.circuit,
+            )))
             .instrument(|t: &self::client::Target| info_span!("endpoint", addr = %t.addr));
 
         let balance = endpoint
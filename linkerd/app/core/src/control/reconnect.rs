@@ -0,0 +1,195 @@
+//! Staggers and caps reconnection attempts to control plane endpoints.
+//!
+//! When a control plane component restarts, every proxy's connection to it
+//! fails at the same time. So that proxies do not all reconnect at once, the
+//! first reconnection attempt is delayed by a random duration of up to
+//! `stagger`; subsequent attempts follow the connection's jittered exponential
+//! backoff. After `failure_threshold` consecutive failed attempts, the circuit
+//! opens and only a single attempt is permitted every `open_timeout` (plus a
+//! random delay of up to `stagger`) until a connection is established.
+
+use crate::{
+    exp_backoff::{ExponentialBackoff, ExponentialBackoffStream},
+    Error, Recover,
+};
+use futures::{ready, Stream, StreamExt};
+use rand::{rngs::SmallRng, thread_rng, Rng, SeedableRng};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time;
+use tracing::{debug, warn};
+
+/// Configures the circuit applied to reconnections to control plane endpoints.
+#[derive(Copy, Clone, Debug)]
+pub struct CircuitConfig {
+    /// The maximum random delay before the first attempt to reconnect to an
+    /// endpoint whose connection failed.
+    pub stagger: Duration,
+
+    /// The number of consecutive failed attempts after which the circuit
+    /// opens.
+    pub failure_threshold: u32,
+
+    /// The time between attempts while the circuit is open.
+    pub open_timeout: Duration,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Reconnect {
+    backoff: ExponentialBackoff,
+    circuit: CircuitConfig,
+}
+
+/// Gates each reconnection attempt of a single endpoint.
+#[derive(Debug)]
+pub(super) struct ReconnectStream {
+    backoff: ExponentialBackoffStream,
+    circuit: CircuitConfig,
+    rng: SmallRng,
+
+    /// The number of attempts permitted since the connection failed.
+    attempts: u32,
+    sleep: Option<Pin<Box<time::Sleep>>>,
+}
+
+// === impl Reconnect ===
+
+impl Reconnect {
+    pub(super) fn new(backoff: ExponentialBackoff, circuit: CircuitConfig) -> Self {
+        Self { backoff, circuit }
+    }
+}
+
+impl<E: Into<Error>> Recover<E> for Reconnect {
+    type Backoff = ReconnectStream;
+
+    fn recover(&self, _: E) -> Result<Self::Backoff, E> {
+        Ok(ReconnectStream {
+            backoff: self.backoff.stream(),
+            circuit: self.circuit,
+            rng: SmallRng::from_rng(&mut thread_rng()).expect("RNG must be valid"),
+            attempts: 0,
+            sleep: None,
+        })
+    }
+}
+
+// === impl ReconnectStream ===
+
+impl ReconnectStream {
+    fn stagger(&mut self) -> Duration {
+        self.rng.gen_range(Duration::ZERO..=self.circuit.stagger)
+    }
+}
+
+impl Stream for ReconnectStream {
+    type Item = ();
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<()>> {
+        let this = &mut *self;
+        loop {
+            if let Some(sleep) = this.sleep.as_mut() {
+                ready!(sleep.as_mut().poll(cx));
+                this.sleep = None;
+                this.attempts = this.attempts.saturating_add(1);
+                return Poll::Ready(Some(()));
+            }
+
+            let delay = if this.attempts == 0 {
+                this.stagger()
+            } else if this.attempts < this.circuit.failure_threshold {
+                let next = ready!(this.backoff.poll_next_unpin(cx));
+                if next.is_some() {
+                    this.attempts += 1;
+                }
+                return Poll::Ready(next);
+            } else {
+                if this.attempts == this.circuit.failure_threshold {
+                    warn!(
+                        attempts = %this.attempts,
+                        timeout = ?this.circuit.open_timeout,
+                        "Circuit opened after repeated reconnection failures",
+                    );
+                }
+                this.circuit.open_timeout + this.stagger()
+            };
+            debug!(?delay, attempts = %this.attempts, "Delaying reconnection");
+            this.sleep = Some(Box::pin(time::sleep(delay)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::Instant;
+
+    const BACKOFF: ExponentialBackoff = ExponentialBackoff::new_unchecked(
+        Duration::from_millis(100),
+        Duration::from_millis(500),
+        0.1,
+    );
+
+    const CIRCUIT: CircuitConfig = CircuitConfig {
+        stagger: Duration::from_secs(1),
+        failure_threshold: 3,
+        open_timeout: Duration::from_secs(10),
+    };
+
+    /// Returns the delay before each of the stream's first `n` attempts.
+    async fn delays(stream: &mut ReconnectStream, n: usize) -> Vec<Duration> {
+        let mut delays = Vec::with_capacity(n);
+        for _ in 0..n {
+            let start = Instant::now();
+            stream.next().await.expect("stream must not end");
+            delays.push(Instant::now().saturating_duration_since(start));
+        }
+        delays
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn staggers_and_opens_circuit() {
+        time::pause();
+
+        let reconnect = Reconnect::new(BACKOFF, CIRCUIT);
+        let mut first_delays = Vec::new();
+        for _ in 0..10 {
+            let mut stream = Recover::<Error>::recover(&reconnect, "connection reset".into())
+                .expect("must recover");
+            let delays = delays(&mut stream, 5).await;
+
+            // The first attempt is staggered.
+            assert!(delays[0] <= CIRCUIT.stagger, "{:?}", delays);
+            first_delays.push(delays[0]);
+
+            // Subsequent attempts follow the jittered backoff.
+            assert!(delays[1] >= Duration::from_millis(100), "{:?}", delays);
+            assert!(delays[1] <= Duration::from_millis(110), "{:?}", delays);
+            assert!(delays[2] >= Duration::from_millis(200), "{:?}", delays);
+            assert!(delays[2] <= Duration::from_millis(220), "{:?}", delays);
+
+            // Once the threshold is reached, attempts are limited by the
+            // circuit.
+            for delay in &delays[3..] {
+                assert!(*delay >= CIRCUIT.open_timeout, "{:?}", delays);
+                assert!(
+                    *delay <= CIRCUIT.open_timeout + CIRCUIT.stagger,
+                    "{:?}",
+                    delays
+                );
+            }
+        }
+
+        first_delays.sort();
+        first_delays.dedup();
+        assert!(
+            first_delays.len() > 1,
+            "reconnects must not be attempted after the same delay: {:?}",
+            first_delays
+        );
+    }
+}
@@ -4,11 +4,13 @@
 use futures::TryFutureExt;
 use linkerd_error::Error;
 use linkerd_idle_cache::{Cached, NewIdleCached};
+use linkerd_metrics::Counter;
 use linkerd_stack::{
     layer, queue, CloneParam, FutureService, MapErrBoxed, NewQueueWithoutTimeout, NewService,
     Oneshot, Param, QueueWithoutTimeout, Service, ServiceExt, ThunkClone,
 };
-use std::{fmt, hash::Hash, task, time};
+use parking_lot::RwLock;
+use std::{fmt, hash::Hash, sync::Arc, task, time};
 
 /// A [`NewService`] that extracts a `K`-typed key from each target to build a
 /// [`Cached`]<[`DiscoverThunk`]>.
@@ -25,8 +27,17 @@ where
 
     // NewService<D::Response>
     inner: N,
+
+    // Counts discoveries that wait for capacity in the cache's queue.
+    backpressure: Option<Arc<Counter>>,
 }
 
+/// A shared registry of discovery caches, used to force cached discoveries to
+/// be re-resolved by their `K`-typed key, i.e. via the admin server.
+pub struct Evictions<K>(Arc<RwLock<Vec<Evict<K>>>>);
+
+type Evict<K> = Box<dyn Fn(&K) -> bool + Send + Sync>;
+
 /// The future that drives discovery to build an new inner service wrapped
 /// in the [`Cached`] decorator from the discovery lookup, preventing the
 /// cache's idle timeout from starting until returned services are dropped.
@@ -37,12 +48,16 @@ pub struct CachedDiscoverFuture<D: Service<()>, N> {
     inner: N,
 
     // Holds a cache handle so that we can carry that forward with the returned
-    // service.
+    // service. The handle is also used to obtain a `D::Response`.
     cached: Cached<D>,
 
-    // A future that obtains a `D::Response` and produces an `N::Service`.
+    // Incremented if the cache's queue is at capacity when the discovery is
+    // first polled.
+    backpressure: Option<Arc<Counter>>,
+
+    // A future that obtains a `D::Response`, set once the queue has capacity.
     #[pin]
-    future: Oneshot<Cached<D>, ()>,
+    future: Option<D::Future>,
 }
 
 /// A [`Service<()>`] that uses a `D`-typed discovery service to build a new
@@ -80,19 +95,69 @@ where
     D::Response: Clone + Send + Sync,
     D::Future: Send + Unpin,
 {
-    pub fn new(inner: N, discover: D, timeout: time::Duration) -> Self {
+    /// Cached discovery results are dropped once they have been unused for
+    /// `timeout`, extended by a random duration of up to `jitter`.
+    pub fn new(inner: N, discover: D, timeout: time::Duration, jitter: time::Duration) -> Self {
         let queue = NewQueueThunk::new(
             NewDiscoverThunk { discover },
             CloneParam::from(QUEUE_CAPACITY),
         );
         Self {
             inner,
-            cache: NewIdleCached::new(queue, timeout),
+            cache: NewIdleCached::new(queue, timeout).with_jitter(jitter),
+            backpressure: None,
+        }
+    }
+
+    /// Increments `counter` each time a discovery must wait because the
+    /// cache's queue is at capacity.
+    pub fn with_backpressure(self, counter: Arc<Counter>) -> Self {
+        Self {
+            backpressure: Some(counter),
+            ..self
         }
     }
 
-    pub fn layer(disco: D, idle: time::Duration) -> impl layer::Layer<N, Service = Self> + Clone {
-        layer::mk(move |inner| Self::new(inner, disco.clone(), idle))
+    /// Re-resolves each cached discovery once it is `max_age` old, even if it
+    /// is still in use. Services built from the prior discovery result are
+    /// retained until they are dropped.
+    pub fn with_max_age(self, max_age: time::Duration) -> Self {
+        Self {
+            cache: self.cache.with_max_age(max_age),
+            ..self
+        }
+    }
+
+    /// Registers the cache with `evictions`, so that its discoveries may be
+    /// evicted.
+    pub fn with_evictions(self, evictions: &Evictions<K>) -> Self {
+        let cache = self.cache.clone();
+        evictions
+            .0
+            .write()
+            .push(Box::new(move |key: &K| cache.evict(key)));
+        self
+    }
+
+    pub fn layer(
+        disco: D,
+        idle: time::Duration,
+        jitter: time::Duration,
+    ) -> impl layer::Layer<N, Service = Self> + Clone {
+        layer::mk(move |inner| Self::new(inner, disco.clone(), idle, jitter))
+    }
+
+    /// Like [`NewCachedDiscover::layer`], but counts discoveries that must
+    /// wait for capacity in the cache's queue.
+    pub fn layer_with_backpressure(
+        disco: D,
+        idle: time::Duration,
+        jitter: time::Duration,
+        backpressure: Arc<Counter>,
+    ) -> impl layer::Layer<N, Service = Self> + Clone {
+        layer::mk(move |inner| {
+            Self::new(inner, disco.clone(), idle, jitter).with_backpressure(backpressure.clone())
+        })
     }
 }
 
@@ -112,15 +177,50 @@ where
         let key = target.param();
         let cached = self.cache.new_service(key);
         let inner = self.inner.new_service(target);
-        let future = cached.clone().oneshot(());
         FutureService::new(CachedDiscoverFuture {
-            future,
+            future: None,
             cached,
+            backpressure: self.backpressure.clone(),
             inner,
         })
     }
 }
 
+// === impl Evictions ===
+
+impl<K> Evictions<K> {
+    /// Evicts the cached discovery for `key` from all registered caches, so
+    /// that it is resolved anew the next time it is used. Services built from
+    /// the evicted discovery are retained until they are dropped. Returns
+    /// `true` if a discovery was evicted.
+    pub fn evict(&self, key: &K) -> bool {
+        self.0
+            .read()
+            .iter()
+            .fold(false, |evicted, evict| evict(key) || evicted)
+    }
+}
+
+impl<K> Clone for Evictions<K> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<K> Default for Evictions<K> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<K> fmt::Debug for Evictions<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Evictions")
+            .field("caches", &self.0.read().len())
+            .finish()
+    }
+}
+
 // === impl NewDiscoverThunk ===
 
 impl<T, D> NewService<T> for NewDiscoverThunk<D>
@@ -150,8 +250,24 @@ where
         self: std::pin::Pin<&mut Self>,
         cx: &mut task::Context<'_>,
     ) -> task::Poll<Self::Output> {
-        let this = self.project();
-        let discovery = futures::ready!(this.future.poll(cx))?;
+        let mut this = self.project();
+        if this.future.is_none() {
+            match this.cached.poll_ready(cx) {
+                task::Poll::Ready(res) => res?,
+                task::Poll::Pending => {
+                    // The queue is at capacity. Each discovery is only
+                    // counted once.
+                    if let Some(counter) = this.backpressure.take() {
+                        counter.incr();
+                    }
+                    return task::Poll::Pending;
+                }
+            }
+            let future = this.cached.call(());
+            this.future.set(Some(future));
+        }
+        let future = this.future.as_pin_mut().expect("future must be set");
+        let discovery = futures::ready!(future.poll(cx))?;
         let inner = this.inner.new_service(discovery);
         let cached = this.cached.clone_with(inner);
         task::Poll::Ready(Ok(cached))
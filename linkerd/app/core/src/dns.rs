@@ -7,6 +7,9 @@ pub struct Config {
     pub min_ttl: Option<Duration>,
     pub max_ttl: Option<Duration>,
     pub resolv_conf_path: PathBuf,
+    /// Determines which address records are queried. When unset, the system
+    /// resolver's lookup strategy is used.
+    pub query_strategy: Option<QueryStrategy>,
 }
 
 pub struct Dns {
@@ -17,8 +20,11 @@ pub struct Dns {
 
 impl Config {
     pub fn build(self) -> Dns {
-        let resolver =
+        let mut resolver =
             Resolver::from_system_config_with(&self).expect("system DNS config must be valid");
+        if let Some(query_strategy) = self.query_strategy {
+            resolver = resolver.with_query_strategy(query_strategy);
+        }
         Dns { resolver }
     }
 }
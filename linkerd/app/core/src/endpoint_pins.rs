@@ -0,0 +1,103 @@
+//! Pins a load balanced backend's requests to one of its endpoints.
+//!
+//! Pins are set via the admin server, i.e. to direct all of a backend's traffic
+//! to a canary. Balancers watch the pin for their backend and, while it is set,
+//! only dispatch requests to the pinned endpoint.
+
+use crate::NameAddr;
+use parking_lot::RwLock;
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use tokio::sync::watch;
+
+/// A shared registry of endpoint pins, by backend address.
+#[derive(Clone, Debug, Default)]
+pub struct EndpointPins(Arc<RwLock<HashMap<NameAddr, watch::Sender<Option<SocketAddr>>>>>);
+
+// === impl EndpointPins ===
+
+impl EndpointPins {
+    /// Pins all requests for `backend` to `endpoint`.
+    pub fn pin(&self, backend: NameAddr, endpoint: SocketAddr) {
+        tracing::info!(%backend, %endpoint, "Pinning backend to endpoint");
+        self.0
+            .write()
+            .entry(backend)
+            .or_insert_with(|| watch::channel(None).0)
+            .send_replace(Some(endpoint));
+    }
+
+    /// Clears the pin for `backend`, returning the endpoint it was pinned to.
+    ///
+    /// The backend is forgotten if no balancers are watching it.
+    pub fn unpin(&self, backend: &NameAddr) -> Option<SocketAddr> {
+        let mut pins = self.0.write();
+        let tx = pins.get(backend)?;
+        let prior = tx.send_replace(None);
+        if tx.receiver_count() == 0 {
+            pins.remove(backend);
+        }
+        drop(pins);
+        if let Some(endpoint) = prior {
+            tracing::info!(%backend, %endpoint, "Unpinning backend");
+        }
+        prior
+    }
+
+    /// Returns all pinned backends with their endpoints.
+    pub fn pins(&self) -> Vec<(NameAddr, SocketAddr)> {
+        self.0
+            .read()
+            .iter()
+            .filter_map(|(backend, tx)| Some((backend.clone(), (*tx.borrow())?)))
+            .collect()
+    }
+
+    /// Watches the pin for `backend`.
+    ///
+    /// Unpinned backends that are no longer watched by any balancer are
+    /// forgotten as new backends are watched.
+    pub fn watch(&self, backend: NameAddr) -> watch::Receiver<Option<SocketAddr>> {
+        if let Some(tx) = self.0.read().get(&backend) {
+            return tx.subscribe();
+        }
+        let mut pins = self.0.write();
+        pins.retain(|_, tx| tx.receiver_count() > 0 || tx.borrow().is_some());
+        pins.entry(backend)
+            .or_insert_with(|| watch::channel(None).0)
+            .subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backend(name: &str) -> NameAddr {
+        format!("{}.ns.svc.cluster.local:8080", name)
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn forgets_unwatched_backends() {
+        let pins = EndpointPins::default();
+        let endpoint = ([10, 0, 0, 1], 8080).into();
+
+        let rx = pins.watch(backend("foo"));
+        pins.pin(backend("foo"), endpoint);
+        assert_eq!(*rx.borrow(), Some(endpoint));
+        drop(rx);
+
+        // A pinned backend is retained until it is unpinned, even if it is not
+        // watched.
+        drop(pins.watch(backend("bar")));
+        assert_eq!(pins.pins(), vec![(backend("foo"), endpoint)]);
+        assert_eq!(pins.unpin(&backend("foo")), Some(endpoint));
+        assert_eq!(pins.0.read().len(), 1);
+
+        // Backends that are no longer watched are forgotten.
+        let _rx = pins.watch(backend("baz"));
+        assert_eq!(pins.0.read().len(), 1);
+        assert!(pins.0.read().contains_key(&backend("baz")));
+    }
+}
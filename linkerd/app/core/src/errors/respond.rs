@@ -1,5 +1,6 @@
 use crate::svc;
-use http::header::{HeaderValue, LOCATION};
+use bytes::Bytes;
+use http::header::{HeaderValue, ALLOW, LOCATION};
 use linkerd_error::{Error, Result};
 use linkerd_error_respond as respond;
 use linkerd_proxy_http::orig_proto;
@@ -15,6 +16,7 @@ use tracing::{debug, info_span, warn};
 
 pub const L5D_PROXY_CONNECTION: &str = "l5d-proxy-connection";
 pub const L5D_PROXY_ERROR: &str = "l5d-proxy-error";
+pub const X_REQUEST_ID: &str = "x-request-id";
 
 pub fn layer<R, P: Clone, N>(
     params: P,
@@ -38,11 +40,23 @@ pub struct SyntheticHttpResponse {
     close_connection: bool,
     message: Cow<'static, str>,
     location: Option<HeaderValue>,
+    allow: Option<HeaderValue>,
 }
 
 #[derive(Copy, Clone, Debug)]
 pub struct EmitHeaders(pub bool);
 
+/// Configures whether synthesized HTTP error responses carry a JSON body
+/// describing the error.
+#[derive(Copy, Clone, Debug)]
+pub struct EmitJsonBody(pub bool);
+
+/// Configures whether errors on HTTP/1 gRPC-Web requests are synthesized as
+/// trailers-only gRPC-Web responses, with a `grpc-status`, rather than as HTTP
+/// error responses.
+#[derive(Copy, Clone, Debug)]
+pub struct EmitGrpcWeb(pub bool);
+
 #[derive(Clone, Debug)]
 pub struct ExtractRespond<P>(P);
 
@@ -50,6 +64,8 @@ pub struct ExtractRespond<P>(P);
 pub struct NewRespond<R> {
     rescue: R,
     emit_headers: bool,
+    emit_json: bool,
+    emit_grpc_web: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -57,9 +73,14 @@ pub struct Respond<R> {
     rescue: R,
     version: http::Version,
     is_grpc: bool,
+    /// The content type of an HTTP/1 gRPC-Web request, if errors are
+    /// synthesized as gRPC-Web responses.
+    grpc_web: Option<HeaderValue>,
     is_orig_proto_upgrade: bool,
     client: Option<ClientHandle>,
     emit_headers: bool,
+    emit_json: bool,
+    request_id: Option<HeaderValue>,
 }
 
 #[pin_project(project = ResponseBodyProj)]
@@ -72,9 +93,12 @@ pub enum ResponseBody<R, B> {
         rescue: R,
         emit_headers: bool,
     },
+    Synthetic(Option<Bytes>),
 }
 
 const GRPC_CONTENT_TYPE: &str = "application/grpc";
+const GRPC_WEB_CONTENT_TYPE: &str = "application/grpc-web";
+const JSON_CONTENT_TYPE: &str = "application/json";
 const GRPC_STATUS: &str = "grpc-status";
 const GRPC_MESSAGE: &str = "grpc-message";
 
@@ -103,6 +127,18 @@ impl SyntheticHttpResponse {
             grpc_status: tonic::Code::Internal,
             message: msg.into(),
             location: None,
+            allow: None,
+        }
+    }
+
+    pub fn bad_request(msg: impl ToString) -> Self {
+        Self {
+            close_connection: true,
+            http_status: http::StatusCode::BAD_REQUEST,
+            grpc_status: tonic::Code::InvalidArgument,
+            message: Cow::Owned(msg.to_string()),
+            location: None,
+            allow: None,
         }
     }
 
@@ -113,6 +149,7 @@ impl SyntheticHttpResponse {
             grpc_status: tonic::Code::Unavailable,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            allow: None,
         }
     }
 
@@ -123,6 +160,7 @@ impl SyntheticHttpResponse {
             grpc_status: tonic::Code::Unavailable,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            allow: None,
         }
     }
 
@@ -133,6 +171,7 @@ impl SyntheticHttpResponse {
             grpc_status: tonic::Code::Unavailable,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            allow: None,
         }
     }
 
@@ -143,6 +182,7 @@ impl SyntheticHttpResponse {
             close_connection: false,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            allow: None,
         }
     }
 
@@ -153,6 +193,7 @@ impl SyntheticHttpResponse {
             close_connection: false,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            allow: None,
         }
     }
 
@@ -163,6 +204,7 @@ impl SyntheticHttpResponse {
             close_connection: true,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            allow: None,
         }
     }
 
@@ -173,6 +215,40 @@ impl SyntheticHttpResponse {
             close_connection: false,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            allow: None,
+        }
+    }
+
+    pub fn payload_too_large(msg: impl ToString) -> Self {
+        Self {
+            http_status: http::StatusCode::PAYLOAD_TOO_LARGE,
+            grpc_status: tonic::Code::ResourceExhausted,
+            close_connection: true,
+            message: Cow::Owned(msg.to_string()),
+            location: None,
+            allow: None,
+        }
+    }
+
+    pub fn request_header_fields_too_large(msg: impl ToString) -> Self {
+        Self {
+            http_status: http::StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            grpc_status: tonic::Code::ResourceExhausted,
+            close_connection: false,
+            message: Cow::Owned(msg.to_string()),
+            location: None,
+            allow: None,
+        }
+    }
+
+    pub fn unsupported_media_type(msg: impl ToString) -> Self {
+        Self {
+            http_status: http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            grpc_status: tonic::Code::InvalidArgument,
+            close_connection: false,
+            message: Cow::Owned(msg.to_string()),
+            location: None,
+            allow: None,
         }
     }
 
@@ -186,6 +262,25 @@ impl SyntheticHttpResponse {
                 HeaderValue::try_from(location.to_string())
                     .expect("location must be a valid header value"),
             ),
+            allow: None,
+        }
+    }
+
+    pub fn method_not_allowed(msg: impl ToString, allowed: &[http::Method]) -> Self {
+        let allow = allowed
+            .iter()
+            .map(http::Method::as_str)
+            .collect::<Vec<_>>()
+            .join(", ");
+        Self {
+            http_status: http::StatusCode::METHOD_NOT_ALLOWED,
+            grpc_status: tonic::Code::Unimplemented,
+            close_connection: false,
+            message: Cow::Owned(msg.to_string()),
+            location: None,
+            allow: Some(
+                HeaderValue::try_from(allow).expect("methods must be a valid header value"),
+            ),
         }
     }
 
@@ -193,6 +288,7 @@ impl SyntheticHttpResponse {
         Self {
             http_status,
             location: None,
+            allow: None,
             grpc_status: tonic::Code::FailedPrecondition,
             close_connection: false,
             message: message.into(),
@@ -210,13 +306,20 @@ impl SyntheticHttpResponse {
         }
     }
 
+    /// Builds a trailers-only gRPC response, which carries its status in its
+    /// headers.
     #[inline]
-    fn grpc_response<B: Default>(&self, emit_headers: bool) -> http::Response<B> {
+    fn grpc_response<B: Default>(
+        &self,
+        version: http::Version,
+        content_type: HeaderValue,
+        emit_headers: bool,
+    ) -> http::Response<B> {
         debug!(code = %self.grpc_status, "Handling error on gRPC connection");
         let mut rsp = http::Response::builder()
-            .version(http::Version::HTTP_2)
+            .version(version)
             .header(http::header::CONTENT_LENGTH, "0")
-            .header(http::header::CONTENT_TYPE, GRPC_CONTENT_TYPE)
+            .header(http::header::CONTENT_TYPE, content_type)
             .header(GRPC_STATUS, code_header(self.grpc_status));
 
         if emit_headers {
@@ -274,9 +377,44 @@ impl SyntheticHttpResponse {
             rsp = rsp.header(LOCATION, loc);
         }
 
+        if let Some(allow) = &self.allow {
+            rsp = rsp.header(ALLOW, allow);
+        }
+
         rsp.body(B::default())
             .expect("error response must be valid")
     }
+
+    /// Encodes the error as a JSON document so that clients may distinguish
+    /// proxy-generated errors from application responses.
+    ///
+    /// The error message is only included when informational headers may be
+    /// emitted, since it may describe internal proxy state.
+    fn json_body(&self, emit_headers: bool, request_id: Option<&HeaderValue>) -> Bytes {
+        let mut body = serde_json::Map::new();
+        body.insert("code".to_string(), self.http_status.as_u16().into());
+        body.insert(
+            "error".to_string(),
+            self.http_status.canonical_reason().into(),
+        );
+        if emit_headers {
+            body.insert(
+                "reason".to_string(),
+                serde_json::Value::from(&*self.message),
+            );
+        }
+        if let Some(id) = request_id.and_then(|v| v.to_str().ok()) {
+            body.insert("request_id".to_string(), id.into());
+        }
+        serde_json::to_vec(&body)
+            .expect("error body must serialize")
+            .into()
+    }
+
+    #[inline]
+    fn is_error(&self) -> bool {
+        self.http_status.is_client_error() || self.http_status.is_server_error()
+    }
 }
 
 // === impl ExtractRespond ===
@@ -285,13 +423,19 @@ impl<T, R, P> ExtractParam<NewRespond<R>, T> for ExtractRespond<P>
 where
     P: ExtractParam<R, T>,
     P: ExtractParam<EmitHeaders, T>,
+    P: ExtractParam<EmitJsonBody, T>,
+    P: ExtractParam<EmitGrpcWeb, T>,
 {
     #[inline]
     fn extract_param(&self, t: &T) -> NewRespond<R> {
         let EmitHeaders(emit_headers) = self.0.extract_param(t);
+        let EmitJsonBody(emit_json) = self.0.extract_param(t);
+        let EmitGrpcWeb(emit_grpc_web) = self.0.extract_param(t);
         NewRespond {
             rescue: self.0.extract_param(t),
             emit_headers,
+            emit_json,
+            emit_grpc_web,
         }
     }
 }
@@ -310,6 +454,12 @@ where
 
         let rescue = self.rescue.clone();
         let emit_headers = self.emit_headers;
+        let emit_json = self.emit_json;
+        let request_id = if emit_json {
+            req.headers().get(X_REQUEST_ID).cloned()
+        } else {
+            None
+        };
 
         match req.version() {
             http::Version::HTTP_2 => {
@@ -322,20 +472,38 @@ where
                     client,
                     rescue,
                     is_grpc,
+                    grpc_web: None,
                     is_orig_proto_upgrade: false,
                     version: http::Version::HTTP_2,
                     emit_headers,
+                    emit_json,
+                    request_id,
                 }
             }
             version => {
                 let is_h2_upgrade = req.extensions().get::<orig_proto::WasUpgrade>().is_some();
+                let grpc_web = if self.emit_grpc_web {
+                    req.headers()
+                        .get(http::header::CONTENT_TYPE)
+                        .filter(|v| {
+                            v.to_str()
+                                .map(|s| s.starts_with(GRPC_WEB_CONTENT_TYPE))
+                                .unwrap_or(false)
+                        })
+                        .cloned()
+                } else {
+                    None
+                };
                 Respond {
                     client,
                     rescue,
                     version,
                     is_grpc: false,
+                    grpc_web,
                     is_orig_proto_upgrade: is_h2_upgrade,
                     emit_headers,
+                    emit_json,
+                    request_id,
                 }
             }
         }
@@ -385,7 +553,7 @@ where
         };
 
         let rsp = info_span!("rescue", client.addr = %self.client_addr()).in_scope(|| {
-            if !self.is_grpc {
+            if !self.is_grpc && self.grpc_web.is_none() {
                 let version = self.version;
                 tracing::info!(error, "{version:?} request failed",);
             } else {
@@ -402,13 +570,39 @@ where
             }
         }
 
-        let rsp = if self.is_grpc {
-            rsp.grpc_response(self.emit_headers)
-        } else {
-            rsp.http_response(self.version, self.emit_headers, self.is_orig_proto_upgrade)
-        };
+        if self.is_grpc {
+            return Ok(rsp.grpc_response(
+                http::Version::HTTP_2,
+                HeaderValue::from_static(GRPC_CONTENT_TYPE),
+                self.emit_headers,
+            ));
+        }
+        if let Some(content_type) = self.grpc_web.clone() {
+            let mut grpc = rsp.grpc_response(self.version, content_type, self.emit_headers);
+            if rsp.close_connection
+                && self.version == http::Version::HTTP_11
+                && !self.is_orig_proto_upgrade
+            {
+                grpc.headers_mut()
+                    .insert(http::header::CONNECTION, HeaderValue::from_static("close"));
+            }
+            return Ok(grpc);
+        }
 
-        Ok(rsp)
+        let mut http =
+            rsp.http_response(self.version, self.emit_headers, self.is_orig_proto_upgrade);
+        if self.emit_json && rsp.is_error() {
+            let body = rsp.json_body(self.emit_headers, self.request_id.as_ref());
+            let headers = http.headers_mut();
+            headers.insert(http::header::CONTENT_LENGTH, body.len().into());
+            headers.insert(
+                http::header::CONTENT_TYPE,
+                HeaderValue::from_static(JSON_CONTENT_TYPE),
+            );
+            *http.body_mut() = ResponseBody::Synthetic(Some(body));
+        }
+
+        Ok(http)
     }
 }
 
@@ -423,6 +617,7 @@ impl<R, B: Default + hyper::body::HttpBody> Default for ResponseBody<R, B> {
 impl<R, B> hyper::body::HttpBody for ResponseBody<R, B>
 where
     B: hyper::body::HttpBody<Error = Error>,
+    B::Data: From<Bytes>,
     R: HttpRescue<B::Error>,
 {
     type Data = B::Data;
@@ -456,6 +651,7 @@ where
                     data => data,
                 }
             }
+            ResponseBodyProj::Synthetic(body) => Poll::Ready(body.take().map(|b| Ok(b.into()))),
         }
     }
 
@@ -472,6 +668,7 @@ where
                 Some(t) => Poll::Ready(Ok(Some(t))),
                 None => inner.poll_trailers(cx),
             },
+            ResponseBodyProj::Synthetic(_) => Poll::Ready(Ok(None)),
         }
     }
 
@@ -482,6 +679,7 @@ where
             Self::GrpcRescue {
                 inner, trailers, ..
             } => trailers.is_none() && inner.is_end_stream(),
+            Self::Synthetic(body) => body.is_none(),
         }
     }
 
@@ -490,6 +688,9 @@ where
         match self {
             Self::Passthru(inner) => inner.size_hint(),
             Self::GrpcRescue { inner, .. } => inner.size_hint(),
+            Self::Synthetic(body) => {
+                http_body::SizeHint::with_exact(body.as_ref().map(|b| b.len() as u64).unwrap_or(0))
+            }
         }
     }
 }
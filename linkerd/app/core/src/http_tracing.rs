@@ -54,11 +54,21 @@ impl SpanConverter {
         sink: OpenCensusSink,
         labels: impl Into<Labels>,
     ) -> impl layer::Layer<S, Service = TraceContext<Option<Self>, S>> + Clone {
-        TraceContext::layer(sink.map(move |sink| Self {
+        TraceContext::layer(Self::new(kind, sink, labels))
+    }
+
+    /// Returns a converter for client spans that are not associated with an
+    /// HTTP request, if a sink is configured.
+    pub(crate) fn client(sink: OpenCensusSink, labels: impl Into<Labels>) -> Option<Self> {
+        Self::new(Kind::Client, sink, labels)
+    }
+
+    fn new(kind: Kind, sink: OpenCensusSink, labels: impl Into<Labels>) -> Option<Self> {
+        sink.map(move |sink| Self {
             kind,
             sink,
             labels: labels.into(),
-        }))
+        })
     }
 
     fn mk_span(&self, mut span: trace_context::Span) -> Result<oc::Span, IdLengthError> {
@@ -85,7 +95,7 @@ impl SpanConverter {
             trace_id: into_bytes(span.trace_id, 16)?,
             span_id: into_bytes(span.span_id, 8)?,
             tracestate: None,
-            parent_span_id: into_bytes(span.parent_id, 8)?,
+            parent_span_id: into_parent_bytes(span.parent_id)?,
             name: Some(truncatable(span.span_name)),
             kind: self.kind as i32,
             start_time: Some(span.start.into()),
@@ -131,6 +141,14 @@ fn into_bytes(id: trace_context::Id, size: usize) -> Result<Vec<u8>, IdLengthErr
     }
 }
 
+/// Root spans, which begin a new trace, have no parent.
+fn into_parent_bytes(id: trace_context::Id) -> Result<Vec<u8>, IdLengthError> {
+    if id.as_ref().is_empty() {
+        return Ok(Vec::new());
+    }
+    into_bytes(id, 8)
+}
+
 fn truncatable(value: String) -> oc::TruncatableString {
     oc::TruncatableString {
         value,
@@ -18,12 +18,15 @@ pub mod config;
 pub mod control;
 pub mod disco_cache;
 pub mod dns;
+pub mod endpoint_pins;
 pub mod errors;
 pub mod http_tracing;
 pub mod metrics;
 pub mod proxy;
 pub mod serve;
+pub mod startup;
 pub mod svc;
+pub mod tcp_tracing;
 pub mod telemetry;
 pub mod transport;
 
@@ -60,10 +63,13 @@ const DEFAULT_PORT: u16 = 80;
 #[derive(Clone, Debug)]
 pub struct ProxyRuntime {
     pub identity: identity::creds::Receiver,
+    /// Credentials presented in place of `identity` to matching servers.
+    pub shadow_identity: Option<tls::Shadow<identity::creds::Receiver>>,
     pub metrics: metrics::Proxy,
     pub tap: proxy::tap::Registry,
     pub span_sink: http_tracing::OpenCensusSink,
     pub drain: drain::Watch,
+    pub startup: startup::StartupGate,
 }
 
 pub fn http_request_authority_addr<B>(req: &http::Request<B>) -> Result<Addr, addr::Error> {
@@ -9,7 +9,8 @@
 pub use crate::transport::labels::{TargetAddr, TlsAccept};
 use crate::{
     classify::{Class, SuccessOrFailure},
-    control, http_metrics, http_metrics as metrics, opencensus, profiles, stack_metrics,
+    control, detect, http_metrics, http_metrics as metrics, identity, opencensus, profiles, proxy,
+    stack_metrics,
     svc::Param,
     telemetry, tls,
     transport::{self, labels::TlsConnect},
@@ -39,6 +40,7 @@ pub struct Metrics {
     pub proxy: Proxy,
     pub control: ControlHttp,
     pub opencensus: opencensus::metrics::Registry,
+    pub tls: identity::Metrics,
 }
 
 #[derive(Clone, Debug)]
@@ -49,6 +51,8 @@ pub struct Proxy {
     pub http_endpoint: HttpEndpoint,
     pub transport: transport::Metrics,
     pub stack: Stack,
+    pub detect: detect::DetectMetrics,
+    pub http_server: proxy::http::ServerMetrics,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -145,12 +149,19 @@ where
 impl Metrics {
     pub fn new(
         retain_idle: Duration,
+        latency_sample_rate: f64,
         start_time: telemetry::StartTime,
     ) -> (Self, impl FmtMetrics + Clone + Send + 'static) {
         let process = telemetry::process::Report::new(start_time);
 
         let build_info = telemetry::build_info::Report::new();
 
+        let tls = identity::Metrics::default();
+
+        let detect = detect::DetectMetrics::default();
+
+        let http_server = proxy::http::ServerMetrics::default();
+
         let (control, control_report) = {
             let m = metrics::Requests::<ControlLabels, Class>::default();
             let r = m.clone().into_report(retain_idle).with_prefix("control");
@@ -158,13 +169,15 @@ impl Metrics {
         };
 
         let (http_endpoint, endpoint_report) = {
-            let m = metrics::Requests::<EndpointLabels, Class>::default();
+            let m = metrics::Requests::<EndpointLabels, Class>::default()
+                .with_latency_sample_rate(latency_sample_rate);
             let r = m.clone().into_report(retain_idle);
             (m, r)
         };
 
         let (http_profile_route, profile_route_report) = {
-            let m = metrics::Requests::<ProfileRouteLabels, Class>::default();
+            let m = metrics::Requests::<ProfileRouteLabels, Class>::default()
+                .with_latency_sample_rate(latency_sample_rate);
             let r = m.clone().into_report(retain_idle).with_prefix("route");
             (m, r)
         };
@@ -195,6 +208,8 @@ impl Metrics {
             http_profile_route_actual,
             stack: stack.clone(),
             transport,
+            detect: detect.clone(),
+            http_server: http_server.clone(),
         };
 
         let (opencensus, opencensus_report) = opencensus::metrics::new();
@@ -203,6 +218,7 @@ impl Metrics {
             proxy,
             control,
             opencensus,
+            tls: tls.clone(),
         };
 
         let report = endpoint_report
@@ -214,7 +230,11 @@ impl Metrics {
             .and_report(opencensus_report)
             .and_report(stack)
             .and_report(process)
-            .and_report(build_info);
+            .and_report(build_info)
+            .and_report(telemetry::tls::Report::new(tls))
+            .and_report(telemetry::detect::Report::new(detect))
+            .and_report(telemetry::h1::Report::new(http_server.clone()))
+            .and_report(telemetry::h2::Report::new(http_server));
 
         (metrics, report)
     }
@@ -0,0 +1,253 @@
+//! Gates accepted connections on the proxy's readiness at startup.
+//!
+//! The proxy cannot serve traffic until it has been configured--e.g. until its
+//! identity has been certified. A [`StartupGate`] determines how connections
+//! accepted before then are handled: they are either held until the proxy
+//! becomes ready (failing if it does not become ready in time) or failed
+//! immediately.
+
+use crate::{svc, Error};
+use futures::{ready, TryFutureExt};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::sync::watch;
+use tracing::debug;
+
+/// Determines how connections accepted before the proxy is ready are handled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StartupMode {
+    /// Connections wait up to `timeout` for the proxy to become ready.
+    Hold { timeout: Duration },
+
+    /// Connections fail immediately.
+    Fail,
+}
+
+/// Tracks whether the proxy is ready to serve connections.
+#[derive(Clone, Debug)]
+pub struct StartupGate {
+    ready: watch::Receiver<bool>,
+    mode: StartupMode,
+}
+
+/// Marks a [`StartupGate`] as ready when released.
+#[derive(Debug)]
+pub struct StartupLatch(watch::Sender<bool>);
+
+#[derive(Debug, thiserror::Error)]
+#[error("proxy is not ready")]
+pub struct NotReady(());
+
+#[derive(Debug, thiserror::Error)]
+#[error("proxy did not become ready within {0:?}")]
+pub struct StartupTimeout(Duration);
+
+#[derive(Clone, Debug)]
+pub struct NewStartupGate<N> {
+    inner: N,
+    gate: StartupGate,
+}
+
+pub struct Gated<S> {
+    inner: S,
+    gate: StartupGate,
+    wait: Option<Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'static>>>,
+}
+
+// === impl StartupGate ===
+
+impl StartupGate {
+    /// Returns a gate that holds or fails connections, per `mode`, until the
+    /// returned latch is released.
+    pub fn new(mode: StartupMode) -> (Self, StartupLatch) {
+        let (tx, ready) = watch::channel(false);
+        (Self { ready, mode }, StartupLatch(tx))
+    }
+
+    /// Returns a gate that is always ready.
+    pub fn ready() -> Self {
+        let (_, ready) = watch::channel(true);
+        Self {
+            ready,
+            mode: StartupMode::Fail,
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        *self.ready.borrow()
+    }
+
+    /// Waits for the gate to become ready, or fails per its mode.
+    fn wait(&self) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'static>> {
+        let timeout = match self.mode {
+            StartupMode::Fail => {
+                debug!("Failing connection before the proxy is ready");
+                return Box::pin(futures::future::err(NotReady(()).into()));
+            }
+            StartupMode::Hold { timeout } => timeout,
+        };
+        debug!(?timeout, "Holding connection until the proxy is ready");
+        let mut ready = self.ready.clone();
+        Box::pin(async move {
+            let wait = async move {
+                while !*ready.borrow_and_update() {
+                    // If the latch is dropped without being released, the
+                    // proxy will never become ready.
+                    if ready.changed().await.is_err() {
+                        return Err(NotReady(()));
+                    }
+                }
+                Ok(())
+            };
+            match tokio::time::timeout(timeout, wait).await {
+                Ok(res) => res.map_err(Into::into),
+                Err(_) => Err(StartupTimeout(timeout).into()),
+            }
+        })
+    }
+}
+
+impl Default for StartupGate {
+    fn default() -> Self {
+        Self::ready()
+    }
+}
+
+// === impl StartupLatch ===
+
+impl StartupLatch {
+    /// Marks the gate as ready, releasing all held connections.
+    pub fn release(self) {
+        let _ = self.0.send(true);
+    }
+}
+
+// === impl NewStartupGate ===
+
+impl<N> NewStartupGate<N> {
+    pub fn layer(gate: StartupGate) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            inner,
+            gate: gate.clone(),
+        })
+    }
+}
+
+impl<T, N: svc::NewService<T>> svc::NewService<T> for NewStartupGate<N> {
+    type Service = Gated<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        Gated {
+            inner: self.inner.new_service(target),
+            gate: self.gate.clone(),
+            wait: None,
+        }
+    }
+}
+
+// === impl Gated ===
+
+impl<Req, S> svc::Service<Req> for Gated<S>
+where
+    S: svc::Service<Req>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = futures::future::ErrInto<S::Future, Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.wait.is_none() && !self.gate.is_ready() {
+            self.wait = Some(self.gate.wait());
+        }
+        if let Some(wait) = self.wait.as_mut() {
+            let res = ready!(wait.as_mut().poll(cx));
+            self.wait = None;
+            res?;
+        }
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    #[inline]
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.inner.call(req).err_into()
+    }
+}
+
+impl<S: std::fmt::Debug> std::fmt::Debug for Gated<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Gated")
+            .field("inner", &self.inner)
+            .field("gate", &self.gate)
+            .field("waiting", &self.wait.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svc::{NewService, ServiceExt};
+
+    fn new_gated(gate: StartupGate) -> Gated<svc::BoxService<(), (), Error>> {
+        let inner = |_: ()| svc::BoxService::new(svc::mk(|()| futures::future::ok::<_, Error>(())));
+        svc::layer::Layer::layer(&NewStartupGate::layer(gate), inner).new_service(())
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn holds_until_ready() {
+        let (gate, latch) = StartupGate::new(StartupMode::Hold {
+            timeout: Duration::from_secs(10),
+        });
+        let conn = tokio::spawn(new_gated(gate).oneshot(()));
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(!conn.is_finished(), "connection must be held");
+
+        latch.release();
+        conn.await
+            .unwrap()
+            .expect("connection must succeed once ready");
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn hold_times_out() {
+        let (gate, _latch) = StartupGate::new(StartupMode::Hold {
+            timeout: Duration::from_secs(10),
+        });
+        let error = new_gated(gate)
+            .oneshot(())
+            .await
+            .expect_err("connection must fail");
+        assert!(error.is::<StartupTimeout>(), "unexpected error: {error}");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn fails_until_ready() {
+        let (gate, latch) = StartupGate::new(StartupMode::Fail);
+
+        let error = new_gated(gate.clone())
+            .oneshot(())
+            .await
+            .expect_err("connection must fail");
+        assert!(error.is::<NotReady>(), "unexpected error: {error}");
+
+        latch.release();
+        new_gated(gate)
+            .oneshot(())
+            .await
+            .expect("connection must succeed once ready");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn ready_by_default() {
+        new_gated(StartupGate::default())
+            .oneshot(())
+            .await
+            .expect("connection must succeed");
+    }
+}
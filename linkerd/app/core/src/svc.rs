@@ -153,11 +153,18 @@ impl<S> Stack<S> {
         self.push(stack::new_service::FromMakeService::layer())
     }
 
+    /// Reconnects the inner service with the given backoff. If `max_elapsed` is
+    /// set, the service fails once it has been unable to reconnect for that
+    /// long.
     pub fn push_new_reconnect(
         self,
         backoff: ExponentialBackoff,
+        max_elapsed: Option<Duration>,
     ) -> Stack<NewReconnect<AlwaysReconnect, S>> {
-        self.push(NewReconnect::layer(AlwaysReconnect(backoff)))
+        self.push(NewReconnect::layer_with_max_elapsed(
+            AlwaysReconnect(backoff),
+            max_elapsed,
+        ))
     }
 
     /// Assuming `S` implements `NewService` or `MakeService`, applies the given
@@ -266,6 +273,7 @@ impl<S> Stack<S> {
         self,
         discover: D,
         idle: Duration,
+        jitter: Duration,
     ) -> Stack<NewCachedDiscover<K, D, S>>
     where
         K: Clone + fmt::Debug + Eq + Hash + Send + Sync + 'static,
@@ -273,7 +281,7 @@ impl<S> Stack<S> {
         D::Response: Clone + Send + Sync + 'static,
         D::Future: Send + Unpin,
     {
-        self.push(NewCachedDiscover::layer(discover, idle))
+        self.push(NewCachedDiscover::layer(discover, idle, jitter))
     }
 
     /// Validates that this stack serves T-typed targets.
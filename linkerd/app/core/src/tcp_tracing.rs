@@ -0,0 +1,278 @@
+//! Emits a span for each outbound TCP connection when tracing is enabled.
+//!
+//! Opaque connections do not carry a trace context, so each connection's span
+//! begins a new trace. The span covers the lifetime of the connection and
+//! records the endpoint's address and the number of bytes read from and
+//! written to it.
+
+use crate::{
+    http_tracing::{Labels, OpenCensusSink, SpanConverter},
+    io, svc,
+    transport::addrs::{Remote, ServerAddr},
+};
+use futures::ready;
+use linkerd_errno::Errno;
+use linkerd_trace_context::{self as trace_context, SpanSink};
+use pin_project::pin_project;
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+    time::SystemTime,
+};
+use tracing::debug;
+
+/// Wraps the connections returned by a connector so that each emits a span.
+#[derive(Clone)]
+pub struct TraceConnect<C> {
+    inner: C,
+    sink: Option<SpanConverter>,
+}
+
+#[pin_project]
+pub struct TraceConnectFuture<F> {
+    #[pin]
+    inner: F,
+    span: Option<ConnectionSpan>,
+}
+
+/// Records a connection's telemetry, emitting a span when the connection is
+/// closed.
+pub struct ConnectionSpan {
+    sink: Option<SpanConverter>,
+    server_addr: SocketAddr,
+    start: SystemTime,
+    bytes_read: u64,
+    bytes_written: u64,
+}
+
+pub type TracedIo<I> = io::SensorIo<I, ConnectionSpan>;
+
+pub fn client<C>(
+    sink: OpenCensusSink,
+    labels: impl Into<Labels>,
+) -> impl svc::layer::Layer<C, Service = TraceConnect<C>> + Clone {
+    let sink = SpanConverter::client(sink, labels);
+    svc::layer::mk(move |inner| TraceConnect {
+        inner,
+        sink: sink.clone(),
+    })
+}
+
+// === impl TraceConnect ===
+
+impl<T, C> svc::Service<T> for TraceConnect<C>
+where
+    T: svc::Param<Remote<ServerAddr>>,
+    C: svc::MakeConnection<T>,
+{
+    type Response = (TracedIo<C::Connection>, C::Metadata);
+    type Error = C::Error;
+    type Future = TraceConnectFuture<C::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        let Remote(ServerAddr(server_addr)) = target.param();
+        TraceConnectFuture {
+            inner: self.inner.connect(target),
+            span: Some(ConnectionSpan {
+                sink: self.sink.clone(),
+                server_addr,
+                start: SystemTime::now(),
+                bytes_read: 0,
+                bytes_written: 0,
+            }),
+        }
+    }
+}
+
+impl<C: fmt::Debug> fmt::Debug for TraceConnect<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceConnect")
+            .field("inner", &self.inner)
+            .field("enabled", &self.sink.is_enabled())
+            .finish()
+    }
+}
+
+// === impl TraceConnectFuture ===
+
+impl<F, I, M, E> Future for TraceConnectFuture<F>
+where
+    F: Future<Output = Result<(I, M), E>>,
+{
+    type Output = Result<(TracedIo<I>, M), E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let (io, meta) = ready!(this.inner.poll(cx))?;
+        let span = this.span.take().expect("polled after ready");
+        Poll::Ready(Ok((io::SensorIo::new(io, span), meta)))
+    }
+}
+
+// === impl ConnectionSpan ===
+
+impl ConnectionSpan {
+    fn emit(&mut self, eos: Option<Errno>) {
+        // The span is emitted at most once.
+        let mut sink = match self.sink.take() {
+            Some(sink) => sink,
+            None => return,
+        };
+
+        let mut labels = HashMap::new();
+        labels.insert("peer.addr", self.server_addr.to_string());
+        labels.insert("tcp.bytes_read", self.bytes_read.to_string());
+        labels.insert("tcp.bytes_written", self.bytes_written.to_string());
+        if let Some(errno) = eos {
+            labels.insert("tcp.error", errno.to_string());
+        }
+
+        let mut rng = rand::thread_rng();
+        let span = trace_context::Span {
+            trace_id: trace_context::Id::new_trace_id(&mut rng),
+            span_id: trace_context::Id::new_span_id(&mut rng),
+            parent_id: trace_context::Id::default(),
+            span_name: format!("tcp {}", self.server_addr),
+            start: self.start,
+            end: SystemTime::now(),
+            labels,
+        };
+        if let Err(error) = sink.try_send(span) {
+            debug!(%error, "Failed to emit connection span");
+        }
+    }
+}
+
+impl io::Sensor for ConnectionSpan {
+    fn record_read(&mut self, sz: usize) {
+        self.bytes_read += sz as u64;
+    }
+
+    fn record_write(&mut self, sz: usize) {
+        self.bytes_written += sz as u64;
+    }
+
+    fn record_close(&mut self, eos: Option<Errno>) {
+        self.emit(eos);
+    }
+
+    fn record_error<T>(&mut self, op: Poll<std::io::Result<T>>) -> Poll<std::io::Result<T>> {
+        if let Poll::Ready(Err(e)) = &op {
+            if e.kind() != std::io::ErrorKind::WouldBlock {
+                self.emit(e.raw_os_error().map(Into::into));
+            }
+        }
+        op
+    }
+}
+
+impl Drop for ConnectionSpan {
+    fn drop(&mut self) {
+        self.emit(None);
+    }
+}
+
+impl fmt::Debug for ConnectionSpan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionSpan")
+            .field("server_addr", &self.server_addr)
+            .field("bytes_read", &self.bytes_read)
+            .field("bytes_written", &self.bytes_written)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svc::ServiceExt;
+    use linkerd_opencensus::proto::trace::v1 as oc;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        sync::mpsc,
+    };
+
+    #[derive(Clone, Debug)]
+    struct Target(SocketAddr);
+
+    impl svc::Param<Remote<ServerAddr>> for Target {
+        fn param(&self) -> Remote<ServerAddr> {
+            Remote(ServerAddr(self.0))
+        }
+    }
+
+    fn attribute(span: &oc::Span, key: &str) -> String {
+        let attrs = &span.attributes.as_ref().expect("span must have attributes");
+        match attrs.attribute_map.get(key).and_then(|v| v.value.as_ref()) {
+            Some(oc::attribute_value::Value::StringValue(s)) => s.value.clone(),
+            v => panic!("unexpected {key} attribute: {v:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn emits_span_when_connection_closes() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let mut labels = HashMap::new();
+        labels.insert("direction".to_string(), "outbound".to_string());
+
+        let (io, mut server) = tokio::io::duplex(64);
+        let mut io = Some(io);
+        let connect = svc::layer::Layer::layer(
+            &client(Some(tx), labels),
+            svc::mk(move |_: Target| {
+                let io = io.take().expect("must only connect once");
+                futures::future::ok::<_, std::io::Error>((io, ()))
+            }),
+        );
+
+        let addr = SocketAddr::from(([192, 0, 2, 10], 8080));
+        let (mut io, ()) = connect.oneshot(Target(addr)).await.expect("must connect");
+        io.write_all(b"hello").await.unwrap();
+        let mut buf = [0u8; 5];
+        server.read_exact(&mut buf).await.unwrap();
+        server.write_all(b"world!").await.unwrap();
+        let mut buf = [0u8; 6];
+        io.read_exact(&mut buf).await.unwrap();
+        assert!(
+            rx.try_recv().is_err(),
+            "span must not be emitted while open"
+        );
+
+        drop(io);
+        let span = rx.recv().await.expect("span must be emitted");
+        assert_eq!(span.trace_id.len(), 16);
+        assert_eq!(span.span_id.len(), 8);
+        assert!(span.parent_span_id.is_empty());
+        assert_eq!(attribute(&span, "peer.addr"), addr.to_string());
+        assert_eq!(attribute(&span, "tcp.bytes_written"), "5");
+        assert_eq!(attribute(&span, "tcp.bytes_read"), "6");
+        assert_eq!(attribute(&span, "direction"), "outbound");
+        assert!(rx.try_recv().is_err(), "span must be emitted once");
+    }
+
+    #[tokio::test]
+    async fn disabled_without_sink() {
+        let (io, _server) = tokio::io::duplex(64);
+        let mut io = Some(io);
+        let connect = svc::layer::Layer::layer(
+            &client(None, HashMap::new()),
+            svc::mk(move |_: Target| {
+                let io = io.take().expect("must only connect once");
+                futures::future::ok::<_, std::io::Error>((io, ()))
+            }),
+        );
+        let addr = SocketAddr::from(([192, 0, 2, 10], 8080));
+        let (mut io, ()) = connect.oneshot(Target(addr)).await.expect("must connect");
+        io.write_all(b"hello").await.unwrap();
+        drop(io);
+    }
+}
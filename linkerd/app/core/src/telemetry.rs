@@ -1,3 +1,7 @@
 pub mod build_info;
+pub mod detect;
+pub mod h1;
+pub mod h2;
 pub mod process;
+pub mod tls;
 pub use self::process::StartTime;
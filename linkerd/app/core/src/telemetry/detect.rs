@@ -0,0 +1,41 @@
+use linkerd_detect::{self as detect, DetectErrorReason};
+use linkerd_metrics::{metrics, Counter, FmtLabels, FmtMetrics};
+use std::fmt;
+
+metrics! {
+    detect_errors_total: Counter {
+        "Total number of connections that failed protocol detection with an error"
+    }
+}
+
+/// Reports protocol detection errors that are tracked by the detect layer.
+#[derive(Clone, Debug)]
+pub struct Report(detect::DetectMetrics);
+
+struct ReasonLabel(DetectErrorReason);
+
+impl Report {
+    pub fn new(metrics: detect::DetectMetrics) -> Self {
+        Self(metrics)
+    }
+}
+
+impl FmtMetrics for Report {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        detect_errors_total.fmt_help(f)?;
+        for reason in DetectErrorReason::ALL {
+            detect_errors_total.fmt_metric_labeled(
+                f,
+                &Counter::from(self.0.errors(reason)),
+                &ReasonLabel(reason),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FmtLabels for ReasonLabel {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "reason=\"{}\"", self.0)
+    }
+}
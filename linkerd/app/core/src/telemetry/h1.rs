@@ -0,0 +1,27 @@
+use crate::proxy::http;
+use linkerd_metrics::{metrics, Counter, FmtMetrics};
+use std::fmt;
+
+metrics! {
+    http_parse_errors_total: Counter {
+        "Total number of HTTP/1 connections closed because the client sent bytes that could not be parsed as HTTP/1"
+    }
+}
+
+/// Reports HTTP/1 connection metrics that are tracked by the HTTP server.
+#[derive(Clone, Debug)]
+pub struct Report(http::ServerMetrics);
+
+impl Report {
+    pub fn new(metrics: http::ServerMetrics) -> Self {
+        Self(metrics)
+    }
+}
+
+impl FmtMetrics for Report {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        http_parse_errors_total.fmt_help(f)?;
+        http_parse_errors_total.fmt_metric(f, &Counter::from(self.0.http1_parse_errors()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use crate::proxy::http;
+use linkerd_metrics::{metrics, Counter, FmtMetrics};
+use std::fmt;
+
+metrics! {
+    h2_oversized_frame_closes_total: Counter {
+        "Total number of HTTP/2 connections closed because the client sent a frame larger than the maximum frame size"
+    },
+    h2_continuation_flood_closes_total: Counter {
+        "Total number of HTTP/2 connections closed because the client sent a header block, across CONTINUATION frames, larger than the maximum header block size"
+    },
+    h2_window_update_flood_closes_total: Counter {
+        "Total number of HTTP/2 connections closed because the client sent WINDOW_UPDATE frames faster than the maximum rate"
+    }
+}
+
+/// Reports HTTP/2 connection metrics that are tracked by the HTTP server.
+#[derive(Clone, Debug)]
+pub struct Report(http::ServerMetrics);
+
+impl Report {
+    pub fn new(metrics: http::ServerMetrics) -> Self {
+        Self(metrics)
+    }
+}
+
+impl FmtMetrics for Report {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        h2_oversized_frame_closes_total.fmt_help(f)?;
+        h2_oversized_frame_closes_total
+            .fmt_metric(f, &Counter::from(self.0.h2_oversized_frame_closes()))?;
+        h2_continuation_flood_closes_total.fmt_help(f)?;
+        h2_continuation_flood_closes_total
+            .fmt_metric(f, &Counter::from(self.0.h2_continuation_flood_closes()))?;
+        h2_window_update_flood_closes_total.fmt_help(f)?;
+        h2_window_update_flood_closes_total
+            .fmt_metric(f, &Counter::from(self.0.h2_window_update_flood_closes()))?;
+        Ok(())
+    }
+}
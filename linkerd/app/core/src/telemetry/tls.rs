@@ -0,0 +1,57 @@
+use linkerd_meshtls as meshtls;
+use linkerd_metrics::{metrics, Counter, FmtLabels, FmtMetrics};
+use std::fmt;
+
+metrics! {
+    tls_truncated_records_total: Counter {
+        "Total number of TLS connections closed by the peer partway through a TLS record"
+    },
+    tls_negotiated_total: Counter {
+        "Total number of successful TLS handshakes by the protocol version and cipher suite they negotiated"
+    }
+}
+
+/// Reports TLS connection metrics that are tracked by the TLS implementation.
+#[derive(Clone, Debug)]
+pub struct Report(meshtls::Metrics);
+
+/// Labels handshakes by direction: the proxy accepts inbound connections and
+/// initiates outbound connections.
+struct NegotiatedLabels<'n>(&'n meshtls::Negotiated);
+
+impl Report {
+    pub fn new(metrics: meshtls::Metrics) -> Self {
+        Self(metrics)
+    }
+}
+
+impl FmtMetrics for Report {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        tls_truncated_records_total.fmt_help(f)?;
+        tls_truncated_records_total.fmt_metric(f, &Counter::from(self.0.truncated_records()))?;
+
+        tls_negotiated_total.fmt_help(f)?;
+        for n in &self.0.negotiated() {
+            tls_negotiated_total.fmt_metric_labeled(
+                f,
+                &Counter::from(n.handshakes),
+                &NegotiatedLabels(n),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FmtLabels for NegotiatedLabels<'_> {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = match self.0.side {
+            meshtls::Side::Client => "outbound",
+            meshtls::Side::Server => "inbound",
+        };
+        write!(
+            f,
+            "direction=\"{}\",version=\"{}\",cipher=\"{}\"",
+            direction, self.0.version, self.0.cipher
+        )
+    }
+}
@@ -0,0 +1,105 @@
+//! Retries connections that the application refuses while it starts.
+//!
+//! The proxy may begin accepting inbound connections before the local
+//! application is listening, in which case connections to the application are
+//! refused. When a grace period is configured, refused connections are retried
+//! with backoff until the grace period elapses so that the application has a
+//! chance to come up. Other connection errors are not retried.
+
+use futures::StreamExt;
+use linkerd_app_core::{
+    cause_ref,
+    exp_backoff::ExponentialBackoff,
+    io,
+    svc::{self, ServiceExt},
+    Error,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::time::{self, Duration, Instant};
+use tracing::debug;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug)]
+pub struct ConnectGrace<S> {
+    inner: S,
+    grace: Option<Duration>,
+    backoff: ExponentialBackoff,
+}
+
+// === impl ConnectGrace ===
+
+impl<S> ConnectGrace<S> {
+    pub fn layer(
+        grace: Option<Duration>,
+        backoff: ExponentialBackoff,
+    ) -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            inner,
+            grace,
+            backoff,
+        })
+    }
+}
+
+impl<T, S> svc::Service<T> for ConnectGrace<S>
+where
+    T: Clone + Send + 'static,
+    S: svc::Service<T, Error = Error> + Clone + Send + 'static,
+    S::Response: Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, Error>> + Send + 'static>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        let connect = self.inner.call(target.clone());
+        let grace = match self.grace {
+            Some(grace) => grace,
+            None => return Box::pin(connect),
+        };
+
+        let deadline = Instant::now() + grace;
+        let inner = self.inner.clone();
+        let mut backoff = self.backoff.stream();
+        Box::pin(async move {
+            let mut error = match connect.await {
+                Ok(conn) => return Ok(conn),
+                Err(error) => error,
+            };
+            loop {
+                if !is_refused(&*error) || Instant::now() >= deadline {
+                    return Err(error);
+                }
+                debug!(%error, "Connection refused; retrying");
+
+                // Wait for the next attempt, but no later than the end of the
+                // grace period, so that a final attempt is made at its end.
+                let _ = time::timeout_at(deadline, backoff.next()).await;
+
+                let connect = inner.clone().oneshot(target.clone());
+                error = match connect.await {
+                    Ok(conn) => return Ok(conn),
+                    Err(error) => error,
+                };
+            }
+        })
+    }
+}
+
+fn is_refused(error: &(dyn std::error::Error + 'static)) -> bool {
+    cause_ref::<io::Error>(error)
+        .map(|e| e.kind() == io::ErrorKind::ConnectionRefused)
+        .unwrap_or(false)
+}
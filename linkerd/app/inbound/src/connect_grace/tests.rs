@@ -0,0 +1,106 @@
+use super::*;
+use linkerd_app_core::svc::Layer;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Builds a connector that refuses connections until the application starts
+/// listening at `ready`.
+fn connect(
+    ready: Instant,
+    attempts: Arc<AtomicUsize>,
+) -> impl svc::Service<(), Response = (), Error = Error, Future = impl Send> + Clone {
+    svc::mk(move |()| {
+        attempts.fetch_add(1, Ordering::SeqCst);
+        let refused = Instant::now() < ready;
+        async move {
+            if refused {
+                return Err(io::Error::from(io::ErrorKind::ConnectionRefused).into());
+            }
+            Ok::<_, Error>(())
+        }
+    })
+}
+
+fn backoff() -> ExponentialBackoff {
+    ExponentialBackoff::try_new(Duration::from_millis(100), Duration::from_millis(500), 0.1)
+        .unwrap()
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn connects_once_app_is_listening() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let start = Instant::now();
+    let connect = ConnectGrace::layer(Some(Duration::from_secs(5)), backoff())
+        .layer(connect(start + Duration::from_secs(2), attempts.clone()));
+
+    connect
+        .oneshot(())
+        .await
+        .expect("connection must succeed within the grace period");
+    assert!(Instant::now().saturating_duration_since(start) < Duration::from_secs(5));
+    assert!(
+        attempts.load(Ordering::SeqCst) > 1,
+        "connections must be retried"
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn fails_after_grace() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let start = Instant::now();
+    let connect = ConnectGrace::layer(Some(Duration::from_secs(1)), backoff())
+        .layer(connect(start + Duration::from_secs(2), Default::default()));
+
+    let error = connect
+        .oneshot(())
+        .await
+        .expect_err("connection must fail once the grace period elapses");
+    assert!(is_refused(&*error), "{}", error);
+    assert_eq!(
+        Instant::now().saturating_duration_since(start),
+        Duration::from_secs(1)
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn no_retries_without_grace() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let connect = ConnectGrace::layer(None, backoff()).layer(connect(
+        Instant::now() + Duration::from_secs(2),
+        attempts.clone(),
+    ));
+
+    connect
+        .oneshot(())
+        .await
+        .expect_err("refused connections must not be retried");
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn other_errors_are_not_retried() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let counted = attempts.clone();
+    let denied = svc::mk(move |()| {
+        counted.fetch_add(1, Ordering::SeqCst);
+        let error = io::Error::from(io::ErrorKind::PermissionDenied);
+        futures::future::err::<(), Error>(error.into())
+    });
+    let connect = ConnectGrace::layer(Some(Duration::from_secs(5)), backoff()).layer(denied);
+
+    connect.oneshot(()).await.expect_err("connection must fail");
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
@@ -14,16 +14,21 @@ use linkerd_app_core::{
     },
     Error, Infallible,
 };
-use std::{fmt::Debug, time};
-use tracing::info;
+use std::{collections::HashMap, fmt::Debug, sync::Arc, time};
+use tracing::{debug, info};
 
 #[cfg(test)]
 mod tests;
 
+/// Maps the SNI values of TLS connections that are passed through to the
+/// application to the local ports to which they are forwarded.
+pub type SniPorts = Arc<HashMap<tls::ServerId, u16>>;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct Forward {
     client_addr: Remote<ClientAddr>,
     orig_dst_addr: OrigDstAddr,
+    server_addr: Remote<ServerAddr>,
     tls: tls::ConditionalServerTls,
     permit: ServerPermit,
 }
@@ -114,7 +119,10 @@ impl<N> Inbound<N> {
                 .push(transport::metrics::NewServer::layer(
                     rt.metrics.proxy.transport.clone(),
                 ))
-                .push_map_target(Forward::from)
+                .push_map_target({
+                    let sni_ports = cfg.tls_sni_ports.clone();
+                    move |(permit, tls): (ServerPermit, Tls)| Forward::new(permit, tls, &sni_ports)
+                })
                 .push(policy::NewTcpPolicy::layer(rt.metrics.tcp_authz.clone()));
 
             let detect_timeout = cfg.proxy.detect_protocol_timeout;
@@ -203,7 +211,10 @@ impl<N> Inbound<N> {
                 .push(transport::metrics::NewServer::layer(
                     rt.metrics.proxy.transport.clone(),
                 ))
-                .push_map_target(Forward::from)
+                .push_map_target({
+                    let sni_ports = cfg.tls_sni_ports.clone();
+                    move |(permit, tls): (ServerPermit, Tls)| Forward::new(permit, tls, &sni_ports)
+                })
                 .push(policy::NewTcpPolicy::layer(rt.metrics.tcp_authz.clone()));
 
             let detect_timeout = cfg.proxy.detect_protocol_timeout;
@@ -251,7 +262,10 @@ impl<N> Inbound<N> {
                     forward.into_inner(),
                 )
                 .lift_new_with_target()
-                .push(detect::NewDetectService::layer(ConfigureHttpDetect));
+                .push(detect::NewDetectService::layer(
+                    ConfigureHttpDetect,
+                    rt.metrics.proxy.detect.clone(),
+                ));
 
             http.push_on_service(svc::MapTargetLayer::new(io::BoxedIo::new))
                 .push(transport::metrics::NewServer::layer(
@@ -294,11 +308,22 @@ impl<N> Inbound<N> {
 
 // === impl Forward ===
 
-impl From<(ServerPermit, Tls)> for Forward {
-    fn from((permit, tls): (ServerPermit, Tls)) -> Self {
+impl Forward {
+    /// Builds a target that forwards the connection to its original
+    /// destination or, when the connection's TLS is passed through to the
+    /// application with an SNI that is mapped to a local port, to that port.
+    fn new(permit: ServerPermit, tls: Tls, sni_ports: &SniPorts) -> Self {
+        let mut server_addr = std::net::SocketAddr::from(tls.orig_dst_addr);
+        if let tls::ConditionalServerTls::Some(tls::ServerTls::Passthru { sni }) = &tls.status {
+            if let Some(&port) = sni_ports.get(sni) {
+                debug!(%sni, port, "Routing TLS connection by SNI");
+                server_addr.set_port(port);
+            }
+        }
         Self {
             client_addr: tls.client_addr,
             orig_dst_addr: tls.orig_dst_addr,
+            server_addr: Remote(ServerAddr(server_addr)),
             tls: tls.status,
             permit,
         }
@@ -307,7 +332,7 @@ impl From<(ServerPermit, Tls)> for Forward {
 
 impl svc::Param<Remote<ServerAddr>> for Forward {
     fn param(&self) -> Remote<ServerAddr> {
-        Remote(ServerAddr(self.orig_dst_addr.into()))
+        self.server_addr
     }
 }
 
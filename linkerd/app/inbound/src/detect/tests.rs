@@ -3,7 +3,7 @@ use crate::test_util;
 use futures::future;
 use linkerd_app_core::{
     io::AsyncWriteExt,
-    svc::{NewService, ServiceExt},
+    svc::{NewService, Param, ServiceExt},
     trace, Error,
 };
 use linkerd_proxy_server_policy::{Authentication, Authorization, Meta, Protocol, ServerPolicy};
@@ -194,6 +194,127 @@ async fn hinted_http2() {
         .expect("should succeed");
 }
 
+#[tokio::test(flavor = "current_thread")]
+async fn forced_http2_skips_detection() {
+    let _trace = trace::test::trace_init();
+    let policy = policy::defaults::http2(policy::defaults::all_unauthenticated(
+        std::time::Duration::from_secs(10),
+    ));
+    let target = Tls {
+        client_addr: client_addr(),
+        orig_dst_addr: orig_dst_addr(),
+        status: tls::ConditionalServerTls::None(tls::NoServerTls::NoClientHello),
+        policy: allow(policy.protocol),
+    };
+
+    // The connection is handled as HTTP/2 even though the client sends an
+    // HTTP/1 request, since no detection is performed.
+    let (ior, mut iow) = io::duplex(100);
+    iow.write_all(HTTP1).await.unwrap();
+
+    inbound()
+        .with_stack(svc::ArcNewService::new(|t: Http| {
+            assert_eq!(t.http, http::Version::H2, "connection must use HTTP/2");
+            svc::BoxService::new(svc::mk(|_: io::BoxedIo| future::ok::<(), Error>(())))
+        }))
+        .push_detect_http(new_panic("tcp stack must not be used"))
+        .into_inner()
+        .new_service(target)
+        .oneshot(ior)
+        .await
+        .expect("should succeed");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn routes_passthru_tls_by_sni() {
+    let _trace = trace::test::trace_init();
+
+    let sni_ports = [("foo.example.com", 5001), ("bar.example.com", 5002)];
+    let mut config = test_util::default_config();
+    config.tls_sni_ports = Arc::new(
+        sni_ports
+            .iter()
+            .map(|(sni, port)| (tls::ServerId(sni.parse().unwrap()), *port))
+            .collect(),
+    );
+    let (rt, _shutdown) = test_util::runtime();
+    let inbound = Inbound::new(config, rt);
+
+    for (sni, port) in sni_ports {
+        let target = Tls {
+            client_addr: client_addr(),
+            orig_dst_addr: orig_dst_addr(),
+            status: tls::ConditionalServerTls::Some(tls::ServerTls::Passthru {
+                sni: tls::ServerId(sni.parse().unwrap()),
+            }),
+            policy: allow(Protocol::Detect {
+                timeout: std::time::Duration::from_secs(10),
+                http: Arc::new([linkerd_proxy_server_policy::http::default(authzs())]),
+                tcp_authorizations: authzs(),
+            }),
+        };
+
+        // The passed-through TLS stream is not HTTP.
+        let (ior, mut iow) = io::duplex(100);
+        iow.write_all(NOT_HTTP).await.unwrap();
+        inbound
+            .clone()
+            .with_stack(new_panic("http stack must not be used"))
+            .push_detect_http(svc::ArcNewService::new(move |t: Forward| {
+                let Remote(ServerAddr(addr)) = t.param();
+                assert_eq!(addr.ip(), orig_dst_addr().0.ip());
+                assert_eq!(addr.port(), port, "{sni} must be routed to port {port}");
+                svc::BoxService::new(svc::mk(|_: io::BoxedIo| future::ok::<(), Error>(())))
+            }))
+            .into_inner()
+            .new_service(target)
+            .oneshot(ior)
+            .await
+            .expect("should succeed");
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn forwards_unmapped_sni_to_orig_dst() {
+    let _trace = trace::test::trace_init();
+
+    let mut config = test_util::default_config();
+    config.tls_sni_ports = Arc::new(
+        Some((tls::ServerId("foo.example.com".parse().unwrap()), 5001))
+            .into_iter()
+            .collect(),
+    );
+    let (rt, _shutdown) = test_util::runtime();
+
+    let target = Tls {
+        client_addr: client_addr(),
+        orig_dst_addr: orig_dst_addr(),
+        status: tls::ConditionalServerTls::Some(tls::ServerTls::Passthru {
+            sni: tls::ServerId("baz.example.com".parse().unwrap()),
+        }),
+        policy: allow(Protocol::Detect {
+            timeout: std::time::Duration::from_secs(10),
+            http: Arc::new([linkerd_proxy_server_policy::http::default(authzs())]),
+            tcp_authorizations: authzs(),
+        }),
+    };
+
+    let (ior, mut iow) = io::duplex(100);
+    iow.write_all(NOT_HTTP).await.unwrap();
+    Inbound::new(config, rt)
+        .with_stack(new_panic("http stack must not be used"))
+        .push_detect_http(svc::ArcNewService::new(|t: Forward| {
+            let Remote(ServerAddr(addr)) = t.param();
+            assert_eq!(addr, orig_dst_addr().0);
+            svc::BoxService::new(svc::mk(|_: io::BoxedIo| future::ok::<(), Error>(())))
+        }))
+        .into_inner()
+        .new_service(target)
+        .oneshot(ior)
+        .await
+        .expect("should succeed");
+}
+
 fn client_id() -> tls::ClientId {
     "testsa.testns.serviceaccount.identity.linkerd.cluster.local"
         .parse()
@@ -1,8 +1,22 @@
+mod allow_methods;
+mod body_timeout;
+mod concurrency_limit;
+mod max_lifetime;
 mod router;
 mod server;
+mod set_dst_port_header;
 mod set_identity_header;
 #[cfg(test)]
 mod tests;
+mod tunnel;
+mod validate_request;
+
+pub use self::{
+    allow_methods::RouteAllowedMethods,
+    concurrency_limit::{ConcurrencyLimitExceeded, ConcurrencyLimitMode},
+    tunnel::HttpConnectMode,
+    validate_request::{RequestValidation, RouteRequestValidations},
+};
 
 fn trace_labels() -> std::collections::HashMap<String, String> {
     let mut l = std::collections::HashMap::new();
@@ -0,0 +1,110 @@
+//! Restricts the HTTP methods accepted by each inbound route.
+//!
+//! Routes are identified by name. Requests on a restricted route whose method
+//! is not allowed fail with a [`MethodNotAllowed`] error, which is served as a
+//! 405 response that lists the route's allowed methods.
+
+use crate::policy::HttpRoutePermit;
+use futures::{future, TryFutureExt};
+use linkerd_app_core::{proxy::http, svc, Error};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// The methods allowed on each restricted route, by route name.
+pub type RouteAllowedMethods = Arc<HashMap<String, Arc<[http::Method]>>>;
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewAllowMethods<N> {
+    inner: N,
+    routes: RouteAllowedMethods,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct AllowMethods<S> {
+    inner: S,
+    allowed: Option<Arc<[http::Method]>>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("method {method} not allowed on route")]
+pub struct MethodNotAllowed {
+    method: http::Method,
+    allowed: Arc<[http::Method]>,
+}
+
+// === impl NewAllowMethods ===
+
+impl<N> NewAllowMethods<N> {
+    pub(crate) fn layer(
+        routes: RouteAllowedMethods,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            inner,
+            routes: routes.clone(),
+        })
+    }
+}
+
+impl<T, N> svc::NewService<(HttpRoutePermit, T)> for NewAllowMethods<N>
+where
+    N: svc::NewService<(HttpRoutePermit, T)>,
+{
+    type Service = AllowMethods<N::Service>;
+
+    fn new_service(&self, (permit, target): (HttpRoutePermit, T)) -> Self::Service {
+        let allowed = self.routes.get(permit.labels.route.route.name()).cloned();
+        AllowMethods {
+            allowed,
+            inner: self.inner.new_service((permit, target)),
+        }
+    }
+}
+
+// === impl AllowMethods ===
+
+impl<B, S> svc::Service<http::Request<B>> for AllowMethods<S>
+where
+    S: svc::Service<http::Request<B>>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<
+        future::ErrInto<S::Future, Error>,
+        future::Ready<Result<S::Response, Error>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if let Some(allowed) = &self.allowed {
+            if !allowed.contains(req.method()) {
+                tracing::debug!(method = %req.method(), ?allowed, "Method not allowed");
+                return future::Either::Right(future::err(
+                    MethodNotAllowed {
+                        method: req.method().clone(),
+                        allowed: allowed.clone(),
+                    }
+                    .into(),
+                ));
+            }
+        }
+
+        future::Either::Left(self.inner.call(req).err_into::<Error>())
+    }
+}
+
+// === impl MethodNotAllowed ===
+
+impl MethodNotAllowed {
+    /// The methods that are allowed on the route.
+    pub fn allowed(&self) -> &[http::Method] {
+        &self.allowed
+    }
+}
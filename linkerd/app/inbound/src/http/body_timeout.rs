@@ -0,0 +1,133 @@
+//! Fails inbound requests whose bodies are not received within a timeout.
+//!
+//! A client may hold a request, and the resources serving it, open by sending
+//! its body slowly. When a timeout is configured, each request's body must
+//! complete within the timeout of the request being dispatched. A body that
+//! does not fails with a [`RequestBodyTimeout`] error, which resets the
+//! request's stream (or closes its HTTP/1 connection).
+
+use futures::{future, TryFutureExt};
+use linkerd_app_core::{
+    proxy::http::{self, HttpBody},
+    svc, Error,
+};
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::{self, Sleep};
+
+#[derive(Clone, Debug)]
+pub(crate) struct TimeoutRequestBody<S> {
+    inner: S,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("request body not received within {timeout:?}")]
+pub struct RequestBodyTimeout {
+    timeout: Duration,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct TimeoutBody<B> {
+    #[pin]
+    inner: B,
+    #[pin]
+    sleep: Sleep,
+    timeout: Duration,
+}
+
+// === impl TimeoutRequestBody ===
+
+impl<S> TimeoutRequestBody<S> {
+    /// When `timeout` is unset, request bodies are not timed out.
+    pub(crate) fn layer(
+        timeout: Option<Duration>,
+    ) -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self { inner, timeout })
+    }
+}
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for TimeoutRequestBody<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::ErrInto<S::Future, Error>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let req = match self.timeout {
+            // Bodies that are already complete cannot time out.
+            Some(timeout) if !req.body().is_end_stream() => req.map(|inner| {
+                http::BoxBody::new(TimeoutBody {
+                    inner,
+                    sleep: time::sleep(timeout),
+                    timeout,
+                })
+            }),
+            _ => req,
+        };
+        self.inner.call(req).err_into::<Error>()
+    }
+}
+
+// === impl TimeoutBody ===
+
+impl<B> HttpBody for TimeoutBody<B>
+where
+    B: HttpBody,
+    B::Error: Into<Error>,
+{
+    type Data = B::Data;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        if let Poll::Ready(data) = this.inner.poll_data(cx) {
+            return Poll::Ready(data.map(|res| res.map_err(Into::into)));
+        }
+        poll_timeout(this.sleep, *this.timeout, cx).map(|error| Some(Err(error)))
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::header::HeaderMap>, Self::Error>> {
+        let this = self.project();
+        if let Poll::Ready(trailers) = this.inner.poll_trailers(cx) {
+            return Poll::Ready(trailers.map_err(Into::into));
+        }
+        poll_timeout(this.sleep, *this.timeout, cx).map(Err)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+fn poll_timeout(sleep: Pin<&mut Sleep>, timeout: Duration, cx: &mut Context<'_>) -> Poll<Error> {
+    futures::ready!(sleep.poll(cx));
+    tracing::debug!(?timeout, "Request body timed out");
+    Poll::Ready(RequestBodyTimeout { timeout }.into())
+}
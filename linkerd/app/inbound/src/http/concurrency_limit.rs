@@ -0,0 +1,122 @@
+//! Limits the number of in-flight inbound HTTP requests.
+//!
+//! Each service built by the layer (i.e., each inbound connection) has its own
+//! limit, shared only by its clones. Once it is reached, new requests are
+//! handled according to the configured [`ConcurrencyLimitMode`]: they either
+//! wait, for a bounded amount of time, for another request to complete, or
+//! they fail immediately. Requests that are not admitted fail with a
+//! [`ConcurrencyLimitExceeded`] error.
+
+use futures::{future, FutureExt, TryFutureExt};
+use linkerd_app_core::{svc, Error};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{sync::Semaphore, time};
+use tracing::debug;
+
+#[cfg(test)]
+mod tests;
+
+/// Determines how inbound HTTP requests are handled once the maximum number of
+/// requests are in flight.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ConcurrencyLimitMode {
+    /// Requests fail immediately.
+    #[default]
+    Shed,
+
+    /// Requests wait for up to the given timeout for another request to
+    /// complete before failing.
+    Queue(Duration),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("too many in-flight requests")]
+pub struct ConcurrencyLimitExceeded(());
+
+#[derive(Clone, Debug)]
+pub(super) struct ConcurrencyLimit<S> {
+    inner: S,
+    permits: Arc<Semaphore>,
+    mode: ConcurrencyLimitMode,
+}
+
+type ResponseFuture<T> = Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'static>>;
+
+// === impl ConcurrencyLimit ===
+
+impl<S> ConcurrencyLimit<S> {
+    /// Returns a layer whose services each have a limit of `max_in_flight`
+    /// requests.
+    pub(super) fn layer(
+        max_in_flight: usize,
+        mode: ConcurrencyLimitMode,
+    ) -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            inner,
+            permits: Arc::new(Semaphore::new(max_in_flight)),
+            mode,
+        })
+    }
+}
+
+impl<Req, S> svc::Service<Req> for ConcurrencyLimit<S>
+where
+    Req: Send + 'static,
+    S: svc::Service<Req> + Clone + Send + 'static,
+    S::Response: Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = ResponseFuture<S::Response>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        // The request is dispatched to the service that was driven to
+        // readiness once it is admitted.
+        let mut inner = {
+            let clone = self.inner.clone();
+            std::mem::replace(&mut self.inner, clone)
+        };
+
+        match self.mode {
+            ConcurrencyLimitMode::Shed => match self.permits.clone().try_acquire_owned() {
+                Ok(permit) => Box::pin(inner.call(req).err_into::<Error>().map(move |res| {
+                    drop(permit);
+                    res
+                })),
+                Err(_) => {
+                    debug!("Concurrency limit reached; shedding request");
+                    Box::pin(future::err(ConcurrencyLimitExceeded(()).into()))
+                }
+            },
+
+            ConcurrencyLimitMode::Queue(timeout) => {
+                let permits = self.permits.clone();
+                Box::pin(async move {
+                    let permit = match time::timeout(timeout, permits.acquire_owned()).await {
+                        Ok(permit) => permit.expect("semaphore must not be closed"),
+                        Err(_) => {
+                            debug!(?timeout, "Concurrency limit reached; request timed out");
+                            return Err(ConcurrencyLimitExceeded(()).into());
+                        }
+                    };
+                    let res = inner.call(req).await.map_err(Into::into);
+                    drop(permit);
+                    res
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use super::*;
+use linkerd_app_core::svc::{Layer, ServiceExt};
+use tokio::sync::oneshot;
+
+/// Builds a service with a limit of one in-flight request.
+fn svc(
+    mode: ConcurrencyLimitMode,
+) -> impl svc::Service<oneshot::Receiver<()>, Response = (), Error = Error, Future = impl Send> + Clone
+{
+    ConcurrencyLimit::layer(1, mode).layer(inner())
+}
+
+/// Builds a service whose responses complete once the request's sender is
+/// dropped.
+fn inner(
+) -> impl svc::Service<oneshot::Receiver<()>, Response = (), Error = Error, Future = impl Send>
+       + Clone
+       + Send {
+    svc::mk(|rx: oneshot::Receiver<()>| async move {
+        let _ = rx.await;
+        Ok::<_, Error>(())
+    })
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn shed_mode_fails_requests_immediately() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let svc = svc(ConcurrencyLimitMode::Shed);
+    let (tx0, rx0) = oneshot::channel();
+    let rsp0 = tokio::spawn(svc.clone().oneshot(rx0));
+    tokio::task::yield_now().await;
+
+    // The limit is saturated, so the request is failed without waiting.
+    let (_tx1, rx1) = oneshot::channel();
+    let error = svc
+        .clone()
+        .oneshot(rx1)
+        .now_or_never()
+        .expect("request must not wait")
+        .expect_err("request must fail");
+    assert!(error.is::<ConcurrencyLimitExceeded>(), "{error}");
+
+    drop(tx0);
+    rsp0.await.unwrap().expect("in-flight request must succeed");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn queue_mode_waits_for_capacity() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let svc = svc(ConcurrencyLimitMode::Queue(Duration::from_secs(1)));
+    let (tx0, rx0) = oneshot::channel();
+    let rsp0 = tokio::spawn(svc.clone().oneshot(rx0));
+    tokio::task::yield_now().await;
+
+    // The request waits for the in-flight request to complete.
+    let (tx1, rx1) = oneshot::channel();
+    let rsp1 = tokio::spawn(svc.clone().oneshot(rx1));
+    time::sleep(Duration::from_millis(500)).await;
+    assert!(!rsp1.is_finished(), "request must wait for capacity");
+    drop(tx0);
+    rsp0.await.unwrap().expect("in-flight request must succeed");
+
+    // Once the request is admitted, additional requests fail if the limit
+    // remains saturated for the timeout.
+    let (_tx2, rx2) = oneshot::channel();
+    let rsp2 = tokio::spawn(svc.clone().oneshot(rx2));
+    time::sleep(Duration::from_millis(999)).await;
+    assert!(!rsp2.is_finished(), "request must wait for the timeout");
+    time::sleep(Duration::from_millis(1)).await;
+    let error = rsp2.await.unwrap().expect_err("request must time out");
+    assert!(error.is::<ConcurrencyLimitExceeded>(), "{error}");
+
+    drop(tx1);
+    rsp1.await.unwrap().expect("queued request must succeed");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn limits_each_service() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let layer = ConcurrencyLimit::layer(1, ConcurrencyLimitMode::Shed);
+    let (tx0, rx0) = oneshot::channel();
+    let rsp0 = tokio::spawn(layer.layer(inner()).oneshot(rx0));
+    tokio::task::yield_now().await;
+
+    // Another service built by the same layer (i.e., for another connection)
+    // is not limited by the first service's in-flight request.
+    let (tx1, rx1) = oneshot::channel();
+    let rsp1 = tokio::spawn(layer.layer(inner()).oneshot(rx1));
+    tokio::task::yield_now().await;
+    assert!(!rsp1.is_finished(), "request must be admitted");
+
+    drop((tx0, tx1));
+    rsp0.await.unwrap().expect("first request must succeed");
+    rsp1.await.unwrap().expect("second request must succeed");
+}
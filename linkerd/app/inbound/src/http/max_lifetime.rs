@@ -0,0 +1,96 @@
+use linkerd_app_core::{proxy::http::ClientHandle, svc};
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Closes each client connection once it has been open for a maximum
+/// lifetime, so that clients reconnect (and reauthenticate).
+///
+/// Connections are closed gracefully: HTTP/2 clients are sent a GOAWAY and
+/// HTTP/1 connections are closed once their in-flight request completes.
+#[derive(Clone, Debug)]
+pub struct NewCloseAfterLifetime<N> {
+    max_lifetime: Option<Duration>,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct CloseAfterLifetime<S> {
+    inner: S,
+    _timer: Option<Arc<Timer>>,
+}
+
+/// Aborts the connection's lifetime timer once the connection's service is
+/// dropped.
+#[derive(Debug)]
+struct Timer(JoinHandle<()>);
+
+// === impl NewCloseAfterLifetime ===
+
+impl<N> NewCloseAfterLifetime<N> {
+    /// When `max_lifetime` is unset, connections are not closed.
+    pub fn layer(max_lifetime: Option<Duration>) -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            max_lifetime,
+            inner,
+        })
+    }
+}
+
+impl<N> svc::NewService<ClientHandle> for NewCloseAfterLifetime<N>
+where
+    N: svc::NewService<ClientHandle>,
+{
+    type Service = CloseAfterLifetime<N::Service>;
+
+    fn new_service(&self, client: ClientHandle) -> Self::Service {
+        let timer = self.max_lifetime.map(|max_lifetime| {
+            let close = client.close.clone();
+            Arc::new(Timer(tokio::spawn(async move {
+                tokio::time::sleep(max_lifetime).await;
+                debug!(
+                    ?max_lifetime,
+                    "Closing connection after its maximum lifetime"
+                );
+                close.close();
+            })))
+        });
+        CloseAfterLifetime {
+            inner: self.inner.new_service(client),
+            _timer: timer,
+        }
+    }
+}
+
+// === impl CloseAfterLifetime ===
+
+impl<S, Req> svc::Service<Req> for CloseAfterLifetime<S>
+where
+    S: svc::Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    #[inline]
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.inner.call(req)
+    }
+}
+
+// === impl Timer ===
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
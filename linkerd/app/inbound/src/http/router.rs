@@ -1,3 +1,6 @@
+use super::{
+    allow_methods::NewAllowMethods, tunnel::NewTunnel, validate_request::NewValidateRequest,
+};
 use crate::{policy, stack_labels, Inbound};
 use linkerd_app_core::{
     classify, errors, http_tracing, metrics, profiles,
@@ -7,7 +10,7 @@ use linkerd_app_core::{
     transport::{self, ClientAddr, Remote, ServerAddr},
     Error, Infallible, NameAddr, Result,
 };
-use std::{fmt, net::SocketAddr};
+use std::{fmt, net::SocketAddr, time::Duration};
 use tracing::{debug, debug_span};
 
 /// Describes an HTTP client target.
@@ -54,7 +57,9 @@ struct ProfileRoute {
 }
 
 #[derive(Copy, Clone, Debug)]
-struct ClientRescue;
+struct ClientRescue {
+    emit_json: bool,
+}
 
 #[derive(Debug, thiserror::Error)]
 struct LogicalError {
@@ -92,13 +97,20 @@ impl<C> Inbound<C> {
         T: Clone + Send + Unpin + 'static,
         P: profiles::GetProfile<Error = Error>,
         C: svc::MakeConnection<Http> + Clone + Send + Sync + Unpin + 'static,
-        C::Connection: Send + Unpin,
+        C::Connection: Send + Unpin + 'static,
         C::Metadata: Send,
         C::Future: Send,
     {
         self.map_stack(|config, rt, connect| {
             let allow_profile = config.allow_discovery.clone();
 
+            // Establishes connections for tunneled CONNECT requests.
+            let tunnel = connect
+                .clone()
+                .push(svc::layer::mk(|inner: C| inner.into_service()))
+                .push_map_target(Http::from)
+                .into_inner();
+
             // Creates HTTP clients for each inbound port & HTTP settings.
             let http = connect
                 .push(svc::layer::mk(|inner: C| inner.into_service()))
@@ -111,17 +123,21 @@ impl<C> Inbound<C> {
                 .push(http::client::layer(
                     config.proxy.connect.h1_settings,
                     config.proxy.connect.h2_settings,
+                    config.proxy.connect.h2_pool,
                 ))
                 .check_service::<Http>()
                 .push_on_service(svc::MapErr::layer_boxed())
                 .into_new_service()
-                .push_new_reconnect(config.proxy.connect.backoff)
+                .push_new_reconnect(
+                    config.proxy.connect.backoff,
+                    config.proxy.connect.backoff_max_elapsed,
+                )
                 .push_map_target(Http::from)
                 // Handle connection-level errors eagerly so that we can report 5XX failures in tap
                 // and metrics. HTTP error metrics are not incremented here so that errors are not
                 // double-counted--i.e., endpoint metrics track these responses and error metrics
                 // track proxy errors that occur higher in the stack.
-                .push(ClientRescue::layer())
+                .push(ClientRescue::layer(config.json_error_bodies))
                 // Registers the stack to be tapped.
                 .push(tap::NewTapHttp::layer(rt.tap.clone()))
                 // Records metrics for each `Logical`.
@@ -197,7 +213,11 @@ impl<C> Inbound<C> {
                 .check_new_service::<(Option<profiles::Receiver>, Logical), http::Request<_>>()
                 .lift_new_with_target()
                 .check_new_new_service::<Logical, Option<profiles::Receiver>, http::Request<_>>()
-                .push_new_cached_discover(profiles.into_service(), config.discovery_idle_timeout)
+                .push_new_cached_discover(
+                    profiles.into_service(),
+                    config.discovery_idle_timeout,
+                    Duration::ZERO,
+                )
                 .check_new_service::<Logical, http::Request<_>>()
                 .push_switch(
                     move |logical: Logical| -> Result<_, Infallible> {
@@ -249,11 +269,21 @@ impl<C> Inbound<C> {
                 .check_new_service::<Logical, http::Request<http::BoxBody>>()
                 .push_on_service(svc::LoadShed::layer())
                 .push(svc::NewMapErr::layer_from_target::<LogicalError, _>())
+                // Handles CONNECT requests as configured. Tunnels bypass the
+                // logical stack and connect directly to the target.
+                .push(NewTunnel::layer(config.http1_connect, tunnel))
                 .lift_new()
                 .check_new_new::<(policy::HttpRoutePermit, T), Logical>()
                 .push(svc::NewOneshotRoute::layer_via(|(permit, t): &(policy::HttpRoutePermit, T)| {
                     LogicalPerRequest::from((permit.clone(), t.clone()))
                 }))
+                // Rejects requests whose methods are not allowed on their
+                // route, if configured.
+                // Rejects requests whose bodies are too large or have a
+                // content type that is not allowed on their route, if
+                // configured.
+                .push(NewValidateRequest::layer(config.http_route_request_validations.clone()))
+                .push(NewAllowMethods::layer(config.http_route_allowed_methods.clone()))
                 .check_new_service::<(policy::HttpRoutePermit, T), http::Request<http::BoxBody>>()
                 .push(policy::NewHttpPolicy::layer(rt.metrics.http_authz.clone()))
                 // Used by tap.
@@ -493,8 +523,9 @@ impl Param<transport::labels::Key> for Http {
 
 impl ClientRescue {
     pub fn layer<N>(
+        emit_json: bool,
     ) -> impl svc::layer::Layer<N, Service = errors::NewRespondService<Self, Self, N>> + Clone {
-        errors::respond::layer(Self)
+        errors::respond::layer(Self { emit_json })
     }
 }
 
@@ -521,6 +552,20 @@ impl ExtractParam<errors::respond::EmitHeaders, Logical> for ClientRescue {
     }
 }
 
+impl<T> ExtractParam<errors::respond::EmitJsonBody, T> for ClientRescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> errors::respond::EmitJsonBody {
+        errors::respond::EmitJsonBody(self.emit_json)
+    }
+}
+
+impl<T> ExtractParam<errors::respond::EmitGrpcWeb, T> for ClientRescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> errors::respond::EmitGrpcWeb {
+        errors::respond::EmitGrpcWeb(false)
+    }
+}
+
 impl errors::HttpRescue<Error> for ClientRescue {
     fn rescue(&self, error: Error) -> Result<errors::SyntheticHttpResponse> {
         if errors::is_caused_by::<std::io::Error>(&*error) {
@@ -1,4 +1,12 @@
-use super::set_identity_header::NewSetIdentityHeader;
+use super::{
+    allow_methods::MethodNotAllowed,
+    body_timeout::{RequestBodyTimeout, TimeoutRequestBody},
+    concurrency_limit::{ConcurrencyLimit, ConcurrencyLimitExceeded},
+    max_lifetime::NewCloseAfterLifetime,
+    set_dst_port_header::NewSetDstPortHeader,
+    set_identity_header::NewSetIdentityHeader,
+    validate_request::{RequestBodyTooLarge, UnsupportedContentType},
+};
 use crate::{policy, Inbound};
 pub use linkerd_app_core::proxy::http::{
     normalize_uri, strip_header, uri, BoxBody, BoxResponse, DetectHttp, Request, Response, Retain,
@@ -18,7 +26,9 @@ use linkerd_http_access_log::NewAccessLog;
 use tracing::debug_span;
 
 #[derive(Copy, Clone, Debug)]
-struct ServerRescue;
+struct ServerRescue {
+    emit_json: bool,
+}
 
 #[derive(Debug, thiserror::Error)]
 #[error("client {client}: server: {dst}: {source}")]
@@ -52,17 +62,48 @@ impl<H> Inbound<H> {
     {
         self.map_stack(|config, rt, http| {
             let ProxyConfig {
-                server: ServerConfig { h2_settings, .. },
+                server:
+                    ServerConfig {
+                        h1_settings,
+                        h2_settings,
+                        ..
+                    },
                 max_in_flight_requests,
                 ..
             } = config.proxy;
 
             http.check_new_service::<T, http::Request<_>>()
+                // Forward HTTP/1.0 requests as HTTP/1.1, if configured. This
+                // must be below the `NewNormalizeUri` layer so that requests
+                // have an authority from which a `Host` header can be set.
+                .push_on_service(http::BridgeHttp10::layer(config.http1_bridge_http10))
+                // Fail HTTP/1 requests for which no authority could be
+                // determined.
+                .push_on_service(http::normalize_uri::RequireAuthority::layer())
                 // Convert origin form HTTP/1 URIs to absolute form for Hyper's
                 // `Client`. This must be below the `orig_proto::Downgrade` layer, since
                 // the request may have been downgraded from a HTTP/2 orig-proto request.
-                .push(http::NewNormalizeUri::layer())
+                .push(http::NewNormalizeUri::layer(config.http1_require_host))
+                // Reject requests whose framing may be interpreted differently
+                // by the application, if configured.
+                .push_on_service(http::RejectAmbiguousFraming::layer(
+                    config.http1_reject_ambiguous_framing,
+                ))
+                // Canonicalize the transfer codings of chunked requests so that
+                // they are framed consistently by each HTTP/1 hop, if configured.
+                .push_on_service(http::NormalizeTransferEncoding::layer(
+                    config.http1_transfer_encoding,
+                ))
+                // Reject requests with header values that exceed the maximum
+                // length, if configured.
+                .push_on_service(http::LimitHeaderValues::layer(
+                    config.http_max_header_value_bytes,
+                ))
+                // Fails requests whose bodies are not received within the
+                // timeout, if configured.
+                .push_on_service(TimeoutRequestBody::layer(config.http_request_body_timeout))
                 .push(NewSetIdentityHeader::layer(()))
+                .push(NewSetDstPortHeader::layer(config.http_dst_port_header))
                 .push_on_service(
                     svc::layers()
                         .push(http::BoxRequest::layer())
@@ -75,15 +116,20 @@ impl<H> Inbound<H> {
                         // streaming bodies. We should change this to an
                         // HTTP-specific imlementation that tracks request and
                         // response bodies.
-                        .push(svc::ConcurrencyLimitLayer::new(max_in_flight_requests))
-                        // Shed load by failing requests when the concurrency
-                        // limit is reached.
-                        .push(svc::LoadShed::layer()),
+                        //
+                        // Requests are queued or failed, according to the
+                        // configured mode, when the limit is reached.
+                        .push(ConcurrencyLimit::layer(
+                            max_in_flight_requests,
+                            config.http_concurrency_limit_mode,
+                        )),
                 )
+                // Accounts for each client identity's requests and body bytes.
+                .push(rt.metrics.identity_accounting.to_layer())
                 .push(svc::NewMapErr::layer_from_target::<ServerError, _>())
                 .push_on_service(svc::MapErr::layer_boxed())
                 .push(rt.metrics.http_errors.to_layer())
-                .push(ServerRescue::layer())
+                .push(ServerRescue::layer(config.json_error_bodies))
                 .push_on_service(
                     svc::layers()
                         .push(http_tracing::server(
@@ -94,12 +140,20 @@ impl<H> Inbound<H> {
                         .push(http::normalize_uri::MarkAbsoluteForm::layer())
                         .push(http::BoxResponse::layer()),
                 )
-                .push(NewAccessLog::layer())
+                .push(NewAccessLog::layer(config.access_log_error_body_bytes))
                 .instrument(|_: &T| debug_span!("http"))
-                .check_new_service::<T, http::Request<_>>()
+                .check_new_service::<T, http::Request<http::BoxBody>>()
                 .unlift_new()
-                .check_new_new_service::<T, http::ClientHandle, http::Request<_>>()
-                .push(http::NewServeHttp::layer(h2_settings, rt.drain.clone()))
+                // Closes connections once they reach their maximum lifetime,
+                // if configured.
+                .push_on_service(NewCloseAfterLifetime::layer(config.max_connection_lifetime))
+                .check_new_new_service::<T, http::ClientHandle, http::Request<http::UpgradeBody>>()
+                .push(http::NewServeHttp::layer(
+                    h1_settings,
+                    h2_settings,
+                    rt.metrics.proxy.http_server.clone(),
+                    rt.drain.clone(),
+                ))
                 .check_new_service::<T, I>()
                 .push_on_service(svc::BoxService::layer())
                 .push(svc::ArcNewService::layer())
@@ -126,8 +180,9 @@ where
 impl ServerRescue {
     /// Synthesizes responses for HTTP requests that encounter proxy errors.
     pub fn layer<N>(
+        emit_json: bool,
     ) -> impl svc::layer::Layer<N, Service = errors::NewRespondService<Self, Self, N>> + Clone {
-        errors::respond::layer(Self)
+        errors::respond::layer(Self { emit_json })
     }
 }
 
@@ -156,6 +211,20 @@ impl<T: Param<tls::ConditionalServerTls>> ExtractParam<errors::respond::EmitHead
     }
 }
 
+impl<T> ExtractParam<errors::respond::EmitJsonBody, T> for ServerRescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> errors::respond::EmitJsonBody {
+        errors::respond::EmitJsonBody(self.emit_json)
+    }
+}
+
+impl<T> ExtractParam<errors::respond::EmitGrpcWeb, T> for ServerRescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> errors::respond::EmitGrpcWeb {
+        errors::respond::EmitGrpcWeb(false)
+    }
+}
+
 impl errors::HttpRescue<Error> for ServerRescue {
     fn rescue(&self, error: Error) -> Result<errors::SyntheticHttpResponse> {
         if errors::is_caused_by::<policy::HttpRouteNotFound>(&*error) {
@@ -165,6 +234,18 @@ impl errors::HttpRescue<Error> for ServerRescue {
         if errors::is_caused_by::<policy::HttpRouteUnauthorized>(&*error) {
             return Ok(errors::SyntheticHttpResponse::permission_denied(error));
         }
+        if let Some(e) = errors::cause_ref::<MethodNotAllowed>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::method_not_allowed(
+                e,
+                e.allowed(),
+            ));
+        }
+        if errors::is_caused_by::<RequestBodyTooLarge>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::payload_too_large(error));
+        }
+        if errors::is_caused_by::<UnsupportedContentType>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::unsupported_media_type(error));
+        }
 
         if errors::is_caused_by::<policy::HttpRouteInvalidRedirect>(&*error) {
             tracing::warn!(%error);
@@ -181,6 +262,16 @@ impl errors::HttpRescue<Error> for ServerRescue {
             ));
         }
 
+        if errors::is_caused_by::<http::normalize_uri::NoAuthority>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::bad_request(error));
+        }
+        if errors::is_caused_by::<http::framing::AmbiguousFraming>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::bad_request(error));
+        }
+        if errors::is_caused_by::<http::header_limit::HeaderValueTooLarge>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::request_header_fields_too_large(error));
+        }
+
         if errors::is_caused_by::<crate::GatewayDomainInvalid>(&*error) {
             return Ok(errors::SyntheticHttpResponse::not_found(error));
         }
@@ -196,7 +287,14 @@ impl errors::HttpRescue<Error> for ServerRescue {
         if errors::is_caused_by::<errors::LoadShedError>(&*error) {
             return Ok(errors::SyntheticHttpResponse::unavailable(error));
         }
+        if errors::is_caused_by::<ConcurrencyLimitExceeded>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::unavailable(error));
+        }
 
+        // Slow clients' streams are reset rather than sent a response.
+        if errors::is_caused_by::<RequestBodyTimeout>(&*error) {
+            return Err(error);
+        }
         if errors::is_caused_by::<errors::H2Error>(&*error) {
             return Err(error);
         }
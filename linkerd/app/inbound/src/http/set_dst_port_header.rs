@@ -0,0 +1,80 @@
+use linkerd_app_core::{proxy::http, svc, transport::OrigDstAddr};
+use std::task::{Context, Poll};
+use tracing::{debug, trace};
+
+const HEADER_NAME: &str = "l5d-dst-port";
+
+/// Sets the `l5d-dst-port` header on inbound requests to the port of the
+/// connection's original destination, so that applications may tell which
+/// port a request was sent to when the proxy forwards it.
+#[derive(Clone, Debug)]
+pub struct NewSetDstPortHeader<N> {
+    enabled: bool,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct SetDstPortHeader<M> {
+    inner: M,
+    value: Option<http::HeaderValue>,
+}
+
+// === impl NewSetDstPortHeader ===
+
+impl<N> NewSetDstPortHeader<N> {
+    /// When `enabled` is false, requests are not modified.
+    pub fn layer(enabled: bool) -> impl svc::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self { enabled, inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewSetDstPortHeader<N>
+where
+    T: svc::Param<OrigDstAddr>,
+    N: svc::NewService<T>,
+{
+    type Service = SetDstPortHeader<N::Service>;
+
+    #[inline]
+    fn new_service(&self, t: T) -> Self::Service {
+        let value = if self.enabled {
+            let OrigDstAddr(addr) = t.param();
+            Some(http::HeaderValue::from(addr.port()))
+        } else {
+            None
+        };
+        SetDstPortHeader {
+            value,
+            inner: self.inner.new_service(t),
+        }
+    }
+}
+
+// === impl Service ===
+
+impl<S, B> tower::Service<http::Request<B>> for SetDstPortHeader<S>
+where
+    S: tower::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        // Any header set by the client is overwritten so that it cannot be
+        // spoofed.
+        if let Some(port) = self.value.clone() {
+            trace!(header = %HEADER_NAME, ?port, "Setting destination port header");
+            if let Some(value) = req.headers_mut().insert(HEADER_NAME, port) {
+                debug!(header = %HEADER_NAME, ?value, "Replaced destination port header");
+            }
+        }
+
+        self.inner.call(req)
+    }
+}
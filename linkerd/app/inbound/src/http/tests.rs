@@ -18,7 +18,8 @@ use linkerd_app_core::{
 };
 use linkerd_app_test::connect::ConnectFuture;
 use linkerd_tracing::test::trace_init;
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::time;
 use tracing::Instrument;
 
 fn build_server<I>(
@@ -76,6 +77,51 @@ async fn unmeshed_http1_hello_world() {
     bg.await.expect("background task failed");
 }
 
+/// Tests that, when configured, connections are closed once they have been
+/// open for their maximum lifetime, even though the client has not closed them.
+#[tokio::test(flavor = "current_thread")]
+async fn closes_connection_after_max_lifetime() {
+    const MAX_LIFETIME: Duration = Duration::from_secs(60);
+
+    let mut server = hyper::server::conn::Http::new();
+    server.http1_only(true);
+    let mut client = ClientBuilder::new();
+    let _trace = trace_init();
+    time::pause();
+
+    let connect = support::connect().endpoint_fn_boxed(Target::addr(), hello_server(server));
+    let profiles = profile::resolver();
+    let profile_tx =
+        profiles.profile_tx(NameAddr::from_str_and_port("foo.svc.cluster.local", 5550).unwrap());
+    profile_tx.send(profile::Profile::default()).unwrap();
+
+    let mut cfg = default_config();
+    cfg.max_connection_lifetime = Some(MAX_LIFETIME);
+    let (rt, _shutdown) = runtime();
+    let server = build_server(cfg, rt, profiles, connect).new_service(Target::UNMESHED_HTTP1);
+    let start = time::Instant::now();
+    let (mut client, bg) = http_util::connect_and_accept(&mut client, server).await;
+
+    let req = Request::builder()
+        .method(http::Method::GET)
+        .uri("http://foo.svc.cluster.local:5550")
+        .body(Body::default())
+        .unwrap();
+    let rsp = http_util::http_request(&mut client, req).await.unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    let body = http_util::body_to_string(rsp.into_body()).await.unwrap();
+    assert_eq!(body, "Hello world!");
+
+    // The client is not dropped, so the connection only completes once the
+    // proxy closes it.
+    bg.await.expect("background task failed");
+    assert!(
+        time::Instant::now().saturating_duration_since(start) >= MAX_LIFETIME,
+        "connection must not be closed before its maximum lifetime"
+    );
+    drop(client);
+}
+
 #[tokio::test(flavor = "current_thread")]
 async fn downgrade_origin_form() {
     // Reproduces https://github.com/linkerd/linkerd2/issues/5298
@@ -468,6 +514,327 @@ async fn grpc_unmeshed_response_error_header() {
     let _ = bg.await;
 }
 
+#[tokio::test(flavor = "current_thread")]
+async fn http1_connect_tunnel() {
+    use io::{AsyncReadExt, AsyncWriteExt};
+    let _trace = trace_init();
+
+    // Build a mock "connector" that returns the IO of a server that echoes
+    // the bytes it receives.
+    let connect = support::connect().endpoint_fn_boxed(Target::addr(), |_| {
+        let (client_io, server_io) = support::io::duplex(4096);
+        tokio::spawn(async move {
+            let (mut rx, mut tx) = tokio::io::split(server_io);
+            let _ = tokio::io::copy(&mut rx, &mut tx).await;
+        });
+        Ok(io::BoxedIo::new(client_io))
+    });
+
+    let profiles = profile::resolver();
+    let profile_tx =
+        profiles.profile_tx(NameAddr::from_str_and_port("foo.svc.cluster.local", 5550).unwrap());
+    profile_tx.send(profile::Profile::default()).unwrap();
+
+    let mut cfg = default_config();
+    cfg.http1_connect = crate::HttpConnectMode::Tunnel;
+    let (rt, _shutdown) = runtime();
+    let server = build_server(cfg, rt, profiles, connect).new_service(Target::UNMESHED_HTTP1);
+    let (mut client_io, _proxy) = http_util::run_proxy(server).await;
+
+    client_io
+        .write_all(
+            b"CONNECT foo.svc.cluster.local:5550 HTTP/1.1\r\n\
+              host: foo.svc.cluster.local:5550\r\n\r\n",
+        )
+        .await
+        .unwrap();
+
+    // Read the response head.
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        let mut byte = [0u8; 1];
+        client_io.read_exact(&mut byte).await.unwrap();
+        head.push(byte[0]);
+    }
+    let head = String::from_utf8(head).unwrap();
+    assert!(head.starts_with("HTTP/1.1 200"), "{}", head);
+
+    // Bytes written after the response are tunneled to the server.
+    client_io.write_all(b"hello").await.unwrap();
+    let mut echoed = [0u8; 5];
+    client_io.read_exact(&mut echoed).await.unwrap();
+    assert_eq!(&echoed, b"hello");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn dst_port_header() {
+    let mut client = ClientBuilder::new();
+    let _trace = trace_init();
+
+    // Build a mock "connector" that returns the IO of a server that responds
+    // with the request's `l5d-dst-port` header.
+    let connect = support::connect().endpoint_fn_boxed(Target::addr(), |_| {
+        let (client_io, server_io) = support::io::duplex(4096);
+        let svc = hyper::service::service_fn(|request: Request<Body>| async move {
+            let port = request
+                .headers()
+                .get("l5d-dst-port")
+                .map(|v| v.to_str().unwrap().to_string())
+                .unwrap_or_default();
+            Ok::<_, io::Error>(Response::new(Body::from(port)))
+        });
+        let mut server = hyper::server::conn::Http::new();
+        server.http1_only(true);
+        tokio::spawn(server.serve_connection(server_io, svc).in_current_span());
+        Ok(io::BoxedIo::new(client_io))
+    });
+
+    let profiles = profile::resolver();
+    let profile_tx =
+        profiles.profile_tx(NameAddr::from_str_and_port("foo.svc.cluster.local", 5550).unwrap());
+    profile_tx.send(profile::Profile::default()).unwrap();
+
+    let mut cfg = default_config();
+    cfg.http_dst_port_header = true;
+    let (rt, _shutdown) = runtime();
+    let server = build_server(cfg, rt, profiles, connect).new_service(Target::UNMESHED_HTTP1);
+    let (mut client, bg) = http_util::connect_and_accept(&mut client, server).await;
+
+    // A header set by the client is replaced with the original destination
+    // port.
+    let req = Request::builder()
+        .method(http::Method::GET)
+        .uri("http://foo.svc.cluster.local:5550")
+        .header("l5d-dst-port", "8080")
+        .body(Body::default())
+        .unwrap();
+    let rsp = http_util::http_request(&mut client, req).await.unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    let body = http_util::body_to_string(rsp.into_body()).await.unwrap();
+    assert_eq!(body, "80");
+
+    drop(client);
+    bg.await.expect("background task failed");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn route_allowed_methods() {
+    let mut server = hyper::server::conn::Http::new();
+    server.http1_only(true);
+    let mut client = ClientBuilder::new();
+    let _trace = trace_init();
+
+    let connect = support::connect().endpoint_fn_boxed(Target::addr(), hello_server(server));
+
+    let profiles = profile::resolver();
+    let profile_tx =
+        profiles.profile_tx(NameAddr::from_str_and_port("foo.svc.cluster.local", 5550).unwrap());
+    profile_tx.send(profile::Profile::default()).unwrap();
+
+    // Only GET and POST requests are allowed on the target's default route.
+    let mut cfg = default_config();
+    cfg.http_route_allowed_methods = Arc::new(
+        Some((
+            "default".to_string(),
+            Arc::from(vec![http::Method::GET, http::Method::POST]),
+        ))
+        .into_iter()
+        .collect(),
+    );
+    let (rt, _shutdown) = runtime();
+    let server = build_server(cfg, rt, profiles, connect).new_service(Target::UNMESHED_HTTP1);
+    let (mut client, bg) = http_util::connect_and_accept(&mut client, server).await;
+
+    let req = Request::builder()
+        .method(http::Method::PUT)
+        .uri("http://foo.svc.cluster.local:5550")
+        .body(Body::default())
+        .unwrap();
+    let rsp = http_util::http_request(&mut client, req).await.unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(
+        rsp.headers()
+            .get(http::header::ALLOW)
+            .map(|v| v.to_str().unwrap()),
+        Some("GET, POST"),
+    );
+
+    let req = Request::builder()
+        .method(http::Method::POST)
+        .uri("http://foo.svc.cluster.local:5550")
+        .body(Body::default())
+        .unwrap();
+    let rsp = http_util::http_request(&mut client, req).await.unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+
+    drop(client);
+    bg.await.expect("background task failed");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn route_request_validation() {
+    let mut server = hyper::server::conn::Http::new();
+    server.http1_only(true);
+    let mut client = ClientBuilder::new();
+    let _trace = trace_init();
+
+    let connect = support::connect().endpoint_fn_boxed(Target::addr(), hello_server(server));
+
+    let profiles = profile::resolver();
+    let profile_tx =
+        profiles.profile_tx(NameAddr::from_str_and_port("foo.svc.cluster.local", 5550).unwrap());
+    profile_tx.send(profile::Profile::default()).unwrap();
+
+    // The target's default route only accepts JSON bodies of up to 8 bytes.
+    let mut cfg = default_config();
+    cfg.http_route_request_validations = Arc::new(
+        Some((
+            "default".to_string(),
+            crate::RequestValidation {
+                max_body_bytes: Some(8),
+                content_types: Some(Arc::from(["application/json".to_string()])),
+            },
+        ))
+        .into_iter()
+        .collect(),
+    );
+    let (rt, _shutdown) = runtime();
+    let server = build_server(cfg, rt, profiles, connect).new_service(Target::UNMESHED_HTTP1);
+    let (mut client, bg) = http_util::connect_and_accept(&mut client, server).await;
+
+    let post = |content_type: &'static str, body: &'static str| {
+        Request::builder()
+            .method(http::Method::POST)
+            .uri("http://foo.svc.cluster.local:5550")
+            .header(http::header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let rsp = http_util::http_request(&mut client, post("application/json", "[0, 1]"))
+        .await
+        .unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+
+    let rsp = http_util::http_request(&mut client, post("text/plain", "[0, 1]"))
+        .await
+        .unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let rsp = http_util::http_request(
+        &mut client,
+        post("application/json; charset=utf-8", "[0, 1, 2, 3]"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::PAYLOAD_TOO_LARGE);
+
+    drop(client);
+    bg.await.expect("background task failed");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn max_header_value_length() {
+    let mut server = hyper::server::conn::Http::new();
+    server.http1_only(true);
+    let mut client = ClientBuilder::new();
+    let _trace = trace_init();
+
+    let connect = support::connect().endpoint_fn_boxed(Target::addr(), hello_server(server));
+
+    let profiles = profile::resolver();
+    let profile_tx =
+        profiles.profile_tx(NameAddr::from_str_and_port("foo.svc.cluster.local", 5550).unwrap());
+    profile_tx.send(profile::Profile::default()).unwrap();
+
+    let mut cfg = default_config();
+    cfg.http_max_header_value_bytes = Some(16);
+    let (rt, _shutdown) = runtime();
+    let server = build_server(cfg, rt, profiles, connect).new_service(Target::UNMESHED_HTTP1);
+    let (mut client, bg) = http_util::connect_and_accept(&mut client, server).await;
+
+    let get = |value: &str| {
+        Request::builder()
+            .method(http::Method::GET)
+            .uri("http://foo.svc.cluster.local:5550")
+            .header("x-value", value)
+            .body(Body::default())
+            .unwrap()
+    };
+
+    let rsp = http_util::http_request(&mut client, get(&"a".repeat(16)))
+        .await
+        .unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+
+    let rsp = http_util::http_request(&mut client, get(&"a".repeat(17)))
+        .await
+        .unwrap();
+    assert_eq!(
+        rsp.status(),
+        http::StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+    );
+
+    drop(client);
+    bg.await.expect("background task failed");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn request_body_timeout() {
+    let mut server = hyper::server::conn::Http::new();
+    server.http2_only(true);
+    let mut client = ClientBuilder::new();
+    client.http2_only(true);
+    let _trace = trace_init();
+
+    let connect = support::connect().endpoint_fn_boxed(Target::addr(), read_body_server(server));
+
+    let profiles = profile::resolver();
+    let profile_tx =
+        profiles.profile_tx(NameAddr::from_str_and_port("foo.svc.cluster.local", 5550).unwrap());
+    profile_tx.send(profile::Profile::default()).unwrap();
+
+    let mut cfg = default_config();
+    cfg.http_request_body_timeout = Some(Duration::from_millis(200));
+    let (rt, _shutdown) = runtime();
+    let server = build_server(cfg, rt, profiles, connect).new_service(Target::UNMESHED_H2);
+    let (mut client, bg) = http_util::connect_and_accept(&mut client, server).await;
+
+    // Drip-feed the request's body, so that it never completes.
+    let (mut body_tx, body) = Body::channel();
+    let drip = tokio::spawn(async move {
+        while body_tx.send_data("a".into()).await.is_ok() {
+            time::sleep(Duration::from_millis(50)).await;
+        }
+    });
+    let req = Request::builder()
+        .method(http::Method::POST)
+        .uri("http://foo.svc.cluster.local:5550")
+        .body(body)
+        .unwrap();
+    let error = time::timeout(
+        Duration::from_secs(5),
+        http_util::http_request(&mut client, req),
+    )
+    .await
+    .expect("request must not hang")
+    .expect_err("stream must be reset");
+    tracing::info!(%error);
+    drip.await.unwrap();
+
+    // Only the slow request's stream is reset.
+    let req = Request::builder()
+        .method(http::Method::POST)
+        .uri("http://foo.svc.cluster.local:5550")
+        .body(Body::from("hello"))
+        .unwrap();
+    let rsp = http_util::http_request(&mut client, req).await.unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+
+    drop(client);
+    let _ = bg.await;
+}
+
 #[tracing::instrument]
 fn hello_server(
     http: hyper::server::conn::Http,
@@ -489,6 +856,24 @@ fn hello_server(
     }
 }
 
+/// Serves each request once its body has been read.
+#[tracing::instrument]
+fn read_body_server(
+    http: hyper::server::conn::Http,
+) -> impl Fn(Remote<ServerAddr>) -> io::Result<io::BoxedIo> {
+    move |endpoint| {
+        let span = tracing::info_span!("read_body_server", ?endpoint);
+        let _e = span.enter();
+        let (client_io, server_io) = support::io::duplex(4096);
+        let svc = hyper::service::service_fn(|request: Request<Body>| async move {
+            hyper::body::to_bytes(request.into_body()).await?;
+            Ok::<_, hyper::Error>(Response::new(Body::from("Hello world!")))
+        });
+        tokio::spawn(http.serve_connection(server_io, svc).in_current_span());
+        Ok(io::BoxedIo::new(client_io))
+    }
+}
+
 #[tracing::instrument]
 fn connect_error() -> impl Fn(Remote<ServerAddr>) -> io::Result<io::BoxedIo> {
     move |_| {
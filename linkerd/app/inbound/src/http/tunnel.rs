@@ -0,0 +1,144 @@
+use futures::{future, TryFutureExt};
+use linkerd_app_core::{
+    io,
+    proxy::http::{self, upgrade::Http11Upgrade},
+    svc::{self, ServiceExt},
+    Error,
+};
+use std::task::{Context, Poll};
+use tracing::debug;
+
+/// Configures how the inbound proxy handles HTTP/1.1 `CONNECT` requests.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum HttpConnectMode {
+    /// `CONNECT` requests are forwarded to the application, which may respond
+    /// to establish a tunnel.
+    #[default]
+    Forward,
+
+    /// `CONNECT` requests are rejected with a `405 Method Not Allowed`
+    /// response.
+    Reject,
+
+    /// The proxy responds to `CONNECT` requests itself and tunnels the
+    /// connection's bytes to the inbound target, as it would for a forwarded
+    /// TCP connection. The request's authority is not used to choose the
+    /// tunnel's destination.
+    Tunnel,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct NewTunnel<C, N> {
+    mode: HttpConnectMode,
+    connect: C,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct Tunnel<T, C, S> {
+    mode: HttpConnectMode,
+    target: T,
+    connect: C,
+    inner: S,
+}
+
+// === impl NewTunnel ===
+
+impl<C: Clone, N> NewTunnel<C, N> {
+    /// Handles `CONNECT` requests according to `mode`, using `connect` to
+    /// establish tunnels.
+    pub(super) fn layer(
+        mode: HttpConnectMode,
+        connect: C,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            mode,
+            connect: connect.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, C, N> svc::NewService<T> for NewTunnel<C, N>
+where
+    T: Clone,
+    C: Clone,
+    N: svc::NewService<T>,
+{
+    type Service = Tunnel<T, C, N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        Tunnel {
+            mode: self.mode,
+            connect: self.connect.clone(),
+            inner: self.inner.new_service(target.clone()),
+            target,
+        }
+    }
+}
+
+// === impl Tunnel ===
+
+impl<T, C, I, M, S> svc::Service<http::Request<http::BoxBody>> for Tunnel<T, C, S>
+where
+    T: Clone + Send + 'static,
+    C: svc::Service<T, Response = (I, M)> + Clone + Send + 'static,
+    C::Error: Into<Error>,
+    C::Future: Send,
+    I: io::AsyncRead + io::AsyncWrite + Send + Unpin + 'static,
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = future::BoxFuture<'static, Result<Self::Response, Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut req: http::Request<http::BoxBody>) -> Self::Future {
+        if req.method() != http::Method::CONNECT {
+            return Box::pin(self.inner.call(req).err_into::<Error>());
+        }
+
+        match self.mode {
+            HttpConnectMode::Forward => Box::pin(self.inner.call(req).err_into::<Error>()),
+
+            HttpConnectMode::Reject => {
+                debug!("Rejecting CONNECT request");
+                let rsp = http::Response::builder()
+                    .status(http::StatusCode::METHOD_NOT_ALLOWED)
+                    .body(http::BoxBody::default())
+                    .expect("response must be valid");
+                Box::pin(future::ok(rsp))
+            }
+
+            HttpConnectMode::Tunnel => {
+                // Only HTTP/1.1 connections may be upgraded; other CONNECT
+                // requests are forwarded.
+                let upgrade = match req.extensions_mut().remove::<Http11Upgrade>() {
+                    Some(upgrade) => upgrade,
+                    None => return Box::pin(self.inner.call(req).err_into::<Error>()),
+                };
+                // Dropping the request releases the server's half of the
+                // upgrade, so that the tunnel is established once the response
+                // has been sent.
+                drop(req);
+
+                let connect = self.connect.clone().oneshot(self.target.clone());
+                Box::pin(async move {
+                    let (io, _) = connect.await.map_err(Into::into)?;
+                    debug!("Tunneling CONNECT request");
+                    upgrade.insert_tunnel(io);
+                    Ok(http::Response::builder()
+                        .status(http::StatusCode::OK)
+                        .body(http::BoxBody::default())
+                        .expect("response must be valid"))
+                })
+            }
+        }
+    }
+}
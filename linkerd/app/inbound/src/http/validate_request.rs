@@ -0,0 +1,230 @@
+//! Validates the bodies of requests on each inbound route.
+//!
+//! Routes are identified by name. A route may limit the size of its request
+//! bodies and the content types they may have. Requests whose `Content-Length`
+//! exceeds the limit fail with a [`RequestBodyTooLarge`] error, which is
+//! served as a 413 response, before they are dispatched; bodies without a
+//! `Content-Length` fail once they exceed the limit. Requests with bodies whose
+//! content type is not allowed fail with an [`UnsupportedContentType`] error,
+//! which is served as a 415 response.
+
+use crate::policy::HttpRoutePermit;
+use bytes::Buf;
+use futures::{future, TryFutureExt};
+use linkerd_app_core::{
+    proxy::http::{self, HttpBody},
+    svc, Error,
+};
+use pin_project::pin_project;
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// Constraints on the bodies of a route's requests.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RequestValidation {
+    /// The maximum size of request bodies, in bytes.
+    pub max_body_bytes: Option<u64>,
+
+    /// The media types (e.g. `application/json`) that request bodies may have.
+    /// When unset, bodies of any content type are allowed.
+    pub content_types: Option<Arc<[String]>>,
+}
+
+/// The request validation of each validated route, by route name.
+pub type RouteRequestValidations = Arc<HashMap<String, RequestValidation>>;
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewValidateRequest<N> {
+    inner: N,
+    routes: RouteRequestValidations,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ValidateRequest<S> {
+    inner: S,
+    validation: Option<RequestValidation>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("request body exceeds the route's limit of {max_bytes} bytes")]
+pub struct RequestBodyTooLarge {
+    max_bytes: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("content type {content_type:?} not allowed on route")]
+pub struct UnsupportedContentType {
+    content_type: Option<String>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+struct LimitedBody {
+    #[pin]
+    inner: http::BoxBody,
+    max_bytes: u64,
+    read: u64,
+}
+
+// === impl NewValidateRequest ===
+
+impl<N> NewValidateRequest<N> {
+    pub(crate) fn layer(
+        routes: RouteRequestValidations,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            inner,
+            routes: routes.clone(),
+        })
+    }
+}
+
+impl<T, N> svc::NewService<(HttpRoutePermit, T)> for NewValidateRequest<N>
+where
+    N: svc::NewService<(HttpRoutePermit, T)>,
+{
+    type Service = ValidateRequest<N::Service>;
+
+    fn new_service(&self, (permit, target): (HttpRoutePermit, T)) -> Self::Service {
+        let validation = self.routes.get(permit.labels.route.route.name()).cloned();
+        ValidateRequest {
+            validation,
+            inner: self.inner.new_service((permit, target)),
+        }
+    }
+}
+
+// === impl ValidateRequest ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for ValidateRequest<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<
+        future::ErrInto<S::Future, Error>,
+        future::Ready<Result<S::Response, Error>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let validation = match &self.validation {
+            Some(validation) => validation,
+            None => return future::Either::Left(self.inner.call(req).err_into::<Error>()),
+        };
+
+        if let Err(error) = validation.check(&req) {
+            tracing::debug!(%error, "Invalid request");
+            return future::Either::Right(future::err(error));
+        }
+
+        let req = match validation.max_body_bytes {
+            Some(max_bytes) => req.map(|inner| {
+                http::BoxBody::new(LimitedBody {
+                    inner,
+                    max_bytes,
+                    read: 0,
+                })
+            }),
+            None => req,
+        };
+        future::Either::Left(self.inner.call(req).err_into::<Error>())
+    }
+}
+
+// === impl RequestValidation ===
+
+impl RequestValidation {
+    /// Checks the request's headers, before its body is read.
+    fn check<B: HttpBody>(&self, req: &http::Request<B>) -> Result<(), Error> {
+        if let Some(max_bytes) = self.max_body_bytes {
+            let content_length = req
+                .headers()
+                .get(http::header::CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok());
+            if content_length.map_or(false, |len| len > max_bytes) {
+                return Err(RequestBodyTooLarge { max_bytes }.into());
+            }
+        }
+
+        if let Some(allowed) = &self.content_types {
+            // Requests without bodies need not declare a content type.
+            if req.body().is_end_stream() {
+                return Ok(());
+            }
+            let content_type = req
+                .headers()
+                .get(http::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok());
+            let media_type = content_type
+                .and_then(|v| v.split(';').next())
+                .map(str::trim);
+            let is_allowed = media_type.map_or(false, |media_type| {
+                allowed.iter().any(|t| t.eq_ignore_ascii_case(media_type))
+            });
+            if !is_allowed {
+                return Err(UnsupportedContentType {
+                    content_type: content_type.map(ToString::to_string),
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// === impl LimitedBody ===
+
+impl HttpBody for LimitedBody {
+    type Data = <http::BoxBody as HttpBody>::Data;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        let data = futures::ready!(this.inner.poll_data(cx));
+        if let Some(Ok(data)) = data.as_ref() {
+            *this.read += data.remaining() as u64;
+            if *this.read > *this.max_bytes {
+                tracing::debug!(max_bytes = *this.max_bytes, "Request body too large");
+                return Poll::Ready(Some(Err(RequestBodyTooLarge {
+                    max_bytes: *this.max_bytes,
+                }
+                .into())));
+            }
+        }
+        Poll::Ready(data)
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::header::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
@@ -7,6 +7,7 @@
 #![forbid(unsafe_code)]
 
 mod accept;
+mod connect_grace;
 mod detect;
 pub mod direct;
 mod http;
@@ -16,14 +17,22 @@ mod server;
 #[cfg(any(test, fuzzing))]
 pub(crate) mod test_util;
 
-pub use self::{metrics::Metrics, policy::DefaultPolicy};
+pub use self::{
+    detect::SniPorts,
+    http::{
+        ConcurrencyLimitMode, HttpConnectMode, RequestValidation, RouteAllowedMethods,
+        RouteRequestValidations,
+    },
+    metrics::{accounting::IdentityAccounting, Metrics},
+    policy::DefaultPolicy,
+};
 use linkerd_app_core::{
     config::{ConnectConfig, ProxyConfig, QueueConfig},
     drain,
     http_tracing::OpenCensusSink,
     identity, io,
-    proxy::{tap, tcp},
-    svc,
+    proxy::{http::framing::TransferEncodingMode, tap, tcp},
+    startup, svc,
     transport::{self, Remote, ServerAddr},
     Error, NameAddr, NameMatch, ProxyRuntime,
 };
@@ -51,6 +60,80 @@ pub struct Config {
 
     /// Configures how HTTP requests are buffered *for each inbound port*.
     pub http_request_queue: QueueConfig,
+
+    /// Determines how HTTP requests are handled once the proxy's
+    /// `max_in_flight_requests` are in flight.
+    pub http_concurrency_limit_mode: ConcurrencyLimitMode,
+
+    /// Whether HTTP error responses synthesized by the proxy include a JSON
+    /// body describing the error.
+    pub json_error_bodies: bool,
+
+    /// Whether origin-form HTTP/1 requests must include a `Host` header. When
+    /// set, requests without one are rejected with a 400 rather than being
+    /// routed to the connection's original destination.
+    pub http1_require_host: bool,
+
+    /// Whether HTTP/1 requests with ambiguous framing (e.g. conflicting
+    /// `Content-Length` and `Transfer-Encoding` headers) are rejected with a
+    /// 400, to defend against request smuggling.
+    pub http1_reject_ambiguous_framing: bool,
+
+    /// Configures how the `Transfer-Encoding` of chunked HTTP/1 requests is
+    /// canonicalized before they are forwarded. When unset, requests are
+    /// forwarded as they are parsed.
+    pub http1_transfer_encoding: Option<TransferEncodingMode>,
+
+    /// The maximum length of each HTTP request header value, in bytes.
+    /// Requests with longer header values are rejected with a 431. When unset,
+    /// header values are not limited.
+    pub http_max_header_value_bytes: Option<usize>,
+
+    /// The time within which each HTTP request's body must be received once
+    /// the request is dispatched. Requests whose bodies are slower are reset.
+    /// When unset, request bodies are not timed out.
+    pub http_request_body_timeout: Option<Duration>,
+
+    /// Whether HTTP/1.0 requests are sent to the application as HTTP/1.1, with
+    /// their responses downgraded to HTTP/1.0.
+    pub http1_bridge_http10: bool,
+
+    /// The maximum number of request body bytes to include in the access log
+    /// of requests that fail with an error response. When unset, request
+    /// bodies are not logged.
+    pub access_log_error_body_bytes: Option<usize>,
+
+    /// Configures how HTTP/1.1 `CONNECT` requests are handled.
+    pub http1_connect: HttpConnectMode,
+
+    /// Whether requests are forwarded to the application with an
+    /// `l5d-dst-port` header indicating the port of the connection's original
+    /// destination.
+    pub http_dst_port_header: bool,
+
+    /// Restricts the HTTP methods accepted by each route, by route name.
+    /// Requests on these routes with other methods fail with a 405.
+    pub http_route_allowed_methods: RouteAllowedMethods,
+
+    /// Validates the size and content type of the request bodies of each
+    /// route, by route name. Requests with over-size bodies fail with a 413,
+    /// and requests with bodies of other content types fail with a 415.
+    pub http_route_request_validations: RouteRequestValidations,
+
+    /// How long connections to the application are retried while they are
+    /// refused, e.g. because the application has not yet started listening.
+    /// When unset, refused connections fail immediately.
+    pub app_connect_grace: Option<Duration>,
+
+    /// The maximum duration for which an HTTP connection may be open before it
+    /// is closed gracefully, so that the client reconnects. When unset,
+    /// connections are not closed after a maximum lifetime.
+    pub max_connection_lifetime: Option<Duration>,
+
+    /// Maps the SNI values of TLS connections that are passed through to the
+    /// application to the local ports to which they are forwarded. Other
+    /// connections are forwarded to their original destination.
+    pub tls_sni_ports: SniPorts,
 }
 
 #[derive(Clone)]
@@ -67,6 +150,7 @@ struct Runtime {
     tap: tap::Registry,
     span_sink: OpenCensusSink,
     drain: drain::Watch,
+    startup: startup::StartupGate,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -153,6 +237,7 @@ impl Inbound<()> {
             tap: runtime.tap,
             span_sink: runtime.span_sink,
             drain: runtime.drain,
+            startup: runtime.startup,
         };
         Self {
             config,
@@ -192,6 +277,7 @@ impl Inbound<()> {
             let ConnectConfig {
                 ref keepalive,
                 ref timeout,
+                ref backoff,
                 ..
             } = config.proxy.connect;
 
@@ -202,6 +288,12 @@ impl Inbound<()> {
             svc::stack(transport::ConnectTcp::new(*keepalive))
                 // Limits the time we wait for a connection to be established.
                 .push_connect_timeout(*timeout)
+                // Gives the application a chance to start listening before
+                // refused connections fail.
+                .push(connect_grace::ConnectGrace::layer(
+                    config.app_connect_grace,
+                    *backoff,
+                ))
                 // Prevent connections that would target the inbound proxy port from looping.
                 .push_filter(move |t: T| {
                     let addr = t.param();
@@ -245,7 +337,7 @@ impl<S> Inbound<S> {
         S::Metadata: Send + Unpin,
         S::Future: Send,
     {
-        self.map_stack(|_, rt, connect| {
+        self.map_stack(|config, rt, connect| {
             connect
                 .push(transport::metrics::Client::layer(
                     rt.metrics.proxy.transport.clone(),
@@ -254,7 +346,10 @@ impl<S> Inbound<S> {
                 .push_new_thunk()
                 .push_on_service(
                     svc::layers()
-                        .push(tcp::Forward::layer())
+                        .push(tcp::Forward::layer(
+                            config.proxy.forward_buffer_capacity,
+                            config.proxy.forward_idle_timeout,
+                        ))
                         .push(drain::Retain::layer(rt.drain.clone())),
                 )
                 .instrument(|_: &_| debug_span!("tcp"))
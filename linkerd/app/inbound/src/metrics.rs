@@ -8,6 +8,7 @@
 //! to be updated frequently or in a performance-critical area. We should probably look to use
 //! `DashMap` as we migrate other metrics registries.
 
+pub(crate) mod accounting;
 pub(crate) mod authz;
 pub(crate) mod error;
 
@@ -22,6 +23,10 @@ pub struct Metrics {
     pub(crate) tcp_authz: authz::TcpAuthzMetrics,
     pub tcp_errors: error::TcpErrorMetrics,
 
+    /// Accounts for the traffic of each client identity. These metrics are
+    /// served separately from the proxy's other metrics.
+    pub identity_accounting: accounting::IdentityAccounting,
+
     /// Holds metrics that are common to both inbound and outbound proxies. These metrics are
     /// reported separately
     pub proxy: Proxy,
@@ -34,6 +39,7 @@ impl Metrics {
             http_errors: error::HttpErrorMetrics::default(),
             tcp_authz: authz::TcpAuthzMetrics::default(),
             tcp_errors: error::TcpErrorMetrics::default(),
+            identity_accounting: accounting::IdentityAccounting::default(),
             proxy,
         }
     }
@@ -47,7 +53,8 @@ impl FmtMetrics for Metrics {
         self.tcp_authz.fmt_metrics(f)?;
         self.tcp_errors.fmt_metrics(f)?;
 
-        // XXX: Proxy metrics are reported elsewhere.
+        // XXX: Proxy metrics are reported elsewhere, and identity accounting
+        // metrics are served by a dedicated admin endpoint.
 
         Ok(())
     }
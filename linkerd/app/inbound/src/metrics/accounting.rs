@@ -0,0 +1,255 @@
+//! Accounts for the inbound HTTP traffic of each client identity, so that usage
+//! may be exported for billing.
+//!
+//! Only requests from clients with a mesh identity are recorded, so the number
+//! of series is bounded by the set of identities that communicate with this
+//! proxy. These metrics are not included in the proxy's `/metrics` output and
+//! are instead served by the admin server's `/accounting` endpoint.
+
+use bytes::Buf;
+use futures::ready;
+use linkerd_app_core::{
+    metrics::{metrics, Counter, FmtLabels, FmtMetrics},
+    proxy::http::{self, HttpBody},
+    svc, tls, Error,
+};
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+#[cfg(test)]
+mod tests;
+
+metrics! {
+    inbound_identity_requests_total: Counter {
+        "The total number of inbound HTTP requests from each client identity"
+    },
+    inbound_identity_request_bytes_total: Counter {
+        "The total number of inbound HTTP request body bytes received from each client identity"
+    },
+    inbound_identity_response_bytes_total: Counter {
+        "The total number of inbound HTTP response body bytes sent to each client identity"
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct IdentityAccounting(Arc<Mutex<HashMap<tls::ClientId, Arc<Usage>>>>);
+
+#[derive(Clone, Debug)]
+pub struct NewAccount<N> {
+    accounting: IdentityAccounting,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct Account<S> {
+    usage: Option<Arc<Usage>>,
+    inner: S,
+}
+
+#[pin_project]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    usage: Option<Arc<Usage>>,
+}
+
+#[derive(Debug, Default)]
+struct Usage {
+    requests: Counter,
+    request_bytes: Counter,
+    response_bytes: Counter,
+}
+
+/// Records the number of bytes read from a body.
+#[pin_project]
+struct AccountBody<B> {
+    #[pin]
+    inner: B,
+    usage: Option<Arc<Usage>>,
+    counter: fn(&Usage) -> &Counter,
+}
+
+struct ClientIdLabel<'i>(&'i tls::ClientId);
+
+// === impl IdentityAccounting ===
+
+impl IdentityAccounting {
+    pub fn to_layer<N>(&self) -> impl svc::layer::Layer<N, Service = NewAccount<N>> + Clone {
+        let accounting = self.clone();
+        svc::layer::mk(move |inner| NewAccount {
+            accounting: accounting.clone(),
+            inner,
+        })
+    }
+
+    fn usage(&self, id: &tls::ClientId) -> Arc<Usage> {
+        self.0.lock().entry(id.clone()).or_default().clone()
+    }
+}
+
+impl FmtMetrics for IdentityAccounting {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let usage = self.0.lock();
+        if usage.is_empty() {
+            return Ok(());
+        }
+
+        inbound_identity_requests_total.fmt_help(f)?;
+        inbound_identity_requests_total.fmt_scopes(
+            f,
+            usage.iter().map(|(id, u)| (ClientIdLabel(id), u)),
+            |u| &u.requests,
+        )?;
+
+        inbound_identity_request_bytes_total.fmt_help(f)?;
+        inbound_identity_request_bytes_total.fmt_scopes(
+            f,
+            usage.iter().map(|(id, u)| (ClientIdLabel(id), u)),
+            |u| &u.request_bytes,
+        )?;
+
+        inbound_identity_response_bytes_total.fmt_help(f)?;
+        inbound_identity_response_bytes_total.fmt_scopes(
+            f,
+            usage.iter().map(|(id, u)| (ClientIdLabel(id), u)),
+            |u| &u.response_bytes,
+        )?;
+
+        Ok(())
+    }
+}
+
+// === impl NewAccount ===
+
+impl<T, N> svc::NewService<T> for NewAccount<N>
+where
+    T: svc::Param<tls::ConditionalServerTls>,
+    N: svc::NewService<T>,
+{
+    type Service = Account<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let usage = match target.param() {
+            tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+                client_id: Some(id),
+                ..
+            }) => Some(self.accounting.usage(&id)),
+            _ => None,
+        };
+        Account {
+            usage,
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl Account ===
+
+impl<S, B> svc::Service<http::Request<B>> for Account<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+    B: HttpBody + Send + 'static,
+    B::Data: Send + 'static,
+    B::Error: Into<Error>,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if let Some(usage) = self.usage.as_ref() {
+            usage.requests.incr();
+        }
+        let req = req.map(|inner| {
+            http::BoxBody::new(AccountBody {
+                inner,
+                usage: self.usage.clone(),
+                counter: |u| &u.request_bytes,
+            })
+        });
+        ResponseFuture {
+            inner: self.inner.call(req),
+            usage: self.usage.clone(),
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<http::BoxBody>, E>>,
+{
+    type Output = Result<http::Response<http::BoxBody>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let rsp = ready!(this.inner.poll(cx))?;
+        let usage = this.usage.take();
+        Poll::Ready(Ok(rsp.map(|inner| {
+            http::BoxBody::new(AccountBody {
+                inner,
+                usage,
+                counter: |u| &u.response_bytes,
+            })
+        })))
+    }
+}
+
+// === impl AccountBody ===
+
+impl<B: HttpBody> HttpBody for AccountBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        let data = ready!(this.inner.poll_data(cx));
+        if let (Some(usage), Some(Ok(data))) = (this.usage.as_ref(), data.as_ref()) {
+            (this.counter)(usage).add(data.remaining() as u64);
+        }
+        Poll::Ready(data)
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::header::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// === impl ClientIdLabel ===
+
+impl FmtLabels for ClientIdLabel<'_> {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "client_id=\"{}\"", self.0)
+    }
+}
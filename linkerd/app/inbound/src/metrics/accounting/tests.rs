@@ -0,0 +1,75 @@
+use super::*;
+use linkerd_app_core::svc::{Layer, NewService, ServiceExt};
+
+const FOO: &str = "foo.ns1.serviceaccount.identity.linkerd.cluster.local";
+const BAR: &str = "bar.ns2.serviceaccount.identity.linkerd.cluster.local";
+
+#[derive(Clone, Debug)]
+struct Target(Option<tls::ClientId>);
+
+impl svc::Param<tls::ConditionalServerTls> for Target {
+    fn param(&self) -> tls::ConditionalServerTls {
+        tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+            client_id: self.0.clone(),
+            negotiated_protocol: None,
+        })
+    }
+}
+
+/// Sends a request with the given body to a server that responds with the
+/// request's body repeated twice.
+async fn send(accounting: &IdentityAccounting, client_id: Option<&str>, body: &'static str) {
+    let target = Target(client_id.map(|id| id.parse().unwrap()));
+    let svc = accounting
+        .to_layer()
+        .layer(|_: Target| {
+            svc::mk(|req: http::Request<http::BoxBody>| async move {
+                let body = hyper::body::to_bytes(req.into_body()).await?;
+                let body = bytes::Bytes::from([body.clone(), body].concat());
+                let rsp = http::Response::new(http::BoxBody::new(http_body::Full::new(body)));
+                Ok::<_, Error>(rsp)
+            })
+        })
+        .new_service(target);
+
+    let req = http::Request::builder()
+        .uri("http://foo.example.com")
+        .body(http::BoxBody::new(http_body::Full::new(body.as_bytes())))
+        .unwrap();
+    let rsp = svc.oneshot(req).await.expect("request must succeed");
+    hyper::body::to_bytes(rsp.into_body())
+        .await
+        .expect("response body must be read");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn accounts_per_identity() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let accounting = IdentityAccounting::default();
+    send(&accounting, Some(FOO), "hello").await;
+    send(&accounting, Some(FOO), "hi").await;
+    send(&accounting, Some(BAR), "hey").await;
+    send(&accounting, None, "unidentified").await;
+
+    let usage = accounting.0.lock();
+    assert_eq!(usage.len(), 2, "only identified clients must be recorded");
+
+    let foo = &usage[&FOO.parse::<tls::ClientId>().unwrap()];
+    assert_eq!(foo.requests.value(), 2.0);
+    assert_eq!(foo.request_bytes.value(), 7.0);
+    assert_eq!(foo.response_bytes.value(), 14.0);
+
+    let bar = &usage[&BAR.parse::<tls::ClientId>().unwrap()];
+    assert_eq!(bar.requests.value(), 1.0);
+    assert_eq!(bar.request_bytes.value(), 3.0);
+    assert_eq!(bar.response_bytes.value(), 6.0);
+    drop(usage);
+
+    let metrics = accounting.as_display().to_string();
+    let series = format!(
+        "inbound_identity_request_bytes_total{{client_id=\"{}\"}}",
+        BAR
+    );
+    assert!(metrics.contains(&series), "{}", metrics);
+}
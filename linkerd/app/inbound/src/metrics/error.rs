@@ -3,6 +3,7 @@ mod tcp;
 
 pub(crate) use self::{http::HttpErrorMetrics, tcp::TcpErrorMetrics};
 use crate::{
+    http::ConcurrencyLimitExceeded,
     policy::{HttpRouteNotFound, HttpRouteUnauthorized, ServerUnauthorized},
     GatewayDomainInvalid, GatewayIdentityRequired, GatewayLoop,
 };
@@ -50,7 +51,7 @@ impl ErrorKind {
             Some(ErrorKind::GatewayIdentityRequired)
         } else if err.is::<GatewayLoop>() {
             Some(ErrorKind::GatewayLoop)
-        } else if err.is::<LoadShedError>() {
+        } else if err.is::<LoadShedError>() || err.is::<ConcurrencyLimitExceeded>() {
             Some(ErrorKind::LoadShed)
         } else if let Some(e) = err.source() {
             Self::mk(e)
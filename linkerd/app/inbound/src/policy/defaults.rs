@@ -45,6 +45,18 @@ pub fn all_mtls_unauthenticated(timeout: Duration) -> ServerPolicy {
     )
 }
 
+/// Updates a policy so that connections are handled as HTTP/2 without protocol
+/// detection, e.g. on ports that are known to serve gRPC. The policy's HTTP
+/// routes are preserved. Policies that do not use protocol detection are
+/// unchanged.
+pub fn http2(mut policy: ServerPolicy) -> ServerPolicy {
+    policy.protocol = match policy.protocol {
+        Protocol::Detect { http, .. } => Protocol::Http2(http),
+        protocol => protocol,
+    };
+    policy
+}
+
 fn all_nets() -> impl Iterator<Item = IpNet> {
     vec![Ipv4Net::default().into(), Ipv6Net::default().into()].into_iter()
 }
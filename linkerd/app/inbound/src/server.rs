@@ -1,7 +1,7 @@
 use crate::{direct, policy, Inbound};
 use futures::Stream;
 use linkerd_app_core::{
-    dns, io, metrics, profiles, serve, svc,
+    dns, io, metrics, profiles, serve, startup, svc,
     transport::{self, ClientAddr, Local, OrigDstAddr, Remote, ServerAddr},
     Error, Result,
 };
@@ -46,6 +46,7 @@ impl Inbound<()> {
         P: profiles::GetProfile<Error = Error>,
     {
         let shutdown = self.runtime.drain.clone().signaled();
+        let startup = self.runtime.startup.clone();
 
         // Handles connections to ports that can't be determined to be HTTP.
         let forward = self
@@ -90,6 +91,9 @@ impl Inbound<()> {
         let server = http
             .push_detect(forward)
             .push_accept(addr.port(), policies, direct)
+            .into_stack()
+            // Hold or fail connections accepted before the proxy is ready.
+            .push(startup::NewStartupGate::layer(startup))
             .into_inner();
 
         serve::serve(listen, server, shutdown).await;
@@ -1,4 +1,4 @@
-use crate::{policy, Config};
+use crate::{policy, Config, HttpConnectMode};
 pub use futures::prelude::*;
 use linkerd_app_core::{
     config,
@@ -58,6 +58,7 @@ pub fn default_config() -> Config {
             server: config::ServerConfig {
                 addr: ListenAddr(([0, 0, 0, 0], 0).into()),
                 keepalive: Keepalive(None),
+                h1_settings: h1::ServerSettings::default(),
                 h2_settings: h2::Settings::default(),
             },
             connect: config::ConnectConfig {
@@ -69,14 +70,20 @@ pub fn default_config() -> Config {
                     0.1,
                 )
                 .unwrap(),
+                backoff_max_elapsed: None,
                 h1_settings: h1::PoolSettings {
                     max_idle: 1,
                     idle_timeout: Duration::from_secs(1),
+                    max_buf_size: None,
+                    close_delimited: Default::default(),
                 },
                 h2_settings: h2::Settings::default(),
+                h2_pool: h2::PoolSettings::default(),
             },
             max_in_flight_requests: 10_000,
             detect_protocol_timeout: Duration::from_secs(10),
+            forward_buffer_capacity: 8 * 1024,
+            forward_idle_timeout: None,
         },
         allowed_ips: Default::default(),
         http_request_queue: config::QueueConfig {
@@ -85,6 +92,22 @@ pub fn default_config() -> Config {
         },
         discovery_idle_timeout: Duration::from_secs(20),
         profile_skip_timeout: Duration::from_secs(1),
+        json_error_bodies: false,
+        http1_require_host: false,
+        http1_reject_ambiguous_framing: false,
+        http_max_header_value_bytes: None,
+        http_request_body_timeout: None,
+        http1_transfer_encoding: None,
+        http1_bridge_http10: false,
+        access_log_error_body_bytes: None,
+        http1_connect: HttpConnectMode::default(),
+        http_concurrency_limit_mode: Default::default(),
+        http_dst_port_header: false,
+        http_route_allowed_methods: Default::default(),
+        http_route_request_validations: Default::default(),
+        app_connect_grace: None,
+        max_connection_lifetime: None,
+        tls_sni_ports: Default::default(),
     }
 }
 
@@ -92,13 +115,15 @@ pub fn runtime() -> (ProxyRuntime, drain::Signal) {
     let (drain_tx, drain) = drain::channel();
     let (tap, _) = tap::new();
     let (metrics, _) =
-        metrics::Metrics::new(std::time::Duration::from_secs(10), Default::default());
+        metrics::Metrics::new(std::time::Duration::from_secs(10), 1.0, Default::default());
     let runtime = ProxyRuntime {
         identity: rustls::creds::default_for_test().1.into(),
+        shadow_identity: None,
         metrics: metrics.proxy,
         tap,
         span_sink: None,
         drain,
+        startup: Default::default(),
     };
     (runtime, drain_tx)
 }
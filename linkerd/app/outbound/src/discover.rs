@@ -1,6 +1,6 @@
 use crate::Outbound;
 use linkerd_app_core::{
-    profiles,
+    disco_cache, profiles,
     svc::{self, stack::Param},
     Error, Infallible,
 };
@@ -10,9 +10,27 @@ use std::{
 };
 use tracing::debug;
 
+mod events;
+mod exclude;
+mod resolver;
+mod stabilize;
+mod stale;
 #[cfg(test)]
 mod tests;
 
+pub(crate) use self::{
+    events::ObserveResolve, exclude::ExcludeEndpoints, stabilize::StabilizeEndpoints,
+    stale::EvictStaleEndpoints,
+};
+pub use self::{
+    events::{DiscoveryEvent, DiscoveryEvents},
+    exclude::EndpointExclusions,
+    resolver::{
+        EndpointUpdates, EndpointsFuture, GetProfiles, ProfileFuture, ResolveEndpoints, Resolver,
+    },
+    stale::{EndpointStaleness, StaleEndpointFallback},
+};
+
 /// Target with a discovery result.
 #[derive(Clone, Debug)]
 pub struct Discovery<T> {
@@ -40,11 +58,44 @@ impl<N> Outbound<N> {
         NSvc: svc::Service<Req, Error = Error> + Send + 'static,
         NSvc::Future: Send,
     {
-        self.map_stack(|config, _, stk| {
+        self.map_stack(|config, rt, stk| {
             let allow = config.allow_discovery.clone();
+            // Publishes profiles to discovery event subscribers as they are
+            // resolved and evicted from the cache, recording the result and
+            // duration of each lookup.
+            let profiles = events::ObserveProfiles::new(
+                rt.metrics.profile_lookups.record(
+                    rt.metrics
+                        .stack_layers
+                        .record_discovery(profiles.into_service()),
+                ),
+                rt.discovery_events.clone(),
+            );
+            let idle = config.discovery_idle_timeout;
+            let jitter = config.discovery_idle_jitter;
+            let max_lifetime = config.discovery_max_lifetime;
+            let backpressure = rt.metrics.discover_backpressure.counter();
+            let evictions = rt.discovery_evictions.clone();
             stk.clone()
                 .lift_new_with_target()
-                .push_new_cached_discover(profiles.into_service(), config.discovery_idle_timeout)
+                // Jitter the idle timeout so that resolutions created together
+                // aren't all dropped (and re-resolved) at once. Discoveries
+                // that must wait for capacity in the cache's queue are counted.
+                // When a maximum lifetime is configured, resolutions are
+                // replaced once they reach it, even while they are in use;
+                // existing connections keep the prior resolution. Resolutions
+                // may also be evicted, i.e. by the admin server, so that they
+                // are re-resolved.
+                .push(svc::layer::mk(move |inner| {
+                    let disco =
+                        disco_cache::NewCachedDiscover::new(inner, profiles.clone(), idle, jitter)
+                            .with_backpressure(backpressure.clone())
+                            .with_evictions(&evictions);
+                    match max_lifetime {
+                        Some(max_lifetime) => disco.with_max_age(max_lifetime),
+                        None => disco,
+                    }
+                }))
                 .push_switch(
                     move |parent: T| -> Result<_, Infallible> {
                         // TODO(ver) Should this allowance be parameterized by
@@ -75,9 +126,12 @@ impl<N> Outbound<N> {
 
 // === impl Discovery ===
 
-impl<T> From<(Option<profiles::Receiver>, T)> for Discovery<T> {
-    fn from((profile, parent): (Option<profiles::Receiver>, T)) -> Self {
-        Self { parent, profile }
+impl<T> From<(events::ObservedProfile, T)> for Discovery<T> {
+    fn from((observed, parent): (events::ObservedProfile, T)) -> Self {
+        Self {
+            parent,
+            profile: observed.profile,
+        }
     }
 }
 
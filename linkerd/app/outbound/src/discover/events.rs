@@ -0,0 +1,291 @@
+//! Publishes discovery events to subscribers.
+//!
+//! Embedders may [`DiscoveryEvents::subscribe`] to be notified as profiles are
+//! resolved and evicted from the discovery cache, and as endpoints are added to
+//! and removed from concrete services' resolutions. Events are published
+//! without blocking the proxy and are buffered in a bounded channel:
+//! subscribers that fall too far behind skip the oldest events.
+
+use futures::{ready, Stream};
+use linkerd_app_core::{
+    profiles,
+    proxy::{
+        api_resolve::ConcreteAddr,
+        core::{Resolve, Update},
+    },
+    svc, Error, NameAddr,
+};
+use pin_project::pin_project;
+use std::{
+    collections::HashSet,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::sync::broadcast;
+
+#[cfg(test)]
+mod tests;
+
+/// The number of events buffered for subscribers.
+const CAPACITY: usize = 1_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiscoveryEvent {
+    /// A profile was resolved for the address.
+    ProfileResolved(profiles::LookupAddr),
+
+    /// The profile resolved for the address was evicted from the discovery
+    /// cache after becoming idle.
+    ProfileEvicted(profiles::LookupAddr),
+
+    /// Endpoints were added to a concrete service's resolution.
+    EndpointsAdded {
+        concrete: NameAddr,
+        endpoints: Vec<SocketAddr>,
+    },
+
+    /// Endpoints were removed from a concrete service's resolution.
+    EndpointsRemoved {
+        concrete: NameAddr,
+        endpoints: Vec<SocketAddr>,
+    },
+}
+
+/// A handle for subscribing to the outbound proxy's discovery events.
+#[derive(Clone, Debug)]
+pub struct DiscoveryEvents(broadcast::Sender<DiscoveryEvent>);
+
+/// Publishes the profiles resolved by an inner discovery service.
+#[derive(Clone, Debug)]
+pub struct ObserveProfiles<P> {
+    inner: P,
+    events: DiscoveryEvents,
+}
+
+/// A profile discovery result. Its eviction is published once it is dropped by
+/// the discovery cache.
+#[derive(Clone, Debug)]
+pub struct ObservedProfile {
+    pub(super) profile: Option<profiles::Receiver>,
+    _evicted: Option<Arc<Evicted>>,
+}
+
+/// Publishes the endpoints added to and removed from an inner resolution.
+#[derive(Clone, Debug)]
+pub struct ObserveResolve<R> {
+    inner: R,
+    events: DiscoveryEvents,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResolveFuture<F> {
+    #[pin]
+    inner: F,
+    observe: Option<(NameAddr, DiscoveryEvents)>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ObservedResolution<S> {
+    #[pin]
+    inner: S,
+    concrete: NameAddr,
+    endpoints: HashSet<SocketAddr>,
+    events: DiscoveryEvents,
+}
+
+#[derive(Debug)]
+struct Evicted {
+    addr: profiles::LookupAddr,
+    events: DiscoveryEvents,
+}
+
+// === impl DiscoveryEvents ===
+
+impl Default for DiscoveryEvents {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(CAPACITY);
+        Self(tx)
+    }
+}
+
+impl DiscoveryEvents {
+    /// Returns a receiver of the events published after it is subscribed.
+    ///
+    /// Receivers that fall behind observe a [`broadcast::error::RecvError::Lagged`]
+    /// error and skip the oldest events.
+    pub fn subscribe(&self) -> broadcast::Receiver<DiscoveryEvent> {
+        self.0.subscribe()
+    }
+
+    fn publish(&self, event: DiscoveryEvent) {
+        // Sending only fails when there are no subscribers.
+        let _ = self.0.send(event);
+    }
+}
+
+// === impl ObserveProfiles ===
+
+impl<P> ObserveProfiles<P> {
+    pub fn new(inner: P, events: DiscoveryEvents) -> Self {
+        Self { inner, events }
+    }
+}
+
+impl<P> svc::Service<profiles::LookupAddr> for ObserveProfiles<P>
+where
+    P: svc::Service<profiles::LookupAddr, Response = Option<profiles::Receiver>, Error = Error>,
+    P::Future: Send + 'static,
+{
+    type Response = ObservedProfile;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<ObservedProfile, Error>> + Send + 'static>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, addr: profiles::LookupAddr) -> Self::Future {
+        let events = self.events.clone();
+        let profile = self.inner.call(addr.clone());
+        Box::pin(async move {
+            let profile = profile.await?;
+            let _evicted = profile.as_ref().map(|_| {
+                events.publish(DiscoveryEvent::ProfileResolved(addr.clone()));
+                Arc::new(Evicted { addr, events })
+            });
+            Ok(ObservedProfile { profile, _evicted })
+        })
+    }
+}
+
+// === impl Evicted ===
+
+impl Drop for Evicted {
+    fn drop(&mut self) {
+        self.events
+            .publish(DiscoveryEvent::ProfileEvicted(self.addr.clone()));
+    }
+}
+
+// === impl ObserveResolve ===
+
+impl<R> ObserveResolve<R> {
+    pub fn new(inner: R, events: DiscoveryEvents) -> Self {
+        Self { inner, events }
+    }
+}
+
+impl<R> svc::Service<ConcreteAddr> for ObserveResolve<R>
+where
+    R: Resolve<ConcreteAddr>,
+{
+    type Response = ObservedResolution<R::Resolution>;
+    type Error = R::Error;
+    type Future = ResolveFuture<R::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, target: ConcreteAddr) -> Self::Future {
+        let ConcreteAddr(concrete) = target.clone();
+        ResolveFuture {
+            inner: self.inner.resolve(target),
+            observe: Some((concrete, self.events.clone())),
+        }
+    }
+}
+
+// === impl ResolveFuture ===
+
+impl<F, S, E> Future for ResolveFuture<F>
+where
+    F: Future<Output = Result<S, E>>,
+{
+    type Output = Result<ObservedResolution<S>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let inner = ready!(this.inner.poll(cx))?;
+        let (concrete, events) = this.observe.take().expect("polled after completion");
+        Poll::Ready(Ok(ObservedResolution {
+            inner,
+            concrete,
+            endpoints: HashSet::new(),
+            events,
+        }))
+    }
+}
+
+// === impl ObservedResolution ===
+
+impl<S, T, E> Stream for ObservedResolution<S>
+where
+    S: Stream<Item = Result<Update<T>, E>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let item = ready!(this.inner.poll_next(cx));
+        if let Some(Ok(update)) = item.as_ref() {
+            let (added, removed) = match update {
+                Update::Add(eps) => {
+                    let added = eps
+                        .iter()
+                        .map(|(addr, _)| *addr)
+                        .filter(|addr| this.endpoints.insert(*addr))
+                        .collect();
+                    (added, Vec::new())
+                }
+                Update::Remove(addrs) => {
+                    let removed = addrs
+                        .iter()
+                        .copied()
+                        .filter(|addr| this.endpoints.remove(addr))
+                        .collect();
+                    (Vec::new(), removed)
+                }
+                Update::Reset(eps) => {
+                    let endpoints = eps.iter().map(|(addr, _)| *addr).collect::<HashSet<_>>();
+                    let added = endpoints.difference(this.endpoints).copied().collect();
+                    let removed = this.endpoints.difference(&endpoints).copied().collect();
+                    *this.endpoints = endpoints;
+                    (added, removed)
+                }
+                Update::DoesNotExist => (Vec::new(), this.endpoints.drain().collect()),
+            };
+            publish_endpoints(this.events, this.concrete, added, removed);
+        }
+        Poll::Ready(item)
+    }
+}
+
+fn publish_endpoints(
+    events: &DiscoveryEvents,
+    concrete: &NameAddr,
+    mut added: Vec<SocketAddr>,
+    mut removed: Vec<SocketAddr>,
+) {
+    if !removed.is_empty() {
+        removed.sort_unstable();
+        events.publish(DiscoveryEvent::EndpointsRemoved {
+            concrete: concrete.clone(),
+            endpoints: removed,
+        });
+    }
+    if !added.is_empty() {
+        added.sort_unstable();
+        events.publish(DiscoveryEvent::EndpointsAdded {
+            concrete: concrete.clone(),
+            endpoints: added,
+        });
+    }
+}
@@ -0,0 +1,91 @@
+use super::*;
+use crate::test_util::support;
+use futures::StreamExt;
+use linkerd_app_core::{proxy::api_resolve::Metadata, svc::ServiceExt};
+
+fn ep(port: u16) -> (SocketAddr, Metadata) {
+    (
+        SocketAddr::new([192, 0, 2, 30].into(), port),
+        Metadata::default(),
+    )
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn publishes_endpoint_events() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let concrete = "foo.ns.svc.cluster.local:8080".parse::<NameAddr>().unwrap();
+    let resolve = support::resolver::<Metadata>();
+    let mut tx = resolve.endpoint_tx(concrete.clone());
+
+    let discovery = DiscoveryEvents::default();
+    let mut events = discovery.subscribe();
+    let mut resolution = ObserveResolve::new(resolve, discovery)
+        .oneshot(ConcreteAddr(concrete.clone()))
+        .await
+        .unwrap();
+
+    let added = |ports: &[u16]| DiscoveryEvent::EndpointsAdded {
+        concrete: concrete.clone(),
+        endpoints: ports.iter().map(|p| ep(*p).0).collect(),
+    };
+    let removed = |ports: &[u16]| DiscoveryEvent::EndpointsRemoved {
+        concrete: concrete.clone(),
+        endpoints: ports.iter().map(|p| ep(*p).0).collect(),
+    };
+
+    tx.add(vec![ep(1), ep(2)]).unwrap();
+    resolution.next().await.unwrap().unwrap();
+    assert_eq!(events.try_recv().unwrap(), added(&[1, 2]));
+
+    // Endpoints that are already resolved are not added again.
+    tx.add(vec![ep(2), ep(3)]).unwrap();
+    resolution.next().await.unwrap().unwrap();
+    assert_eq!(events.try_recv().unwrap(), added(&[3]));
+
+    tx.remove(vec![ep(1).0]).unwrap();
+    resolution.next().await.unwrap().unwrap();
+    assert_eq!(events.try_recv().unwrap(), removed(&[1]));
+
+    // Resets are published as the difference from the prior endpoints.
+    tx.reset(vec![ep(3), ep(4)]).unwrap();
+    resolution.next().await.unwrap().unwrap();
+    assert_eq!(events.try_recv().unwrap(), removed(&[2]));
+    assert_eq!(events.try_recv().unwrap(), added(&[4]));
+
+    tx.does_not_exist().unwrap();
+    resolution.next().await.unwrap().unwrap();
+    assert_eq!(events.try_recv().unwrap(), removed(&[3, 4]));
+    assert!(events.try_recv().is_err(), "no other events are published");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn publishing_does_not_block() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let concrete = "foo.ns.svc.cluster.local:8080".parse::<NameAddr>().unwrap();
+    let resolve = support::resolver::<Metadata>();
+    let mut tx = resolve.endpoint_tx(concrete.clone());
+
+    let discovery = DiscoveryEvents::default();
+    let mut events = discovery.subscribe();
+    let mut resolution = ObserveResolve::new(resolve, discovery)
+        .oneshot(ConcreteAddr(concrete))
+        .await
+        .unwrap();
+
+    // Resolution proceeds even though the subscriber does not keep up.
+    for port in 0..(CAPACITY as u16 * 2) {
+        tx.add(vec![ep(port)]).unwrap();
+        resolution.next().await.unwrap().unwrap();
+    }
+
+    assert!(
+        matches!(
+            events.try_recv(),
+            Err(broadcast::error::TryRecvError::Lagged(_))
+        ),
+        "slow subscribers must skip events"
+    );
+    assert!(events.try_recv().is_ok());
+}
@@ -0,0 +1,230 @@
+//! Excludes endpoints from resolutions by their metadata.
+//!
+//! Endpoints may remain in a service's resolution while they should not
+//! receive new traffic--e.g. while they are draining. When exclusions are
+//! configured, resolved endpoints that carry any of the excluded labels are
+//! omitted from the resolution, and endpoints whose labels are updated to match
+//! an exclusion are removed from it, so that they are not added to load
+//! balancers.
+
+use futures::{ready, Stream};
+use linkerd_app_core::{
+    proxy::{
+        api_resolve::Metadata,
+        core::{Resolve, Update},
+    },
+    svc,
+};
+use pin_project::pin_project;
+use std::{
+    collections::HashSet,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tracing::debug;
+
+#[cfg(test)]
+mod tests;
+
+/// Endpoint labels, as `(key, value)` pairs, that exclude endpoints from
+/// resolutions.
+#[derive(Clone, Debug)]
+pub struct EndpointExclusions(Arc<[(String, String)]>);
+
+#[derive(Clone, Debug)]
+pub(crate) struct ExcludeEndpoints<R> {
+    inner: R,
+    exclusions: EndpointExclusions,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResolveFuture<F> {
+    #[pin]
+    inner: F,
+    exclusions: Option<EndpointExclusions>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ExcludedResolution<S> {
+    #[pin]
+    inner: S,
+    exclusions: EndpointExclusions,
+    /// The endpoints that have been published by this resolution.
+    endpoints: HashSet<SocketAddr>,
+    /// An update to be published before the next inner update is read.
+    pending: Option<Update<Metadata>>,
+}
+
+// === impl EndpointExclusions ===
+
+impl Default for EndpointExclusions {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl EndpointExclusions {
+    pub fn new(labels: impl IntoIterator<Item = (String, String)>) -> Self {
+        Self(labels.into_iter().collect())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn excludes(&self, metadata: &Metadata) -> bool {
+        let labels = metadata.labels();
+        self.0
+            .iter()
+            .any(|(k, v)| labels.get(k).map_or(false, |l| l == v))
+    }
+}
+
+impl FromIterator<(String, String)> for EndpointExclusions {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+// === impl ExcludeEndpoints ===
+
+impl<R> ExcludeEndpoints<R> {
+    pub fn new(inner: R, exclusions: EndpointExclusions) -> Self {
+        Self { inner, exclusions }
+    }
+}
+
+impl<T, R> svc::Service<T> for ExcludeEndpoints<R>
+where
+    R: Resolve<T, Endpoint = Metadata>,
+{
+    type Response = ExcludedResolution<R::Resolution>;
+    type Error = R::Error;
+    type Future = ResolveFuture<R::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        ResolveFuture {
+            inner: self.inner.resolve(target),
+            exclusions: Some(self.exclusions.clone()),
+        }
+    }
+}
+
+// === impl ResolveFuture ===
+
+impl<F, S, E> Future for ResolveFuture<F>
+where
+    F: Future<Output = Result<S, E>>,
+{
+    type Output = Result<ExcludedResolution<S>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let inner = ready!(this.inner.poll(cx))?;
+        let exclusions = this.exclusions.take().expect("polled after completion");
+        Poll::Ready(Ok(ExcludedResolution {
+            inner,
+            exclusions,
+            endpoints: HashSet::new(),
+            pending: None,
+        }))
+    }
+}
+
+// === impl ExcludedResolution ===
+
+impl<S, E> Stream for ExcludedResolution<S>
+where
+    S: Stream<Item = Result<Update<Metadata>, E>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if let Some(update) = this.pending.take() {
+            return Poll::Ready(Some(Ok(update)));
+        }
+        if this.exclusions.is_empty() {
+            return this.inner.poll_next(cx);
+        }
+
+        loop {
+            let update = match ready!(this.inner.as_mut().poll_next(cx)) {
+                Some(Ok(update)) => update,
+                item => return Poll::Ready(item),
+            };
+            let update = match update {
+                Update::Add(eps) => {
+                    let (excluded, eps) = partition(this.exclusions, eps);
+                    // Endpoints that were updated to match an exclusion are
+                    // removed.
+                    let removed = excluded
+                        .into_iter()
+                        .filter(|addr| this.endpoints.remove(addr))
+                        .collect::<Vec<_>>();
+                    this.endpoints.extend(eps.iter().map(|(addr, _)| *addr));
+                    match (eps.is_empty(), removed.is_empty()) {
+                        (true, true) => continue,
+                        (true, false) => Update::Remove(removed),
+                        (false, true) => Update::Add(eps),
+                        (false, false) => {
+                            *this.pending = Some(Update::Remove(removed));
+                            Update::Add(eps)
+                        }
+                    }
+                }
+                Update::Reset(eps) => {
+                    let (_, eps) = partition(this.exclusions, eps);
+                    *this.endpoints = eps.iter().map(|(addr, _)| *addr).collect();
+                    Update::Reset(eps)
+                }
+                Update::Remove(addrs) => {
+                    let addrs = addrs
+                        .into_iter()
+                        .filter(|addr| this.endpoints.remove(addr))
+                        .collect::<Vec<_>>();
+                    if addrs.is_empty() {
+                        continue;
+                    }
+                    Update::Remove(addrs)
+                }
+                Update::DoesNotExist => {
+                    this.endpoints.clear();
+                    Update::DoesNotExist
+                }
+            };
+            return Poll::Ready(Some(Ok(update)));
+        }
+    }
+}
+
+/// Splits endpoints into the addresses of those that are excluded and those
+/// that are not.
+fn partition(
+    exclusions: &EndpointExclusions,
+    eps: Vec<(SocketAddr, Metadata)>,
+) -> (Vec<SocketAddr>, Vec<(SocketAddr, Metadata)>) {
+    let mut excluded = Vec::new();
+    let eps = eps
+        .into_iter()
+        .filter(|(addr, metadata)| {
+            if exclusions.excludes(metadata) {
+                debug!(%addr, "Excluding endpoint");
+                excluded.push(*addr);
+                return false;
+            }
+            true
+        })
+        .collect();
+    (excluded, eps)
+}
@@ -0,0 +1,78 @@
+use super::*;
+use futures::StreamExt;
+use linkerd_app_core::{proxy::api_resolve::ProtocolHint, Infallible};
+
+fn draining() -> EndpointExclusions {
+    EndpointExclusions::new([("draining".to_string(), "true".to_string())])
+}
+
+fn metadata(draining: bool) -> Metadata {
+    Metadata::new(
+        [("draining".to_string(), draining.to_string())],
+        ProtocolHint::Unknown,
+        None,
+        None,
+        None,
+    )
+}
+
+/// Returns the updates published for the given inner updates.
+async fn updates(
+    exclusions: EndpointExclusions,
+    updates: Vec<Update<Metadata>>,
+) -> Vec<Update<Metadata>> {
+    let mut resolution = ExcludedResolution {
+        inner: futures::stream::iter(updates.into_iter().map(Ok::<_, Infallible>)),
+        exclusions,
+        endpoints: HashSet::new(),
+        pending: None,
+    };
+    let mut published = Vec::new();
+    while let Some(update) = resolution.next().await {
+        published.push(update.unwrap());
+    }
+    published
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn excludes_draining_endpoints() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let ep0 = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let ep1 = SocketAddr::new([192, 0, 2, 31].into(), 8080);
+    let ep2 = SocketAddr::new([192, 0, 2, 32].into(), 8080);
+    let published = updates(
+        draining(),
+        vec![
+            Update::Reset(vec![(ep0, metadata(true)), (ep1, metadata(false))]),
+            Update::Add(vec![(ep2, metadata(true))]),
+            Update::Remove(vec![ep0, ep2]),
+            Update::Add(vec![(ep0, metadata(false))]),
+            // Once it is draining, ep1 is removed.
+            Update::Add(vec![(ep1, metadata(true)), (ep2, metadata(false))]),
+        ],
+    )
+    .await;
+    assert_eq!(
+        published,
+        vec![
+            Update::Reset(vec![(ep1, metadata(false))]),
+            Update::Add(vec![(ep0, metadata(false))]),
+            Update::Add(vec![(ep2, metadata(false))]),
+            Update::Remove(vec![ep1]),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn passes_updates_without_exclusions() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let ep0 = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let inner = vec![
+        Update::Add(vec![(ep0, metadata(true))]),
+        Update::Remove(vec![ep0]),
+    ];
+    let published = updates(EndpointExclusions::default(), inner.clone()).await;
+    assert_eq!(published, inner);
+}
@@ -0,0 +1,90 @@
+//! A pluggable interface for service discovery backends.
+//!
+//! The outbound stacks consume profile discovery as a [`profiles::GetProfile`]
+//! and endpoint resolution as a [`Resolve`](linkerd_app_core::proxy::core::Resolve), which are usually implemented by
+//! control plane clients. Embedders with their own discovery backends may
+//! instead implement [`Resolver`] and pass its [`Resolver::profiles`] and
+//! [`Resolver::endpoints`] adapters to the stacks--e.g. to
+//! [`Outbound::push_discover`](crate::Outbound::push_discover) and
+//! [`Outbound::serve`](crate::Outbound::serve).
+
+use futures::{future::BoxFuture, stream::BoxStream};
+use linkerd_app_core::{
+    profiles,
+    proxy::{
+        api_resolve::{ConcreteAddr, Metadata},
+        core::Update,
+    },
+    svc, Error,
+};
+use std::task::{Context, Poll};
+
+/// Discovers the profiles and endpoints of outbound destinations.
+pub trait Resolver: Clone + Send + Sync + Unpin + 'static {
+    /// Discovers the profile of a destination. Resolves to `None` when the
+    /// destination has no profile.
+    fn get_profile(&self, addr: profiles::LookupAddr) -> ProfileFuture;
+
+    /// Resolves the endpoints of a concrete destination as a stream of
+    /// updates.
+    fn resolve_endpoints(&self, addr: ConcreteAddr) -> EndpointsFuture;
+
+    /// Returns a profile discovery service backed by this resolver.
+    fn profiles(self) -> GetProfiles<Self> {
+        GetProfiles(self)
+    }
+
+    /// Returns an endpoint resolution service backed by this resolver.
+    fn endpoints(self) -> ResolveEndpoints<Self> {
+        ResolveEndpoints(self)
+    }
+}
+
+pub type ProfileFuture = BoxFuture<'static, Result<Option<profiles::Receiver>, Error>>;
+
+pub type EndpointUpdates = BoxStream<'static, Result<Update<Metadata>, Error>>;
+
+pub type EndpointsFuture = BoxFuture<'static, Result<EndpointUpdates, Error>>;
+
+/// Implements [`profiles::GetProfile`] with a [`Resolver`].
+#[derive(Clone, Debug)]
+pub struct GetProfiles<R>(R);
+
+/// Implements [`Resolve`](linkerd_app_core::proxy::core::Resolve) with a
+/// [`Resolver`].
+#[derive(Clone, Debug)]
+pub struct ResolveEndpoints<R>(R);
+
+// === impl GetProfiles ===
+
+impl<R: Resolver> svc::Service<profiles::LookupAddr> for GetProfiles<R> {
+    type Response = Option<profiles::Receiver>;
+    type Error = Error;
+    type Future = ProfileFuture;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, addr: profiles::LookupAddr) -> Self::Future {
+        self.0.get_profile(addr)
+    }
+}
+
+// === impl ResolveEndpoints ===
+
+impl<R: Resolver> svc::Service<ConcreteAddr> for ResolveEndpoints<R> {
+    type Response = EndpointUpdates;
+    type Error = Error;
+    type Future = EndpointsFuture;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, addr: ConcreteAddr) -> Self::Future {
+        self.0.resolve_endpoints(addr)
+    }
+}
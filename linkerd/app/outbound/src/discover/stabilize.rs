@@ -0,0 +1,218 @@
+//! Smooths over transient endpoint removals in a resolution.
+//!
+//! While a service scales, its resolution may churn rapidly, briefly reporting
+//! partial or empty endpoint sets before endpoints are re-added. When a
+//! stabilization window is configured, endpoints are added to the balancer as
+//! soon as they are resolved, but each removal is deferred for the window; an
+//! endpoint that is re-added within the window is never removed from the
+//! balancer. Resets are handled as additions of their endpoints and deferred
+//! removals of all others.
+//!
+//! Resolutions that report that the service does not exist are not deferred.
+
+use futures::{ready, Stream};
+use linkerd_app_core::{
+    proxy::{
+        api_resolve::Metadata,
+        core::{Resolve, Update},
+    },
+    svc,
+};
+use pin_project::pin_project;
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::time::{self, Duration, Instant, Sleep};
+use tracing::{debug, trace};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug)]
+pub(crate) struct StabilizeEndpoints<R> {
+    inner: R,
+    window: Option<Duration>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResolveFuture<F> {
+    #[pin]
+    inner: F,
+    window: Option<Option<Duration>>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct StabilizedResolution<S> {
+    #[pin]
+    inner: S,
+    window: Option<Duration>,
+    /// The endpoints that have been published to the balancer.
+    published: HashSet<SocketAddr>,
+    /// The time at which each removed endpoint's removal is published, unless
+    /// it is re-added first.
+    removals: HashMap<SocketAddr, Instant>,
+    /// Fires when the earliest deferred removal is due.
+    expiry: Option<Pin<Box<Sleep>>>,
+}
+
+// === impl StabilizeEndpoints ===
+
+impl<R> StabilizeEndpoints<R> {
+    /// When `window` is `None`, updates are passed through unchanged.
+    pub fn new(inner: R, window: Option<Duration>) -> Self {
+        Self { inner, window }
+    }
+}
+
+impl<T, R> svc::Service<T> for StabilizeEndpoints<R>
+where
+    R: Resolve<T, Endpoint = Metadata>,
+{
+    type Response = StabilizedResolution<R::Resolution>;
+    type Error = R::Error;
+    type Future = ResolveFuture<R::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        ResolveFuture {
+            inner: self.inner.resolve(target),
+            window: Some(self.window),
+        }
+    }
+}
+
+// === impl ResolveFuture ===
+
+impl<F, S, E> Future for ResolveFuture<F>
+where
+    F: Future<Output = Result<S, E>>,
+{
+    type Output = Result<StabilizedResolution<S>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let inner = ready!(this.inner.poll(cx))?;
+        let window = this.window.take().expect("polled after completion");
+        Poll::Ready(Ok(StabilizedResolution::new(inner, window)))
+    }
+}
+
+// === impl StabilizedResolution ===
+
+impl<S> StabilizedResolution<S> {
+    fn new(inner: S, window: Option<Duration>) -> Self {
+        Self {
+            inner,
+            window,
+            published: HashSet::new(),
+            removals: HashMap::new(),
+            expiry: None,
+        }
+    }
+}
+
+impl<S, E> Stream for StabilizedResolution<S>
+where
+    S: Stream<Item = Result<Update<Metadata>, E>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        let window = match *this.window {
+            Some(window) => window,
+            None => return this.inner.poll_next(cx),
+        };
+
+        loop {
+            if let Poll::Ready(item) = this.inner.as_mut().poll_next(cx) {
+                let update = match item {
+                    Some(Ok(update)) => update,
+                    item => return Poll::Ready(item),
+                };
+                let due = Instant::now() + window;
+                let update = match update {
+                    Update::Add(eps) => {
+                        for (addr, _) in &eps {
+                            this.removals.remove(addr);
+                            this.published.insert(*addr);
+                        }
+                        Some(Update::Add(eps))
+                    }
+                    Update::Reset(eps) => {
+                        let addrs = eps.iter().map(|(addr, _)| *addr).collect::<HashSet<_>>();
+                        for addr in this.published.difference(&addrs) {
+                            this.removals.entry(*addr).or_insert(due);
+                        }
+                        for addr in &addrs {
+                            this.removals.remove(addr);
+                        }
+                        this.published.extend(addrs);
+                        Some(Update::Add(eps))
+                    }
+                    Update::Remove(addrs) => {
+                        for addr in addrs {
+                            if this.published.contains(&addr) {
+                                this.removals.entry(addr).or_insert(due);
+                            }
+                        }
+                        None
+                    }
+                    Update::DoesNotExist => {
+                        this.published.clear();
+                        this.removals.clear();
+                        Some(Update::DoesNotExist)
+                    }
+                };
+                *this.expiry = next_expiry(this.removals);
+                match update {
+                    Some(Update::Add(eps)) if eps.is_empty() => continue,
+                    Some(update) => return Poll::Ready(Some(Ok(update))),
+                    None => {
+                        trace!(removals = this.removals.len(), "Deferring removals");
+                        continue;
+                    }
+                }
+            }
+
+            let expiry = match this.expiry.as_mut() {
+                Some(expiry) => expiry,
+                None => return Poll::Pending,
+            };
+            ready!(expiry.as_mut().poll(cx));
+
+            let now = Instant::now();
+            let removed = this
+                .removals
+                .iter()
+                .filter(|(_, due)| **due <= now)
+                .map(|(addr, _)| *addr)
+                .collect::<Vec<_>>();
+            for addr in &removed {
+                this.removals.remove(addr);
+                this.published.remove(addr);
+            }
+            *this.expiry = next_expiry(this.removals);
+            if !removed.is_empty() {
+                debug!(?removed, ?window, "Removing endpoints after stabilization");
+                return Poll::Ready(Some(Ok(Update::Remove(removed))));
+            }
+        }
+    }
+}
+
+/// Returns a timer that fires when the earliest deferred removal is due.
+fn next_expiry(removals: &HashMap<SocketAddr, Instant>) -> Option<Pin<Box<Sleep>>> {
+    let earliest = removals.values().min()?;
+    Some(Box::pin(time::sleep_until(*earliest)))
+}
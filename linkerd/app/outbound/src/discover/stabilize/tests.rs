@@ -0,0 +1,102 @@
+use super::*;
+use futures::{channel::mpsc, StreamExt};
+use linkerd_app_core::Infallible;
+
+const WINDOW: Duration = Duration::from_secs(10);
+
+/// Returns a resolution whose inner updates are sent on the returned channel.
+fn resolution(
+    window: Option<Duration>,
+) -> (
+    mpsc::UnboundedSender<Result<Update<Metadata>, Infallible>>,
+    StabilizedResolution<mpsc::UnboundedReceiver<Result<Update<Metadata>, Infallible>>>,
+) {
+    let (tx, rx) = mpsc::unbounded();
+    (tx, StabilizedResolution::new(rx, window))
+}
+
+fn add(addrs: &[SocketAddr]) -> Update<Metadata> {
+    Update::Add(addrs.iter().map(|a| (*a, Metadata::default())).collect())
+}
+
+fn reset(addrs: &[SocketAddr]) -> Update<Metadata> {
+    Update::Reset(addrs.iter().map(|a| (*a, Metadata::default())).collect())
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn stabilizes_endpoint_churn() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let ep0 = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let ep1 = SocketAddr::new([192, 0, 2, 31].into(), 8080);
+    let ep2 = SocketAddr::new([192, 0, 2, 32].into(), 8080);
+    let (tx, mut resolution) = resolution(Some(WINDOW));
+    let start = Instant::now();
+
+    tx.unbounded_send(Ok(reset(&[ep0, ep1]))).unwrap();
+    assert_eq!(resolution.next().await.unwrap().unwrap(), add(&[ep0, ep1]));
+
+    // The resolution churns through partial and empty sets while the service
+    // scales, but endpoints that return within the window are never removed.
+    tx.unbounded_send(Ok(Update::Remove(vec![ep1]))).unwrap();
+    tx.unbounded_send(Ok(reset(&[]))).unwrap();
+    time::sleep(Duration::from_secs(2)).await;
+    tx.unbounded_send(Ok(reset(&[ep0]))).unwrap();
+    assert_eq!(resolution.next().await.unwrap().unwrap(), add(&[ep0]));
+    tx.unbounded_send(Ok(add(&[ep1, ep2]))).unwrap();
+    assert_eq!(resolution.next().await.unwrap().unwrap(), add(&[ep1, ep2]));
+
+    // An endpoint that does not return is removed once the window elapses.
+    time::sleep(Duration::from_secs(3)).await;
+    tx.unbounded_send(Ok(reset(&[ep0, ep2]))).unwrap();
+    assert_eq!(resolution.next().await.unwrap().unwrap(), add(&[ep0, ep2]));
+    assert_eq!(
+        resolution.next().await.unwrap().unwrap(),
+        Update::Remove(vec![ep1])
+    );
+    assert_eq!(
+        Instant::now().saturating_duration_since(start),
+        Duration::from_secs(5) + WINDOW
+    );
+
+    time::timeout(WINDOW * 10, resolution.next())
+        .await
+        .expect_err("the stabilized set must not change");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn does_not_defer_nonexistence() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let ep0 = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let (tx, mut resolution) = resolution(Some(WINDOW));
+
+    tx.unbounded_send(Ok(add(&[ep0]))).unwrap();
+    assert_eq!(resolution.next().await.unwrap().unwrap(), add(&[ep0]));
+    tx.unbounded_send(Ok(Update::Remove(vec![ep0]))).unwrap();
+    tx.unbounded_send(Ok(Update::DoesNotExist)).unwrap();
+    assert_eq!(
+        resolution.next().await.unwrap().unwrap(),
+        Update::DoesNotExist
+    );
+
+    // Removals deferred before the service ceased to exist are dropped.
+    time::timeout(WINDOW * 10, resolution.next())
+        .await
+        .expect_err("no removals must be published");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn passes_updates_without_window() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let ep0 = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let (tx, mut resolution) = resolution(None);
+    tx.unbounded_send(Ok(reset(&[ep0]))).unwrap();
+    assert_eq!(resolution.next().await.unwrap().unwrap(), reset(&[ep0]));
+    tx.unbounded_send(Ok(Update::Remove(vec![ep0]))).unwrap();
+    assert_eq!(
+        resolution.next().await.unwrap().unwrap(),
+        Update::Remove(vec![ep0])
+    );
+}
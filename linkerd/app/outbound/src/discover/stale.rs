@@ -0,0 +1,231 @@
+//! Evicts endpoints that have not been refreshed by their resolution.
+//!
+//! If a resolution stream goes silent--e.g. because the control plane stops
+//! sending updates without closing the stream--its endpoints may become stale.
+//! When a maximum age is configured, each endpoint is timestamped when it is
+//! added (or re-added) to the resolution, and endpoints that are not refreshed
+//! within the maximum age are removed from it.
+//!
+//! Evicting every endpoint leaves the balancer without endpoints, so requests
+//! fail until the resolution is updated. When the fallback is
+//! [`StaleEndpointFallback::Retain`], the resolution's endpoints are retained
+//! once they are all stale, so that traffic continues to be sent to the last
+//! known endpoints.
+
+use futures::{ready, Stream};
+use linkerd_app_core::{
+    proxy::{
+        api_resolve::Metadata,
+        core::{Resolve, Update},
+    },
+    svc,
+};
+use pin_project::pin_project;
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::time::{self, Duration, Instant, Sleep};
+use tracing::debug;
+
+#[cfg(test)]
+mod tests;
+
+/// Configures the eviction of stale endpoints.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EndpointStaleness {
+    /// The maximum time an endpoint remains in a resolution without being
+    /// refreshed.
+    pub max_age: Duration,
+    pub fallback: StaleEndpointFallback,
+}
+
+/// Determines what happens once all of a resolution's endpoints are stale.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum StaleEndpointFallback {
+    /// All stale endpoints are evicted.
+    #[default]
+    Evict,
+
+    /// Stale endpoints are retained while no endpoints are fresh.
+    Retain,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct EvictStaleEndpoints<R> {
+    inner: R,
+    staleness: Option<EndpointStaleness>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResolveFuture<F> {
+    #[pin]
+    inner: F,
+    staleness: Option<Option<EndpointStaleness>>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct StaleResolution<S> {
+    #[pin]
+    inner: S,
+    staleness: Option<EndpointStaleness>,
+    /// The time at which each published endpoint was last refreshed.
+    endpoints: HashMap<SocketAddr, Instant>,
+    /// Fires when the oldest endpoint becomes stale.
+    expiry: Option<Pin<Box<Sleep>>>,
+}
+
+// === impl EvictStaleEndpoints ===
+
+impl<R> EvictStaleEndpoints<R> {
+    pub fn new(inner: R, staleness: Option<EndpointStaleness>) -> Self {
+        Self { inner, staleness }
+    }
+}
+
+impl<T, R> svc::Service<T> for EvictStaleEndpoints<R>
+where
+    R: Resolve<T, Endpoint = Metadata>,
+{
+    type Response = StaleResolution<R::Resolution>;
+    type Error = R::Error;
+    type Future = ResolveFuture<R::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        ResolveFuture {
+            inner: self.inner.resolve(target),
+            staleness: Some(self.staleness),
+        }
+    }
+}
+
+// === impl ResolveFuture ===
+
+impl<F, S, E> Future for ResolveFuture<F>
+where
+    F: Future<Output = Result<S, E>>,
+{
+    type Output = Result<StaleResolution<S>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let inner = ready!(this.inner.poll(cx))?;
+        let staleness = this.staleness.take().expect("polled after completion");
+        Poll::Ready(Ok(StaleResolution::new(inner, staleness)))
+    }
+}
+
+// === impl StaleResolution ===
+
+impl<S> StaleResolution<S> {
+    fn new(inner: S, staleness: Option<EndpointStaleness>) -> Self {
+        Self {
+            inner,
+            staleness,
+            endpoints: HashMap::new(),
+            expiry: None,
+        }
+    }
+}
+
+impl<S, E> Stream for StaleResolution<S>
+where
+    S: Stream<Item = Result<Update<Metadata>, E>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        let EndpointStaleness { max_age, fallback } = match *this.staleness {
+            Some(staleness) => staleness,
+            None => return this.inner.poll_next(cx),
+        };
+
+        loop {
+            if let Poll::Ready(item) = this.inner.as_mut().poll_next(cx) {
+                let update = match item {
+                    Some(Ok(update)) => update,
+                    item => return Poll::Ready(item),
+                };
+                let now = Instant::now();
+                let update = match update {
+                    Update::Add(eps) => {
+                        this.endpoints
+                            .extend(eps.iter().map(|(addr, _)| (*addr, now)));
+                        Update::Add(eps)
+                    }
+                    Update::Reset(eps) => {
+                        *this.endpoints = eps.iter().map(|(addr, _)| (*addr, now)).collect();
+                        Update::Reset(eps)
+                    }
+                    Update::Remove(addrs) => {
+                        // Endpoints that were already evicted are not removed
+                        // again.
+                        let addrs = addrs
+                            .into_iter()
+                            .filter(|addr| this.endpoints.remove(addr).is_some())
+                            .collect::<Vec<_>>();
+                        if addrs.is_empty() {
+                            continue;
+                        }
+                        Update::Remove(addrs)
+                    }
+                    Update::DoesNotExist => {
+                        this.endpoints.clear();
+                        Update::DoesNotExist
+                    }
+                };
+                *this.expiry = next_expiry(this.endpoints, max_age);
+                return Poll::Ready(Some(Ok(update)));
+            }
+
+            let expiry = match this.expiry.as_mut() {
+                Some(expiry) => expiry,
+                None => return Poll::Pending,
+            };
+            ready!(expiry.as_mut().poll(cx));
+
+            let now = Instant::now();
+            let stale = this
+                .endpoints
+                .iter()
+                .filter(|(_, refreshed)| **refreshed + max_age <= now)
+                .map(|(addr, _)| *addr)
+                .collect::<Vec<_>>();
+            if fallback == StaleEndpointFallback::Retain && stale.len() == this.endpoints.len() {
+                // Endpoints are not evicted again until the resolution is
+                // updated.
+                debug!(endpoints = stale.len(), "Retaining stale endpoints");
+                *this.expiry = None;
+                return Poll::Pending;
+            }
+            for addr in &stale {
+                this.endpoints.remove(addr);
+            }
+            *this.expiry = next_expiry(this.endpoints, max_age);
+            if !stale.is_empty() {
+                debug!(?stale, ?max_age, "Evicting stale endpoints");
+                return Poll::Ready(Some(Ok(Update::Remove(stale))));
+            }
+        }
+    }
+}
+
+/// Returns a timer that fires when the oldest endpoint becomes stale.
+fn next_expiry(
+    endpoints: &HashMap<SocketAddr, Instant>,
+    max_age: Duration,
+) -> Option<Pin<Box<Sleep>>> {
+    let oldest = endpoints.values().min()?;
+    Some(Box::pin(time::sleep_until(*oldest + max_age)))
+}
@@ -0,0 +1,105 @@
+use super::*;
+use futures::{channel::mpsc, StreamExt};
+use linkerd_app_core::Infallible;
+
+const MAX_AGE: Duration = Duration::from_secs(10);
+
+/// Returns a resolution whose inner updates are sent on the returned channel.
+fn resolution(
+    fallback: StaleEndpointFallback,
+) -> (
+    mpsc::UnboundedSender<Result<Update<Metadata>, Infallible>>,
+    StaleResolution<mpsc::UnboundedReceiver<Result<Update<Metadata>, Infallible>>>,
+) {
+    let (tx, rx) = mpsc::unbounded();
+    let staleness = EndpointStaleness {
+        max_age: MAX_AGE,
+        fallback,
+    };
+    (tx, StaleResolution::new(rx, Some(staleness)))
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn evicts_stale_endpoints() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let ep0 = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let ep1 = SocketAddr::new([192, 0, 2, 31].into(), 8080);
+    let (tx, mut resolution) = resolution(StaleEndpointFallback::Evict);
+    let start = Instant::now();
+
+    let reset = Update::Reset(vec![(ep0, Metadata::default()), (ep1, Metadata::default())]);
+    tx.unbounded_send(Ok(reset.clone())).unwrap();
+    assert_eq!(resolution.next().await.unwrap().unwrap(), reset);
+
+    // Only ep1 is refreshed before the stream stops updating.
+    time::sleep(Duration::from_secs(5)).await;
+    let add = Update::Add(vec![(ep1, Metadata::default())]);
+    tx.unbounded_send(Ok(add.clone())).unwrap();
+    assert_eq!(resolution.next().await.unwrap().unwrap(), add);
+
+    // Each endpoint is evicted once it reaches the maximum age.
+    assert_eq!(
+        resolution.next().await.unwrap().unwrap(),
+        Update::Remove(vec![ep0])
+    );
+    assert_eq!(Instant::now().saturating_duration_since(start), MAX_AGE);
+    assert_eq!(
+        resolution.next().await.unwrap().unwrap(),
+        Update::Remove(vec![ep1])
+    );
+    assert_eq!(
+        Instant::now().saturating_duration_since(start),
+        MAX_AGE + Duration::from_secs(5)
+    );
+
+    // Evicted endpoints are not removed again.
+    tx.unbounded_send(Ok(Update::Remove(vec![ep0]))).unwrap();
+    let add = Update::Add(vec![(ep0, Metadata::default())]);
+    tx.unbounded_send(Ok(add.clone())).unwrap();
+    assert_eq!(resolution.next().await.unwrap().unwrap(), add);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn retains_endpoints_when_all_are_stale() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let ep0 = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let ep1 = SocketAddr::new([192, 0, 2, 31].into(), 8080);
+    let (tx, mut resolution) = resolution(StaleEndpointFallback::Retain);
+
+    let reset = Update::Reset(vec![(ep0, Metadata::default())]);
+    tx.unbounded_send(Ok(reset.clone())).unwrap();
+    assert_eq!(resolution.next().await.unwrap().unwrap(), reset);
+    time::sleep(Duration::from_secs(5)).await;
+    let add = Update::Add(vec![(ep1, Metadata::default())]);
+    tx.unbounded_send(Ok(add.clone())).unwrap();
+    assert_eq!(resolution.next().await.unwrap().unwrap(), add);
+
+    // Stale endpoints are evicted while others are fresh ...
+    assert_eq!(
+        resolution.next().await.unwrap().unwrap(),
+        Update::Remove(vec![ep0])
+    );
+
+    // ... but the last endpoints are retained once they are all stale.
+    time::timeout(MAX_AGE * 10, resolution.next())
+        .await
+        .expect_err("stale endpoints must be retained");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn passes_updates_without_max_age() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let ep0 = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let (tx, rx) = mpsc::unbounded::<Result<_, Infallible>>();
+    let mut resolution = StaleResolution::new(rx, None);
+    let add = Update::Add(vec![(ep0, Metadata::default())]);
+    tx.unbounded_send(Ok(add.clone())).unwrap();
+    assert_eq!(resolution.next().await.unwrap().unwrap(), add);
+
+    time::timeout(MAX_AGE * 10, resolution.next())
+        .await
+        .expect_err("endpoints must not be evicted");
+}
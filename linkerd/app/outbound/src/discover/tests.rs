@@ -1,7 +1,9 @@
 use super::*;
 use crate::{tcp, test_util::*};
 use linkerd_app_core::{
-    io, profiles,
+    io,
+    metrics::FmtMetrics,
+    profiles,
     svc::{NewService, Service, ServiceExt},
     transport::addrs::OrigDstAddr,
     AddrMatch, IpNet,
@@ -169,6 +171,273 @@ async fn caches_profiles_until_idle() {
     task2.abort();
 }
 
+/// Tests that the discover stack re-resolves profiles once they reach the
+/// configured maximum lifetime, even while they are continuously in use, and
+/// that connections using the prior resolution are not disrupted.
+#[tokio::test(flavor = "current_thread")]
+async fn reresolves_profiles_after_max_lifetime() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause(); // Run the test with a mocked clock.
+
+    let addr = SocketAddr::new([192, 0, 2, 22].into(), 5552);
+    let max_lifetime = time::Duration::from_secs(10);
+
+    // Mock an inner stack with a service that never returns, so that each
+    // connection holds its resolution.
+    let stack = |_: _| svc::mk(move |_: io::DuplexStream| future::pending::<Result<(), Error>>());
+
+    let profile_lookups = Arc::new(AtomicUsize::new(0));
+    let profiles = {
+        let profile = support::profile::resolver().profile(addr, profiles::Profile::default());
+        let lookups = profile_lookups.clone();
+        svc::mk(move |a: profiles::LookupAddr| {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            profile.clone().oneshot(a)
+        })
+    };
+
+    // The idle timeout is longer than the test, so that resolutions are only
+    // replaced due to their age.
+    let cfg = {
+        let mut cfg = default_config();
+        cfg.discovery_idle_timeout = max_lifetime * 10;
+        cfg.discovery_max_lifetime = Some(max_lifetime);
+        cfg
+    };
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(cfg, rt)
+        .with_stack(stack)
+        .push_discover(profiles)
+        .into_inner();
+
+    // Keep a connection open for the duration of the test.
+    let task0 = spawn_conn(stack.new_service(tcp::Accept::from(OrigDstAddr(addr))));
+    time::advance(time::Duration::from_millis(100)).await;
+    assert_eq!(
+        profile_lookups.load(Ordering::SeqCst),
+        1,
+        "exactly one profile lookup"
+    );
+
+    // New connections share the resolution until it reaches its maximum
+    // lifetime.
+    let task1 = spawn_conn(stack.new_service(tcp::Accept::from(OrigDstAddr(addr))));
+    time::advance(time::Duration::from_millis(100)).await;
+    assert_eq!(
+        profile_lookups.load(Ordering::SeqCst),
+        1,
+        "exactly one profile lookup"
+    );
+
+    // Once the resolution reaches its maximum lifetime, new connections
+    // re-resolve the profile even though it is still in use.
+    time::sleep(max_lifetime).await;
+    let task2 = spawn_conn(stack.new_service(tcp::Accept::from(OrigDstAddr(addr))));
+    time::advance(time::Duration::from_millis(100)).await;
+    assert_eq!(
+        profile_lookups.load(Ordering::SeqCst),
+        2,
+        "second profile lookup after max lifetime"
+    );
+
+    // Connections that use the prior resolution are not disrupted.
+    assert!(!task0.is_finished(), "connection must not be dropped");
+    assert!(!task1.is_finished(), "connection must not be dropped");
+
+    task0.abort();
+    task1.abort();
+    task2.abort();
+}
+
+/// Tests that evicting a cached profile causes the next service for its
+/// address to re-resolve the profile, without disrupting connections that use
+/// the prior resolution.
+#[tokio::test(flavor = "current_thread")]
+async fn evicted_profiles_are_reresolved() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause(); // Run the test with a mocked clock.
+
+    let addr = SocketAddr::new([192, 0, 2, 22].into(), 5553);
+
+    let stack = |_: _| svc::mk(move |_: io::DuplexStream| future::pending::<Result<(), Error>>());
+
+    let profile_lookups = Arc::new(AtomicUsize::new(0));
+    let profiles = {
+        let profile = support::profile::resolver().profile(addr, profiles::Profile::default());
+        let lookups = profile_lookups.clone();
+        svc::mk(move |a: profiles::LookupAddr| {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            profile.clone().oneshot(a)
+        })
+    };
+
+    let (rt, _shutdown) = runtime();
+    let outbound = Outbound::new(default_config(), rt);
+    let evictions = outbound.discovery_evictions();
+    let stack = outbound
+        .with_stack(stack)
+        .push_discover(profiles)
+        .into_inner();
+
+    let task0 = spawn_conn(stack.new_service(tcp::Accept::from(OrigDstAddr(addr))));
+    time::advance(time::Duration::from_millis(100)).await;
+    assert_eq!(
+        profile_lookups.load(Ordering::SeqCst),
+        1,
+        "exactly one profile lookup"
+    );
+
+    let lookup = profiles::LookupAddr(addr.into());
+    assert!(evictions.evict(&lookup), "profile must be evicted");
+    assert!(
+        !evictions.evict(&lookup),
+        "profile must not be evicted twice"
+    );
+
+    let task1 = spawn_conn(stack.new_service(tcp::Accept::from(OrigDstAddr(addr))));
+    time::advance(time::Duration::from_millis(100)).await;
+    assert_eq!(
+        profile_lookups.load(Ordering::SeqCst),
+        2,
+        "second profile lookup after eviction"
+    );
+    assert!(!task0.is_finished(), "connection must not be dropped");
+
+    task0.abort();
+    task1.abort();
+}
+
+/// Tests that discovery event subscribers are notified when profiles are
+/// resolved and when they are evicted from the cache after idling out.
+#[tokio::test(flavor = "current_thread")]
+async fn publishes_profile_events() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause(); // Run the test with a mocked clock.
+
+    let addr = SocketAddr::new([192, 0, 2, 22].into(), 5551);
+    let idle_timeout = time::Duration::from_secs(1);
+
+    let stack = |_: _| svc::mk(move |_: io::DuplexStream| future::pending::<Result<(), Error>>());
+    let profiles = support::profile::resolver().profile(addr, profiles::Profile::default());
+
+    let cfg = {
+        let mut cfg = default_config();
+        cfg.discovery_idle_timeout = idle_timeout;
+        cfg
+    };
+    let (rt, _shutdown) = runtime();
+    let outbound = Outbound::new(cfg, rt);
+    let mut events = outbound.discovery_events().subscribe();
+    let stack = outbound
+        .with_stack(stack)
+        .push_discover(profiles)
+        .into_inner();
+
+    let svc = stack.new_service(tcp::Accept::from(OrigDstAddr(addr)));
+    let task = spawn_conn(svc);
+    time::advance(time::Duration::from_millis(100)).await;
+    let lookup = profiles::LookupAddr(addr.into());
+    assert_eq!(
+        events.try_recv().expect("profile must be resolved"),
+        DiscoveryEvent::ProfileResolved(lookup.clone()),
+    );
+
+    // The profile is not evicted while its service is in use.
+    time::sleep(idle_timeout * 2).await;
+    assert!(events.try_recv().is_err(), "profile must not be evicted");
+
+    task.abort();
+    let evicted = time::timeout(idle_timeout * 2, events.recv())
+        .await
+        .expect("profile must be evicted after idling out")
+        .unwrap();
+    assert_eq!(evicted, DiscoveryEvent::ProfileEvicted(lookup));
+}
+
+/// Tests that the discover stack uses profiles discovered by a custom
+/// [`Resolver`].
+#[tokio::test(flavor = "current_thread")]
+async fn custom_resolver() {
+    use linkerd_app_core::proxy::{
+        api_resolve::{ConcreteAddr, Metadata},
+        core::{Resolve, Update},
+    };
+
+    /// Discovers a fixed profile and endpoint for all destinations.
+    #[derive(Clone, Debug)]
+    struct StaticResolver {
+        endpoint: SocketAddr,
+        lookups: Arc<AtomicUsize>,
+    }
+
+    impl Resolver for StaticResolver {
+        fn get_profile(&self, _: profiles::LookupAddr) -> ProfileFuture {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            let profile = support::profile::only(profiles::Profile {
+                endpoint: Some((self.endpoint, Metadata::default())),
+                ..Default::default()
+            });
+            Box::pin(future::ok(Some(profile)))
+        }
+
+        fn resolve_endpoints(&self, _: ConcreteAddr) -> EndpointsFuture {
+            let update = Update::Reset(vec![(self.endpoint, Metadata::default())]);
+            let updates: EndpointUpdates = Box::pin(futures::stream::iter([Ok(update)]));
+            Box::pin(future::ok(updates))
+        }
+    }
+
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let addr = SocketAddr::new([192, 0, 2, 22].into(), 2225);
+    let endpoint = SocketAddr::new([192, 0, 2, 23].into(), 2225);
+    let lookups = Arc::new(AtomicUsize::new(0));
+    let resolver = StaticResolver {
+        endpoint,
+        lookups: lookups.clone(),
+    };
+
+    // Mock an inner stack with a service that asserts that the custom
+    // resolver's profile is discovered.
+    let stack = move |d: Discovery<_>| {
+        let profile = d.profile.expect("profile must resolve");
+        assert_eq!(
+            profile.endpoint().map(|(addr, _)| addr),
+            Some(endpoint),
+            "profile must be discovered by the custom resolver"
+        );
+        svc::mk(move |_: io::DuplexStream| future::ok::<(), Error>(()))
+    };
+
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt)
+        .with_stack(stack)
+        .push_discover(resolver.clone().profiles())
+        .into_inner();
+
+    let svc = stack.new_service(tcp::Accept::from(OrigDstAddr(addr)));
+    spawn_conn(svc).await.unwrap().expect("must not fail");
+    assert_eq!(
+        lookups.load(Ordering::SeqCst),
+        1,
+        "exactly one profile lookup"
+    );
+
+    // The same resolver resolves endpoints for the balancer stacks.
+    let mut updates = resolver
+        .endpoints()
+        .resolve(ConcreteAddr("foo.ns.svc.cluster.local:80".parse().unwrap()))
+        .await
+        .expect("resolution must succeed");
+    assert_eq!(
+        futures::StreamExt::next(&mut updates)
+            .await
+            .unwrap()
+            .unwrap(),
+        Update::Reset(vec![(endpoint, Metadata::default())]),
+    );
+}
+
 /// Tests that the discover stack avoids resolutions when the stack is not configured to permit
 /// resolutions.
 #[tokio::test(flavor = "current_thread")]
@@ -213,6 +482,106 @@ async fn no_profiles_when_outside_search_nets() {
     spawn_conn(svc).await.unwrap().expect("must not fail");
 }
 
+/// Tests that lookups that complete without a profile are counted separately
+/// from lookups that fail.
+#[tokio::test(flavor = "current_thread")]
+async fn counts_lookups_without_profiles() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let addr = SocketAddr::new([192, 0, 2, 22].into(), 2223);
+    let profiles = support::profile::resolver().no_profile(addr);
+
+    let stack = |d: Discovery<_>| {
+        assert!(d.profile.is_none(), "profile must not resolve");
+        svc::mk(move |_: io::DuplexStream| future::ok::<(), Error>(()))
+    };
+
+    let (rt, _shutdown) = runtime();
+    let outbound = Outbound::new(default_config(), rt);
+    let metrics = outbound.metrics();
+    let stack = outbound
+        .with_stack(stack)
+        .push_discover(profiles)
+        .into_inner();
+
+    let svc = stack.new_service(tcp::Accept::from(OrigDstAddr(addr)));
+    spawn_conn(svc).await.unwrap().expect("must not fail");
+
+    let metrics = metrics.as_display().to_string();
+    for (result, count) in [("profile", 0), ("no_profile", 1), ("error", 0)] {
+        let sample = format!(
+            "outbound_profile_lookups_total{{result=\"{}\"}} {}",
+            result, count
+        );
+        assert!(
+            metrics.lines().any(|l| l == sample),
+            "{} not found in:\n{}",
+            sample,
+            metrics
+        );
+    }
+}
+
+/// Tests that connections that must wait for capacity in a destination's
+/// discovery queue are counted.
+#[tokio::test(flavor = "current_thread")]
+async fn counts_discovery_backpressure() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause(); // Run the test with a mocked clock.
+
+    // The discovery cache's queue holds this many discoveries for each
+    // destination.
+    const QUEUE_CAPACITY: usize = 10;
+
+    let addr = SocketAddr::new([192, 0, 2, 22].into(), 2224);
+    // Lookups never complete, so discoveries remain queued.
+    let profiles = svc::mk(|_: profiles::LookupAddr| {
+        future::pending::<Result<Option<profiles::Receiver>, Error>>()
+    });
+
+    let stack = |_: Discovery<_>| svc::mk(move |_: io::DuplexStream| future::ok::<(), Error>(()));
+
+    let (rt, _shutdown) = runtime();
+    let outbound = Outbound::new(default_config(), rt);
+    let metrics = outbound.metrics();
+    let stack = outbound
+        .with_stack(stack)
+        .push_discover(profiles)
+        .into_inner();
+    let backpressure = |count: usize| {
+        let sample = format!("outbound_discover_backpressure_total {}", count);
+        let metrics = metrics.as_display().to_string();
+        assert!(
+            metrics.lines().any(|l| l == sample),
+            "{} not found in:\n{}",
+            sample,
+            metrics
+        );
+    };
+
+    // Fill the destination's discovery queue.
+    let mut conns = (0..QUEUE_CAPACITY)
+        .map(|_| spawn_conn(stack.new_service(tcp::Accept::from(OrigDstAddr(addr)))))
+        .collect::<Vec<_>>();
+    time::advance(time::Duration::from_millis(100)).await;
+    backpressure(0);
+
+    // Additional connections must wait for capacity.
+    conns.extend(
+        (0..2).map(|_| spawn_conn(stack.new_service(tcp::Accept::from(OrigDstAddr(addr))))),
+    );
+    time::advance(time::Duration::from_millis(100)).await;
+    backpressure(2);
+
+    // Each waiting discovery is only counted once.
+    time::advance(time::Duration::from_secs(1)).await;
+    backpressure(2);
+
+    for conn in conns {
+        conn.abort();
+    }
+}
+
 fn spawn_conn<S>(mut svc: S) -> tokio::task::JoinHandle<Result<(), Error>>
 where
     S: Service<io::DuplexStream, Response = (), Error = Error> + Send + 'static,
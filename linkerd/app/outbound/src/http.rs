@@ -16,16 +16,47 @@ use linkerd_app_core::{
 use std::{fmt::Debug, hash::Hash};
 
 mod concrete;
+pub(crate) mod connection_limit;
 mod endpoint;
+mod endpoint_pin;
+mod fair_queue;
+mod filter_trailers;
+mod grpc_status;
+mod health_check;
+mod inject_faults;
+mod latency_outlier;
 mod logical;
 mod proxy_connection_close;
+mod request_coalescing;
 mod require_id_header;
+mod response_body_limit;
+mod response_cache;
 mod retry;
+mod rewrite_authority;
 mod server;
 mod strip_proxy_error;
+mod translate_version;
 
-pub use self::logical::Logical;
-pub(crate) use self::require_id_header::IdentityRequired;
+pub use self::{
+    concrete::BalancePolicy,
+    connection_limit::{ConnectionLimitConfig, ConnectionLimitMode},
+    filter_trailers::{TrailerFilter, TrailerPattern},
+    grpc_status::GrpcStatusMapping,
+    health_check::HealthCheckConfig,
+    inject_faults::{FaultRatio, InjectAbort, InjectDelay, RouteFaults},
+    latency_outlier::{EjectionBackoff, LatencyOutlierConfig},
+    logical::{BackendFallback, HeaderBackends, Logical, RouteConfig},
+    request_coalescing::RequestCoalescingConfig,
+    response_body_limit::ResponseBodyLimitMode,
+    response_cache::ResponseCacheConfig,
+    retry::ResetBehavior,
+};
+pub(crate) use self::{
+    fair_queue::{QueueFull, RequestPriority},
+    request_coalescing::Coalesced,
+    require_id_header::{IdentityRequired, MtlsRequired, RequireMtls},
+    response_cache::CacheHit,
+};
 pub use linkerd_app_core::proxy::http::{self as http, *};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
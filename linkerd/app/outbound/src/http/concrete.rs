@@ -1,8 +1,21 @@
 //! A stack that (optionally) resolves a service to a set of endpoint replicas
 //! and distributes HTTP requests among them.
 
-use super::{balance, client, normalize_uri};
-use crate::{http, stack_labels, Outbound};
+use super::{
+    balance, client,
+    connection_limit::BackendConnections,
+    endpoint_pin::{EndpointPin, NewPinEndpoints, NewPinnedBalance},
+    fair_queue::NewFairQueue,
+    health_check::NewHealthCheck,
+    latency_outlier::NewLatencyOutlierDetection,
+    normalize_uri,
+};
+use crate::{
+    discover::{EvictStaleEndpoints, ExcludeEndpoints, ObserveResolve, StabilizeEndpoints},
+    http,
+    metrics::stack_layer::StackLayer,
+    stack_labels, Outbound,
+};
 use linkerd_app_core::{
     metrics, profiles,
     proxy::{
@@ -18,6 +31,9 @@ use linkerd_app_core::{
 use std::{fmt::Debug, net::SocketAddr};
 use tracing::info_span;
 
+#[cfg(test)]
+mod tests;
+
 /// Parameter configuring dispatcher behavior.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Dispatch {
@@ -25,6 +41,24 @@ pub enum Dispatch {
     Forward(Remote<ServerAddr>, Metadata),
 }
 
+/// Determines how a backend's requests are distributed among its endpoints.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BalancePolicy {
+    /// Requests are sent to the less loaded of two randomly chosen endpoints,
+    /// by peak-EWMA latency.
+    #[default]
+    PeakEwma,
+
+    /// Requests are sent to endpoints in a deterministic order, in proportion
+    /// to the weights discovered for them.
+    WeightedRoundRobin,
+
+    /// Requests are sent to endpoints by the hash of their path and the values
+    /// of a set of headers, so that requests with the same key are sent to the
+    /// same endpoint, e.g. for the cache locality of caching backends.
+    CompositeKeyHash(balance::CompositeKey),
+}
+
 /// Wraps errors encountered in this module.
 #[derive(Debug, thiserror::Error)]
 #[error("concrete service {addr}: {source}")]
@@ -40,6 +74,7 @@ pub struct Endpoint<T> {
     addr: Remote<ServerAddr>,
     is_local: bool,
     metadata: Metadata,
+    connections: Option<BackendConnections>,
     parent: T,
 }
 
@@ -48,6 +83,9 @@ pub struct Endpoint<T> {
 struct Balance<T> {
     addr: NameAddr,
     ewma: balance::EwmaConfig,
+    policy: BalancePolicy,
+    connections: Option<BackendConnections>,
+    pin: EndpointPin,
     parent: T,
 }
 
@@ -92,12 +130,29 @@ impl<N> Outbound<N> {
         NSvc::Error: Into<Error>,
         NSvc::Future: Send,
     {
-        let resolve =
-            svc::MapTargetLayer::new(|t: Balance<T>| -> ConcreteAddr { ConcreteAddr(t.addr) })
-                .layer(resolve.into_service());
-
         self.map_stack(|config, rt, inner| {
+            // Publishes endpoint updates to discovery event subscribers,
+            // omitting endpoints whose metadata excludes them from balancers,
+            // evicting endpoints that become stale, and deferring removals
+            // while the resolution stabilizes.
+            let resolve = ExcludeEndpoints::new(resolve, config.endpoint_exclusions.clone());
+            let resolve = EvictStaleEndpoints::new(resolve, config.endpoint_staleness);
+            let resolve = StabilizeEndpoints::new(resolve, config.endpoint_stabilization_window);
+            let resolve =
+                svc::MapTargetLayer::new(|t: Balance<T>| -> ConcreteAddr { ConcreteAddr(t.addr) })
+                    .layer(ObserveResolve::new(resolve, rt.discovery_events.clone()));
+
             let inbound_ips = config.inbound_ips.clone();
+            let connection_limit = config.http_backend_connection_limit;
+            let queue = config.http_request_queue;
+            let fair_queue = config.http_request_queue_fair;
+            let prioritized = config
+                .http_routes
+                .values()
+                .flat_map(|routes| routes.values())
+                .any(|route| route.priority.is_some());
+            let endpoint_pins = rt.endpoint_pins.clone();
+            let balancers = config.http_backend_balancers.clone();
 
             let forward = inner
                 .clone()
@@ -110,6 +165,9 @@ impl<N> Outbound<N> {
                 .instrument(|e: &Endpoint<T>| info_span!("forward", addr = %e.addr));
 
             let endpoint = inner
+                // Exclude endpoints from the balancer while they fail active
+                // health checks, if configured.
+                .push(NewHealthCheck::layer(config.http_health_check.clone()))
                 .push_on_service(
                     rt.metrics
                         .proxy
@@ -118,7 +176,7 @@ impl<N> Outbound<N> {
                 )
                 .instrument(|e: &Endpoint<T>| info_span!("endpoint", addr = %e.addr));
 
-            let balance = endpoint
+            let endpoints = endpoint
                 .push_map_target({
                     let inbound_ips = inbound_ips.clone();
                     move |((addr, metadata), target): ((SocketAddr, Metadata), Balance<T>)| {
@@ -128,14 +186,57 @@ impl<N> Outbound<N> {
                             addr: Remote(ServerAddr(addr)),
                             metadata,
                             is_local,
+                            // All of a balancer's endpoints share its
+                            // connection limit.
+                            connections: target.connections.clone(),
                             parent: target.parent,
                         }
                     }
                 })
                 .lift_new_with_target()
+                // Eject endpoints whose tail latency is an outlier among the
+                // balancer's endpoints, if configured.
+                .push(NewLatencyOutlierDetection::layer(
+                    config.http_latency_outlier_detection.clone(),
+                ))
+                // Exclude all but the pinned endpoint while the backend is
+                // pinned via the admin server.
+                .push(NewPinEndpoints::layer());
+
+            let hashed = endpoints
+                .clone()
+                .push(http::NewBalanceHashed::layer(resolve.clone()))
+                .push_on_service(http::BoxResponse::layer());
+
+            let weighted = endpoints
+                .clone()
+                .push(http::NewBalanceWeightedRoundRobin::layer(resolve.clone()))
+                .push_on_service(http::BoxResponse::layer())
+                .push_switch(
+                    |t: Balance<T>| -> Result<_, Infallible> {
+                        Ok(match t.policy {
+                            BalancePolicy::CompositeKeyHash(_) => svc::Either::B(t),
+                            _ => svc::Either::A(t),
+                        })
+                    },
+                    hashed.into_inner(),
+                );
+
+            let balance = endpoints
                 .push(http::NewBalancePeakEwma::layer(resolve))
-                .push(svc::NewMapErr::layer_from_target::<ConcreteError, _>())
                 .push_on_service(http::BoxResponse::layer())
+                .push_switch(
+                    |t: Balance<T>| -> Result<_, Infallible> {
+                        Ok(match t.policy {
+                            BalancePolicy::PeakEwma => svc::Either::A(t),
+                            BalancePolicy::WeightedRoundRobin
+                            | BalancePolicy::CompositeKeyHash(_) => svc::Either::B(t),
+                        })
+                    },
+                    weighted.into_inner(),
+                )
+                .push(NewPinnedBalance::layer())
+                .push(svc::NewMapErr::layer_from_target::<ConcreteError, _>())
                 .push_on_service(
                     rt.metrics
                         .proxy
@@ -147,16 +248,24 @@ impl<N> Outbound<N> {
             balance
                 .push_switch(
                     move |parent: T| -> Result<_, Infallible> {
+                        // Each backend is limited independently.
+                        let connections = connection_limit.map(BackendConnections::new);
                         Ok(match parent.param() {
-                            Dispatch::Balance(addr, ewma) => {
-                                svc::Either::A(Balance { addr, ewma, parent })
-                            }
+                            Dispatch::Balance(addr, ewma) => svc::Either::A(Balance {
+                                pin: EndpointPin::new(&endpoint_pins, addr.clone()),
+                                policy: balancers.get(&addr).cloned().unwrap_or_default(),
+                                addr,
+                                ewma,
+                                connections,
+                                parent,
+                            }),
                             Dispatch::Forward(addr, metadata) => svc::Either::B({
                                 let is_local = inbound_ips.contains(&addr.ip());
                                 Endpoint {
                                     is_local,
                                     addr,
                                     metadata,
+                                    connections,
                                     parent,
                                 }
                             }),
@@ -164,7 +273,21 @@ impl<N> Outbound<N> {
                     },
                     forward.into_inner(),
                 )
-                .push(svc::NewQueue::layer_via(config.http_request_queue))
+                .push_on_service(rt.metrics.request_queue_wait.to_dispatch_layer())
+                // Queue requests for each backend, dispatching them
+                // round-robin across clients and by route priority, if
+                // configured.
+                .push(svc::layer::mk(move |inner| {
+                    if fair_queue {
+                        svc::Either::A(NewFairQueue::new(inner, queue))
+                    } else if prioritized {
+                        svc::Either::A(NewFairQueue::prioritized(inner, queue))
+                    } else {
+                        svc::Either::B(svc::NewQueue::new(inner, queue))
+                    }
+                }))
+                .push_on_service(rt.metrics.request_queue_wait.to_enqueue_layer())
+                .push(rt.metrics.stack_layers.to_layer(StackLayer::Balance))
                 .push(svc::ArcNewService::layer())
         })
     }
@@ -189,6 +312,27 @@ impl<T> svc::Param<http::balance::EwmaConfig> for Balance<T> {
     }
 }
 
+impl<T> svc::Param<ConcreteAddr> for Balance<T> {
+    fn param(&self) -> ConcreteAddr {
+        ConcreteAddr(self.addr.clone())
+    }
+}
+
+impl<T> svc::Param<balance::CompositeKey> for Balance<T> {
+    fn param(&self) -> balance::CompositeKey {
+        match &self.policy {
+            BalancePolicy::CompositeKeyHash(key) => key.clone(),
+            _ => balance::CompositeKey::default(),
+        }
+    }
+}
+
+impl<T> svc::Param<EndpointPin> for Balance<T> {
+    fn param(&self) -> EndpointPin {
+        self.pin.clone()
+    }
+}
+
 // === impl Endpoint ===
 
 impl<T> svc::Param<Remote<ServerAddr>> for Endpoint<T> {
@@ -197,6 +341,12 @@ impl<T> svc::Param<Remote<ServerAddr>> for Endpoint<T> {
     }
 }
 
+impl<T> svc::Param<Option<BackendConnections>> for Endpoint<T> {
+    fn param(&self) -> Option<BackendConnections> {
+        self.connections.clone()
+    }
+}
+
 impl<T> svc::Param<Option<crate::tcp::tagged_transport::PortOverride>> for Endpoint<T> {
     fn param(&self) -> Option<crate::tcp::tagged_transport::PortOverride> {
         if self.is_local {
@@ -0,0 +1,226 @@
+use super::*;
+use crate::{discover::EndpointExclusions, test_util::*};
+use futures::future;
+use linkerd_app_core::{
+    metrics::FmtMetrics,
+    svc::{NewService, ServiceExt},
+};
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+#[derive(Clone, Debug)]
+struct Target(NameAddr);
+
+impl svc::Param<Dispatch> for Target {
+    fn param(&self) -> Dispatch {
+        const EWMA: http::balance::EwmaConfig = http::balance::EwmaConfig {
+            default_rtt: Duration::from_millis(30),
+            decay: Duration::from_secs(10),
+        };
+        Dispatch::Balance(self.0.clone(), EWMA)
+    }
+}
+
+fn metadata(draining: bool) -> Metadata {
+    Metadata::new(
+        [("draining".to_string(), draining.to_string())],
+        ProtocolHint::Unknown,
+        None,
+        None,
+        None,
+    )
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn excludes_endpoints_by_metadata() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let backend = "backend.example.com:8080".parse::<NameAddr>().unwrap();
+    let ep0 = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let ep1 = SocketAddr::new([192, 0, 2, 31].into(), 8080);
+    let ep2 = SocketAddr::new([192, 0, 2, 32].into(), 8080);
+    let resolve = support::resolver::<Metadata>();
+    let mut resolve_tx = resolve.endpoint_tx(backend.clone());
+    resolve_tx
+        .add([
+            (ep0, metadata(true)),
+            (ep1, metadata(false)),
+            (ep2, metadata(false)),
+        ])
+        .unwrap();
+
+    // Requests are balanced round-robin so that each endpoint serves some.
+    let mut config = default_config();
+    config.endpoint_exclusions =
+        EndpointExclusions::new([("draining".to_string(), "true".to_string())]);
+    config.http_backend_balancers =
+        Arc::new(std::iter::once((backend.clone(), BalancePolicy::WeightedRoundRobin)).collect());
+    let (rt, _shutdown) = runtime();
+    let svc = Outbound::new(config, rt)
+        .with_stack(|ep: Endpoint<Target>| {
+            let Remote(ServerAddr(addr)) = svc::Param::param(&ep);
+            svc::mk(move |_: http::Request<http::BoxBody>| {
+                let rsp = http::Response::builder()
+                    .header("x-endpoint", addr.to_string())
+                    .body(http::BoxBody::default())
+                    .unwrap();
+                future::ok::<_, Error>(rsp)
+            })
+        })
+        .push_http_concrete(resolve)
+        .into_inner()
+        .new_service(Target(backend));
+
+    let mut endpoints = HashSet::new();
+    for _ in 0..100 {
+        let rsp = svc
+            .clone()
+            .oneshot(http::Request::new(http::BoxBody::default()))
+            .await
+            .expect("request must succeed");
+        endpoints.insert(rsp.headers()["x-endpoint"].to_str().unwrap().to_string());
+    }
+    assert_eq!(
+        endpoints,
+        [ep1.to_string(), ep2.to_string()].into_iter().collect(),
+        "draining endpoint must not be selected"
+    );
+}
+
+/// Tests that a backend balanced by a composite key sends requests with the
+/// same path and header values to the same endpoint.
+#[tokio::test(flavor = "current_thread")]
+async fn balances_by_composite_key() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let backend = "cache.example.com:8080".parse::<NameAddr>().unwrap();
+    let resolve = support::resolver::<Metadata>();
+    let mut resolve_tx = resolve.endpoint_tx(backend.clone());
+    resolve_tx
+        .add((30..34).map(|i| {
+            (
+                SocketAddr::new([192, 0, 2, i].into(), 8080),
+                metadata(false),
+            )
+        }))
+        .unwrap();
+
+    let mut config = default_config();
+    config.http_backend_balancers = Arc::new(
+        std::iter::once((
+            backend.clone(),
+            BalancePolicy::CompositeKeyHash(http::balance::CompositeKey::new([
+                http::HeaderName::from_static("x-tenant"),
+            ])),
+        ))
+        .collect(),
+    );
+    let (rt, _shutdown) = runtime();
+    let svc = Outbound::new(config, rt)
+        .with_stack(|ep: Endpoint<Target>| {
+            let Remote(ServerAddr(addr)) = svc::Param::param(&ep);
+            svc::mk(move |_: http::Request<http::BoxBody>| {
+                let rsp = http::Response::builder()
+                    .header("x-endpoint", addr.to_string())
+                    .body(http::BoxBody::default())
+                    .unwrap();
+                future::ok::<_, Error>(rsp)
+            })
+        })
+        .push_http_concrete(resolve)
+        .into_inner()
+        .new_service(Target(backend));
+
+    let send = |path: String, tenant: &'static str| {
+        let req = http::Request::builder()
+            .uri(path)
+            .header("x-tenant", tenant)
+            .body(http::BoxBody::default())
+            .unwrap();
+        let svc = svc.clone();
+        async move {
+            let rsp = svc.oneshot(req).await.expect("request must succeed");
+            rsp.headers()["x-endpoint"].to_str().unwrap().to_string()
+        }
+    };
+
+    // Wait for all of the endpoints to be discovered.
+    let mut discovered = HashSet::new();
+    for i in 0.. {
+        assert!(i < 1000, "all endpoints must be discovered");
+        discovered.insert(send(format!("/discover/{}", i), "a").await);
+        if discovered.len() == 4 {
+            break;
+        }
+    }
+
+    let mut endpoints = HashSet::new();
+    for i in 0..20 {
+        for tenant in ["a", "b"] {
+            let path = format!("/objects/{}", i);
+            let endpoint = send(path.clone(), tenant).await;
+            for _ in 0..5 {
+                assert_eq!(
+                    send(path.clone(), tenant).await,
+                    endpoint,
+                    "{} for tenant {} must be sent to the same endpoint",
+                    path,
+                    tenant
+                );
+            }
+            endpoints.insert(endpoint);
+        }
+    }
+    assert!(endpoints.len() > 1, "keys must be spread across endpoints");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn records_request_queue_wait() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let backend = "backend.example.com:8080".parse::<NameAddr>().unwrap();
+    let ep = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let resolve = support::resolver::<Metadata>();
+    let mut resolve_tx = resolve.endpoint_tx(backend.clone());
+    resolve_tx.add([(ep, metadata(false))]).unwrap();
+
+    let (rt, _shutdown) = runtime();
+    let outbound = Outbound::new(default_config(), rt);
+    let metrics = outbound.metrics();
+    // The endpoint serves one request at a time, so that the balancer is
+    // saturated while a request is in flight.
+    let svc = outbound
+        .with_stack(|_: Endpoint<Target>| {
+            svc::ConcurrencyLimitLayer::new(1).layer(svc::mk(
+                |_: http::Request<http::BoxBody>| async move {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    Ok::<_, Error>(http::Response::new(http::BoxBody::default()))
+                },
+            ))
+        })
+        .push_http_concrete(resolve)
+        .into_inner()
+        .new_service(Target(backend));
+
+    let (rsp0, rsp1) = tokio::join!(
+        svc.clone()
+            .oneshot(http::Request::new(http::BoxBody::default())),
+        svc.oneshot(http::Request::new(http::BoxBody::default())),
+    );
+    rsp0.expect("request must succeed");
+    rsp1.expect("request must succeed");
+
+    let metrics = metrics.as_display().to_string();
+    let sample = |name: &str| -> f64 {
+        metrics
+            .lines()
+            .find_map(|l| l.strip_prefix(name)?.strip_prefix(' '))
+            .unwrap_or_else(|| panic!("{name} must be reported:\n{metrics}"))
+            .parse()
+            .unwrap()
+    };
+    assert_eq!(sample("request_queue_wait_seconds_count"), 2.0);
+    assert!(
+        sample("request_queue_wait_seconds_sum") >= 1.0,
+        "the queued request must record its wait:\n{metrics}"
+    );
+}
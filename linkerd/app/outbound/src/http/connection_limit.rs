@@ -0,0 +1,216 @@
+//! Limits the number of connections established to each backend.
+//!
+//! When configured, each backend--i.e., each balancer or forwarded
+//! endpoint--is given a fixed number of connection permits that are shared by
+//! all of its endpoints. A permit is held for as long as a connection remains
+//! open. Once all permits are held, new connections either wait for a permit
+//! to be released or fail with a [`ConnectionLimitReached`] error, depending on
+//! the configured [`ConnectionLimitMode`].
+
+use linkerd_app_core::{
+    io,
+    svc::{self, ServiceExt},
+    Error,
+};
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+#[cfg(test)]
+mod tests;
+
+/// Configures the maximum number of connections to each backend.
+#[derive(Copy, Clone, Debug)]
+pub struct ConnectionLimitConfig {
+    /// The maximum number of open connections to a backend, across all of its
+    /// endpoints.
+    pub max_connections: usize,
+
+    /// Determines how connections are handled once the limit is reached.
+    pub mode: ConnectionLimitMode,
+}
+
+/// Determines how new connections are handled once a backend's connection
+/// limit is reached.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionLimitMode {
+    /// New connections wait for an open connection to be closed.
+    Queue,
+
+    /// New connections fail immediately.
+    Shed,
+}
+
+/// The connection permits shared by all endpoints of a single backend.
+#[derive(Clone, Debug)]
+pub struct BackendConnections {
+    permits: Arc<Semaphore>,
+    config: ConnectionLimitConfig,
+}
+
+#[derive(Clone, Debug)]
+pub struct LimitConnections<S> {
+    inner: S,
+}
+
+/// Holds a backend connection permit until the connection is dropped.
+#[pin_project]
+#[derive(Debug)]
+pub struct PermittedIo<I> {
+    #[pin]
+    io: I,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("backend connection limit of {0} reached")]
+pub struct ConnectionLimitReached(usize);
+
+type ConnectFuture<I, M> =
+    Pin<Box<dyn Future<Output = Result<(PermittedIo<I>, M), Error>> + Send + 'static>>;
+
+// === impl BackendConnections ===
+
+impl BackendConnections {
+    pub(crate) fn new(config: ConnectionLimitConfig) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(config.max_connections)),
+            config,
+        }
+    }
+
+    async fn acquire(self) -> Result<OwnedSemaphorePermit, ConnectionLimitReached> {
+        match self.config.mode {
+            ConnectionLimitMode::Queue => Ok(self
+                .permits
+                .acquire_owned()
+                .await
+                .expect("semaphore must not be closed")),
+            ConnectionLimitMode::Shed => self
+                .permits
+                .try_acquire_owned()
+                .map_err(|_| ConnectionLimitReached(self.config.max_connections)),
+        }
+    }
+}
+
+/// Backends are compared by identity, so that endpoints of distinct backends
+/// are never considered equal.
+impl PartialEq for BackendConnections {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.permits, &other.permits)
+    }
+}
+
+impl Eq for BackendConnections {}
+
+// === impl LimitConnections ===
+
+impl<S> LimitConnections<S> {
+    pub fn layer() -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, S, I, M> svc::Service<T> for LimitConnections<S>
+where
+    T: svc::Param<Option<BackendConnections>> + Send + 'static,
+    S: svc::Service<T, Response = (I, M)> + Clone + Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send,
+    I: Send + 'static,
+    M: Send + 'static,
+{
+    type Response = (PermittedIo<I>, M);
+    type Error = Error;
+    type Future = ConnectFuture<I, M>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        let connections = match target.param() {
+            Some(connections) => connections,
+            None => {
+                let connect = self.inner.call(target);
+                return Box::pin(async move {
+                    let (io, meta) = connect.await.map_err(Into::into)?;
+                    Ok((PermittedIo { io, _permit: None }, meta))
+                });
+            }
+        };
+
+        // The permit must be acquired before the connection is initiated, so
+        // the inner service is called once a permit is available.
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            let permit = connections.acquire().await?;
+            let (io, meta) = inner.oneshot(target).await.map_err(Into::into)?;
+            Ok((
+                PermittedIo {
+                    io,
+                    _permit: Some(permit),
+                },
+                meta,
+            ))
+        })
+    }
+}
+
+// === impl PermittedIo ===
+
+impl<I: io::AsyncRead> io::AsyncRead for PermittedIo<I> {
+    #[inline]
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut io::ReadBuf<'_>,
+    ) -> io::Poll<()> {
+        self.project().io.poll_read(cx, buf)
+    }
+}
+
+impl<I: io::AsyncWrite> io::AsyncWrite for PermittedIo<I> {
+    #[inline]
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> io::Poll<usize> {
+        self.project().io.poll_write(cx, buf)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        self.project().io.poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        self.project().io.poll_shutdown(cx)
+    }
+
+    #[inline]
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> io::Poll<usize> {
+        self.project().io.poll_write_vectored(cx, bufs)
+    }
+
+    #[inline]
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+}
+
+impl<I: io::PeerAddr> io::PeerAddr for PermittedIo<I> {
+    #[inline]
+    fn peer_addr(&self) -> io::Result<std::net::SocketAddr> {
+        self.io.peer_addr()
+    }
+}
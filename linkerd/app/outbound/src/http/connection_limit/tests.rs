@@ -0,0 +1,106 @@
+use super::*;
+use futures::FutureExt;
+use linkerd_app_core::svc::{Layer, Service};
+
+#[derive(Clone, Debug)]
+struct Target(Option<BackendConnections>);
+
+impl svc::Param<Option<BackendConnections>> for Target {
+    fn param(&self) -> Option<BackendConnections> {
+        self.0.clone()
+    }
+}
+
+fn backend(max_connections: usize, mode: ConnectionLimitMode) -> BackendConnections {
+    BackendConnections::new(ConnectionLimitConfig {
+        max_connections,
+        mode,
+    })
+}
+
+/// Builds a connector that establishes in-memory connections.
+fn connect(
+) -> impl svc::Service<Target, Response = (io::DuplexStream, ()), Error = Error, Future = impl Send>
+       + Clone {
+    svc::mk(|_: Target| async move {
+        let (client, _server) = io::duplex(64);
+        Ok::<_, Error>((client, ()))
+    })
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn queues_connections_beyond_limit() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let mut connect = LimitConnections::layer().layer(connect());
+    let target = Target(Some(backend(2, ConnectionLimitMode::Queue)));
+
+    let first = connect.ready().await.unwrap().call(target.clone()).await;
+    let first = first.expect("connection must be permitted");
+    let second = connect.ready().await.unwrap().call(target.clone()).await;
+    let _second = second.expect("connection must be permitted");
+
+    // The backend's permits are exhausted, so a third connection waits.
+    let mut third = connect.ready().await.unwrap().call(target.clone());
+    assert!(
+        (&mut third).now_or_never().is_none(),
+        "connections beyond the limit must wait"
+    );
+
+    // Other backends are limited independently.
+    let other = Target(Some(backend(2, ConnectionLimitMode::Queue)));
+    connect
+        .ready()
+        .await
+        .unwrap()
+        .call(other)
+        .await
+        .expect("connections to other backends must be permitted");
+
+    // Closing a connection releases its permit to the waiting connection.
+    drop(first);
+    third
+        .await
+        .expect("connection must be permitted once a permit is released");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn sheds_connections_beyond_limit() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let mut connect = LimitConnections::layer().layer(connect());
+    let target = Target(Some(backend(1, ConnectionLimitMode::Shed)));
+
+    let first = connect.ready().await.unwrap().call(target.clone()).await;
+    let first = first.expect("connection must be permitted");
+
+    let error = connect
+        .ready()
+        .await
+        .unwrap()
+        .call(target.clone())
+        .await
+        .expect_err("connections beyond the limit must fail");
+    assert!(error.is::<ConnectionLimitReached>(), "{}", error);
+
+    drop(first);
+    connect
+        .ready()
+        .await
+        .unwrap()
+        .call(target)
+        .await
+        .expect("connection must be permitted once a permit is released");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn unlimited_without_config() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let mut connect = LimitConnections::layer().layer(connect());
+    let mut connections = Vec::new();
+    for _ in 0..10 {
+        let conn = connect.ready().await.unwrap().call(Target(None)).await;
+        connections.push(conn.expect("connections must not be limited"));
+    }
+}
@@ -1,7 +1,10 @@
 //! A stack that sends requests to an HTTP endpoint.
 
-use super::{NewRequireIdentity, NewStripProxyError, ProxyConnectionClose};
-use crate::{tcp::tagged_transport, Outbound};
+use super::{
+    connection_limit::{BackendConnections, ConnectionLimitReached, LimitConnections},
+    NewRequireIdentity, NewStripProxyError, ProxyConnectionClose,
+};
+use crate::{metrics::stack_layer::StackLayer, tcp::tagged_transport, Outbound};
 use linkerd_app_core::{
     classify, config, errors, http_tracing, metrics,
     proxy::{http, tap},
@@ -32,6 +35,8 @@ pub struct EndpointError {
 #[derive(Copy, Clone, Debug)]
 struct ClientRescue {
     emit_headers: bool,
+    emit_json: bool,
+    emit_grpc_web: bool,
 }
 
 impl<C> Outbound<C> {
@@ -43,6 +48,7 @@ impl<C> Outbound<C> {
         T: svc::Param<Option<http::AuthorityOverride>>,
         T: svc::Param<metrics::EndpointLabels>,
         T: svc::Param<tls::ConditionalClientTls>,
+        T: svc::Param<Option<BackendConnections>>,
         T: tap::Inspect,
         T: Clone + Send + Sync + 'static,
         // Http endpoint body.
@@ -50,15 +56,17 @@ impl<C> Outbound<C> {
         B::Data: Send + 'static,
         // TCP endpoint stack.
         C: svc::MakeConnection<Connect<T>> + Clone + Send + Sync + Unpin + 'static,
-        C::Connection: Send + Unpin,
-        C::Metadata: Send + Unpin,
+        C::Connection: Send + Unpin + 'static,
+        C::Metadata: Send + Unpin + 'static,
         C::Future: Send + Unpin + 'static,
     {
         self.map_stack(|config, rt, inner| {
             let config::ConnectConfig {
                 h1_settings,
                 h2_settings,
+                h2_pool,
                 backoff,
+                backoff_max_elapsed,
                 ..
             } = config.proxy.connect;
 
@@ -67,15 +75,20 @@ impl<C> Outbound<C> {
             // HTTP/1.x fallback is supported as needed.
             svc::stack(inner.into_inner().into_service())
                 .check_service::<Connect<T>>()
+                // Limits the number of connections to the endpoint's backend,
+                // if configured.
+                .push(LimitConnections::layer())
+                // Records the time taken to establish each connection.
+                .push(rt.metrics.stack_layers.to_connect_layer())
                 .push_map_target(|(version, inner)| Connect { version, inner })
-                .push(http::client::layer(h1_settings, h2_settings))
+                .push(http::client::layer(h1_settings, h2_settings, h2_pool))
                 .push_on_service(svc::MapErr::layer_boxed())
                 .check_service::<T>()
                 .into_new_service()
                 // Drive the connection to completion regardless of whether the reconnect is being
                 // actively polled.
                 .push_on_service(svc::layer::mk(svc::SpawnReady::new))
-                .push_new_reconnect(backoff)
+                .push_new_reconnect(backoff, backoff_max_elapsed)
                 // Set the TLS status on responses so that the stack can detect whether the request
                 // was sent over a meshed connection.
                 .push_http_response_insert_target::<tls::ConditionalClientTls>()
@@ -91,7 +104,11 @@ impl<C> Outbound<C> {
                 // and metrics. HTTP error metrics are not incremented here so that errors are not
                 // double-counted--i.e., endpoint metrics track these responses and error metrics
                 // track proxy errors that occur higher in the stack.
-                .push(ClientRescue::layer(config.emit_headers))
+                .push(ClientRescue::layer(
+                    config.emit_headers,
+                    config.json_error_bodies,
+                    config.grpc_web_errors,
+                ))
                 .push(tap::NewTapHttp::layer(rt.tap.clone()))
                 .push(
                     rt.metrics
@@ -108,6 +125,7 @@ impl<C> Outbound<C> {
                     "host",
                     CANONICAL_DST_HEADER,
                 ]))
+                .push(rt.metrics.stack_layers.to_layer(StackLayer::Upstream))
                 .push_on_service(
                     svc::layers()
                         .push(http::BoxResponse::layer())
@@ -124,8 +142,14 @@ impl ClientRescue {
     /// Synthesizes responses for HTTP requests that encounter proxy errors.
     pub fn layer<N>(
         emit_headers: bool,
+        emit_json: bool,
+        emit_grpc_web: bool,
     ) -> impl svc::layer::Layer<N, Service = errors::NewRespondService<Self, Self, N>> + Clone {
-        errors::respond::layer(Self { emit_headers })
+        errors::respond::layer(Self {
+            emit_headers,
+            emit_json,
+            emit_grpc_web,
+        })
     }
 }
 
@@ -144,6 +168,20 @@ impl<T> ExtractParam<errors::respond::EmitHeaders, T> for ClientRescue {
     }
 }
 
+impl<T> ExtractParam<errors::respond::EmitJsonBody, T> for ClientRescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> errors::respond::EmitJsonBody {
+        errors::respond::EmitJsonBody(self.emit_json)
+    }
+}
+
+impl<T> ExtractParam<errors::respond::EmitGrpcWeb, T> for ClientRescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> errors::respond::EmitGrpcWeb {
+        errors::respond::EmitGrpcWeb(self.emit_grpc_web)
+    }
+}
+
 impl errors::HttpRescue<Error> for ClientRescue {
     fn rescue(&self, error: Error) -> Result<errors::SyntheticHttpResponse> {
         if errors::is_caused_by::<http::orig_proto::DowngradedH2Error>(&*error) {
@@ -155,6 +193,12 @@ impl errors::HttpRescue<Error> for ClientRescue {
         if errors::is_caused_by::<errors::ConnectTimeout>(&*error) {
             return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
         }
+        if errors::is_caused_by::<tls::client::HandshakeTimeout>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
+        }
+        if errors::is_caused_by::<ConnectionLimitReached>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::unavailable(error));
+        }
 
         Err(error)
     }
@@ -206,6 +250,15 @@ impl<T: svc::Param<Option<http::AuthorityOverride>>> svc::Param<Option<http::Aut
     }
 }
 
+impl<T: svc::Param<Option<BackendConnections>>> svc::Param<Option<BackendConnections>>
+    for Connect<T>
+{
+    #[inline]
+    fn param(&self) -> Option<BackendConnections> {
+        self.inner.param()
+    }
+}
+
 impl<T: svc::Param<transport::labels::Key>> svc::Param<transport::labels::Key> for Connect<T> {
     #[inline]
     fn param(&self) -> transport::labels::Key {
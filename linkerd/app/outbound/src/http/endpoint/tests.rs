@@ -257,6 +257,12 @@ impl svc::Param<tls::ConditionalClientTls> for Endpoint {
     }
 }
 
+impl svc::Param<Option<http::connection_limit::BackendConnections>> for Endpoint {
+    fn param(&self) -> Option<http::connection_limit::BackendConnections> {
+        None
+    }
+}
+
 impl svc::Param<Option<tcp::tagged_transport::PortOverride>> for Endpoint {
     fn param(&self) -> Option<tcp::tagged_transport::PortOverride> {
         None
@@ -0,0 +1,268 @@
+//! Pins a balancer's requests to a single endpoint.
+//!
+//! Pins are set per-backend via the admin server's [`EndpointPins`] registry.
+//! While a balancer's backend is pinned, its other endpoints do not advertise
+//! readiness, so all requests are dispatched to the pinned endpoint. If the
+//! pinned endpoint is not among the balancer's endpoints--i.e. because it was
+//! never discovered, or because it failed and was removed from the
+//! balancer--requests fail immediately instead of waiting for the endpoint to
+//! become available.
+
+use futures::{future, ready, FutureExt, TryFutureExt};
+use linkerd_app_core::{endpoint_pins::EndpointPins, svc, Error, NameAddr};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::sync::watch;
+
+#[cfg(test)]
+mod tests;
+
+/// The pin state shared by a balancer and its endpoints.
+#[derive(Clone, Debug)]
+pub struct EndpointPin {
+    pinned: watch::Receiver<Option<SocketAddr>>,
+    /// The number of live services for each of the balancer's endpoints.
+    endpoints: Arc<Mutex<HashMap<SocketAddr, usize>>>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("pinned endpoint {0} is unavailable")]
+pub struct PinnedEndpointUnavailable(SocketAddr);
+
+/// Fails requests when a balancer's pinned endpoint is unavailable.
+#[derive(Clone, Debug)]
+pub struct NewPinnedBalance<N> {
+    inner: N,
+}
+
+#[derive(Debug)]
+pub struct PinnedBalance<S> {
+    inner: S,
+    pin: EndpointPin,
+    unavailable: Option<SocketAddr>,
+}
+
+/// Wraps a balancer's endpoint stack so that its endpoints honor the
+/// balancer's pin.
+#[derive(Clone, Debug)]
+pub struct NewPinEndpoints<N> {
+    inner: N,
+}
+
+/// Builds endpoint services for a single balancer.
+#[derive(Clone, Debug)]
+pub struct PinEndpoints<N> {
+    inner: N,
+    pin: EndpointPin,
+}
+
+/// Advertises an endpoint's readiness only while the balancer is not pinned to
+/// another endpoint.
+pub struct Pinnable<S> {
+    inner: S,
+    addr: SocketAddr,
+    pin: EndpointPin,
+    changed: Option<Pin<Box<dyn Future<Output = ()> + Send + 'static>>>,
+}
+
+// === impl EndpointPin ===
+
+impl EndpointPin {
+    pub(crate) fn new(pins: &EndpointPins, backend: NameAddr) -> Self {
+        Self {
+            pinned: pins.watch(backend),
+            endpoints: Default::default(),
+        }
+    }
+
+    /// Returns true if the balancer is pinned to an endpoint that it does not
+    /// have.
+    ///
+    /// Until the balancer has endpoints, its endpoints may still be being
+    /// resolved, so the pinned endpoint is not considered unavailable.
+    fn unavailable(&self) -> Option<SocketAddr> {
+        let pinned = (*self.pinned.borrow())?;
+        let endpoints = self.endpoints.lock();
+        if endpoints.is_empty() || endpoints.contains_key(&pinned) {
+            return None;
+        }
+        Some(pinned)
+    }
+}
+
+impl PartialEq for EndpointPin {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.endpoints, &other.endpoints)
+    }
+}
+
+impl Eq for EndpointPin {}
+
+// === impl NewPinnedBalance ===
+
+impl<N> NewPinnedBalance<N> {
+    pub fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewPinnedBalance<N>
+where
+    T: svc::Param<EndpointPin>,
+    N: svc::NewService<T>,
+{
+    type Service = PinnedBalance<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        PinnedBalance {
+            pin: target.param(),
+            inner: self.inner.new_service(target),
+            unavailable: None,
+        }
+    }
+}
+
+// === impl PinnedBalance ===
+
+impl<Req, S> svc::Service<Req> for PinnedBalance<S>
+where
+    S: svc::Service<Req>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<
+        future::Ready<Result<S::Response, Error>>,
+        future::ErrInto<S::Future, Error>,
+    >;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.unavailable = self.pin.unavailable();
+        if self.unavailable.is_some() {
+            return Poll::Ready(Ok(()));
+        }
+
+        let poll = self.inner.poll_ready(cx).map_err(Into::into);
+        if poll.is_pending() {
+            // Polling the balancer may have removed the pinned endpoint.
+            self.unavailable = self.pin.unavailable();
+            if self.unavailable.is_some() {
+                return Poll::Ready(Ok(()));
+            }
+        }
+        poll
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        if let Some(addr) = self.unavailable.take() {
+            return future::Either::Left(future::err(PinnedEndpointUnavailable(addr).into()));
+        }
+        future::Either::Right(self.inner.call(req).err_into())
+    }
+}
+
+// === impl NewPinEndpoints ===
+
+impl<N> NewPinEndpoints<N> {
+    pub fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewPinEndpoints<N>
+where
+    T: svc::Param<EndpointPin>,
+    N: svc::NewService<T>,
+{
+    type Service = PinEndpoints<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        PinEndpoints {
+            pin: target.param(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl PinEndpoints ===
+
+impl<M, N> svc::NewService<(SocketAddr, M)> for PinEndpoints<N>
+where
+    N: svc::NewService<(SocketAddr, M)>,
+{
+    type Service = Pinnable<N::Service>;
+
+    fn new_service(&self, (addr, meta): (SocketAddr, M)) -> Self::Service {
+        *self.pin.endpoints.lock().entry(addr).or_default() += 1;
+        Pinnable {
+            inner: self.inner.new_service((addr, meta)),
+            addr,
+            pin: self.pin.clone(),
+            changed: None,
+        }
+    }
+}
+
+// === impl Pinnable ===
+
+impl<Req, S> svc::Service<Req> for Pinnable<S>
+where
+    S: svc::Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        loop {
+            let excluded = matches!(
+                *self.pin.pinned.borrow_and_update(),
+                Some(pinned) if pinned != self.addr
+            );
+            if !excluded {
+                self.changed = None;
+                break;
+            }
+
+            // Wait for the pin to change.
+            let changed = self.changed.get_or_insert_with(|| {
+                let mut pinned = self.pin.pinned.clone();
+                Box::pin(async move {
+                    if pinned.changed().await.is_err() {
+                        // The registry has been dropped, so the pin never
+                        // changes.
+                        future::pending::<()>().await;
+                    }
+                })
+            });
+            ready!(changed.poll_unpin(cx));
+            self.changed = None;
+        }
+
+        self.inner.poll_ready(cx)
+    }
+
+    #[inline]
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.inner.call(req)
+    }
+}
+
+impl<S> Drop for Pinnable<S> {
+    fn drop(&mut self) {
+        let mut endpoints = self.pin.endpoints.lock();
+        if let Some(n) = endpoints.get_mut(&self.addr) {
+            *n -= 1;
+            if *n == 0 {
+                endpoints.remove(&self.addr);
+            }
+        }
+    }
+}
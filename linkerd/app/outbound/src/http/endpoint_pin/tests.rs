@@ -0,0 +1,154 @@
+use super::*;
+use crate::{
+    http::{self, concrete},
+    test_util::*,
+    Outbound,
+};
+use linkerd_app_core::{
+    errors,
+    proxy::api_resolve::Metadata,
+    svc::{NewService, ServiceExt},
+    transport::addrs::*,
+};
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+#[derive(Clone, Debug)]
+struct Target(NameAddr);
+
+impl svc::Param<concrete::Dispatch> for Target {
+    fn param(&self) -> concrete::Dispatch {
+        const EWMA: http::balance::EwmaConfig = http::balance::EwmaConfig {
+            default_rtt: Duration::from_millis(30),
+            decay: Duration::from_secs(10),
+        };
+        concrete::Dispatch::Balance(self.0.clone(), EWMA)
+    }
+}
+
+/// Builds a balancer for `backend` whose endpoints identify themselves in their
+/// responses.
+fn balancer(
+    outbound: Outbound<()>,
+    resolve: support::resolver::Dst<Metadata>,
+    backend: NameAddr,
+) -> impl svc::Service<
+    http::Request<http::BoxBody>,
+    Response = http::Response<http::BoxBody>,
+    Error = Error,
+> + Clone {
+    outbound
+        .with_stack(|ep: concrete::Endpoint<Target>| {
+            let Remote(ServerAddr(addr)) = svc::Param::param(&ep);
+            svc::mk(move |_: http::Request<http::BoxBody>| {
+                let rsp = http::Response::builder()
+                    .header("x-endpoint", addr.to_string())
+                    .body(http::BoxBody::default())
+                    .unwrap();
+                future::ok::<_, Error>(rsp)
+            })
+        })
+        .push_http_concrete(resolve)
+        .into_inner()
+        .new_service(Target(backend))
+}
+
+/// Sends `n` requests, returning the endpoints that served them.
+async fn send<S>(svc: &S, n: usize) -> HashSet<String>
+where
+    S: svc::Service<
+            http::Request<http::BoxBody>,
+            Response = http::Response<http::BoxBody>,
+            Error = Error,
+        > + Clone,
+{
+    let mut endpoints = HashSet::new();
+    for _ in 0..n {
+        let rsp = svc
+            .clone()
+            .oneshot(http::Request::new(http::BoxBody::default()))
+            .await
+            .expect("request must succeed");
+        let endpoint = rsp.headers()["x-endpoint"].to_str().unwrap().to_string();
+        endpoints.insert(endpoint);
+    }
+    endpoints
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn routes_requests_to_pinned_endpoint() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let backend = "backend.example.com:8080".parse::<NameAddr>().unwrap();
+    let ep0 = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let ep1 = SocketAddr::new([192, 0, 2, 31].into(), 8080);
+    let resolve = support::resolver::<Metadata>();
+    let mut resolve_tx = resolve.endpoint_tx(backend.clone());
+    resolve_tx
+        .add([(ep0, Metadata::default()), (ep1, Metadata::default())])
+        .unwrap();
+
+    // Requests are balanced round-robin so that each endpoint serves some.
+    let mut config = default_config();
+    config.http_backend_balancers = Arc::new(
+        std::iter::once((backend.clone(), concrete::BalancePolicy::WeightedRoundRobin)).collect(),
+    );
+    let (rt, _shutdown) = runtime();
+    let outbound = Outbound::new(config, rt);
+    let pins = outbound.endpoint_pins();
+    let svc = balancer(outbound, resolve, backend.clone());
+
+    let endpoints = send(&svc, 100).await;
+    assert_eq!(
+        endpoints.len(),
+        2,
+        "requests must be balanced: {endpoints:?}"
+    );
+
+    // While the backend is pinned, all requests are sent to the pinned
+    // endpoint.
+    pins.pin(backend.clone(), ep1);
+    let endpoints = send(&svc, 100).await;
+    assert_eq!(endpoints, Some(ep1.to_string()).into_iter().collect());
+
+    assert_eq!(pins.unpin(&backend), Some(ep1));
+    let endpoints = send(&svc, 100).await;
+    assert_eq!(
+        endpoints.len(),
+        2,
+        "requests must be balanced: {endpoints:?}"
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn fails_when_pinned_endpoint_is_unavailable() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let backend = "backend.example.com:8080".parse::<NameAddr>().unwrap();
+    let ep0 = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let ep1 = SocketAddr::new([192, 0, 2, 31].into(), 8080);
+    let resolve = support::resolver::<Metadata>();
+    let mut resolve_tx = resolve.endpoint_tx(backend.clone());
+    resolve_tx
+        .add([(ep0, Metadata::default()), (ep1, Metadata::default())])
+        .unwrap();
+
+    let (rt, _shutdown) = runtime();
+    let outbound = Outbound::new(default_config(), rt);
+    let pins = outbound.endpoint_pins();
+    let svc = balancer(outbound, resolve, backend.clone());
+    send(&svc, 1).await;
+
+    // Once the pinned endpoint is removed, requests fail without waiting for
+    // it to become available.
+    pins.pin(backend, ep1);
+    resolve_tx.remove([ep1]).unwrap();
+    let error = svc
+        .clone()
+        .oneshot(http::Request::new(http::BoxBody::default()))
+        .await
+        .expect_err("request must fail");
+    assert!(
+        errors::is_caused_by::<PinnedEndpointUnavailable>(&*error),
+        "unexpected error: {error}"
+    );
+}
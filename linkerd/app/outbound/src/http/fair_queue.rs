@@ -0,0 +1,371 @@
+//! A backend request queue that dispatches requests fairly across clients.
+//!
+//! The default backend queue dispatches requests in the order in which they
+//! are queued, so a single client that floods a backend with requests can
+//! occupy the entire queue. Instead, a `FairQueue` holds a queue of requests
+//! for each client and, whenever the backend becomes ready, dispatches the next
+//! request from each client in turn.
+//!
+//! Outbound clients do not authenticate themselves, so clients are identified
+//! by their IP address.
+//!
+//! Requests may also be prioritized by their route. Each request's
+//! [`RequestPriority`] is a weight, and the queued requests of each priority are
+//! dispatched in proportion to their weights by smooth weighted round-robin, so
+//! that higher-priority requests are dispatched first without starving
+//! lower-priority requests. Requests without a priority have a weight of 1.
+//!
+//! The queue's capacity bounds the number of requests queued across all
+//! clients. Requests are failed with a [`QueueFull`] error once it is reached.
+
+use futures::{future, TryFutureExt};
+use linkerd_app_core::{
+    config::QueueConfig,
+    proxy::http::{self, ClientHandle},
+    svc::{self, Layer},
+    Error,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    net::IpAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::sync::{oneshot, Notify};
+use tracing::{debug, trace};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug)]
+pub struct NewFairQueue<N> {
+    inner: N,
+    config: QueueConfig,
+    fair_clients: bool,
+}
+
+/// A handle to a backend's fair queue. The queue is processed by a background
+/// task until all of its handles are dropped.
+pub struct FairQueue<B, Rsp> {
+    shared: Arc<Shared<B, Rsp>>,
+    _close: Arc<CloseOnDrop<B, Rsp>>,
+}
+
+/// Set as a request extension on routes with a configured priority.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct RequestPriority(pub u32);
+
+#[derive(Debug, thiserror::Error)]
+#[error("backend queue is full")]
+pub struct QueueFull(());
+
+#[derive(Debug, thiserror::Error)]
+#[error("backend queue closed")]
+pub struct QueueClosed(());
+
+type ResponseFuture<Rsp> = Pin<Box<dyn Future<Output = Result<Rsp, Error>> + Send + 'static>>;
+
+/// The key by which requests are queued. Requests without a client address
+/// share a queue.
+type ClientKey = Option<IpAddr>;
+
+struct Shared<B, Rsp> {
+    state: Mutex<State<B, Rsp>>,
+    notify: Notify,
+    capacity: usize,
+    /// When false, all clients' requests share a queue within each priority.
+    fair_clients: bool,
+}
+
+struct State<B, Rsp> {
+    /// The queued requests of each priority, by weight.
+    classes: HashMap<u32, Class<B, Rsp>>,
+    len: usize,
+    closed: bool,
+}
+
+/// The queued requests of a single priority.
+struct Class<B, Rsp> {
+    /// Each client's queued requests.
+    queues: HashMap<ClientKey, VecDeque<Pending<B, Rsp>>>,
+    /// Clients with queued requests, in the order in which they are served.
+    ready: VecDeque<ClientKey>,
+    /// The class's current weight for smooth weighted round-robin selection.
+    current: i64,
+}
+
+struct Pending<B, Rsp> {
+    req: http::Request<B>,
+    tx: oneshot::Sender<ResponseFuture<Rsp>>,
+}
+
+struct CloseOnDrop<B, Rsp>(Arc<Shared<B, Rsp>>);
+
+// === impl NewFairQueue ===
+
+impl<N> NewFairQueue<N> {
+    pub fn new(inner: N, config: QueueConfig) -> Self {
+        Self {
+            inner,
+            config,
+            fair_clients: true,
+        }
+    }
+
+    /// Returns a queue that dispatches requests by priority only, in the
+    /// order in which each priority's requests are queued.
+    pub fn prioritized(inner: N, config: QueueConfig) -> Self {
+        Self {
+            inner,
+            config,
+            fair_clients: false,
+        }
+    }
+}
+
+impl<T, N, S> svc::NewService<T> for NewFairQueue<N>
+where
+    N: svc::NewService<T, Service = S>,
+    S: svc::Service<http::Request<http::BoxBody>> + Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+    S::Response: Send + 'static,
+{
+    type Service = FairQueue<http::BoxBody, S::Response>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let inner = svc::FailFast::layer(self.config.failfast_timeout)
+            .layer(self.inner.new_service(target));
+        FairQueue::spawn(self.config.capacity, self.fair_clients, inner)
+    }
+}
+
+// === impl FairQueue ===
+
+impl<B, Rsp> FairQueue<B, Rsp>
+where
+    B: Send + 'static,
+    Rsp: Send + 'static,
+{
+    pub(crate) fn spawn<S>(capacity: usize, fair_clients: bool, inner: S) -> Self
+    where
+        S: svc::Service<http::Request<B>, Response = Rsp> + Send + 'static,
+        S::Error: Into<Error>,
+        S::Future: Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                classes: HashMap::new(),
+                len: 0,
+                closed: false,
+            }),
+            notify: Notify::new(),
+            capacity,
+            fair_clients,
+        });
+        tokio::spawn(Self::dispatch(shared.clone(), inner));
+        Self {
+            _close: Arc::new(CloseOnDrop(shared.clone())),
+            shared,
+        }
+    }
+
+    /// Dispatches queued requests to the inner service as it becomes ready.
+    async fn dispatch<S>(shared: Arc<Shared<B, Rsp>>, mut inner: S)
+    where
+        S: svc::Service<http::Request<B>, Response = Rsp> + Send + 'static,
+        S::Error: Into<Error>,
+        S::Future: Send + 'static,
+    {
+        loop {
+            // Wait for a request to be queued before driving the inner service
+            // to readiness.
+            loop {
+                {
+                    let state = shared.state.lock();
+                    if state.closed {
+                        trace!("Queue closed");
+                        return;
+                    }
+                    if state.len > 0 {
+                        break;
+                    }
+                }
+                shared.notify.notified().await;
+            }
+
+            if let Err(error) = future::poll_fn(|cx| inner.poll_ready(cx)).await {
+                let error: Error = error.into();
+                debug!(%error, "Backend failed");
+                // Pending requests are failed as their response channels are
+                // dropped.
+                let mut state = shared.state.lock();
+                state.closed = true;
+                state.classes.clear();
+                state.len = 0;
+                return;
+            }
+
+            // Dispatch the next request, skipping requests whose callers have
+            // given up waiting.
+            let next = {
+                let mut state = shared.state.lock();
+                let mut next = None;
+                while let Some(pending) = state.pop() {
+                    if !pending.tx.is_closed() {
+                        next = Some(pending);
+                        break;
+                    }
+                }
+                next
+            };
+            if let Some(Pending { req, tx }) = next {
+                let rsp = inner.call(req).err_into::<Error>();
+                let _ = tx.send(Box::pin(rsp));
+            }
+        }
+    }
+}
+
+impl<B, Rsp> svc::Service<http::Request<B>> for FairQueue<B, Rsp>
+where
+    B: Send + 'static,
+    Rsp: Send + 'static,
+{
+    type Response = Rsp;
+    type Error = Error;
+    type Future = ResponseFuture<Rsp>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let client = req
+            .extensions()
+            .get::<ClientHandle>()
+            .filter(|_| self.shared.fair_clients)
+            .map(|ClientHandle { addr, .. }| addr.ip());
+        let RequestPriority(weight) = req
+            .extensions()
+            .get::<RequestPriority>()
+            .copied()
+            .unwrap_or_default();
+
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut state = self.shared.state.lock();
+            if state.closed {
+                return Box::pin(future::err(QueueClosed(()).into()));
+            }
+            if state.len >= self.shared.capacity {
+                debug!(?client, capacity = self.shared.capacity, "Queue full");
+                return Box::pin(future::err(QueueFull(()).into()));
+            }
+            state.push(weight, client, Pending { req, tx });
+        }
+        self.shared.notify.notify_one();
+
+        Box::pin(
+            rx.map_err(|_| -> Error { QueueClosed(()).into() })
+                .and_then(|rsp| rsp),
+        )
+    }
+}
+
+impl<B, Rsp> Clone for FairQueue<B, Rsp> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            _close: self._close.clone(),
+        }
+    }
+}
+
+// === impl RequestPriority ===
+
+impl Default for RequestPriority {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+// === impl State ===
+
+impl<B, Rsp> State<B, Rsp> {
+    fn push(&mut self, weight: u32, client: ClientKey, pending: Pending<B, Rsp>) {
+        let class = self.classes.entry(weight.max(1)).or_insert_with(|| Class {
+            queues: HashMap::new(),
+            ready: VecDeque::new(),
+            current: 0,
+        });
+        class.push(client, pending);
+        self.len += 1;
+    }
+
+    /// Pops the next request from the priority whose turn it is.
+    fn pop(&mut self) -> Option<Pending<B, Rsp>> {
+        // Each priority's current weight is increased by its weight, and the
+        // priority with the greatest current weight is served and has its
+        // current weight reduced by the total weight.
+        let total = self.classes.keys().map(|w| i64::from(*w)).sum::<i64>();
+        let mut next = None;
+        for (weight, class) in self.classes.iter_mut() {
+            class.current += i64::from(*weight);
+            if next.map_or(true, |(_, current)| class.current > current) {
+                next = Some((*weight, class.current));
+            }
+        }
+        let (weight, _) = next?;
+
+        let class = self.classes.get_mut(&weight)?;
+        class.current -= total;
+        let pending = class.pop();
+        if class.ready.is_empty() {
+            self.classes.remove(&weight);
+        }
+        if pending.is_some() {
+            self.len -= 1;
+        }
+        pending
+    }
+}
+
+// === impl Class ===
+
+impl<B, Rsp> Class<B, Rsp> {
+    fn push(&mut self, client: ClientKey, pending: Pending<B, Rsp>) {
+        let queue = self.queues.entry(client).or_default();
+        if queue.is_empty() {
+            self.ready.push_back(client);
+        }
+        queue.push_back(pending);
+    }
+
+    /// Pops the next request from the client whose turn it is.
+    fn pop(&mut self) -> Option<Pending<B, Rsp>> {
+        let client = self.ready.pop_front()?;
+        let queue = self.queues.get_mut(&client)?;
+        let pending = queue.pop_front();
+        if queue.is_empty() {
+            self.queues.remove(&client);
+        } else {
+            // The client goes to the back of the line.
+            self.ready.push_back(client);
+        }
+        pending
+    }
+}
+
+// === impl CloseOnDrop ===
+
+impl<B, Rsp> Drop for CloseOnDrop<B, Rsp> {
+    fn drop(&mut self) {
+        self.0.state.lock().closed = true;
+        self.0.notify.notify_one();
+    }
+}
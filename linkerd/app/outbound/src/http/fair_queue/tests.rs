@@ -0,0 +1,136 @@
+use super::*;
+use linkerd_app_core::svc::Service;
+use std::net::SocketAddr;
+use tower_test::mock;
+
+type Req = http::Request<http::BoxBody>;
+type Rsp = http::Response<http::BoxBody>;
+
+fn request(client: SocketAddr) -> Req {
+    let (handle, _closed) = ClientHandle::new(client);
+    let mut req = http::Request::new(http::BoxBody::default());
+    req.extensions_mut().insert(handle);
+    req
+}
+
+fn prioritized(mut req: Req, weight: u32) -> Req {
+    req.extensions_mut().insert(RequestPriority(weight));
+    req
+}
+
+fn priority(req: &Req) -> u32 {
+    let RequestPriority(weight) = req
+        .extensions()
+        .get::<RequestPriority>()
+        .copied()
+        .unwrap_or_default();
+    weight
+}
+
+fn client(req: &Req) -> IpAddr {
+    req.extensions()
+        .get::<ClientHandle>()
+        .expect("requests must have a client handle")
+        .addr
+        .ip()
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn dispatches_round_robin_across_clients() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (inner, mut handle) = mock::pair::<Req, Rsp>();
+    handle.allow(0);
+    let mut queue = FairQueue::spawn(100, true, inner);
+
+    // One client floods the queue before another sends its requests.
+    let client0 = SocketAddr::new([192, 0, 2, 10].into(), 40000);
+    let client1 = SocketAddr::new([192, 0, 2, 11].into(), 40000);
+    let mut rsps = Vec::new();
+    for _ in 0..10 {
+        rsps.push(queue.call(request(client0)));
+    }
+    for _ in 0..3 {
+        rsps.push(queue.call(request(client1)));
+    }
+
+    // While both clients have queued requests, they take turns.
+    handle.allow(13);
+    let mut dispatched = Vec::new();
+    for _ in 0..13 {
+        let (req, send_rsp) = handle
+            .next_request()
+            .await
+            .expect("request must be dispatched");
+        dispatched.push(client(&req));
+        send_rsp.send_response(http::Response::default());
+    }
+    let (client0, client1) = (client0.ip(), client1.ip());
+    assert_eq!(
+        dispatched[..6],
+        [client0, client1, client0, client1, client0, client1]
+    );
+    assert!(dispatched[6..].iter().all(|c| *c == client0));
+
+    for rsp in rsps {
+        rsp.await.expect("request must succeed");
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn dispatches_by_priority() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    // The backend is saturated while requests are queued.
+    let (inner, mut handle) = mock::pair::<Req, Rsp>();
+    handle.allow(0);
+    let mut queue = FairQueue::spawn(100, false, inner);
+
+    // Low-priority requests are queued before high-priority requests.
+    let client = SocketAddr::new([192, 0, 2, 10].into(), 40000);
+    let mut rsps = Vec::new();
+    for _ in 0..5 {
+        rsps.push(queue.call(prioritized(request(client), 1)));
+    }
+    for _ in 0..5 {
+        rsps.push(queue.call(prioritized(request(client), 4)));
+    }
+
+    // As the backend becomes ready, high-priority requests are dispatched
+    // first, in proportion to their weight, without starving low-priority
+    // requests.
+    handle.allow(10);
+    let mut dispatched = Vec::new();
+    for _ in 0..10 {
+        let (req, send_rsp) = handle
+            .next_request()
+            .await
+            .expect("request must be dispatched");
+        dispatched.push(priority(&req));
+        send_rsp.send_response(http::Response::default());
+    }
+    assert_eq!(dispatched[..5], [4, 4, 1, 4, 4]);
+    assert_eq!(dispatched[5..], [4, 1, 1, 1, 1]);
+
+    for rsp in rsps {
+        rsp.await.expect("request must succeed");
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn fails_requests_when_full() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (inner, mut handle) = mock::pair::<Req, Rsp>();
+    handle.allow(0);
+    let mut queue = FairQueue::spawn(2, true, inner);
+
+    let client = SocketAddr::new([192, 0, 2, 10].into(), 40000);
+    let _rsp0 = queue.call(request(client));
+    let _rsp1 = queue.call(request(client));
+    let error = queue
+        .call(request(client))
+        .await
+        .expect_err("request must fail");
+    assert!(error.is::<QueueFull>(), "unexpected error: {error}");
+}
@@ -0,0 +1,241 @@
+//! Filters the trailers of responses forwarded to clients.
+//!
+//! Servers may emit trailers that are not intended for clients--e.g. internal
+//! `grpc-*-bin` debugging trailers. A [`TrailerFilter`] configures which
+//! response trailers are forwarded: when an allow list is set, only trailers
+//! that match it are forwarded, and trailers that match the deny list are
+//! always stripped. Patterns match trailer names case-insensitively, and `*`
+//! matches any sequence of characters.
+
+use crate::http::{
+    self,
+    header::{HeaderMap, HeaderName, HeaderValue},
+    HttpBody,
+};
+use futures::ready;
+use linkerd_app_core::svc;
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    str::FromStr,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tracing::trace;
+
+#[cfg(test)]
+mod tests;
+
+/// Determines which response trailers are forwarded to clients.
+///
+/// By default, all trailers are forwarded.
+#[derive(Clone, Debug)]
+pub struct TrailerFilter {
+    /// When set, only trailers matching one of these patterns are forwarded.
+    pub allow: Option<Arc<[TrailerPattern]>>,
+
+    /// Trailers matching any of these patterns are stripped.
+    pub deny: Arc<[TrailerPattern]>,
+}
+
+/// A trailer name pattern, in which `*` matches any sequence of characters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrailerPattern(Arc<str>);
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid trailer pattern: {0}")]
+pub struct InvalidTrailerPattern(String);
+
+#[derive(Clone, Debug)]
+pub(crate) struct FilterTrailers<S> {
+    inner: S,
+    filter: TrailerFilter,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    filter: Option<TrailerFilter>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct FilteredBody<B> {
+    #[pin]
+    inner: B,
+    filter: Option<TrailerFilter>,
+}
+
+// === impl TrailerFilter ===
+
+impl Default for TrailerFilter {
+    fn default() -> Self {
+        Self {
+            allow: None,
+            deny: Arc::new([]),
+        }
+    }
+}
+
+impl TrailerFilter {
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_none() && self.deny.is_empty()
+    }
+
+    fn forwards(&self, name: &HeaderName) -> bool {
+        let name = name.as_str();
+        if let Some(allow) = self.allow.as_ref() {
+            if !allow.iter().any(|p| p.matches(name)) {
+                return false;
+            }
+        }
+        !self.deny.iter().any(|p| p.matches(name))
+    }
+
+    fn filter(&self, trailers: HeaderMap) -> HeaderMap {
+        let mut filtered = HeaderMap::with_capacity(trailers.len());
+        let mut name = None;
+        for (n, value) in trailers {
+            // Subsequent values of a trailer are yielded without a name.
+            if n.is_some() {
+                name = n;
+            }
+            let name = name.as_ref().expect("first trailer must have a name");
+            if self.forwards(name) {
+                filtered.append(name.clone(), value);
+            } else {
+                trace!(trailer = %name, "Stripping trailer");
+            }
+        }
+        filtered
+    }
+}
+
+// === impl TrailerPattern ===
+
+impl TrailerPattern {
+    fn matches(&self, name: &str) -> bool {
+        let mut parts = self.0.split('*');
+        let mut rest = match name.strip_prefix(parts.next().unwrap_or_default()) {
+            Some(rest) => rest,
+            None => return false,
+        };
+        let parts = parts.collect::<Vec<_>>();
+        let (last, middle) = match parts.split_last() {
+            Some(split) => split,
+            // The pattern has no wildcards.
+            None => return rest.is_empty(),
+        };
+        for part in middle {
+            match rest.find(part) {
+                Some(idx) => rest = &rest[idx + part.len()..],
+                None => return false,
+            }
+        }
+        rest.ends_with(last)
+    }
+}
+
+impl FromStr for TrailerPattern {
+    type Err = InvalidTrailerPattern;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = s.trim().to_ascii_lowercase();
+        let literal = pattern.replace('*', "");
+        if pattern.is_empty() || (!literal.is_empty() && HeaderName::from_str(&literal).is_err()) {
+            return Err(InvalidTrailerPattern(s.to_string()));
+        }
+        Ok(Self(pattern.into()))
+    }
+}
+
+// === impl FilterTrailers ===
+
+impl<S> FilterTrailers<S> {
+    pub fn layer(filter: TrailerFilter) -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            inner,
+            filter: filter.clone(),
+        })
+    }
+}
+
+impl<S, Req, B> svc::Service<Req> for FilterTrailers<S>
+where
+    S: svc::Service<Req, Response = http::Response<B>>,
+{
+    type Response = http::Response<FilteredBody<B>>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        ResponseFuture {
+            inner: self.inner.call(req),
+            filter: Some(self.filter.clone()).filter(|f| !f.is_empty()),
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+{
+    type Output = Result<http::Response<FilteredBody<B>>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let rsp = ready!(this.inner.poll(cx))?;
+        let filter = this.filter.take();
+        Poll::Ready(Ok(rsp.map(|inner| FilteredBody { inner, filter })))
+    }
+}
+
+// === impl FilteredBody ===
+
+impl<B: HttpBody> HttpBody for FilteredBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    #[inline]
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        self.project().inner.poll_data(cx)
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap<HeaderValue>>, Self::Error>> {
+        let this = self.project();
+        let trailers = ready!(this.inner.poll_trailers(cx))?;
+        let filter = match this.filter.as_ref() {
+            Some(filter) => filter,
+            None => return Poll::Ready(Ok(trailers)),
+        };
+        // If all trailers are stripped, none are sent.
+        let trailers = trailers.map(|t| filter.filter(t)).filter(|t| !t.is_empty());
+        Poll::Ready(Ok(trailers))
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,121 @@
+use super::*;
+use bytes::Bytes;
+use linkerd_app_core::{svc::ServiceExt, Error};
+
+fn patterns(patterns: &[&str]) -> Arc<[TrailerPattern]> {
+    patterns.iter().map(|p| p.parse().unwrap()).collect()
+}
+
+/// Sends a request to a backend that responds with `trailers`, returning the
+/// trailers that are forwarded.
+async fn forwarded(filter: TrailerFilter, trailers: HeaderMap) -> Option<HeaderMap> {
+    let svc = svc::layer::Layer::layer(
+        &FilterTrailers::layer(filter),
+        svc::mk(move |_: http::Request<http::BoxBody>| {
+            let trailers = trailers.clone();
+            async move {
+                let (mut tx, body) = hyper::Body::channel();
+                tokio::spawn(async move {
+                    tx.send_data(Bytes::from_static(b"hello")).await?;
+                    tx.send_trailers(trailers).await
+                });
+                Ok::<_, Error>(http::Response::new(body))
+            }
+        }),
+    );
+    let mut body = svc
+        .oneshot(http::Request::new(http::BoxBody::default()))
+        .await
+        .expect("response must succeed")
+        .into_body();
+    while let Some(data) = body.data().await {
+        data.expect("body must succeed");
+    }
+    body.trailers().await.expect("trailers must succeed")
+}
+
+fn trailers() -> HeaderMap {
+    let mut trailers = HeaderMap::new();
+    trailers.insert("grpc-status", HeaderValue::from_static("0"));
+    trailers.insert("grpc-message", HeaderValue::from_static("ok"));
+    trailers.insert("grpc-debug-bin", HeaderValue::from_static("AAAA"));
+    trailers.insert("grpc-trace-bin", HeaderValue::from_static("AAAA"));
+    trailers.append("x-checksum", HeaderValue::from_static("a"));
+    trailers.append("x-checksum", HeaderValue::from_static("b"));
+    trailers
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn strips_denied_trailers() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let filter = TrailerFilter {
+        allow: None,
+        deny: patterns(&["grpc-*-bin"]),
+    };
+    let trailers = forwarded(filter, trailers())
+        .await
+        .expect("trailers must be forwarded");
+    assert!(trailers.get("grpc-debug-bin").is_none());
+    assert!(trailers.get("grpc-trace-bin").is_none());
+    assert_eq!(trailers["grpc-status"], "0");
+    assert_eq!(trailers["grpc-message"], "ok");
+    assert_eq!(
+        trailers.get_all("x-checksum").iter().collect::<Vec<_>>(),
+        ["a", "b"],
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn forwards_only_allowed_trailers() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let filter = TrailerFilter {
+        allow: Some(patterns(&["GRPC-*"])),
+        deny: patterns(&["grpc-debug-bin"]),
+    };
+    let trailers = forwarded(filter, trailers())
+        .await
+        .expect("trailers must be forwarded");
+    let mut names = trailers.keys().map(|n| n.as_str()).collect::<Vec<_>>();
+    names.sort_unstable();
+    assert_eq!(names, ["grpc-message", "grpc-status", "grpc-trace-bin"]);
+
+    // When all trailers are stripped, none are forwarded.
+    let filter = TrailerFilter {
+        allow: Some(patterns(&["x-*"])),
+        deny: patterns(&["*"]),
+    };
+    assert!(forwarded(filter, self::trailers()).await.is_none());
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn forwards_all_trailers_by_default() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let trailers = forwarded(TrailerFilter::default(), trailers())
+        .await
+        .expect("trailers must be forwarded");
+    assert_eq!(trailers, self::trailers());
+}
+
+#[test]
+fn matches_patterns() {
+    for (pattern, name, matches) in [
+        ("grpc-status", "grpc-status", true),
+        ("grpc-status", "grpc-status-details-bin", false),
+        ("grpc-*-bin", "grpc-status-details-bin", true),
+        ("grpc-*-bin", "grpc-bin", false),
+        ("*-bin", "x-debug-bin", true),
+        ("x-*", "x-debug-bin", true),
+        ("x-*", "grpc-status", false),
+        ("*", "grpc-status", true),
+        ("*debug*", "x-debug-bin", true),
+    ] {
+        let p = pattern.parse::<TrailerPattern>().unwrap();
+        assert_eq!(p.matches(name), matches, "{pattern} matching {name}");
+    }
+
+    assert!("".parse::<TrailerPattern>().is_err());
+    assert!("grpc status".parse::<TrailerPattern>().is_err());
+}
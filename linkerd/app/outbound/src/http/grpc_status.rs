@@ -0,0 +1,170 @@
+//! Maps gRPC statuses to HTTP statuses for clients that do not speak gRPC.
+//!
+//! Routes may translate HTTP/1 requests to a backend that speaks gRPC, but
+//! gRPC responses always have a `200 OK` status, so such clients cannot observe
+//! a failed call unless they inspect the `grpc-status`. When a route has a
+//! mapping configured, the status of a gRPC response to a non-gRPC request is
+//! set from its `grpc-status`.
+//!
+//! Only trailers-only responses--i.e. those that carry their `grpc-status` in
+//! the response headers--can be mapped, since a response's status has already
+//! been sent by the time its trailers are received. A `grpc-status` in a
+//! response's trailers is forwarded unchanged.
+
+use crate::http;
+use futures::ready;
+use linkerd_app_core::{errors::Grpc, svc};
+use pin_project::pin_project;
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::{Hash, Hasher},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tracing::debug;
+
+#[cfg(test)]
+mod tests;
+
+/// A route's mapping from the `grpc-status` of its gRPC responses to the HTTP
+/// statuses returned to clients that do not speak gRPC.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GrpcStatusMapping(Arc<HashMap<Grpc, http::StatusCode>>);
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewMapGrpcStatus<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct MapGrpcStatus<S> {
+    inner: S,
+    mapping: GrpcStatusMapping,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    mapping: Option<GrpcStatusMapping>,
+}
+
+// === impl GrpcStatusMapping ===
+
+impl From<HashMap<Grpc, http::StatusCode>> for GrpcStatusMapping {
+    fn from(mapping: HashMap<Grpc, http::StatusCode>) -> Self {
+        Self(Arc::new(mapping))
+    }
+}
+
+impl Hash for GrpcStatusMapping {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Equal maps may iterate in different orders, so entries are hashed
+        // in order of their gRPC codes.
+        let mut entries = self
+            .0
+            .iter()
+            .map(|(code, status)| (*code as i32, status.as_u16()))
+            .collect::<Vec<_>>();
+        entries.sort_unstable();
+        entries.hash(state);
+    }
+}
+
+// === impl NewMapGrpcStatus ===
+
+impl<N> NewMapGrpcStatus<N> {
+    pub fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewMapGrpcStatus<N>
+where
+    T: svc::Param<GrpcStatusMapping>,
+    N: svc::NewService<T>,
+{
+    type Service = MapGrpcStatus<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        MapGrpcStatus {
+            mapping: target.param(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl MapGrpcStatus ===
+
+impl<S, B, RspB> svc::Service<http::Request<B>> for MapGrpcStatus<S>
+where
+    S: svc::Service<http::Request<B>, Response = http::Response<RspB>>,
+{
+    type Response = http::Response<RspB>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let mapping = if self.mapping.0.is_empty() || is_grpc(req.headers()) {
+            None
+        } else {
+            Some(self.mapping.clone())
+        };
+        ResponseFuture {
+            inner: self.inner.call(req),
+            mapping,
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+{
+    type Output = Result<http::Response<B>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut rsp = ready!(this.inner.poll(cx))?;
+        if let Some(mapping) = this.mapping.take() {
+            if let Some(status) = grpc_status(&rsp).and_then(|code| mapping.0.get(&code)) {
+                debug!(?status, "Mapping gRPC status");
+                *rsp.status_mut() = *status;
+            }
+        }
+        Poll::Ready(Ok(rsp))
+    }
+}
+
+fn is_grpc(headers: &http::header::HeaderMap) -> bool {
+    headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|ct| ct.to_str().ok())
+        .map(|ct| ct.starts_with("application/grpc"))
+        .unwrap_or(false)
+}
+
+/// Returns the `grpc-status` of a trailers-only gRPC response.
+fn grpc_status<B>(rsp: &http::Response<B>) -> Option<Grpc> {
+    if !is_grpc(rsp.headers()) {
+        return None;
+    }
+    let code = rsp
+        .headers()
+        .get("grpc-status")?
+        .to_str()
+        .ok()?
+        .parse::<i32>()
+        .ok()?;
+    Some(Grpc::from_i32(code))
+}
@@ -0,0 +1,107 @@
+use super::*;
+use linkerd_app_core::{
+    svc::{Layer, NewService, ServiceExt},
+    Error,
+};
+
+fn mapping() -> GrpcStatusMapping {
+    [
+        (Grpc::NotFound, http::StatusCode::NOT_FOUND),
+        (Grpc::Unavailable, http::StatusCode::SERVICE_UNAVAILABLE),
+    ]
+    .into_iter()
+    .collect::<HashMap<_, _>>()
+    .into()
+}
+
+/// Builds a gRPC backend that responds with the `grpc-status` set in each
+/// request's path, in a trailers-only response.
+fn backend(
+    mapping: GrpcStatusMapping,
+) -> impl svc::Service<
+    http::Request<http::BoxBody>,
+    Response = http::Response<http::BoxBody>,
+    Error = Error,
+> {
+    let backend = svc::mk(|req: http::Request<http::BoxBody>| async move {
+        let code = req.uri().path().trim_start_matches('/').to_string();
+        let rsp = http::Response::builder()
+            .header(http::header::CONTENT_TYPE, "application/grpc")
+            .header("grpc-status", code)
+            .body(http::BoxBody::default())
+            .unwrap();
+        Ok::<_, Error>(rsp)
+    });
+    NewMapGrpcStatus::layer()
+        .layer(move |_: GrpcStatusMapping| backend.clone())
+        .new_service(mapping)
+}
+
+async fn status(mapping: GrpcStatusMapping, content_type: &str, code: Grpc) -> http::StatusCode {
+    let req = http::Request::post(format!("/{}", code as i32))
+        .header(http::header::CONTENT_TYPE, content_type)
+        .body(http::BoxBody::default())
+        .unwrap();
+    let rsp = backend(mapping).oneshot(req).await.unwrap();
+    rsp.status()
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn maps_grpc_statuses_for_http_clients() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    assert_eq!(
+        status(mapping(), "application/json", Grpc::NotFound).await,
+        http::StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        status(mapping(), "application/json", Grpc::Unavailable).await,
+        http::StatusCode::SERVICE_UNAVAILABLE
+    );
+
+    // Statuses without a mapping are not changed.
+    assert_eq!(
+        status(mapping(), "application/json", Grpc::Internal).await,
+        http::StatusCode::OK
+    );
+}
+
+#[test]
+fn mappings_hash_by_their_entries() {
+    use std::collections::hash_map::DefaultHasher;
+
+    let hash = |mapping: &GrpcStatusMapping| {
+        let mut hasher = DefaultHasher::new();
+        mapping.hash(&mut hasher);
+        hasher.finish()
+    };
+    let reversed = [
+        (Grpc::Unavailable, http::StatusCode::SERVICE_UNAVAILABLE),
+        (Grpc::NotFound, http::StatusCode::NOT_FOUND),
+    ]
+    .into_iter()
+    .collect::<HashMap<_, _>>()
+    .into();
+    assert_eq!(mapping(), reversed);
+    assert_eq!(hash(&mapping()), hash(&reversed));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn preserves_statuses_for_grpc_clients() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    assert_eq!(
+        status(mapping(), "application/grpc", Grpc::NotFound).await,
+        http::StatusCode::OK
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn preserves_statuses_without_mapping() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    assert_eq!(
+        status(Default::default(), "application/json", Grpc::NotFound).await,
+        http::StatusCode::OK
+    );
+}
@@ -0,0 +1,250 @@
+//! Actively probes balanced endpoints so that unhealthy endpoints are excluded
+//! from load balancing.
+//!
+//! When configured, a background task is spawned for each endpoint. The task
+//! periodically sends a `GET` request for the configured path through a
+//! dedicated client for the endpoint. After `unhealthy_threshold` consecutive
+//! failed probes, the endpoint's service stops advertising readiness so that the
+//! balancer routes requests to other endpoints; after `healthy_threshold`
+//! consecutive successful probes, it becomes ready again.
+
+use crate::http;
+use futures::{future, ready, FutureExt, TryFutureExt};
+use linkerd_app_core::{
+    svc::{self, ServiceExt},
+    transport::addrs::*,
+    Error,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Weak,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{sync::Notify, time};
+use tokio_util::sync::ReusableBoxFuture;
+use tracing::{debug, info, Instrument};
+
+#[cfg(test)]
+mod tests;
+
+/// Configures active health checks for balanced endpoints.
+#[derive(Clone, Debug)]
+pub struct HealthCheckConfig {
+    /// The path requested by each probe.
+    pub path: http::uri::PathAndQuery,
+
+    /// The time between probes.
+    pub interval: Duration,
+
+    /// The time after which a probe is considered failed.
+    pub timeout: Duration,
+
+    /// The number of consecutive successful probes after which an unhealthy
+    /// endpoint is considered healthy.
+    pub healthy_threshold: u32,
+
+    /// The number of consecutive failed probes after which a healthy endpoint
+    /// is considered unhealthy.
+    pub unhealthy_threshold: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewHealthCheck<N> {
+    inner: N,
+    config: Option<Arc<HealthCheckConfig>>,
+}
+
+/// Advertises an endpoint's readiness only while its health checks succeed.
+#[derive(Debug)]
+pub struct HealthCheck<S> {
+    inner: S,
+    health: Option<Arc<Health>>,
+
+    /// Is this service currently waiting on a notification that the endpoint
+    /// has become healthy?
+    is_waiting: bool,
+    waiting: ReusableBoxFuture<'static, ()>,
+}
+
+#[derive(Debug)]
+struct Health {
+    unhealthy: AtomicBool,
+    notify: Notify,
+}
+
+// === impl NewHealthCheck ===
+
+impl<N> NewHealthCheck<N> {
+    /// When `config` is `None`, endpoints are not probed.
+    pub fn layer(config: Option<HealthCheckConfig>) -> impl svc::layer::Layer<N, Service = Self> {
+        let config = config.map(Arc::new);
+        svc::layer::mk(move |inner| Self {
+            inner,
+            config: config.clone(),
+        })
+    }
+}
+
+impl<T, N, S> svc::NewService<T> for NewHealthCheck<N>
+where
+    T: svc::Param<Remote<ServerAddr>> + Clone,
+    N: svc::NewService<T, Service = S>,
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+    S: Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send,
+{
+    type Service = HealthCheck<S>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let health = self.config.clone().map(|config| {
+            let health = Arc::new(Health {
+                unhealthy: AtomicBool::new(false),
+                notify: Notify::new(),
+            });
+
+            // Probes use a dedicated client so that they do not compete with
+            // application traffic for the endpoint's readiness.
+            let Remote(ServerAddr(addr)) = target.param();
+            let probe = self.inner.new_service(target.clone());
+            tokio::spawn(
+                probe_endpoint(probe, addr, config, Arc::downgrade(&health))
+                    .instrument(tracing::debug_span!("health_check", %addr)),
+            );
+            health
+        });
+
+        HealthCheck::new(self.inner.new_service(target), health)
+    }
+}
+
+/// Probes the endpoint until its `HealthCheck` service is dropped.
+async fn probe_endpoint<S>(
+    mut svc: S,
+    addr: std::net::SocketAddr,
+    config: Arc<HealthCheckConfig>,
+    health: Weak<Health>,
+) where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+    S::Error: Into<Error>,
+{
+    let uri = match http::uri::Uri::builder()
+        .scheme(http::uri::Scheme::HTTP)
+        .authority(addr.to_string())
+        .path_and_query(config.path.clone())
+        .build()
+    {
+        Ok(uri) => uri,
+        Err(error) => {
+            tracing::warn!(%error, "Invalid health check URI");
+            return;
+        }
+    };
+
+    let mut interval = time::interval(config.interval);
+    let (mut successes, mut failures) = (0u32, 0u32);
+    loop {
+        interval.tick().await;
+        let health = match health.upgrade() {
+            Some(health) => health,
+            None => return,
+        };
+
+        let req = http::Request::get(uri.clone())
+            .body(http::BoxBody::default())
+            .expect("health check request must be valid");
+        let probe = svc.ready().err_into::<Error>().and_then(|svc| {
+            svc.call(req)
+                .err_into::<Error>()
+                .map_ok(|rsp| rsp.status().is_success())
+        });
+        let healthy = match time::timeout(config.timeout, probe).await {
+            Ok(Ok(healthy)) => healthy,
+            Ok(Err(error)) => {
+                debug!(%error, "Health check failed");
+                false
+            }
+            Err(_) => {
+                debug!(timeout = ?config.timeout, "Health check timed out");
+                false
+            }
+        };
+
+        if healthy {
+            successes = successes.saturating_add(1);
+            failures = 0;
+            if successes >= config.healthy_threshold
+                && health.unhealthy.swap(false, Ordering::AcqRel)
+            {
+                info!("Endpoint is healthy");
+                health.notify.notify_waiters();
+            }
+        } else {
+            failures = failures.saturating_add(1);
+            successes = 0;
+            if failures >= config.unhealthy_threshold
+                && !health.unhealthy.swap(true, Ordering::AcqRel)
+            {
+                info!("Endpoint is unhealthy");
+            }
+        }
+    }
+}
+
+// === impl HealthCheck ===
+
+impl<S> HealthCheck<S> {
+    fn new(inner: S, health: Option<Arc<Health>>) -> Self {
+        Self {
+            inner,
+            health,
+            is_waiting: false,
+            waiting: ReusableBoxFuture::new(future::pending()),
+        }
+    }
+}
+
+impl<S, Req> svc::Service<Req> for HealthCheck<S>
+where
+    S: svc::Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        loop {
+            let health = match self.health.as_ref() {
+                Some(health) if health.unhealthy.load(Ordering::Acquire) => health,
+                _ => {
+                    self.is_waiting = false;
+                    return self.inner.poll_ready(cx);
+                }
+            };
+
+            // Wait to be notified that the endpoint has become healthy.
+            if !self.is_waiting {
+                let health = health.clone();
+                self.waiting.set(async move {
+                    let notified = health.notify.notified();
+                    // The endpoint may have become healthy before the
+                    // notification was registered.
+                    if health.unhealthy.load(Ordering::Acquire) {
+                        notified.await;
+                    }
+                });
+                self.is_waiting = true;
+            }
+            ready!(self.waiting.poll_unpin(cx));
+            self.is_waiting = false;
+        }
+    }
+
+    #[inline]
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.inner.call(req)
+    }
+}
@@ -0,0 +1,99 @@
+use super::*;
+use linkerd_app_core::svc::{Layer, NewService};
+
+#[derive(Clone, Debug)]
+struct Target;
+
+impl svc::Param<Remote<ServerAddr>> for Target {
+    fn param(&self) -> Remote<ServerAddr> {
+        Remote(ServerAddr(([192, 0, 2, 10], 8080).into()))
+    }
+}
+
+fn config() -> HealthCheckConfig {
+    HealthCheckConfig {
+        path: "/healthz".parse().unwrap(),
+        interval: Duration::from_secs(1),
+        timeout: Duration::from_millis(100),
+        healthy_threshold: 2,
+        unhealthy_threshold: 2,
+    }
+}
+
+/// Builds an endpoint service that fails health checks unless `probe_ok` is
+/// set.
+fn endpoint(
+    probe_ok: Arc<AtomicBool>,
+) -> impl svc::Service<
+    http::Request<http::BoxBody>,
+    Response = http::Response<http::BoxBody>,
+    Error = Error,
+    Future = impl Send,
+> {
+    svc::mk(move |req: http::Request<http::BoxBody>| {
+        let status = if req.uri().path() == "/healthz" && !probe_ok.load(Ordering::Acquire) {
+            http::StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            http::StatusCode::OK
+        };
+        future::ok::<_, Error>(
+            http::Response::builder()
+                .status(status)
+                .body(http::BoxBody::default())
+                .unwrap(),
+        )
+    })
+}
+
+fn is_ready<S>(svc: &mut S) -> bool
+where
+    S: svc::Service<http::Request<http::BoxBody>>,
+{
+    future::poll_fn(|cx| svc.poll_ready(cx))
+        .now_or_never()
+        .is_some()
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn failing_probes_exclude_endpoint() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let probe_ok = Arc::new(AtomicBool::new(false));
+    let mut svc = NewHealthCheck::layer(Some(config()))
+        .layer({
+            let probe_ok = probe_ok.clone();
+            move |_: Target| endpoint(probe_ok.clone())
+        })
+        .new_service(Target);
+    assert!(
+        is_ready(&mut svc),
+        "endpoints must be ready before they are probed"
+    );
+
+    // Probes fail at t=0s and t=1s.
+    time::sleep(Duration::from_millis(1500)).await;
+    assert!(!is_ready(&mut svc), "unhealthy endpoints must not be ready");
+
+    // Probes succeed at t=2s and t=3s.
+    probe_ok.store(true, Ordering::Release);
+    time::sleep(Duration::from_millis(1000)).await;
+    assert!(
+        !is_ready(&mut svc),
+        "a single successful probe must not restore the endpoint"
+    );
+    time::sleep(Duration::from_millis(1000)).await;
+    assert!(is_ready(&mut svc), "healthy endpoints must be ready");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn disabled_without_config() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let mut svc = NewHealthCheck::layer(None)
+        .layer(|_: Target| endpoint(Arc::new(AtomicBool::new(false))))
+        .new_service(Target);
+    time::sleep(Duration::from_secs(10)).await;
+    assert!(is_ready(&mut svc));
+}
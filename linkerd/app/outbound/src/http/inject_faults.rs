@@ -0,0 +1,148 @@
+//! Injects faults into a fraction of each HTTP route's requests.
+//!
+//! Routes may be configured, for resilience testing, to delay a fraction of
+//! their requests before they are dispatched and to abort a fraction of their
+//! requests with a synthesized response. Delays are applied within the route's
+//! timeout, and aborted requests are not retried, so that injected faults are
+//! handled--and recorded in the route's metrics--as if the backend had been
+//! slow or failed.
+
+use crate::http;
+use futures::{future, TryFutureExt};
+use linkerd_app_core::{svc, Error};
+use rand::Rng;
+use std::{
+    task::{Context, Poll},
+    time::Duration,
+};
+use tracing::debug;
+
+#[cfg(test)]
+mod tests;
+
+/// The faults injected into a route's requests.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RouteFaults {
+    pub delay: Option<InjectDelay>,
+    pub abort: Option<InjectAbort>,
+}
+
+/// Delays a fraction of requests before they are dispatched.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InjectDelay {
+    pub delay: Duration,
+    pub ratio: FaultRatio,
+}
+
+/// Responds to a fraction of requests with an error status, without
+/// dispatching them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InjectAbort {
+    pub status: http::StatusCode,
+    pub ratio: FaultRatio,
+}
+
+/// The fraction of requests into which a fault is injected, in millionths.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FaultRatio(u32);
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewInjectFaults<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct InjectFaults<S> {
+    inner: S,
+    faults: RouteFaults,
+}
+
+const MILLION: u32 = 1_000_000;
+
+// === impl FaultRatio ===
+
+impl FaultRatio {
+    /// Returns a ratio for a fraction between 0 and 1.
+    pub fn from_fraction(fraction: f64) -> Option<Self> {
+        if !(0.0..=1.0).contains(&fraction) {
+            return None;
+        }
+        Some(Self((fraction * MILLION as f64).round() as u32))
+    }
+
+    fn sample(&self) -> bool {
+        self.0 > 0 && rand::thread_rng().gen_ratio(self.0, MILLION)
+    }
+}
+
+// === impl NewInjectFaults ===
+
+impl<N> NewInjectFaults<N> {
+    pub fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewInjectFaults<N>
+where
+    T: svc::Param<RouteFaults>,
+    N: svc::NewService<T>,
+{
+    type Service = InjectFaults<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        InjectFaults {
+            faults: target.param(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl InjectFaults ===
+
+impl<B, S> svc::Service<http::Request<B>> for InjectFaults<S>
+where
+    B: Send + 'static,
+    S: svc::Service<http::Request<B>, Response = http::Response<http::BoxBody>>,
+    S: Clone + Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = future::BoxFuture<'static, Result<Self::Response, Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let RouteFaults { delay, abort } = self.faults;
+        let delay = delay.filter(|d| d.ratio.sample()).map(|d| d.delay);
+        let abort = abort.filter(|a| a.ratio.sample()).map(|a| a.status);
+
+        if delay.is_none() && abort.is_none() {
+            return Box::pin(self.inner.call(req).err_into::<Error>());
+        }
+
+        // The ready service is held until the request is dispatched.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move {
+            if let Some(delay) = delay {
+                debug!(?delay, "Delaying request");
+                tokio::time::sleep(delay).await;
+            }
+            if let Some(status) = abort {
+                debug!(%status, "Aborting request");
+                let rsp = http::Response::builder()
+                    .status(status)
+                    .body(http::BoxBody::default())
+                    .expect("response must be valid");
+                return Ok(rsp);
+            }
+            inner.call(req).await.map_err(Into::into)
+        })
+    }
+}
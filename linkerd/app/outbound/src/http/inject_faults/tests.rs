@@ -0,0 +1,172 @@
+use super::*;
+use linkerd_app_core::svc::{Layer, NewService, ServiceExt};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use tokio::time::Instant;
+
+#[derive(Clone, Debug)]
+struct Target(RouteFaults);
+
+impl svc::Param<RouteFaults> for Target {
+    fn param(&self) -> RouteFaults {
+        self.0
+    }
+}
+
+const REQUESTS: usize = 1_000;
+
+/// Sends `REQUESTS` requests to a backend that responds immediately,
+/// returning the status and latency of each response and the number of
+/// requests the backend received.
+async fn send(faults: RouteFaults) -> (Vec<(http::StatusCode, Duration)>, usize) {
+    let dispatched = Arc::new(AtomicUsize::new(0));
+    let new_svc = NewInjectFaults::layer().layer({
+        let dispatched = dispatched.clone();
+        move |_: Target| {
+            let dispatched = dispatched.clone();
+            svc::mk(move |_: http::Request<http::BoxBody>| {
+                dispatched.fetch_add(1, Ordering::SeqCst);
+                future::ok::<_, Error>(http::Response::new(http::BoxBody::default()))
+            })
+        }
+    });
+
+    let mut responses = Vec::with_capacity(REQUESTS);
+    for _ in 0..REQUESTS {
+        let start = Instant::now();
+        let rsp = new_svc
+            .new_service(Target(faults))
+            .oneshot(http::Request::new(http::BoxBody::default()))
+            .await
+            .expect("request must not fail");
+        responses.push((
+            rsp.status(),
+            Instant::now().saturating_duration_since(start),
+        ));
+    }
+    (responses, dispatched.load(Ordering::SeqCst))
+}
+
+fn ratio(fraction: f64) -> FaultRatio {
+    FaultRatio::from_fraction(fraction).expect("fraction must be valid")
+}
+
+/// Asserts that roughly a quarter of the requests were faulted.
+fn assert_quarter(faulted: usize) {
+    assert!(
+        (150..=350).contains(&faulted),
+        "{} of {} requests were faulted",
+        faulted,
+        REQUESTS
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn delays_fraction_of_requests() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let delay = Duration::from_secs(1);
+    let (responses, dispatched) = send(RouteFaults {
+        delay: Some(InjectDelay {
+            delay,
+            ratio: ratio(0.25),
+        }),
+        abort: None,
+    })
+    .await;
+
+    assert_eq!(dispatched, REQUESTS, "all requests must be dispatched");
+    let delayed = responses
+        .iter()
+        .filter(|(status, latency)| {
+            assert_eq!(*status, http::StatusCode::OK);
+            match *latency {
+                latency if latency == delay => true,
+                latency if latency == Duration::ZERO => false,
+                latency => panic!("unexpected latency {:?}", latency),
+            }
+        })
+        .count();
+    assert_quarter(delayed);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn aborts_fraction_of_requests() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (responses, dispatched) = send(RouteFaults {
+        delay: None,
+        abort: Some(InjectAbort {
+            status: http::StatusCode::SERVICE_UNAVAILABLE,
+            ratio: ratio(0.25),
+        }),
+    })
+    .await;
+
+    let aborted = responses
+        .iter()
+        .filter(|(status, latency)| {
+            assert_eq!(*latency, Duration::ZERO);
+            match *status {
+                http::StatusCode::SERVICE_UNAVAILABLE => true,
+                http::StatusCode::OK => false,
+                status => panic!("unexpected status {}", status),
+            }
+        })
+        .count();
+    assert_quarter(aborted);
+    assert_eq!(
+        dispatched,
+        REQUESTS - aborted,
+        "aborted requests must not be dispatched"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn delays_aborted_requests() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let delay = Duration::from_millis(100);
+    let (responses, dispatched) = send(RouteFaults {
+        delay: Some(InjectDelay {
+            delay,
+            ratio: ratio(1.0),
+        }),
+        abort: Some(InjectAbort {
+            status: http::StatusCode::INTERNAL_SERVER_ERROR,
+            ratio: ratio(1.0),
+        }),
+    })
+    .await;
+
+    assert_eq!(dispatched, 0, "requests must not be dispatched");
+    for (status, latency) in responses {
+        assert_eq!(status, http::StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(latency, delay);
+    }
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn no_faults_by_default() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (responses, dispatched) = send(RouteFaults::default()).await;
+
+    assert_eq!(dispatched, REQUESTS);
+    for (status, latency) in responses {
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(latency, Duration::ZERO);
+    }
+}
+
+#[test]
+fn fault_ratios() {
+    assert_eq!(FaultRatio::from_fraction(0.0), Some(FaultRatio(0)));
+    assert_eq!(FaultRatio::from_fraction(0.5), Some(FaultRatio(500_000)));
+    assert_eq!(FaultRatio::from_fraction(1.0), Some(FaultRatio(MILLION)));
+    assert_eq!(FaultRatio::from_fraction(1.5), None);
+    assert_eq!(FaultRatio::from_fraction(-0.5), None);
+    assert_eq!(FaultRatio::from_fraction(f64::NAN), None);
+}
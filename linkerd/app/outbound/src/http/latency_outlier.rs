@@ -0,0 +1,375 @@
+//! Ejects balanced endpoints whose tail latency is an outlier.
+//!
+//! When configured, the response latency of each endpoint in a balancer is
+//! recorded over its most recent requests. Periodically, the p99 latency of
+//! each endpoint with enough recorded requests is compared to the median of
+//! these p99 latencies across the balancer's endpoints. Endpoints whose p99
+//! latency exceeds `latency_multiple` times the median are ejected from the
+//! balancer, slowest first, so long as no more than `max_ejection_percent` of
+//! the balancer's endpoints are ejected at once. Ejected endpoints do not
+//! advertise readiness, so the balancer routes requests to other endpoints.
+//!
+//! An endpoint that is ejected repeatedly is ejected for longer each time: its
+//! `n`th consecutive ejection lasts `base * multiplier^(n-1)`, up to `max`.
+//! An endpoint's consecutive ejections are reset once it is evaluated and found
+//! not to be an outlier. Each backend may override these ejection times.
+
+use crate::http;
+use futures::{ready, FutureExt};
+use linkerd_app_core::{proxy::api_resolve::ConcreteAddr, svc, NameAddr};
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Weak,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::{self, Instant};
+use tracing::{debug, info, Instrument};
+
+#[cfg(test)]
+mod tests;
+
+/// Configures latency-based outlier detection for balanced endpoints.
+#[derive(Clone, Debug)]
+pub struct LatencyOutlierConfig {
+    /// Endpoints whose p99 latency exceeds this multiple of the median p99
+    /// latency of all endpoints are ejected.
+    pub latency_multiple: f64,
+
+    /// The maximum percentage of a balancer's endpoints that may be ejected at
+    /// once. When nonzero, at least one endpoint may always be ejected.
+    pub max_ejection_percent: u32,
+
+    /// The time between outlier evaluations.
+    pub interval: Duration,
+
+    /// The amount of time for which an outlier is ejected.
+    pub ejection: EjectionBackoff,
+
+    /// Overrides the ejection times of each named backend.
+    pub backend_ejections: HashMap<NameAddr, EjectionBackoff>,
+
+    /// The number of requests that must be recorded for an endpoint before its
+    /// latency is evaluated.
+    pub min_requests: usize,
+}
+
+/// Configures the amount of time for which an outlier is ejected.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EjectionBackoff {
+    /// The amount of time for which an outlier is first ejected.
+    pub base: Duration,
+
+    /// Each consecutive ejection of an endpoint lasts this multiple of the
+    /// prior ejection.
+    pub multiplier: f64,
+
+    /// The maximum amount of time for which an outlier is ejected.
+    pub max: Duration,
+}
+
+/// The number of recent response latencies recorded for each endpoint.
+const MAX_SAMPLES: usize = 1_000;
+
+#[derive(Clone, Debug)]
+pub struct NewLatencyOutlierDetection<N> {
+    inner: N,
+    config: Option<Arc<LatencyOutlierConfig>>,
+}
+
+/// Builds endpoint services whose latencies are tracked for a single balancer.
+#[derive(Clone, Debug)]
+pub struct LatencyOutlierDetection<N> {
+    inner: N,
+    fleet: Option<Arc<Fleet>>,
+}
+
+/// Advertises an endpoint's readiness only while it is not ejected.
+#[derive(Debug)]
+pub struct Ejectable<S> {
+    inner: S,
+    stats: Option<Arc<Stats>>,
+    sleep: Option<Pin<Box<time::Sleep>>>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    recording: Option<(Arc<Stats>, Instant)>,
+}
+
+/// The endpoints of a single balancer.
+#[derive(Debug)]
+struct Fleet {
+    endpoints: Mutex<Vec<Weak<Stats>>>,
+    ejection: EjectionBackoff,
+}
+
+#[derive(Debug)]
+struct Stats {
+    addr: SocketAddr,
+    latencies: Mutex<VecDeque<Duration>>,
+    ejected_until: Mutex<Option<Instant>>,
+    /// The number of times the endpoint has been ejected since it was last
+    /// found not to be an outlier.
+    ejections: AtomicU32,
+}
+
+// === impl NewLatencyOutlierDetection ===
+
+impl<N> NewLatencyOutlierDetection<N> {
+    /// When `config` is `None`, endpoints are never ejected.
+    pub fn layer(
+        config: Option<LatencyOutlierConfig>,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        let config = config.map(Arc::new);
+        svc::layer::mk(move |inner| Self {
+            inner,
+            config: config.clone(),
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewLatencyOutlierDetection<N>
+where
+    T: svc::Param<ConcreteAddr>,
+    N: svc::NewService<T>,
+{
+    type Service = LatencyOutlierDetection<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let fleet = self.config.clone().map(|config| {
+            let ConcreteAddr(addr) = target.param();
+            let fleet = Arc::new(Fleet {
+                endpoints: Mutex::default(),
+                ejection: config
+                    .backend_ejections
+                    .get(&addr)
+                    .copied()
+                    .unwrap_or(config.ejection),
+            });
+            tokio::spawn(
+                detect_outliers(Arc::downgrade(&fleet), config)
+                    .instrument(tracing::debug_span!("latency_outliers")),
+            );
+            fleet
+        });
+        LatencyOutlierDetection {
+            inner: self.inner.new_service(target),
+            fleet,
+        }
+    }
+}
+
+/// Evaluates the balancer's endpoints until it is dropped.
+async fn detect_outliers(fleet: Weak<Fleet>, config: Arc<LatencyOutlierConfig>) {
+    let mut interval = time::interval(config.interval);
+    // The first tick completes immediately.
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        match fleet.upgrade() {
+            Some(fleet) => fleet.eject_outliers(&config),
+            None => return,
+        }
+    }
+}
+
+// === impl LatencyOutlierDetection ===
+
+impl<M, N> svc::NewService<(SocketAddr, M)> for LatencyOutlierDetection<N>
+where
+    N: svc::NewService<(SocketAddr, M)>,
+{
+    type Service = Ejectable<N::Service>;
+
+    fn new_service(&self, (addr, meta): (SocketAddr, M)) -> Self::Service {
+        let stats = self.fleet.as_ref().map(|fleet| {
+            let stats = Arc::new(Stats {
+                addr,
+                latencies: Mutex::new(VecDeque::with_capacity(MAX_SAMPLES)),
+                ejected_until: Mutex::new(None),
+                ejections: AtomicU32::new(0),
+            });
+            fleet.endpoints.lock().push(Arc::downgrade(&stats));
+            stats
+        });
+        Ejectable {
+            inner: self.inner.new_service((addr, meta)),
+            stats,
+            sleep: None,
+        }
+    }
+}
+
+// === impl Fleet ===
+
+impl Fleet {
+    fn eject_outliers(&self, config: &LatencyOutlierConfig) {
+        let endpoints = {
+            let mut endpoints = self.endpoints.lock();
+            endpoints.retain(|e| e.strong_count() > 0);
+            endpoints
+                .iter()
+                .filter_map(Weak::upgrade)
+                .collect::<Vec<_>>()
+        };
+
+        let now = Instant::now();
+        let mut ejected = endpoints.iter().filter(|e| e.is_ejected(now)).count();
+        let mut max_ejected = endpoints.len() * config.max_ejection_percent as usize / 100;
+        if config.max_ejection_percent > 0 {
+            max_ejected = max_ejected.max(1);
+        }
+
+        // Ejected endpoints receive no requests, so only endpoints that are in
+        // service are compared.
+        let mut p99s = endpoints
+            .iter()
+            .filter(|e| !e.is_ejected(now))
+            .filter_map(|e| Some((e.p99(config.min_requests)?, e)))
+            .collect::<Vec<_>>();
+        if p99s.len() < 2 {
+            return;
+        }
+        p99s.sort_by(|(a, _), (b, _)| b.cmp(a));
+        let median = p99s[p99s.len() / 2].0;
+        let threshold = median.mul_f64(config.latency_multiple);
+        for (p99, endpoint) in p99s.iter() {
+            if *p99 <= threshold {
+                endpoint.ejections.store(0, Ordering::Release);
+            }
+        }
+
+        for (p99, endpoint) in p99s {
+            if p99 <= threshold {
+                break;
+            }
+            if ejected >= max_ejected {
+                debug!(addr = %endpoint.addr, ?p99, ?median, "Too many endpoints ejected");
+                break;
+            }
+            let ejections = endpoint.ejections.fetch_add(1, Ordering::AcqRel) + 1;
+            let ejection_time = self.ejection.ejection_time(ejections);
+            info!(addr = %endpoint.addr, ?p99, ?median, ejections, ?ejection_time, "Ejecting latency outlier");
+            endpoint.eject(now + ejection_time);
+            ejected += 1;
+        }
+    }
+}
+
+// === impl EjectionBackoff ===
+
+impl EjectionBackoff {
+    /// Returns the duration of an endpoint's `ejections`th consecutive
+    /// ejection.
+    fn ejection_time(&self, ejections: u32) -> Duration {
+        let exp = ejections.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let secs = self.base.as_secs_f64() * self.multiplier.powi(exp);
+        if !secs.is_finite() || secs >= self.max.as_secs_f64() {
+            return self.max;
+        }
+        Duration::from_secs_f64(secs)
+    }
+}
+
+// === impl Stats ===
+
+impl Stats {
+    fn record(&self, latency: Duration) {
+        let mut latencies = self.latencies.lock();
+        if latencies.len() == MAX_SAMPLES {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    fn p99(&self, min_requests: usize) -> Option<Duration> {
+        let mut latencies = self.latencies.lock().iter().copied().collect::<Vec<_>>();
+        if latencies.is_empty() || latencies.len() < min_requests {
+            return None;
+        }
+        latencies.sort_unstable();
+        Some(latencies[(latencies.len() - 1) * 99 / 100])
+    }
+
+    fn is_ejected(&self, now: Instant) -> bool {
+        self.ejected_until
+            .lock()
+            .map(|until| until > now)
+            .unwrap_or(false)
+    }
+
+    fn eject(&self, until: Instant) {
+        *self.ejected_until.lock() = Some(until);
+        // The endpoint is evaluated on new requests once it is returned to
+        // service.
+        self.latencies.lock().clear();
+    }
+}
+
+// === impl Ejectable ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for Ejectable<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if let Some(stats) = self.stats.as_ref() {
+            loop {
+                let until = match *stats.ejected_until.lock() {
+                    Some(until) if until > Instant::now() => until,
+                    _ => break,
+                };
+                // Wait for the ejection to end.
+                match self.sleep.as_mut() {
+                    Some(sleep) if sleep.deadline() == until => {}
+                    Some(sleep) => sleep.as_mut().reset(until),
+                    None => self.sleep = Some(Box::pin(time::sleep_until(until))),
+                }
+                let sleep = self.sleep.as_mut().expect("sleep must be set");
+                ready!(sleep.poll_unpin(cx));
+            }
+        }
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        ResponseFuture {
+            inner: self.inner.call(req),
+            recording: self.stats.clone().map(|stats| (stats, Instant::now())),
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F, T, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    type Output = Result<T, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.inner.poll(cx));
+        if let (Ok(_), Some((stats, start))) = (res.as_ref(), this.recording.take()) {
+            stats.record(Instant::now().saturating_duration_since(start));
+        }
+        Poll::Ready(res)
+    }
+}
@@ -0,0 +1,229 @@
+use super::*;
+use futures::future;
+use linkerd_app_core::{
+    svc::{Layer, NewService, Service, ServiceExt},
+    Error,
+};
+
+fn slow_addr() -> SocketAddr {
+    ([192, 0, 2, 10], 8080).into()
+}
+
+fn config() -> LatencyOutlierConfig {
+    LatencyOutlierConfig {
+        latency_multiple: 3.0,
+        max_ejection_percent: 50,
+        interval: Duration::from_secs(10),
+        ejection: EjectionBackoff {
+            base: Duration::from_secs(30),
+            multiplier: 1.0,
+            max: Duration::from_secs(30),
+        },
+        backend_ejections: HashMap::new(),
+        min_requests: 10,
+    }
+}
+
+fn backend() -> ConcreteAddr {
+    ConcreteAddr("backend.example.com:8080".parse().unwrap())
+}
+
+/// Builds an endpoint service that responds after 100ms if it is slow and
+/// after 10ms otherwise.
+fn endpoint(
+    (addr, ()): (SocketAddr, ()),
+) -> impl svc::Service<
+    http::Request<http::BoxBody>,
+    Response = http::Response<http::BoxBody>,
+    Error = Error,
+> {
+    let latency = if addr == slow_addr() {
+        Duration::from_millis(100)
+    } else {
+        Duration::from_millis(10)
+    };
+    svc::mk(move |_: http::Request<http::BoxBody>| async move {
+        time::sleep(latency).await;
+        Ok::<_, Error>(http::Response::new(http::BoxBody::default()))
+    })
+}
+
+fn is_ready<S>(svc: &mut S) -> bool
+where
+    S: svc::Service<http::Request<http::BoxBody>>,
+{
+    future::poll_fn(|cx| svc.poll_ready(cx))
+        .now_or_never()
+        .is_some()
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn ejects_slow_endpoint() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let new_endpoint = NewLatencyOutlierDetection::layer(Some(config()))
+        .layer(|_: ConcreteAddr| endpoint)
+        .new_service(backend());
+    let fast = [11, 12, 13]
+        .iter()
+        .map(|i| SocketAddr::from(([192, 0, 2, *i], 8080)));
+    let mut endpoints = std::iter::once(slow_addr())
+        .chain(fast)
+        .map(|addr| (addr, new_endpoint.new_service((addr, ()))))
+        .collect::<Vec<_>>();
+
+    for (_, svc) in endpoints.iter_mut() {
+        for _ in 0..20 {
+            svc.ready()
+                .await
+                .unwrap()
+                .call(http::Request::new(http::BoxBody::default()))
+                .await
+                .unwrap();
+        }
+    }
+
+    // Wait for the outliers to be evaluated.
+    time::sleep(Duration::from_secs(10)).await;
+    for (addr, svc) in endpoints.iter_mut() {
+        if *addr == slow_addr() {
+            assert!(!is_ready(svc), "slow endpoints must be ejected");
+        } else {
+            assert!(is_ready(svc), "fast endpoints must not be ejected");
+        }
+    }
+
+    // Ejected endpoints are returned to service after the ejection time.
+    time::sleep(Duration::from_secs(30)).await;
+    for (_, svc) in endpoints.iter_mut() {
+        assert!(is_ready(svc));
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn respects_max_ejection_percent() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    // Only one of the three slow endpoints may be ejected.
+    let new_endpoint = NewLatencyOutlierDetection::layer(Some(LatencyOutlierConfig {
+        latency_multiple: 1.5,
+        max_ejection_percent: 25,
+        interval: Duration::from_secs(60),
+        ..config()
+    }))
+    .layer(|_: ConcreteAddr| {
+        |(addr, ()): (SocketAddr, ())| {
+            let latency = Duration::from_millis(u64::from(addr.port()));
+            svc::mk(move |_: http::Request<http::BoxBody>| async move {
+                time::sleep(latency).await;
+                Ok::<_, Error>(http::Response::new(http::BoxBody::default()))
+            })
+        }
+    })
+    .new_service(backend());
+    let mut endpoints = [10, 10, 10, 10, 100, 200, 300]
+        .iter()
+        .map(|ms| new_endpoint.new_service((SocketAddr::from(([192, 0, 2, 10], *ms)), ())))
+        .collect::<Vec<_>>();
+
+    for svc in endpoints.iter_mut() {
+        for _ in 0..20 {
+            svc.ready()
+                .await
+                .unwrap()
+                .call(http::Request::new(http::BoxBody::default()))
+                .await
+                .unwrap();
+        }
+    }
+
+    time::sleep(Duration::from_secs(60)).await;
+    let ready = endpoints.iter_mut().map(is_ready).collect::<Vec<_>>();
+    assert_eq!(
+        ready,
+        vec![true, true, true, true, true, true, false],
+        "only the slowest endpoint may be ejected"
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn backs_off_repeated_ejections() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let fleet = Fleet {
+        endpoints: Mutex::default(),
+        ejection: EjectionBackoff {
+            base: Duration::from_secs(10),
+            multiplier: 2.0,
+            max: Duration::from_secs(35),
+        },
+    };
+    let endpoints = [10, 11, 12]
+        .iter()
+        .map(|i| {
+            let stats = Arc::new(Stats {
+                addr: ([192, 0, 2, *i], 8080).into(),
+                latencies: Mutex::default(),
+                ejected_until: Mutex::new(None),
+                ejections: AtomicU32::new(0),
+            });
+            fleet.endpoints.lock().push(Arc::downgrade(&stats));
+            stats
+        })
+        .collect::<Vec<_>>();
+    let (slow, fast) = (&endpoints[0], &endpoints[1..]);
+
+    // Records latencies for each endpoint and evaluates them, returning the
+    // duration for which the slow endpoint is ejected.
+    let evaluate = |slow_latency: Duration| {
+        for _ in 0..10 {
+            slow.record(slow_latency);
+            for endpoint in fast {
+                endpoint.record(Duration::from_millis(10));
+            }
+        }
+        let now = Instant::now();
+        fleet.eject_outliers(&config());
+        slow.ejected_until
+            .lock()
+            .filter(|until| *until > now)
+            .map(|until| until.saturating_duration_since(now))
+    };
+
+    // Each consecutive ejection is longer than the last, up to the maximum.
+    let mut ejections = Vec::new();
+    for _ in 0..4 {
+        let ejection = evaluate(Duration::from_millis(100)).expect("endpoint must be ejected");
+        ejections.push(ejection);
+        time::sleep(ejection).await;
+    }
+    assert_eq!(
+        ejections,
+        [10, 20, 35, 35].map(Duration::from_secs),
+        "ejections must back off"
+    );
+
+    // Once the endpoint is no longer an outlier, its next ejection is reset to
+    // the base ejection time.
+    assert_eq!(evaluate(Duration::from_millis(10)), None);
+    assert_eq!(
+        evaluate(Duration::from_millis(100)),
+        Some(Duration::from_secs(10))
+    );
+}
+
+#[test]
+fn ejection_time_caps_at_max() {
+    let backoff = EjectionBackoff {
+        base: Duration::from_secs(1),
+        multiplier: 10.0,
+        max: Duration::from_secs(60),
+    };
+    assert_eq!(backoff.ejection_time(1), Duration::from_secs(1));
+    assert_eq!(backoff.ejection_time(2), Duration::from_secs(10));
+    assert_eq!(backoff.ejection_time(3), Duration::from_secs(60));
+    assert_eq!(backoff.ejection_time(u32::MAX), Duration::from_secs(60));
+}
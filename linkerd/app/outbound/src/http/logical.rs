@@ -1,7 +1,19 @@
 //! A stack that routes HTTP requests to concrete backends.
 
-use super::{concrete, retry};
-use crate::Outbound;
+use super::{
+    concrete,
+    grpc_status::{GrpcStatusMapping, NewMapGrpcStatus},
+    inject_faults::{NewInjectFaults, RouteFaults},
+    request_coalescing,
+    response_body_limit::{self, ResponseBodyLimit},
+    response_cache, retry,
+    rewrite_authority::{NewRewriteAuthority, RewriteAuthority},
+    translate_version, RequestPriority, RequireMtls,
+};
+use crate::{
+    metrics::{slo::LatencySlo, stack_layer::StackLayer},
+    Outbound,
+};
 use linkerd_app_core::{
     classify, metrics,
     profiles::{self, Profile},
@@ -14,9 +26,18 @@ use linkerd_app_core::{
     Error, Infallible, NameAddr, CANONICAL_DST_HEADER,
 };
 use linkerd_distribute as distribute;
-use std::{fmt::Debug, hash::Hash, sync::Arc, time};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+    sync::Arc,
+    time,
+};
 use tokio::sync::watch;
 
+#[cfg(test)]
+mod tests;
+
 #[derive(Clone, Debug)]
 pub enum Logical {
     Route(NameAddr, profiles::Receiver),
@@ -27,6 +48,71 @@ pub enum Logical {
 pub struct Concrete<T> {
     target: concrete::Dispatch,
     parent: T,
+
+    /// Overrides the parent's HTTP version for this backend, so that requests
+    /// are translated to the protocol the backend speaks.
+    version: Option<http::Version>,
+}
+
+/// Configures how the requests on a logical service's route, identified by
+/// the name in its `route` label, are handled.
+#[derive(Clone, Debug, Default)]
+pub struct RouteConfig {
+    /// Whether the route's requests may only be sent to endpoints over mTLS.
+    /// Requests fail when the endpoint cannot be meshed.
+    pub require_mtls: bool,
+
+    /// The maximum size of the route's response bodies, in bytes.
+    pub response_body_limit: Option<usize>,
+
+    /// The weight by which the route's requests are dispatched from saturated
+    /// backends' queues. Routes without a priority have a weight of 1.
+    pub priority: Option<u32>,
+
+    /// The faults injected into the route's requests.
+    pub faults: RouteFaults,
+
+    /// The authority to which the route's requests are rewritten before they
+    /// are dispatched, e.g. for virtually-hosted upstreams.
+    pub authority_rewrite: Option<http::uri::Authority>,
+
+    /// Routes the route's requests to backends by the value of a header.
+    pub header_backends: Option<HeaderBackends>,
+
+    /// Overrides the maximum number of request body bytes buffered so that
+    /// the route's requests may be retried.
+    pub retry_max_buffered_bytes: Option<usize>,
+
+    /// Overrides the response statuses that are retried on the route.
+    pub retryable_statuses: Option<Arc<HashSet<http::StatusCode>>>,
+
+    /// Maps the `grpc-status` of the route's gRPC responses to the HTTP status
+    /// returned to clients that do not speak gRPC.
+    pub grpc_status_mapping: GrpcStatusMapping,
+
+    /// Overrides the latency SLO of the route.
+    pub latency_slo: Option<time::Duration>,
+}
+
+/// Routes a route's requests to backends by the value of a request header.
+/// Requests without the header, or with a value that has no backend, are
+/// distributed over the route's backends.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeaderBackends {
+    pub header: http::HeaderName,
+    pub backends: HashMap<String, NameAddr>,
+}
+
+/// Determines how requests are handled when none of the backends a profile
+/// references are available, e.g. because they do not resolve to endpoints.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BackendFallback {
+    /// Requests fail fast.
+    #[default]
+    FailFast,
+
+    /// Requests are sent to the logical service's default backend.
+    Default,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -45,16 +131,36 @@ pub struct LogicalError {
 struct Params<T: Clone + Debug + Eq + Hash> {
     parent: T,
     addr: NameAddr,
-    routes: Arc<[(profiles::http::RequestMatch, RouteParams<T>)]>,
+    routes: Arc<[(profiles::http::RequestMatch, Route<T>)]>,
     backends: distribute::Backends<Concrete<T>>,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Route<T: Clone + Debug + Eq + Hash> {
+    params: RouteParams<T>,
+    header_backends: Option<RouteHeaderBackends<T>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct RouteHeaderBackends<T: Clone + Debug + Eq + Hash> {
+    header: http::HeaderName,
+    distributions: Arc<HashMap<String, Distribution<T>>>,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct RouteParams<T> {
     parent: T,
     addr: NameAddr,
     profile: profiles::http::Route,
     distribution: Distribution<T>,
+    require_mtls: RequireMtls,
+    response_body_limit: Option<ResponseBodyLimit>,
+    priority: RequestPriority,
+    faults: RouteFaults,
+    rewrite_authority: Option<RewriteAuthority>,
+    grpc_status_mapping: GrpcStatusMapping,
+    latency_slo: Option<LatencySlo>,
+    retry: retry::RouteRetryParams,
 }
 
 type BackendCache<T, N, S> = distribute::BackendCache<Concrete<T>, N, S>;
@@ -65,6 +171,11 @@ struct Routable<T> {
     parent: T,
     addr: NameAddr,
     profile: profiles::Receiver,
+    backend_protocols: Arc<HashMap<NameAddr, http::Version>>,
+    routes: Arc<HashMap<NameAddr, HashMap<String, RouteConfig>>>,
+    response_body_limit_mode: response_body_limit::ResponseBodyLimitMode,
+    latency_slo: Option<time::Duration>,
+    backend_fallback: BackendFallback,
 }
 
 #[derive(Clone, Debug)]
@@ -96,6 +207,7 @@ impl<N> Outbound<N> {
     where
         // Logical target.
         T: svc::Param<Logical>,
+        T: svc::Param<http::Version>,
         T: Eq + Hash + Clone + Debug + Send + Sync + 'static,
         // Concrete stack.
         N: svc::NewService<Concrete<T>, Service = NSvc> + Clone + Send + Sync + 'static,
@@ -107,7 +219,10 @@ impl<N> Outbound<N> {
         NSvc::Error: Into<Error>,
         NSvc::Future: Send,
     {
-        self.map_stack(|_, rt, concrete| {
+        self.map_stack(|config, rt, concrete| {
+            // Translates requests to the HTTP version of each backend.
+            let concrete = concrete.push(translate_version::NewTranslateVersion::layer());
+
             let route = svc::layers()
                 .push_on_service(
                     svc::layers()
@@ -118,6 +233,15 @@ impl<N> Outbound<N> {
                         .push(svc::LoadShed::layer()),
                 )
                 .push(http::insert::NewInsert::<RouteParams<T>, _>::layer())
+                // Marks requests on routes that may only be sent over mTLS, so
+                // that they fail on endpoints that cannot be meshed.
+                .push(http::insert::NewInsert::<RequireMtls, _>::layer())
+                // Marks requests with their route's priority, so that backend
+                // queues dispatch them by weight.
+                .push(http::insert::NewInsert::<RequestPriority, _>::layer())
+                // Rewrites the authority of requests on routes that are
+                // configured for virtually-hosted upstreams.
+                .push(NewRewriteAuthority::layer())
                 .push(
                     rt.metrics
                         .proxy
@@ -128,10 +252,31 @@ impl<N> Outbound<N> {
                 // retried, it may have one of two `Body` types. This
                 // layer unifies any `Body` type into `BoxBody`.
                 .push_on_service(http::BoxRequest::erased())
+                // Bounds each attempt so that the route's timeout leaves
+                // headroom for the response to be processed.
+                .push(retry::NewAttemptTimeout::layer(
+                    config.http_retry_timeout_headroom,
+                ))
                 // Sets an optional retry policy.
                 .push(retry::layer(
                     rt.metrics.proxy.http_profile_route_retry.clone(),
+                    retry::RetryParams {
+                        max_buffered_bytes: config.http_retry_max_buffered_bytes,
+                        retryable_statuses: config.http_retryable_statuses.clone(),
+                        min_attempt_time: config.http_retry_min_attempt_time,
+                        max_retry_after: config.http_retry_after_max,
+                        idempotent_header: config.http_retry_idempotent_header.clone(),
+                        on_reset: config.http_retry_on_reset,
+                        timeout_headroom: config.http_retry_timeout_headroom,
+                    },
                 ))
+                // Reports the utilization of the route's retry budget, if it
+                // has one.
+                .push(rt.metrics.route_retry_budgets.to_layer())
+                // Injects the route's configured faults, if any. Injected
+                // delays count against the request's timeout, and injected
+                // aborts are not retried.
+                .push(NewInjectFaults::layer())
                 // Sets an optional request timeout.
                 .push(http::NewTimeout::layer())
                 // Records per-route metrics.
@@ -141,11 +286,48 @@ impl<N> Outbound<N> {
                         .http_profile_route
                         .to_layer::<classify::Response, _, RouteParams<T>>(),
                 )
+                // Records requests that exceed the route's latency SLO.
+                .push(
+                    rt.metrics
+                        .route_slo
+                        .to_layer(config.route_latency_slo_log_breaches),
+                )
+                // Records the proportion of each route's recent requests that
+                // were classified as successful.
+                .push(
+                    rt.metrics
+                        .route_availability
+                        .to_layer(config.route_availability_window),
+                )
+                // Records the sizes of each route's request and response
+                // bodies.
+                .push(
+                    rt.metrics
+                        .route_payload_sizes
+                        .to_layer(config.route_payload_size_buckets),
+                )
                 // Sets the per-route response classifier as a request
                 // extension.
                 .push(classify::NewClassify::layer())
                 // TODO(ver) .push(svc::NewMapErr::layer_from_target::<RouteError, _>())
-                .push_on_service(http::BoxResponse::layer());
+                // Enforces the route's response body limit, if it has one.
+                .push(response_body_limit::NewLimitResponseBody::layer())
+                .push_on_service(http::BoxResponse::layer())
+                // Maps the statuses of gRPC responses to non-gRPC requests,
+                // if the route has a mapping.
+                .push(NewMapGrpcStatus::layer())
+                // Shares the responses of in-flight requests with identical
+                // requests, if configured.
+                .push(request_coalescing::NewCoalesceRequests::layer(
+                    config.http_request_coalescing.clone(),
+                ))
+                // Serves cacheable responses from an optional per-route cache.
+                .push(response_cache::NewResponseCache::layer(
+                    config.http_response_cache.clone(),
+                ))
+                // Counts the requests that were served coalesced or cached
+                // responses.
+                .push(rt.metrics.route_dedup.to_layer());
 
             // A `NewService`--instantiated once per logical target--that caches
             // a set of concrete services so that, as the watch provides new
@@ -177,21 +359,35 @@ impl<N> Outbound<N> {
                 // TODO(ver) do we need to strip headers here?
                 .push(http::NewHeaderFromTarget::<CanonicalDstHeader, _>::layer())
                 .push_switch(
-                    |parent: T| -> Result<_, Infallible> {
-                        Ok(match parent.param() {
-                            Logical::Route(addr, profile) => svc::Either::A(Routable {
-                                addr,
-                                parent,
-                                profile,
-                            }),
-                            Logical::Forward(addr, meta) => svc::Either::B(Concrete {
-                                target: concrete::Dispatch::Forward(addr, meta),
-                                parent,
-                            }),
-                        })
+                    {
+                        let backend_protocols = config.http_backend_protocols.clone();
+                        let routes = config.http_routes.clone();
+                        let response_body_limit_mode = config.http_route_response_body_limit_mode;
+                        let latency_slo = config.route_latency_slo;
+                        let backend_fallback = config.http_backend_fallback;
+                        move |parent: T| -> Result<_, Infallible> {
+                            Ok(match parent.param() {
+                                Logical::Route(addr, profile) => svc::Either::A(Routable {
+                                    addr,
+                                    parent,
+                                    profile,
+                                    backend_protocols: backend_protocols.clone(),
+                                    routes: routes.clone(),
+                                    response_body_limit_mode,
+                                    latency_slo,
+                                    backend_fallback,
+                                }),
+                                Logical::Forward(addr, meta) => svc::Either::B(Concrete {
+                                    target: concrete::Dispatch::Forward(addr, meta),
+                                    parent,
+                                    version: None,
+                                }),
+                            })
+                        }
                     },
                     concrete.into_inner(),
                 )
+                .push(rt.metrics.stack_layers.to_layer(StackLayer::Logical))
                 .push(svc::ArcNewService::layer())
         })
     }
@@ -223,14 +419,17 @@ where
             decay: time::Duration::from_secs(10),
         };
 
+        // The logical service's default backend.
+        let default = Concrete {
+            target: concrete::Dispatch::Balance(routable.addr.clone(), EWMA),
+            parent: routable.parent.clone(),
+            version: routable.backend_protocols.get(&routable.addr).copied(),
+        };
+
         // Create concrete targets for all of the profile's routes.
-        let (backends, distribution) = if profile.targets.is_empty() {
-            let concrete = Concrete {
-                target: concrete::Dispatch::Balance(routable.addr.clone(), EWMA),
-                parent: routable.parent.clone(),
-            };
-            let backends = std::iter::once(concrete.clone()).collect();
-            let distribution = Distribution::first_available(std::iter::once(concrete));
+        let (mut backends, mut distribution): (Vec<_>, _) = if profile.targets.is_empty() {
+            let backends = std::iter::once(default.clone()).collect();
+            let distribution = Distribution::first_available(std::iter::once(default.clone()));
             (backends, distribution)
         } else {
             let backends = profile
@@ -239,11 +438,13 @@ where
                 .map(|t| Concrete {
                     target: concrete::Dispatch::Balance(t.addr.clone(), EWMA),
                     parent: routable.parent.clone(),
+                    version: routable.backend_protocols.get(&t.addr).copied(),
                 })
                 .collect();
             let distribution = Distribution::random_available(profile.targets.iter().cloned().map(
                 |profiles::Target { addr, weight }| {
                     let concrete = Concrete {
+                        version: routable.backend_protocols.get(&addr).copied(),
                         target: concrete::Dispatch::Balance(addr, EWMA),
                         parent: routable.parent.clone(),
                     };
@@ -255,27 +456,108 @@ where
             (backends, distribution)
         };
 
+        // When the profile's backends are unavailable, requests may fall back
+        // to the default backend rather than failing.
+        let fallback = match routable.backend_fallback {
+            BackendFallback::FailFast => None,
+            BackendFallback::Default => {
+                if !backends.contains(&default) {
+                    backends.push(default.clone());
+                }
+                distribution = distribution.with_fallback(default.clone());
+                Some(default)
+            }
+        };
+
+        // Routes are named by their `route` label.
+        let configs = routable.routes.get(&routable.addr);
+        let unconfigured = RouteConfig::default();
         let routes = profile
             .http_routes
             .iter()
             .cloned()
             .map(|(req_match, profile)| {
+                let config = configs
+                    .zip(profile.labels().get("route"))
+                    .and_then(|(configs, name)| configs.get(name))
+                    .unwrap_or(&unconfigured);
+                let response_body_limit =
+                    config
+                        .response_body_limit
+                        .map(|max_bytes| ResponseBodyLimit {
+                            max_bytes,
+                            mode: routable.response_body_limit_mode,
+                        });
+                // Header-selected backends are distinct from the profile's
+                // targets, so each must be added to the set of backends.
+                let header_backends = config.header_backends.as_ref().map(|hb| {
+                    let distributions = hb
+                        .backends
+                        .iter()
+                        .map(|(value, addr)| {
+                            let concrete = Concrete {
+                                target: concrete::Dispatch::Balance(addr.clone(), EWMA),
+                                version: routable.backend_protocols.get(addr).copied(),
+                                parent: routable.parent.clone(),
+                            };
+                            backends.push(concrete.clone());
+                            let distribution = Distribution::from(concrete);
+                            let distribution = match fallback.clone() {
+                                Some(fallback) => distribution.with_fallback(fallback),
+                                None => distribution,
+                            };
+                            (value.clone(), distribution)
+                        })
+                        .collect();
+                    RouteHeaderBackends {
+                        header: hb.header.clone(),
+                        distributions: Arc::new(distributions),
+                    }
+                });
                 let params = RouteParams {
                     addr: routable.addr.clone(),
                     profile,
                     parent: routable.parent.clone(),
                     distribution: distribution.clone(),
+                    require_mtls: RequireMtls(config.require_mtls),
+                    response_body_limit,
+                    priority: config.priority.map(RequestPriority).unwrap_or_default(),
+                    faults: config.faults,
+                    rewrite_authority: config.authority_rewrite.clone().map(RewriteAuthority),
+                    grpc_status_mapping: config.grpc_status_mapping.clone(),
+                    latency_slo: config.latency_slo.or(routable.latency_slo).map(LatencySlo),
+                    retry: retry::RouteRetryParams {
+                        max_buffered_bytes: config.retry_max_buffered_bytes,
+                        retryable_statuses: config.retryable_statuses.clone(),
+                    },
                 };
-                (req_match, params)
+                (
+                    req_match,
+                    Route {
+                        params,
+                        header_backends,
+                    },
+                )
             })
             // Add a default route.
             .chain(std::iter::once((
                 profiles::http::RequestMatch::default(),
-                RouteParams {
-                    addr: routable.addr.clone(),
-                    profile: Default::default(),
-                    parent: routable.parent.clone(),
-                    distribution: distribution.clone(),
+                Route {
+                    params: RouteParams {
+                        addr: routable.addr.clone(),
+                        profile: Default::default(),
+                        parent: routable.parent.clone(),
+                        distribution: distribution.clone(),
+                        require_mtls: RequireMtls::default(),
+                        response_body_limit: None,
+                        priority: RequestPriority::default(),
+                        faults: RouteFaults::default(),
+                        rewrite_authority: None,
+                        grpc_status_mapping: GrpcStatusMapping::default(),
+                        latency_slo: routable.latency_slo.map(LatencySlo),
+                        retry: retry::RouteRetryParams::default(),
+                    },
+                    header_backends: None,
                 },
             )))
             .collect::<Arc<[(_, _)]>>();
@@ -283,7 +565,7 @@ where
         Self {
             addr: routable.addr,
             parent: routable.parent,
-            backends,
+            backends: backends.into_iter().collect(),
             routes,
         }
     }
@@ -315,9 +597,31 @@ where
     type Error = NoRoute;
 
     fn select(&self, req: &http::Request<B>) -> Result<Self::Key, Self::Error> {
-        profiles::http::route_for_request(&*self.routes, req)
-            .ok_or(NoRoute)
-            .cloned()
+        let route = profiles::http::route_for_request(&*self.routes, req).ok_or(NoRoute)?;
+        Ok(route.select(req.headers()))
+    }
+}
+
+// === impl Route ===
+
+impl<T> Route<T>
+where
+    T: Eq + Hash + Clone + Debug,
+{
+    /// Returns the route's parameters, with a distribution selected by the
+    /// request's headers, if the route routes by header.
+    fn select(&self, headers: &http::header::HeaderMap) -> RouteParams<T> {
+        let distribution = self.header_backends.as_ref().and_then(|hb| {
+            let value = headers.get(&hb.header)?.to_str().ok()?;
+            hb.distributions.get(value)
+        });
+        match distribution {
+            Some(distribution) => RouteParams {
+                distribution: distribution.clone(),
+                ..self.params.clone()
+            },
+            None => self.params.clone(),
+        }
     }
 }
 
@@ -350,12 +654,60 @@ impl<T> svc::Param<metrics::ProfileRouteLabels> for RouteParams<T> {
     }
 }
 
+impl<T> svc::Param<RequireMtls> for RouteParams<T> {
+    fn param(&self) -> RequireMtls {
+        self.require_mtls
+    }
+}
+
+impl<T> svc::Param<RequestPriority> for RouteParams<T> {
+    fn param(&self) -> RequestPriority {
+        self.priority
+    }
+}
+
+impl<T> svc::Param<RouteFaults> for RouteParams<T> {
+    fn param(&self) -> RouteFaults {
+        self.faults
+    }
+}
+
+impl<T> svc::Param<Option<RewriteAuthority>> for RouteParams<T> {
+    fn param(&self) -> Option<RewriteAuthority> {
+        self.rewrite_authority.clone()
+    }
+}
+
+impl<T> svc::Param<Option<ResponseBodyLimit>> for RouteParams<T> {
+    fn param(&self) -> Option<ResponseBodyLimit> {
+        self.response_body_limit
+    }
+}
+
 impl<T> svc::Param<http::ResponseTimeout> for RouteParams<T> {
     fn param(&self) -> http::ResponseTimeout {
         http::ResponseTimeout(self.profile.timeout())
     }
 }
 
+impl<T> svc::Param<GrpcStatusMapping> for RouteParams<T> {
+    fn param(&self) -> GrpcStatusMapping {
+        self.grpc_status_mapping.clone()
+    }
+}
+
+impl<T> svc::Param<Option<LatencySlo>> for RouteParams<T> {
+    fn param(&self) -> Option<LatencySlo> {
+        self.latency_slo
+    }
+}
+
+impl<T> svc::Param<retry::RouteRetryParams> for RouteParams<T> {
+    fn param(&self) -> retry::RouteRetryParams {
+        self.retry.clone()
+    }
+}
+
 impl<T> classify::CanClassify for RouteParams<T> {
     type Classify = classify::Request;
 
@@ -382,7 +734,7 @@ where
     T: svc::Param<http::Version>,
 {
     fn param(&self) -> http::Version {
-        self.parent.param()
+        self.version.unwrap_or_else(|| self.parent.param())
     }
 }
 
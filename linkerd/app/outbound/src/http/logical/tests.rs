@@ -0,0 +1,369 @@
+use super::*;
+use crate::test_util::*;
+use linkerd_app_core::svc::{NewService, ServiceExt};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Target(Logical);
+
+impl svc::Param<Logical> for Target {
+    fn param(&self) -> Logical {
+        self.0.clone()
+    }
+}
+
+impl svc::Param<http::Version> for Target {
+    fn param(&self) -> http::Version {
+        http::Version::Http1
+    }
+}
+
+/// Tests that traffic split across backends that speak different protocols is
+/// translated to each backend's HTTP version.
+#[tokio::test(flavor = "current_thread")]
+async fn splits_across_protocol_versions() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let laddr = "xyz.example.com:8080".parse::<NameAddr>().unwrap();
+    let h1_addr = "h1.example.com:8080".parse::<NameAddr>().unwrap();
+    let h2_addr = "h2.example.com:8080".parse::<NameAddr>().unwrap();
+    let (_tx, rx) = watch::channel(Profile {
+        addr: Some(profiles::LogicalAddr(laddr.clone())),
+        targets: vec![
+            profiles::Target {
+                addr: h1_addr.clone(),
+                weight: 1,
+            },
+            profiles::Target {
+                addr: h2_addr.clone(),
+                weight: 1,
+            },
+        ]
+        .into(),
+        ..Default::default()
+    });
+
+    let mut config = default_config();
+    config.http_backend_protocols =
+        Arc::new(std::iter::once((h2_addr.clone(), http::Version::H2)).collect());
+
+    // Each backend responds with its address and the version and connection
+    // headers of the request it received.
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(config, rt)
+        .with_stack(|concrete: Concrete<Target>| {
+            let backend = match svc::Param::<concrete::Dispatch>::param(&concrete) {
+                concrete::Dispatch::Balance(addr, _) => addr,
+                dispatch => unreachable!("unexpected dispatch: {:?}", dispatch),
+            };
+            svc::mk(move |req: http::Request<http::BoxBody>| {
+                let rsp = http::Response::builder()
+                    .header("x-backend", backend.to_string())
+                    .header("x-version", format!("{:?}", req.version()))
+                    .header(
+                        "x-connection",
+                        req.headers()
+                            .contains_key(http::header::CONNECTION)
+                            .to_string(),
+                    )
+                    .body(http::BoxBody::default())
+                    .unwrap();
+                futures::future::ok::<_, Error>(rsp)
+            })
+        })
+        .push_http_logical()
+        .into_inner()
+        .new_service(Target(Logical::Route(laddr, rx.into())));
+
+    let (mut seen_h1, mut seen_h2) = (false, false);
+    for _ in 0..100 {
+        let req = http::Request::get("http://xyz.example.com:8080/")
+            .version(::http::Version::HTTP_11)
+            .header(http::header::CONNECTION, "keep-alive")
+            .body(http::BoxBody::default())
+            .unwrap();
+        let rsp = stack
+            .clone()
+            .oneshot(req)
+            .await
+            .expect("request must succeed");
+        let header = |name: &str| rsp.headers()[name].to_str().unwrap().to_string();
+        let backend = header("x-backend");
+        if backend == h1_addr.to_string() {
+            seen_h1 = true;
+            assert_eq!(header("x-version"), "HTTP/1.1");
+            assert_eq!(header("x-connection"), "true");
+        } else if backend == h2_addr.to_string() {
+            seen_h2 = true;
+            assert_eq!(header("x-version"), "HTTP/2.0");
+            assert_eq!(
+                header("x-connection"),
+                "false",
+                "connection headers must not be sent to HTTP/2 backends"
+            );
+        } else {
+            panic!("unexpected backend: {}", backend);
+        }
+    }
+    assert!(seen_h1, "must route to the HTTP/1 backend");
+    assert!(seen_h2, "must route to the HTTP/2 backend");
+}
+
+/// Tests that requests on a route with header backends are routed by the
+/// header's value, and that requests without a configured value are
+/// distributed over the route's backends.
+#[tokio::test(flavor = "current_thread")]
+async fn routes_by_header_value() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let laddr = "xyz.example.com:8080".parse::<NameAddr>().unwrap();
+    let a_addr = "a.example.com:8080".parse::<NameAddr>().unwrap();
+    let b_addr = "b.example.com:8080".parse::<NameAddr>().unwrap();
+    let route = profiles::http::Route::new(
+        std::iter::once(("route".to_string(), "experiment".to_string())),
+        Vec::new(),
+    );
+    let (_tx, rx) = watch::channel(Profile {
+        addr: Some(profiles::LogicalAddr(laddr.clone())),
+        http_routes: vec![(profiles::http::RequestMatch::default(), route)].into(),
+        ..Default::default()
+    });
+
+    let mut config = default_config();
+    config.http_routes = Arc::new(
+        std::iter::once((
+            laddr.clone(),
+            std::iter::once((
+                "experiment".to_string(),
+                RouteConfig {
+                    header_backends: Some(HeaderBackends {
+                        header: http::HeaderName::from_static("x-variant"),
+                        backends: vec![
+                            ("a".to_string(), a_addr.clone()),
+                            ("b".to_string(), b_addr.clone()),
+                        ]
+                        .into_iter()
+                        .collect(),
+                    }),
+                    ..Default::default()
+                },
+            ))
+            .collect(),
+        ))
+        .collect(),
+    );
+
+    // Each backend responds with its address.
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(config, rt)
+        .with_stack(|concrete: Concrete<Target>| {
+            let backend = match svc::Param::<concrete::Dispatch>::param(&concrete) {
+                concrete::Dispatch::Balance(addr, _) => addr,
+                dispatch => unreachable!("unexpected dispatch: {:?}", dispatch),
+            };
+            svc::mk(move |_: http::Request<http::BoxBody>| {
+                let rsp = http::Response::builder()
+                    .header("x-backend", backend.to_string())
+                    .body(http::BoxBody::default())
+                    .unwrap();
+                futures::future::ok::<_, Error>(rsp)
+            })
+        })
+        .push_http_logical()
+        .into_inner()
+        .new_service(Target(Logical::Route(laddr.clone(), rx.into())));
+
+    for (variant, expected) in [
+        (Some("a"), &a_addr),
+        (Some("b"), &b_addr),
+        (Some("c"), &laddr),
+        (None, &laddr),
+        (Some("a"), &a_addr),
+    ] {
+        let mut req = http::Request::get("http://xyz.example.com:8080/");
+        if let Some(variant) = variant {
+            req = req.header("x-variant", variant);
+        }
+        let rsp = stack
+            .clone()
+            .oneshot(req.body(http::BoxBody::default()).unwrap())
+            .await
+            .expect("request must succeed");
+        assert_eq!(
+            rsp.headers()["x-backend"].to_str().unwrap(),
+            expected.to_string(),
+            "variant {:?} must be routed to {}",
+            variant,
+            expected
+        );
+    }
+}
+
+/// Tests that requests on a route with an authority rewrite are dispatched
+/// with the configured authority, and that other routes' requests are not
+/// rewritten.
+#[tokio::test(flavor = "current_thread")]
+async fn rewrites_route_authority() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let laddr = "xyz.example.com:8080".parse::<NameAddr>().unwrap();
+    let route = profiles::http::Route::new(
+        std::iter::once(("route".to_string(), "vhost".to_string())),
+        Vec::new(),
+    );
+    let (_tx, rx) = watch::channel(Profile {
+        addr: Some(profiles::LogicalAddr(laddr.clone())),
+        http_routes: vec![(
+            profiles::http::RequestMatch::Method(http::Method::POST),
+            route,
+        )]
+        .into(),
+        ..Default::default()
+    });
+
+    let mut config = default_config();
+    config.http_routes = Arc::new(
+        std::iter::once((
+            laddr.clone(),
+            std::iter::once((
+                "vhost".to_string(),
+                RouteConfig {
+                    authority_rewrite: Some("site.internal.example.com:80".parse().unwrap()),
+                    ..Default::default()
+                },
+            ))
+            .collect(),
+        ))
+        .collect(),
+    );
+
+    // The backend responds with the authority and host of the request it
+    // received.
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(config, rt)
+        .with_stack(|_: Concrete<Target>| {
+            svc::mk(move |req: http::Request<http::BoxBody>| {
+                let rsp = http::Response::builder()
+                    .header("x-authority", req.uri().authority().unwrap().as_str())
+                    .header("x-host", req.headers()[http::header::HOST].clone())
+                    .body(http::BoxBody::default())
+                    .unwrap();
+                futures::future::ok::<_, Error>(rsp)
+            })
+        })
+        .push_http_logical()
+        .into_inner()
+        .new_service(Target(Logical::Route(laddr.clone(), rx.into())));
+
+    for (method, expected) in [
+        (http::Method::POST, "site.internal.example.com:80"),
+        (http::Method::GET, "xyz.example.com:8080"),
+    ] {
+        let req = http::Request::builder()
+            .method(method.clone())
+            .uri("http://xyz.example.com:8080/")
+            .header(http::header::HOST, "xyz.example.com:8080")
+            .body(http::BoxBody::default())
+            .unwrap();
+        let rsp = stack
+            .clone()
+            .oneshot(req)
+            .await
+            .expect("request must succeed");
+        assert_eq!(rsp.headers()["x-authority"], expected, "method {}", method);
+        assert_eq!(rsp.headers()["x-host"], expected, "method {}", method);
+    }
+}
+
+/// A backend that responds with its address, unless it is unavailable.
+#[derive(Clone, Debug)]
+struct Backend {
+    addr: NameAddr,
+    available: bool,
+}
+
+impl svc::Service<http::Request<http::BoxBody>> for Backend {
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = futures::future::Ready<Result<Self::Response, Error>>;
+
+    fn poll_ready(&mut self, _: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Error>> {
+        if self.available {
+            std::task::Poll::Ready(Ok(()))
+        } else {
+            std::task::Poll::Pending
+        }
+    }
+
+    fn call(&mut self, _: http::Request<http::BoxBody>) -> Self::Future {
+        let rsp = http::Response::builder()
+            .header("x-backend", self.addr.to_string())
+            .body(http::BoxBody::default())
+            .unwrap();
+        futures::future::ok(rsp)
+    }
+}
+
+/// Sends a request on a route whose profile references a backend that does
+/// not resolve to endpoints, returning the backend that served it.
+async fn send_to_unknown_backend(fallback: BackendFallback) -> Result<String, Error> {
+    let laddr = "xyz.example.com:8080".parse::<NameAddr>().unwrap();
+    let unknown = "unknown.example.com:8080".parse::<NameAddr>().unwrap();
+    let route = profiles::http::Route::new(
+        std::iter::once(("route".to_string(), "list".to_string())),
+        Vec::new(),
+    );
+    let (_tx, rx) = watch::channel(Profile {
+        addr: Some(profiles::LogicalAddr(laddr.clone())),
+        http_routes: vec![(profiles::http::RequestMatch::default(), route)].into(),
+        targets: vec![profiles::Target {
+            addr: unknown.clone(),
+            weight: 1,
+        }]
+        .into(),
+        ..Default::default()
+    });
+
+    let mut config = default_config();
+    config.http_backend_fallback = fallback;
+
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(config, rt)
+        .with_stack(move |concrete: Concrete<Target>| {
+            let addr = match svc::Param::<concrete::Dispatch>::param(&concrete) {
+                concrete::Dispatch::Balance(addr, _) => addr,
+                dispatch => unreachable!("unexpected dispatch: {:?}", dispatch),
+            };
+            Backend {
+                available: addr != unknown,
+                addr,
+            }
+        })
+        .push_http_logical()
+        .into_inner()
+        .new_service(Target(Logical::Route(laddr, rx.into())));
+
+    let rsp = stack
+        .oneshot(
+            http::Request::get("http://xyz.example.com:8080/")
+                .body(http::BoxBody::default())
+                .unwrap(),
+        )
+        .await?;
+    Ok(rsp.headers()["x-backend"].to_str().unwrap().to_string())
+}
+
+/// Tests that requests fail fast when a profile's backend does not resolve to
+/// endpoints, unless they are configured to fall back to the default backend.
+#[tokio::test(flavor = "current_thread")]
+async fn unknown_backend_fallback() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    send_to_unknown_backend(BackendFallback::FailFast)
+        .await
+        .expect_err("request must fail fast");
+
+    let backend = send_to_unknown_backend(BackendFallback::Default)
+        .await
+        .expect("request must succeed");
+    assert_eq!(backend, "xyz.example.com:8080");
+}
@@ -0,0 +1,511 @@
+//! Coalesces identical in-flight `GET` requests on each route.
+//!
+//! Requests are identified by a signature: their method, URI, credentials
+//! (i.e. `Authorization` and `Cookie` headers), and the values of a configured
+//! set of key headers. While a request is awaiting its response, identical
+//! requests on the same route are not dispatched; instead, they wait to share
+//! the in-flight request's response. Only `GET` and `HEAD` requests without
+//! bodies are coalesced.
+//!
+//! A response that no requests are waiting on is streamed to its request
+//! unchanged. Otherwise, its body is copied as it is streamed to the original
+//! request, and the copy is shared with the waiting requests once the body
+//! completes. If the body exceeds the configured limit, fails, or is dropped
+//! before it completes, the response is not shared and the waiting requests are
+//! dispatched on their own. Responses that are marked `private` or `no-store`,
+//! or that set cookies, are never shared. The original request receives its
+//! response with its extensions intact; shared responses include the original
+//! response's status, headers, body, and trailers, but not its extensions,
+//! which cannot be cloned.
+
+use super::response_cache::cache_control;
+use crate::http::{
+    self,
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    HttpBody,
+};
+use bytes::{Buf, Bytes, BytesMut};
+use futures::{future, FutureExt, TryFutureExt};
+use linkerd_app_core::{svc, Error};
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::sync::oneshot;
+use tracing::{debug, trace};
+
+#[cfg(test)]
+mod tests;
+
+/// Configures the coalescing of identical requests on each HTTP route.
+#[derive(Clone, Debug)]
+pub struct RequestCoalescingConfig {
+    /// Headers whose values, in addition to the request's method and URI,
+    /// distinguish requests that may not be coalesced.
+    pub key_headers: Arc<[HeaderName]>,
+
+    /// The largest response body that may be shared by coalesced requests.
+    pub max_body_bytes: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewCoalesceRequests<N> {
+    inner: N,
+    config: Option<Arc<RequestCoalescingConfig>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CoalesceRequests<S> {
+    inner: S,
+    in_flight: Option<Arc<InFlight>>,
+}
+
+/// A response extension that marks a response that was shared from another
+/// in-flight request.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Coalesced;
+
+/// The error of a request whose response was shared with coalesced requests.
+#[derive(Clone, Debug)]
+struct Shared(Arc<Error>);
+
+/// Resolves with the response shared by an in-flight request, or `None` if its
+/// response may not be shared.
+type SharedResponse = future::Shared<future::BoxFuture<'static, Result<Option<Buffered>, Shared>>>;
+
+#[derive(Debug)]
+struct InFlight {
+    config: Arc<RequestCoalescingConfig>,
+    requests: Mutex<HashMap<Signature, Pending>>,
+}
+
+/// An in-flight request that is awaiting its response.
+#[derive(Debug)]
+struct Pending {
+    rsp: SharedResponse,
+    /// The number of requests waiting on the response.
+    waiting: usize,
+    /// Identifies the request that is awaiting the response.
+    id: Arc<()>,
+}
+
+/// Removes a request from the in-flight requests if it is dropped before its
+/// response is received.
+struct Dispatched {
+    in_flight: Arc<InFlight>,
+    signature: Signature,
+    id: Arc<()>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Signature {
+    method: http::Method,
+    uri: http::uri::Uri,
+    headers: Vec<Vec<HeaderValue>>,
+}
+
+#[derive(Clone, Debug)]
+struct Buffered {
+    status: http::StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    trailers: Option<HeaderMap>,
+}
+
+/// A response body that copies its data so that the response may be shared
+/// once the body completes.
+#[pin_project]
+struct TeeBody {
+    #[pin]
+    inner: http::BoxBody,
+    tee: Option<Tee>,
+}
+
+struct Tee {
+    tx: oneshot::Sender<Result<Option<Buffered>, Shared>>,
+    status: http::StatusCode,
+    headers: HeaderMap,
+    buf: BytesMut,
+    max_bytes: usize,
+}
+
+/// The body of a shared response.
+#[derive(Default)]
+struct SharedBody {
+    data: Option<Bytes>,
+    trailers: Option<HeaderMap>,
+}
+
+// === impl NewCoalesceRequests ===
+
+impl<N> NewCoalesceRequests<N> {
+    /// When `config` is `None`, requests are not coalesced.
+    pub fn layer(
+        config: Option<RequestCoalescingConfig>,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        let config = config.map(Arc::new);
+        svc::layer::mk(move |inner| Self {
+            inner,
+            config: config.clone(),
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewCoalesceRequests<N>
+where
+    N: svc::NewService<T>,
+{
+    type Service = CoalesceRequests<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        // Each route tracks its own in-flight requests, so that requests are
+        // never coalesced across routes.
+        let in_flight = self.config.clone().map(|config| {
+            Arc::new(InFlight {
+                config,
+                requests: Mutex::new(HashMap::default()),
+            })
+        });
+        CoalesceRequests {
+            inner: self.inner.new_service(target),
+            in_flight,
+        }
+    }
+}
+
+// === impl CoalesceRequests ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for CoalesceRequests<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+    S: Clone + Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = future::BoxFuture<'static, Result<Self::Response, Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let in_flight = match self.in_flight.as_ref() {
+            Some(in_flight) if is_coalescable(&req) => in_flight.clone(),
+            _ => return Box::pin(self.inner.call(req).err_into::<Error>()),
+        };
+
+        let signature = Signature::new(&req, &in_flight.config.key_headers);
+        let mut requests = in_flight.requests.lock();
+        if let Some(pending) = requests.get_mut(&signature) {
+            trace!(?signature, "Coalescing request");
+            pending.waiting += 1;
+            let rsp = pending.rsp.clone();
+            drop(requests);
+
+            // The request is dispatched on its own if the in-flight request's
+            // response is not shared.
+            let inner = self.inner.clone();
+            return Box::pin(async move {
+                match rsp.await? {
+                    Some(buffered) => {
+                        let mut rsp = buffered.into_response();
+                        rsp.extensions_mut().insert(Coalesced);
+                        Ok(rsp)
+                    }
+                    None => {
+                        debug!("Response was not shared; dispatching request");
+                        svc::ServiceExt::oneshot(inner, req)
+                            .err_into::<Error>()
+                            .await
+                    }
+                }
+            });
+        }
+
+        debug!(?signature, "Dispatching request");
+        let (tx, rx) = oneshot::channel();
+        let shared = rx.map(|res| res.unwrap_or(Ok(None))).boxed().shared();
+        let id = Arc::new(());
+        requests.insert(
+            signature.clone(),
+            Pending {
+                rsp: shared,
+                waiting: 0,
+                id: id.clone(),
+            },
+        );
+        drop(requests);
+
+        let max_bytes = in_flight.config.max_body_bytes;
+        let dispatched = Dispatched {
+            in_flight,
+            signature,
+            id,
+        };
+        let rsp = self.inner.call(req).err_into::<Error>();
+        Box::pin(async move {
+            let res = rsp.await;
+            // Requests received after the response is received are dispatched
+            // anew.
+            let waiting = dispatched.remove();
+            match res {
+                Ok(rsp) if waiting == 0 => Ok(rsp),
+                Ok(rsp) => {
+                    trace!(waiting, "Sharing response");
+                    Ok(TeeBody::share(rsp, tx, max_bytes))
+                }
+                Err(error) => {
+                    let error = Shared(Arc::new(error));
+                    let _ = tx.send(Err(error.clone()));
+                    Err(error.into())
+                }
+            }
+        })
+    }
+}
+
+fn is_coalescable(req: &http::Request<http::BoxBody>) -> bool {
+    (req.method() == http::Method::GET || req.method() == http::Method::HEAD)
+        && req.body().is_end_stream()
+}
+
+/// Responses that are private to their client, that may not be stored, or that
+/// set cookies are never shared.
+fn is_shareable(headers: &HeaderMap) -> bool {
+    !headers.contains_key(header::SET_COOKIE)
+        && !cache_control(headers)
+            .any(|d| matches!(d.split('=').next(), Some("private" | "no-store")))
+}
+
+// === impl Dispatched ===
+
+impl Dispatched {
+    /// Removes the request from the in-flight requests, returning the number
+    /// of requests waiting on its response.
+    fn remove(&self) -> usize {
+        let mut requests = self.in_flight.requests.lock();
+        match requests.get(&self.signature) {
+            Some(pending) if Arc::ptr_eq(&pending.id, &self.id) => requests
+                .remove(&self.signature)
+                .map(|p| p.waiting)
+                .unwrap_or(0),
+            _ => 0,
+        }
+    }
+}
+
+impl Drop for Dispatched {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
+
+// === impl Signature ===
+
+impl Signature {
+    /// Requests with distinct credentials are never coalesced, so that one
+    /// client's response is not shared with another.
+    const CREDENTIAL_HEADERS: [HeaderName; 2] = [header::AUTHORIZATION, header::COOKIE];
+
+    fn new<B>(req: &http::Request<B>, key_headers: &[HeaderName]) -> Self {
+        Self {
+            method: req.method().clone(),
+            uri: req.uri().clone(),
+            headers: Self::CREDENTIAL_HEADERS
+                .iter()
+                .chain(key_headers)
+                .map(|h| req.headers().get_all(h).iter().cloned().collect())
+                .collect(),
+        }
+    }
+}
+
+// === impl Buffered ===
+
+impl Buffered {
+    fn into_response(self) -> http::Response<http::BoxBody> {
+        let Self {
+            status,
+            headers,
+            body,
+            trailers,
+        } = self;
+        let body = SharedBody {
+            data: Some(body).filter(|b| !b.is_empty()),
+            trailers,
+        };
+        let mut rsp = http::Response::new(http::BoxBody::new(body));
+        *rsp.status_mut() = status;
+        *rsp.headers_mut() = headers;
+        rsp
+    }
+}
+
+// === impl TeeBody ===
+
+impl TeeBody {
+    /// Wraps the response's body so that the response is shared once the body
+    /// completes, if it may be shared.
+    fn share(
+        rsp: http::Response<http::BoxBody>,
+        tx: oneshot::Sender<Result<Option<Buffered>, Shared>>,
+        max_bytes: usize,
+    ) -> http::Response<http::BoxBody> {
+        if !is_shareable(rsp.headers()) {
+            debug!("Response may not be shared");
+            let _ = tx.send(Ok(None));
+            return rsp;
+        }
+
+        let (parts, inner) = rsp.into_parts();
+        let mut tee = Some(Tee {
+            tx,
+            status: parts.status,
+            headers: parts.headers.clone(),
+            buf: BytesMut::new(),
+            max_bytes,
+        });
+        // Empty bodies may never be polled.
+        if inner.is_end_stream() {
+            Tee::finish(&mut tee, None);
+        }
+        http::Response::from_parts(parts, http::BoxBody::new(TeeBody { inner, tee }))
+    }
+}
+
+impl HttpBody for TeeBody {
+    type Data = Bytes;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let mut this = self.project();
+        let data = futures::ready!(this.inner.as_mut().poll_data(cx))
+            .map(|res| res.map(|mut data| data.copy_to_bytes(data.remaining())));
+        match &data {
+            Some(Ok(data)) => {
+                if let Some(tee) = this.tee.as_mut() {
+                    if tee.buf.len() + data.len() > tee.max_bytes {
+                        debug!(max_bytes = tee.max_bytes, "Response too large to share");
+                        *this.tee = None;
+                    } else {
+                        tee.buf.extend_from_slice(data);
+                        // Callers, like hyper, may stop polling the body once
+                        // it reports the end of the stream.
+                        if this.inner.is_end_stream() {
+                            Tee::finish(this.tee, None);
+                        }
+                    }
+                }
+            }
+            Some(Err(_)) => *this.tee = None,
+            None => {
+                // The body may still have trailers.
+                if this.inner.is_end_stream() {
+                    Tee::finish(this.tee, None);
+                }
+            }
+        }
+        Poll::Ready(data)
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap<HeaderValue>>, Self::Error>> {
+        let this = self.project();
+        let trailers = futures::ready!(this.inner.poll_trailers(cx));
+        match &trailers {
+            Ok(trailers) => Tee::finish(this.tee, trailers.clone()),
+            Err(_) => *this.tee = None,
+        }
+        Poll::Ready(trailers)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// === impl Tee ===
+
+impl Tee {
+    /// Shares the buffered response with the requests waiting on it.
+    fn finish(tee: &mut Option<Self>, trailers: Option<HeaderMap>) {
+        if let Some(Self {
+            tx,
+            status,
+            headers,
+            buf,
+            ..
+        }) = tee.take()
+        {
+            let _ = tx.send(Ok(Some(Buffered {
+                status,
+                headers,
+                body: buf.freeze(),
+                trailers,
+            })));
+        }
+    }
+}
+
+// === impl SharedBody ===
+
+impl HttpBody for SharedBody {
+    type Data = Bytes;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.data.is_none() && self.trailers.is_none()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        Poll::Ready(self.get_mut().data.take().map(Ok))
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap<HeaderValue>>, Self::Error>> {
+        Poll::Ready(Ok(self.get_mut().trailers.take()))
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        let len = self.data.as_ref().map(|d| d.len() as u64).unwrap_or(0);
+        http_body::SizeHint::with_exact(len)
+    }
+}
+
+// === impl Shared ===
+
+impl std::fmt::Display for Shared {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for Shared {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&**self.0)
+    }
+}
@@ -0,0 +1,296 @@
+use super::*;
+use linkerd_app_core::svc::{Layer, NewService, ServiceExt};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+const REQUESTS: usize = 5;
+
+fn config() -> RequestCoalescingConfig {
+    RequestCoalescingConfig {
+        key_headers: vec![HeaderName::from_static("x-tenant")].into(),
+        max_body_bytes: 1024,
+    }
+}
+
+/// Builds a route service that responds after a delay and counts the requests
+/// it receives.
+fn route(
+    calls: Arc<AtomicUsize>,
+) -> CoalesceRequests<
+    impl svc::Service<
+            http::Request<http::BoxBody>,
+            Response = http::Response<http::BoxBody>,
+            Error = Error,
+            Future = impl Send,
+        > + Clone,
+> {
+    route_with(calls, body)
+}
+
+/// Builds a route service that responds with the bodies built by `mk_body`
+/// after a delay.
+fn route_with(
+    calls: Arc<AtomicUsize>,
+    mk_body: fn(usize) -> http::BoxBody,
+) -> CoalesceRequests<
+    impl svc::Service<
+            http::Request<http::BoxBody>,
+            Response = http::Response<http::BoxBody>,
+            Error = Error,
+            Future = impl Send,
+        > + Clone,
+> {
+    route_with_headers(calls, HeaderMap::new(), mk_body)
+}
+
+/// Builds a route service that responds with `headers` and the bodies built by
+/// `mk_body` after a delay.
+fn route_with_headers(
+    calls: Arc<AtomicUsize>,
+    headers: HeaderMap,
+    mk_body: fn(usize) -> http::BoxBody,
+) -> CoalesceRequests<
+    impl svc::Service<
+            http::Request<http::BoxBody>,
+            Response = http::Response<http::BoxBody>,
+            Error = Error,
+            Future = impl Send,
+        > + Clone,
+> {
+    NewCoalesceRequests::layer(Some(config()))
+        .layer(move |()| {
+            let calls = calls.clone();
+            let headers = headers.clone();
+            svc::mk(move |_: http::Request<http::BoxBody>| {
+                let n = calls.fetch_add(1, Ordering::SeqCst);
+                let headers = headers.clone();
+                async move {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    let mut rsp = http::Response::new(mk_body(n));
+                    *rsp.headers_mut() = headers;
+                    Ok::<_, Error>(rsp)
+                }
+            })
+        })
+        .new_service(())
+}
+
+fn body(n: usize) -> http::BoxBody {
+    http::BoxBody::new(http_body::Full::new(Bytes::from(format!("response {}", n))))
+}
+
+/// Builds a body that is larger than the configured limit.
+fn large_body(n: usize) -> http::BoxBody {
+    let body = format!("response {}", n).repeat(config().max_body_bytes);
+    http::BoxBody::new(http_body::Full::new(Bytes::from(body)))
+}
+
+/// Sends `REQUESTS` concurrent requests built by `mk_req`, returning their
+/// response bodies.
+async fn send_concurrently<S>(
+    svc: S,
+    mk_req: impl Fn(usize) -> http::Request<http::BoxBody>,
+) -> Vec<String>
+where
+    S: svc::Service<
+            http::Request<http::BoxBody>,
+            Response = http::Response<http::BoxBody>,
+            Error = Error,
+        > + Clone
+        + Send
+        + 'static,
+    S::Future: Send,
+{
+    let rsps = (0..REQUESTS)
+        .map(|i| {
+            let rsp = svc.clone().oneshot(mk_req(i));
+            tokio::spawn(async move {
+                let rsp = rsp.await.expect("request must succeed");
+                let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+                String::from_utf8(body.to_vec()).unwrap()
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut bodies = Vec::new();
+    for rsp in rsps {
+        bodies.push(rsp.await.unwrap());
+    }
+    bodies
+}
+
+fn req(method: http::Method, tenant: &str) -> http::Request<http::BoxBody> {
+    http::Request::builder()
+        .method(method)
+        .uri("http://foo.example.com/bar")
+        .header("x-tenant", tenant)
+        .body(http::BoxBody::default())
+        .unwrap()
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn coalesces_identical_gets() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let svc = route(calls.clone());
+    let bodies = send_concurrently(svc.clone(), |_| req(http::Method::GET, "a")).await;
+    assert_eq!(calls.load(Ordering::SeqCst), 1, "must dispatch one request");
+    assert_eq!(bodies, vec!["response 0"; REQUESTS]);
+
+    // Once the response is complete, requests are dispatched anew.
+    let bodies = send_concurrently(svc, |_| req(http::Method::GET, "a")).await;
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(bodies, vec!["response 1"; REQUESTS]);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn does_not_coalesce_distinct_key_headers() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    send_concurrently(route(calls.clone()), |i| {
+        req(http::Method::GET, &i.to_string())
+    })
+    .await;
+    assert_eq!(calls.load(Ordering::SeqCst), REQUESTS);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn does_not_coalesce_distinct_credentials() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    for name in [header::AUTHORIZATION, header::COOKIE] {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let bodies = send_concurrently(route(calls.clone()), |i| {
+            let mut req = req(http::Method::GET, "a");
+            let value = HeaderValue::from_str(&format!("secret-{}", i % 2)).unwrap();
+            req.headers_mut().insert(name.clone(), value);
+            req
+        })
+        .await;
+        assert_eq!(
+            calls.load(Ordering::SeqCst),
+            2,
+            "each {} must be dispatched",
+            name
+        );
+        // Requests with the same credentials may still be coalesced, but never
+        // with those of other clients.
+        for (i, body) in bodies.iter().enumerate() {
+            assert_eq!(body, &format!("response {}", i % 2), "{}", name);
+        }
+    }
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn does_not_share_private_responses() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    for (name, value) in [
+        (header::CACHE_CONTROL, "private"),
+        (header::CACHE_CONTROL, "max-age=60, no-store"),
+        (header::SET_COOKIE, "session=abc"),
+    ] {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut headers = HeaderMap::new();
+        headers.insert(name.clone(), HeaderValue::from_static(value));
+        send_concurrently(route_with_headers(calls.clone(), headers, body), |_| {
+            req(http::Method::GET, "a")
+        })
+        .await;
+        assert_eq!(
+            calls.load(Ordering::SeqCst),
+            REQUESTS,
+            "responses with {}: {} must not be shared",
+            name,
+            value
+        );
+    }
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn does_not_coalesce_non_idempotent_requests() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    send_concurrently(route(calls.clone()), |_| req(http::Method::POST, "a")).await;
+    assert_eq!(calls.load(Ordering::SeqCst), REQUESTS);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn streams_responses_without_waiting_requests() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let rsp = route_with(calls.clone(), large_body)
+        .oneshot(req(http::Method::GET, "a"))
+        .await
+        .expect("request must succeed");
+    assert!(rsp.extensions().get::<Coalesced>().is_none());
+    let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+    assert_eq!(body.len(), "response 0".len() * config().max_body_bytes);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn dispatches_waiting_requests_when_response_is_too_large() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let bodies = send_concurrently(route_with(calls.clone(), large_body), |_| {
+        req(http::Method::GET, "a")
+    })
+    .await;
+    assert_eq!(
+        calls.load(Ordering::SeqCst),
+        REQUESTS,
+        "waiting requests must be dispatched on their own"
+    );
+    for (i, body) in bodies.iter().enumerate() {
+        assert_eq!(body.len(), "response 0".len() * config().max_body_bytes);
+        if i == 0 {
+            assert!(body.starts_with("response 0"));
+        }
+    }
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn shares_trailers() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let svc = route_with(calls.clone(), |_| {
+        let (mut tx, body) = hyper::Body::channel();
+        tokio::spawn(async move {
+            tx.send_data(Bytes::from_static(b"response")).await.unwrap();
+            let mut trailers = HeaderMap::new();
+            trailers.insert("grpc-status", HeaderValue::from_static("0"));
+            tx.send_trailers(trailers).await.unwrap();
+        });
+        http::BoxBody::new(body)
+    });
+    let rsps = (0..REQUESTS)
+        .map(|_| {
+            let rsp = svc.clone().oneshot(req(http::Method::GET, "a"));
+            tokio::spawn(async move {
+                let rsp = rsp.await.expect("request must succeed");
+                let mut body = rsp.into_body();
+                let mut data = BytesMut::new();
+                while let Some(chunk) = body.data().await {
+                    let mut chunk = chunk.unwrap();
+                    data.extend_from_slice(&chunk.copy_to_bytes(chunk.remaining()));
+                }
+                let trailers = body.trailers().await.unwrap();
+                (data.freeze(), trailers)
+            })
+        })
+        .collect::<Vec<_>>();
+    for rsp in rsps {
+        let (data, trailers) = rsp.await.unwrap();
+        assert_eq!(data, "response");
+        let trailers = trailers.expect("response must have trailers");
+        assert_eq!(trailers.get("grpc-status").unwrap(), "0");
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1, "must dispatch one request");
+}
@@ -4,6 +4,9 @@ use std::task::{Context, Poll};
 use thiserror::Error;
 use tracing::{debug, trace};
 
+#[cfg(test)]
+mod tests;
+
 const HEADER_NAME: &str = "l5d-require-id";
 
 #[derive(Clone, Debug)]
@@ -17,6 +20,17 @@ pub(super) struct RequireIdentity<N> {
     inner: N,
 }
 
+/// Set as a request extension on routes whose requests may only be sent to
+/// endpoints over mTLS.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct RequireMtls(pub bool);
+
+#[derive(Debug, Error)]
+#[error("route requires mTLS, but the endpoint cannot be meshed: {reason}")]
+pub(crate) struct MtlsRequired {
+    pub reason: tls::NoClientTls,
+}
+
 #[derive(Debug, Error)]
 #[error("required id {required:?}; found {found:?}")]
 pub(crate) struct IdentityRequired {
@@ -78,6 +92,16 @@ where
     }
 
     fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // Routes may require that their requests are only sent over mTLS, so
+        // the request fails if the endpoint is not meshed.
+        if let Some(RequireMtls(true)) = request.extensions().get::<RequireMtls>() {
+            if let Conditional::None(reason) = self.tls {
+                debug!(%reason, "Route requires mTLS");
+                let e = MtlsRequired { reason };
+                return future::Either::Left(future::err(e.into()));
+            }
+        }
+
         // If the `l5d-require-id` header is present, then we should expect the target's
         // `peer_identity` to match; if the two values do not match or there is no `peer_identity`,
         // then we fail the request.
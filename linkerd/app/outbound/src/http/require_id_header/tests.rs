@@ -0,0 +1,60 @@
+use super::*;
+use crate::http;
+use linkerd_app_core::svc::{Layer, NewService, ServiceExt};
+
+#[derive(Clone, Debug)]
+struct Target(tls::ConditionalClientTls);
+
+impl svc::Param<tls::ConditionalClientTls> for Target {
+    fn param(&self) -> tls::ConditionalClientTls {
+        self.0.clone()
+    }
+}
+
+fn meshed() -> Target {
+    Target(Conditional::Some(tls::ClientTls {
+        server_id: tls::ServerId(
+            "foo.ns1.serviceaccount.identity.linkerd.cluster.local"
+                .parse()
+                .unwrap(),
+        ),
+        alpn: None,
+    }))
+}
+
+fn plaintext() -> Target {
+    Target(Conditional::None(
+        tls::NoClientTls::NotProvidedByServiceDiscovery,
+    ))
+}
+
+async fn send(target: Target, require_mtls: bool) -> Result<http::Response<http::BoxBody>, Error> {
+    let svc = NewRequireIdentity::layer()
+        .layer(|_: Target| {
+            svc::mk(|_: http::Request<http::BoxBody>| {
+                future::ok::<_, Error>(http::Response::new(http::BoxBody::default()))
+            })
+        })
+        .new_service(target);
+    let mut req = http::Request::new(http::BoxBody::default());
+    req.extensions_mut().insert(RequireMtls(require_mtls));
+    svc.oneshot(req).await
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn mtls_required_routes_fail_on_plaintext_endpoints() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    send(meshed(), true)
+        .await
+        .expect("meshed endpoints must satisfy the route");
+
+    let error = send(plaintext(), true)
+        .await
+        .expect_err("plaintext endpoints must not satisfy the route");
+    assert!(error.is::<MtlsRequired>(), "unexpected error: {error}");
+
+    send(plaintext(), false)
+        .await
+        .expect("routes that do not require mTLS may use plaintext endpoints");
+}
@@ -0,0 +1,303 @@
+//! Limits the size of each HTTP route's response bodies.
+//!
+//! Routes may be configured with a maximum response body size. Responses whose
+//! `Content-Length` exceeds the limit are handled before their headers are
+//! sent: they either fail with a [`ResponseBodyTooLarge`] error, so that the
+//! client receives a `502 Bad Gateway`, or are truncated and marked with an
+//! `l5d-response-truncated` header. Other bodies are checked as they are
+//! streamed: an over-size body either fails or is truncated and ends with an
+//! `l5d-response-truncated` trailer.
+
+use crate::http::{
+    self,
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    HttpBody,
+};
+use bytes::{Buf, Bytes};
+use futures::ready;
+use linkerd_app_core::{svc, Error};
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tracing::debug;
+
+#[cfg(test)]
+mod tests;
+
+/// Determines how response bodies that exceed their route's limit are handled.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ResponseBodyLimitMode {
+    /// The response fails.
+    #[default]
+    Error,
+
+    /// The body is truncated to the limit.
+    Truncate,
+}
+
+/// A route's response body limit.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct ResponseBodyLimit {
+    pub max_bytes: usize,
+    pub mode: ResponseBodyLimitMode,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("response body exceeds the route's limit of {max_bytes} bytes")]
+pub(crate) struct ResponseBodyTooLarge {
+    max_bytes: usize,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewLimitResponseBody<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct LimitResponseBody<S> {
+    inner: S,
+    limit: Option<ResponseBodyLimit>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    limit: Option<ResponseBodyLimit>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct LimitedBody<B> {
+    #[pin]
+    inner: B,
+    limit: Option<ResponseBodyLimit>,
+    /// The number of bytes that may still be read from the inner body.
+    remaining: usize,
+    /// Set once the body has been truncated, until its trailers are emitted.
+    truncated: Option<bool>,
+    /// Set when the response's headers already mark it as truncated, so that
+    /// no trailer is emitted.
+    marked: bool,
+}
+
+static TRUNCATED: HeaderName = HeaderName::from_static("l5d-response-truncated");
+
+// === impl NewLimitResponseBody ===
+
+impl<N> NewLimitResponseBody<N> {
+    pub fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewLimitResponseBody<N>
+where
+    T: svc::Param<Option<ResponseBodyLimit>>,
+    N: svc::NewService<T>,
+{
+    type Service = LimitResponseBody<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        LimitResponseBody {
+            limit: target.param(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl LimitResponseBody ===
+
+impl<S, Req, B> svc::Service<Req> for LimitResponseBody<S>
+where
+    S: svc::Service<Req, Response = http::Response<B>>,
+    S::Error: Into<Error>,
+{
+    type Response = http::Response<LimitedBody<B>>;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        ResponseFuture {
+            inner: self.inner.call(req),
+            limit: self.limit,
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+    E: Into<Error>,
+{
+    type Output = Result<http::Response<LimitedBody<B>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut rsp = ready!(this.inner.poll(cx)).map_err(Into::into)?;
+
+        let limit = match *this.limit {
+            Some(limit) => limit,
+            None => return Poll::Ready(Ok(rsp.map(|inner| LimitedBody::new(inner, None)))),
+        };
+
+        let content_length = rsp
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        if let Some(len) = content_length.filter(|len| *len > limit.max_bytes as u64) {
+            match limit.mode {
+                ResponseBodyLimitMode::Error => {
+                    debug!(
+                        content_length = len,
+                        max_bytes = limit.max_bytes,
+                        "Response too large"
+                    );
+                    return Poll::Ready(Err(ResponseBodyTooLarge {
+                        max_bytes: limit.max_bytes,
+                    }
+                    .into()));
+                }
+                ResponseBodyLimitMode::Truncate => {
+                    debug!(
+                        content_length = len,
+                        max_bytes = limit.max_bytes,
+                        "Truncating response"
+                    );
+                    let headers = rsp.headers_mut();
+                    headers.insert(header::CONTENT_LENGTH, limit.max_bytes.into());
+                    headers.insert(TRUNCATED.clone(), HeaderValue::from_static("true"));
+                    return Poll::Ready(Ok(rsp.map(|inner| LimitedBody {
+                        marked: true,
+                        ..LimitedBody::new(inner, Some(limit))
+                    })));
+                }
+            }
+        }
+
+        Poll::Ready(Ok(rsp.map(|inner| LimitedBody::new(inner, Some(limit)))))
+    }
+}
+
+// === impl LimitedBody ===
+
+impl<B> LimitedBody<B> {
+    fn new(inner: B, limit: Option<ResponseBodyLimit>) -> Self {
+        Self {
+            inner,
+            remaining: limit.map_or(0, |l| l.max_bytes),
+            limit,
+            truncated: None,
+            marked: false,
+        }
+    }
+}
+
+impl<B> HttpBody for LimitedBody<B>
+where
+    B: HttpBody,
+    B::Error: Into<Error>,
+{
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        if this.truncated.is_some() {
+            return Poll::Ready(None);
+        }
+
+        let mut data = match ready!(this.inner.poll_data(cx)) {
+            Some(Ok(data)) => data,
+            Some(Err(e)) => return Poll::Ready(Some(Err(e.into()))),
+            None => return Poll::Ready(None),
+        };
+        let limit = match this.limit {
+            Some(limit) => limit,
+            None => return Poll::Ready(Some(Ok(data.copy_to_bytes(data.remaining())))),
+        };
+
+        if data.remaining() <= *this.remaining {
+            *this.remaining -= data.remaining();
+            return Poll::Ready(Some(Ok(data.copy_to_bytes(data.remaining()))));
+        }
+
+        match limit.mode {
+            ResponseBodyLimitMode::Error => {
+                debug!(max_bytes = limit.max_bytes, "Response body too large");
+                Poll::Ready(Some(Err(ResponseBodyTooLarge {
+                    max_bytes: limit.max_bytes,
+                }
+                .into())))
+            }
+            ResponseBodyLimitMode::Truncate => {
+                debug!(max_bytes = limit.max_bytes, "Truncating response body");
+                // Responses whose headers mark the truncation have no trailer.
+                *this.truncated = Some(*this.marked);
+                match std::mem::take(this.remaining) {
+                    0 => Poll::Ready(None),
+                    n => Poll::Ready(Some(Ok(data.copy_to_bytes(n)))),
+                }
+            }
+        }
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap<HeaderValue>>, Self::Error>> {
+        let this = self.project();
+        match this.truncated {
+            // The inner body's trailers are not read after it is truncated.
+            Some(false) => {
+                *this.truncated = Some(true);
+                let mut trailers = HeaderMap::new();
+                trailers.insert(TRUNCATED.clone(), HeaderValue::from_static("true"));
+                Poll::Ready(Ok(Some(trailers)))
+            }
+            Some(true) => Poll::Ready(Ok(None)),
+            None => this.inner.poll_trailers(cx).map_err(Into::into),
+        }
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        match self.truncated {
+            Some(sent_trailers) => sent_trailers,
+            None => self.inner.is_end_stream(),
+        }
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        let hint = self.inner.size_hint();
+        if self.limit.is_none() {
+            return hint;
+        }
+        if self.truncated.is_some() {
+            return http_body::SizeHint::with_exact(0);
+        }
+        match hint.upper() {
+            Some(upper) if upper <= self.remaining as u64 => hint,
+            _ => {
+                let mut hint = http_body::SizeHint::new();
+                hint.set_upper(self.remaining as u64);
+                hint
+            }
+        }
+    }
+}
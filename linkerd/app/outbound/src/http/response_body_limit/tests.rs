@@ -0,0 +1,131 @@
+use super::*;
+use linkerd_app_core::svc::{Layer, NewService, ServiceExt};
+
+#[derive(Clone, Debug)]
+struct Target(Option<ResponseBodyLimit>);
+
+impl svc::Param<Option<ResponseBodyLimit>> for Target {
+    fn param(&self) -> Option<ResponseBodyLimit> {
+        self.0
+    }
+}
+
+const BODY: &[u8] = b"0123456789";
+
+fn limit(max_bytes: usize, mode: ResponseBodyLimitMode) -> Target {
+    Target(Some(ResponseBodyLimit { max_bytes, mode }))
+}
+
+/// Sends a request to a backend that responds with `BODY`, either with a
+/// `Content-Length` or streamed in two chunks.
+async fn send(
+    target: Target,
+    content_length: bool,
+) -> Result<http::Response<LimitedBody<http::BoxBody>>, Error> {
+    let svc = NewLimitResponseBody::layer()
+        .layer(move |_: Target| {
+            svc::mk(move |_: http::Request<http::BoxBody>| async move {
+                let rsp = if content_length {
+                    http::Response::builder()
+                        .header(header::CONTENT_LENGTH, BODY.len())
+                        .body(http::BoxBody::new(http_body::Full::new(
+                            Bytes::from_static(BODY),
+                        )))
+                        .unwrap()
+                } else {
+                    let (mut tx, body) = hyper::Body::channel();
+                    tokio::spawn(async move {
+                        let (a, b) = BODY.split_at(BODY.len() / 2);
+                        tx.send_data(Bytes::from_static(a)).await?;
+                        tx.send_data(Bytes::from_static(b)).await
+                    });
+                    http::Response::new(http::BoxBody::new(body))
+                };
+                Ok::<_, Error>(rsp)
+            })
+        })
+        .new_service(target);
+    svc.oneshot(http::Request::new(http::BoxBody::default()))
+        .await
+}
+
+/// Reads a body, returning its data and trailers.
+async fn read<B: HttpBody + Unpin>(mut body: B) -> Result<(Vec<u8>, Option<HeaderMap>), B::Error> {
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        data.extend_from_slice(chunk?.chunk());
+    }
+    let trailers = body.trailers().await?;
+    Ok((data, trailers))
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn passes_bodies_within_limit() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    for content_length in [true, false] {
+        let rsp = send(
+            limit(BODY.len(), ResponseBodyLimitMode::Error),
+            content_length,
+        )
+        .await
+        .expect("response must succeed");
+        assert!(rsp.headers().get(&TRUNCATED).is_none());
+        let (data, trailers) = read(rsp.into_body()).await.expect("body must succeed");
+        assert_eq!(data, BODY);
+        assert!(trailers.is_none());
+    }
+
+    let rsp = send(Target(None), false).await.unwrap();
+    let (data, _) = read(rsp.into_body()).await.unwrap();
+    assert_eq!(data, BODY);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn truncates_oversize_bodies() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    // When the body's length is known, the truncation is marked in headers
+    // and not again in trailers.
+    let rsp = send(limit(4, ResponseBodyLimitMode::Truncate), true)
+        .await
+        .expect("response must succeed");
+    assert_eq!(rsp.headers()[header::CONTENT_LENGTH], "4");
+    assert_eq!(rsp.headers()[&TRUNCATED], "true");
+    let (data, trailers) = read(rsp.into_body()).await.expect("body must succeed");
+    assert_eq!(data, &BODY[..4]);
+    assert!(trailers.is_none(), "trailers must not be set");
+
+    // Otherwise, it is marked in trailers.
+    let rsp = send(limit(7, ResponseBodyLimitMode::Truncate), false)
+        .await
+        .expect("response must succeed");
+    assert!(rsp.headers().get(&TRUNCATED).is_none());
+    let (data, trailers) = read(rsp.into_body()).await.expect("body must succeed");
+    assert_eq!(data, &BODY[..7]);
+    assert_eq!(trailers.expect("trailers must be set")[&TRUNCATED], "true");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn fails_oversize_bodies() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    // When the body's length is known, the response fails before it is sent.
+    let error = send(limit(4, ResponseBodyLimitMode::Error), true)
+        .await
+        .expect_err("response must fail");
+    assert!(
+        error.is::<ResponseBodyTooLarge>(),
+        "unexpected error: {error}"
+    );
+
+    // Otherwise, the body fails once it exceeds the limit.
+    let rsp = send(limit(7, ResponseBodyLimitMode::Error), false)
+        .await
+        .expect("response must succeed");
+    let error = read(rsp.into_body()).await.expect_err("body must fail");
+    assert!(
+        error.is::<ResponseBodyTooLarge>(),
+        "unexpected error: {error}"
+    );
+}
@@ -0,0 +1,437 @@
+//! A per-route cache of responses to idempotent `GET` requests.
+//!
+//! Responses are only cached when the upstream explicitly marks them as
+//! cacheable with a `Cache-Control: max-age` (or `s-maxage`) directive. They
+//! are never cached when marked `no-store`, `private`, or `no-cache`, or when
+//! they set cookies or vary on request headers. Requests that carry
+//! credentials (an `Authorization` or `Cookie` header) or conditional headers
+//! bypass the cache entirely, since entries are keyed only by URI.
+//!
+//! Cached responses are served until they become stale. A stale response with
+//! an `ETag` is revalidated with an `If-None-Match` request, so that a `304 Not
+//! Modified` response refreshes and serves the cached response.
+
+use crate::http::{
+    self,
+    header::{self, HeaderMap, HeaderValue},
+    HttpBody,
+};
+use bytes::{Buf, Bytes, BytesMut};
+use futures::{future, ready, FutureExt, TryFutureExt};
+use linkerd_app_core::{svc, Error};
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::Instant;
+use tracing::{debug, trace};
+
+#[cfg(test)]
+mod tests;
+
+/// Configures the response cache for each HTTP route.
+#[derive(Clone, Debug)]
+pub struct ResponseCacheConfig {
+    /// The maximum number of responses cached for each route.
+    pub max_entries: usize,
+
+    /// The maximum amount of time a response may be cached, regardless of its
+    /// `Cache-Control` directives.
+    pub max_ttl: Duration,
+
+    /// The largest response body that may be cached.
+    pub max_body_bytes: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewResponseCache<N> {
+    inner: N,
+    config: Option<Arc<ResponseCacheConfig>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ResponseCache<S> {
+    inner: S,
+    store: Option<Arc<Store>>,
+}
+
+#[derive(Debug)]
+struct Store {
+    config: Arc<ResponseCacheConfig>,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    status: http::StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    expires: Instant,
+}
+
+/// A response extension that marks a response that was served from the cache.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct CacheHit;
+
+/// A response body that copies its data into the cache when it completes.
+#[pin_project]
+struct CacheBody {
+    #[pin]
+    inner: http::BoxBody,
+    capture: Option<Capture>,
+}
+
+struct Capture {
+    store: Arc<Store>,
+    key: String,
+    status: http::StatusCode,
+    headers: HeaderMap,
+    expires: Instant,
+    buf: BytesMut,
+
+    /// Set once the body's data has been read and the response has been
+    /// cached.
+    cached: bool,
+}
+
+// === impl NewResponseCache ===
+
+impl<N> NewResponseCache<N> {
+    /// When `config` is `None`, responses are not cached.
+    pub fn layer(
+        config: Option<ResponseCacheConfig>,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        let config = config.map(Arc::new);
+        svc::layer::mk(move |inner| Self {
+            inner,
+            config: config.clone(),
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewResponseCache<N>
+where
+    N: svc::NewService<T>,
+{
+    type Service = ResponseCache<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        // Each route has its own store, so that responses are never shared
+        // across routes.
+        let store = self.config.clone().map(|config| {
+            Arc::new(Store {
+                config,
+                entries: Mutex::new(HashMap::default()),
+            })
+        });
+        ResponseCache {
+            inner: self.inner.new_service(target),
+            store,
+        }
+    }
+}
+
+// === impl ResponseCache ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for ResponseCache<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = future::BoxFuture<'static, Result<Self::Response, Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut req: http::Request<http::BoxBody>) -> Self::Future {
+        let store = match self.store.as_ref() {
+            Some(store) if is_cacheable_request(&req) => store.clone(),
+            _ => return Box::pin(self.inner.call(req).err_into::<Error>()),
+        };
+
+        let key = req.uri().to_string();
+        let cached = store.get(&key);
+        if let Some(entry) = cached.as_ref() {
+            if entry.expires > Instant::now() {
+                trace!(%key, "Serving cached response");
+                return Box::pin(future::ok(entry.to_response()));
+            }
+            if let Some(etag) = entry.headers.get(header::ETAG) {
+                trace!(%key, "Revalidating stale response");
+                req.headers_mut()
+                    .insert(header::IF_NONE_MATCH, etag.clone());
+            }
+        }
+
+        Box::pin(self.inner.call(req).err_into::<Error>().map(move |res| {
+            let rsp = res?;
+            if let Some(entry) = cached {
+                if rsp.status() == http::StatusCode::NOT_MODIFIED
+                    && entry.headers.contains_key(header::ETAG)
+                {
+                    let rsp = match store.freshness(rsp.headers()) {
+                        Some(ttl) => store.insert(
+                            key,
+                            Entry {
+                                expires: Instant::now() + ttl,
+                                ..entry
+                            },
+                        ),
+                        None => {
+                            store.remove(&key);
+                            entry
+                        }
+                    }
+                    .to_response();
+                    return Ok(rsp);
+                }
+            }
+            Ok(store.capture(key, rsp))
+        }))
+    }
+}
+
+fn is_cacheable_request<B>(req: &http::Request<B>) -> bool {
+    let headers = req.headers();
+    req.method() == http::Method::GET
+        && !headers.contains_key(header::AUTHORIZATION)
+        && !headers.contains_key(header::COOKIE)
+        && !headers.contains_key(header::RANGE)
+        && !headers.contains_key(header::IF_NONE_MATCH)
+        && !headers.contains_key(header::IF_MODIFIED_SINCE)
+        && !cache_control(headers).any(|d| d == "no-store" || d == "no-cache")
+}
+
+/// Iterates over the lowercased directives of all `Cache-Control` headers.
+pub(super) fn cache_control(headers: &HeaderMap) -> impl Iterator<Item = String> + '_ {
+    headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|d| d.trim().to_ascii_lowercase())
+}
+
+// === impl Store ===
+
+impl Store {
+    fn get(&self, key: &str) -> Option<Entry> {
+        self.entries.lock().get(key).cloned()
+    }
+
+    fn remove(&self, key: &str) {
+        self.entries.lock().remove(key);
+    }
+
+    fn insert(&self, key: String, entry: Entry) -> Entry {
+        let mut entries = self.entries.lock();
+        if entries.len() >= self.config.max_entries && !entries.contains_key(&key) {
+            // Make room by dropping stale entries and, if the cache is still
+            // full, the entry closest to expiring.
+            let now = Instant::now();
+            entries.retain(|_, e| e.expires > now);
+            if entries.len() >= self.config.max_entries {
+                let oldest = entries
+                    .iter()
+                    .min_by_key(|(_, e)| e.expires)
+                    .map(|(k, _)| k.clone());
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        if self.config.max_entries > 0 {
+            debug!(%key, expires = ?entry.expires, "Caching response");
+            entries.insert(key, entry.clone());
+        }
+        entry
+    }
+
+    /// Returns the amount of time for which a response may be cached, if it is
+    /// cacheable.
+    fn freshness(&self, headers: &HeaderMap) -> Option<Duration> {
+        if headers.contains_key(header::SET_COOKIE) || headers.contains_key(header::VARY) {
+            return None;
+        }
+
+        let (mut max_age, mut s_maxage) = (None, None);
+        for directive in cache_control(headers) {
+            match directive.split_once('=') {
+                Some(("max-age", secs)) => max_age = secs.trim_matches('"').parse().ok(),
+                Some(("s-maxage", secs)) => s_maxage = secs.trim_matches('"').parse().ok(),
+                None if directive == "no-store"
+                    || directive == "private"
+                    || directive == "no-cache" =>
+                {
+                    return None;
+                }
+                _ => {}
+            }
+        }
+        let secs = s_maxage.or(max_age)?;
+        if secs == 0 {
+            return None;
+        }
+        Some(Duration::from_secs(secs).min(self.config.max_ttl))
+    }
+
+    /// Wraps the response's body so that the response is cached once the body
+    /// has been read, if the response is cacheable.
+    fn capture(
+        self: Arc<Self>,
+        key: String,
+        rsp: http::Response<http::BoxBody>,
+    ) -> http::Response<http::BoxBody> {
+        if rsp.status() != http::StatusCode::OK {
+            return rsp;
+        }
+        let ttl = match self.freshness(rsp.headers()) {
+            Some(ttl) => ttl,
+            None => return rsp,
+        };
+        let too_large = rsp
+            .body()
+            .size_hint()
+            .upper()
+            .map(|n| n > self.config.max_body_bytes as u64)
+            .unwrap_or(false);
+        if too_large {
+            return rsp;
+        }
+
+        let (parts, body) = rsp.into_parts();
+        let mut capture = Capture {
+            key,
+            status: parts.status,
+            headers: parts.headers.clone(),
+            expires: Instant::now() + ttl,
+            buf: BytesMut::new(),
+            cached: false,
+            store: self,
+        };
+        // Empty bodies may never be polled.
+        if body.is_end_stream() {
+            capture.finish();
+        }
+        http::Response::from_parts(
+            parts,
+            http::BoxBody::new(CacheBody {
+                inner: body,
+                capture: Some(capture),
+            }),
+        )
+    }
+}
+
+// === impl Entry ===
+
+impl Entry {
+    fn to_response(&self) -> http::Response<http::BoxBody> {
+        let mut rsp =
+            http::Response::new(http::BoxBody::new(http_body::Full::new(self.body.clone())));
+        *rsp.status_mut() = self.status;
+        *rsp.headers_mut() = self.headers.clone();
+        rsp.extensions_mut().insert(CacheHit);
+        rsp
+    }
+}
+
+// === impl Capture ===
+
+impl Capture {
+    /// Caches the response once its body's data has been read.
+    fn finish(&mut self) {
+        if self.cached {
+            return;
+        }
+        self.cached = true;
+        self.store.insert(
+            self.key.clone(),
+            Entry {
+                status: self.status,
+                headers: std::mem::take(&mut self.headers),
+                body: std::mem::take(&mut self.buf).freeze(),
+                expires: self.expires,
+            },
+        );
+    }
+}
+
+// === impl CacheBody ===
+
+impl HttpBody for CacheBody {
+    type Data = Bytes;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let mut this = self.project();
+        let data = ready!(this.inner.as_mut().poll_data(cx))
+            .map(|res| res.map(|mut data| data.copy_to_bytes(data.remaining())));
+        if let Some(capture) = this.capture.as_mut().filter(|c| !c.cached) {
+            match &data {
+                Some(Ok(data)) => {
+                    if capture.buf.len() + data.len() > capture.store.config.max_body_bytes {
+                        // The body is too large to be cached.
+                        *this.capture = None;
+                    } else {
+                        capture.buf.extend_from_slice(data);
+                        // Callers, like hyper, may stop polling the body once
+                        // it reports the end of the stream.
+                        if this.inner.is_end_stream() {
+                            capture.finish();
+                        }
+                    }
+                }
+                Some(Err(_)) => *this.capture = None,
+                None => capture.finish(),
+            }
+        }
+        Poll::Ready(data)
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap<HeaderValue>>, Self::Error>> {
+        let this = self.project();
+        let trailers = ready!(this.inner.poll_trailers(cx));
+        match &trailers {
+            Ok(None) => {
+                if let Some(capture) = this.capture.as_mut() {
+                    capture.finish();
+                }
+            }
+            _ => {
+                // Responses with trailers are not cached, since the cache does
+                // not store them.
+                if let Some(capture) = this.capture.take().filter(|c| c.cached) {
+                    capture.store.remove(&capture.key);
+                }
+            }
+        }
+        Poll::Ready(trailers)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,191 @@
+use super::*;
+use linkerd_app_core::svc::{Layer, NewService, ServiceExt};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+fn config() -> ResponseCacheConfig {
+    ResponseCacheConfig {
+        max_entries: 10,
+        max_ttl: Duration::from_secs(60),
+        max_body_bytes: 1024,
+    }
+}
+
+/// Builds a route service that responds with the given `Cache-Control` header
+/// and counts the requests it receives.
+fn route(
+    cache_control: &'static str,
+    calls: Arc<AtomicUsize>,
+) -> ResponseCache<
+    impl svc::Service<
+            http::Request<http::BoxBody>,
+            Response = http::Response<http::BoxBody>,
+            Error = Error,
+            Future = impl Send,
+        > + Clone,
+> {
+    NewResponseCache::layer(Some(config()))
+        .layer(move |()| {
+            let calls = calls.clone();
+            svc::mk(move |_: http::Request<http::BoxBody>| {
+                let n = calls.fetch_add(1, Ordering::SeqCst);
+                future::ok::<_, Error>(
+                    http::Response::builder()
+                        .header(header::CACHE_CONTROL, cache_control)
+                        .body(http::BoxBody::new(http_body::Full::new(Bytes::from(
+                            format!("response {}", n),
+                        ))))
+                        .unwrap(),
+                )
+            })
+        })
+        .new_service(())
+}
+
+async fn get<S>(svc: S) -> String
+where
+    S: svc::Service<
+        http::Request<http::BoxBody>,
+        Response = http::Response<http::BoxBody>,
+        Error = Error,
+    >,
+{
+    let req = http::Request::get("http://foo.example.com/bar")
+        .body(http::BoxBody::default())
+        .unwrap();
+    send(svc, req).await
+}
+
+async fn get_with_cookie<S>(svc: S, cookie: &'static str) -> String
+where
+    S: svc::Service<
+        http::Request<http::BoxBody>,
+        Response = http::Response<http::BoxBody>,
+        Error = Error,
+    >,
+{
+    let req = http::Request::get("http://foo.example.com/bar")
+        .header(header::COOKIE, cookie)
+        .body(http::BoxBody::default())
+        .unwrap();
+    send(svc, req).await
+}
+
+async fn send<S>(svc: S, req: http::Request<http::BoxBody>) -> String
+where
+    S: svc::Service<
+        http::Request<http::BoxBody>,
+        Response = http::Response<http::BoxBody>,
+        Error = Error,
+    >,
+{
+    let rsp = svc.oneshot(req).await.unwrap();
+    let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+/// Reads a response body the way hyper's server does: polling stops as soon as
+/// the body reports the end of its stream.
+async fn get_until_end_stream<S>(svc: S) -> String
+where
+    S: svc::Service<
+        http::Request<http::BoxBody>,
+        Response = http::Response<http::BoxBody>,
+        Error = Error,
+    >,
+{
+    let req = http::Request::get("http://foo.example.com/bar")
+        .body(http::BoxBody::default())
+        .unwrap();
+    let mut body = svc.oneshot(req).await.unwrap().into_body();
+    let mut buf = BytesMut::new();
+    while !body.is_end_stream() {
+        match body.data().await {
+            Some(data) => {
+                let mut data = data.unwrap();
+                buf.extend_from_slice(&data.copy_to_bytes(data.remaining()));
+            }
+            None => break,
+        }
+    }
+    String::from_utf8(buf.to_vec()).unwrap()
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn caches_fresh_responses() {
+    let _trace = linkerd_tracing::test::trace_init();
+    tokio::time::pause();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let svc = route("public, max-age=10", calls.clone());
+
+    assert_eq!(get(svc.clone()).await, "response 0");
+    assert_eq!(get(svc.clone()).await, "response 0");
+    assert_eq!(calls.load(Ordering::SeqCst), 1, "must be served from cache");
+
+    // Once the response is stale, it must be fetched again.
+    tokio::time::sleep(Duration::from_secs(11)).await;
+    assert_eq!(get(svc).await, "response 1");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn caches_responses_read_until_end_of_stream() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let svc = route("public, max-age=10", calls.clone());
+
+    assert_eq!(get_until_end_stream(svc.clone()).await, "response 0");
+    assert_eq!(get_until_end_stream(svc).await, "response 0");
+    assert_eq!(calls.load(Ordering::SeqCst), 1, "must be served from cache");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn does_not_cache_no_store() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let svc = route("no-store, max-age=10", calls.clone());
+
+    assert_eq!(get(svc.clone()).await, "response 0");
+    assert_eq!(get(svc).await, "response 1");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn does_not_cache_private() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let svc = route("private, max-age=10", calls.clone());
+
+    assert_eq!(get(svc.clone()).await, "response 0");
+    assert_eq!(get(svc).await, "response 1");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn does_not_share_responses_across_cookies() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let svc = route("public, max-age=10", calls.clone());
+
+    assert_eq!(
+        get_with_cookie(svc.clone(), "session=a").await,
+        "response 0"
+    );
+    assert_eq!(
+        get_with_cookie(svc.clone(), "session=b").await,
+        "response 1"
+    );
+    assert_eq!(
+        calls.load(Ordering::SeqCst),
+        2,
+        "must not be served from cache"
+    );
+
+    // Nor are responses to requests with cookies served to other clients.
+    assert_eq!(get(svc).await, "response 2");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
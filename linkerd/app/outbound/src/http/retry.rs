@@ -1,10 +1,10 @@
 use futures::{future, FutureExt};
 use linkerd_app_core::{
-    classify,
+    classify, errors,
     http_metrics::retries::Handle,
     metrics::{self, ProfileRouteLabels},
     profiles::{self, http::Route},
-    proxy::http::{ClientHandle, EraseResponse, HttpBody},
+    proxy::http::{ClientHandle, EraseResponse, HasH2Reason, HttpBody},
     svc::{layer, Either, Param},
     Error,
 };
@@ -14,21 +14,101 @@ use linkerd_http_retry::{
     ReplayBody,
 };
 use linkerd_retry as retry;
-use std::sync::Arc;
+use std::{
+    collections::HashSet,
+    future::Future,
+    hash::{Hash, Hasher},
+    io,
+    pin::Pin,
+    sync::Arc,
+    time::SystemTime,
+};
+use tokio::time::{self, Duration, Instant};
+
+mod attempt_timeout;
+#[cfg(test)]
+mod tests;
 
+pub(crate) use self::attempt_timeout::NewAttemptTimeout;
+
+/// Retries requests on each route according to its retry policy, as
+/// configured by `params`.
 pub fn layer<N>(
     metrics: metrics::HttpProfileRouteRetry,
+    params: RetryParams,
 ) -> impl layer::Layer<N, Service = retry::NewRetry<NewRetryPolicy, N, EraseResponse<()>>> + Clone {
-    retry::layer(NewRetryPolicy::new(metrics))
+    let policy = NewRetryPolicy::new(metrics, params);
+    retry::layer(policy)
         // Because we wrap the response body type on retries, we must include a
         // `Proxy` middleware for unifying the response body types of the retry
         // and non-retry services.
         .with_proxy(EraseResponse::new(()))
 }
 
+/// Configures how requests are retried on routes with retry policies.
+#[derive(Clone, Debug, Default)]
+pub struct RetryParams {
+    /// Requests with bodies larger than this are not retried, since their
+    /// bodies must be buffered so that they can be replayed.
+    pub max_buffered_bytes: usize,
+
+    /// Responses with these statuses are retried, in addition to responses
+    /// that the route classifies as failures.
+    pub retryable_statuses: Arc<HashSet<http::StatusCode>>,
+
+    /// When set, requests on routes with a timeout are only retried if the
+    /// time remaining before the timeout allows for another attempt that takes
+    /// at least as long as the prior attempt and no less than this.
+    pub min_attempt_time: Option<Duration>,
+
+    /// When set, 429 and 503 responses with a `Retry-After` header are retried
+    /// after the delay the header specifies, clamped to this. Otherwise,
+    /// requests are retried immediately.
+    pub max_retry_after: Option<Duration>,
+
+    /// When set, only requests without bodies and requests that include this
+    /// header--marking them as safe to replay--are retried, regardless of
+    /// their methods.
+    pub idempotent_header: Option<http::header::HeaderName>,
+
+    /// Determines how requests that fail because the upstream reset the
+    /// connection or stream are handled.
+    pub on_reset: ResetBehavior,
+
+    /// When set, this is not counted as time remaining before a route's
+    /// timeout, since each attempt is bounded so that the headroom is left for
+    /// the response to be processed.
+    pub timeout_headroom: Option<Duration>,
+}
+
+/// Overrides a route's `RetryParams`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteRetryParams {
+    pub max_buffered_bytes: Option<usize>,
+    pub retryable_statuses: Option<Arc<HashSet<http::StatusCode>>>,
+}
+
+/// Determines how requests are handled when the upstream resets the
+/// connection or stream before the response has been received.
+///
+/// Resets are only observed before the response's headers, or, for HTTP/2
+/// responses, its first body frame, have been received. Once a response is
+/// streamed to the client, later errors are always propagated.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ResetBehavior {
+    /// The error is returned to the client.
+    #[default]
+    Propagate,
+
+    /// Requests with idempotent methods are retried on routes with a retry
+    /// budget. Errors on other requests are returned to the client.
+    RetryIdempotent,
+}
+
 #[derive(Clone, Debug)]
 pub struct NewRetryPolicy {
     metrics: metrics::HttpProfileRouteRetry,
+    params: RetryParams,
 }
 
 #[derive(Clone, Debug)]
@@ -36,67 +116,251 @@ pub struct RetryPolicy {
     metrics: Handle,
     budget: Arc<retry::Budget>,
     response_classes: profiles::http::ResponseClasses,
+    max_buffered_bytes: usize,
+    retryable_statuses: Arc<HashSet<http::StatusCode>>,
+    timeout: Option<Duration>,
+    min_attempt_time: Option<Duration>,
+    max_retry_after: Option<Duration>,
+    idempotent_header: Option<http::header::HeaderName>,
+    on_reset: ResetBehavior,
+    timeout_headroom: Option<Duration>,
+}
+
+/// Records when a request and its latest attempt were dispatched.
+#[derive(Copy, Clone, Debug)]
+struct Attempt {
+    request: Instant,
+    attempt: Instant,
 }
 
-/// Allow buffering requests up to 64 kb
-const MAX_BUFFERED_BYTES: usize = 64 * 1024;
+// === impl RouteRetryParams ===
+
+impl Hash for RouteRetryParams {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.max_buffered_bytes.hash(state);
+        // Equal sets may iterate in different orders, so statuses are hashed
+        // in order.
+        let statuses = self.retryable_statuses.as_ref().map(|statuses| {
+            let mut statuses = statuses.iter().map(|s| s.as_u16()).collect::<Vec<_>>();
+            statuses.sort_unstable();
+            statuses
+        });
+        statuses.hash(state);
+    }
+}
 
 // === impl NewRetryPolicy ===
 
 impl NewRetryPolicy {
-    pub fn new(metrics: metrics::HttpProfileRouteRetry) -> Self {
-        Self { metrics }
+    pub fn new(metrics: metrics::HttpProfileRouteRetry, params: RetryParams) -> Self {
+        Self { metrics, params }
     }
 }
 
 impl<T> retry::NewPolicy<T> for NewRetryPolicy
 where
-    T: Param<Route> + Param<ProfileRouteLabels>,
+    T: Param<Route> + Param<RouteRetryParams> + Param<ProfileRouteLabels>,
 {
     type Policy = RetryPolicy;
 
     fn new_policy(&self, target: &T) -> Option<Self::Policy> {
         let route: Route = target.param();
         let labels: ProfileRouteLabels = target.param();
+        let overrides: RouteRetryParams = target.param();
+        let RetryParams {
+            max_buffered_bytes,
+            ref retryable_statuses,
+            min_attempt_time,
+            max_retry_after,
+            ref idempotent_header,
+            on_reset,
+            timeout_headroom,
+        } = self.params;
         Some(RetryPolicy {
             metrics: self.metrics.get_handle(labels),
             budget: route.retries()?.budget().clone(),
             response_classes: route.response_classes().clone(),
+            max_buffered_bytes: overrides.max_buffered_bytes.unwrap_or(max_buffered_bytes),
+            retryable_statuses: overrides
+                .retryable_statuses
+                .unwrap_or_else(|| retryable_statuses.clone()),
+            timeout: route.timeout(),
+            min_attempt_time,
+            max_retry_after,
+            idempotent_header: idempotent_header.clone(),
+            on_reset,
+            timeout_headroom,
         })
     }
 }
 
 // === impl Retry ===
 
+impl RetryPolicy {
+    /// Determines whether enough time remains before the route's timeout for
+    /// the request to be attempted again after `delay`.
+    fn has_time_to_retry<B>(&self, req: &http::Request<B>, delay: Duration) -> bool {
+        let (timeout, min_attempt_time) = match (self.timeout, self.min_attempt_time) {
+            (Some(timeout), Some(min_attempt_time)) => (timeout, min_attempt_time),
+            _ => return true,
+        };
+        let Attempt { request, attempt } = match req.extensions().get::<Attempt>() {
+            Some(attempt) => *attempt,
+            None => return true,
+        };
+
+        // Another attempt is expected to take at least as long as the last.
+        let now = Instant::now();
+        let remaining = timeout
+            .saturating_sub(now.saturating_duration_since(request))
+            .saturating_sub(self.timeout_headroom.unwrap_or_default());
+        let expected = now.saturating_duration_since(attempt).max(min_attempt_time) + delay;
+        tracing::trace!(?remaining, ?expected);
+        remaining >= expected
+    }
+
+    /// Determines whether a request may be replayed. When an idempotency header
+    /// is configured, only requests whose bodies are known to be empty and
+    /// requests marked with the header may be replayed.
+    fn is_replayable<B: HttpBody>(&self, head: &http::request::Parts, body: &B) -> bool {
+        match self.idempotent_header {
+            Some(ref header) => body.is_end_stream() || head.headers.contains_key(header),
+            None => true,
+        }
+    }
+
+    /// Determines whether a request that failed because the upstream reset the
+    /// connection or stream may be retried.
+    fn is_retryable_reset<A>(
+        &self,
+        req: &http::Request<ReplayBody<A>>,
+        error: &(dyn std::error::Error + 'static),
+    ) -> bool
+    where
+        A: HttpBody + Unpin,
+        A::Error: Into<Error>,
+    {
+        if self.on_reset != ResetBehavior::RetryIdempotent || !is_reset(error) {
+            return false;
+        }
+        let is_idempotent = is_idempotent(req.method());
+        let exceeded_max_len = req.body().is_capped();
+        let has_time = self.has_time_to_retry(req, Duration::ZERO);
+        let retryable = is_idempotent && !exceeded_max_len && has_time;
+        tracing::debug!(%error, is_idempotent, exceeded_max_len, has_time, retryable, "Upstream reset");
+        retryable
+    }
+
+    /// Returns how long to wait before retrying a response, if it specifies a
+    /// `Retry-After` delay and such delays are honored.
+    fn retry_after<B>(&self, rsp: &http::Response<B>) -> Duration {
+        let max = match self.max_retry_after {
+            Some(max) => max,
+            None => return Duration::ZERO,
+        };
+        let status = rsp.status();
+        if status != http::StatusCode::TOO_MANY_REQUESTS
+            && status != http::StatusCode::SERVICE_UNAVAILABLE
+        {
+            return Duration::ZERO;
+        }
+        rsp.headers()
+            .get(http::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_retry_after)
+            .map(|delay| delay.min(max))
+            .unwrap_or_default()
+    }
+}
+
+/// Determines whether an error indicates that the upstream reset the connection
+/// or stream.
+fn is_reset(error: &(dyn std::error::Error + 'static)) -> bool {
+    if error.h2_reason().is_some() {
+        return true;
+    }
+    matches!(
+        errors::cause_ref::<io::Error>(error).map(io::Error::kind),
+        Some(
+            io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::BrokenPipe
+                | io::ErrorKind::UnexpectedEof
+        )
+    )
+}
+
+/// Determines whether a request's method is idempotent, so that it may be
+/// safely replayed after the upstream may have partially processed it.
+fn is_idempotent(method: &http::Method) -> bool {
+    matches!(
+        *method,
+        http::Method::GET
+            | http::Method::HEAD
+            | http::Method::OPTIONS
+            | http::Method::TRACE
+            | http::Method::PUT
+            | http::Method::DELETE
+    )
+}
+
+/// Parses a `Retry-After` header value, which is either a number of seconds or
+/// an HTTP-date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    // Dates in the past allow the request to be retried immediately.
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
 impl<A, B, E> retry::Policy<http::Request<ReplayBody<A>>, http::Response<WithTrailers<B>>, E>
     for RetryPolicy
 where
     A: HttpBody + Unpin,
     A::Error: Into<Error>,
     B: HttpBody + Unpin,
+    B::Error: AsRef<dyn std::error::Error + Send + Sync + 'static>,
+    E: AsRef<dyn std::error::Error + Send + Sync + 'static>,
 {
-    type Future = future::Ready<Self>;
+    type Future =
+        future::Either<future::Ready<Self>, Pin<Box<dyn Future<Output = Self> + Send + 'static>>>;
 
     fn retry(
         &self,
         req: &http::Request<ReplayBody<A>>,
         result: Result<&http::Response<WithTrailers<B>>, &E>,
     ) -> Option<Self::Future> {
+        let mut delay = Duration::ZERO;
         let retryable = match result {
-            Err(_) => false,
-            Ok(rsp) => {
-                // is the request a failure?
-                let is_failure = classify::Request::from(self.response_classes.clone())
-                    .classify(req)
-                    .start(rsp)
-                    .eos(rsp.body().trailers())
-                    .is_failure();
-                // did the body exceed the maximum length limit?
-                let exceeded_max_len = req.body().is_capped();
-                let retryable = is_failure && !exceeded_max_len;
-                tracing::trace!(is_failure, exceeded_max_len, retryable);
-                retryable
-            }
+            Err(error) => self.is_retryable_reset(req, error.as_ref()),
+            Ok(rsp) => match rsp.body().error() {
+                // The upstream failed after sending the response's headers.
+                Some(error) => self.is_retryable_reset(req, error.as_ref()),
+                None => {
+                    // is the request a failure?
+                    let is_failure = classify::Request::from(self.response_classes.clone())
+                        .classify(req)
+                        .start(rsp)
+                        .eos(rsp.body().trailers())
+                        .is_failure()
+                        || self.retryable_statuses.contains(&rsp.status());
+                    // did the body exceed the maximum length limit?
+                    let exceeded_max_len = req.body().is_capped();
+                    // did the response ask us to wait before retrying?
+                    delay = self.retry_after(rsp);
+                    // would another attempt exceed the route's timeout?
+                    let has_time = self.has_time_to_retry(req, delay);
+                    let retryable = is_failure && !exceeded_max_len && has_time;
+                    tracing::trace!(is_failure, exceeded_max_len, ?delay, has_time, retryable);
+                    retryable
+                }
+            },
         };
 
         if !retryable {
@@ -110,7 +374,14 @@ where
             return None;
         }
 
-        Some(future::ready(self.clone()))
+        if delay.is_zero() {
+            return Some(future::Either::Left(future::ready(self.clone())));
+        }
+        let policy = self.clone();
+        Some(future::Either::Right(Box::pin(async move {
+            time::sleep(delay).await;
+            policy
+        })))
     }
 
     fn clone_request(
@@ -131,6 +402,14 @@ where
             clone.extensions_mut().insert(client_handle);
         }
 
+        // The clone is dispatched as the next attempt of the original request.
+        if let Some(Attempt { request, .. }) = req.extensions().get::<Attempt>().copied() {
+            clone.extensions_mut().insert(Attempt {
+                request,
+                attempt: Instant::now(),
+            });
+        }
+
         Some(clone)
     }
 }
@@ -141,7 +420,8 @@ where
     A::Error: Into<Error>,
     B: HttpBody + Unpin + Send + 'static,
     B::Data: Unpin + Send,
-    B::Error: Unpin + Send,
+    B::Error: AsRef<dyn std::error::Error + Send + Sync + 'static> + Unpin + Send,
+    E: AsRef<dyn std::error::Error + Send + Sync + 'static>,
 {
     type RetryRequest = http::Request<ReplayBody<A>>;
     type RetryResponse = http::Response<WithTrailers<B>>;
@@ -154,8 +434,13 @@ where
         &self,
         req: http::Request<A>,
     ) -> Either<Self::RetryRequest, http::Request<A>> {
-        let (head, body) = req.into_parts();
-        let replay_body = match ReplayBody::try_new(body, MAX_BUFFERED_BYTES) {
+        let (mut head, body) = req.into_parts();
+        if !self.is_replayable(&head, &body) {
+            tracing::debug!("Request has a body and is not marked idempotent");
+            return Either::B(http::Request::from_parts(head, body));
+        }
+
+        let replay_body = match ReplayBody::try_new(body, self.max_buffered_bytes) {
             Ok(body) => body,
             Err(body) => {
                 tracing::debug!(
@@ -166,6 +451,12 @@ where
             }
         };
 
+        let now = Instant::now();
+        head.extensions.insert(Attempt {
+            request: now,
+            attempt: now,
+        });
+
         // The body may still be too large to be buffered if the body's length was not known.
         // `ReplayBody` handles this gracefully.
         Either::A(http::Request::from_parts(head, replay_body))
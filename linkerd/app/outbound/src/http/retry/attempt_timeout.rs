@@ -0,0 +1,107 @@
+//! Bounds each attempt of a request on a route with a timeout.
+//!
+//! When a headroom is configured, each attempt times out once the route's
+//! timeout, less the headroom, has elapsed since the request was first
+//! dispatched. This leaves time for the failed response to be processed before
+//! the route's timeout fails the request as a whole.
+
+use super::Attempt;
+use futures::{future, FutureExt, TryFutureExt};
+use linkerd_app_core::{
+    profiles::http::Route,
+    proxy::http::ResponseTimeoutError,
+    svc::{layer, NewService, Param, Service},
+    Error,
+};
+use std::task::{Context, Poll};
+use tokio::time::{self, Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewAttemptTimeout<N> {
+    inner: N,
+    headroom: Option<Duration>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct AttemptTimeout<S> {
+    inner: S,
+    timeout: Option<Duration>,
+    headroom: Duration,
+}
+
+// === impl NewAttemptTimeout ===
+
+impl<N> NewAttemptTimeout<N> {
+    pub fn layer(headroom: Option<Duration>) -> impl layer::Layer<N, Service = Self> + Clone {
+        layer::mk(move |inner| Self { inner, headroom })
+    }
+}
+
+impl<T, N> NewService<T> for NewAttemptTimeout<N>
+where
+    T: Param<Route>,
+    N: NewService<T>,
+{
+    type Service = AttemptTimeout<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let route: Route = target.param();
+        // Attempts are only bounded on routes with a timeout.
+        let timeout = route.timeout().filter(|_| self.headroom.is_some());
+        AttemptTimeout {
+            timeout,
+            headroom: self.headroom.unwrap_or_default(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl AttemptTimeout ===
+
+impl<S> AttemptTimeout<S> {
+    /// Returns how long an attempt of the request may take: the time remaining
+    /// before the route's timeout, less the headroom.
+    fn attempt_timeout<B>(&self, req: &http::Request<B>) -> Option<Duration> {
+        let timeout = self.timeout?;
+        let elapsed = req
+            .extensions()
+            .get::<Attempt>()
+            .map(|Attempt { request, .. }| Instant::now().saturating_duration_since(*request))
+            .unwrap_or_default();
+        Some(
+            timeout
+                .saturating_sub(elapsed)
+                .saturating_sub(self.headroom),
+        )
+    }
+}
+
+impl<B, S> Service<http::Request<B>> for AttemptTimeout<S>
+where
+    S: Service<http::Request<B>>,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::BoxFuture<'static, Result<S::Response, Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let timeout = match self.attempt_timeout(&req) {
+            Some(timeout) => timeout,
+            None => return Box::pin(self.inner.call(req).err_into::<Error>()),
+        };
+        tracing::trace!(?timeout, "Bounding attempt");
+        Box::pin(
+            time::timeout(timeout, self.inner.call(req)).map(move |res| match res {
+                Ok(res) => res.map_err(Into::into),
+                Err(_) => Err(ResponseTimeoutError::new(timeout).into()),
+            }),
+        )
+    }
+}
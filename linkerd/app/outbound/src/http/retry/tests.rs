@@ -0,0 +1,608 @@
+use super::*;
+use bytes::Bytes;
+use linkerd_app_core::{
+    profiles::http::{ResponseClass, ResponseMatch},
+    proxy::http::{self, BoxBody, BoxRequest},
+    svc::{self, Layer, NewService, ServiceExt},
+    NameAddr,
+};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+#[derive(Clone, Debug)]
+struct Target(Route, RouteRetryParams);
+
+impl Param<Route> for Target {
+    fn param(&self) -> Route {
+        self.0.clone()
+    }
+}
+
+impl Param<RouteRetryParams> for Target {
+    fn param(&self) -> RouteRetryParams {
+        self.1.clone()
+    }
+}
+
+impl Param<ProfileRouteLabels> for Target {
+    fn param(&self) -> ProfileRouteLabels {
+        ProfileRouteLabels::outbound(profiles::LogicalAddr(addr()), &self.0)
+    }
+}
+
+fn addr() -> NameAddr {
+    "xyz.example.com:8080".parse().unwrap()
+}
+
+fn route() -> Route {
+    let mut route = Route::new(
+        std::iter::once(("route".to_string(), "upload".to_string())),
+        vec![ResponseClass::new(
+            true,
+            ResponseMatch::Status {
+                min: http::StatusCode::INTERNAL_SERVER_ERROR,
+                max: http::StatusCode::from_u16(599).unwrap(),
+            },
+        )],
+    );
+    route.set_retries(Arc::new(retry::Budget::new(
+        Duration::from_secs(10),
+        10,
+        0.2,
+    )));
+    route
+}
+
+/// Sends a request with the given body to a backend that responds to the first
+/// request with `first_status`, returning the response status and the number of
+/// requests the backend received.
+async fn send(
+    max_buffered_bytes: usize,
+    retryable_statuses: &[http::StatusCode],
+    first_status: http::StatusCode,
+    body: &'static [u8],
+) -> (http::StatusCode, usize) {
+    let params = RetryParams {
+        max_buffered_bytes,
+        retryable_statuses: Arc::new(retryable_statuses.iter().copied().collect()),
+        ..Default::default()
+    };
+    send_with(params, Default::default(), first_status, body).await
+}
+
+/// Sends a request like [`send`], with the given retry parameters and route
+/// overrides.
+async fn send_with(
+    params: RetryParams,
+    overrides: RouteRetryParams,
+    first_status: http::StatusCode,
+    body: &'static [u8],
+) -> (http::StatusCode, usize) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let backend = {
+        let calls = calls.clone();
+        move |_: Target| {
+            let calls = calls.clone();
+            BoxRequest::erased().layer(svc::mk(move |req: http::Request<BoxBody>| {
+                let calls = calls.clone();
+                async move {
+                    let mut body = req.into_body();
+                    while let Some(res) = body.data().await {
+                        res?;
+                    }
+                    let status = match calls.fetch_add(1, Ordering::SeqCst) {
+                        0 => first_status,
+                        _ => http::StatusCode::OK,
+                    };
+                    let rsp = http::Response::builder()
+                        .status(status)
+                        .body(BoxBody::default())
+                        .unwrap();
+                    Ok::<_, Error>(rsp)
+                }
+            }))
+        }
+    };
+
+    let svc = layer(Default::default(), params)
+        .layer(backend)
+        .new_service(Target(route(), overrides));
+    let req = http::Request::post("http://xyz.example.com:8080/")
+        .body(BoxBody::new(http_body::Full::new(Bytes::from_static(body))))
+        .unwrap();
+    let rsp = svc.oneshot(req).await.expect("request must succeed");
+    (rsp.status(), calls.load(Ordering::SeqCst))
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn retries_buffered_bodies() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (status, calls) = send(8, &[], http::StatusCode::INTERNAL_SERVER_ERROR, b"hello").await;
+    assert_eq!(status, http::StatusCode::OK);
+    assert_eq!(calls, 2, "request must be retried");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn does_not_retry_bodies_over_limit() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (status, calls) = send(
+        8,
+        &[],
+        http::StatusCode::INTERNAL_SERVER_ERROR,
+        b"hello world",
+    )
+    .await;
+    assert_eq!(status, http::StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(calls, 1, "request must not be retried");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn limits_buffered_bodies_by_route() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let params = RetryParams {
+        max_buffered_bytes: 8,
+        ..Default::default()
+    };
+    let body = b"hello world";
+
+    let (status, calls) = send_with(
+        params.clone(),
+        RouteRetryParams {
+            max_buffered_bytes: Some(64),
+            ..Default::default()
+        },
+        http::StatusCode::INTERNAL_SERVER_ERROR,
+        body,
+    )
+    .await;
+    assert_eq!(status, http::StatusCode::OK);
+    assert_eq!(calls, 2, "the route's limit must be used");
+
+    let (status, calls) = send_with(
+        params,
+        Default::default(),
+        http::StatusCode::INTERNAL_SERVER_ERROR,
+        body,
+    )
+    .await;
+    assert_eq!(status, http::StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(calls, 1, "routes without a limit must use the default");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn retries_configured_statuses() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let too_early = http::StatusCode::from_u16(425).unwrap();
+    let (status, calls) = send(64, &[too_early], too_early, b"hello").await;
+    assert_eq!(status, http::StatusCode::OK);
+    assert_eq!(calls, 2, "configured statuses must be retried");
+
+    let (status, calls) = send(64, &[too_early], http::StatusCode::NOT_FOUND, b"hello").await;
+    assert_eq!(status, http::StatusCode::NOT_FOUND);
+    assert_eq!(calls, 1, "other statuses must not be retried");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn retries_configured_statuses_by_route() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let too_early = http::StatusCode::from_u16(425).unwrap();
+    let params = RetryParams {
+        max_buffered_bytes: 64,
+        retryable_statuses: Arc::new(std::iter::once(http::StatusCode::NOT_FOUND).collect()),
+        ..Default::default()
+    };
+    let overrides = RouteRetryParams {
+        retryable_statuses: Some(Arc::new(std::iter::once(too_early).collect())),
+        ..Default::default()
+    };
+
+    let (status, calls) = send_with(params.clone(), overrides.clone(), too_early, b"hello").await;
+    assert_eq!(status, http::StatusCode::OK);
+    assert_eq!(calls, 2, "the route's statuses must be retried");
+
+    let (status, calls) = send_with(
+        params.clone(),
+        overrides,
+        http::StatusCode::NOT_FOUND,
+        b"hello",
+    )
+    .await;
+    assert_eq!(status, http::StatusCode::NOT_FOUND);
+    assert_eq!(calls, 1, "the route's statuses must replace the defaults");
+
+    let (status, calls) = send_with(params, Default::default(), too_early, b"hello").await;
+    assert_eq!(status, too_early);
+    assert_eq!(calls, 1, "routes without statuses must use the defaults");
+}
+
+/// Sends a request on a route with a 1s timeout to a backend that fails the
+/// first request after `latency`, returning the response status and the number
+/// of requests the backend received.
+async fn send_with_latency(latency: Duration) -> (http::StatusCode, usize) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let backend = {
+        let calls = calls.clone();
+        move |_: Target| {
+            let calls = calls.clone();
+            BoxRequest::erased().layer(svc::mk(move |_: http::Request<BoxBody>| {
+                let attempt = calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    let status = if attempt == 0 {
+                        tokio::time::sleep(latency).await;
+                        http::StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        http::StatusCode::OK
+                    };
+                    let rsp = http::Response::builder()
+                        .status(status)
+                        .body(BoxBody::default())
+                        .unwrap();
+                    Ok::<_, Error>(rsp)
+                }
+            }))
+        }
+    };
+
+    let mut route = route();
+    route.set_timeout(Duration::from_secs(1));
+    let svc = layer(
+        Default::default(),
+        RetryParams {
+            max_buffered_bytes: 64,
+            min_attempt_time: Some(Duration::from_millis(100)),
+            ..Default::default()
+        },
+    )
+    .layer(backend)
+    .new_service(Target(route, Default::default()));
+    let req = http::Request::get("http://xyz.example.com:8080/")
+        .body(BoxBody::default())
+        .unwrap();
+    let rsp = svc.oneshot(req).await.expect("request must succeed");
+    (rsp.status(), calls.load(Ordering::SeqCst))
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn retries_within_timeout() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (status, calls) = send_with_latency(Duration::from_millis(200)).await;
+    assert_eq!(status, http::StatusCode::OK);
+    assert_eq!(calls, 2, "request must be retried");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn does_not_retry_without_time_for_another_attempt() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    // Only 400ms remain before the timeout, but another attempt is expected to
+    // take as long as the first.
+    let (status, calls) = send_with_latency(Duration::from_millis(600)).await;
+    assert_eq!(status, http::StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(calls, 1, "request must not be retried");
+}
+
+/// Sends a request to a backend that fails the first request with a 503 and
+/// the given `Retry-After` header, returning the number of requests the backend
+/// received and how long the request took.
+async fn send_with_retry_after(
+    retry_after: &str,
+    max_retry_after: Option<Duration>,
+) -> (usize, Duration) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let backend = {
+        let calls = calls.clone();
+        let retry_after = http::HeaderValue::from_str(retry_after).unwrap();
+        move |_: Target| {
+            let calls = calls.clone();
+            let retry_after = retry_after.clone();
+            BoxRequest::erased().layer(svc::mk(move |_: http::Request<BoxBody>| {
+                let mut rsp = http::Response::builder();
+                if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    rsp = rsp
+                        .status(http::StatusCode::SERVICE_UNAVAILABLE)
+                        .header(http::header::RETRY_AFTER, retry_after.clone());
+                }
+                future::ok::<_, Error>(rsp.body(BoxBody::default()).unwrap())
+            }))
+        }
+    };
+
+    let svc = layer(
+        Default::default(),
+        RetryParams {
+            max_buffered_bytes: 64,
+            max_retry_after,
+            ..Default::default()
+        },
+    )
+    .layer(backend)
+    .new_service(Target(route(), Default::default()));
+    let req = http::Request::get("http://xyz.example.com:8080/")
+        .body(BoxBody::default())
+        .unwrap();
+    let start = tokio::time::Instant::now();
+    let rsp = svc.oneshot(req).await.expect("request must succeed");
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    (
+        calls.load(Ordering::SeqCst),
+        tokio::time::Instant::now().saturating_duration_since(start),
+    )
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn waits_for_retry_after() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (calls, elapsed) = send_with_retry_after("3", Some(Duration::from_secs(10))).await;
+    assert_eq!(calls, 2, "request must be retried");
+    assert_eq!(elapsed, Duration::from_secs(3));
+
+    // HTTP-dates are only precise to the second.
+    let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(5));
+    let (calls, elapsed) = send_with_retry_after(&date, Some(Duration::from_secs(10))).await;
+    assert_eq!(calls, 2, "request must be retried");
+    assert!(
+        (Duration::from_secs(4)..=Duration::from_secs(5)).contains(&elapsed),
+        "unexpected delay: {elapsed:?}"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn clamps_retry_after_to_max() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (calls, elapsed) = send_with_retry_after("60", Some(Duration::from_secs(2))).await;
+    assert_eq!(calls, 2, "request must be retried");
+    assert_eq!(elapsed, Duration::from_secs(2));
+
+    // When no maximum is configured, `Retry-After` is ignored.
+    let (calls, elapsed) = send_with_retry_after("60", None).await;
+    assert_eq!(calls, 2, "request must be retried");
+    assert_eq!(elapsed, Duration::ZERO);
+}
+
+/// Sends a POST request with the given body to a backend that fails the first
+/// request, on a route that only retries bodyless requests and requests marked
+/// with an `x-idempotent` header. Returns the response status and the number of
+/// requests the backend received.
+async fn send_post(body: &'static [u8], marked: bool) -> (http::StatusCode, usize) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let backend = {
+        let calls = calls.clone();
+        move |_: Target| {
+            let calls = calls.clone();
+            BoxRequest::erased().layer(svc::mk(move |req: http::Request<BoxBody>| {
+                let calls = calls.clone();
+                async move {
+                    let mut body = req.into_body();
+                    while let Some(res) = body.data().await {
+                        res?;
+                    }
+                    let status = match calls.fetch_add(1, Ordering::SeqCst) {
+                        0 => http::StatusCode::INTERNAL_SERVER_ERROR,
+                        _ => http::StatusCode::OK,
+                    };
+                    let rsp = http::Response::builder()
+                        .status(status)
+                        .body(BoxBody::default())
+                        .unwrap();
+                    Ok::<_, Error>(rsp)
+                }
+            }))
+        }
+    };
+
+    let svc = layer(
+        Default::default(),
+        RetryParams {
+            max_buffered_bytes: 64,
+            idempotent_header: Some(http::HeaderName::from_static("x-idempotent")),
+            ..Default::default()
+        },
+    )
+    .layer(backend)
+    .new_service(Target(route(), Default::default()));
+    let mut req = http::Request::post("http://xyz.example.com:8080/");
+    if marked {
+        req = req.header("x-idempotent", "true");
+    }
+    let req = req
+        .body(BoxBody::new(http_body::Full::new(Bytes::from_static(body))))
+        .unwrap();
+    let rsp = svc.oneshot(req).await.expect("request must succeed");
+    (rsp.status(), calls.load(Ordering::SeqCst))
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn retries_bodyless_requests() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (status, calls) = send_post(b"", false).await;
+    assert_eq!(status, http::StatusCode::OK);
+    assert_eq!(calls, 2, "bodyless POST must be retried");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn does_not_retry_unmarked_requests_with_bodies() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (status, calls) = send_post(b"hello", false).await;
+    assert_eq!(status, http::StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(calls, 1, "POST with a body must not be retried");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn retries_marked_requests_with_bodies() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (status, calls) = send_post(b"hello", true).await;
+    assert_eq!(status, http::StatusCode::OK);
+    assert_eq!(calls, 2, "marked POST must be retried");
+}
+
+/// A response body that fails as if the upstream reset the connection.
+struct ResetBody;
+
+impl http_body::Body for ResetBody {
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        Poll::Ready(Some(Err(
+            io::Error::from(io::ErrorKind::ConnectionReset).into()
+        )))
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::header::HeaderMap>, Self::Error>> {
+        Poll::Ready(Ok(None))
+    }
+}
+
+/// Sends a bodyless request with the given method to an HTTP/2 backend that
+/// resets the first request after sending its response headers, returning the
+/// response and the number of requests the backend received.
+async fn send_reset(
+    method: http::Method,
+    on_reset: ResetBehavior,
+) -> (http::Response<BoxBody>, usize) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let backend = {
+        let calls = calls.clone();
+        move |_: Target| {
+            let calls = calls.clone();
+            BoxRequest::erased().layer(svc::mk(move |_: http::Request<BoxBody>| {
+                let body = match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => BoxBody::new(ResetBody),
+                    _ => BoxBody::default(),
+                };
+                let rsp = http::Response::builder()
+                    .version(::http::Version::HTTP_2)
+                    .body(body)
+                    .unwrap();
+                future::ok::<_, Error>(rsp)
+            }))
+        }
+    };
+
+    let svc = layer(
+        Default::default(),
+        RetryParams {
+            max_buffered_bytes: 64,
+            on_reset,
+            ..Default::default()
+        },
+    )
+    .layer(backend)
+    .new_service(Target(route(), Default::default()));
+    let req = http::Request::builder()
+        .method(method)
+        .uri("http://xyz.example.com:8080/")
+        .body(BoxBody::default())
+        .unwrap();
+    let rsp = svc.oneshot(req).await.expect("request must succeed");
+    (rsp, calls.load(Ordering::SeqCst))
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn retries_idempotent_requests_on_reset() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (rsp, calls) = send_reset(http::Method::GET, ResetBehavior::RetryIdempotent).await;
+    assert_eq!(calls, 2, "GET must be retried");
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    hyper::body::to_bytes(rsp.into_body())
+        .await
+        .expect("retried response must not fail");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn propagates_resets() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (rsp, calls) = send_reset(http::Method::POST, ResetBehavior::RetryIdempotent).await;
+    assert_eq!(calls, 1, "POST must not be retried");
+    hyper::body::to_bytes(rsp.into_body())
+        .await
+        .expect_err("reset must be propagated");
+
+    let (rsp, calls) = send_reset(http::Method::GET, ResetBehavior::Propagate).await;
+    assert_eq!(calls, 1, "resets must not be retried by default");
+    hyper::body::to_bytes(rsp.into_body())
+        .await
+        .expect_err("reset must be propagated");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn last_attempt_reserves_timeout_headroom() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    // The backend fails the first attempt after 300ms and never responds to
+    // the second.
+    let calls = Arc::new(AtomicUsize::new(0));
+    let backend = {
+        let calls = calls.clone();
+        move |_: Target| {
+            let calls = calls.clone();
+            BoxRequest::erased().layer(svc::mk(move |_: http::Request<BoxBody>| {
+                let attempt = calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if attempt > 0 {
+                        future::pending::<()>().await;
+                    }
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                    let rsp = http::Response::builder()
+                        .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+                        .body(BoxBody::default())
+                        .unwrap();
+                    Ok::<_, Error>(rsp)
+                }
+            }))
+        }
+    };
+
+    let headroom = Some(Duration::from_millis(100));
+    let mut route = route();
+    route.set_timeout(Duration::from_secs(1));
+    let svc = layer(
+        Default::default(),
+        RetryParams {
+            max_buffered_bytes: 64,
+            timeout_headroom: headroom,
+            ..Default::default()
+        },
+    )
+    .layer(NewAttemptTimeout::layer(headroom).layer(backend))
+    .new_service(Target(route, Default::default()));
+    let req = http::Request::get("http://xyz.example.com:8080/")
+        .body(BoxBody::default())
+        .unwrap();
+    let start = tokio::time::Instant::now();
+    let error = svc
+        .oneshot(req)
+        .await
+        .expect_err("last attempt must time out");
+    let elapsed = tokio::time::Instant::now().saturating_duration_since(start);
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2, "request must be retried");
+    assert!(error.is::<http::ResponseTimeoutError>(), "{error}");
+    assert_eq!(
+        elapsed,
+        Duration::from_millis(900),
+        "last attempt must leave the headroom before the route's timeout"
+    );
+}
@@ -0,0 +1,87 @@
+//! Rewrites the authority of each HTTP route's requests.
+//!
+//! Some upstreams are virtually hosted, serving several sites that are
+//! distinguished by the request's authority. Routes may be configured to
+//! rewrite the authority--both the URI's authority and the `Host` header--of
+//! their requests before they are dispatched to a backend.
+
+use crate::http;
+use linkerd_app_core::svc;
+use std::task::{Context, Poll};
+use tracing::debug;
+
+/// The authority to which a route's requests are rewritten.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct RewriteAuthority(pub(crate) http::uri::Authority);
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewRewriteAuthority<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct RewriteAuthorityService<S> {
+    inner: S,
+    authority: Option<http::uri::Authority>,
+}
+
+// === impl NewRewriteAuthority ===
+
+impl<N> NewRewriteAuthority<N> {
+    pub fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewRewriteAuthority<N>
+where
+    T: svc::Param<Option<RewriteAuthority>>,
+    N: svc::NewService<T>,
+{
+    type Service = RewriteAuthorityService<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        RewriteAuthorityService {
+            authority: target.param().map(|RewriteAuthority(a)| a),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl RewriteAuthorityService ===
+
+impl<S, B> svc::Service<http::Request<B>> for RewriteAuthorityService<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        if let Some(authority) = self.authority.clone() {
+            debug!(%authority, "Rewriting authority");
+            if req.headers().contains_key(http::header::HOST) {
+                let host = http::HeaderValue::from_str(authority.as_str())
+                    .expect("authority must be a valid header value");
+                req.headers_mut().insert(http::header::HOST, host);
+            }
+
+            let mut parts = std::mem::take(req.uri_mut()).into_parts();
+            // An origin-form URI must also be given a scheme once it has an
+            // authority.
+            if parts.scheme.is_none() && parts.path_and_query.is_some() {
+                parts.scheme = Some(http::uri::Scheme::HTTP);
+            }
+            parts.authority = Some(authority);
+            *req.uri_mut() = http::uri::Uri::from_parts(parts).expect("URI must be valid");
+        }
+
+        self.inner.call(req)
+    }
+}
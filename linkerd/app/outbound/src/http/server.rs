@@ -1,4 +1,4 @@
-use super::{IdentityRequired, ProxyConnectionClose};
+use super::{IdentityRequired, MtlsRequired, ProxyConnectionClose};
 use crate::{http, trace_labels, Outbound};
 use linkerd_app_core::{
     errors, http_tracing, io,
@@ -6,9 +6,14 @@ use linkerd_app_core::{
     Error, Result,
 };
 
+#[cfg(test)]
+mod tests;
+
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct ServerRescue {
     emit_headers: bool,
+    emit_json: bool,
+    emit_grpc_web: bool,
 }
 
 impl<N> Outbound<N> {
@@ -65,19 +70,47 @@ impl<N> Outbound<N> {
                         // Tear down server connections when a peer proxy generates an error.
                         .push(ProxyConnectionClose::layer()),
                 )
+                // Fail HTTP/1 requests for which no authority could be
+                // determined. This is below the rescue layer so that they are
+                // failed with a synthesized response.
+                .push_on_service(http::normalize_uri::RequireAuthority::layer())
+                // Reject requests whose framing may be interpreted differently
+                // by the application, if configured.
+                .push_on_service(http::RejectAmbiguousFraming::layer(
+                    config.http1_reject_ambiguous_framing,
+                ))
+                // Canonicalize the transfer codings of chunked requests so that
+                // they are framed consistently by each HTTP/1 hop, if configured.
+                .push_on_service(http::NormalizeTransferEncoding::layer(
+                    config.http1_transfer_encoding,
+                ))
+                // Reject requests with header values that exceed the maximum
+                // length, if configured.
+                .push_on_service(http::LimitHeaderValues::layer(
+                    config.http_max_header_value_bytes,
+                ))
                 // Synthesizes responses for proxy errors.
                 .check_new_service::<T, http::Request<_>>()
-                .push(ServerRescue::layer(config.emit_headers))
+                .push(ServerRescue::layer(
+                    config.emit_headers,
+                    config.json_error_bodies,
+                    config.grpc_web_errors,
+                ))
                 .check_new_service::<T, http::Request<_>>()
                 .push_on_service(
                     svc::layers()
+                        // Strips response trailers that are not forwarded to
+                        // clients, if configured.
+                        .push(super::filter_trailers::FilterTrailers::layer(
+                            config.http_response_trailers.clone(),
+                        ))
                         // Initiates OpenCensus tracing.
                         .push(http_tracing::server(rt.span_sink.clone(), trace_labels()))
                         .push(http::BoxResponse::layer()),
                 )
                 // Convert origin form HTTP/1 URIs to absolute form for Hyper's
                 // `Client`.
-                .push(http::NewNormalizeUri::layer())
+                .push(http::NewNormalizeUri::layer(config.http1_require_host))
                 // Record when a HTTP/1 URI originated in absolute form
                 .push_on_service(http::normalize_uri::MarkAbsoluteForm::layer())
                 .push(svc::ArcNewService::layer())
@@ -107,7 +140,9 @@ impl<N> Outbound<N> {
             http.unlift_new()
                 .push(svc::ArcNewService::layer())
                 .push(http::NewServeHttp::layer(
+                    config.proxy.server.h1_settings,
                     config.proxy.server.h2_settings,
+                    rt.metrics.proxy.http_server.clone(),
                     rt.drain.clone(),
                 ))
         })
@@ -119,8 +154,14 @@ impl<N> Outbound<N> {
 impl ServerRescue {
     pub fn layer<N>(
         emit_headers: bool,
+        emit_json: bool,
+        emit_grpc_web: bool,
     ) -> impl svc::layer::Layer<N, Service = errors::NewRespondService<Self, Self, N>> + Clone {
-        errors::respond::layer(Self { emit_headers })
+        errors::respond::layer(Self {
+            emit_headers,
+            emit_json,
+            emit_grpc_web,
+        })
     }
 }
 
@@ -138,6 +179,20 @@ impl<T> ExtractParam<errors::respond::EmitHeaders, T> for ServerRescue {
     }
 }
 
+impl<T> ExtractParam<errors::respond::EmitJsonBody, T> for ServerRescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> errors::respond::EmitJsonBody {
+        errors::respond::EmitJsonBody(self.emit_json)
+    }
+}
+
+impl<T> ExtractParam<errors::respond::EmitGrpcWeb, T> for ServerRescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> errors::respond::EmitGrpcWeb {
+        errors::respond::EmitGrpcWeb(self.emit_grpc_web)
+    }
+}
+
 impl errors::HttpRescue<Error> for ServerRescue {
     fn rescue(&self, error: Error) -> Result<errors::SyntheticHttpResponse> {
         if errors::is_caused_by::<http::ResponseTimeoutError>(&*error) {
@@ -146,12 +201,42 @@ impl errors::HttpRescue<Error> for ServerRescue {
         if errors::is_caused_by::<IdentityRequired>(&*error) {
             return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
         }
+        if errors::is_caused_by::<MtlsRequired>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
+        }
+        if errors::is_caused_by::<super::response_body_limit::ResponseBodyTooLarge>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
+        }
         if errors::is_caused_by::<errors::FailFastError>(&*error) {
             return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
         }
+        // Connection errors that were not handled by an endpoint's rescue,
+        // e.g. for requests that are not balanced.
+        if errors::is_caused_by::<errors::ConnectTimeout>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
+        }
+        if errors::is_caused_by::<std::io::Error>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
+        }
         if errors::is_caused_by::<errors::LoadShedError>(&*error) {
             return Ok(errors::SyntheticHttpResponse::unavailable(error));
         }
+        if errors::is_caused_by::<super::endpoint_pin::PinnedEndpointUnavailable>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::unavailable(error));
+        }
+        if errors::is_caused_by::<super::fair_queue::QueueFull>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::unavailable(error));
+        }
+
+        if errors::is_caused_by::<http::normalize_uri::NoAuthority>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::bad_request(error));
+        }
+        if errors::is_caused_by::<http::framing::AmbiguousFraming>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::bad_request(error));
+        }
+        if errors::is_caused_by::<http::header_limit::HeaderValueTooLarge>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::request_header_fields_too_large(error));
+        }
 
         if errors::is_caused_by::<super::logical::NoRoute>(&*error) {
             return Ok(errors::SyntheticHttpResponse::not_found(error));
@@ -0,0 +1,420 @@
+use super::*;
+use crate::test_util::*;
+use linkerd_app_core::svc::{NewService, ServiceExt};
+use std::time::Duration;
+
+/// Tests that, when configured, a response timeout is rescued into a 504 with
+/// a JSON body that describes the error and references the request's ID.
+#[tokio::test(flavor = "current_thread")]
+async fn timeout_json_error_body() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let mut config = default_config();
+    config.json_error_bodies = true;
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(config, rt)
+        .with_stack(
+            svc::stack(|_: Target| {
+                svc::mk(|_: http::Request<http::BoxBody>| {
+                    future::pending::<Result<http::Response<http::BoxBody>, Error>>()
+                })
+            })
+            .push(http::NewTimeout::layer())
+            .into_inner(),
+        )
+        .push_http_server()
+        .into_inner();
+
+    let req = http::Request::builder()
+        .uri("http://foo.example.com")
+        .header("x-request-id", "req-1234")
+        .extension(http::ClientHandle::new(([192, 0, 2, 101], 40200).into()).0)
+        .body(http::BoxBody::default())
+        .unwrap();
+    let rsp = stack.new_service(Target).oneshot(req).await.unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(
+        rsp.headers().get(http::header::CONTENT_TYPE),
+        Some(&http::HeaderValue::from_static("application/json"))
+    );
+
+    let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+    let json = serde_json::from_slice::<serde_json::Value>(&body).unwrap();
+    assert_eq!(json["code"], 504);
+    assert_eq!(json["error"], "Gateway Timeout");
+    assert_eq!(json["request_id"], "req-1234");
+    assert!(json["reason"]
+        .as_str()
+        .expect("reason must be set")
+        .contains("timeout"));
+}
+
+/// Tests that error bodies are empty by default.
+#[tokio::test(flavor = "current_thread")]
+async fn timeout_empty_error_body() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt)
+        .with_stack(
+            svc::stack(|_: Target| {
+                svc::mk(|_: http::Request<http::BoxBody>| {
+                    future::pending::<Result<http::Response<http::BoxBody>, Error>>()
+                })
+            })
+            .push(http::NewTimeout::layer())
+            .into_inner(),
+        )
+        .push_http_server()
+        .into_inner();
+
+    let req = http::Request::builder()
+        .uri("http://foo.example.com")
+        .header("x-request-id", "req-1234")
+        .extension(http::ClientHandle::new(([192, 0, 2, 101], 40200).into()).0)
+        .body(http::BoxBody::default())
+        .unwrap();
+    let rsp = stack.new_service(Target).oneshot(req).await.unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::GATEWAY_TIMEOUT);
+    assert!(rsp.headers().get(http::header::CONTENT_TYPE).is_none());
+
+    let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+    assert!(body.is_empty());
+}
+
+/// Tests that, when a `Host` header is required, origin-form HTTP/1 requests
+/// without one are rejected with a 400.
+#[tokio::test(flavor = "current_thread")]
+async fn missing_host_rejected_when_required() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let mut config = default_config();
+    config.http1_require_host = true;
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(config, rt)
+        .with_stack(|_: Target| {
+            svc::mk(|_: http::Request<http::BoxBody>| {
+                future::ok::<_, Error>(http::Response::new(http::BoxBody::default()))
+            })
+        })
+        .push_http_server()
+        .into_inner();
+
+    let req = http::Request::builder()
+        .uri("/")
+        .extension(http::ClientHandle::new(([192, 0, 2, 101], 40200).into()).0)
+        .body(http::BoxBody::default())
+        .unwrap();
+    let rsp = stack.new_service(Target).oneshot(req).await.unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::BAD_REQUEST);
+}
+
+/// Tests that, by default, origin-form HTTP/1 requests without a `Host` header
+/// are routed to the target's default authority.
+#[tokio::test(flavor = "current_thread")]
+async fn missing_host_uses_default_authority() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt)
+        .with_stack(|_: Target| {
+            svc::mk(|req: http::Request<http::BoxBody>| {
+                assert_eq!(
+                    req.uri().authority().map(|a| a.as_str()),
+                    Some(DEFAULT_AUTHORITY)
+                );
+                future::ok::<_, Error>(http::Response::new(http::BoxBody::default()))
+            })
+        })
+        .push_http_server()
+        .into_inner();
+
+    let req = http::Request::builder()
+        .uri("/")
+        .extension(http::ClientHandle::new(([192, 0, 2, 101], 40200).into()).0)
+        .body(http::BoxBody::default())
+        .unwrap();
+    let rsp = stack.new_service(Target).oneshot(req).await.unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+}
+
+/// Sends a POST request with the given headers through a server that rejects
+/// requests with ambiguous framing, returning the response status.
+async fn send_framed(headers: &[(&str, &str)]) -> http::StatusCode {
+    let mut config = default_config();
+    config.http1_reject_ambiguous_framing = true;
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(config, rt)
+        .with_stack(|_: Target| {
+            svc::mk(|_: http::Request<http::BoxBody>| {
+                future::ok::<_, Error>(http::Response::new(http::BoxBody::default()))
+            })
+        })
+        .push_http_server()
+        .into_inner();
+
+    let mut req = http::Request::builder()
+        .method(http::Method::POST)
+        .uri("http://foo.example.com")
+        .extension(http::ClientHandle::new(([192, 0, 2, 101], 40200).into()).0);
+    for (name, value) in headers {
+        req = req.header(*name, *value);
+    }
+    let req = req.body(http::BoxBody::default()).unwrap();
+    let rsp = stack.new_service(Target).oneshot(req).await.unwrap();
+    rsp.status()
+}
+
+/// Tests that requests with both `Content-Length` and `Transfer-Encoding`
+/// headers are rejected with a 400.
+#[tokio::test(flavor = "current_thread")]
+async fn conflicting_framing_rejected() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let status = send_framed(&[("content-length", "5"), ("transfer-encoding", "chunked")]).await;
+    assert_eq!(status, http::StatusCode::BAD_REQUEST);
+
+    let status = send_framed(&[("transfer-encoding", "chunked")]).await;
+    assert_eq!(status, http::StatusCode::OK);
+}
+
+/// Tests that requests with duplicate `Content-Length` headers are rejected
+/// with a 400, even when the values are identical.
+#[tokio::test(flavor = "current_thread")]
+async fn duplicate_content_length_rejected() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let status = send_framed(&[("content-length", "5"), ("content-length", "5")]).await;
+    assert_eq!(status, http::StatusCode::BAD_REQUEST);
+
+    let status = send_framed(&[("content-length", "5, 5")]).await;
+    assert_eq!(status, http::StatusCode::BAD_REQUEST);
+
+    let status = send_framed(&[("content-length", "5")]).await;
+    assert_eq!(status, http::StatusCode::OK);
+}
+
+/// Tests that requests with a header value that exceeds the configured
+/// maximum length are rejected with a 431.
+#[tokio::test(flavor = "current_thread")]
+async fn max_header_value_length() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let mut config = default_config();
+    config.http_max_header_value_bytes = Some(16);
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(config, rt)
+        .with_stack(|_: Target| {
+            svc::mk(|_: http::Request<http::BoxBody>| {
+                future::ok::<_, Error>(http::Response::new(http::BoxBody::default()))
+            })
+        })
+        .push_http_server()
+        .into_inner();
+
+    for (len, status) in [
+        (16, http::StatusCode::OK),
+        (17, http::StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE),
+    ] {
+        let req = http::Request::builder()
+            .uri("http://foo.example.com")
+            .header("x-value", "a".repeat(len))
+            .extension(http::ClientHandle::new(([192, 0, 2, 101], 40200).into()).0)
+            .body(http::BoxBody::default())
+            .unwrap();
+        let rsp = stack.new_service(Target).oneshot(req).await.unwrap();
+        assert_eq!(rsp.status(), status, "{} byte header value", len);
+    }
+}
+
+/// Sends a chunked POST request with the given headers through a server that
+/// normalizes transfer codings in the given mode, returning the response, which
+/// echoes the headers of the forwarded request.
+async fn send_transfer_encoded(
+    mode: http::framing::TransferEncodingMode,
+    headers: &[(&str, &str)],
+) -> http::Response<http::BoxBody> {
+    let mut config = default_config();
+    config.http1_transfer_encoding = Some(mode);
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(config, rt)
+        .with_stack(|_: Target| {
+            svc::mk(|req: http::Request<http::BoxBody>| {
+                let mut rsp = http::Response::new(http::BoxBody::default());
+                *rsp.headers_mut() = req.headers().clone();
+                future::ok::<_, Error>(rsp)
+            })
+        })
+        .push_http_server()
+        .into_inner();
+
+    let mut req = http::Request::builder()
+        .method(http::Method::POST)
+        .uri("http://foo.example.com")
+        .extension(http::ClientHandle::new(([192, 0, 2, 101], 40200).into()).0);
+    for (name, value) in headers {
+        req = req.header(*name, *value);
+    }
+    let req = req.body(http::BoxBody::default()).unwrap();
+    stack.new_service(Target).oneshot(req).await.unwrap()
+}
+
+/// Tests that, in lenient mode, transfer codings are forwarded in a single,
+/// canonical header without a conflicting `Content-Length`.
+#[tokio::test(flavor = "current_thread")]
+async fn transfer_encoding_canonicalized() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let rsp = send_transfer_encoded(
+        http::framing::TransferEncodingMode::Lenient,
+        &[
+            ("transfer-encoding", "GZIP"),
+            ("transfer-encoding", "chunked, Chunked"),
+            ("content-length", "5"),
+        ],
+    )
+    .await;
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    let codings = rsp
+        .headers()
+        .get_all(http::header::TRANSFER_ENCODING)
+        .iter()
+        .collect::<Vec<_>>();
+    assert_eq!(codings, vec!["gzip, chunked"]);
+    assert!(rsp.headers().get(http::header::CONTENT_LENGTH).is_none());
+}
+
+/// Tests that, in strict mode, requests with conflicting transfer codings are
+/// rejected with a 400.
+#[tokio::test(flavor = "current_thread")]
+async fn transfer_encoding_rejected_when_strict() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let strict = http::framing::TransferEncodingMode::Strict;
+    let rsp = send_transfer_encoded(strict, &[("transfer-encoding", "chunked, chunked")]).await;
+    assert_eq!(rsp.status(), http::StatusCode::BAD_REQUEST);
+
+    let rsp = send_transfer_encoded(
+        strict,
+        &[("transfer-encoding", "chunked"), ("content-length", "5")],
+    )
+    .await;
+    assert_eq!(rsp.status(), http::StatusCode::BAD_REQUEST);
+
+    let rsp = send_transfer_encoded(strict, &[("transfer-encoding", "Chunked")]).await;
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    assert_eq!(
+        rsp.headers().get(http::header::TRANSFER_ENCODING),
+        Some(&http::HeaderValue::from_static("chunked"))
+    );
+}
+
+/// Sends a gRPC request with the given version and content type through a
+/// server whose backend fails to connect, returning the response.
+async fn send_grpc_connect_error(
+    config: crate::Config,
+    version: ::http::Version,
+    content_type: &'static str,
+) -> http::Response<http::BoxBody> {
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(config, rt)
+        .with_stack(|_: Target| {
+            svc::mk(|_: http::Request<http::BoxBody>| {
+                future::err::<http::Response<http::BoxBody>, Error>(
+                    std::io::Error::from(std::io::ErrorKind::ConnectionRefused).into(),
+                )
+            })
+        })
+        .push_http_server()
+        .into_inner();
+
+    let req = http::Request::builder()
+        .method(http::Method::POST)
+        .version(version)
+        .uri("http://foo.example.com/foo.Bar/Baz")
+        .header(http::header::CONTENT_TYPE, content_type)
+        .extension(http::ClientHandle::new(([192, 0, 2, 101], 40200).into()).0)
+        .body(http::BoxBody::default())
+        .unwrap();
+    stack.new_service(Target).oneshot(req).await.unwrap()
+}
+
+/// Asserts that a response is a trailers-only gRPC response with an
+/// `UNAVAILABLE` status.
+async fn assert_grpc_unavailable(rsp: http::Response<http::BoxBody>, content_type: &str) {
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    assert_eq!(
+        rsp.headers().get(http::header::CONTENT_TYPE),
+        Some(&http::HeaderValue::from_str(content_type).unwrap())
+    );
+    assert_eq!(
+        rsp.headers().get("grpc-status"),
+        Some(&http::HeaderValue::from_static("14"))
+    );
+    let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+    assert!(body.is_empty());
+}
+
+/// Tests that a connect failure to a gRPC backend is rescued into a
+/// trailers-only response with an `UNAVAILABLE` status.
+#[tokio::test(flavor = "current_thread")]
+async fn grpc_connect_error_unavailable() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let rsp = send_grpc_connect_error(
+        default_config(),
+        ::http::Version::HTTP_2,
+        "application/grpc",
+    )
+    .await;
+    assert_grpc_unavailable(rsp, "application/grpc").await;
+}
+
+/// Tests that, when configured, a connect failure on an HTTP/1 gRPC-Web
+/// request is rescued into a trailers-only gRPC-Web response.
+#[tokio::test(flavor = "current_thread")]
+async fn grpc_web_connect_error_unavailable() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let mut config = default_config();
+    config.grpc_web_errors = true;
+    let content_type = "application/grpc-web+proto";
+    let rsp = send_grpc_connect_error(config, ::http::Version::HTTP_11, content_type).await;
+    assert_eq!(rsp.version(), ::http::Version::HTTP_11);
+    assert_grpc_unavailable(rsp, content_type).await;
+}
+
+/// Tests that, by default, a connect failure on an HTTP/1 gRPC-Web request is
+/// rescued into an HTTP error response.
+#[tokio::test(flavor = "current_thread")]
+async fn grpc_web_connect_error_bad_gateway_by_default() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let rsp = send_grpc_connect_error(
+        default_config(),
+        ::http::Version::HTTP_11,
+        "application/grpc-web+proto",
+    )
+    .await;
+    assert_eq!(rsp.status(), http::StatusCode::BAD_GATEWAY);
+    assert!(rsp.headers().get("grpc-status").is_none());
+}
+
+const DEFAULT_AUTHORITY: &str = "default.example.com:8080";
+
+#[derive(Clone, Debug)]
+struct Target;
+
+// === impl Target ===
+
+impl svc::Param<http::normalize_uri::DefaultAuthority> for Target {
+    fn param(&self) -> http::normalize_uri::DefaultAuthority {
+        http::normalize_uri::DefaultAuthority(Some(DEFAULT_AUTHORITY.parse().unwrap()))
+    }
+}
+
+impl svc::Param<http::ResponseTimeout> for Target {
+    fn param(&self) -> http::ResponseTimeout {
+        http::ResponseTimeout(Some(Duration::from_millis(10)))
+    }
+}
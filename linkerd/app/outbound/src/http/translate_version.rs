@@ -0,0 +1,80 @@
+use crate::http;
+use linkerd_app_core::svc;
+use std::task::{Context, Poll};
+use tracing::trace;
+
+/// Sends requests with the HTTP version of the target backend, so that traffic
+/// may be split across backends that speak different protocols.
+#[derive(Clone, Debug)]
+pub struct NewTranslateVersion<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct TranslateVersion<S> {
+    version: http::Version,
+    inner: S,
+}
+
+// === impl NewTranslateVersion ===
+
+impl<N> NewTranslateVersion<N> {
+    pub fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone + Copy {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewTranslateVersion<N>
+where
+    T: svc::Param<http::Version>,
+    N: svc::NewService<T>,
+{
+    type Service = TranslateVersion<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        TranslateVersion {
+            version: target.param(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl TranslateVersion ===
+
+impl<S, B> svc::Service<http::Request<B>> for TranslateVersion<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        // HTTP/1 upgrades cannot be translated.
+        if req
+            .extensions()
+            .get::<http::upgrade::Http11Upgrade>()
+            .is_none()
+        {
+            match (self.version, req.version()) {
+                (http::Version::H2, ::http::Version::HTTP_10 | ::http::Version::HTTP_11) => {
+                    trace!("Translating HTTP/1 request to HTTP/2");
+                    http::h1::strip_connection_headers(req.headers_mut());
+                    *req.version_mut() = ::http::Version::HTTP_2;
+                }
+                (http::Version::Http1, ::http::Version::HTTP_2) => {
+                    trace!("Translating HTTP/2 request to HTTP/1.1");
+                    *req.version_mut() = ::http::Version::HTTP_11;
+                }
+                _ => {}
+            }
+        }
+
+        self.inner.call(req)
+    }
+}
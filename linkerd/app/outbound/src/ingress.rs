@@ -66,7 +66,7 @@ impl Outbound<()> {
         // it doesn't include TCP metrics, since they are already instrumented
         // on this ingress stack.
         let opaque = self
-            .to_tcp_connect()
+            .to_opaq_tcp_connect()
             .push_opaq_cached(resolve.clone())
             .map_stack(|_, _, stk| stk.push_map_target(Opaq))
             .push_discover(profiles.clone())
@@ -124,7 +124,12 @@ impl<N> Outbound<N> {
             let Config {
                 proxy:
                     ProxyConfig {
-                        server: ServerConfig { h2_settings, .. },
+                        server:
+                            ServerConfig {
+                                h1_settings,
+                                h2_settings,
+                                ..
+                            },
                         ..
                     },
                 ..
@@ -157,7 +162,12 @@ impl<N> Outbound<N> {
             // destination address.
             http.check_new_service::<Http<T>, http::Request<_>>()
                 .unlift_new()
-                .push(http::NewServeHttp::layer(*h2_settings, rt.drain.clone()))
+                .push(http::NewServeHttp::layer(
+                    *h1_settings,
+                    *h2_settings,
+                    rt.metrics.proxy.http_server.clone(),
+                    rt.drain.clone(),
+                ))
                 .check_new_service::<Http<T>, I>()
                 .push_switch(
                     |(detected, target): (detect::Result<http::Version>, T)| -> Result<_, Infallible> {
@@ -172,7 +182,10 @@ impl<N> Outbound<N> {
                     fallback,
                 )
                 .lift_new_with_target()
-                .push(detect::NewDetectService::layer(detect_http))
+                .push(detect::NewDetectService::layer(
+                    detect_http,
+                    rt.metrics.proxy.detect.clone(),
+                ))
                 .check_new_service::<T, I>()
                 .push_on_service(svc::BoxService::layer())
                 .push(svc::ArcNewService::layer())
@@ -8,7 +8,8 @@
 use futures::Stream;
 use linkerd_app_core::{
     config::{ProxyConfig, QueueConfig},
-    drain,
+    disco_cache, drain,
+    endpoint_pins::EndpointPins,
     http_tracing::OpenCensusSink,
     identity, io, profiles,
     proxy::{
@@ -16,11 +17,11 @@ use linkerd_app_core::{
         core::Resolve,
         tap,
     },
-    serve,
+    serve, startup,
     svc::{self, stack::Param},
     tls,
-    transport::addrs::*,
-    AddrMatch, Error, ProxyRuntime, Result,
+    transport::{self, addrs::*},
+    AddrMatch, Error, NameAddr, ProxyRuntime, Result,
 };
 use std::{
     collections::{HashMap, HashSet},
@@ -41,7 +42,21 @@ pub mod tcp;
 #[cfg(test)]
 pub(crate) mod test_util;
 
-pub use self::{discover::Discovery, metrics::Metrics};
+pub use self::{
+    discover::{
+        Discovery, DiscoveryEvent, DiscoveryEvents, EndpointExclusions, EndpointStaleness,
+        EndpointUpdates, EndpointsFuture, GetProfiles, ProfileFuture, ResolveEndpoints, Resolver,
+        StaleEndpointFallback,
+    },
+    http::{
+        BackendFallback, BalancePolicy, ConnectionLimitConfig, ConnectionLimitMode,
+        EjectionBackoff, FaultRatio, GrpcStatusMapping, HeaderBackends, HealthCheckConfig,
+        InjectAbort, InjectDelay, LatencyOutlierConfig, RequestCoalescingConfig, ResetBehavior,
+        ResponseBodyLimitMode, ResponseCacheConfig, RouteConfig, RouteFaults, TrailerFilter,
+        TrailerPattern,
+    },
+    metrics::{Metrics, PayloadSizeBuckets},
+};
 
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -49,11 +64,51 @@ pub struct Config {
 
     pub proxy: ProxyConfig,
 
+    /// The maximum amount of time a TLS handshake may take once a TCP
+    /// connection has been established. The connect timeout bounds the TCP
+    /// connection, and the two together bound the entire connection attempt.
+    pub tls_handshake_timeout: Duration,
+
     /// Configures the duration the proxy will retain idle stacks (with no
     /// active connections) for an outbound address. When an idle stack is
     /// dropped, all cached service discovery information is dropped.
     pub discovery_idle_timeout: Duration,
 
+    /// The maximum amount of time by which each discovery result's idle
+    /// timeout is randomly extended.
+    pub discovery_idle_jitter: Duration,
+
+    /// The maximum amount of time a discovery result is cached, regardless of
+    /// whether it is in use. Once a result reaches this age, new connections
+    /// re-resolve it while existing connections keep the prior result. When
+    /// unset, results are cached until they idle out.
+    pub discovery_max_lifetime: Option<Duration>,
+
+    /// Endpoint labels that exclude resolved endpoints from load balancers.
+    /// Endpoints with any of these labels are not sent traffic while they
+    /// remain in a service's resolution.
+    pub endpoint_exclusions: EndpointExclusions,
+
+    /// When set, resolved endpoints that are not refreshed by their
+    /// resolution within a maximum age are evicted from load balancers.
+    pub endpoint_staleness: Option<EndpointStaleness>,
+
+    /// When set, endpoints removed from a resolution are only removed from
+    /// load balancers if they are not re-added within this window, so that
+    /// balancers are not disturbed by transient endpoint sets while services
+    /// scale.
+    pub endpoint_stabilization_window: Option<Duration>,
+
+    /// The local addresses to which connections to endpoints are bound, by
+    /// the endpoint's network. Connections to other endpoints are bound to an
+    /// address chosen by the operating system.
+    pub connect_source_addrs: transport::SourceAddrs,
+
+    /// Configures TCP keepalive probes on opaque connections to endpoints, so
+    /// that long-lived connections to peers that stop responding are closed.
+    /// When unset, opaque connections use the proxy's connect keepalive.
+    pub opaq_keepalive_probes: Option<transport::KeepaliveProbes>,
+
     /// Configures how connections are buffered *for each outbound address*.
     ///
     /// A buffer capacity of 100 means that 100 connections may be buffered for
@@ -66,6 +121,12 @@ pub struct Config {
     /// each IP:port to which an application has opened an outbound TCP connection.
     pub http_request_queue: QueueConfig,
 
+    /// Whether each backend's request queue dispatches requests round-robin
+    /// across clients, so that no single client may monopolize the queue.
+    /// Otherwise, requests are dispatched in the order in which they are
+    /// queued.
+    pub http_request_queue_fair: bool,
+
     // In "ingress mode", we assume we are always routing HTTP requests and do
     // not perform per-target-address discovery. Non-HTTP connections are
     // forwarded without discovery/routing/mTLS.
@@ -74,6 +135,128 @@ pub struct Config {
 
     // Whether the proxy may include informational headers on HTTP responses.
     pub emit_headers: bool,
+
+    /// Whether HTTP error responses synthesized by the proxy include a JSON
+    /// body describing the error.
+    pub json_error_bodies: bool,
+
+    /// Whether errors on HTTP/1 gRPC-Web requests are synthesized as
+    /// trailers-only gRPC-Web responses rather than HTTP error responses.
+    pub grpc_web_errors: bool,
+
+    /// Whether origin-form HTTP/1 requests must include a `Host` header. When
+    /// set, requests without one are rejected with a 400 rather than being
+    /// routed to the connection's original destination.
+    pub http1_require_host: bool,
+
+    /// Whether HTTP/1 requests with ambiguous framing (e.g. conflicting
+    /// `Content-Length` and `Transfer-Encoding` headers) are rejected with a
+    /// 400, to defend against request smuggling.
+    pub http1_reject_ambiguous_framing: bool,
+
+    /// Configures how the `Transfer-Encoding` of chunked HTTP/1 requests is
+    /// canonicalized before they are forwarded. When unset, requests are
+    /// forwarded as they are parsed.
+    pub http1_transfer_encoding: Option<http::framing::TransferEncodingMode>,
+
+    /// The maximum length of each HTTP request header value, in bytes.
+    /// Requests with longer header values are rejected with a 431. When unset,
+    /// header values are not limited.
+    pub http_max_header_value_bytes: Option<usize>,
+
+    /// An optional latency SLO applied to each HTTP route that is not
+    /// configured with its own. Requests exceeding their route's SLO are
+    /// counted per-route.
+    pub route_latency_slo: Option<Duration>,
+
+    /// Whether each request that exceeds its route's latency SLO is logged.
+    pub route_latency_slo_log_breaches: bool,
+
+    /// The window over which the availability of each HTTP route is reported.
+    /// When unset, route availability is not reported.
+    pub route_availability_window: Option<Duration>,
+
+    /// The bucket boundaries of the histograms of each HTTP route's request
+    /// and response body sizes. When unset, payload sizes are not reported.
+    pub route_payload_size_buckets: Option<PayloadSizeBuckets>,
+
+    /// Configures active health checks of balanced HTTP endpoints. When unset,
+    /// endpoints are not probed.
+    pub http_health_check: Option<HealthCheckConfig>,
+
+    /// Configures the ejection of balanced HTTP endpoints whose tail latency
+    /// is an outlier. When unset, endpoints are not ejected.
+    pub http_latency_outlier_detection: Option<LatencyOutlierConfig>,
+
+    /// Limits the number of connections to each HTTP backend, across all of
+    /// its endpoints. When unset, connections are not limited.
+    pub http_backend_connection_limit: Option<ConnectionLimitConfig>,
+
+    /// Configures a cache of responses to `GET` requests for each HTTP route.
+    /// When unset, responses are not cached.
+    pub http_response_cache: Option<ResponseCacheConfig>,
+
+    /// Configures the coalescing of identical in-flight `GET` requests on each
+    /// HTTP route. When unset, requests are not coalesced.
+    pub http_request_coalescing: Option<RequestCoalescingConfig>,
+
+    /// Overrides the HTTP version used to reach each named backend of a
+    /// traffic split, so that traffic may be split across backends that speak
+    /// different protocols. Requests are translated to each backend's version.
+    pub http_backend_protocols: Arc<HashMap<NameAddr, http::Version>>,
+
+    /// Overrides the load balancing policy of each named backend. Backends
+    /// are balanced by peak-EWMA latency by default.
+    pub http_backend_balancers: Arc<HashMap<NameAddr, BalancePolicy>>,
+
+    /// Configures the HTTP routes of each logical service, by the name in
+    /// their `route` label.
+    pub http_routes: Arc<HashMap<NameAddr, HashMap<String, RouteConfig>>>,
+
+    /// Determines whether response bodies that exceed their route's limit fail
+    /// or are truncated.
+    pub http_route_response_body_limit_mode: ResponseBodyLimitMode,
+
+    /// Determines how requests are handled when none of the backends a
+    /// service's profile references are available, e.g. because they do not
+    /// resolve to endpoints.
+    pub http_backend_fallback: BackendFallback,
+
+    /// The maximum number of request body bytes buffered so that a request may
+    /// be retried. Requests with larger bodies are not retried.
+    pub http_retry_max_buffered_bytes: usize,
+
+    /// Response statuses that are retried on routes with a retry budget, in
+    /// addition to those the route classifies as failures.
+    pub http_retryable_statuses: Arc<HashSet<http::StatusCode>>,
+
+    /// The minimum time a retried request is expected to take. When set,
+    /// requests on routes with a timeout are not retried unless the time
+    /// remaining before the timeout allows for another attempt that takes at
+    /// least this long, or as long as the prior attempt if it took longer.
+    pub http_retry_min_attempt_time: Option<Duration>,
+
+    /// The maximum time a request waits before being retried when a 429 or 503
+    /// response includes a `Retry-After` header. When unset, `Retry-After`
+    /// headers are ignored.
+    pub http_retry_after_max: Option<Duration>,
+
+    /// A header that marks requests as idempotent. When set, only requests
+    /// without bodies and requests that include this header are retried,
+    /// regardless of their methods.
+    pub http_retry_idempotent_header: Option<http::HeaderName>,
+
+    /// Determines whether requests are retried when the upstream resets the
+    /// connection or stream before the response has been received.
+    pub http_retry_on_reset: ResetBehavior,
+
+    /// The time reserved before a route's timeout for a failed attempt's
+    /// response to be processed. When set, each attempt on a route with a
+    /// timeout times out this long before the route's timeout would elapse.
+    pub http_retry_timeout_headroom: Option<Duration>,
+
+    /// Determines which response trailers are forwarded to clients.
+    pub http_response_trailers: TrailerFilter,
 }
 
 #[derive(Clone, Debug)]
@@ -86,10 +269,14 @@ pub struct Outbound<S> {
 #[derive(Clone, Debug)]
 struct Runtime {
     metrics: Metrics,
-    identity: identity::NewClient,
+    identity: tls::NewShadowClient<identity::NewClient>,
     tap: tap::Registry,
     span_sink: OpenCensusSink,
     drain: drain::Watch,
+    discovery_events: DiscoveryEvents,
+    discovery_evictions: disco_cache::Evictions<profiles::LookupAddr>,
+    endpoint_pins: EndpointPins,
+    startup: startup::StartupGate,
 }
 
 pub type ConnectMeta = tls::ConnectMeta<Local<ClientAddr>>;
@@ -100,10 +287,20 @@ impl Outbound<()> {
     pub fn new(config: Config, runtime: ProxyRuntime) -> Self {
         let runtime = Runtime {
             metrics: Metrics::new(runtime.metrics),
-            identity: runtime.identity.new_client(),
+            identity: tls::NewShadowClient::new(
+                runtime.identity.new_client(),
+                runtime.shadow_identity.map(|shadow| tls::Shadow {
+                    servers: shadow.servers,
+                    identity: shadow.identity.new_client(),
+                }),
+            ),
             tap: runtime.tap,
             span_sink: runtime.span_sink,
             drain: runtime.drain,
+            discovery_events: DiscoveryEvents::default(),
+            discovery_evictions: Default::default(),
+            endpoint_pins: EndpointPins::default(),
+            startup: runtime.startup,
         };
         Self {
             config,
@@ -130,6 +327,23 @@ impl<S> Outbound<S> {
         self.runtime.metrics.proxy.stack.clone()
     }
 
+    /// Returns a handle for subscribing to profile and endpoint discovery
+    /// events.
+    pub fn discovery_events(&self) -> DiscoveryEvents {
+        self.runtime.discovery_events.clone()
+    }
+
+    /// Returns a handle for evicting cached discoveries, so that they are
+    /// re-resolved.
+    pub fn discovery_evictions(&self) -> disco_cache::Evictions<profiles::LookupAddr> {
+        self.runtime.discovery_evictions.clone()
+    }
+
+    /// Returns a handle for pinning balanced backends to specific endpoints.
+    pub fn endpoint_pins(&self) -> EndpointPins {
+        self.runtime.endpoint_pins.clone()
+    }
+
     pub fn with_stack<Svc>(self, stack: Svc) -> Outbound<Svc> {
         self.map_stack(move |_, _, _| svc::stack(stack))
     }
@@ -190,11 +404,15 @@ impl Outbound<()> {
     {
         if self.config.ingress_mode {
             tracing::info!("Outbound routing in ingress-mode");
-            let server = self.mk_ingress(profiles, resolve);
+            let server = svc::stack(self.mk_ingress(profiles, resolve))
+                .push(startup::NewStartupGate::layer(self.runtime.startup.clone()))
+                .into_inner();
             let shutdown = self.runtime.drain.signaled();
             serve::serve(listen, server, shutdown).await;
         } else {
-            let proxy = self.mk_sidecar(profiles, resolve);
+            let proxy = svc::stack(self.mk_sidecar(profiles, resolve))
+                .push(startup::NewStartupGate::layer(self.runtime.startup.clone()))
+                .into_inner();
             let shutdown = self.runtime.drain.signaled();
             serve::serve(listen, proxy, shutdown).await;
         }
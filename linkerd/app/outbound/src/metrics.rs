@@ -8,8 +8,19 @@
 //! to be updated frequently or in a performance-critical area. We should probably look to use
 //! `DashMap` as we migrate other metrics registries.
 
+pub(crate) mod availability;
+pub(crate) mod connect;
+pub(crate) mod dedup;
+pub(crate) mod discovery;
 pub(crate) mod error;
+pub(crate) mod keepalive;
+pub(crate) mod payload_size;
+pub(crate) mod queue_wait;
+pub(crate) mod retry_budget;
+pub(crate) mod slo;
+pub(crate) mod stack_layer;
 
+pub use self::payload_size::PayloadSizeBuckets;
 pub use linkerd_app_core::metrics::*;
 
 /// Holds outbound proxy metrics.
@@ -17,6 +28,17 @@ pub use linkerd_app_core::metrics::*;
 pub struct Metrics {
     pub(crate) http_errors: error::Http,
     pub(crate) tcp_errors: error::Tcp,
+    pub(crate) tcp_keepalive_closes: keepalive::KeepaliveCloses,
+    pub(crate) connect_errors: connect::ConnectErrors,
+    pub(crate) route_slo: slo::RouteSlo,
+    pub(crate) route_availability: availability::RouteAvailability,
+    pub(crate) route_retry_budgets: retry_budget::RouteRetryBudgets,
+    pub(crate) route_dedup: dedup::RouteDedup,
+    pub(crate) route_payload_sizes: payload_size::RoutePayloadSizes,
+    pub(crate) profile_lookups: discovery::ProfileLookups,
+    pub(crate) discover_backpressure: discovery::DiscoverBackpressure,
+    pub(crate) stack_layers: stack_layer::StackLayers,
+    pub(crate) request_queue_wait: queue_wait::RequestQueueWait,
 
     /// Holds metrics that are common to both inbound and outbound proxies. These metrics are
     /// reported separately
@@ -28,6 +50,17 @@ impl Metrics {
         Self {
             http_errors: error::Http::default(),
             tcp_errors: error::Tcp::default(),
+            tcp_keepalive_closes: keepalive::KeepaliveCloses::default(),
+            connect_errors: connect::ConnectErrors::default(),
+            route_slo: slo::RouteSlo::default(),
+            route_availability: availability::RouteAvailability::default(),
+            route_retry_budgets: retry_budget::RouteRetryBudgets::default(),
+            route_dedup: dedup::RouteDedup::default(),
+            route_payload_sizes: payload_size::RoutePayloadSizes::default(),
+            profile_lookups: discovery::ProfileLookups::default(),
+            discover_backpressure: discovery::DiscoverBackpressure::default(),
+            stack_layers: stack_layer::StackLayers::default(),
+            request_queue_wait: queue_wait::RequestQueueWait::default(),
             proxy,
         }
     }
@@ -37,6 +70,17 @@ impl FmtMetrics for Metrics {
     fn fmt_metrics(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.http_errors.fmt_metrics(f)?;
         self.tcp_errors.fmt_metrics(f)?;
+        self.tcp_keepalive_closes.fmt_metrics(f)?;
+        self.connect_errors.fmt_metrics(f)?;
+        self.route_slo.fmt_metrics(f)?;
+        self.route_availability.fmt_metrics(f)?;
+        self.route_retry_budgets.fmt_metrics(f)?;
+        self.route_dedup.fmt_metrics(f)?;
+        self.route_payload_sizes.fmt_metrics(f)?;
+        self.profile_lookups.fmt_metrics(f)?;
+        self.discover_backpressure.fmt_metrics(f)?;
+        self.stack_layers.fmt_metrics(f)?;
+        self.request_queue_wait.fmt_metrics(f)?;
 
         // XXX: Proxy metrics are reported elsewhere.
 
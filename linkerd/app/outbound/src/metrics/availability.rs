@@ -0,0 +1,377 @@
+//! Reports the availability of each outbound HTTP route: the proportion of
+//! its requests that were classified as successful over a rolling window.
+
+use crate::http;
+use futures::ready;
+use http_body::Body;
+use linkerd_app_core::{
+    classify,
+    metrics::{metrics, FmtLabels, FmtMetric, FmtMetrics, ProfileRouteLabels},
+    svc, Error,
+};
+use linkerd_http_classify::{ClassifyEos, ClassifyResponse};
+use parking_lot::{Mutex, RwLock};
+use pin_project::{pin_project, pinned_drop};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time;
+
+#[cfg(test)]
+mod tests;
+
+metrics! {
+    outbound_http_route_availability: Availability {
+        "The proportion of an outbound HTTP route's recent requests that were classified as successful."
+    }
+}
+
+/// The number of buckets into which each route's window is divided. Buckets
+/// expire whole, so the window advances in increments of `window / BUCKETS`.
+const BUCKETS: u32 = 10;
+
+/// Holds the availability of each route.
+///
+/// Availabilities are shared with the services of the routes they track, so
+/// that routes that are no longer in use are dropped from the registry once
+/// they have been reported.
+#[derive(Clone, Debug, Default)]
+pub struct RouteAvailability(Arc<RwLock<HashMap<ProfileRouteLabels, Arc<Availability>>>>);
+
+/// Tracks the classifications of a route's requests over a rolling window.
+#[derive(Debug)]
+pub struct Availability {
+    window: Duration,
+    buckets: Mutex<VecDeque<Bucket>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewRecordAvailability<N> {
+    inner: N,
+    window: Option<Duration>,
+    registry: RouteAvailability,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordAvailability<S> {
+    inner: S,
+    availability: Option<Arc<Availability>>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    state: Option<(classify::Response, Arc<Availability>)>,
+}
+
+#[pin_project(PinnedDrop)]
+#[derive(Debug)]
+pub struct ResponseBody<B> {
+    #[pin]
+    inner: B,
+    state: Option<(classify::Eos, Arc<Availability>)>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    start: time::Instant,
+    successes: u64,
+    failures: u64,
+}
+
+// === impl RouteAvailability ===
+
+impl RouteAvailability {
+    /// Returns a layer that records the availability of each route target.
+    ///
+    /// When `window` is `None`, requests are not recorded.
+    pub(crate) fn to_layer<N>(
+        &self,
+        window: Option<Duration>,
+    ) -> impl svc::layer::Layer<N, Service = NewRecordAvailability<N>> + Clone {
+        let registry = self.clone();
+        svc::layer::mk(move |inner| NewRecordAvailability {
+            inner,
+            window,
+            registry: registry.clone(),
+        })
+    }
+
+    fn get_or_insert(&self, labels: ProfileRouteLabels, window: Duration) -> Arc<Availability> {
+        if let Some(availability) = self.0.read().get(&labels) {
+            return availability.clone();
+        }
+        self.0
+            .write()
+            .entry(labels)
+            .or_insert_with(|| Arc::new(Availability::new(window)))
+            .clone()
+    }
+
+    #[cfg(test)]
+    fn ratio(&self, labels: &ProfileRouteLabels) -> Option<f64> {
+        self.0.read().get(labels)?.ratio()
+    }
+}
+
+impl FmtMetrics for RouteAvailability {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut metrics = self.0.write();
+        if metrics.is_empty() {
+            return Ok(());
+        }
+        outbound_http_route_availability.fmt_help(f)?;
+        outbound_http_route_availability.fmt_scopes(f, metrics.iter(), |a| a)?;
+
+        // Routes whose services have been dropped are reported one last time
+        // and then forgotten.
+        metrics.retain(|_, a| Arc::strong_count(a) > 1);
+        Ok(())
+    }
+}
+
+// === impl Availability ===
+
+impl Availability {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            buckets: Mutex::new(VecDeque::with_capacity(BUCKETS as usize + 1)),
+        }
+    }
+
+    fn record(&self, class: classify::Class) {
+        let now = time::Instant::now();
+        let mut buckets = self.buckets.lock();
+        self.expire(&mut buckets, now);
+
+        let width = self.window / BUCKETS;
+        let is_stale = buckets
+            .back()
+            .map(|b| now.saturating_duration_since(b.start) >= width)
+            .unwrap_or(true);
+        if is_stale {
+            buckets.push_back(Bucket {
+                start: now,
+                successes: 0,
+                failures: 0,
+            });
+        }
+
+        let bucket = buckets.back_mut().expect("bucket must be present");
+        if class.is_failure() {
+            bucket.failures += 1;
+        } else {
+            bucket.successes += 1;
+        }
+    }
+
+    /// Returns the proportion of requests in the window that succeeded, or
+    /// `None` if no requests were recorded in the window.
+    fn ratio(&self) -> Option<f64> {
+        let mut buckets = self.buckets.lock();
+        self.expire(&mut buckets, time::Instant::now());
+
+        let (successes, failures) = buckets
+            .iter()
+            .fold((0, 0), |(s, f), b| (s + b.successes, f + b.failures));
+        let total = successes + failures;
+        if total == 0 {
+            return None;
+        }
+        Some(successes as f64 / total as f64)
+    }
+
+    fn expire(&self, buckets: &mut VecDeque<Bucket>, now: time::Instant) {
+        while let Some(bucket) = buckets.front() {
+            if now.saturating_duration_since(bucket.start) < self.window {
+                break;
+            }
+            buckets.pop_front();
+        }
+    }
+}
+
+/// Routes without requests in the window are not reported.
+impl FmtMetric for Availability {
+    const KIND: &'static str = "gauge";
+
+    fn fmt_metric<N: fmt::Display>(&self, f: &mut fmt::Formatter<'_>, name: N) -> fmt::Result {
+        match self.ratio() {
+            Some(ratio) => writeln!(f, "{} {}", name, ratio),
+            None => Ok(()),
+        }
+    }
+
+    fn fmt_metric_labeled<N, L>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: N,
+        labels: L,
+    ) -> fmt::Result
+    where
+        N: fmt::Display,
+        L: FmtLabels,
+    {
+        let ratio = match self.ratio() {
+            Some(ratio) => ratio,
+            None => return Ok(()),
+        };
+        write!(f, "{}{{", name)?;
+        labels.fmt_labels(f)?;
+        writeln!(f, "}} {}", ratio)
+    }
+}
+
+// === impl NewRecordAvailability ===
+
+impl<T, N> svc::NewService<T> for NewRecordAvailability<N>
+where
+    T: svc::Param<ProfileRouteLabels>,
+    N: svc::NewService<T>,
+{
+    type Service = RecordAvailability<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let availability = self
+            .window
+            .map(|window| self.registry.get_or_insert(target.param(), window));
+        let inner = self.inner.new_service(target);
+        RecordAvailability {
+            inner,
+            availability,
+        }
+    }
+}
+
+// === impl RecordAvailability ===
+
+impl<B, RspB, S> svc::Service<http::Request<B>> for RecordAvailability<S>
+where
+    S: svc::Service<http::Request<B>, Response = http::Response<RspB>>,
+    S::Error: Into<Error>,
+{
+    type Response = http::Response<ResponseBody<RspB>>;
+    type Error = Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        // The route's classifier is set as a request extension by an outer
+        // layer.
+        let state = self.availability.clone().map(|availability| {
+            let classify = req
+                .extensions()
+                .get::<classify::Response>()
+                .cloned()
+                .unwrap_or_default();
+            (classify, availability)
+        });
+        ResponseFuture {
+            inner: self.inner.call(req),
+            state,
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<B, E, F> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+    E: Into<Error>,
+{
+    type Output = Result<http::Response<ResponseBody<B>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        match ready!(this.inner.poll(cx)) {
+            Ok(rsp) => {
+                let state = this
+                    .state
+                    .take()
+                    .map(|(classify, availability)| (classify.start(&rsp), availability));
+                Poll::Ready(Ok(rsp.map(|inner| ResponseBody { inner, state })))
+            }
+            Err(error) => {
+                let error = error.into();
+                if let Some((classify, availability)) = this.state.take() {
+                    availability.record(classify.error(&error));
+                }
+                Poll::Ready(Err(error))
+            }
+        }
+    }
+}
+
+// === impl ResponseBody ===
+
+impl<B> ResponseBody<B> {
+    fn record_error(self: Pin<&mut Self>, error: Error) -> Error {
+        if let Some((eos, availability)) = self.project().state.take() {
+            availability.record(eos.error(&error));
+        }
+        error
+    }
+}
+
+impl<B> Body for ResponseBody<B>
+where
+    B: Body,
+    B::Error: Into<Error>,
+{
+    type Data = B::Data;
+    type Error = Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let frame = ready!(self.as_mut().project().inner.poll_data(cx));
+        Poll::Ready(frame.map(|res| res.map_err(|e| self.as_mut().record_error(e.into()))))
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::header::HeaderMap>, Self::Error>> {
+        let trailers = ready!(self.as_mut().project().inner.poll_trailers(cx))
+            .map_err(|e| self.as_mut().record_error(e.into()))?;
+        if let Some((eos, availability)) = self.project().state.take() {
+            availability.record(eos.eos(trailers.as_ref()));
+        }
+        Poll::Ready(Ok(trailers))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[pinned_drop]
+impl<B> PinnedDrop for ResponseBody<B> {
+    fn drop(self: Pin<&mut Self>) {
+        // Responses whose trailers are not read are classified without them.
+        if let Some((eos, availability)) = self.project().state.take() {
+            availability.record(eos.eos(None));
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use super::*;
+use linkerd_app_core::{
+    profiles,
+    svc::{NewService, ServiceExt},
+    NameAddr,
+};
+
+#[derive(Clone, Debug)]
+struct Target(ProfileRouteLabels);
+
+impl svc::Param<ProfileRouteLabels> for Target {
+    fn param(&self) -> ProfileRouteLabels {
+        self.0.clone()
+    }
+}
+
+fn labels() -> ProfileRouteLabels {
+    ProfileRouteLabels::outbound(
+        profiles::LogicalAddr("foo.ns.svc.cluster.local:8080".parse::<NameAddr>().unwrap()),
+        &profiles::http::Route::default(),
+    )
+}
+
+/// Builds a route service that fails requests to `/fail` with a 500.
+fn route(
+    registry: &RouteAvailability,
+    window: Option<Duration>,
+) -> impl svc::Service<
+    http::Request<http::BoxBody>,
+    Response = http::Response<ResponseBody<http::BoxBody>>,
+    Error = Error,
+> + Clone {
+    svc::stack(|_: Target| {
+        svc::mk(|req: http::Request<http::BoxBody>| async move {
+            let mut rsp = http::Response::new(http::BoxBody::default());
+            if req.uri().path() == "/fail" {
+                *rsp.status_mut() = http::StatusCode::INTERNAL_SERVER_ERROR;
+            }
+            Ok::<_, Error>(rsp)
+        })
+    })
+    .push(registry.to_layer(window))
+    .into_inner()
+    .new_service(Target(labels()))
+}
+
+async fn send(
+    svc: impl svc::Service<
+        http::Request<http::BoxBody>,
+        Response = http::Response<ResponseBody<http::BoxBody>>,
+        Error = Error,
+    >,
+    path: &str,
+) {
+    let req = http::Request::get(path)
+        .body(http::BoxBody::default())
+        .unwrap();
+    let rsp = svc.oneshot(req).await.unwrap();
+    hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn reports_success_ratio() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let registry = RouteAvailability::default();
+    let svc = route(&registry, Some(Duration::from_secs(60)));
+    assert_eq!(registry.ratio(&labels()), None);
+
+    for path in ["/ok", "/ok", "/fail", "/ok"] {
+        send(svc.clone(), path).await;
+    }
+    assert_eq!(registry.ratio(&labels()), Some(0.75));
+
+    let metrics = registry.as_display().to_string();
+    let line = metrics
+        .lines()
+        .find(|l| l.starts_with("outbound_http_route_availability{"))
+        .expect("availability must be reported");
+    assert!(line.ends_with(" 0.75"), "{}", line);
+
+    // Requests expire from the window in buckets.
+    time::sleep(Duration::from_secs(30)).await;
+    send(svc.clone(), "/fail").await;
+    assert_eq!(registry.ratio(&labels()), Some(0.6));
+
+    time::sleep(Duration::from_secs(30)).await;
+    assert_eq!(registry.ratio(&labels()), Some(0.0));
+
+    time::sleep(Duration::from_secs(30)).await;
+    assert_eq!(registry.ratio(&labels()), None);
+    assert!(
+        !registry
+            .as_display()
+            .to_string()
+            .contains("outbound_http_route_availability{"),
+        "routes without requests in the window must not be reported"
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn disabled_without_window() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let registry = RouteAvailability::default();
+    let svc = route(&registry, None);
+    send(svc, "/fail").await;
+    assert_eq!(registry.ratio(&labels()), None);
+    assert!(registry.as_display().to_string().is_empty());
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn forgets_dropped_routes() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let registry = RouteAvailability::default();
+    let svc = route(&registry, Some(Duration::from_secs(60)));
+    send(svc, "/ok").await;
+    assert_eq!(registry.ratio(&labels()), Some(1.0));
+
+    // The route is reported after it is dropped, and then it is forgotten.
+    assert!(registry
+        .as_display()
+        .to_string()
+        .contains("outbound_http_route_availability{"));
+    assert_eq!(registry.ratio(&labels()), None);
+    assert!(registry.as_display().to_string().is_empty());
+}
@@ -0,0 +1,210 @@
+//! Counts failed outbound connects, by the reason they failed.
+//!
+//! Each error is classified by inspecting its chain of causes, so that, e.g., a
+//! timeout is counted as a timeout regardless of the layer that reported it.
+//! Errors that cannot be classified are counted as `other`.
+
+use linkerd_app_core::{
+    dns,
+    errors::ConnectTimeout,
+    io,
+    metrics::{metrics, Counter, FmtLabels, FmtMetrics},
+    svc, tls, transport, Error,
+};
+use pin_project::pin_project;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+#[cfg(test)]
+mod tests;
+
+metrics! {
+    outbound_connect_errors_total: Counter {
+        "The total number of failed outbound connects, by reason."
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ConnectErrors(Arc<Counters>);
+
+#[derive(Debug, Default)]
+struct Counters {
+    dns: Counter,
+    refused: Counter,
+    timeout: Counter,
+    tls: Counter,
+    no_route: Counter,
+    other: Counter,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Reason {
+    Dns,
+    Refused,
+    Timeout,
+    Tls,
+    NoRoute,
+    Other,
+}
+
+/// Records the reason each failed connect made by an inner connector failed.
+#[derive(Clone, Debug)]
+pub struct RecordConnectErrors<S> {
+    inner: S,
+    registry: ConnectErrors,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ConnectFuture<F> {
+    #[pin]
+    inner: F,
+    registry: ConnectErrors,
+}
+
+// === impl ConnectErrors ===
+
+impl ConnectErrors {
+    /// Returns a layer that records the errors of the connector it wraps.
+    pub(crate) fn layer<S>(
+        &self,
+    ) -> impl svc::layer::Layer<S, Service = RecordConnectErrors<S>> + Clone {
+        let registry = self.clone();
+        svc::layer::mk(move |inner| RecordConnectErrors {
+            inner,
+            registry: registry.clone(),
+        })
+    }
+
+    fn counter(&self, reason: Reason) -> &Counter {
+        match reason {
+            Reason::Dns => &self.0.dns,
+            Reason::Refused => &self.0.refused,
+            Reason::Timeout => &self.0.timeout,
+            Reason::Tls => &self.0.tls,
+            Reason::NoRoute => &self.0.no_route,
+            Reason::Other => &self.0.other,
+        }
+    }
+}
+
+impl FmtMetrics for ConnectErrors {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        outbound_connect_errors_total.fmt_help(f)?;
+        outbound_connect_errors_total.fmt_scopes(
+            f,
+            [
+                Reason::Dns,
+                Reason::Refused,
+                Reason::Timeout,
+                Reason::Tls,
+                Reason::NoRoute,
+                Reason::Other,
+            ]
+            .into_iter()
+            .map(|reason| (reason, self.counter(reason))),
+            |c| c,
+        )
+    }
+}
+
+// === impl Reason ===
+
+impl Reason {
+    fn mk(err: &(dyn std::error::Error + 'static)) -> Self {
+        if err.is::<ConnectTimeout>() {
+            return Reason::Timeout;
+        }
+        if err.is::<tls::client::HandshakeTimeout>() {
+            return Reason::Tls;
+        }
+        if err.is::<dns::ResolveError>() {
+            return Reason::Dns;
+        }
+        if let Some(e) = err.downcast_ref::<io::Error>() {
+            match e.kind() {
+                io::ErrorKind::ConnectionRefused => return Reason::Refused,
+                io::ErrorKind::TimedOut => return Reason::Timeout,
+                // TLS implementations report invalid handshakes as invalid
+                // data.
+                io::ErrorKind::InvalidData => return Reason::Tls,
+                _ if transport::is_unreachable(e) => return Reason::NoRoute,
+                _ => {}
+            }
+            // The source of an I/O error is the source of the error it wraps,
+            // so the wrapped error must be checked directly.
+            if let Some(inner) = e.get_ref() {
+                let reason = Self::mk(inner);
+                if reason != Reason::Other {
+                    return reason;
+                }
+            }
+        }
+        match err.source() {
+            Some(e) => Self::mk(e),
+            None => Reason::Other,
+        }
+    }
+}
+
+impl FmtLabels for Reason {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Self::Dns => "dns",
+            Self::Refused => "refused",
+            Self::Timeout => "timeout",
+            Self::Tls => "tls",
+            Self::NoRoute => "no_route",
+            Self::Other => "other",
+        };
+        write!(f, "reason=\"{}\"", reason)
+    }
+}
+
+// === impl RecordConnectErrors ===
+
+impl<T, S> svc::Service<T> for RecordConnectErrors<S>
+where
+    S: svc::Service<T, Error = Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = ConnectFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        ConnectFuture {
+            inner: self.inner.call(target),
+            registry: self.registry.clone(),
+        }
+    }
+}
+
+// === impl ConnectFuture ===
+
+impl<F, T> Future for ConnectFuture<F>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = futures::ready!(this.inner.poll(cx));
+        if let Err(error) = &res {
+            let reason = Reason::mk(&**error);
+            tracing::debug!(?reason, %error, "Connect failed");
+            this.registry.counter(reason).incr();
+        }
+        Poll::Ready(res)
+    }
+}
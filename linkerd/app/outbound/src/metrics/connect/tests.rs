@@ -0,0 +1,128 @@
+use super::*;
+use crate::{tcp, test_util::*, Outbound};
+use futures::future;
+use linkerd_app_core::{
+    metrics::OutboundEndpointLabels,
+    svc::{MakeConnection, ServiceExt},
+    transport::{ClientAddr, Local, Remote, ServerAddr},
+    transport_header::SessionProtocol,
+};
+use std::net::SocketAddr;
+
+#[derive(Clone, Debug)]
+struct Endpoint(Remote<ServerAddr>);
+
+/// Tests that refused connects are counted as refused.
+#[tokio::test(flavor = "current_thread")]
+async fn counts_refused_connects() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let connect = svc::mk(|_: tcp::Connect| {
+        future::err::<(io::DuplexStream, Local<ClientAddr>), _>(io::Error::from(
+            io::ErrorKind::ConnectionRefused,
+        ))
+    });
+    let errors = connect_errors(connect).await;
+    assert_counts(&errors, "refused");
+}
+
+/// Tests that connects that exceed the connect timeout are counted as timeouts.
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn counts_connect_timeouts() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let connect = svc::mk(|_: tcp::Connect| {
+        future::pending::<io::Result<(io::DuplexStream, Local<ClientAddr>)>>()
+    });
+    let errors = connect_errors(connect).await;
+    assert_counts(&errors, "timeout");
+}
+
+/// Connects to an endpoint with the given connector, returning the formatted
+/// connect error metrics once the connect fails.
+async fn connect_errors<C>(connect: C) -> String
+where
+    C: svc::MakeConnection<tcp::Connect, Metadata = Local<ClientAddr>, Error = io::Error>,
+    C: Clone + Send + 'static,
+    C::Connection: Send + Unpin,
+    C::Metadata: Send + Unpin,
+    C::Future: Send + 'static,
+{
+    let addr = SocketAddr::new([192, 0, 2, 30].into(), 3030);
+    let (rt, _shutdown) = runtime();
+    let outbound = Outbound::new(default_config(), rt);
+    let metrics = outbound.metrics();
+    let stack = outbound
+        .with_stack(connect)
+        .push_tcp_endpoint()
+        .into_inner();
+
+    stack
+        .into_service()
+        .oneshot(Endpoint(Remote(ServerAddr(addr))))
+        .await
+        .err()
+        .expect("connect must fail");
+    metrics.connect_errors.as_display().to_string()
+}
+
+/// Asserts that exactly one error was counted, with the given reason.
+#[track_caller]
+fn assert_counts(metrics: &str, expected: &str) {
+    for reason in ["dns", "refused", "timeout", "tls", "no_route", "other"] {
+        let count = if reason == expected { 1 } else { 0 };
+        let sample = format!(
+            "outbound_connect_errors_total{{reason=\"{}\"}} {}",
+            reason, count
+        );
+        assert!(
+            metrics.lines().any(|l| l == sample),
+            "{} not found in:\n{}",
+            sample,
+            metrics
+        );
+    }
+}
+
+// === impl Endpoint ===
+
+impl svc::Param<Remote<ServerAddr>> for Endpoint {
+    fn param(&self) -> Remote<ServerAddr> {
+        self.0
+    }
+}
+
+impl svc::Param<tls::ConditionalClientTls> for Endpoint {
+    fn param(&self) -> tls::ConditionalClientTls {
+        tls::ConditionalClientTls::None(tls::NoClientTls::Disabled)
+    }
+}
+
+impl svc::Param<Option<tcp::tagged_transport::PortOverride>> for Endpoint {
+    fn param(&self) -> Option<tcp::tagged_transport::PortOverride> {
+        None
+    }
+}
+
+impl svc::Param<Option<crate::http::AuthorityOverride>> for Endpoint {
+    fn param(&self) -> Option<crate::http::AuthorityOverride> {
+        None
+    }
+}
+
+impl svc::Param<Option<SessionProtocol>> for Endpoint {
+    fn param(&self) -> Option<SessionProtocol> {
+        None
+    }
+}
+
+impl svc::Param<transport::labels::Key> for Endpoint {
+    fn param(&self) -> transport::labels::Key {
+        transport::labels::Key::OutboundClient(OutboundEndpointLabels {
+            authority: None,
+            labels: None,
+            server_id: self.param(),
+            target_addr: self.0.into(),
+        })
+    }
+}
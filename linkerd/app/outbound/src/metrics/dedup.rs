@@ -0,0 +1,206 @@
+//! Counts outbound HTTP requests that were deduplicated on each route, either
+//! by sharing the response of an identical in-flight request or by serving a
+//! cached response.
+
+use crate::http;
+use linkerd_app_core::{
+    metrics::{metrics, Counter, FmtMetrics, ProfileRouteLabels},
+    svc,
+};
+use parking_lot::RwLock;
+use pin_project::pin_project;
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+#[cfg(test)]
+mod tests;
+
+metrics! {
+    route_dedup_coalesced_total: Counter {
+        "The total number of outbound HTTP requests that were served the response of an identical in-flight request on the route."
+    },
+    route_dedup_cache_hits_total: Counter {
+        "The total number of outbound HTTP requests that were served a cached response on the route."
+    }
+}
+
+/// Holds the deduplication counters for each route.
+///
+/// Counters are shared with the services of the routes they count, so that
+/// routes that are no longer in use are dropped from the registry once they
+/// have been reported.
+#[derive(Clone, Debug, Default)]
+pub struct RouteDedup {
+    coalesced: Arc<RwLock<Counters>>,
+    cache_hits: Arc<RwLock<Counters>>,
+}
+
+type Counters = HashMap<ProfileRouteLabels, Arc<Counter>>;
+
+#[derive(Clone, Debug)]
+pub struct NewRecordDedup<N> {
+    inner: N,
+    registry: RouteDedup,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordDedup<S> {
+    inner: S,
+    route: Arc<Route>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    route: Arc<Route>,
+}
+
+#[derive(Debug)]
+struct Route {
+    coalesced: Arc<Counter>,
+    cache_hits: Arc<Counter>,
+}
+
+// === impl RouteDedup ===
+
+impl RouteDedup {
+    /// Returns a layer that records deduplicated responses for each route
+    /// target.
+    pub(crate) fn to_layer<N>(
+        &self,
+    ) -> impl svc::layer::Layer<N, Service = NewRecordDedup<N>> + Clone {
+        let registry = self.clone();
+        svc::layer::mk(move |inner| NewRecordDedup {
+            inner,
+            registry: registry.clone(),
+        })
+    }
+
+    fn counter(counters: &RwLock<Counters>, labels: &ProfileRouteLabels) -> Arc<Counter> {
+        if let Some(counter) = counters.read().get(labels) {
+            return counter.clone();
+        }
+        counters.write().entry(labels.clone()).or_default().clone()
+    }
+
+    #[cfg(test)]
+    fn coalesced(&self, labels: &ProfileRouteLabels) -> u64 {
+        self.coalesced
+            .read()
+            .get(labels)
+            .map(|c| u64::from(&**c))
+            .unwrap_or(0)
+    }
+
+    #[cfg(test)]
+    fn cache_hits(&self, labels: &ProfileRouteLabels) -> u64 {
+        self.cache_hits
+            .read()
+            .get(labels)
+            .map(|c| u64::from(&**c))
+            .unwrap_or(0)
+    }
+}
+
+impl FmtMetrics for RouteDedup {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Only routes with deduplicated requests are reported. Routes whose
+        // services have been dropped are reported one last time and then
+        // forgotten.
+        let mut coalesced = self.coalesced.write();
+        let mut routes = coalesced
+            .iter()
+            .filter(|(_, c)| u64::from(&***c) > 0)
+            .peekable();
+        if routes.peek().is_some() {
+            route_dedup_coalesced_total.fmt_help(f)?;
+            route_dedup_coalesced_total.fmt_scopes(f, routes, |c| &**c)?;
+        }
+        coalesced.retain(|_, c| Arc::strong_count(c) > 1);
+        drop(coalesced);
+
+        let mut cache_hits = self.cache_hits.write();
+        let mut routes = cache_hits
+            .iter()
+            .filter(|(_, c)| u64::from(&***c) > 0)
+            .peekable();
+        if routes.peek().is_some() {
+            route_dedup_cache_hits_total.fmt_help(f)?;
+            route_dedup_cache_hits_total.fmt_scopes(f, routes, |c| &**c)?;
+        }
+        cache_hits.retain(|_, c| Arc::strong_count(c) > 1);
+        Ok(())
+    }
+}
+
+// === impl NewRecordDedup ===
+
+impl<T, N> svc::NewService<T> for NewRecordDedup<N>
+where
+    T: svc::Param<ProfileRouteLabels>,
+    N: svc::NewService<T>,
+{
+    type Service = RecordDedup<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let labels: ProfileRouteLabels = target.param();
+        let route = Arc::new(Route {
+            coalesced: RouteDedup::counter(&self.registry.coalesced, &labels),
+            cache_hits: RouteDedup::counter(&self.registry.cache_hits, &labels),
+        });
+        let inner = self.inner.new_service(target);
+        RecordDedup { inner, route }
+    }
+}
+
+// === impl RecordDedup ===
+
+impl<Req, B, S> svc::Service<Req> for RecordDedup<S>
+where
+    S: svc::Service<Req, Response = http::Response<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        ResponseFuture {
+            inner: self.inner.call(req),
+            route: self.route.clone(),
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let rsp = futures::ready!(this.inner.poll(cx))?;
+        if rsp.extensions().get::<http::Coalesced>().is_some() {
+            this.route.coalesced.incr();
+        }
+        if rsp.extensions().get::<http::CacheHit>().is_some() {
+            this.route.cache_hits.incr();
+        }
+        Poll::Ready(Ok(rsp))
+    }
+}
@@ -0,0 +1,106 @@
+use super::*;
+use linkerd_app_core::{
+    profiles,
+    svc::{NewService, ServiceExt},
+    Error, NameAddr,
+};
+
+#[derive(Clone, Debug)]
+struct Target(ProfileRouteLabels);
+
+impl svc::Param<ProfileRouteLabels> for Target {
+    fn param(&self) -> ProfileRouteLabels {
+        self.0.clone()
+    }
+}
+
+fn labels() -> ProfileRouteLabels {
+    ProfileRouteLabels::outbound(
+        profiles::LogicalAddr("foo.ns.svc.cluster.local:8080".parse::<NameAddr>().unwrap()),
+        &profiles::http::Route::default(),
+    )
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn records_coalesced_and_cached_responses() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let labels = labels();
+    let registry = RouteDedup::default();
+
+    // The route marks its responses as the coalescing and caching layers do.
+    let svc = svc::stack(|_: Target| {
+        svc::mk(|req: http::Request<()>| async move {
+            let mut rsp = http::Response::new(());
+            match req.uri().path() {
+                "/coalesced" => {
+                    rsp.extensions_mut().insert(http::Coalesced);
+                }
+                "/cached" => {
+                    rsp.extensions_mut().insert(http::CacheHit);
+                }
+                _ => {}
+            }
+            Ok::<_, Error>(rsp)
+        })
+    })
+    .push(registry.to_layer())
+    .into_inner()
+    .new_service(Target(labels.clone()));
+
+    let req = |path: &str| http::Request::get(path).body(()).unwrap();
+
+    svc.clone().oneshot(req("/dispatched")).await.unwrap();
+    assert_eq!(registry.coalesced(&labels), 0);
+    assert_eq!(registry.cache_hits(&labels), 0);
+    assert!(
+        registry.as_display().to_string().is_empty(),
+        "routes without deduplicated requests must not be reported"
+    );
+
+    for _ in 0..2 {
+        svc.clone().oneshot(req("/coalesced")).await.unwrap();
+    }
+    svc.clone().oneshot(req("/cached")).await.unwrap();
+    assert_eq!(registry.coalesced(&labels), 2);
+    assert_eq!(registry.cache_hits(&labels), 1);
+
+    let metrics = registry.as_display().to_string();
+    let line = |name: &str| {
+        metrics
+            .lines()
+            .find(|l| l.starts_with(&format!("{}{{", name)))
+            .unwrap_or_else(|| panic!("{} must be reported", name))
+            .to_string()
+    };
+    assert!(line("route_dedup_coalesced_total").ends_with(" 2"));
+    assert!(line("route_dedup_cache_hits_total").ends_with(" 1"));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn forgets_dropped_routes() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let registry = RouteDedup::default();
+    let svc = svc::stack(|_: Target| {
+        svc::mk(|_: http::Request<()>| async move {
+            let mut rsp = http::Response::new(());
+            rsp.extensions_mut().insert(http::Coalesced);
+            Ok::<_, Error>(rsp)
+        })
+    })
+    .push(registry.to_layer())
+    .into_inner()
+    .new_service(Target(labels()));
+
+    svc.oneshot(http::Request::new(())).await.unwrap();
+    assert_eq!(registry.coalesced(&labels()), 1);
+
+    // The route is reported after it is dropped, and then it is forgotten.
+    assert!(registry
+        .as_display()
+        .to_string()
+        .contains("route_dedup_coalesced_total"));
+    assert_eq!(registry.coalesced(&labels()), 0);
+    assert!(registry.as_display().to_string().is_empty());
+}
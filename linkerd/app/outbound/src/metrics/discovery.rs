@@ -0,0 +1,175 @@
+//! Counts the results of outbound profile lookups.
+//!
+//! Lookups are only performed when a profile is not already cached, so these
+//! counts reflect discovery cache misses. Lookups that complete without a
+//! profile--i.e. when the destination is unknown to the controller--are counted
+//! separately from those that fail.
+//!
+//! Connections that must wait for capacity in a destination's discovery queue
+//! are also counted, since they indicate that discovery is throttling accepts.
+
+use linkerd_app_core::{
+    metrics::{metrics, Counter, FmtLabels, FmtMetrics},
+    profiles, svc, Error,
+};
+use pin_project::pin_project;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+metrics! {
+    outbound_profile_lookups_total: Counter {
+        "The total number of completed outbound profile lookups, by result."
+    },
+    outbound_discover_backpressure_total: Counter {
+        "The total number of outbound discoveries that waited for capacity in a discovery queue."
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ProfileLookups(Arc<Counters>);
+
+#[derive(Debug, Default)]
+struct Counters {
+    profile: Counter,
+    no_profile: Counter,
+    error: Counter,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DiscoverBackpressure(Arc<Counter>);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum LookupResult {
+    Profile,
+    NoProfile,
+    Error,
+}
+
+/// Records the result of each lookup made by an inner discovery service.
+#[derive(Clone, Debug)]
+pub struct RecordLookups<P> {
+    inner: P,
+    registry: ProfileLookups,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    registry: ProfileLookups,
+}
+
+// === impl ProfileLookups ===
+
+impl ProfileLookups {
+    /// Wraps a discovery service so that the result of each lookup is recorded.
+    pub(crate) fn record<P>(&self, inner: P) -> RecordLookups<P> {
+        RecordLookups {
+            inner,
+            registry: self.clone(),
+        }
+    }
+
+    fn counter(&self, result: LookupResult) -> &Counter {
+        match result {
+            LookupResult::Profile => &self.0.profile,
+            LookupResult::NoProfile => &self.0.no_profile,
+            LookupResult::Error => &self.0.error,
+        }
+    }
+}
+
+impl FmtMetrics for ProfileLookups {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        outbound_profile_lookups_total.fmt_help(f)?;
+        outbound_profile_lookups_total.fmt_scopes(
+            f,
+            [
+                LookupResult::Profile,
+                LookupResult::NoProfile,
+                LookupResult::Error,
+            ]
+            .into_iter()
+            .map(|result| (result, self.counter(result))),
+            |c| c,
+        )
+    }
+}
+
+// === impl DiscoverBackpressure ===
+
+impl DiscoverBackpressure {
+    pub(crate) fn counter(&self) -> Arc<Counter> {
+        self.0.clone()
+    }
+}
+
+impl FmtMetrics for DiscoverBackpressure {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        outbound_discover_backpressure_total.fmt_help(f)?;
+        outbound_discover_backpressure_total.fmt_metric(f, &*self.0)
+    }
+}
+
+// === impl LookupResult ===
+
+impl FmtLabels for LookupResult {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = match self {
+            Self::Profile => "profile",
+            Self::NoProfile => "no_profile",
+            Self::Error => "error",
+        };
+        write!(f, "result=\"{}\"", result)
+    }
+}
+
+// === impl RecordLookups ===
+
+impl<P> svc::Service<profiles::LookupAddr> for RecordLookups<P>
+where
+    P: svc::Service<profiles::LookupAddr, Response = Option<profiles::Receiver>, Error = Error>,
+{
+    type Response = Option<profiles::Receiver>;
+    type Error = Error;
+    type Future = ResponseFuture<P::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, addr: profiles::LookupAddr) -> Self::Future {
+        ResponseFuture {
+            inner: self.inner.call(addr),
+            registry: self.registry.clone(),
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Option<profiles::Receiver>, Error>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = futures::ready!(this.inner.poll(cx));
+        let result = match &res {
+            Ok(Some(_)) => LookupResult::Profile,
+            Ok(None) => LookupResult::NoProfile,
+            Err(_) => LookupResult::Error,
+        };
+        this.registry.counter(result).incr();
+        Poll::Ready(res)
+    }
+}
@@ -2,11 +2,12 @@ mod http;
 mod tcp;
 
 pub(crate) use self::{http::Http, tcp::Tcp};
-use crate::http::IdentityRequired;
+use crate::http::{IdentityRequired, MtlsRequired, QueueFull};
 use linkerd_app_core::{
     errors::{FailFastError, LoadShedError},
     metrics::FmtLabels,
     proxy::http::ResponseTimeoutError,
+    tls,
 };
 use std::fmt;
 
@@ -15,8 +16,10 @@ use std::fmt;
 enum ErrorKind {
     FailFast,
     IdentityRequired,
+    MtlsRequired,
     Io,
     ResponseTimeout,
+    TlsHandshakeTimeout,
     Unexpected,
     LoadShed,
 }
@@ -29,11 +32,15 @@ impl ErrorKind {
             ErrorKind::Io
         } else if err.is::<IdentityRequired>() {
             ErrorKind::IdentityRequired
+        } else if err.is::<MtlsRequired>() {
+            ErrorKind::MtlsRequired
         } else if err.is::<FailFastError>() {
             ErrorKind::FailFast
         } else if err.is::<ResponseTimeoutError>() {
             ErrorKind::ResponseTimeout
-        } else if err.is::<LoadShedError>() {
+        } else if err.is::<tls::client::HandshakeTimeout>() {
+            ErrorKind::TlsHandshakeTimeout
+        } else if err.is::<LoadShedError>() || err.is::<QueueFull>() {
             ErrorKind::LoadShed
         } else if let Some(e) = err.source() {
             Self::mk(e)
@@ -52,8 +59,10 @@ impl FmtLabels for ErrorKind {
                 ErrorKind::LoadShed => "loadshed",
                 ErrorKind::FailFast => "failfast",
                 ErrorKind::IdentityRequired => "identity required",
+                ErrorKind::MtlsRequired => "mtls required",
                 ErrorKind::Io => "i/o",
                 ErrorKind::ResponseTimeout => "response timeout",
+                ErrorKind::TlsHandshakeTimeout => "tls handshake timeout",
                 ErrorKind::Unexpected => "unexpected",
             }
         )
@@ -0,0 +1,76 @@
+//! Counts opaque connections that were closed because their peer stopped
+//! responding to TCP keepalive probes.
+
+use linkerd_app_core::{
+    metrics::{metrics, Counter, FmtMetrics},
+    svc, transport, Error,
+};
+use std::sync::Arc;
+
+#[cfg(test)]
+mod tests;
+
+metrics! {
+    outbound_tcp_keepalive_closes_total: Counter {
+        "The total number of outbound opaque connections closed because the peer stopped responding to TCP keepalive probes."
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct KeepaliveCloses(Arc<Counter>);
+
+// === impl KeepaliveCloses ===
+
+impl KeepaliveCloses {
+    pub(crate) fn to_layer<N>(
+        &self,
+    ) -> impl svc::layer::Layer<N, Service = svc::stack::NewMonitor<Self, N>> + Clone {
+        svc::stack::NewMonitor::layer(self.clone())
+    }
+
+    #[cfg(test)]
+    fn get(&self) -> u64 {
+        u64::from(&*self.0)
+    }
+}
+
+impl<T> svc::stack::MonitorNewService<T> for KeepaliveCloses {
+    type MonitorService = Self;
+
+    #[inline]
+    fn monitor(&self, _: &T) -> Self::MonitorService {
+        self.clone()
+    }
+}
+
+impl<Req> svc::stack::MonitorService<Req> for KeepaliveCloses {
+    type MonitorResponse = Self;
+
+    #[inline]
+    fn monitor_request(&mut self, _: &Req) -> Self::MonitorResponse {
+        self.clone()
+    }
+}
+
+impl svc::stack::MonitorError<Error> for KeepaliveCloses {
+    fn monitor_error(&mut self, e: &Error) {
+        let mut error: Option<&(dyn std::error::Error + 'static)> = Some(&**e);
+        while let Some(e) = error {
+            if let Some(e) = e.downcast_ref::<std::io::Error>() {
+                if transport::is_keepalive_timeout(e) {
+                    tracing::debug!("Peer stopped responding to keepalive probes");
+                    self.0.incr();
+                }
+                return;
+            }
+            error = e.source();
+        }
+    }
+}
+
+impl FmtMetrics for KeepaliveCloses {
+    fn fmt_metrics(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        outbound_tcp_keepalive_closes_total.fmt_help(f)?;
+        outbound_tcp_keepalive_closes_total.fmt_metric(f, &*self.0)
+    }
+}
@@ -0,0 +1,51 @@
+use super::*;
+use linkerd_app_core::svc::{NewService, ServiceExt};
+
+/// The value of `ETIMEDOUT` on Linux.
+#[cfg(target_os = "linux")]
+const ETIMEDOUT: i32 = 110;
+
+/// Builds a connection service that fails with the given error.
+fn forward(
+    closes: &KeepaliveCloses,
+    error: fn() -> std::io::Error,
+) -> impl svc::Service<(), Response = (), Error = Error> {
+    svc::stack(move |_: ()| svc::mk(move |()| futures::future::err::<(), Error>(error().into())))
+        .push(closes.to_layer())
+        .into_inner()
+        .new_service(())
+}
+
+// Keepalive timeouts are only recognized on Linux.
+#[cfg(target_os = "linux")]
+#[tokio::test(flavor = "current_thread")]
+async fn counts_keepalive_timeouts() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let closes = KeepaliveCloses::default();
+    forward(&closes, || std::io::Error::from_raw_os_error(ETIMEDOUT))
+        .oneshot(())
+        .await
+        .expect_err("connection must fail");
+    assert_eq!(closes.get(), 1);
+
+    // Other timeouts, like idle timeouts, are not counted.
+    forward(&closes, || {
+        std::io::Error::new(std::io::ErrorKind::TimedOut, "connection idle timeout")
+    })
+    .oneshot(())
+    .await
+    .expect_err("connection must fail");
+    forward(&closes, || std::io::ErrorKind::ConnectionReset.into())
+        .oneshot(())
+        .await
+        .expect_err("connection must fail");
+    assert_eq!(closes.get(), 1);
+
+    let metrics = closes.as_display().to_string();
+    assert!(
+        metrics.contains("outbound_tcp_keepalive_closes_total 1"),
+        "{}",
+        metrics
+    );
+}
@@ -0,0 +1,286 @@
+//! Records the sizes of the request and response bodies on each outbound HTTP
+//! route, so that routes' payload profiles may be inspected.
+//!
+//! Each body's size is the number of data bytes read from it, and is recorded
+//! once the body is dropped--i.e. after it has been fully streamed or after
+//! its stream was abandoned. The histograms' bucket boundaries are configured
+//! per-deployment.
+
+use crate::http;
+use bytes::Buf;
+use futures::ready;
+use http_body::Body;
+use linkerd_app_core::{
+    metrics::{metrics, Bounds, Bucket, FmtMetrics, Histogram, ProfileRouteLabels},
+    svc,
+};
+use parking_lot::RwLock;
+use pin_project::{pin_project, pinned_drop};
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+#[cfg(test)]
+mod tests;
+
+metrics! {
+    route_request_size_bytes: Histogram<u64> {
+        "The sizes of the request bodies sent on an outbound HTTP route, in bytes."
+    },
+    route_response_size_bytes: Histogram<u64> {
+        "The sizes of the response bodies received on an outbound HTTP route, in bytes."
+    }
+}
+
+/// The bucket boundaries of the route payload size histograms, in bytes.
+#[derive(Copy, Clone, Debug)]
+pub struct PayloadSizeBuckets(&'static Bounds);
+
+#[derive(Clone, Debug, Default)]
+pub struct RoutePayloadSizes {
+    requests: Arc<RwLock<HashMap<ProfileRouteLabels, Arc<Histogram<u64>>>>>,
+    responses: Arc<RwLock<HashMap<ProfileRouteLabels, Arc<Histogram<u64>>>>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewRecordPayloadSizes<N> {
+    inner: N,
+    buckets: Option<PayloadSizeBuckets>,
+    registry: RoutePayloadSizes,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordPayloadSizes<S> {
+    inner: S,
+    histograms: Option<(Arc<Histogram<u64>>, Arc<Histogram<u64>>)>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    histogram: Option<Arc<Histogram<u64>>>,
+}
+
+/// Counts the data bytes read from a body, recording the total in a histogram
+/// when the body is dropped.
+#[pin_project(PinnedDrop)]
+#[derive(Debug)]
+pub struct SizedBody<B> {
+    #[pin]
+    inner: B,
+    bytes: u64,
+    histogram: Option<Arc<Histogram<u64>>>,
+}
+
+// === impl PayloadSizeBuckets ===
+
+impl PayloadSizeBuckets {
+    /// Returns buckets with the given upper bounds, which must be positive and
+    /// strictly increasing. A final, unbounded bucket is always included.
+    ///
+    /// The bounds are allocated for the lifetime of the process, so this
+    /// should only be used when the proxy's configuration is loaded.
+    pub fn new(bounds: impl IntoIterator<Item = u64>) -> Option<Self> {
+        let mut buckets = Vec::new();
+        let mut prior = 0;
+        for bound in bounds {
+            if bound <= prior {
+                return None;
+            }
+            buckets.push(Bucket::Le(bound as f64));
+            prior = bound;
+        }
+        buckets.push(Bucket::Inf);
+        let bounds = Box::leak(Box::new(Bounds(Box::leak(buckets.into_boxed_slice()))));
+        Some(Self(bounds))
+    }
+}
+
+// === impl RoutePayloadSizes ===
+
+impl RoutePayloadSizes {
+    /// Returns a layer that records the payload sizes of each route target.
+    /// When `buckets` is `None`, payload sizes are not recorded.
+    pub(crate) fn to_layer<N>(
+        &self,
+        buckets: Option<PayloadSizeBuckets>,
+    ) -> impl svc::layer::Layer<N, Service = NewRecordPayloadSizes<N>> + Clone {
+        let registry = self.clone();
+        svc::layer::mk(move |inner| NewRecordPayloadSizes {
+            inner,
+            buckets,
+            registry: registry.clone(),
+        })
+    }
+
+    fn get_or_insert(
+        histograms: &RwLock<HashMap<ProfileRouteLabels, Arc<Histogram<u64>>>>,
+        labels: &ProfileRouteLabels,
+        PayloadSizeBuckets(bounds): PayloadSizeBuckets,
+    ) -> Arc<Histogram<u64>> {
+        if let Some(histogram) = histograms.read().get(labels) {
+            return histogram.clone();
+        }
+        histograms
+            .write()
+            .entry(labels.clone())
+            .or_insert_with(|| Arc::new(Histogram::new(bounds)))
+            .clone()
+    }
+}
+
+impl FmtMetrics for RoutePayloadSizes {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Routes whose services and bodies have been dropped are reported one
+        // last time and then forgotten.
+        let mut requests = self.requests.write();
+        if !requests.is_empty() {
+            route_request_size_bytes.fmt_help(f)?;
+            route_request_size_bytes.fmt_scopes(f, requests.iter(), |h| &**h)?;
+            requests.retain(|_, h| Arc::strong_count(h) > 1);
+        }
+        drop(requests);
+
+        let mut responses = self.responses.write();
+        if !responses.is_empty() {
+            route_response_size_bytes.fmt_help(f)?;
+            route_response_size_bytes.fmt_scopes(f, responses.iter(), |h| &**h)?;
+            responses.retain(|_, h| Arc::strong_count(h) > 1);
+        }
+        Ok(())
+    }
+}
+
+// === impl NewRecordPayloadSizes ===
+
+impl<T, N> svc::NewService<T> for NewRecordPayloadSizes<N>
+where
+    T: svc::Param<ProfileRouteLabels>,
+    N: svc::NewService<T>,
+{
+    type Service = RecordPayloadSizes<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let histograms = self.buckets.map(|buckets| {
+            let labels = target.param();
+            let requests =
+                RoutePayloadSizes::get_or_insert(&self.registry.requests, &labels, buckets);
+            let responses =
+                RoutePayloadSizes::get_or_insert(&self.registry.responses, &labels, buckets);
+            (requests, responses)
+        });
+        let inner = self.inner.new_service(target);
+        RecordPayloadSizes { inner, histograms }
+    }
+}
+
+// === impl RecordPayloadSizes ===
+
+impl<RspB, S> svc::Service<http::Request<http::BoxBody>> for RecordPayloadSizes<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<RspB>>,
+{
+    type Response = http::Response<SizedBody<RspB>>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let (req, histogram) = match self.histograms.clone() {
+            Some((requests, responses)) => {
+                let req =
+                    req.map(|inner| http::BoxBody::new(SizedBody::new(inner, Some(requests))));
+                (req, Some(responses))
+            }
+            None => (req, None),
+        };
+        ResponseFuture {
+            inner: self.inner.call(req),
+            histogram,
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<B, E, F> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+{
+    type Output = Result<http::Response<SizedBody<B>>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let rsp = ready!(this.inner.poll(cx))?;
+        let histogram = this.histogram.take();
+        Poll::Ready(Ok(rsp.map(|inner| SizedBody::new(inner, histogram))))
+    }
+}
+
+// === impl SizedBody ===
+
+impl<B> SizedBody<B> {
+    fn new(inner: B, histogram: Option<Arc<Histogram<u64>>>) -> Self {
+        Self {
+            inner,
+            bytes: 0,
+            histogram,
+        }
+    }
+}
+
+impl<B: Body> Body for SizedBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        let frame = ready!(this.inner.poll_data(cx));
+        if let Some(Ok(data)) = frame.as_ref() {
+            *this.bytes = this.bytes.saturating_add(data.remaining() as u64);
+        }
+        Poll::Ready(frame)
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::header::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[pinned_drop]
+impl<B> PinnedDrop for SizedBody<B> {
+    fn drop(self: Pin<&mut Self>) {
+        let this = self.project();
+        if let Some(histogram) = this.histogram.take() {
+            histogram.add(*this.bytes);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use super::*;
+use linkerd_app_core::{
+    profiles,
+    svc::{NewService, ServiceExt},
+    Error, NameAddr,
+};
+
+#[derive(Clone, Debug)]
+struct Target(ProfileRouteLabels);
+
+impl svc::Param<ProfileRouteLabels> for Target {
+    fn param(&self) -> ProfileRouteLabels {
+        self.0.clone()
+    }
+}
+
+fn labels() -> ProfileRouteLabels {
+    ProfileRouteLabels::outbound(
+        profiles::LogicalAddr("foo.ns.svc.cluster.local:8080".parse::<NameAddr>().unwrap()),
+        &profiles::http::Route::default(),
+    )
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn records_sizes_in_configured_buckets() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let labels = labels();
+    let registry = RoutePayloadSizes::default();
+    let buckets = PayloadSizeBuckets::new([10, 100]).expect("bounds must be valid");
+
+    // The route reads each request's body and responds with a body of the
+    // requested size.
+    let svc = svc::stack(|_: Target| {
+        svc::mk(|req: http::Request<http::BoxBody>| async move {
+            let len = req.uri().path().trim_start_matches('/').parse().unwrap();
+            hyper::body::to_bytes(req.into_body()).await?;
+            let body = http::BoxBody::new(hyper::Body::from(vec![b'a'; len]));
+            Ok::<_, Error>(http::Response::new(body))
+        })
+    })
+    .push(registry.to_layer(Some(buckets)))
+    .into_inner()
+    .new_service(Target(labels));
+
+    for (req_len, rsp_len) in [(5, 50), (500, 0)] {
+        let req = http::Request::get(format!("/{}", rsp_len))
+            .body(http::BoxBody::new(hyper::Body::from(vec![b'a'; req_len])))
+            .unwrap();
+        let rsp = svc.clone().oneshot(req).await.unwrap();
+        hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+    }
+
+    let metrics = registry.as_display().to_string();
+    let value = |name: &str, le: Option<&str>| {
+        metrics
+            .lines()
+            .find(|l| {
+                l.starts_with(&format!("{}{{", name))
+                    && le.map_or(true, |le| l.contains(&format!("le=\"{}\"", le)))
+            })
+            .unwrap_or_else(|| panic!("{} le={:?} must be reported", name, le))
+            .rsplit(' ')
+            .next()
+            .unwrap()
+            .parse::<u64>()
+            .unwrap()
+    };
+
+    // Buckets are cumulative.
+    assert_eq!(value("route_request_size_bytes_bucket", Some("10")), 1);
+    assert_eq!(value("route_request_size_bytes_bucket", Some("100")), 1);
+    assert_eq!(value("route_request_size_bytes_bucket", Some("+Inf")), 2);
+    assert_eq!(value("route_request_size_bytes_sum", None), 505);
+
+    assert_eq!(value("route_response_size_bytes_bucket", Some("10")), 1);
+    assert_eq!(value("route_response_size_bytes_bucket", Some("100")), 2);
+    assert_eq!(value("route_response_size_bytes_bucket", Some("+Inf")), 2);
+    assert_eq!(value("route_response_size_bytes_sum", None), 50);
+
+    assert!(
+        !metrics.contains("le=\"1000\""),
+        "only the configured boundaries may be reported"
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn forgets_dropped_routes() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let registry = RoutePayloadSizes::default();
+    let buckets = PayloadSizeBuckets::new([10]).expect("bounds must be valid");
+    let svc = svc::stack(|_: Target| {
+        svc::mk(|_: http::Request<http::BoxBody>| async move {
+            Ok::<_, Error>(http::Response::new(http::BoxBody::default()))
+        })
+    })
+    .push(registry.to_layer(Some(buckets)))
+    .into_inner()
+    .new_service(Target(labels()));
+
+    let rsp = svc
+        .oneshot(http::Request::new(http::BoxBody::default()))
+        .await
+        .unwrap();
+
+    // The response body still records into the route's histogram, so the
+    // route must not be forgotten.
+    assert!(registry
+        .as_display()
+        .to_string()
+        .contains("route_response_size_bytes"));
+    drop(rsp);
+
+    // The route is reported after its last body is dropped, and then it is
+    // forgotten.
+    assert!(registry
+        .as_display()
+        .to_string()
+        .contains("route_response_size_bytes"));
+    assert!(registry.as_display().to_string().is_empty());
+}
+
+#[test]
+fn rejects_unordered_buckets() {
+    assert!(PayloadSizeBuckets::new([0, 10]).is_none());
+    assert!(PayloadSizeBuckets::new([10, 10]).is_none());
+    assert!(PayloadSizeBuckets::new([100, 10]).is_none());
+}
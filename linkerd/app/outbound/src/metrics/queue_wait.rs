@@ -0,0 +1,143 @@
+//! Records the time outbound HTTP requests wait in each backend's request
+//! queue.
+//!
+//! Requests are queued while a backend's balancer (or forwarding endpoint) is
+//! not ready--e.g. while all of its endpoints are at capacity. Each request is
+//! stamped as it enters the queue and its wait is recorded as it's dispatched
+//! from the queue, so the wait excludes the time spent processing the request
+//! after it's dispatched.
+
+use crate::http;
+use linkerd_app_core::{
+    metrics::{latency, metrics, Bounds, Bucket, FmtMetrics, Histogram, MicrosAsSeconds},
+    svc,
+};
+use std::{
+    fmt,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::time;
+
+metrics! {
+    request_queue_wait_seconds: Histogram<latency::Us, MicrosAsSeconds> {
+        "The time outbound requests wait in a backend's queue before they are dispatched."
+    }
+}
+
+const BOUNDS: &Bounds = &Bounds(&[
+    Bucket::Le(0.000_1),
+    Bucket::Le(0.000_5),
+    Bucket::Le(0.001),
+    Bucket::Le(0.005),
+    Bucket::Le(0.01),
+    Bucket::Le(0.05),
+    Bucket::Le(0.1),
+    Bucket::Le(0.5),
+    Bucket::Le(1.0),
+    Bucket::Le(5.0),
+    Bucket::Le(10.0),
+    Bucket::Inf,
+]);
+
+#[derive(Clone, Debug)]
+pub struct RequestQueueWait(Arc<Histogram<latency::Us, MicrosAsSeconds>>);
+
+/// Marks the time at which each request enters the queue.
+#[derive(Clone, Debug)]
+pub struct MarkEnqueued<S> {
+    inner: S,
+}
+
+/// Records the time each request waited since it entered the queue.
+#[derive(Clone, Debug)]
+pub struct RecordDispatch<S> {
+    inner: S,
+    histogram: RequestQueueWait,
+}
+
+/// A request extension holding the time at which the request was enqueued.
+#[derive(Copy, Clone, Debug)]
+struct Enqueued(time::Instant);
+
+// === impl RequestQueueWait ===
+
+impl RequestQueueWait {
+    /// Returns a layer to be applied to the queue's services, marking when
+    /// each request enters the queue.
+    pub(crate) fn to_enqueue_layer<S>(
+        &self,
+    ) -> impl svc::layer::Layer<S, Service = MarkEnqueued<S>> + Clone {
+        svc::layer::mk(|inner| MarkEnqueued { inner })
+    }
+
+    /// Returns a layer to be applied to the services behind the queue,
+    /// recording each request's wait as it's dispatched.
+    pub(crate) fn to_dispatch_layer<S>(
+        &self,
+    ) -> impl svc::layer::Layer<S, Service = RecordDispatch<S>> + Clone {
+        let histogram = self.clone();
+        svc::layer::mk(move |inner| RecordDispatch {
+            inner,
+            histogram: histogram.clone(),
+        })
+    }
+}
+
+impl Default for RequestQueueWait {
+    fn default() -> Self {
+        Self(Arc::new(Histogram::new(BOUNDS)))
+    }
+}
+
+impl FmtMetrics for RequestQueueWait {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        request_queue_wait_seconds.fmt_help(f)?;
+        request_queue_wait_seconds.fmt_metric(f, &*self.0)
+    }
+}
+
+// === impl MarkEnqueued ===
+
+impl<B, S> svc::Service<http::Request<B>> for MarkEnqueued<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        req.extensions_mut().insert(Enqueued(time::Instant::now()));
+        self.inner.call(req)
+    }
+}
+
+// === impl RecordDispatch ===
+
+impl<B, S> svc::Service<http::Request<B>> for RecordDispatch<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        if let Some(Enqueued(enqueued)) = req.extensions_mut().remove::<Enqueued>() {
+            let wait = time::Instant::now().saturating_duration_since(enqueued);
+            self.histogram.0.add(wait);
+        }
+        self.inner.call(req)
+    }
+}
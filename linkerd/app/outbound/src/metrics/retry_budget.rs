@@ -0,0 +1,129 @@
+//! Reports the utilization of each outbound HTTP route's retry budget: the
+//! proportion of the budget's allowance that retries have withdrawn over its
+//! window.
+
+use linkerd_app_core::{
+    metrics::{metrics, FmtLabels, FmtMetric, FmtMetrics, ProfileRouteLabels},
+    profiles::http::Route,
+    svc,
+};
+use linkerd_retry::Budget;
+use parking_lot::RwLock;
+use std::{collections::HashMap, fmt, sync::Arc};
+
+#[cfg(test)]
+mod tests;
+
+metrics! {
+    route_retry_budget_utilization: Utilization {
+        "The proportion, between 0 and 1, of an outbound HTTP route's retry budget that has been used over the budget's window."
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RouteRetryBudgets(Arc<RwLock<HashMap<ProfileRouteLabels, Utilization>>>);
+
+/// Reports the utilization of a route's retry budget.
+#[derive(Clone, Debug)]
+pub struct Utilization(Arc<Budget>);
+
+#[derive(Clone, Debug)]
+pub struct NewRecordRetryBudget<N> {
+    inner: N,
+    registry: RouteRetryBudgets,
+}
+
+// === impl RouteRetryBudgets ===
+
+impl RouteRetryBudgets {
+    /// Returns a layer that registers the retry budget of each route target.
+    pub(crate) fn to_layer<N>(
+        &self,
+    ) -> impl svc::layer::Layer<N, Service = NewRecordRetryBudget<N>> + Clone {
+        let registry = self.clone();
+        svc::layer::mk(move |inner| NewRecordRetryBudget {
+            inner,
+            registry: registry.clone(),
+        })
+    }
+
+    /// Registers the route's current budget, replacing any prior budget, or
+    /// stops reporting a route that no longer has a retry budget.
+    fn register(&self, labels: ProfileRouteLabels, budget: Option<Arc<Budget>>) {
+        let mut budgets = self.0.write();
+        match budget {
+            Some(budget) => {
+                budgets.insert(labels, Utilization(budget));
+            }
+            None => {
+                budgets.remove(&labels);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn utilization(&self, labels: &ProfileRouteLabels) -> Option<f64> {
+        self.0
+            .read()
+            .get(labels)
+            .map(|Utilization(b)| b.utilization())
+    }
+}
+
+impl FmtMetrics for RouteRetryBudgets {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut metrics = self.0.write();
+        if metrics.is_empty() {
+            return Ok(());
+        }
+        route_retry_budget_utilization.fmt_help(f)?;
+        route_retry_budget_utilization.fmt_scopes(f, metrics.iter(), |u| u)?;
+
+        // Budgets that are no longer held by any route are reported one last
+        // time and then forgotten.
+        metrics.retain(|_, Utilization(b)| Arc::strong_count(b) > 1);
+        Ok(())
+    }
+}
+
+// === impl Utilization ===
+
+impl FmtMetric for Utilization {
+    const KIND: &'static str = "gauge";
+
+    fn fmt_metric<N: fmt::Display>(&self, f: &mut fmt::Formatter<'_>, name: N) -> fmt::Result {
+        writeln!(f, "{} {}", name, self.0.utilization())
+    }
+
+    fn fmt_metric_labeled<N, L>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: N,
+        labels: L,
+    ) -> fmt::Result
+    where
+        N: fmt::Display,
+        L: FmtLabels,
+    {
+        write!(f, "{}{{", name)?;
+        labels.fmt_labels(f)?;
+        writeln!(f, "}} {}", self.0.utilization())
+    }
+}
+
+// === impl NewRecordRetryBudget ===
+
+impl<T, N> svc::NewService<T> for NewRecordRetryBudget<N>
+where
+    T: svc::Param<ProfileRouteLabels> + svc::Param<Route>,
+    N: svc::NewService<T>,
+{
+    type Service = N::Service;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let route: Route = target.param();
+        let budget = route.retries().map(|r| r.budget().clone());
+        self.registry.register(target.param(), budget);
+        self.inner.new_service(target)
+    }
+}
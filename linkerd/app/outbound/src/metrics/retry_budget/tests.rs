@@ -0,0 +1,119 @@
+use super::*;
+use linkerd_app_core::{profiles, svc::NewService, NameAddr};
+use std::time::Duration;
+use tokio::time;
+
+#[derive(Clone, Debug)]
+struct Target(Route);
+
+impl svc::Param<ProfileRouteLabels> for Target {
+    fn param(&self) -> ProfileRouteLabels {
+        labels()
+    }
+}
+
+impl svc::Param<Route> for Target {
+    fn param(&self) -> Route {
+        self.0.clone()
+    }
+}
+
+fn labels() -> ProfileRouteLabels {
+    ProfileRouteLabels::outbound(
+        profiles::LogicalAddr("foo.ns.svc.cluster.local:8080".parse::<NameAddr>().unwrap()),
+        &Route::default(),
+    )
+}
+
+fn register(registry: &RouteRetryBudgets, route: Route) {
+    svc::stack(|_: Target| ())
+        .push(registry.to_layer())
+        .into_inner()
+        .new_service(Target(route));
+}
+
+fn reported(registry: &RouteRetryBudgets) -> Option<String> {
+    registry
+        .as_display()
+        .to_string()
+        .lines()
+        .find(|l| l.starts_with("route_retry_budget_utilization{"))
+        .map(Into::into)
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn reports_budget_utilization() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let budget = Arc::new(Budget::new(Duration::from_secs(10), 1, 0.5));
+    let mut route = Route::default();
+    route.set_retries(budget.clone());
+
+    let registry = RouteRetryBudgets::default();
+    register(&registry, route);
+    assert_eq!(registry.utilization(&labels()), Some(0.0));
+
+    // The budget allows 10 reserved retries plus half of the 4 requests.
+    for _ in 0..4 {
+        budget.deposit();
+    }
+    for _ in 0..3 {
+        budget.withdraw().unwrap();
+    }
+    assert_eq!(registry.utilization(&labels()), Some(0.25));
+    for _ in 0..3 {
+        budget.withdraw().unwrap();
+    }
+    assert_eq!(registry.utilization(&labels()), Some(0.5));
+    let line = reported(&registry).expect("utilization must be reported");
+    assert!(line.ends_with(" 0.5"), "{}", line);
+
+    // Utilization resets as retries expire from the budget's window.
+    time::sleep(Duration::from_secs(10)).await;
+    assert_eq!(registry.utilization(&labels()), Some(0.0));
+    let line = reported(&registry).expect("utilization must be reported");
+    assert!(line.ends_with(" 0"), "{}", line);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn routes_without_budgets_are_not_reported() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let registry = RouteRetryBudgets::default();
+    register(&registry, Route::default());
+    assert_eq!(registry.utilization(&labels()), None);
+    assert!(registry.as_display().to_string().is_empty());
+
+    // A route whose budget is removed is no longer reported.
+    let mut route = Route::default();
+    route.set_retries(Arc::new(Budget::new(Duration::from_secs(10), 1, 0.5)));
+    register(&registry, route);
+    assert!(reported(&registry).is_some());
+    register(&registry, Route::default());
+    assert!(reported(&registry).is_none());
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn forgets_dropped_budgets() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let budget = Arc::new(Budget::new(Duration::from_secs(10), 1, 0.5));
+    let mut route = Route::default();
+    route.set_retries(budget.clone());
+
+    let registry = RouteRetryBudgets::default();
+    register(&registry, route.clone());
+    assert!(reported(&registry).is_some());
+    assert!(
+        reported(&registry).is_some(),
+        "held budgets must not be forgotten"
+    );
+
+    // The budget is reported after its last route is dropped, and then it is
+    // forgotten.
+    drop((route, budget));
+    assert!(reported(&registry).is_some());
+    assert_eq!(registry.utilization(&labels()), None);
+    assert!(registry.as_display().to_string().is_empty());
+}
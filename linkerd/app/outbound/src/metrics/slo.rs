@@ -0,0 +1,193 @@
+//! Counts outbound HTTP requests that exceed a route's latency SLO.
+
+use linkerd_app_core::{
+    metrics::{metrics, Counter, FmtMetrics, ProfileRouteLabels},
+    svc,
+};
+use parking_lot::RwLock;
+use pin_project::pin_project;
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time;
+
+#[cfg(test)]
+mod tests;
+
+metrics! {
+    outbound_http_route_slo_breaches_total: Counter {
+        "The total number of outbound HTTP requests whose latency exceeded the route's latency SLO."
+    }
+}
+
+/// Holds a breach counter for each route.
+///
+/// Counters are shared with the services of the routes they count, so that
+/// routes that are no longer in use (e.g. because their profile changed) are
+/// dropped from the registry once they have been reported.
+#[derive(Clone, Debug, Default)]
+pub struct RouteSlo(Arc<RwLock<HashMap<ProfileRouteLabels, Arc<Counter>>>>);
+
+/// A route's latency SLO: requests that take longer than this to complete
+/// are recorded as breaches.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct LatencySlo(pub(crate) Duration);
+
+#[derive(Clone, Debug)]
+pub struct NewRecordSlo<N> {
+    inner: N,
+    log_breaches: bool,
+    registry: RouteSlo,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordSlo<S> {
+    inner: S,
+    route: Option<Arc<Route>>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    start: Option<(time::Instant, Arc<Route>)>,
+}
+
+#[derive(Debug)]
+struct Route {
+    threshold: Duration,
+    log_breaches: bool,
+    labels: ProfileRouteLabels,
+    breaches: Arc<Counter>,
+}
+
+// === impl RouteSlo ===
+
+impl RouteSlo {
+    /// Returns a layer that records SLO breaches for each route target.
+    ///
+    /// Requests on routes without a latency SLO are not measured.
+    pub(crate) fn to_layer<N>(
+        &self,
+        log_breaches: bool,
+    ) -> impl svc::layer::Layer<N, Service = NewRecordSlo<N>> + Clone {
+        let registry = self.clone();
+        svc::layer::mk(move |inner| NewRecordSlo {
+            inner,
+            log_breaches,
+            registry: registry.clone(),
+        })
+    }
+
+    fn counter(&self, labels: &ProfileRouteLabels) -> Arc<Counter> {
+        if let Some(counter) = self.0.read().get(labels) {
+            return counter.clone();
+        }
+        self.0.write().entry(labels.clone()).or_default().clone()
+    }
+
+    #[cfg(test)]
+    fn breaches(&self, labels: &ProfileRouteLabels) -> u64 {
+        self.0
+            .read()
+            .get(labels)
+            .map(|c| u64::from(&**c))
+            .unwrap_or(0)
+    }
+}
+
+impl FmtMetrics for RouteSlo {
+    fn fmt_metrics(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut metrics = self.0.write();
+        if metrics.is_empty() {
+            return Ok(());
+        }
+        outbound_http_route_slo_breaches_total.fmt_help(f)?;
+        outbound_http_route_slo_breaches_total.fmt_scopes(f, metrics.iter(), |c| &**c)?;
+
+        // Routes whose services have been dropped are reported one last time
+        // and then forgotten.
+        metrics.retain(|_, c| Arc::strong_count(c) > 1);
+        Ok(())
+    }
+}
+
+// === impl NewRecordSlo ===
+
+impl<T, N> svc::NewService<T> for NewRecordSlo<N>
+where
+    T: svc::Param<ProfileRouteLabels> + svc::Param<Option<LatencySlo>>,
+    N: svc::NewService<T>,
+{
+    type Service = RecordSlo<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let slo: Option<LatencySlo> = target.param();
+        let route = slo.map(|LatencySlo(threshold)| {
+            let labels: ProfileRouteLabels = target.param();
+            Arc::new(Route {
+                threshold,
+                log_breaches: self.log_breaches,
+                breaches: self.registry.counter(&labels),
+                labels,
+            })
+        });
+        let inner = self.inner.new_service(target);
+        RecordSlo { inner, route }
+    }
+}
+
+// === impl RecordSlo ===
+
+impl<Req, S> svc::Service<Req> for RecordSlo<S>
+where
+    S: svc::Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        ResponseFuture {
+            start: self.route.clone().map(|r| (time::Instant::now(), r)),
+            inner: self.inner.call(req),
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F: Future> Future for ResponseFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let out = futures::ready!(this.inner.poll(cx));
+        if let Some((start, route)) = this.start.take() {
+            let elapsed = time::Instant::now().saturating_duration_since(start);
+            if elapsed > route.threshold {
+                route.breaches.incr();
+                if route.log_breaches {
+                    tracing::info!(
+                        labels = ?route.labels,
+                        ?elapsed,
+                        threshold = ?route.threshold,
+                        "Request exceeded its route's latency SLO"
+                    );
+                }
+            }
+        }
+        Poll::Ready(out)
+    }
+}
@@ -0,0 +1,120 @@
+use super::*;
+use linkerd_app_core::{
+    profiles,
+    svc::{NewService, ServiceExt},
+    Error, NameAddr,
+};
+
+#[derive(Clone, Debug)]
+struct Target(ProfileRouteLabels, Option<LatencySlo>);
+
+impl svc::Param<ProfileRouteLabels> for Target {
+    fn param(&self) -> ProfileRouteLabels {
+        self.0.clone()
+    }
+}
+
+impl svc::Param<Option<LatencySlo>> for Target {
+    fn param(&self) -> Option<LatencySlo> {
+        self.1
+    }
+}
+
+fn labels() -> ProfileRouteLabels {
+    ProfileRouteLabels::outbound(
+        profiles::LogicalAddr("foo.ns.svc.cluster.local:8080".parse::<NameAddr>().unwrap()),
+        &profiles::http::Route::default(),
+    )
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn records_slow_requests() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let labels = labels();
+    let registry = RouteSlo::default();
+    let slo = LatencySlo(Duration::from_millis(100));
+
+    let svc = svc::stack(|_: Target| {
+        svc::mk(|req: http::Request<()>| async move {
+            if req.uri().path() == "/slow" {
+                time::sleep(Duration::from_millis(200)).await;
+            }
+            Ok::<_, Error>(http::Response::new(()))
+        })
+    })
+    .push(registry.to_layer(true))
+    .into_inner()
+    .new_service(Target(labels.clone(), Some(slo)));
+
+    let req = |path: &str| http::Request::get(path).body(()).unwrap();
+
+    svc.clone().oneshot(req("/fast")).await.unwrap();
+    assert_eq!(
+        registry.breaches(&labels),
+        0,
+        "fast requests must not breach"
+    );
+
+    svc.clone().oneshot(req("/slow")).await.unwrap();
+    assert_eq!(registry.breaches(&labels), 1, "slow requests must breach");
+
+    svc.oneshot(req("/fast")).await.unwrap();
+    assert_eq!(registry.breaches(&labels), 1);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn disabled_without_slo() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let labels = labels();
+    let registry = RouteSlo::default();
+
+    let svc = svc::stack(|_: Target| {
+        svc::mk(|_: http::Request<()>| async move {
+            time::sleep(Duration::from_secs(10)).await;
+            Ok::<_, Error>(http::Response::new(()))
+        })
+    })
+    .push(registry.to_layer(true))
+    .into_inner()
+    .new_service(Target(labels.clone(), None));
+
+    svc.oneshot(http::Request::new(())).await.unwrap();
+    assert_eq!(registry.breaches(&labels), 0);
+    assert!(registry.as_display().to_string().is_empty());
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn forgets_dropped_routes() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let registry = RouteSlo::default();
+    let svc = svc::stack(|_: Target| {
+        svc::mk(|_: http::Request<()>| async move {
+            time::sleep(Duration::from_secs(1)).await;
+            Ok::<_, Error>(http::Response::new(()))
+        })
+    })
+    .push(registry.to_layer(false))
+    .into_inner()
+    .new_service(Target(
+        labels(),
+        Some(LatencySlo(Duration::from_millis(100))),
+    ));
+
+    svc.oneshot(http::Request::new(())).await.unwrap();
+    assert_eq!(registry.breaches(&labels()), 1);
+
+    // The breach is reported after the route is dropped, and then the route is
+    // forgotten.
+    assert!(registry
+        .as_display()
+        .to_string()
+        .contains("outbound_http_route_slo_breaches_total"));
+    assert_eq!(registry.breaches(&labels()), 0);
+    assert!(registry.as_display().to_string().is_empty());
+}
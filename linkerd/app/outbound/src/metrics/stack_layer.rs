@@ -0,0 +1,338 @@
+//! Records the time outbound HTTP requests spend in each major layer of the
+//! stack.
+//!
+//! Requests are timed at the top of the logical (routing), concrete
+//! (balancing), and endpoint (upstream) stacks. Each layer records the time
+//! between receiving a request and its response, excluding the time spent in
+//! inner instrumented layers, so that a request's latency is attributed to the
+//! layer in which it was spent. Profile discovery and connection establishment
+//! are recorded separately, for each lookup and connection, as the `discovery`
+//! and `connect` layers.
+
+use crate::http;
+use linkerd_app_core::{
+    metrics::{
+        latency, metrics, Bounds, Bucket, FmtLabels, FmtMetrics, Histogram, MicrosAsSeconds,
+    },
+    svc,
+};
+use pin_project::pin_project;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time;
+
+#[cfg(test)]
+mod tests;
+
+metrics! {
+    stack_layer_duration_seconds: Histogram<latency::Us, MicrosAsSeconds> {
+        "The time outbound requests spend in each layer of the proxy's stack."
+    }
+}
+
+const BOUNDS: &Bounds = &Bounds(&[
+    Bucket::Le(0.000_1),
+    Bucket::Le(0.000_5),
+    Bucket::Le(0.001),
+    Bucket::Le(0.005),
+    Bucket::Le(0.01),
+    Bucket::Le(0.05),
+    Bucket::Le(0.1),
+    Bucket::Le(0.5),
+    Bucket::Le(1.0),
+    Bucket::Le(5.0),
+    Bucket::Le(10.0),
+    Bucket::Inf,
+]);
+
+/// A layer of the outbound stack whose latency is recorded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StackLayer {
+    Discovery,
+    Logical,
+    Balance,
+    Connect,
+    Upstream,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct StackLayers(Arc<Histograms>);
+
+#[derive(Debug)]
+struct Histograms {
+    discovery: Histogram<latency::Us, MicrosAsSeconds>,
+    logical: Histogram<latency::Us, MicrosAsSeconds>,
+    balance: Histogram<latency::Us, MicrosAsSeconds>,
+    connect: Histogram<latency::Us, MicrosAsSeconds>,
+    upstream: Histogram<latency::Us, MicrosAsSeconds>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewRecordLayer<N> {
+    inner: N,
+    layer: StackLayer,
+    registry: StackLayers,
+}
+
+/// Records the time each request spends in a layer, excluding the time spent
+/// in inner instrumented layers.
+#[derive(Clone, Debug)]
+pub struct RecordLayer<S> {
+    inner: S,
+    layer: StackLayer,
+    registry: StackLayers,
+}
+
+/// Records the time taken by each call, e.g. to establish a connection.
+#[derive(Clone, Debug)]
+pub struct RecordCall<S> {
+    inner: S,
+    layer: StackLayer,
+    registry: StackLayers,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    layer: StackLayer,
+    registry: StackLayers,
+    start: time::Instant,
+    timing: Option<Timing>,
+}
+
+/// Tracks the time spent by inner layers, so that it is not attributed to
+/// outer layers.
+#[derive(Debug)]
+struct Timing {
+    /// The time spent in this layer's inner instrumented layers.
+    inner: Arc<InnerMicros>,
+
+    /// The outer layer's accumulator, if this request was already instrumented.
+    outer: Option<Arc<InnerMicros>>,
+}
+
+/// A request extension through which each instrumented layer reports its
+/// duration to the outer instrumented layer.
+#[derive(Debug, Default)]
+struct InnerMicros(AtomicU64);
+
+// === impl StackLayers ===
+
+impl StackLayers {
+    /// Returns a layer that records the time each request spends in `layer`.
+    pub(crate) fn to_layer<N>(
+        &self,
+        layer: StackLayer,
+    ) -> impl svc::layer::Layer<N, Service = NewRecordLayer<N>> + Clone {
+        let registry = self.clone();
+        svc::layer::mk(move |inner| NewRecordLayer {
+            inner,
+            layer,
+            registry: registry.clone(),
+        })
+    }
+
+    /// Returns a layer that records the time taken by each connection attempt.
+    pub(crate) fn to_connect_layer<S>(
+        &self,
+    ) -> impl svc::layer::Layer<S, Service = RecordCall<S>> + Clone {
+        let registry = self.clone();
+        svc::layer::mk(move |inner| RecordCall {
+            inner,
+            layer: StackLayer::Connect,
+            registry: registry.clone(),
+        })
+    }
+
+    /// Records the time taken by each of `inner`'s profile lookups.
+    pub(crate) fn record_discovery<S>(&self, inner: S) -> RecordCall<S> {
+        RecordCall {
+            inner,
+            layer: StackLayer::Discovery,
+            registry: self.clone(),
+        }
+    }
+
+    fn histogram(&self, layer: StackLayer) -> &Histogram<latency::Us, MicrosAsSeconds> {
+        match layer {
+            StackLayer::Discovery => &self.0.discovery,
+            StackLayer::Logical => &self.0.logical,
+            StackLayer::Balance => &self.0.balance,
+            StackLayer::Connect => &self.0.connect,
+            StackLayer::Upstream => &self.0.upstream,
+        }
+    }
+
+    fn record(&self, layer: StackLayer, duration: Duration) {
+        self.histogram(layer).add(duration);
+    }
+}
+
+impl Default for Histograms {
+    fn default() -> Self {
+        Self {
+            discovery: Histogram::new(BOUNDS),
+            logical: Histogram::new(BOUNDS),
+            balance: Histogram::new(BOUNDS),
+            connect: Histogram::new(BOUNDS),
+            upstream: Histogram::new(BOUNDS),
+        }
+    }
+}
+
+impl FmtMetrics for StackLayers {
+    fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        stack_layer_duration_seconds.fmt_help(f)?;
+        stack_layer_duration_seconds.fmt_scopes(
+            f,
+            [
+                StackLayer::Discovery,
+                StackLayer::Logical,
+                StackLayer::Balance,
+                StackLayer::Connect,
+                StackLayer::Upstream,
+            ]
+            .into_iter()
+            .map(|layer| (layer, self.histogram(layer))),
+            |h| h,
+        )
+    }
+}
+
+// === impl StackLayer ===
+
+impl StackLayer {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Discovery => "discovery",
+            Self::Logical => "logical",
+            Self::Balance => "balance",
+            Self::Connect => "connect",
+            Self::Upstream => "upstream",
+        }
+    }
+}
+
+impl FmtLabels for StackLayer {
+    fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "layer=\"{}\"", self.as_str())
+    }
+}
+
+// === impl NewRecordLayer ===
+
+impl<T, N> svc::NewService<T> for NewRecordLayer<N>
+where
+    N: svc::NewService<T>,
+{
+    type Service = RecordLayer<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        RecordLayer {
+            inner: self.inner.new_service(target),
+            layer: self.layer,
+            registry: self.registry.clone(),
+        }
+    }
+}
+
+// === impl RecordLayer ===
+
+impl<B, S> svc::Service<http::Request<B>> for RecordLayer<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        let inner = Arc::new(InnerMicros::default());
+        let outer = req.extensions_mut().insert(inner.clone());
+        ResponseFuture {
+            start: time::Instant::now(),
+            inner: self.inner.call(req),
+            layer: self.layer,
+            registry: self.registry.clone(),
+            timing: Some(Timing { inner, outer }),
+        }
+    }
+}
+
+// === impl RecordCall ===
+
+impl<T, S> svc::Service<T> for RecordCall<S>
+where
+    S: svc::Service<T>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        ResponseFuture {
+            start: time::Instant::now(),
+            inner: self.inner.call(target),
+            layer: self.layer,
+            registry: self.registry.clone(),
+            timing: None,
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F: Future> Future for ResponseFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let out = futures::ready!(this.inner.poll(cx));
+        let elapsed = time::Instant::now().saturating_duration_since(*this.start);
+        let exclusive = match this.timing.take() {
+            None => elapsed,
+            Some(Timing { inner, outer }) => {
+                if let Some(outer) = outer {
+                    outer.add(elapsed);
+                }
+                elapsed.saturating_sub(inner.get())
+            }
+        };
+        this.registry.record(*this.layer, exclusive);
+        Poll::Ready(out)
+    }
+}
+
+// === impl InnerMicros ===
+
+impl InnerMicros {
+    fn add(&self, duration: Duration) {
+        let micros = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+        self.0.fetch_add(micros, Ordering::AcqRel);
+    }
+
+    fn get(&self) -> Duration {
+        Duration::from_micros(self.0.load(Ordering::Acquire))
+    }
+}
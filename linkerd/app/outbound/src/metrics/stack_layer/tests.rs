@@ -0,0 +1,90 @@
+use super::*;
+use linkerd_app_core::{svc::ServiceExt, Error};
+
+fn record<S>(registry: &StackLayers, layer: StackLayer, inner: S) -> RecordLayer<S> {
+    RecordLayer {
+        inner,
+        layer,
+        registry: registry.clone(),
+    }
+}
+
+/// Returns the bucket holding each of the layer's observations.
+fn buckets(registry: &StackLayers, layer: StackLayer) -> Vec<Bucket> {
+    registry
+        .histogram(layer)
+        .into_iter()
+        .filter(|(_, count)| u64::from(*count) > 0)
+        .map(|(bucket, _)| *bucket)
+        .collect()
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn attributes_time_to_layers() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let registry = StackLayers::default();
+
+    let upstream = record(
+        &registry,
+        StackLayer::Upstream,
+        svc::mk(|_: http::Request<()>| async move {
+            time::sleep(Duration::from_millis(10)).await;
+            Ok::<_, Error>(http::Response::new(()))
+        }),
+    );
+    // The balance layer delays each request before dispatching it upstream.
+    let balance = record(
+        &registry,
+        StackLayer::Balance,
+        svc::mk(move |req: http::Request<()>| {
+            let upstream = upstream.clone();
+            async move {
+                time::sleep(Duration::from_millis(500)).await;
+                upstream.oneshot(req).await
+            }
+        }),
+    );
+    let logical = record(&registry, StackLayer::Logical, balance);
+
+    logical
+        .oneshot(http::Request::new(()))
+        .await
+        .expect("request must succeed");
+
+    assert_eq!(
+        buckets(&registry, StackLayer::Balance),
+        vec![Bucket::Le(0.5)],
+        "the delay must be attributed to the balance layer"
+    );
+    assert_eq!(
+        buckets(&registry, StackLayer::Upstream),
+        vec![Bucket::Le(0.01)]
+    );
+    assert_eq!(
+        buckets(&registry, StackLayer::Logical),
+        vec![Bucket::Le(0.000_1)],
+        "time spent in inner layers must not be attributed to outer layers"
+    );
+    assert!(buckets(&registry, StackLayer::Discovery).is_empty());
+    assert!(buckets(&registry, StackLayer::Connect).is_empty());
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn records_discovery_lookups() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let registry = StackLayers::default();
+    let lookup = registry.record_discovery(svc::mk(|_: ()| async move {
+        time::sleep(Duration::from_millis(50)).await;
+        Ok::<_, Error>(())
+    }));
+    lookup.oneshot(()).await.expect("lookup must succeed");
+
+    assert_eq!(
+        buckets(&registry, StackLayer::Discovery),
+        vec![Bucket::Le(0.05)],
+        "the lookup's duration must be attributed to the discovery layer"
+    );
+    assert!(buckets(&registry, StackLayer::Logical).is_empty());
+}
@@ -1,4 +1,7 @@
-use crate::{stack_labels, Outbound};
+use crate::{
+    discover::{ExcludeEndpoints, ObserveResolve, StabilizeEndpoints},
+    stack_labels, Outbound,
+};
 use linkerd_app_core::{
     drain, io, metrics, profiles,
     proxy::{
@@ -8,7 +11,7 @@ use linkerd_app_core::{
         tcp::{self, balance},
     },
     svc::{self, layer::Layer},
-    tls,
+    tcp_tracing, tls,
     transport::{self, addrs::*},
     transport_header::SessionProtocol,
     Error, Infallible, NameAddr,
@@ -85,17 +88,27 @@ impl<C> Outbound<C> {
         C::Future: Send,
         C: Send + Sync + 'static,
     {
-        let resolve =
-            svc::MapTargetLayer::new(|t: Balance<T>| -> ConcreteAddr { ConcreteAddr(t.addr) })
-                .layer(resolve.into_service());
-
         self.map_stack(|config, rt, inner| {
+            // Publishes endpoint updates to discovery event subscribers,
+            // omitting endpoints whose metadata excludes them from balancers
+            // and deferring removals while the resolution stabilizes.
+            let resolve = ExcludeEndpoints::new(resolve, config.endpoint_exclusions.clone());
+            let resolve = StabilizeEndpoints::new(resolve, config.endpoint_stabilization_window);
+            let resolve =
+                svc::MapTargetLayer::new(|t: Balance<T>| -> ConcreteAddr { ConcreteAddr(t.addr) })
+                    .layer(ObserveResolve::new(resolve, rt.discovery_events.clone()));
+
             let crate::Config {
+                proxy,
                 tcp_connection_queue,
                 ..
             } = config;
 
             let connect = inner
+                .push(tcp_tracing::client(
+                    rt.span_sink.clone(),
+                    crate::trace_labels(),
+                ))
                 .push(svc::stack::WithoutConnectionMetadata::layer())
                 .push_new_thunk();
 
@@ -160,7 +173,12 @@ impl<C> Outbound<C> {
                     },
                     forward.into_inner(),
                 )
-                .push_on_service(tcp::Forward::layer())
+                .push_on_service(tcp::Forward::layer(
+                    proxy.forward_buffer_capacity,
+                    proxy.forward_idle_timeout,
+                ))
+                // Counts connections closed by keepalive probes.
+                .push(rt.metrics.tcp_keepalive_closes.to_layer())
                 .push_on_service(drain::Retain::layer(rt.drain.clone()))
                 .push(svc::NewQueue::layer_via(*tcp_connection_queue))
                 .push(svc::ArcNewService::layer())
@@ -58,12 +58,14 @@ impl<N> Outbound<N> {
             stk.push_on_service(http::BoxRequest::layer())
                 .unlift_new()
                 .push(http::NewServeHttp::layer(
+                    config.proxy.server.h1_settings,
                     config.proxy.server.h2_settings,
+                    rt.metrics.proxy.http_server.clone(),
                     rt.drain.clone(),
                 ))
         });
 
-        let detect = http.clone().map_stack(|config, _, http| {
+        let detect = http.clone().map_stack(|config, rt, http| {
             http.push_switch(
                 |(result, parent): (detect::Result<http::Version>, T)| -> Result<_, Infallible> {
                     Ok(match detect::allow_timeout(result) {
@@ -79,7 +81,10 @@ impl<N> Outbound<N> {
             .push_on_service(svc::LoadShed::layer())
             .push_on_service(svc::MapTargetLayer::new(io::EitherIo::Right))
             .lift_new_with_target::<(detect::Result<http::Version>, T)>()
-            .push(detect::NewDetectService::layer(config.proxy.detect_http()))
+            .push(detect::NewDetectService::layer(
+                config.proxy.detect_http(),
+                rt.metrics.proxy.detect.clone(),
+            ))
         });
 
         http.map_stack(|_, _, http| {
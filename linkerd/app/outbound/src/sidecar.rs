@@ -33,7 +33,7 @@ impl Outbound<()> {
         // Endpoint resolver.
         R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
     {
-        let opaq = self.to_tcp_connect().push_opaq_cached(resolve.clone());
+        let opaq = self.to_opaq_tcp_connect().push_opaq_cached(resolve.clone());
         let http = self.to_tcp_connect().push_http_cached(resolve);
 
         opaq.push_protocol(http.into_inner())
@@ -21,9 +21,23 @@ pub struct PreventLoopback<S>(S);
 
 impl Outbound<()> {
     pub fn to_tcp_connect(&self) -> Outbound<PreventLoopback<ConnectTcp>> {
-        let connect = PreventLoopback(ConnectTcp::new(self.config.proxy.connect.keepalive));
+        let connect = PreventLoopback(
+            ConnectTcp::new(self.config.proxy.connect.keepalive)
+                .with_source_addrs(self.config.connect_source_addrs.clone()),
+        );
         self.clone().with_stack(connect)
     }
+
+    /// Builds a connect stack for opaque connections, which may be configured
+    /// with keepalive probes that detect dead peers.
+    pub fn to_opaq_tcp_connect(&self) -> Outbound<PreventLoopback<ConnectTcp>> {
+        let mut connect = ConnectTcp::new(self.config.proxy.connect.keepalive)
+            .with_source_addrs(self.config.connect_source_addrs.clone());
+        if let Some(probes) = self.config.opaq_keepalive_probes {
+            connect = connect.with_keepalive_probes(probes);
+        }
+        self.clone().with_stack(PreventLoopback(connect))
+    }
 }
 
 // === impl PreventLoopback ===
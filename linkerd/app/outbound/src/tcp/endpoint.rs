@@ -37,17 +37,27 @@ impl<C> Outbound<C> {
         C::Future: Send + 'static,
     {
         self.map_stack(|config, rt, connect| {
-            connect
+            svc::stack(connect.into_inner().into_service())
+                // Limits the time we wait for a TCP connection to be established.
+                .push_connect_timeout(config.proxy.connect.timeout)
                 // Initiates mTLS if the target is configured with identity. The
                 // endpoint configures ALPN when there is an opaque transport hint OR
                 // when an authority override is present (indicating the target is a
-                // remote cluster gateway).
-                .push(tls::Client::layer(rt.identity.clone()))
+                // remote cluster gateway). The handshake is limited separately from
+                // the TCP connection.
+                .push(tls::Client::layer_with_handshake_timeout(
+                    rt.identity.clone(),
+                    Some(config.tls_handshake_timeout),
+                ))
                 // Encodes a transport header if the established connection is TLS'd and
                 // ALPN negotiation indicates support.
                 .push(TaggedTransport::layer())
-                // Limits the time we wait for a connection to be established.
-                .push_connect_timeout(config.proxy.connect.timeout)
+                // Limits the time we wait for a connection to be established,
+                // including its TLS handshake and transport header.
+                .push_connect_timeout(config.proxy.connect.timeout + config.tls_handshake_timeout)
+                // Counts failed connects by the reason they failed, including
+                // failed TLS handshakes.
+                .push(rt.metrics.connect_errors.layer())
                 .push(svc::stack::BoxFuture::layer())
                 .push(transport::metrics::Client::layer(
                     rt.metrics.proxy.transport.clone(),
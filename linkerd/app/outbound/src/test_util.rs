@@ -21,11 +21,41 @@ pub(crate) fn default_config() -> Config {
     Config {
         ingress_mode: false,
         emit_headers: true,
+        json_error_bodies: false,
+        grpc_web_errors: false,
+        http1_require_host: false,
+        http1_reject_ambiguous_framing: false,
+        http_max_header_value_bytes: None,
+        http1_transfer_encoding: None,
+        route_latency_slo: None,
+        route_latency_slo_log_breaches: false,
+        route_availability_window: None,
+        route_payload_size_buckets: None,
+        http_health_check: None,
+        http_latency_outlier_detection: None,
+        http_backend_connection_limit: None,
+        http_response_cache: None,
+        http_request_coalescing: None,
+        http_backend_protocols: Default::default(),
+        http_backend_balancers: Default::default(),
+        http_routes: Default::default(),
+        http_route_response_body_limit_mode: Default::default(),
+        http_backend_fallback: Default::default(),
+        http_retry_max_buffered_bytes: 64 * 1024,
+        http_retryable_statuses: Default::default(),
+        http_retry_min_attempt_time: None,
+        http_retry_after_max: None,
+        http_retry_idempotent_header: None,
+        http_retry_on_reset: Default::default(),
+        http_retry_timeout_headroom: None,
+        http_response_trailers: Default::default(),
+        tls_handshake_timeout: Duration::from_secs(1),
         allow_discovery: IpMatch::new(Some(IpNet::from_str("0.0.0.0/0").unwrap())).into(),
         proxy: config::ProxyConfig {
             server: config::ServerConfig {
                 addr: ListenAddr(([0, 0, 0, 0], 0).into()),
                 keepalive: Keepalive(None),
+                h1_settings: h1::ServerSettings::default(),
                 h2_settings: h2::Settings::default(),
             },
             connect: config::ConnectConfig {
@@ -37,19 +67,33 @@ pub(crate) fn default_config() -> Config {
                     0.1,
                 )
                 .unwrap(),
+                backoff_max_elapsed: None,
                 h1_settings: h1::PoolSettings {
                     max_idle: 1,
                     idle_timeout: Duration::from_secs(1),
+                    max_buf_size: None,
+                    close_delimited: Default::default(),
                 },
                 h2_settings: h2::Settings::default(),
+                h2_pool: h2::PoolSettings::default(),
             },
             max_in_flight_requests: 10_000,
             detect_protocol_timeout: Duration::from_secs(3),
+            forward_buffer_capacity: 8 * 1024,
+            forward_idle_timeout: None,
         },
         inbound_ips: Default::default(),
         discovery_idle_timeout: Duration::from_secs(60),
+        discovery_idle_jitter: Duration::ZERO,
+        discovery_max_lifetime: None,
+        endpoint_exclusions: Default::default(),
+        endpoint_staleness: None,
+        endpoint_stabilization_window: None,
+        connect_source_addrs: Default::default(),
+        opaq_keepalive_probes: None,
         tcp_connection_queue: buffer,
         http_request_queue: buffer,
+        http_request_queue_fair: false,
     }
 }
 
@@ -57,13 +101,15 @@ pub(crate) fn runtime() -> (ProxyRuntime, drain::Signal) {
     let (drain_tx, drain) = drain::channel();
     let (tap, _) = tap::new();
     let (metrics, _) =
-        metrics::Metrics::new(std::time::Duration::from_secs(10), Default::default());
+        metrics::Metrics::new(std::time::Duration::from_secs(10), 1.0, Default::default());
     let runtime = ProxyRuntime {
         identity: linkerd_meshtls_rustls::creds::default_for_test().1.into(),
+        shadow_identity: None,
         metrics: metrics.proxy,
         tap,
         span_sink: None,
         drain,
+        startup: Default::default(),
     };
     (runtime, drain_tx)
 }
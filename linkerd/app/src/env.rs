@@ -1,16 +1,17 @@
 use crate::core::{
     addr,
     config::*,
-    control::{Config as ControlConfig, ControlAddr},
-    proxy::http::{h1, h2},
+    control::{CircuitConfig, Config as ControlConfig, ControlAddr},
+    proxy::http::{self, h1, h2},
+    startup::StartupMode,
     tls,
-    transport::{Keepalive, ListenAddr},
-    Addr, AddrMatch, Conditional, IpNet,
+    transport::{self, Keepalive, ListenAddr},
+    Addr, AddrMatch, Conditional, IpNet, NameAddr,
 };
 use crate::{dns, gateway, identity, inbound, oc_collector, outbound};
 use inbound::policy;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{hash_map::Entry, HashMap, HashSet},
     fs,
     net::{IpAddr, SocketAddr},
     path::PathBuf,
@@ -46,6 +47,8 @@ pub enum ParseError {
     NotADuration,
     #[error("not a valid DNS domain suffix")]
     NotADomainSuffix,
+    #[error("not a valid DNS query strategy: {0}")]
+    InvalidDnsQueryStrategy(String),
     #[error("not a boolean value: {0}")]
     NotABool(
         #[from]
@@ -64,6 +67,16 @@ pub enum ParseError {
         #[source]
         std::num::ParseFloatError,
     ),
+    #[error("latency multiple must be a finite number greater than 1")]
+    InvalidLatencyMultiple,
+    #[error("ejection multiplier must be a finite number of at least 1")]
+    InvalidEjectionMultiplier,
+    #[error("not a valid backend ejection: {0}")]
+    InvalidBackendEjection(String),
+    #[error("sample rate must be a number between 0 and 1")]
+    InvalidSampleRate,
+    #[error("HTTP/2 max frame size must be between 16384 and 16777215 bytes")]
+    InvalidH2MaxFrameSize,
     #[error("not a valid subnet mask")]
     NotANetwork,
     #[error("host is not an IP address")]
@@ -84,6 +97,82 @@ pub enum ParseError {
     InvalidTrustAnchors,
     #[error("not a valid port policy: {0}")]
     InvalidPortPolicy(String),
+    #[error("buffer size must be at least {0} bytes")]
+    BufferTooSmall(usize),
+    #[error("not a valid HTTP path")]
+    NotAPath,
+    #[error("not a valid CONNECT mode: {0}")]
+    InvalidConnectMode(String),
+    #[error("not a valid transfer-encoding mode: {0}")]
+    InvalidTransferEncodingMode(String),
+    #[error("not a valid connection limit mode: {0}")]
+    InvalidConnectionLimitMode(String),
+    #[error("not a valid concurrency limit mode: {0}")]
+    InvalidConcurrencyLimitMode(String),
+    #[error("not a valid backend protocol: {0}")]
+    InvalidBackendProtocol(String),
+    #[error("not a valid backend balancer: {0}")]
+    InvalidBackendBalancer(String),
+    #[error("not a valid route requiring mTLS: {0}")]
+    InvalidRoute(String),
+    #[error("not a valid route method: {0}")]
+    InvalidRouteMethod(String),
+    #[error("not a valid route request body limit: {0}")]
+    InvalidRouteRequestBodyLimit(String),
+    #[error("not a valid route content type: {0}")]
+    InvalidRouteContentType(String),
+    #[error("not a valid endpoint label: {0}")]
+    InvalidEndpointLabel(String),
+    #[error("not a valid source address: {0}")]
+    InvalidSourceAddr(String),
+    #[error("not a valid route response body limit: {0}")]
+    InvalidRouteResponseBodyLimit(String),
+    #[error("not a valid route retry buffer limit: {0}")]
+    InvalidRouteRetryMaxBufferedBytes(String),
+    #[error("not a valid route retryable status: {0}")]
+    InvalidRouteRetryableStatus(String),
+    #[error("not a valid HTTP/1 pipelining mode: {0}")]
+    InvalidPipelining(String),
+    #[error("not a valid HTTP/1 close-delimited response mode: {0}")]
+    InvalidCloseDelimitedResponses(String),
+    #[error("not a valid HTTP/1 parse error mode: {0}")]
+    InvalidParseErrors(String),
+    #[error("not a valid stale endpoint fallback: {0}")]
+    InvalidStaleEndpointFallback(String),
+    #[error("not a valid route priority: {0}")]
+    InvalidRoutePriority(String),
+    #[error("not a valid route latency SLO: {0}")]
+    InvalidRouteLatencySlo(String),
+    #[error("not a valid route fault: {0}")]
+    InvalidRouteFault(String),
+    #[error("not a valid route header backend: {0}")]
+    InvalidRouteHeaderBackend(String),
+    #[error("not a valid route authority rewrite: {0}")]
+    InvalidRouteAuthorityRewrite(String),
+    #[error("not a valid backend fallback: {0}")]
+    InvalidBackendFallback(String),
+    #[error("not a valid reset behavior: {0}")]
+    InvalidResetBehavior(String),
+    #[error("not a valid response body limit mode: {0}")]
+    InvalidResponseBodyLimitMode(String),
+    #[error("not a valid SNI port mapping: {0}")]
+    InvalidSniPort(String),
+    #[error("not a valid HTTP status: {0}")]
+    InvalidStatus(String),
+    #[error("not a valid route gRPC status mapping: {0}")]
+    InvalidRouteGrpcStatusMapping(String),
+    #[error("not a valid trailer pattern: {0}")]
+    InvalidTrailerPattern(String),
+    #[error("not a valid header name: {0}")]
+    InvalidHeaderName(String),
+    #[error("not a valid startup mode: {0}")]
+    InvalidStartupMode(String),
+    #[error("not a valid pinned certificate: {0}")]
+    InvalidPinnedCert(String),
+    #[error("payload size buckets must be positive and strictly increasing")]
+    InvalidPayloadSizeBuckets,
+    #[error("duration must be positive")]
+    ZeroDuration,
 }
 
 // Environment variables to look at when loading the configuration
@@ -94,6 +183,13 @@ pub const ENV_ADMIN_LISTEN_ADDR: &str = "LINKERD2_PROXY_ADMIN_LISTEN_ADDR";
 
 pub const ENV_METRICS_RETAIN_IDLE: &str = "LINKERD2_PROXY_METRICS_RETAIN_IDLE";
 
+/// Configures the fraction of HTTP requests whose latencies are recorded in
+/// endpoint and route latency histograms, between 0 and 1. Request and
+/// response counts are always recorded.
+///
+/// If unspecified, the latencies of all requests are recorded.
+pub const ENV_METRICS_LATENCY_SAMPLE_RATE: &str = "LINKERD2_PROXY_METRICS_LATENCY_SAMPLE_RATE";
+
 const ENV_INGRESS_MODE: &str = "LINKERD2_PROXY_INGRESS_MODE";
 
 const ENV_INBOUND_HTTP_QUEUE_CAPACITY: &str = "LINKERD2_PROXY_INBOUND_HTTP_QUEUE_CAPACITY";
@@ -104,12 +200,30 @@ const ENV_OUTBOUND_TCP_FAILFAST_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_TCP_FAI
 const ENV_OUTBOUND_HTTP_QUEUE_CAPACITY: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_QUEUE_CAPACITY";
 const ENV_OUTBOUND_HTTP_FAILFAST_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_FAILFAST_TIMEOUT";
 
+/// Configures whether each outbound backend's request queue dispatches requests
+/// round-robin across clients, rather than in the order in which they are
+/// queued.
+const ENV_OUTBOUND_HTTP_QUEUE_FAIR: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_QUEUE_FAIR";
+
 pub const ENV_INBOUND_DETECT_TIMEOUT: &str = "LINKERD2_PROXY_INBOUND_DETECT_TIMEOUT";
 const ENV_OUTBOUND_DETECT_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_DETECT_TIMEOUT";
 
 const ENV_INBOUND_CONNECT_TIMEOUT: &str = "LINKERD2_PROXY_INBOUND_CONNECT_TIMEOUT";
 const ENV_OUTBOUND_CONNECT_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_CONNECT_TIMEOUT";
 
+/// Configures the maximum amount of time an outbound TLS handshake may take
+/// once a TCP connection has been established. When unset, the handshake is
+/// limited by the outbound connect timeout.
+const ENV_OUTBOUND_TLS_HANDSHAKE_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_TLS_HANDSHAKE_TIMEOUT";
+
+/// Configures the maximum amount of time an endpoint may spend backing off and
+/// reconnecting before it fails and is removed from load balancing. When unset,
+/// endpoints reconnect indefinitely.
+const ENV_INBOUND_CONNECT_BACKOFF_MAX_ELAPSED: &str =
+    "LINKERD2_PROXY_INBOUND_CONNECT_BACKOFF_MAX_ELAPSED";
+const ENV_OUTBOUND_CONNECT_BACKOFF_MAX_ELAPSED: &str =
+    "LINKERD2_PROXY_OUTBOUND_CONNECT_BACKOFF_MAX_ELAPSED";
+
 const ENV_INBOUND_ACCEPT_KEEPALIVE: &str = "LINKERD2_PROXY_INBOUND_ACCEPT_KEEPALIVE";
 const ENV_OUTBOUND_ACCEPT_KEEPALIVE: &str = "LINKERD2_PROXY_OUTBOUND_ACCEPT_KEEPALIVE";
 
@@ -126,6 +240,548 @@ pub const ENV_OUTBOUND_MAX_IN_FLIGHT: &str = "LINKERD2_PROXY_OUTBOUND_MAX_IN_FLI
 const ENV_OUTBOUND_DISABLE_INFORMATIONAL_HEADERS: &str =
     "LINKERD2_PROXY_OUTBOUND_DISABLE_INFORMATIONAL_HEADERS";
 
+/// Configures whether HTTP error responses synthesized by the proxy (e.g. on
+/// timeouts, load shedding, or failfast) include a JSON body describing the
+/// error, its HTTP status code, and the request's `x-request-id`, if one was set.
+///
+/// By default, these responses have empty bodies.
+const ENV_INBOUND_JSON_ERROR_BODIES: &str = "LINKERD2_PROXY_INBOUND_JSON_ERROR_BODIES";
+const ENV_OUTBOUND_JSON_ERROR_BODIES: &str = "LINKERD2_PROXY_OUTBOUND_JSON_ERROR_BODIES";
+
+/// Configures whether errors on outbound HTTP/1 gRPC-Web requests are
+/// synthesized as trailers-only gRPC-Web responses that carry a `grpc-status`.
+///
+/// By default, these requests are failed with HTTP error responses. Errors on
+/// HTTP/2 gRPC requests are always synthesized as trailers-only responses.
+const ENV_OUTBOUND_GRPC_WEB_ERRORS: &str = "LINKERD2_PROXY_OUTBOUND_GRPC_WEB_ERRORS";
+
+/// Configures whether origin-form HTTP/1 requests without a `Host` header are
+/// rejected with a 400 response.
+///
+/// By default, these requests are routed to the connection's original
+/// destination address.
+const ENV_INBOUND_HTTP1_REQUIRE_HOST: &str = "LINKERD2_PROXY_INBOUND_HTTP1_REQUIRE_HOST";
+const ENV_OUTBOUND_HTTP1_REQUIRE_HOST: &str = "LINKERD2_PROXY_OUTBOUND_HTTP1_REQUIRE_HOST";
+
+/// Configures whether HTTP/1 requests with ambiguous framing are rejected with a
+/// 400 response. A request's framing is ambiguous when it sets both
+/// `Content-Length` and `Transfer-Encoding`, sets multiple `Content-Length`
+/// values, or has a `Transfer-Encoding` that doesn't end with a single `chunked`
+/// coding.
+///
+/// By default, requests are framed as they are parsed by the proxy.
+const ENV_INBOUND_HTTP1_REJECT_AMBIGUOUS_FRAMING: &str =
+    "LINKERD2_PROXY_INBOUND_HTTP1_REJECT_AMBIGUOUS_FRAMING";
+const ENV_OUTBOUND_HTTP1_REJECT_AMBIGUOUS_FRAMING: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP1_REJECT_AMBIGUOUS_FRAMING";
+
+/// Configures the maximum length of each HTTP request header value, in bytes.
+/// Requests with longer header values are rejected with a 431 response.
+///
+/// By default, header values are not limited.
+const ENV_INBOUND_HTTP_MAX_HEADER_VALUE_LENGTH: &str =
+    "LINKERD2_PROXY_INBOUND_HTTP_MAX_HEADER_VALUE_LENGTH";
+const ENV_OUTBOUND_HTTP_MAX_HEADER_VALUE_LENGTH: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_MAX_HEADER_VALUE_LENGTH";
+
+/// Configures how the `Transfer-Encoding` of chunked HTTP/1 requests is
+/// normalized before they are forwarded. Transfer codings are always combined
+/// into a single, lowercased header. In `lenient` mode, repeated `chunked`
+/// codings are collapsed and a conflicting `Content-Length` is removed; in
+/// `strict` mode, these requests are rejected with a 400 response. Requests
+/// whose transfer codings don't end with `chunked` are rejected in both modes.
+///
+/// By default, requests are forwarded as they are parsed by the proxy.
+const ENV_INBOUND_HTTP1_TRANSFER_ENCODING: &str = "LINKERD2_PROXY_INBOUND_HTTP1_TRANSFER_ENCODING";
+const ENV_OUTBOUND_HTTP1_TRANSFER_ENCODING: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP1_TRANSFER_ENCODING";
+
+/// Configures whether inbound HTTP/1.0 requests are forwarded to the
+/// application as HTTP/1.1 requests, so that they may use pooled keep-alive
+/// connections. Responses are downgraded to HTTP/1.0.
+///
+/// By default, HTTP/1.0 requests are forwarded unmodified.
+const ENV_INBOUND_HTTP1_BRIDGE_HTTP10: &str = "LINKERD2_PROXY_INBOUND_HTTP1_BRIDGE_HTTP10";
+
+/// Configures the maximum number of request body bytes that are included in
+/// the access log of inbound requests that receive error (4xx or 5xx)
+/// responses.
+///
+/// By default, request bodies are not logged.
+const ENV_INBOUND_ACCESS_LOG_ERROR_BODY_BYTES: &str =
+    "LINKERD2_PROXY_INBOUND_ACCESS_LOG_ERROR_BODY_BYTES";
+
+/// Configures how inbound HTTP/1.1 `CONNECT` requests are handled: `forward`
+/// sends them to the application, `reject` fails them with a 405, and `tunnel`
+/// responds to them in the proxy and tunnels the connection to the inbound
+/// target.
+///
+/// By default, `CONNECT` requests are forwarded.
+const ENV_INBOUND_HTTP1_CONNECT_MODE: &str = "LINKERD2_PROXY_INBOUND_HTTP1_CONNECT_MODE";
+
+/// Configures whether inbound HTTP requests are forwarded to the application
+/// with an `l5d-dst-port` header set to the port of the connection's original
+/// destination. Any such header set by the client is overwritten.
+///
+/// By default, the header is not set.
+const ENV_INBOUND_DST_PORT_HEADER: &str = "LINKERD2_PROXY_INBOUND_DST_PORT_HEADER";
+
+/// Restricts the HTTP methods accepted by inbound routes, as a comma-separated
+/// list of `route=METHOD` entries, where `route` is the name of an inbound
+/// policy route. A route may be listed once for each of its allowed methods.
+/// Requests on these routes with other methods fail with a 405.
+///
+/// By default, routes accept all methods.
+const ENV_INBOUND_HTTP_ROUTE_ALLOWED_METHODS: &str =
+    "LINKERD2_PROXY_INBOUND_HTTP_ROUTE_ALLOWED_METHODS";
+
+/// Configures the maximum request body size, in bytes, of inbound routes, as a
+/// comma-separated list of `route=bytes` entries, where `route` is the name of
+/// an inbound policy route. Requests on these routes with larger bodies fail
+/// with a 413.
+///
+/// By default, request bodies are not limited.
+const ENV_INBOUND_HTTP_ROUTE_REQUEST_BODY_LIMITS: &str =
+    "LINKERD2_PROXY_INBOUND_HTTP_ROUTE_REQUEST_BODY_LIMITS";
+
+/// Restricts the content types of request bodies accepted by inbound routes,
+/// as a comma-separated list of `route=type` entries (e.g.
+/// `api=application/json`), where `route` is the name of an inbound policy
+/// route. A route may be listed once for each of its allowed content types.
+/// Requests on these routes with bodies of other content types fail with a
+/// 415.
+///
+/// By default, routes accept bodies of all content types.
+const ENV_INBOUND_HTTP_ROUTE_REQUEST_CONTENT_TYPES: &str =
+    "LINKERD2_PROXY_INBOUND_HTTP_ROUTE_REQUEST_CONTENT_TYPES";
+
+/// Configures how long inbound connections to the application are retried,
+/// with the inbound connect backoff, while the application refuses them. This
+/// gives the application a chance to start listening after the proxy starts.
+///
+/// By default, refused connections fail immediately.
+const ENV_INBOUND_APP_CONNECT_GRACE: &str = "LINKERD2_PROXY_INBOUND_APP_CONNECT_GRACE";
+
+/// Configures the maximum duration for which an inbound HTTP connection may be
+/// open. Once it is reached the connection is closed gracefully, after its
+/// in-flight requests complete, so that the client reconnects.
+///
+/// By default, connections are not closed after a maximum lifetime.
+const ENV_INBOUND_MAX_CONNECTION_LIFETIME: &str = "LINKERD2_PROXY_INBOUND_MAX_CONNECTION_LIFETIME";
+
+/// Configures the time within which the body of each inbound HTTP request must
+/// be received once the request is dispatched, so that slow clients cannot
+/// hold requests open. Requests whose bodies are slower are reset.
+///
+/// By default, request bodies are not timed out.
+const ENV_INBOUND_HTTP_REQUEST_BODY_TIMEOUT: &str =
+    "LINKERD2_PROXY_INBOUND_HTTP_REQUEST_BODY_TIMEOUT";
+
+/// Routes inbound TLS connections that are passed through to the application
+/// by their SNI, as a comma-separated list of `sni=port` entries. Connections
+/// with a listed SNI are forwarded to the given local port rather than to
+/// their original destination port.
+///
+/// By default, connections are forwarded to their original destination.
+const ENV_INBOUND_TLS_SNI_PORTS: &str = "LINKERD2_PROXY_INBOUND_TLS_SNI_PORTS";
+
+/// Configures how inbound HTTP requests are handled once the maximum number of
+/// in-flight requests is reached: either `queue`, to wait for another request
+/// to complete, or `shed`, to fail immediately with a 503.
+///
+/// By default, requests are shed.
+const ENV_INBOUND_HTTP_CONCURRENCY_LIMIT_MODE: &str =
+    "LINKERD2_PROXY_INBOUND_HTTP_CONCURRENCY_LIMIT_MODE";
+
+/// Configures how long inbound HTTP requests wait for the number of in-flight
+/// requests to drop below the limit, when they are queued, before failing with
+/// a 503.
+const ENV_INBOUND_HTTP_CONCURRENCY_LIMIT_QUEUE_TIMEOUT: &str =
+    "LINKERD2_PROXY_INBOUND_HTTP_CONCURRENCY_LIMIT_QUEUE_TIMEOUT";
+
+/// Configures a latency SLO for outbound HTTP routes. Requests that take longer
+/// than this duration are counted, per-route, in the
+/// `outbound_http_route_slo_breaches_total` metric.
+///
+/// By default, no SLO is enforced.
+const ENV_OUTBOUND_ROUTE_LATENCY_SLO: &str = "LINKERD2_PROXY_OUTBOUND_ROUTE_LATENCY_SLO";
+
+/// Configures the latency SLOs of outbound HTTP routes, as a comma-separated
+/// list of `name:port=route=duration` entries, where `route` is the name of one
+/// of the service's profile routes. These override
+/// `LINKERD2_PROXY_OUTBOUND_ROUTE_LATENCY_SLO` for the routes they name.
+const ENV_OUTBOUND_HTTP_ROUTE_LATENCY_SLOS: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_ROUTE_LATENCY_SLOS";
+
+/// Configures whether each outbound route latency SLO breach is logged.
+const ENV_OUTBOUND_LOG_ROUTE_SLO_BREACHES: &str = "LINKERD2_PROXY_OUTBOUND_LOG_ROUTE_SLO_BREACHES";
+
+/// Configures the rolling window over which the proportion of each outbound
+/// HTTP route's requests that are classified as successful is reported in the
+/// `outbound_http_route_availability` metric.
+///
+/// By default, route availability is not reported.
+const ENV_OUTBOUND_ROUTE_AVAILABILITY_WINDOW: &str =
+    "LINKERD2_PROXY_OUTBOUND_ROUTE_AVAILABILITY_WINDOW";
+
+/// Configures the bucket boundaries, in bytes, of the histograms of each
+/// outbound HTTP route's request and response body sizes, as a comma-separated
+/// list of strictly increasing sizes (e.g. `1024,16384,1048576`).
+///
+/// By default, route payload sizes are not reported.
+const ENV_OUTBOUND_ROUTE_PAYLOAD_SIZE_BUCKETS: &str =
+    "LINKERD2_PROXY_OUTBOUND_ROUTE_PAYLOAD_SIZE_BUCKETS";
+
+/// Configures the path requested by active health checks of balanced outbound
+/// HTTP endpoints. Endpoints that fail consecutive health checks are excluded
+/// from load balancing until they pass consecutive health checks.
+///
+/// By default, endpoints are not actively health checked.
+const ENV_OUTBOUND_HEALTH_CHECK_PATH: &str = "LINKERD2_PROXY_OUTBOUND_HEALTH_CHECK_PATH";
+const ENV_OUTBOUND_HEALTH_CHECK_INTERVAL: &str = "LINKERD2_PROXY_OUTBOUND_HEALTH_CHECK_INTERVAL";
+const ENV_OUTBOUND_HEALTH_CHECK_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_HEALTH_CHECK_TIMEOUT";
+const ENV_OUTBOUND_HEALTH_CHECK_HEALTHY_THRESHOLD: &str =
+    "LINKERD2_PROXY_OUTBOUND_HEALTH_CHECK_HEALTHY_THRESHOLD";
+const ENV_OUTBOUND_HEALTH_CHECK_UNHEALTHY_THRESHOLD: &str =
+    "LINKERD2_PROXY_OUTBOUND_HEALTH_CHECK_UNHEALTHY_THRESHOLD";
+
+/// Configures the multiple of the median p99 latency of a balancer's endpoints
+/// beyond which an endpoint is considered a latency outlier. Outliers are
+/// excluded from load balancing for the configured ejection time, so long as
+/// no more than the configured percentage of endpoints are ejected.
+///
+/// By default, endpoints are not ejected for their latency.
+const ENV_OUTBOUND_LATENCY_OUTLIER_MULTIPLE: &str =
+    "LINKERD2_PROXY_OUTBOUND_LATENCY_OUTLIER_MULTIPLE";
+const ENV_OUTBOUND_LATENCY_OUTLIER_MAX_EJECTION_PERCENT: &str =
+    "LINKERD2_PROXY_OUTBOUND_LATENCY_OUTLIER_MAX_EJECTION_PERCENT";
+const ENV_OUTBOUND_LATENCY_OUTLIER_INTERVAL: &str =
+    "LINKERD2_PROXY_OUTBOUND_LATENCY_OUTLIER_INTERVAL";
+const ENV_OUTBOUND_LATENCY_OUTLIER_EJECTION_TIME: &str =
+    "LINKERD2_PROXY_OUTBOUND_LATENCY_OUTLIER_EJECTION_TIME";
+const ENV_OUTBOUND_LATENCY_OUTLIER_MIN_REQUESTS: &str =
+    "LINKERD2_PROXY_OUTBOUND_LATENCY_OUTLIER_MIN_REQUESTS";
+
+/// Configures how latency outlier ejections back off. Each consecutive
+/// ejection of an endpoint lasts the multiplier times the prior ejection,
+/// starting at the ejection time and up to the maximum ejection time.
+///
+/// The backend ejections override these times for named backends, as a
+/// comma-separated list of `name:port=base:multiplier:max` entries, e.g.
+/// `web.ns.svc.cluster.local:8080=10s:2:5m`.
+///
+/// By default, every ejection lasts the ejection time.
+const ENV_OUTBOUND_LATENCY_OUTLIER_EJECTION_MULTIPLIER: &str =
+    "LINKERD2_PROXY_OUTBOUND_LATENCY_OUTLIER_EJECTION_MULTIPLIER";
+const ENV_OUTBOUND_LATENCY_OUTLIER_MAX_EJECTION_TIME: &str =
+    "LINKERD2_PROXY_OUTBOUND_LATENCY_OUTLIER_MAX_EJECTION_TIME";
+const ENV_OUTBOUND_LATENCY_OUTLIER_BACKEND_EJECTIONS: &str =
+    "LINKERD2_PROXY_OUTBOUND_LATENCY_OUTLIER_BACKEND_EJECTIONS";
+
+/// Configures the maximum number of connections the outbound proxy opens to
+/// each HTTP backend, across all of the backend's endpoints.
+///
+/// By default, connections are not limited.
+const ENV_OUTBOUND_BACKEND_MAX_CONNECTIONS: &str =
+    "LINKERD2_PROXY_OUTBOUND_BACKEND_MAX_CONNECTIONS";
+
+/// Configures how new connections to a backend are handled once its connection
+/// limit is reached: either `queue`, to wait for an open connection to close,
+/// or `shed`, to fail immediately.
+///
+/// By default, new connections are queued.
+const ENV_OUTBOUND_BACKEND_CONNECTION_LIMIT_MODE: &str =
+    "LINKERD2_PROXY_OUTBOUND_BACKEND_CONNECTION_LIMIT_MODE";
+
+/// Configures the maximum number of responses cached for each outbound HTTP
+/// route. Only responses to `GET` requests that are marked cacheable by their
+/// `Cache-Control` headers are cached.
+///
+/// By default, responses are not cached.
+const ENV_OUTBOUND_HTTP_RESPONSE_CACHE_MAX_ENTRIES: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RESPONSE_CACHE_MAX_ENTRIES";
+const ENV_OUTBOUND_HTTP_RESPONSE_CACHE_MAX_TTL: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RESPONSE_CACHE_MAX_TTL";
+const ENV_OUTBOUND_HTTP_RESPONSE_CACHE_MAX_BODY_BYTES: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RESPONSE_CACHE_MAX_BODY_BYTES";
+
+/// Enables the coalescing of identical in-flight `GET` requests on each
+/// outbound HTTP route, so that concurrent requests with the same method, URI,
+/// and key headers (a comma-separated list of header names) share a single
+/// upstream response. Shared response bodies are buffered up to the max body
+/// byte limit.
+///
+/// By default, requests are not coalesced.
+const ENV_OUTBOUND_HTTP_REQUEST_COALESCING: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_REQUEST_COALESCING";
+const ENV_OUTBOUND_HTTP_REQUEST_COALESCING_KEY_HEADERS: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_REQUEST_COALESCING_KEY_HEADERS";
+const ENV_OUTBOUND_HTTP_REQUEST_COALESCING_MAX_BODY_BYTES: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_REQUEST_COALESCING_MAX_BODY_BYTES";
+
+/// Configures the HTTP version used to reach named backends of outbound traffic
+/// splits, as a comma-separated list of `name:port=h1` or `name:port=h2`
+/// entries. Requests are translated to each backend's version, so that traffic
+/// may be split across backends that speak different protocols.
+///
+/// By default, backends use the version of the original request.
+const ENV_OUTBOUND_HTTP_BACKEND_PROTOCOLS: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_BACKEND_PROTOCOLS";
+
+/// Configures the load balancer of named outbound HTTP backends, as a
+/// comma-separated list of `name:port=peak-ewma`, `name:port=wrr`, or
+/// `name:port=hash[:header...]` entries. `wrr` backends send requests to their
+/// endpoints in a deterministic weighted round-robin order, using the weights
+/// provided by the destination service. `hash` backends send requests with the
+/// same path and values of the listed headers to the same endpoint, e.g.
+/// `cache.ns.svc.cluster.local:8080=hash:x-tenant`.
+///
+/// By default, backends are balanced by peak-EWMA latency.
+const ENV_OUTBOUND_HTTP_BACKEND_BALANCERS: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_BACKEND_BALANCERS";
+
+/// Configures outbound HTTP routes whose requests may only be sent to endpoints
+/// over mTLS, as a comma-separated list of `name:port=route` entries, where
+/// `route` is the name of one of the service's profile routes. Requests on these
+/// routes fail when the endpoint cannot be meshed.
+///
+/// By default, no routes require mTLS.
+const ENV_OUTBOUND_HTTP_MTLS_REQUIRED_ROUTES: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_MTLS_REQUIRED_ROUTES";
+
+/// Configures the maximum response body size, in bytes, of outbound HTTP routes,
+/// as a comma-separated list of `name:port=route=bytes` entries, where `route`
+/// is the name of one of the service's profile routes.
+///
+/// By default, response bodies are not limited.
+const ENV_OUTBOUND_HTTP_ROUTE_RESPONSE_BODY_LIMITS: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_ROUTE_RESPONSE_BODY_LIMITS";
+
+/// Configures the priorities of outbound HTTP routes, as a comma-separated list
+/// of `name:port=route=weight` entries, where `route` is the name of one of the
+/// service's profile routes and `weight` is a positive integer. When a backend
+/// is saturated, its queued requests are dispatched in proportion to their
+/// routes' weights.
+///
+/// By default, routes have a weight of 1 and requests are not prioritized.
+const ENV_OUTBOUND_HTTP_ROUTE_PRIORITIES: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_ROUTE_PRIORITIES";
+
+/// Configures faults to inject into outbound HTTP routes for resilience
+/// testing, as a comma-separated list of `name:port=route=fault` entries, where
+/// `route` is the name of one of the service's profile routes. Each fault is
+/// either `delay:<duration>:<fraction>`, which delays the given fraction of
+/// requests before they are dispatched, or `abort:<status>:<fraction>`, which
+/// responds to the given fraction of requests with the given status. A route
+/// may have both a delay and an abort, e.g.
+/// `web.ns.svc.cluster.local:8080=list=delay:500ms:0.1,web.ns.svc.cluster.local:8080=list=abort:503:0.05`.
+///
+/// By default, faults are not injected.
+const ENV_OUTBOUND_HTTP_ROUTE_FAULTS: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_ROUTE_FAULTS";
+
+/// Configures outbound HTTP routes to route requests to backends by the value
+/// of a request header, as a comma-separated list of
+/// `name:port=route=header:value=backend:port` entries, where `route` is the
+/// name of one of the service's profile routes, e.g.
+/// `web.ns.svc.cluster.local:8080=list=x-variant:a=web-a.ns.svc.cluster.local:8080`.
+/// Each of a route's entries must use the same header. Requests without the
+/// header, or with other values, are distributed over the route's backends.
+///
+/// By default, requests are not routed by header.
+const ENV_OUTBOUND_HTTP_ROUTE_HEADER_BACKENDS: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_ROUTE_HEADER_BACKENDS";
+
+/// Configures outbound HTTP routes to rewrite the authority--and `Host`
+/// header--of their requests before they are dispatched, e.g. for virtually
+/// hosted upstreams, as a comma-separated list of `name:port=route=authority`
+/// entries, where `route` is the name of one of the service's profile routes,
+/// e.g. `web.ns.svc.cluster.local:8080=list=list.web.example.com`.
+///
+/// By default, authorities are not rewritten.
+const ENV_OUTBOUND_HTTP_ROUTE_AUTHORITY_REWRITES: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_ROUTE_AUTHORITY_REWRITES";
+
+/// Configures how outbound HTTP requests are handled when none of the backends
+/// a service's profile references are available, e.g. because they do not
+/// resolve to endpoints: `fail-fast` fails them, and `default` sends them to
+/// the service's default backend.
+///
+/// By default, requests fail fast.
+const ENV_OUTBOUND_HTTP_BACKEND_FALLBACK: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_BACKEND_FALLBACK";
+
+/// Configures how responses whose bodies exceed their route's limit are
+/// handled: `error` fails them with a 502 (or resets them, once their headers
+/// have been sent), and `truncate` truncates their bodies, marking them with an
+/// `l5d-response-truncated` header or trailer.
+///
+/// Defaults to `error`.
+const ENV_OUTBOUND_HTTP_ROUTE_RESPONSE_BODY_LIMIT_MODE: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_ROUTE_RESPONSE_BODY_LIMIT_MODE";
+
+/// Configures the maximum size, in bytes, of request bodies buffered so that
+/// outbound HTTP requests may be retried. Requests with larger bodies are not
+/// retried.
+const ENV_OUTBOUND_HTTP_RETRY_MAX_BUFFERED_BYTES: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RETRY_MAX_BUFFERED_BYTES";
+
+/// Configures the maximum size, in bytes, of request bodies buffered so that
+/// requests on individual outbound HTTP routes may be retried, as a
+/// comma-separated list of `name:port=route=bytes` entries, where `route` is
+/// the name of one of the service's profile routes.
+///
+/// By default, routes use the `LINKERD2_PROXY_OUTBOUND_HTTP_RETRY_MAX_BUFFERED_BYTES`
+/// limit.
+const ENV_OUTBOUND_HTTP_ROUTE_RETRY_MAX_BUFFERED_BYTES: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_ROUTE_RETRY_MAX_BUFFERED_BYTES";
+
+/// Configures a comma-separated list of HTTP response statuses (e.g. `425,429`)
+/// that are retried on outbound routes with a retry budget, in addition to the
+/// responses each route classifies as failures.
+///
+/// By default, only failures are retried.
+const ENV_OUTBOUND_HTTP_RETRYABLE_STATUSES: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RETRYABLE_STATUSES";
+
+/// Configures the HTTP response statuses that are retried on individual
+/// outbound routes with a retry budget, as a comma-separated list of
+/// `name:port=route=status` entries, where `route` is the name of one of the
+/// service's profile routes. A route may have several entries, e.g.
+/// `web.ns.svc.cluster.local:8080=list=425,web.ns.svc.cluster.local:8080=list=429`.
+/// A route's statuses replace those configured by
+/// `LINKERD2_PROXY_OUTBOUND_HTTP_RETRYABLE_STATUSES`.
+///
+/// By default, routes retry the `LINKERD2_PROXY_OUTBOUND_HTTP_RETRYABLE_STATUSES`
+/// statuses.
+const ENV_OUTBOUND_HTTP_ROUTE_RETRYABLE_STATUSES: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_ROUTE_RETRYABLE_STATUSES";
+
+/// Configures the minimum time a retried outbound HTTP request is expected to
+/// take. When set, requests on routes with a timeout are only retried if the
+/// time remaining before the timeout allows for another attempt that takes at
+/// least this long (or as long as the prior attempt, if that took longer).
+///
+/// By default, retries do not consider the route's timeout.
+const ENV_OUTBOUND_HTTP_RETRY_MIN_ATTEMPT_TIME: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RETRY_MIN_ATTEMPT_TIME";
+
+/// Configures the maximum time an outbound HTTP request waits before being
+/// retried when a 429 or 503 response includes a `Retry-After` header. Longer
+/// delays are clamped to this value.
+///
+/// By default, `Retry-After` headers are ignored and requests are retried
+/// immediately.
+const ENV_OUTBOUND_HTTP_RETRY_AFTER_MAX: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_RETRY_AFTER_MAX";
+
+/// Configures the name of a header (e.g. `idempotency-key`) that marks
+/// outbound HTTP requests as safe to retry. When set, only requests without
+/// bodies and requests that include the header are retried, regardless of
+/// their methods.
+///
+/// By default, requests are retried regardless of their bodies.
+const ENV_OUTBOUND_HTTP_RETRY_IDEMPOTENT_HEADER: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RETRY_IDEMPOTENT_HEADER";
+
+/// Configures how outbound HTTP requests are handled when the upstream resets
+/// the connection or stream before the response has been received:
+/// `propagate` returns the error to the client, and `retry-idempotent` retries
+/// requests with idempotent methods on routes with a retry budget.
+///
+/// By default, errors are propagated.
+const ENV_OUTBOUND_HTTP_RETRY_ON_RESET: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_RETRY_ON_RESET";
+
+/// Configures the time reserved before an outbound route's timeout for a
+/// failed attempt's response to be processed. When set, each attempt on a
+/// route with a timeout times out this long before the route's timeout would
+/// elapse.
+///
+/// By default, attempts are only bounded by the route's timeout.
+const ENV_OUTBOUND_HTTP_RETRY_TIMEOUT_HEADROOM: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RETRY_TIMEOUT_HEADROOM";
+
+/// Configures the HTTP statuses returned to non-gRPC clients for gRPC
+/// responses on individual outbound routes, as a comma-separated list of
+/// `name:port=route=grpc-status:http-status` entries, where `route` is the name
+/// of one of the service's profile routes. A route may have several entries,
+/// e.g. `web.ns.svc.cluster.local:8080=get=5:404,web.ns.svc.cluster.local:8080=get=14:503`.
+/// Only responses that carry their `grpc-status` in headers are mapped; a
+/// `grpc-status` in trailers arrives after the response's status was sent.
+///
+/// By default, statuses are not mapped.
+const ENV_OUTBOUND_HTTP_ROUTE_GRPC_STATUS_MAPPING: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_ROUTE_GRPC_STATUS_MAPPING";
+
+/// Restricts the response trailers forwarded to clients to those matching a
+/// comma-separated list of trailer name patterns, in which `*` matches any
+/// sequence of characters (e.g. `grpc-*`).
+///
+/// By default, all trailers are forwarded, except those that are denied.
+const ENV_OUTBOUND_HTTP_RESPONSE_TRAILERS_ALLOW: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RESPONSE_TRAILERS_ALLOW";
+
+/// Strips response trailers matching a comma-separated list of trailer name
+/// patterns, in which `*` matches any sequence of characters (e.g.
+/// `grpc-*-bin`), before responses are forwarded to clients.
+///
+/// By default, no trailers are stripped.
+const ENV_OUTBOUND_HTTP_RESPONSE_TRAILERS_DENY: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RESPONSE_TRAILERS_DENY";
+
+/// The size, in bytes, of the buffers used to copy data in each direction when
+/// forwarding opaque TCP streams.
+const ENV_FORWARD_BUFFER_SIZE: &str = "LINKERD2_PROXY_FORWARD_BUFFER_SIZE";
+
+/// Configures the duration after which forwarded opaque TCP streams are closed
+/// if no data is read in either direction.
+///
+/// By default, idle streams are not closed.
+const ENV_FORWARD_IDLE_TIMEOUT: &str = "LINKERD2_PROXY_FORWARD_IDLE_TIMEOUT";
+
+/// The maximum size, in bytes, of the read and write buffers used by HTTP/1
+/// client and server connections. By default, hyper's limits are used.
+const ENV_HTTP1_MAX_BUFFER_SIZE: &str = "LINKERD2_PROXY_HTTP1_MAX_BUFFER_SIZE";
+
+/// Configures whether, while the proxy is draining, responses on HTTP/1
+/// keep-alive server connections include a `Connection: close` header so that
+/// clients stop reusing them before they are closed.
+const ENV_HTTP1_CLOSE_ON_DRAIN: &str = "LINKERD2_PROXY_HTTP1_CLOSE_ON_DRAIN";
+
+/// Configures how HTTP/1 responses whose bodies are delimited by the server
+/// closing the connection--i.e. that have neither a `Content-Length` nor a
+/// chunked `Transfer-Encoding`--are framed to clients: `stream` streams them,
+/// `close` streams them and closes HTTP/1 client connections once they
+/// complete, and `buffer` buffers bodies of up to the max buffered bytes so
+/// that they are sent with a `Content-Length` (handling larger bodies as with
+/// `close`).
+///
+/// Defaults to `stream`, with up to 64KB buffered.
+const ENV_HTTP1_CLOSE_DELIMITED_RESPONSES: &str = "LINKERD2_PROXY_HTTP1_CLOSE_DELIMITED_RESPONSES";
+const ENV_HTTP1_CLOSE_DELIMITED_MAX_BUFFERED_BYTES: &str =
+    "LINKERD2_PROXY_HTTP1_CLOSE_DELIMITED_MAX_BUFFERED_BYTES";
+
+/// Configures how HTTP/1 server connections on which the client sends bytes
+/// that cannot be parsed as HTTP/1 are closed: `fail` fails the connection
+/// with the parse error, and `abort` closes it cleanly. Either way, the
+/// connection is counted by the `http_parse_errors_total` metric.
+///
+/// Defaults to `fail`.
+const ENV_HTTP1_PARSE_ERRORS: &str = "LINKERD2_PROXY_HTTP1_PARSE_ERRORS";
+
+/// Configures how inbound HTTP/1 server connections handle pipelined requests:
+/// `serialize` serves them in order, and `reject` fails them with a 503
+/// response with `Connection: close` and closes the connection.
+///
+/// Defaults to `serialize`.
+const ENV_INBOUND_HTTP1_PIPELINING: &str = "LINKERD2_PROXY_INBOUND_HTTP1_PIPELINING";
+
+/// Configures the maximum random delay before a control plane client attempts
+/// to reconnect to an endpoint whose connection failed, so that proxies do not
+/// all reconnect at once when a control plane component restarts.
+const ENV_CONTROL_RECONNECT_STAGGER: &str = "LINKERD2_PROXY_CONTROL_RECONNECT_STAGGER";
+
+/// Configures the number of consecutive failed reconnection attempts after
+/// which a control plane client's circuit opens. While the circuit is open,
+/// only a single attempt is made every `LINKERD2_PROXY_CONTROL_CIRCUIT_OPEN_TIMEOUT`.
+const ENV_CONTROL_CIRCUIT_FAILURE_THRESHOLD: &str =
+    "LINKERD2_PROXY_CONTROL_CIRCUIT_FAILURE_THRESHOLD";
+const ENV_CONTROL_CIRCUIT_OPEN_TIMEOUT: &str = "LINKERD2_PROXY_CONTROL_CIRCUIT_OPEN_TIMEOUT";
+
 pub const ENV_TRACE_ATTRIBUTES_PATH: &str = "LINKERD2_PROXY_TRACE_ATTRIBUTES_PATH";
 
 /// Constrains which destination names may be used for profile/route discovery.
@@ -159,6 +815,11 @@ pub const ENV_INBOUND_GATEWAY_SUFFIXES: &str = "LINKERD2_PROXY_INBOUND_GATEWAY_S
 pub const ENV_INBOUND_PORTS_DISABLE_PROTOCOL_DETECTION: &str =
     "LINKERD2_PROXY_INBOUND_PORTS_DISABLE_PROTOCOL_DETECTION";
 
+/// Configures ports on which inbound connections are handled as HTTP/2 without
+/// protocol detection, e.g. for ports known to serve gRPC. Only applies when
+/// policies are not discovered from the policy controller.
+pub const ENV_INBOUND_PORTS_FORCE_HTTP2: &str = "LINKERD2_PROXY_INBOUND_PORTS_FORCE_HTTP2";
+
 pub const ENV_INBOUND_PORTS_REQUIRE_IDENTITY: &str =
     "LINKERD2_PROXY_INBOUND_PORTS_REQUIRE_IDENTITY";
 
@@ -189,6 +850,29 @@ pub const ENV_IDENTITY_MAX_REFRESH: &str = "LINKERD2_PROXY_IDENTITY_MAX_REFRESH"
 
 pub const ENV_IDENTITY_SVC_BASE: &str = "LINKERD2_PROXY_IDENTITY_SVC";
 
+/// Pins the identities of servers, e.g. trusted upstreams with self-signed
+/// certificates, to specific certificates, as a comma-separated list of
+/// `name=fingerprint` entries. Each fingerprint is the hex-encoded SHA-256
+/// digest of the server's DER-encoded certificate, optionally with
+/// colon-separated bytes. Servers with pinned identities are verified by
+/// their certificates' fingerprints instead of against the trust anchors.
+///
+/// By default, no identities are pinned.
+pub const ENV_IDENTITY_PINNED_SERVER_CERTS: &str = "LINKERD2_PROXY_IDENTITY_PINNED_SERVER_CERTS";
+
+/// Configures a shadow identity, e.g. issued by a staging CA, that is presented
+/// in place of the proxy's identity on outbound connections to servers whose
+/// identities match one of a comma-separated list of suffixes. The shadow
+/// identity's directory must contain its private key, `key.p8`, and its
+/// certificate, `crt.der`, which is validated against its trust anchors.
+///
+/// By default, no shadow identity is presented.
+pub const ENV_IDENTITY_SHADOW_SERVER_SUFFIXES: &str =
+    "LINKERD2_PROXY_IDENTITY_SHADOW_SERVER_SUFFIXES";
+pub const ENV_IDENTITY_SHADOW_DIR: &str = "LINKERD2_PROXY_IDENTITY_SHADOW_DIR";
+pub const ENV_IDENTITY_SHADOW_TRUST_ANCHORS: &str = "LINKERD2_PROXY_IDENTITY_SHADOW_TRUST_ANCHORS";
+pub const ENV_IDENTITY_SHADOW_LOCAL_NAME: &str = "LINKERD2_PROXY_IDENTITY_SHADOW_LOCAL_NAME";
+
 pub const ENV_DESTINATION_SVC_BASE: &str = "LINKERD2_PROXY_DESTINATION_SVC";
 
 pub const ENV_HOSTNAME: &str = "HOSTNAME";
@@ -211,6 +895,13 @@ const ENV_DNS_MIN_TTL: &str = "LINKERD2_PROXY_DNS_MIN_TTL";
 /// Lookups with TTLs above this value will use this value instead.
 const ENV_DNS_MAX_TTL: &str = "LINKERD2_PROXY_DNS_MAX_TTL";
 
+/// Configures which address records are queried when resolving names to IP
+/// addresses: `ipv4-only`, `ipv6-only`, `both`, `prefer-ipv4`, or
+/// `prefer-ipv6`.
+///
+/// If unspecified, the system resolver's lookup strategy is used.
+const ENV_DNS_QUERY_STRATEGY: &str = "LINKERD2_PROXY_DNS_QUERY_STRATEGY";
+
 /// Configure the stream or connection level flow control setting for HTTP2.
 ///
 /// If unspecified, the default value of 65,535 is used.
@@ -218,13 +909,66 @@ const ENV_INITIAL_STREAM_WINDOW_SIZE: &str = "LINKERD2_PROXY_HTTP2_INITIAL_STREA
 const ENV_INITIAL_CONNECTION_WINDOW_SIZE: &str =
     "LINKERD2_PROXY_HTTP2_INITIAL_CONNECTION_WINDOW_SIZE";
 
+/// Configures the largest HTTP/2 frame payload, in bytes, that peers may send.
+/// Connections that send larger frames are closed with a protocol error.
+///
+/// If unspecified, the protocol default of 16,384 is used.
+const ENV_HTTP2_MAX_FRAME_SIZE: &str = "LINKERD2_PROXY_HTTP2_MAX_FRAME_SIZE";
+
+/// Configures the largest HTTP/2 header block, in bytes, that inbound clients
+/// may send across a HEADERS frame and its CONTINUATION frames. Connections
+/// that send larger header blocks (e.g. by flooding the connection with
+/// CONTINUATION frames) are closed.
+///
+/// If unspecified, header blocks are limited to 1MB.
+const ENV_INBOUND_HTTP2_MAX_HEADER_BLOCK_SIZE: &str =
+    "LINKERD2_PROXY_INBOUND_HTTP2_MAX_HEADER_BLOCK_SIZE";
+
+/// Configures the most HTTP/2 WINDOW_UPDATE frames that inbound clients may
+/// send per second. Connections that send more (e.g. by flooding the
+/// connection with WINDOW_UPDATE frames) are closed.
+///
+/// By default, WINDOW_UPDATE frames are not limited.
+const ENV_INBOUND_HTTP2_MAX_WINDOW_UPDATES_PER_SECOND: &str =
+    "LINKERD2_PROXY_INBOUND_HTTP2_MAX_WINDOW_UPDATES_PER_SECOND";
+
 const ENV_INBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT: &str =
     "LINKERD2_PROXY_INBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT";
 const ENV_OUTBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT: &str =
     "LINKERD2_PROXY_OUTBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT";
 
+/// Configures the maximum number of HTTP/2 connections the outbound proxy may
+/// open to each endpoint. Additional connections are only opened when every
+/// connection to the endpoint has `ENV_OUTBOUND_HTTP2_MAX_CONCURRENT_STREAMS`
+/// streams in flight.
+///
+/// If unspecified, all requests to an endpoint are multiplexed over a single
+/// connection.
+const ENV_OUTBOUND_HTTP2_MAX_CONNECTIONS_PER_ENDPOINT: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP2_MAX_CONNECTIONS_PER_ENDPOINT";
+
+/// Configures the number of in-flight streams at which an outbound HTTP/2
+/// connection is considered saturated.
+///
+/// If unspecified, the default value of 100 is used.
+const ENV_OUTBOUND_HTTP2_MAX_CONCURRENT_STREAMS: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP2_MAX_CONCURRENT_STREAMS";
+
 const ENV_SHUTDOWN_GRACE_PERIOD: &str = "LINKERD2_PROXY_SHUTDOWN_GRACE_PERIOD";
 
+/// Determines how connections that are accepted before the proxy's identity is
+/// certified are handled: `hold` connections until the proxy is ready, for up
+/// to `LINKERD2_PROXY_STARTUP_HOLD_TIMEOUT`, or `fail` them.
+///
+/// By default, connections are not accepted until the proxy is ready.
+const ENV_STARTUP_MODE: &str = "LINKERD2_PROXY_STARTUP_MODE";
+
+/// The maximum time for which connections are held before the proxy is ready,
+/// when `LINKERD2_PROXY_STARTUP_MODE` is `hold`.
+///
+/// If unspecified, the default value of 10s is used.
+const ENV_STARTUP_HOLD_TIMEOUT: &str = "LINKERD2_PROXY_STARTUP_HOLD_TIMEOUT";
+
 // Default values for various configuration fields
 const DEFAULT_OUTBOUND_LISTEN_ADDR: &str = "127.0.0.1:4140";
 pub const DEFAULT_INBOUND_LISTEN_ADDR: &str = "0.0.0.0:4143";
@@ -233,8 +977,16 @@ pub const DEFAULT_CONTROL_LISTEN_ADDR: &str = "0.0.0.0:4190";
 const DEFAULT_ADMIN_LISTEN_ADDR: &str = "127.0.0.1:4191";
 const DEFAULT_METRICS_RETAIN_IDLE: Duration = Duration::from_secs(10 * 60);
 
+const DEFAULT_FORWARD_BUFFER_SIZE: usize = 8 * 1024;
+const DEFAULT_HTTP1_CLOSE_DELIMITED_MAX_BUFFERED_BYTES: usize = 64 * 1024;
+
+// Hyper does not support HTTP/1 buffers smaller than 8KB, so we enforce the same
+// lower bound for all configurable IO buffers.
+const MIN_BUFFER_SIZE: usize = 8 * 1024;
+
 const DEFAULT_INBOUND_HTTP_QUEUE_CAPACITY: usize = 100;
 const DEFAULT_INBOUND_HTTP_FAILFAST_TIMEOUT: Duration = Duration::from_secs(1);
+const DEFAULT_INBOUND_HTTP_CONCURRENCY_LIMIT_QUEUE_TIMEOUT: Duration = Duration::from_secs(1);
 const DEFAULT_INBOUND_DETECT_TIMEOUT: Duration = Duration::from_secs(10);
 const DEFAULT_INBOUND_CONNECT_TIMEOUT: Duration = Duration::from_millis(300);
 const DEFAULT_INBOUND_CONNECT_BACKOFF: ExponentialBackoff =
@@ -244,6 +996,19 @@ const DEFAULT_OUTBOUND_TCP_QUEUE_CAPACITY: usize = 10;
 const DEFAULT_OUTBOUND_TCP_FAILFAST_TIMEOUT: Duration = Duration::from_secs(3);
 const DEFAULT_OUTBOUND_HTTP_QUEUE_CAPACITY: usize = 100;
 const DEFAULT_OUTBOUND_HTTP_FAILFAST_TIMEOUT: Duration = Duration::from_secs(3);
+const DEFAULT_OUTBOUND_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const DEFAULT_OUTBOUND_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+const DEFAULT_OUTBOUND_HEALTH_CHECK_HEALTHY_THRESHOLD: u32 = 2;
+const DEFAULT_OUTBOUND_HEALTH_CHECK_UNHEALTHY_THRESHOLD: u32 = 3;
+const DEFAULT_OUTBOUND_LATENCY_OUTLIER_MAX_EJECTION_PERCENT: u32 = 10;
+const DEFAULT_OUTBOUND_LATENCY_OUTLIER_INTERVAL: Duration = Duration::from_secs(10);
+const DEFAULT_OUTBOUND_LATENCY_OUTLIER_EJECTION_TIME: Duration = Duration::from_secs(30);
+const DEFAULT_OUTBOUND_LATENCY_OUTLIER_MIN_REQUESTS: usize = 20;
+const DEFAULT_OUTBOUND_LATENCY_OUTLIER_MAX_EJECTION_TIME: Duration = Duration::from_secs(300);
+const DEFAULT_OUTBOUND_HTTP_RESPONSE_CACHE_MAX_TTL: Duration = Duration::from_secs(60);
+const DEFAULT_OUTBOUND_HTTP_RESPONSE_CACHE_MAX_BODY_BYTES: usize = 64 * 1024;
+const DEFAULT_OUTBOUND_HTTP_REQUEST_COALESCING_MAX_BODY_BYTES: usize = 1024 * 1024;
+const DEFAULT_OUTBOUND_HTTP_RETRY_MAX_BUFFERED_BYTES: usize = 64 * 1024;
 const DEFAULT_OUTBOUND_DETECT_TIMEOUT: Duration = Duration::from_secs(10);
 const DEFAULT_OUTBOUND_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
 const DEFAULT_OUTBOUND_CONNECT_BACKOFF: ExponentialBackoff =
@@ -251,6 +1016,9 @@ const DEFAULT_OUTBOUND_CONNECT_BACKOFF: ExponentialBackoff =
 
 const DEFAULT_CONTROL_QUEUE_CAPACITY: usize = 100;
 const DEFAULT_CONTROL_FAILFAST_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_CONTROL_RECONNECT_STAGGER: Duration = Duration::from_secs(1);
+const DEFAULT_CONTROL_CIRCUIT_FAILURE_THRESHOLD: u32 = 10;
+const DEFAULT_CONTROL_CIRCUIT_OPEN_TIMEOUT: Duration = Duration::from_secs(5);
 
 const DEFAULT_RESOLV_CONF: &str = "/etc/resolv.conf";
 
@@ -259,6 +1027,7 @@ const DEFAULT_INITIAL_CONNECTION_WINDOW_SIZE: u32 = 1048576; // 1MB ~ 16 streams
 
 // 2 minutes seems like a reasonable amount of time to wait for connections to close...
 const DEFAULT_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(2 * 60);
+const DEFAULT_STARTUP_HOLD_TIMEOUT: Duration = Duration::from_secs(10);
 
 // This configuration limits the amount of time Linkerd retains cached clients &
 // connections for a given destination ip:port, as referenced by the application
@@ -272,6 +1041,62 @@ const DEFAULT_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(2 * 60);
 const ENV_OUTBOUND_DISCOVERY_IDLE_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_DISCOVERY_IDLE_TIMEOUT";
 const DEFAULT_OUTBOUND_DISCOVERY_IDLE_TIMEOUT: Duration = Duration::from_secs(5);
 
+// Configures the maximum amount of time by which each outbound discovery
+// result's idle timeout is randomly extended, so that results created together
+// (e.g. when an application starts) don't all idle out and re-resolve at once.
+// By default, idle timeouts are not jittered.
+const ENV_OUTBOUND_DISCOVERY_IDLE_JITTER: &str = "LINKERD2_PROXY_OUTBOUND_DISCOVERY_IDLE_JITTER";
+
+// Configures the maximum amount of time an outbound discovery result is cached,
+// even while it is in use, so that actively-used destinations periodically
+// re-resolve their profiles. New connections use the new result while existing
+// connections keep the prior one. By default, results are cached until they
+// idle out.
+const ENV_OUTBOUND_DISCOVERY_MAX_LIFETIME: &str = "LINKERD2_PROXY_OUTBOUND_DISCOVERY_MAX_LIFETIME";
+
+// Configures endpoint labels, as a comma-separated list of `key=value` entries
+// (e.g. `draining=true`), that exclude resolved endpoints from outbound load
+// balancers. By default, no endpoints are excluded.
+const ENV_OUTBOUND_ENDPOINT_EXCLUSIONS: &str = "LINKERD2_PROXY_OUTBOUND_ENDPOINT_EXCLUSIONS";
+
+// Configures the maximum time a resolved endpoint remains in outbound load
+// balancers without being refreshed by its resolution. By default, endpoints
+// are not evicted while their resolution is silent.
+const ENV_OUTBOUND_ENDPOINT_MAX_AGE: &str = "LINKERD2_PROXY_OUTBOUND_ENDPOINT_MAX_AGE";
+
+// Configures what happens once all of a resolution's endpoints exceed the
+// maximum age: `evict` evicts them, and `retain` retains them until the
+// resolution is updated. Defaults to `evict`.
+const ENV_OUTBOUND_ENDPOINT_STALE_FALLBACK: &str =
+    "LINKERD2_PROXY_OUTBOUND_ENDPOINT_STALE_FALLBACK";
+
+// Configures a window for which endpoint removals are deferred, so that
+// outbound load balancers are not disturbed by transient partial or empty
+// endpoint sets while services scale. Endpoints that are re-added within the
+// window are never removed. By default, removals take effect immediately.
+const ENV_OUTBOUND_ENDPOINT_STABILIZATION_WINDOW: &str =
+    "LINKERD2_PROXY_OUTBOUND_ENDPOINT_STABILIZATION_WINDOW";
+
+// Configures the local addresses to which outbound connections are bound, as a
+// comma-separated list of `network=address` entries (e.g.
+// `10.0.0.0/8=10.1.2.3`). Each connection is bound to the address of the first
+// entry whose network contains the endpoint's address. By default, the
+// operating system chooses each connection's source address.
+const ENV_OUTBOUND_CONNECT_SOURCE_ADDRS: &str = "LINKERD2_PROXY_OUTBOUND_CONNECT_SOURCE_ADDRS";
+
+// Configures TCP keepalive probes on outbound opaque connections, so that
+// long-lived connections (e.g. to databases) whose peer silently stops
+// responding are closed. Probes are enabled when the keepalive time--the idle
+// time before probes are sent--is set; the interval between probes and the
+// number of unacknowledged probes after which a connection is closed default
+// to the operating system's settings. By default, opaque connections use the
+// outbound connect keepalive.
+const ENV_OUTBOUND_OPAQUE_KEEPALIVE_TIME: &str = "LINKERD2_PROXY_OUTBOUND_OPAQUE_KEEPALIVE_TIME";
+const ENV_OUTBOUND_OPAQUE_KEEPALIVE_INTERVAL: &str =
+    "LINKERD2_PROXY_OUTBOUND_OPAQUE_KEEPALIVE_INTERVAL";
+const ENV_OUTBOUND_OPAQUE_KEEPALIVE_RETRIES: &str =
+    "LINKERD2_PROXY_OUTBOUND_OPAQUE_KEEPALIVE_RETRIES";
+
 // On the inbound side, we may lookup per-port policy or per-service profile
 // configuration. We are more permissive in retaining inbound configuration,
 // because we expect this to be a generally lower-cardinality set of
@@ -286,6 +1111,10 @@ const DEFAULT_INBOUND_DISCOVERY_IDLE_TIMEOUT: Duration = Duration::from_secs(90)
 // TODO(ver) this should be made configurable per-server from the proxy API.
 const DEFAULT_INBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(3);
 
+// Legitimate header blocks are far smaller than this, since their decoded
+// size is limited by the HTTP/2 implementation.
+const DEFAULT_INBOUND_HTTP2_MAX_HEADER_BLOCK_SIZE: u32 = 1024 * 1024;
+
 // TODO(ver) This should be configurable at the load balancer level.
 const DEFAULT_OUTBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(3);
 
@@ -321,6 +1150,11 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
 
     let inbound_detect_timeout = parse(strings, ENV_INBOUND_DETECT_TIMEOUT, parse_duration);
     let inbound_connect_timeout = parse(strings, ENV_INBOUND_CONNECT_TIMEOUT, parse_duration);
+    let inbound_connect_backoff_max_elapsed = parse(
+        strings,
+        ENV_INBOUND_CONNECT_BACKOFF_MAX_ELAPSED,
+        parse_duration,
+    );
     let inbound_http_queue_capacity = parse(strings, ENV_INBOUND_HTTP_QUEUE_CAPACITY, parse_number);
     let inbound_http_failfast_timeout =
         parse(strings, ENV_INBOUND_HTTP_FAILFAST_TIMEOUT, parse_duration);
@@ -334,6 +1168,13 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
     let outbound_http_failfast_timeout =
         parse(strings, ENV_OUTBOUND_HTTP_FAILFAST_TIMEOUT, parse_duration);
     let outbound_connect_timeout = parse(strings, ENV_OUTBOUND_CONNECT_TIMEOUT, parse_duration);
+    let outbound_tls_handshake_timeout =
+        parse(strings, ENV_OUTBOUND_TLS_HANDSHAKE_TIMEOUT, parse_duration);
+    let outbound_connect_backoff_max_elapsed = parse(
+        strings,
+        ENV_OUTBOUND_CONNECT_BACKOFF_MAX_ELAPSED,
+        parse_duration,
+    );
 
     let inbound_accept_keepalive = parse(strings, ENV_INBOUND_ACCEPT_KEEPALIVE, parse_duration);
     let outbound_accept_keepalive = parse(strings, ENV_OUTBOUND_ACCEPT_KEEPALIVE, parse_duration);
@@ -342,17 +1183,54 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
     let outbound_connect_keepalive = parse(strings, ENV_OUTBOUND_CONNECT_KEEPALIVE, parse_duration);
 
     let shutdown_grace_period = parse(strings, ENV_SHUTDOWN_GRACE_PERIOD, parse_duration);
+    let startup_mode = parse(strings, ENV_STARTUP_MODE, parse_startup_mode);
+    let startup_hold_timeout = parse(strings, ENV_STARTUP_HOLD_TIMEOUT, parse_duration);
 
     let inbound_disable_ports = parse(
         strings,
         ENV_INBOUND_PORTS_DISABLE_PROTOCOL_DETECTION,
         parse_port_set,
     );
+    let inbound_http2_ports = parse(strings, ENV_INBOUND_PORTS_FORCE_HTTP2, parse_port_set);
 
     let inbound_discovery_idle_timeout =
         parse(strings, ENV_INBOUND_DISCOVERY_IDLE_TIMEOUT, parse_duration);
     let outbound_discovery_idle_timeout =
         parse(strings, ENV_OUTBOUND_DISCOVERY_IDLE_TIMEOUT, parse_duration);
+    let outbound_discovery_idle_jitter =
+        parse(strings, ENV_OUTBOUND_DISCOVERY_IDLE_JITTER, parse_duration);
+    let outbound_discovery_max_lifetime =
+        parse(strings, ENV_OUTBOUND_DISCOVERY_MAX_LIFETIME, parse_duration);
+    let outbound_endpoint_exclusions = parse(
+        strings,
+        ENV_OUTBOUND_ENDPOINT_EXCLUSIONS,
+        parse_endpoint_exclusions,
+    );
+    let outbound_endpoint_max_age = parse(strings, ENV_OUTBOUND_ENDPOINT_MAX_AGE, parse_duration);
+    let outbound_endpoint_stabilization_window = parse(
+        strings,
+        ENV_OUTBOUND_ENDPOINT_STABILIZATION_WINDOW,
+        parse_duration,
+    );
+    let outbound_endpoint_stale_fallback = parse(
+        strings,
+        ENV_OUTBOUND_ENDPOINT_STALE_FALLBACK,
+        parse_stale_endpoint_fallback,
+    );
+    let outbound_connect_source_addrs = parse(
+        strings,
+        ENV_OUTBOUND_CONNECT_SOURCE_ADDRS,
+        parse_source_addrs,
+    );
+    let outbound_opaque_keepalive_time =
+        parse(strings, ENV_OUTBOUND_OPAQUE_KEEPALIVE_TIME, parse_duration);
+    let outbound_opaque_keepalive_interval = parse(
+        strings,
+        ENV_OUTBOUND_OPAQUE_KEEPALIVE_INTERVAL,
+        parse_duration,
+    );
+    let outbound_opaque_keepalive_retries =
+        parse(strings, ENV_OUTBOUND_OPAQUE_KEEPALIVE_RETRIES, parse_number);
 
     let inbound_max_idle_per_endpoint = parse(
         strings,
@@ -369,6 +1247,340 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
     let outbound_max_in_flight = parse(strings, ENV_OUTBOUND_MAX_IN_FLIGHT, parse_number);
 
     let metrics_retain_idle = parse(strings, ENV_METRICS_RETAIN_IDLE, parse_duration);
+    let metrics_latency_sample_rate =
+        parse(strings, ENV_METRICS_LATENCY_SAMPLE_RATE, parse_sample_rate);
+
+    let inbound_json_error_bodies = parse(strings, ENV_INBOUND_JSON_ERROR_BODIES, parse_bool);
+    let outbound_json_error_bodies = parse(strings, ENV_OUTBOUND_JSON_ERROR_BODIES, parse_bool);
+    let outbound_grpc_web_errors = parse(strings, ENV_OUTBOUND_GRPC_WEB_ERRORS, parse_bool);
+    let outbound_http_queue_fair = parse(strings, ENV_OUTBOUND_HTTP_QUEUE_FAIR, parse_bool);
+
+    let inbound_http1_require_host = parse(strings, ENV_INBOUND_HTTP1_REQUIRE_HOST, parse_bool);
+    let outbound_http1_require_host = parse(strings, ENV_OUTBOUND_HTTP1_REQUIRE_HOST, parse_bool);
+    let inbound_http1_reject_ambiguous_framing = parse(
+        strings,
+        ENV_INBOUND_HTTP1_REJECT_AMBIGUOUS_FRAMING,
+        parse_bool,
+    );
+    let outbound_http1_reject_ambiguous_framing = parse(
+        strings,
+        ENV_OUTBOUND_HTTP1_REJECT_AMBIGUOUS_FRAMING,
+        parse_bool,
+    );
+    let inbound_http_max_header_value_bytes = parse(
+        strings,
+        ENV_INBOUND_HTTP_MAX_HEADER_VALUE_LENGTH,
+        parse_number,
+    );
+    let outbound_http_max_header_value_bytes = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_MAX_HEADER_VALUE_LENGTH,
+        parse_number,
+    );
+    let inbound_http1_transfer_encoding = parse(
+        strings,
+        ENV_INBOUND_HTTP1_TRANSFER_ENCODING,
+        parse_transfer_encoding_mode,
+    );
+    let outbound_http1_transfer_encoding = parse(
+        strings,
+        ENV_OUTBOUND_HTTP1_TRANSFER_ENCODING,
+        parse_transfer_encoding_mode,
+    );
+    let inbound_http1_bridge_http10 = parse(strings, ENV_INBOUND_HTTP1_BRIDGE_HTTP10, parse_bool);
+    let inbound_access_log_error_body_bytes = parse(
+        strings,
+        ENV_INBOUND_ACCESS_LOG_ERROR_BODY_BYTES,
+        parse_number::<usize>,
+    );
+    let inbound_http1_connect_mode =
+        parse(strings, ENV_INBOUND_HTTP1_CONNECT_MODE, parse_connect_mode);
+    let inbound_dst_port_header = parse(strings, ENV_INBOUND_DST_PORT_HEADER, parse_bool);
+    let inbound_http_route_allowed_methods = parse(
+        strings,
+        ENV_INBOUND_HTTP_ROUTE_ALLOWED_METHODS,
+        parse_route_allowed_methods,
+    );
+    let inbound_http_route_request_body_limits = parse(
+        strings,
+        ENV_INBOUND_HTTP_ROUTE_REQUEST_BODY_LIMITS,
+        parse_route_request_body_limits,
+    );
+    let inbound_http_route_request_content_types = parse(
+        strings,
+        ENV_INBOUND_HTTP_ROUTE_REQUEST_CONTENT_TYPES,
+        parse_route_request_content_types,
+    );
+    let inbound_app_connect_grace = parse(strings, ENV_INBOUND_APP_CONNECT_GRACE, parse_duration);
+    let inbound_max_connection_lifetime =
+        parse(strings, ENV_INBOUND_MAX_CONNECTION_LIFETIME, parse_duration);
+    let inbound_http_request_body_timeout = parse(
+        strings,
+        ENV_INBOUND_HTTP_REQUEST_BODY_TIMEOUT,
+        parse_duration,
+    );
+    let inbound_tls_sni_ports = parse(strings, ENV_INBOUND_TLS_SNI_PORTS, parse_sni_ports);
+    let inbound_concurrency_limit_mode = parse(
+        strings,
+        ENV_INBOUND_HTTP_CONCURRENCY_LIMIT_MODE,
+        parse_concurrency_limit_mode,
+    );
+    let inbound_concurrency_limit_queue_timeout = parse(
+        strings,
+        ENV_INBOUND_HTTP_CONCURRENCY_LIMIT_QUEUE_TIMEOUT,
+        parse_duration,
+    );
+
+    let outbound_route_latency_slo = parse(strings, ENV_OUTBOUND_ROUTE_LATENCY_SLO, parse_duration);
+    let outbound_http_route_latency_slos = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_ROUTE_LATENCY_SLOS,
+        parse_route_latency_slos,
+    );
+    let outbound_log_route_slo_breaches =
+        parse(strings, ENV_OUTBOUND_LOG_ROUTE_SLO_BREACHES, parse_bool);
+    let outbound_route_availability_window = parse(
+        strings,
+        ENV_OUTBOUND_ROUTE_AVAILABILITY_WINDOW,
+        parse_duration,
+    );
+    let outbound_route_payload_size_buckets = parse(
+        strings,
+        ENV_OUTBOUND_ROUTE_PAYLOAD_SIZE_BUCKETS,
+        parse_payload_size_buckets,
+    );
+
+    let outbound_health_check_path =
+        parse(strings, ENV_OUTBOUND_HEALTH_CHECK_PATH, parse_http_path);
+    let outbound_health_check_interval = parse(
+        strings,
+        ENV_OUTBOUND_HEALTH_CHECK_INTERVAL,
+        parse_nonzero_duration,
+    );
+    let outbound_health_check_timeout = parse(
+        strings,
+        ENV_OUTBOUND_HEALTH_CHECK_TIMEOUT,
+        parse_nonzero_duration,
+    );
+    let outbound_health_check_healthy_threshold = parse(
+        strings,
+        ENV_OUTBOUND_HEALTH_CHECK_HEALTHY_THRESHOLD,
+        parse_number::<u32>,
+    );
+    let outbound_health_check_unhealthy_threshold = parse(
+        strings,
+        ENV_OUTBOUND_HEALTH_CHECK_UNHEALTHY_THRESHOLD,
+        parse_number::<u32>,
+    );
+
+    let outbound_latency_outlier_multiple = parse(
+        strings,
+        ENV_OUTBOUND_LATENCY_OUTLIER_MULTIPLE,
+        parse_latency_multiple,
+    );
+    let outbound_latency_outlier_max_ejection_percent = parse(
+        strings,
+        ENV_OUTBOUND_LATENCY_OUTLIER_MAX_EJECTION_PERCENT,
+        parse_number::<u32>,
+    );
+    let outbound_latency_outlier_interval = parse(
+        strings,
+        ENV_OUTBOUND_LATENCY_OUTLIER_INTERVAL,
+        parse_duration,
+    );
+    let outbound_latency_outlier_ejection_time = parse(
+        strings,
+        ENV_OUTBOUND_LATENCY_OUTLIER_EJECTION_TIME,
+        parse_duration,
+    );
+    let outbound_latency_outlier_min_requests = parse(
+        strings,
+        ENV_OUTBOUND_LATENCY_OUTLIER_MIN_REQUESTS,
+        parse_number::<usize>,
+    );
+    let outbound_latency_outlier_ejection_multiplier = parse(
+        strings,
+        ENV_OUTBOUND_LATENCY_OUTLIER_EJECTION_MULTIPLIER,
+        parse_ejection_multiplier,
+    );
+    let outbound_latency_outlier_max_ejection_time = parse(
+        strings,
+        ENV_OUTBOUND_LATENCY_OUTLIER_MAX_EJECTION_TIME,
+        parse_duration,
+    );
+    let outbound_latency_outlier_backend_ejections = parse(
+        strings,
+        ENV_OUTBOUND_LATENCY_OUTLIER_BACKEND_EJECTIONS,
+        parse_backend_ejections,
+    );
+
+    let outbound_backend_max_connections = parse(
+        strings,
+        ENV_OUTBOUND_BACKEND_MAX_CONNECTIONS,
+        parse_number::<usize>,
+    );
+    let outbound_backend_connection_limit_mode = parse(
+        strings,
+        ENV_OUTBOUND_BACKEND_CONNECTION_LIMIT_MODE,
+        parse_connection_limit_mode,
+    );
+
+    let outbound_http_response_cache_max_entries = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RESPONSE_CACHE_MAX_ENTRIES,
+        parse_number::<usize>,
+    );
+    let outbound_http_response_cache_max_ttl = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RESPONSE_CACHE_MAX_TTL,
+        parse_duration,
+    );
+    let outbound_http_response_cache_max_body_bytes = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RESPONSE_CACHE_MAX_BODY_BYTES,
+        parse_number::<usize>,
+    );
+    let outbound_http_request_coalescing =
+        parse(strings, ENV_OUTBOUND_HTTP_REQUEST_COALESCING, parse_bool);
+    let outbound_http_request_coalescing_key_headers = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_REQUEST_COALESCING_KEY_HEADERS,
+        parse_header_names,
+    );
+    let outbound_http_request_coalescing_max_body_bytes = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_REQUEST_COALESCING_MAX_BODY_BYTES,
+        parse_number::<usize>,
+    );
+
+    let outbound_http_backend_protocols = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_BACKEND_PROTOCOLS,
+        parse_backend_protocols,
+    );
+    let outbound_http_backend_balancers = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_BACKEND_BALANCERS,
+        parse_backend_balancers,
+    );
+    let outbound_http_mtls_required_routes = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_MTLS_REQUIRED_ROUTES,
+        parse_mtls_required_routes,
+    );
+    let outbound_http_route_response_body_limits = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_ROUTE_RESPONSE_BODY_LIMITS,
+        parse_route_response_body_limits,
+    );
+    let outbound_http_route_priorities = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_ROUTE_PRIORITIES,
+        parse_route_priorities,
+    );
+    let outbound_http_route_faults =
+        parse(strings, ENV_OUTBOUND_HTTP_ROUTE_FAULTS, parse_route_faults);
+    let outbound_http_route_header_backends = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_ROUTE_HEADER_BACKENDS,
+        parse_route_header_backends,
+    );
+    let outbound_http_route_authority_rewrites = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_ROUTE_AUTHORITY_REWRITES,
+        parse_route_authority_rewrites,
+    );
+    let outbound_http_backend_fallback = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_BACKEND_FALLBACK,
+        parse_backend_fallback,
+    );
+    let outbound_http_route_response_body_limit_mode = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_ROUTE_RESPONSE_BODY_LIMIT_MODE,
+        parse_response_body_limit_mode,
+    );
+    let outbound_http_retry_max_buffered_bytes = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RETRY_MAX_BUFFERED_BYTES,
+        parse_number::<usize>,
+    );
+    let outbound_http_route_retry_max_buffered_bytes = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_ROUTE_RETRY_MAX_BUFFERED_BYTES,
+        parse_route_retry_max_buffered_bytes,
+    );
+    let outbound_http_retryable_statuses = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RETRYABLE_STATUSES,
+        parse_status_set,
+    );
+    let outbound_http_route_retryable_statuses = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_ROUTE_RETRYABLE_STATUSES,
+        parse_route_retryable_statuses,
+    );
+    let outbound_http_retry_min_attempt_time = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RETRY_MIN_ATTEMPT_TIME,
+        parse_duration,
+    );
+    let outbound_http_retry_after_max =
+        parse(strings, ENV_OUTBOUND_HTTP_RETRY_AFTER_MAX, parse_duration);
+    let outbound_http_retry_idempotent_header = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RETRY_IDEMPOTENT_HEADER,
+        parse_header_name,
+    );
+    let outbound_http_retry_on_reset = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RETRY_ON_RESET,
+        parse_reset_behavior,
+    );
+    let outbound_http_retry_timeout_headroom = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RETRY_TIMEOUT_HEADROOM,
+        parse_duration,
+    );
+    let outbound_http_route_grpc_status_mappings = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_ROUTE_GRPC_STATUS_MAPPING,
+        parse_route_grpc_status_mappings,
+    );
+    let outbound_http_response_trailers_allow = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RESPONSE_TRAILERS_ALLOW,
+        parse_trailer_patterns,
+    );
+    let outbound_http_response_trailers_deny = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RESPONSE_TRAILERS_DENY,
+        parse_trailer_patterns,
+    );
+
+    let forward_buffer_capacity = parse(strings, ENV_FORWARD_BUFFER_SIZE, parse_buffer_size)?
+        .unwrap_or(DEFAULT_FORWARD_BUFFER_SIZE);
+    let forward_idle_timeout = parse(strings, ENV_FORWARD_IDLE_TIMEOUT, parse_duration)?;
+    let h1_max_buf_size = parse(strings, ENV_HTTP1_MAX_BUFFER_SIZE, parse_buffer_size)?;
+    let h1_close_delimited = {
+        let max_bytes = parse(
+            strings,
+            ENV_HTTP1_CLOSE_DELIMITED_MAX_BUFFERED_BYTES,
+            parse_number::<usize>,
+        )?
+        .unwrap_or(DEFAULT_HTTP1_CLOSE_DELIMITED_MAX_BUFFERED_BYTES);
+        parse(strings, ENV_HTTP1_CLOSE_DELIMITED_RESPONSES, move |s| {
+            parse_close_delimited_responses(s, max_bytes)
+        })?
+        .unwrap_or_default()
+    };
+    let h1_server_settings = h1::ServerSettings {
+        max_buf_size: h1_max_buf_size,
+        close_on_drain: parse(strings, ENV_HTTP1_CLOSE_ON_DRAIN, parse_bool)?.unwrap_or(false),
+        pipelining: h1::Pipelining::default(),
+        parse_errors: parse(strings, ENV_HTTP1_PARSE_ERRORS, parse_parse_errors)?
+            .unwrap_or_default(),
+    };
 
     // DNS
 
@@ -376,8 +1588,15 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
 
     let dns_min_ttl = parse(strings, ENV_DNS_MIN_TTL, parse_duration);
     let dns_max_ttl = parse(strings, ENV_DNS_MAX_TTL, parse_duration);
+    let dns_query_strategy = parse(strings, ENV_DNS_QUERY_STRATEGY, parse_dns_query_strategy);
 
     let identity_config = parse_identity_config(strings);
+    let shadow_identity_config = parse_shadow_identity_config(strings);
+    let pinned_certs = parse(
+        strings,
+        ENV_IDENTITY_PINNED_SERVER_CERTS,
+        parse_pinned_certs,
+    );
 
     let hostname = strings.get(ENV_HOSTNAME);
 
@@ -387,6 +1606,20 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
 
     let gateway_suffixes = parse(strings, ENV_INBOUND_GATEWAY_SUFFIXES, parse_dns_suffixes);
 
+    let control_circuit = CircuitConfig {
+        stagger: parse(strings, ENV_CONTROL_RECONNECT_STAGGER, parse_duration)?
+            .unwrap_or(DEFAULT_CONTROL_RECONNECT_STAGGER),
+        failure_threshold: parse(
+            strings,
+            ENV_CONTROL_CIRCUIT_FAILURE_THRESHOLD,
+            parse_number::<u32>,
+        )?
+        .unwrap_or(DEFAULT_CONTROL_CIRCUIT_FAILURE_THRESHOLD)
+        .max(1),
+        open_timeout: parse(strings, ENV_CONTROL_CIRCUIT_OPEN_TIMEOUT, parse_duration)?
+            .unwrap_or(DEFAULT_CONTROL_CIRCUIT_OPEN_TIMEOUT),
+    };
+
     let dst_addr = parse_control_addr(strings, ENV_DESTINATION_SVC_BASE);
     let dst_token = strings.get(ENV_DESTINATION_CONTEXT);
     let dst_profile_skip_timeout = parse(
@@ -404,6 +1637,18 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
     let initial_stream_window_size = parse(strings, ENV_INITIAL_STREAM_WINDOW_SIZE, parse_number);
     let initial_connection_window_size =
         parse(strings, ENV_INITIAL_CONNECTION_WINDOW_SIZE, parse_number);
+    let h2_max_frame_size = parse(strings, ENV_HTTP2_MAX_FRAME_SIZE, parse_h2_max_frame_size);
+    let inbound_h1_pipelining = parse(strings, ENV_INBOUND_HTTP1_PIPELINING, parse_pipelining);
+    let inbound_h2_max_header_block_size = parse(
+        strings,
+        ENV_INBOUND_HTTP2_MAX_HEADER_BLOCK_SIZE,
+        parse_number,
+    );
+    let inbound_h2_max_window_updates_per_second = parse(
+        strings,
+        ENV_INBOUND_HTTP2_MAX_WINDOW_UPDATES_PER_SECOND,
+        parse_number,
+    );
 
     let tap = parse_tap_config(strings);
 
@@ -414,6 +1659,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         initial_connection_window_size: Some(
             initial_connection_window_size?.unwrap_or(DEFAULT_INITIAL_CONNECTION_WINDOW_SIZE),
         ),
+        max_frame_size: h2_max_frame_size?,
         ..Default::default()
     };
 
@@ -454,6 +1700,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         let server = ServerConfig {
             addr,
             keepalive,
+            h1_settings: h1_server_settings,
             h2_settings,
         };
         let discovery_idle_timeout =
@@ -467,6 +1714,26 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
             parse_duration,
         )?;
 
+        let h2_pool = {
+            let max_connections = parse(
+                strings,
+                ENV_OUTBOUND_HTTP2_MAX_CONNECTIONS_PER_ENDPOINT,
+                parse_number::<usize>,
+            )?;
+            let max_concurrent_streams = parse(
+                strings,
+                ENV_OUTBOUND_HTTP2_MAX_CONCURRENT_STREAMS,
+                parse_number::<usize>,
+            )?;
+            let defaults = h2::PoolSettings::default();
+            h2::PoolSettings {
+                max_connections,
+                max_concurrent_streams: max_concurrent_streams
+                    .unwrap_or(defaults.max_concurrent_streams)
+                    .max(1),
+            }
+        };
+
         let connect = ConnectConfig {
             keepalive,
             timeout: outbound_connect_timeout?.unwrap_or(DEFAULT_OUTBOUND_CONNECT_TIMEOUT),
@@ -475,11 +1742,15 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                 OUTBOUND_CONNECT_BASE,
                 DEFAULT_OUTBOUND_CONNECT_BACKOFF,
             )?,
+            backoff_max_elapsed: outbound_connect_backoff_max_elapsed?,
             h2_settings,
+            h2_pool,
             h1_settings: h1::PoolSettings {
                 max_idle,
                 idle_timeout: connection_pool_timeout
                     .unwrap_or(DEFAULT_OUTBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT),
+                max_buf_size: h1_max_buf_size,
+                close_delimited: h1_close_delimited,
             },
         };
 
@@ -495,9 +1766,189 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         let http_failfast_timeout =
             outbound_http_failfast_timeout?.unwrap_or(DEFAULT_OUTBOUND_HTTP_FAILFAST_TIMEOUT);
 
+        let interval =
+            outbound_health_check_interval?.unwrap_or(DEFAULT_OUTBOUND_HEALTH_CHECK_INTERVAL);
+        let timeout =
+            outbound_health_check_timeout?.unwrap_or(DEFAULT_OUTBOUND_HEALTH_CHECK_TIMEOUT);
+        let healthy_threshold = outbound_health_check_healthy_threshold?
+            .unwrap_or(DEFAULT_OUTBOUND_HEALTH_CHECK_HEALTHY_THRESHOLD)
+            .max(1);
+        let unhealthy_threshold = outbound_health_check_unhealthy_threshold?
+            .unwrap_or(DEFAULT_OUTBOUND_HEALTH_CHECK_UNHEALTHY_THRESHOLD)
+            .max(1);
+        let http_health_check =
+            outbound_health_check_path?.map(|path| outbound::HealthCheckConfig {
+                path,
+                interval,
+                timeout,
+                healthy_threshold,
+                unhealthy_threshold,
+            });
+
+        let max_ejection_percent = outbound_latency_outlier_max_ejection_percent?
+            .unwrap_or(DEFAULT_OUTBOUND_LATENCY_OUTLIER_MAX_EJECTION_PERCENT)
+            .min(100);
+        let interval =
+            outbound_latency_outlier_interval?.unwrap_or(DEFAULT_OUTBOUND_LATENCY_OUTLIER_INTERVAL);
+        let ejection_time = outbound_latency_outlier_ejection_time?
+            .unwrap_or(DEFAULT_OUTBOUND_LATENCY_OUTLIER_EJECTION_TIME);
+        let ejection = outbound::EjectionBackoff {
+            base: ejection_time,
+            multiplier: outbound_latency_outlier_ejection_multiplier?.unwrap_or(1.0),
+            max: outbound_latency_outlier_max_ejection_time?
+                .unwrap_or(DEFAULT_OUTBOUND_LATENCY_OUTLIER_MAX_EJECTION_TIME)
+                .max(ejection_time),
+        };
+        let backend_ejections = outbound_latency_outlier_backend_ejections?.unwrap_or_default();
+        let min_requests = outbound_latency_outlier_min_requests?
+            .unwrap_or(DEFAULT_OUTBOUND_LATENCY_OUTLIER_MIN_REQUESTS)
+            .max(1);
+        let http_latency_outlier_detection =
+            outbound_latency_outlier_multiple?.map(|latency_multiple| {
+                outbound::LatencyOutlierConfig {
+                    latency_multiple,
+                    max_ejection_percent,
+                    interval,
+                    ejection,
+                    backend_ejections,
+                    min_requests,
+                }
+            });
+
+        let mode =
+            outbound_backend_connection_limit_mode?.unwrap_or(outbound::ConnectionLimitMode::Queue);
+        let http_backend_connection_limit =
+            outbound_backend_max_connections?.map(|max_connections| {
+                outbound::ConnectionLimitConfig {
+                    max_connections: max_connections.max(1),
+                    mode,
+                }
+            });
+
+        let max_ttl = outbound_http_response_cache_max_ttl?
+            .unwrap_or(DEFAULT_OUTBOUND_HTTP_RESPONSE_CACHE_MAX_TTL);
+        let max_body_bytes = outbound_http_response_cache_max_body_bytes?
+            .unwrap_or(DEFAULT_OUTBOUND_HTTP_RESPONSE_CACHE_MAX_BODY_BYTES);
+        let http_response_cache = outbound_http_response_cache_max_entries?.map(|max_entries| {
+            outbound::ResponseCacheConfig {
+                max_entries,
+                max_ttl,
+                max_body_bytes,
+            }
+        });
+
+        let key_headers = outbound_http_request_coalescing_key_headers?.unwrap_or_default();
+        let max_body_bytes = outbound_http_request_coalescing_max_body_bytes?
+            .unwrap_or(DEFAULT_OUTBOUND_HTTP_REQUEST_COALESCING_MAX_BODY_BYTES);
+        let http_request_coalescing =
+            outbound_http_request_coalescing?.unwrap_or(false).then(|| {
+                outbound::RequestCoalescingConfig {
+                    key_headers: key_headers.into(),
+                    max_body_bytes,
+                }
+            });
+
+        // Each route's settings are merged into a single config, so that
+        // routes are only looked up once.
+        let mut http_routes = HashMap::<_, HashMap<_, outbound::RouteConfig>>::new();
+        for (addr, names) in outbound_http_mtls_required_routes?.unwrap_or_default() {
+            let routes = http_routes.entry(addr).or_default();
+            for name in names {
+                routes.entry(name).or_default().require_mtls = true;
+            }
+        }
+        set_route_configs(
+            &mut http_routes,
+            outbound_http_route_response_body_limits?,
+            |route, max_bytes| route.response_body_limit = Some(max_bytes),
+        );
+        set_route_configs(
+            &mut http_routes,
+            outbound_http_route_priorities?,
+            |route, weight| route.priority = Some(weight),
+        );
+        set_route_configs(
+            &mut http_routes,
+            outbound_http_route_faults?,
+            |route, faults| route.faults = faults,
+        );
+        set_route_configs(
+            &mut http_routes,
+            outbound_http_route_authority_rewrites?,
+            |route, authority| route.authority_rewrite = Some(authority),
+        );
+        set_route_configs(
+            &mut http_routes,
+            outbound_http_route_header_backends?,
+            |route, backends| route.header_backends = Some(backends),
+        );
+        set_route_configs(
+            &mut http_routes,
+            outbound_http_route_retry_max_buffered_bytes?,
+            |route, max_bytes| route.retry_max_buffered_bytes = Some(max_bytes),
+        );
+        set_route_configs(
+            &mut http_routes,
+            outbound_http_route_retryable_statuses?,
+            |route, statuses| route.retryable_statuses = Some(statuses),
+        );
+        set_route_configs(
+            &mut http_routes,
+            outbound_http_route_grpc_status_mappings?,
+            |route, mapping| route.grpc_status_mapping = mapping,
+        );
+        set_route_configs(
+            &mut http_routes,
+            outbound_http_route_latency_slos?,
+            |route, slo| route.latency_slo = Some(slo),
+        );
+
         outbound::Config {
             ingress_mode,
             emit_headers: !disable_headers,
+            json_error_bodies: outbound_json_error_bodies?.unwrap_or(false),
+            grpc_web_errors: outbound_grpc_web_errors?.unwrap_or(false),
+            http1_require_host: outbound_http1_require_host?.unwrap_or(false),
+            http1_reject_ambiguous_framing: outbound_http1_reject_ambiguous_framing?
+                .unwrap_or(false),
+            http1_transfer_encoding: outbound_http1_transfer_encoding?,
+            http_max_header_value_bytes: outbound_http_max_header_value_bytes?,
+            route_latency_slo: outbound_route_latency_slo?,
+            route_latency_slo_log_breaches: outbound_log_route_slo_breaches?.unwrap_or(false),
+            route_availability_window: outbound_route_availability_window?,
+            route_payload_size_buckets: outbound_route_payload_size_buckets?,
+            http_health_check,
+            http_latency_outlier_detection,
+            http_backend_connection_limit,
+            http_response_cache,
+            http_request_coalescing,
+            http_backend_protocols: std::sync::Arc::new(
+                outbound_http_backend_protocols?.unwrap_or_default(),
+            ),
+            http_backend_balancers: std::sync::Arc::new(
+                outbound_http_backend_balancers?.unwrap_or_default(),
+            ),
+            http_routes: std::sync::Arc::new(http_routes),
+            http_route_response_body_limit_mode: outbound_http_route_response_body_limit_mode?
+                .unwrap_or_default(),
+            http_backend_fallback: outbound_http_backend_fallback?.unwrap_or_default(),
+            http_retry_max_buffered_bytes: outbound_http_retry_max_buffered_bytes?
+                .unwrap_or(DEFAULT_OUTBOUND_HTTP_RETRY_MAX_BUFFERED_BYTES),
+            http_retryable_statuses: std::sync::Arc::new(
+                outbound_http_retryable_statuses?.unwrap_or_default(),
+            ),
+            http_retry_min_attempt_time: outbound_http_retry_min_attempt_time?,
+            http_retry_after_max: outbound_http_retry_after_max?,
+            http_retry_idempotent_header: outbound_http_retry_idempotent_header?,
+            http_retry_on_reset: outbound_http_retry_on_reset?.unwrap_or_default(),
+            http_retry_timeout_headroom: outbound_http_retry_timeout_headroom?,
+            http_response_trailers: outbound::TrailerFilter {
+                allow: outbound_http_response_trailers_allow?.map(Into::into),
+                deny: outbound_http_response_trailers_deny?
+                    .unwrap_or_default()
+                    .into(),
+            },
+            tls_handshake_timeout: outbound_tls_handshake_timeout?.unwrap_or(connect.timeout),
             allow_discovery: AddrMatch::new(dst_profile_suffixes.clone(), dst_profile_networks),
             proxy: ProxyConfig {
                 server,
@@ -505,9 +1956,30 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                 max_in_flight_requests: outbound_max_in_flight?
                     .unwrap_or(DEFAULT_OUTBOUND_MAX_IN_FLIGHT),
                 detect_protocol_timeout,
+                forward_buffer_capacity,
+                forward_idle_timeout,
             },
             inbound_ips: inbound_ips.clone(),
             discovery_idle_timeout,
+            discovery_idle_jitter: outbound_discovery_idle_jitter?.unwrap_or_default(),
+            discovery_max_lifetime: outbound_discovery_max_lifetime?,
+            endpoint_exclusions: outbound_endpoint_exclusions?.unwrap_or_default(),
+            endpoint_staleness: {
+                let fallback = outbound_endpoint_stale_fallback?.unwrap_or_default();
+                outbound_endpoint_max_age?
+                    .map(|max_age| outbound::EndpointStaleness { max_age, fallback })
+            },
+            endpoint_stabilization_window: outbound_endpoint_stabilization_window?,
+            connect_source_addrs: outbound_connect_source_addrs?.unwrap_or_default(),
+            opaq_keepalive_probes: {
+                let interval = outbound_opaque_keepalive_interval?;
+                let retries = outbound_opaque_keepalive_retries?;
+                outbound_opaque_keepalive_time?.map(|time| transport::KeepaliveProbes {
+                    time,
+                    interval,
+                    retries,
+                })
+            },
             tcp_connection_queue: QueueConfig {
                 capacity: tcp_queue_capacity,
                 failfast_timeout: tcp_failfast_timeout,
@@ -516,6 +1988,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                 capacity: http_queue_capacity,
                 failfast_timeout: http_failfast_timeout,
             },
+            http_request_queue_fair: outbound_http_queue_fair?.unwrap_or(false),
         }
     };
 
@@ -535,7 +2008,18 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         let server = ServerConfig {
             addr,
             keepalive,
-            h2_settings,
+            h1_settings: h1::ServerSettings {
+                pipelining: inbound_h1_pipelining?.unwrap_or_default(),
+                ..h1_server_settings
+            },
+            h2_settings: h2::Settings {
+                max_header_block_size: Some(
+                    inbound_h2_max_header_block_size?
+                        .unwrap_or(DEFAULT_INBOUND_HTTP2_MAX_HEADER_BLOCK_SIZE),
+                ),
+                max_window_updates_per_second: inbound_h2_max_window_updates_per_second?,
+                ..h2_settings
+            },
         };
         let discovery_idle_timeout =
             inbound_discovery_idle_timeout?.unwrap_or(DEFAULT_INBOUND_DISCOVERY_IDLE_TIMEOUT);
@@ -556,10 +2040,14 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                 INBOUND_CONNECT_BASE,
                 DEFAULT_INBOUND_CONNECT_BACKOFF,
             )?,
+            backoff_max_elapsed: inbound_connect_backoff_max_elapsed?,
             h2_settings,
+            h2_pool: h2::PoolSettings::default(),
             h1_settings: h1::PoolSettings {
                 max_idle,
                 idle_timeout: connection_pool_timeout,
+                max_buf_size: h1_max_buf_size,
+                close_delimited: h1_close_delimited,
             },
         };
 
@@ -638,6 +2126,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                                 capacity: DEFAULT_CONTROL_QUEUE_CAPACITY,
                                 failfast_timeout: DEFAULT_CONTROL_FAILFAST_TIMEOUT,
                             },
+                            circuit: control_circuit,
                         }
                     };
 
@@ -664,6 +2153,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                     // - ports that require some form of proxy-terminated TLS, though not
                     //   necessarily with a client identity.
                     // - opaque ports
+                    // - ports that are handled as HTTP/2 without detection
                     let require_identity_ports =
                         parse(strings, ENV_INBOUND_PORTS_REQUIRE_IDENTITY, parse_port_set)?
                             .unwrap_or_default()
@@ -727,6 +2217,42 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                         ports
                     };
 
+                    let http2_ports = {
+                        let ports = inbound_http2_ports?
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|p| {
+                                let sp = require_identity_ports
+                                    .get(&p)
+                                    .or_else(|| require_tls_ports.get(&p))
+                                    .cloned()
+                                    .unwrap_or_else(|| default_allow.clone());
+                                (p, policy::defaults::http2(sp))
+                            })
+                            .collect::<HashMap<_, inbound::policy::ServerPolicy>>();
+                        // Ensure that the inbound port is not forced to HTTP/2, as it must
+                        // support protocol detection.
+                        if ports.contains_key(&inbound_port) {
+                            error!(
+                                "{} must not contain {} ({})",
+                                ENV_INBOUND_PORTS_FORCE_HTTP2,
+                                ENV_INBOUND_LISTEN_ADDR,
+                                inbound_port
+                            );
+                            return Err(EnvError::InvalidEnvVar);
+                        }
+                        if let Some(port) = ports.keys().find(|p| opaque_ports.contains_key(p)) {
+                            error!(
+                                "{} must not contain ports in {} ({})",
+                                ENV_INBOUND_PORTS_FORCE_HTTP2,
+                                ENV_INBOUND_PORTS_DISABLE_PROTOCOL_DETECTION,
+                                port
+                            );
+                            return Err(EnvError::InvalidEnvVar);
+                        }
+                        ports
+                    };
+
                     inbound::policy::Config::Fixed {
                         default,
                         cache_max_idle_age: discovery_idle_timeout,
@@ -734,6 +2260,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                             .into_iter()
                             .chain(require_tls_ports)
                             .chain(opaque_ports)
+                            .chain(http2_ports)
                             .collect(),
                     }
                 }
@@ -748,6 +2275,8 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                 max_in_flight_requests: inbound_max_in_flight?
                     .unwrap_or(DEFAULT_INBOUND_MAX_IN_FLIGHT),
                 detect_protocol_timeout,
+                forward_buffer_capacity,
+                forward_idle_timeout,
             },
             policy,
             profile_skip_timeout: dst_profile_skip_timeout?
@@ -761,6 +2290,34 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                 failfast_timeout: inbound_http_failfast_timeout?
                     .unwrap_or(DEFAULT_INBOUND_HTTP_FAILFAST_TIMEOUT),
             },
+            http_concurrency_limit_mode: match inbound_concurrency_limit_mode?.unwrap_or_default() {
+                inbound::ConcurrencyLimitMode::Queue(_) => inbound::ConcurrencyLimitMode::Queue(
+                    inbound_concurrency_limit_queue_timeout?
+                        .unwrap_or(DEFAULT_INBOUND_HTTP_CONCURRENCY_LIMIT_QUEUE_TIMEOUT),
+                ),
+                mode => mode,
+            },
+            json_error_bodies: inbound_json_error_bodies?.unwrap_or(false),
+            http1_require_host: inbound_http1_require_host?.unwrap_or(false),
+            http1_reject_ambiguous_framing: inbound_http1_reject_ambiguous_framing?
+                .unwrap_or(false),
+            http1_transfer_encoding: inbound_http1_transfer_encoding?,
+            http_max_header_value_bytes: inbound_http_max_header_value_bytes?,
+            http_request_body_timeout: inbound_http_request_body_timeout?,
+            http1_bridge_http10: inbound_http1_bridge_http10?.unwrap_or(false),
+            access_log_error_body_bytes: inbound_access_log_error_body_bytes?,
+            http1_connect: inbound_http1_connect_mode?.unwrap_or_default(),
+            http_dst_port_header: inbound_dst_port_header?.unwrap_or(false),
+            http_route_allowed_methods: std::sync::Arc::new(
+                inbound_http_route_allowed_methods?.unwrap_or_default(),
+            ),
+            http_route_request_validations: std::sync::Arc::new(route_request_validations(
+                inbound_http_route_request_body_limits?.unwrap_or_default(),
+                inbound_http_route_request_content_types?.unwrap_or_default(),
+            )),
+            app_connect_grace: inbound_app_connect_grace?,
+            max_connection_lifetime: inbound_max_connection_lifetime?,
+            tls_sni_ports: std::sync::Arc::new(inbound_tls_sni_ports?.unwrap_or_default()),
         }
     };
 
@@ -785,15 +2342,18 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                     capacity: DEFAULT_CONTROL_QUEUE_CAPACITY,
                     failfast_timeout,
                 },
+                circuit: control_circuit,
             },
         }
     };
 
     let admin = super::admin::Config {
         metrics_retain_idle: metrics_retain_idle?.unwrap_or(DEFAULT_METRICS_RETAIN_IDLE),
+        metrics_latency_sample_rate: metrics_latency_sample_rate?.unwrap_or(1.0),
         server: ServerConfig {
             addr: ListenAddr(admin_listener_addr),
             keepalive: inbound.proxy.server.keepalive,
+            h1_settings: h1_server_settings,
             h2_settings,
         },
     };
@@ -804,6 +2364,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         resolv_conf_path: resolv_conf_path?
             .unwrap_or_else(|| DEFAULT_RESOLV_CONF.into())
             .into(),
+        query_strategy: dns_query_strategy?,
     };
 
     let oc_collector = match trace_collector_addr? {
@@ -836,6 +2397,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                         capacity: DEFAULT_CONTROL_QUEUE_CAPACITY,
                         failfast_timeout,
                     },
+                    circuit: control_circuit,
                 },
             }))
         }
@@ -847,6 +2409,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
             config: ServerConfig {
                 addr: ListenAddr(addr),
                 keepalive: inbound.proxy.server.keepalive,
+                h1_settings: h1_server_settings,
                 h2_settings,
             },
         })
@@ -874,110 +2437,709 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                     capacity: DEFAULT_CONTROL_QUEUE_CAPACITY,
                     failfast_timeout,
                 },
+                circuit: control_circuit,
             },
             documents,
+            shadow: shadow_identity_config?,
+            pinned_certs: pinned_certs?.unwrap_or_default(),
+        }
+    };
+
+    Ok(super::Config {
+        admin,
+        dns,
+        dst,
+        tap,
+        oc_collector,
+        identity,
+        outbound,
+        gateway,
+        inbound,
+        shutdown_grace_period: shutdown_grace_period?.unwrap_or(DEFAULT_SHUTDOWN_GRACE_PERIOD),
+        startup_mode: {
+            let timeout = startup_hold_timeout?.unwrap_or(DEFAULT_STARTUP_HOLD_TIMEOUT);
+            startup_mode?.map(|mode| match mode {
+                StartupModeKind::Hold => StartupMode::Hold { timeout },
+                StartupModeKind::Fail => StartupMode::Fail,
+            })
+        },
+    })
+}
+
+fn oc_trace_attributes(oc_attributes_file_path: String) -> HashMap<String, String> {
+    match fs::read_to_string(oc_attributes_file_path.clone()) {
+        Ok(attributes_string) => convert_attributes_string_to_map(attributes_string),
+        Err(err) => {
+            warn!(
+                "could not read OC trace attributes file at {}: {}",
+                oc_attributes_file_path, err
+            );
+            HashMap::new()
+        }
+    }
+}
+
+fn convert_attributes_string_to_map(attributes: String) -> HashMap<String, String> {
+    attributes
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, '=');
+            parts.next().and_then(move |key| {
+                parts.next().map(move |val|
+                // Trim double quotes in value, present by default when attached through k8s downwardAPI
+                (key.to_string(), val.trim_matches('"').to_string()))
+            })
+        })
+        .collect()
+}
+
+// === impl Env ===
+
+impl Strings for Env {
+    fn get(&self, key: &str) -> Result<Option<String>, EnvError> {
+        use std::env;
+
+        match env::var(key) {
+            Ok(value) => Ok(Some(value)),
+            Err(env::VarError::NotPresent) => Ok(None),
+            Err(env::VarError::NotUnicode(_)) => {
+                error!("{} is not encoded in Unicode", key);
+                Err(EnvError::InvalidEnvVar)
+            }
+        }
+    }
+}
+
+impl Env {
+    pub fn try_config(&self) -> Result<super::Config, EnvError> {
+        parse_config(self)
+    }
+}
+
+// === Parsing ===
+
+/// There is a dependency on identity being enabled for tap to work. The
+/// status of tap is determined by the ENV_TAP_SVC_NAME env variable being set
+/// or not set.
+///
+/// - If identity is disabled, tap is disabled, but a warning is issued if
+///   ENV_TAP_SVC_NAME is set.
+/// - If identity is enabled, the status of tap is determined by
+///   ENV_TAP_SVC_NAME.
+fn parse_tap_config(
+    strings: &dyn Strings,
+) -> Result<Option<(SocketAddr, HashSet<tls::server::ClientId>)>, EnvError> {
+    let tap_identity = parse(strings, ENV_TAP_SVC_NAME, parse_identity)?;
+    let addr = parse(strings, ENV_CONTROL_LISTEN_ADDR, parse_socket_addr)?
+        .unwrap_or_else(|| parse_socket_addr(DEFAULT_CONTROL_LISTEN_ADDR).unwrap());
+    if let Some(id) = tap_identity {
+        return Ok(Some((
+            addr,
+            vec![id].into_iter().map(tls::ClientId).collect(),
+        )));
+    }
+    Ok(None)
+}
+
+fn parse_bool(s: &str) -> Result<bool, ParseError> {
+    s.parse().map_err(Into::into)
+}
+
+fn parse_number<T>(s: &str) -> Result<T, ParseError>
+where
+    T: FromStr,
+    ParseError: From<T::Err>,
+{
+    s.parse().map_err(Into::into)
+}
+
+fn parse_buffer_size(s: &str) -> Result<usize, ParseError> {
+    let sz = parse_number(s)?;
+    if sz < MIN_BUFFER_SIZE {
+        return Err(ParseError::BufferTooSmall(MIN_BUFFER_SIZE));
+    }
+    Ok(sz)
+}
+
+fn parse_h2_max_frame_size(s: &str) -> Result<u32, ParseError> {
+    // The range permitted by RFC 9113, sec. 6.5.2.
+    const MIN: u32 = 16_384;
+    const MAX: u32 = 16_777_215;
+    let sz = parse_number(s)?;
+    if !(MIN..=MAX).contains(&sz) {
+        return Err(ParseError::InvalidH2MaxFrameSize);
+    }
+    Ok(sz)
+}
+
+fn parse_latency_multiple(s: &str) -> Result<f64, ParseError> {
+    let multiple = parse_number::<f64>(s)?;
+    if !(multiple > 1.0 && multiple.is_finite()) {
+        return Err(ParseError::InvalidLatencyMultiple);
+    }
+    Ok(multiple)
+}
+
+fn parse_ejection_multiplier(s: &str) -> Result<f64, ParseError> {
+    let multiplier = parse_number::<f64>(s)?;
+    if !(multiplier >= 1.0 && multiplier.is_finite()) {
+        return Err(ParseError::InvalidEjectionMultiplier);
+    }
+    Ok(multiplier)
+}
+
+fn parse_backend_ejections(
+    s: &str,
+) -> Result<HashMap<NameAddr, outbound::EjectionBackoff>, ParseError> {
+    let mut ejections = HashMap::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let invalid = || ParseError::InvalidBackendEjection(entry.to_string());
+        let (addr, backoff) = entry.split_once('=').ok_or_else(invalid)?;
+        let addr = addr.trim().parse().map_err(ParseError::AddrError)?;
+        let (base, multiplier, max) = match backoff.split(':').collect::<Vec<_>>()[..] {
+            [base, multiplier, max] => (base.trim(), multiplier.trim(), max.trim()),
+            _ => return Err(invalid()),
+        };
+        let base = parse_duration(base)?;
+        let multiplier = parse_ejection_multiplier(multiplier)?;
+        let max = parse_duration(max)?.max(base);
+        ejections.insert(
+            addr,
+            outbound::EjectionBackoff {
+                base,
+                multiplier,
+                max,
+            },
+        );
+    }
+    Ok(ejections)
+}
+
+fn parse_sample_rate(s: &str) -> Result<f64, ParseError> {
+    let rate = parse_number::<f64>(s)?;
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(ParseError::InvalidSampleRate);
+    }
+    Ok(rate)
+}
+
+fn parse_http_path(s: &str) -> Result<outbound::http::uri::PathAndQuery, ParseError> {
+    if !s.starts_with('/') {
+        return Err(ParseError::NotAPath);
+    }
+    s.parse().map_err(|_| ParseError::NotAPath)
+}
+
+fn parse_connect_mode(s: &str) -> Result<inbound::HttpConnectMode, ParseError> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "forward" => Ok(inbound::HttpConnectMode::Forward),
+        "reject" => Ok(inbound::HttpConnectMode::Reject),
+        "tunnel" => Ok(inbound::HttpConnectMode::Tunnel),
+        _ => Err(ParseError::InvalidConnectMode(s.to_string())),
+    }
+}
+
+fn parse_concurrency_limit_mode(s: &str) -> Result<inbound::ConcurrencyLimitMode, ParseError> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "queue" => Ok(inbound::ConcurrencyLimitMode::Queue(
+            DEFAULT_INBOUND_HTTP_CONCURRENCY_LIMIT_QUEUE_TIMEOUT,
+        )),
+        "shed" => Ok(inbound::ConcurrencyLimitMode::Shed),
+        _ => Err(ParseError::InvalidConcurrencyLimitMode(s.to_string())),
+    }
+}
+
+fn parse_transfer_encoding_mode(
+    s: &str,
+) -> Result<outbound::http::framing::TransferEncodingMode, ParseError> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "lenient" => Ok(outbound::http::framing::TransferEncodingMode::Lenient),
+        "strict" => Ok(outbound::http::framing::TransferEncodingMode::Strict),
+        _ => Err(ParseError::InvalidTransferEncodingMode(s.to_string())),
+    }
+}
+
+fn parse_connection_limit_mode(s: &str) -> Result<outbound::ConnectionLimitMode, ParseError> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "queue" => Ok(outbound::ConnectionLimitMode::Queue),
+        "shed" => Ok(outbound::ConnectionLimitMode::Shed),
+        _ => Err(ParseError::InvalidConnectionLimitMode(s.to_string())),
+    }
+}
+
+fn parse_backend_protocols(
+    s: &str,
+) -> Result<HashMap<NameAddr, outbound::http::Version>, ParseError> {
+    let mut protocols = HashMap::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (addr, version) = entry
+            .rsplit_once('=')
+            .ok_or_else(|| ParseError::InvalidBackendProtocol(entry.to_string()))?;
+        let addr = addr.trim().parse().map_err(ParseError::AddrError)?;
+        let version = match version.trim().to_ascii_lowercase().as_str() {
+            "h1" | "http1" => outbound::http::Version::Http1,
+            "h2" | "http2" => outbound::http::Version::H2,
+            _ => return Err(ParseError::InvalidBackendProtocol(entry.to_string())),
+        };
+        protocols.insert(addr, version);
+    }
+    Ok(protocols)
+}
+
+fn parse_backend_balancers(
+    s: &str,
+) -> Result<HashMap<NameAddr, outbound::BalancePolicy>, ParseError> {
+    let mut balancers = HashMap::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (addr, policy) = entry
+            .rsplit_once('=')
+            .ok_or_else(|| ParseError::InvalidBackendBalancer(entry.to_string()))?;
+        let addr = addr.trim().parse().map_err(ParseError::AddrError)?;
+        let mut parts = policy.split(':').map(str::trim);
+        let policy = match parts
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "peak-ewma" | "ewma" => outbound::BalancePolicy::PeakEwma,
+            "wrr" | "weighted-round-robin" => outbound::BalancePolicy::WeightedRoundRobin,
+            "hash" => {
+                let headers = parts
+                    .by_ref()
+                    .map(parse_header_name)
+                    .collect::<Result<Vec<_>, _>>()?;
+                outbound::BalancePolicy::CompositeKeyHash(http::balance::CompositeKey::new(headers))
+            }
+            _ => return Err(ParseError::InvalidBackendBalancer(entry.to_string())),
+        };
+        if parts.next().is_some() {
+            return Err(ParseError::InvalidBackendBalancer(entry.to_string()));
+        }
+        balancers.insert(addr, policy);
+    }
+    Ok(balancers)
+}
+
+fn parse_mtls_required_routes(s: &str) -> Result<HashMap<NameAddr, HashSet<String>>, ParseError> {
+    let mut routes = HashMap::<_, HashSet<_>>::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (addr, route) = entry
+            .split_once('=')
+            .ok_or_else(|| ParseError::InvalidRoute(entry.to_string()))?;
+        let addr = addr.trim().parse().map_err(ParseError::AddrError)?;
+        let route = route.trim();
+        if route.is_empty() {
+            return Err(ParseError::InvalidRoute(entry.to_string()));
+        }
+        routes.entry(addr).or_default().insert(route.to_string());
+    }
+    Ok(routes)
+}
+
+fn parse_endpoint_exclusions(s: &str) -> Result<outbound::EndpointExclusions, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|entry| {
+            let (key, value) = entry
+                .split_once('=')
+                .ok_or_else(|| ParseError::InvalidEndpointLabel(entry.to_string()))?;
+            let key = key.trim();
+            if key.is_empty() {
+                return Err(ParseError::InvalidEndpointLabel(entry.to_string()));
+            }
+            Ok((key.to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+fn parse_source_addrs(s: &str) -> Result<transport::SourceAddrs, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|entry| {
+            let invalid = || ParseError::InvalidSourceAddr(entry.to_string());
+            let (net, addr) = entry.split_once('=').ok_or_else(invalid)?;
+            let net = IpNet::from_str(net.trim()).map_err(|_| invalid())?;
+            let addr = IpAddr::from_str(addr.trim()).map_err(|_| invalid())?;
+            if net.addr().is_ipv4() != addr.is_ipv4() {
+                return Err(invalid());
+            }
+            Ok((net, addr))
+        })
+        .collect()
+}
+
+fn parse_route_response_body_limits(
+    s: &str,
+) -> Result<HashMap<NameAddr, HashMap<String, usize>>, ParseError> {
+    let mut limits = HashMap::<_, HashMap<_, _>>::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let invalid = || ParseError::InvalidRouteResponseBodyLimit(entry.to_string());
+        let (addr, route) = entry.split_once('=').ok_or_else(invalid)?;
+        let (route, max_bytes) = route.rsplit_once('=').ok_or_else(invalid)?;
+        let addr = addr.trim().parse().map_err(ParseError::AddrError)?;
+        let route = route.trim();
+        if route.is_empty() {
+            return Err(invalid());
+        }
+        let max_bytes = max_bytes.trim().parse().map_err(|_| invalid())?;
+        limits
+            .entry(addr)
+            .or_default()
+            .insert(route.to_string(), max_bytes);
+    }
+    Ok(limits)
+}
+
+fn parse_route_priorities(s: &str) -> Result<HashMap<NameAddr, HashMap<String, u32>>, ParseError> {
+    let mut priorities = HashMap::<_, HashMap<_, _>>::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let invalid = || ParseError::InvalidRoutePriority(entry.to_string());
+        let (addr, route) = entry.split_once('=').ok_or_else(invalid)?;
+        let (route, weight) = route.rsplit_once('=').ok_or_else(invalid)?;
+        let addr = addr.trim().parse().map_err(ParseError::AddrError)?;
+        let route = route.trim();
+        if route.is_empty() {
+            return Err(invalid());
+        }
+        let weight = weight
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|w| *w > 0)
+            .ok_or_else(invalid)?;
+        priorities
+            .entry(addr)
+            .or_default()
+            .insert(route.to_string(), weight);
+    }
+    Ok(priorities)
+}
+
+fn parse_route_latency_slos(
+    s: &str,
+) -> Result<HashMap<NameAddr, HashMap<String, Duration>>, ParseError> {
+    let mut slos = HashMap::<_, HashMap<_, _>>::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let invalid = || ParseError::InvalidRouteLatencySlo(entry.to_string());
+        let (addr, route) = entry.split_once('=').ok_or_else(invalid)?;
+        let (route, threshold) = route.rsplit_once('=').ok_or_else(invalid)?;
+        let addr = addr.trim().parse().map_err(ParseError::AddrError)?;
+        let route = route.trim();
+        if route.is_empty() {
+            return Err(invalid());
+        }
+        let threshold = parse_duration(threshold).map_err(|_| invalid())?;
+        slos.entry(addr)
+            .or_default()
+            .insert(route.to_string(), threshold);
+    }
+    Ok(slos)
+}
+
+fn parse_route_faults(
+    s: &str,
+) -> Result<HashMap<NameAddr, HashMap<String, outbound::RouteFaults>>, ParseError> {
+    let mut faults = HashMap::<_, HashMap<_, outbound::RouteFaults>>::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let invalid = || ParseError::InvalidRouteFault(entry.to_string());
+        let (addr, route) = entry.split_once('=').ok_or_else(invalid)?;
+        let (route, fault) = route.rsplit_once('=').ok_or_else(invalid)?;
+        let addr = addr.trim().parse().map_err(ParseError::AddrError)?;
+        let route = route.trim();
+        if route.is_empty() {
+            return Err(invalid());
+        }
+        let (kind, value, fraction) = match fault.split(':').collect::<Vec<_>>()[..] {
+            [kind, value, fraction] => (kind.trim(), value.trim(), fraction.trim()),
+            _ => return Err(invalid()),
+        };
+        let ratio = fraction
+            .parse::<f64>()
+            .ok()
+            .and_then(outbound::FaultRatio::from_fraction)
+            .ok_or_else(invalid)?;
+        let route = faults
+            .entry(addr)
+            .or_default()
+            .entry(route.to_string())
+            .or_default();
+        match kind.to_ascii_lowercase().as_str() {
+            "delay" => {
+                route.delay = Some(outbound::InjectDelay {
+                    delay: parse_duration(value)?,
+                    ratio,
+                });
+            }
+            "abort" => {
+                let status = value
+                    .parse::<u16>()
+                    .ok()
+                    .and_then(|s| http::StatusCode::from_u16(s).ok())
+                    .ok_or_else(invalid)?;
+                route.abort = Some(outbound::InjectAbort { status, ratio });
+            }
+            _ => return Err(invalid()),
         }
-    };
+    }
+    Ok(faults)
+}
 
-    Ok(super::Config {
-        admin,
-        dns,
-        dst,
-        tap,
-        oc_collector,
-        identity,
-        outbound,
-        gateway,
-        inbound,
-        shutdown_grace_period: shutdown_grace_period?.unwrap_or(DEFAULT_SHUTDOWN_GRACE_PERIOD),
-    })
+fn parse_route_header_backends(
+    s: &str,
+) -> Result<HashMap<NameAddr, HashMap<String, outbound::HeaderBackends>>, ParseError> {
+    let mut routes = HashMap::<_, HashMap<_, outbound::HeaderBackends>>::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let invalid = || ParseError::InvalidRouteHeaderBackend(entry.to_string());
+        let (addr, route, header, backend) = match entry.split('=').collect::<Vec<_>>()[..] {
+            [addr, route, header, backend] => (addr, route.trim(), header, backend),
+            _ => return Err(invalid()),
+        };
+        if route.is_empty() {
+            return Err(invalid());
+        }
+        let addr = addr.trim().parse().map_err(ParseError::AddrError)?;
+        let backend = backend.trim().parse().map_err(ParseError::AddrError)?;
+        let (header, value) = header.split_once(':').ok_or_else(invalid)?;
+        let header = parse_header_name(header)?;
+        // Header values are matched as strings, so they must be visible ASCII.
+        let value = value.trim();
+        if http::HeaderValue::from_str(value).map_or(true, |v| v.to_str().is_err()) {
+            return Err(invalid());
+        }
+        let value = value.to_string();
+        match routes.entry(addr).or_default().entry(route.to_string()) {
+            Entry::Occupied(mut e) => {
+                if e.get().header != header {
+                    return Err(invalid());
+                }
+                e.get_mut().backends.insert(value, backend);
+            }
+            Entry::Vacant(e) => {
+                e.insert(outbound::HeaderBackends {
+                    header,
+                    backends: std::iter::once((value, backend)).collect(),
+                });
+            }
+        }
+    }
+    Ok(routes)
 }
 
-fn oc_trace_attributes(oc_attributes_file_path: String) -> HashMap<String, String> {
-    match fs::read_to_string(oc_attributes_file_path.clone()) {
-        Ok(attributes_string) => convert_attributes_string_to_map(attributes_string),
-        Err(err) => {
-            warn!(
-                "could not read OC trace attributes file at {}: {}",
-                oc_attributes_file_path, err
-            );
-            HashMap::new()
+fn parse_route_authority_rewrites(
+    s: &str,
+) -> Result<HashMap<NameAddr, HashMap<String, http::uri::Authority>>, ParseError> {
+    let mut rewrites = HashMap::<_, HashMap<_, _>>::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let invalid = || ParseError::InvalidRouteAuthorityRewrite(entry.to_string());
+        let (addr, route, authority) = match entry.split('=').collect::<Vec<_>>()[..] {
+            [addr, route, authority] => (addr, route.trim(), authority.trim()),
+            _ => return Err(invalid()),
+        };
+        if route.is_empty() {
+            return Err(invalid());
         }
+        let addr = addr.trim().parse().map_err(ParseError::AddrError)?;
+        let authority = authority
+            .parse::<http::uri::Authority>()
+            .map_err(|_| invalid())?;
+        rewrites
+            .entry(addr)
+            .or_default()
+            .insert(route.to_string(), authority);
     }
+    Ok(rewrites)
 }
 
-fn convert_attributes_string_to_map(attributes: String) -> HashMap<String, String> {
-    attributes
-        .lines()
-        .filter_map(|line| {
-            let mut parts = line.splitn(2, '=');
-            parts.next().and_then(move |key| {
-                parts.next().map(move |val|
-                // Trim double quotes in value, present by default when attached through k8s downwardAPI
-                (key.to_string(), val.trim_matches('"').to_string()))
-            })
-        })
-        .collect()
+fn parse_backend_fallback(s: &str) -> Result<outbound::BackendFallback, ParseError> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "fail-fast" => Ok(outbound::BackendFallback::FailFast),
+        "default" => Ok(outbound::BackendFallback::Default),
+        _ => Err(ParseError::InvalidBackendFallback(s.to_string())),
+    }
 }
 
-// === impl Env ===
+fn parse_reset_behavior(s: &str) -> Result<outbound::ResetBehavior, ParseError> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "propagate" => Ok(outbound::ResetBehavior::Propagate),
+        "retry-idempotent" => Ok(outbound::ResetBehavior::RetryIdempotent),
+        _ => Err(ParseError::InvalidResetBehavior(s.to_string())),
+    }
+}
 
-impl Strings for Env {
-    fn get(&self, key: &str) -> Result<Option<String>, EnvError> {
-        use std::env;
+fn parse_close_delimited_responses(
+    s: &str,
+    max_bytes: usize,
+) -> Result<h1::CloseDelimitedResponses, ParseError> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "stream" => Ok(h1::CloseDelimitedResponses::Stream),
+        "close" => Ok(h1::CloseDelimitedResponses::Close),
+        "buffer" => Ok(h1::CloseDelimitedResponses::Buffer { max_bytes }),
+        _ => Err(ParseError::InvalidCloseDelimitedResponses(s.to_string())),
+    }
+}
 
-        match env::var(key) {
-            Ok(value) => Ok(Some(value)),
-            Err(env::VarError::NotPresent) => Ok(None),
-            Err(env::VarError::NotUnicode(_)) => {
-                error!("{} is not encoded in Unicode", key);
-                Err(EnvError::InvalidEnvVar)
-            }
+fn parse_parse_errors(s: &str) -> Result<h1::ParseErrors, ParseError> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "fail" => Ok(h1::ParseErrors::Fail),
+        "abort" => Ok(h1::ParseErrors::Abort),
+        _ => Err(ParseError::InvalidParseErrors(s.to_string())),
+    }
+}
+
+fn parse_pipelining(s: &str) -> Result<h1::Pipelining, ParseError> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "serialize" => Ok(h1::Pipelining::Serialize),
+        "reject" => Ok(h1::Pipelining::Reject),
+        _ => Err(ParseError::InvalidPipelining(s.to_string())),
+    }
+}
+
+fn parse_stale_endpoint_fallback(s: &str) -> Result<outbound::StaleEndpointFallback, ParseError> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "evict" => Ok(outbound::StaleEndpointFallback::Evict),
+        "retain" => Ok(outbound::StaleEndpointFallback::Retain),
+        _ => Err(ParseError::InvalidStaleEndpointFallback(s.to_string())),
+    }
+}
+
+fn parse_response_body_limit_mode(s: &str) -> Result<outbound::ResponseBodyLimitMode, ParseError> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "error" => Ok(outbound::ResponseBodyLimitMode::Error),
+        "truncate" => Ok(outbound::ResponseBodyLimitMode::Truncate),
+        _ => Err(ParseError::InvalidResponseBodyLimitMode(s.to_string())),
+    }
+}
+
+fn parse_route_allowed_methods(
+    s: &str,
+) -> Result<HashMap<String, std::sync::Arc<[http::Method]>>, ParseError> {
+    let mut routes = HashMap::<_, Vec<_>>::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (route, method) = entry
+            .split_once('=')
+            .ok_or_else(|| ParseError::InvalidRouteMethod(entry.to_string()))?;
+        let route = route.trim();
+        let method = http::Method::from_bytes(method.trim().as_bytes())
+            .map_err(|_| ParseError::InvalidRouteMethod(entry.to_string()))?;
+        if route.is_empty() {
+            return Err(ParseError::InvalidRouteMethod(entry.to_string()));
+        }
+        let methods = routes.entry(route.to_string()).or_default();
+        if !methods.contains(&method) {
+            methods.push(method);
         }
     }
+    Ok(routes
+        .into_iter()
+        .map(|(route, methods)| (route, methods.into()))
+        .collect())
 }
 
-impl Env {
-    pub fn try_config(&self) -> Result<super::Config, EnvError> {
-        parse_config(self)
+fn parse_route_retry_max_buffered_bytes(
+    s: &str,
+) -> Result<HashMap<NameAddr, HashMap<String, usize>>, ParseError> {
+    let mut limits = HashMap::<_, HashMap<_, _>>::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let invalid = || ParseError::InvalidRouteRetryMaxBufferedBytes(entry.to_string());
+        let (addr, route) = entry.split_once('=').ok_or_else(invalid)?;
+        let (route, max_bytes) = route.rsplit_once('=').ok_or_else(invalid)?;
+        let addr = addr.trim().parse().map_err(ParseError::AddrError)?;
+        let route = route.trim();
+        if route.is_empty() {
+            return Err(invalid());
+        }
+        let max_bytes = max_bytes.trim().parse().map_err(|_| invalid())?;
+        limits
+            .entry(addr)
+            .or_default()
+            .insert(route.to_string(), max_bytes);
     }
+    Ok(limits)
 }
 
-// === Parsing ===
+fn parse_route_request_body_limits(s: &str) -> Result<HashMap<String, u64>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|entry| {
+            let invalid = || ParseError::InvalidRouteRequestBodyLimit(entry.to_string());
+            let (route, max_bytes) = entry.rsplit_once('=').ok_or_else(invalid)?;
+            let route = route.trim();
+            if route.is_empty() {
+                return Err(invalid());
+            }
+            let max_bytes = max_bytes.trim().parse().map_err(|_| invalid())?;
+            Ok((route.to_string(), max_bytes))
+        })
+        .collect()
+}
 
-/// There is a dependency on identity being enabled for tap to work. The
-/// status of tap is determined by the ENV_TAP_SVC_NAME env variable being set
-/// or not set.
-///
-/// - If identity is disabled, tap is disabled, but a warning is issued if
-///   ENV_TAP_SVC_NAME is set.
-/// - If identity is enabled, the status of tap is determined by
-///   ENV_TAP_SVC_NAME.
-fn parse_tap_config(
-    strings: &dyn Strings,
-) -> Result<Option<(SocketAddr, HashSet<tls::server::ClientId>)>, EnvError> {
-    let tap_identity = parse(strings, ENV_TAP_SVC_NAME, parse_identity)?;
-    let addr = parse(strings, ENV_CONTROL_LISTEN_ADDR, parse_socket_addr)?
-        .unwrap_or_else(|| parse_socket_addr(DEFAULT_CONTROL_LISTEN_ADDR).unwrap());
-    if let Some(id) = tap_identity {
-        return Ok(Some((
-            addr,
-            vec![id].into_iter().map(tls::ClientId).collect(),
-        )));
+fn parse_route_request_content_types(s: &str) -> Result<HashMap<String, Vec<String>>, ParseError> {
+    let mut routes = HashMap::<_, Vec<_>>::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let invalid = || ParseError::InvalidRouteContentType(entry.to_string());
+        let (route, content_type) = entry.split_once('=').ok_or_else(invalid)?;
+        let route = route.trim();
+        let content_type = content_type.trim().to_ascii_lowercase();
+        // Content types are matched by their media type, without parameters.
+        if route.is_empty() || content_type.is_empty() || content_type.contains(';') {
+            return Err(invalid());
+        }
+        let types = routes.entry(route.to_string()).or_default();
+        if !types.contains(&content_type) {
+            types.push(content_type);
+        }
     }
-    Ok(None)
+    Ok(routes)
 }
 
-fn parse_bool(s: &str) -> Result<bool, ParseError> {
-    s.parse().map_err(Into::into)
+/// Combines the request body limits and allowed content types of each route.
+fn route_request_validations(
+    body_limits: HashMap<String, u64>,
+    content_types: HashMap<String, Vec<String>>,
+) -> HashMap<String, inbound::RequestValidation> {
+    let mut validations = HashMap::<_, inbound::RequestValidation>::new();
+    for (route, max_bytes) in body_limits {
+        validations.entry(route).or_default().max_body_bytes = Some(max_bytes);
+    }
+    for (route, types) in content_types {
+        validations.entry(route).or_default().content_types = Some(types.into());
+    }
+    validations
 }
 
-fn parse_number<T>(s: &str) -> Result<T, ParseError>
-where
-    T: FromStr,
-    ParseError: From<T::Err>,
-{
-    s.parse().map_err(Into::into)
+/// Applies a per-route setting to the config of each route it names.
+fn set_route_configs<V>(
+    routes: &mut HashMap<NameAddr, HashMap<String, outbound::RouteConfig>>,
+    values: Option<HashMap<NameAddr, HashMap<String, V>>>,
+    set: impl Fn(&mut outbound::RouteConfig, V),
+) {
+    for (addr, values) in values.unwrap_or_default() {
+        let routes = routes.entry(addr).or_default();
+        for (name, value) in values {
+            set(routes.entry(name).or_default(), value);
+        }
+    }
+}
+
+fn parse_sni_ports(s: &str) -> Result<HashMap<tls::ServerId, u16>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|entry| {
+            let invalid = || ParseError::InvalidSniPort(entry.to_string());
+            let (sni, port) = entry.split_once('=').ok_or_else(invalid)?;
+            let sni = parse_identity(sni.trim()).map_err(|_| invalid())?;
+            let port = port.trim().parse::<u16>().map_err(|_| invalid())?;
+            if port == 0 {
+                return Err(invalid());
+            }
+            Ok((tls::ServerId(sni), port))
+        })
+        .collect()
 }
 
 fn parse_duration(s: &str) -> Result<Duration, ParseError> {
@@ -999,6 +3161,14 @@ fn parse_duration(s: &str) -> Result<Duration, ParseError> {
     }
 }
 
+/// Parses a duration that must not be zero, e.g. the period of a timer.
+fn parse_nonzero_duration(s: &str) -> Result<Duration, ParseError> {
+    match parse_duration(s)? {
+        d if d.is_zero() => Err(ParseError::ZeroDuration),
+        d => Ok(d),
+    }
+}
+
 fn parse_socket_addr(s: &str) -> Result<SocketAddr, ParseError> {
     match parse_addr(s)? {
         Addr::Socket(a) => Ok(a),
@@ -1032,6 +3202,151 @@ fn parse_port_set(s: &str) -> Result<HashSet<u16>, ParseError> {
     Ok(set)
 }
 
+fn parse_payload_size_buckets(s: &str) -> Result<outbound::PayloadSizeBuckets, ParseError> {
+    let mut bounds = Vec::new();
+    for size in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        bounds.push(parse_number::<u64>(size)?);
+    }
+    outbound::PayloadSizeBuckets::new(bounds).ok_or(ParseError::InvalidPayloadSizeBuckets)
+}
+
+fn parse_status_set(s: &str) -> Result<HashSet<outbound::http::StatusCode>, ParseError> {
+    let mut set = HashSet::new();
+    for code in s.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+        let status = outbound::http::StatusCode::from_u16(parse_number::<u16>(code)?)
+            .map_err(|_| ParseError::InvalidStatus(code.to_string()))?;
+        set.insert(status);
+    }
+    Ok(set)
+}
+
+#[allow(clippy::type_complexity)]
+fn parse_route_retryable_statuses(
+    s: &str,
+) -> Result<
+    HashMap<NameAddr, HashMap<String, std::sync::Arc<HashSet<outbound::http::StatusCode>>>>,
+    ParseError,
+> {
+    let mut statuses = HashMap::<_, HashMap<_, HashSet<_>>>::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let invalid = || ParseError::InvalidRouteRetryableStatus(entry.to_string());
+        let (addr, route) = entry.split_once('=').ok_or_else(invalid)?;
+        let (route, status) = route.rsplit_once('=').ok_or_else(invalid)?;
+        let addr = addr.trim().parse().map_err(ParseError::AddrError)?;
+        let route = route.trim();
+        if route.is_empty() {
+            return Err(invalid());
+        }
+        let status = status
+            .trim()
+            .parse::<u16>()
+            .ok()
+            .and_then(|s| outbound::http::StatusCode::from_u16(s).ok())
+            .ok_or_else(invalid)?;
+        statuses
+            .entry(addr)
+            .or_default()
+            .entry(route.to_string())
+            .or_default()
+            .insert(status);
+    }
+    Ok(statuses
+        .into_iter()
+        .map(|(addr, routes)| {
+            let routes = routes
+                .into_iter()
+                .map(|(route, statuses)| (route, std::sync::Arc::new(statuses)))
+                .collect();
+            (addr, routes)
+        })
+        .collect())
+}
+
+fn parse_route_grpc_status_mappings(
+    s: &str,
+) -> Result<HashMap<NameAddr, HashMap<String, outbound::GrpcStatusMapping>>, ParseError> {
+    let mut mappings = HashMap::<_, HashMap<_, HashMap<_, _>>>::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let invalid = || ParseError::InvalidRouteGrpcStatusMapping(entry.to_string());
+        let (addr, route) = entry.split_once('=').ok_or_else(invalid)?;
+        let (route, mapping) = route.rsplit_once('=').ok_or_else(invalid)?;
+        let addr = addr.trim().parse().map_err(ParseError::AddrError)?;
+        let route = route.trim();
+        if route.is_empty() {
+            return Err(invalid());
+        }
+        let (code, status) = mapping.split_once(':').ok_or_else(invalid)?;
+        let code = match code.trim().parse::<i32>() {
+            Ok(code @ 0..=16) => crate::core::errors::Grpc::from_i32(code),
+            _ => return Err(invalid()),
+        };
+        let status = status
+            .trim()
+            .parse::<u16>()
+            .ok()
+            .and_then(|s| outbound::http::StatusCode::from_u16(s).ok())
+            .ok_or_else(invalid)?;
+        mappings
+            .entry(addr)
+            .or_default()
+            .entry(route.to_string())
+            .or_default()
+            .insert(code, status);
+    }
+    Ok(mappings
+        .into_iter()
+        .map(|(addr, routes)| {
+            let routes = routes
+                .into_iter()
+                .map(|(route, mapping)| (route, mapping.into()))
+                .collect();
+            (addr, routes)
+        })
+        .collect())
+}
+
+/// A startup mode, before its hold timeout is configured.
+enum StartupModeKind {
+    Hold,
+    Fail,
+}
+
+fn parse_startup_mode(s: &str) -> Result<StartupModeKind, ParseError> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "hold" => Ok(StartupModeKind::Hold),
+        "fail" => Ok(StartupModeKind::Fail),
+        _ => Err(ParseError::InvalidStartupMode(s.to_string())),
+    }
+}
+
+fn parse_trailer_patterns(s: &str) -> Result<Vec<outbound::TrailerPattern>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            p.parse()
+                .map_err(|_| ParseError::InvalidTrailerPattern(p.to_string()))
+        })
+        .collect()
+}
+
+fn parse_header_names(s: &str) -> Result<Vec<http::HeaderName>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|h| !h.is_empty())
+        .map(|h| {
+            h.parse()
+                .map_err(|_| ParseError::InvalidHeaderName(h.to_string()))
+        })
+        .collect()
+}
+
+fn parse_header_name(s: &str) -> Result<http::HeaderName, ParseError> {
+    let s = s.trim();
+    s.parse()
+        .map_err(|_| ParseError::InvalidHeaderName(s.to_string()))
+}
+
 pub(super) fn parse_identity(s: &str) -> Result<identity::Name, ParseError> {
     identity::Name::from_str(s).map_err(|identity::InvalidName| {
         error!("Not a valid identity name: {}", s);
@@ -1095,6 +3410,29 @@ fn parse_dns_suffixes(list: &str) -> Result<HashSet<dns::Suffix>, ParseError> {
     Ok(suffixes)
 }
 
+fn parse_pinned_certs(s: &str) -> Result<tls::PinnedCerts, ParseError> {
+    let mut pins = HashMap::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let invalid = || ParseError::InvalidPinnedCert(entry.to_string());
+        let (name, fingerprint) = entry.split_once('=').ok_or_else(invalid)?;
+        let name = parse_identity(name.trim())?;
+        let fingerprint = fingerprint.parse().map_err(|_| invalid())?;
+        pins.insert(name, fingerprint);
+    }
+    Ok(pins.into_iter().collect())
+}
+
+fn parse_dns_query_strategy(s: &str) -> Result<dns::QueryStrategy, ParseError> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "ipv4-only" => Ok(dns::QueryStrategy::Ipv4Only),
+        "ipv6-only" => Ok(dns::QueryStrategy::Ipv6Only),
+        "both" => Ok(dns::QueryStrategy::Both),
+        "prefer-ipv4" => Ok(dns::QueryStrategy::PreferIpv4),
+        "prefer-ipv6" => Ok(dns::QueryStrategy::PreferIpv6),
+        _ => Err(ParseError::InvalidDnsQueryStrategy(s.to_string())),
+    }
+}
+
 fn parse_dns_suffix(s: &str) -> Result<dns::Suffix, ParseError> {
     if s == "." {
         return Ok(dns::Suffix::Root);
@@ -1303,6 +3641,68 @@ pub fn parse_identity_config<S: Strings>(
     }
 }
 
+pub fn parse_shadow_identity_config<S: Strings>(
+    strings: &S,
+) -> Result<Option<identity::ShadowConfig>, EnvError> {
+    let servers = parse(
+        strings,
+        ENV_IDENTITY_SHADOW_SERVER_SUFFIXES,
+        parse_dns_suffixes,
+    );
+    let ta = parse(strings, ENV_IDENTITY_SHADOW_TRUST_ANCHORS, |s| {
+        if s.is_empty() {
+            return Err(ParseError::InvalidTrustAnchors);
+        }
+        Ok(s.to_string())
+    });
+    let dir = parse(strings, ENV_IDENTITY_SHADOW_DIR, |ref s| {
+        Ok(PathBuf::from(s))
+    });
+    let li = parse(strings, ENV_IDENTITY_SHADOW_LOCAL_NAME, parse_identity);
+
+    match (servers?, ta?, dir?, li?) {
+        (None, None, None, None) => Ok(None),
+        (Some(servers), Some(trust_anchors_pem), Some(dir), Some(local_name)) => {
+            let read = |name: &str| {
+                let p = dir.join(name);
+                match fs::read(&p) {
+                    Ok(b) if !b.is_empty() => Ok(b),
+                    Ok(_) => {
+                        error!("{} is empty", p.display());
+                        Err(EnvError::InvalidEnvVar)
+                    }
+                    Err(e) => {
+                        error!("Failed to read {}: {}", p.display(), e);
+                        Err(EnvError::InvalidEnvVar)
+                    }
+                }
+            };
+            Ok(Some(identity::ShadowConfig {
+                servers: servers.into_iter().collect(),
+                documents: identity::ShadowDocuments {
+                    id: identity::LocalId(local_name),
+                    trust_anchors_pem,
+                    key_pkcs8: read("key.p8")?,
+                    crt_der: read("crt.der")?,
+                },
+            }))
+        }
+        (servers, trust_anchors, dir, local_name) => {
+            for (unset, name) in &[
+                (servers.is_none(), ENV_IDENTITY_SHADOW_SERVER_SUFFIXES),
+                (trust_anchors.is_none(), ENV_IDENTITY_SHADOW_TRUST_ANCHORS),
+                (dir.is_none(), ENV_IDENTITY_SHADOW_DIR),
+                (local_name.is_none(), ENV_IDENTITY_SHADOW_LOCAL_NAME),
+            ] {
+                if *unset {
+                    error!("{} must be set to configure a shadow identity.", name);
+                }
+            }
+            Err(EnvError::InvalidEnvVar)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1354,6 +3754,153 @@ mod tests {
         assert_eq!(parse_duration("1 ms"), Err(ParseError::NotADuration));
     }
 
+    #[test]
+    fn parse_payload_size_buckets_rejects_unordered_bounds() {
+        assert!(parse_payload_size_buckets("1024, 16384,1048576").is_ok());
+        for invalid in ["0,1024", "1024,1024", "16384,1024"] {
+            assert!(
+                matches!(
+                    parse_payload_size_buckets(invalid),
+                    Err(ParseError::InvalidPayloadSizeBuckets)
+                ),
+                "{} must be rejected",
+                invalid
+            );
+        }
+        assert!(matches!(
+            parse_payload_size_buckets("1k"),
+            Err(ParseError::NotAnInteger(_))
+        ));
+    }
+
+    #[test]
+    fn parse_buffer_sizes() {
+        assert_eq!(parse_buffer_size("8192"), Ok(8192));
+        assert_eq!(parse_buffer_size("65536"), Ok(65536));
+        assert_eq!(
+            parse_buffer_size("4096"),
+            Err(ParseError::BufferTooSmall(MIN_BUFFER_SIZE))
+        );
+        assert!(matches!(
+            parse_buffer_size("64k"),
+            Err(ParseError::NotAnInteger(_))
+        ));
+    }
+
+    #[test]
+    fn parse_route_retry_max_buffered_bytes_by_route() {
+        let limits = parse_route_retry_max_buffered_bytes(
+            "web.ns.svc.cluster.local:8080=upload=1048576, web.ns.svc.cluster.local:8080=list=0",
+        )
+        .unwrap();
+        let addr = "web.ns.svc.cluster.local:8080".parse::<NameAddr>().unwrap();
+        assert_eq!(limits[&addr]["upload"], 1024 * 1024);
+        assert_eq!(limits[&addr]["list"], 0);
+        for invalid in [
+            "web.ns.svc.cluster.local:8080=upload",
+            "web.ns.svc.cluster.local:8080==1024",
+            "web.ns.svc.cluster.local:8080=upload=1k",
+        ] {
+            assert_eq!(
+                parse_route_retry_max_buffered_bytes(invalid),
+                Err(ParseError::InvalidRouteRetryMaxBufferedBytes(
+                    invalid.to_string()
+                )),
+            );
+        }
+    }
+
+    #[test]
+    fn parse_route_retryable_statuses_by_route() {
+        let statuses = parse_route_retryable_statuses(
+            "web.ns.svc.cluster.local:8080=list=425, web.ns.svc.cluster.local:8080=list=429,\
+             web.ns.svc.cluster.local:8080=get=503",
+        )
+        .unwrap();
+        let addr = "web.ns.svc.cluster.local:8080".parse::<NameAddr>().unwrap();
+        let status = |code| outbound::http::StatusCode::from_u16(code).unwrap();
+        assert_eq!(
+            *statuses[&addr]["list"],
+            [status(425), status(429)].into_iter().collect()
+        );
+        assert_eq!(
+            *statuses[&addr]["get"],
+            std::iter::once(status(503)).collect()
+        );
+        for invalid in [
+            "web.ns.svc.cluster.local:8080=list",
+            "web.ns.svc.cluster.local:8080==429",
+            "web.ns.svc.cluster.local:8080=list=1000",
+        ] {
+            assert_eq!(
+                parse_route_retryable_statuses(invalid),
+                Err(ParseError::InvalidRouteRetryableStatus(invalid.to_string())),
+            );
+        }
+    }
+
+    #[test]
+    fn parse_route_grpc_status_mappings_by_route() {
+        let mappings = parse_route_grpc_status_mappings(
+            "web.ns.svc.cluster.local:8080=get=5:404, web.ns.svc.cluster.local:8080=get=14:503,\
+             web.ns.svc.cluster.local:8080=list=14:502",
+        )
+        .unwrap();
+        let addr = "web.ns.svc.cluster.local:8080".parse::<NameAddr>().unwrap();
+        let mapping = |entries: &[(crate::core::errors::Grpc, u16)]| {
+            entries
+                .iter()
+                .map(|&(code, status)| {
+                    (code, outbound::http::StatusCode::from_u16(status).unwrap())
+                })
+                .collect::<HashMap<_, _>>()
+                .into()
+        };
+        assert_eq!(
+            mappings[&addr]["get"],
+            mapping(&[
+                (crate::core::errors::Grpc::NotFound, 404),
+                (crate::core::errors::Grpc::Unavailable, 503),
+            ])
+        );
+        assert_eq!(
+            mappings[&addr]["list"],
+            mapping(&[(crate::core::errors::Grpc::Unavailable, 502)])
+        );
+        for invalid in [
+            "web.ns.svc.cluster.local:8080=get",
+            "web.ns.svc.cluster.local:8080==5:404",
+            "web.ns.svc.cluster.local:8080=get=5",
+            "web.ns.svc.cluster.local:8080=get=17:404",
+            "web.ns.svc.cluster.local:8080=get=5:1000",
+        ] {
+            assert_eq!(
+                parse_route_grpc_status_mappings(invalid),
+                Err(ParseError::InvalidRouteGrpcStatusMapping(
+                    invalid.to_string()
+                )),
+            );
+        }
+    }
+
+    #[test]
+    fn parse_nonzero_duration_rejects_zero() {
+        assert_eq!(parse_nonzero_duration("10s"), Ok(Duration::from_secs(10)));
+        assert_eq!(parse_nonzero_duration("1ms"), Ok(Duration::from_millis(1)));
+        for zero in ["0", "0ms", "0s"] {
+            assert_eq!(
+                parse_nonzero_duration(zero),
+                Err(ParseError::ZeroDuration),
+                "{} must be rejected",
+                zero
+            );
+        }
+        assert_eq!(
+            parse_nonzero_duration("1.5s"),
+            Err(ParseError::NotADuration)
+        );
+    }
+
     #[test]
     fn parse_duration_overflows_invalid() {
         assert!(matches!(
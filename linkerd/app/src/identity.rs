@@ -7,12 +7,12 @@ use linkerd_app_core::{
     exp_backoff::{ExponentialBackoff, ExponentialBackoffStream},
     identity::{
         client::{Certify, Metrics as IdentityMetrics},
-        creds, Credentials, DerX509, Mode,
+        creds, Credentials, DerX509, Metrics as TlsMetrics, Mode,
     },
     metrics::ControlHttp as ClientMetrics,
-    Error, Result,
+    tls, Error, Result,
 };
-use std::{future::Future, pin::Pin};
+use std::{future::Future, pin::Pin, time::SystemTime};
 use tokio::sync::watch;
 use tracing::Instrument;
 
@@ -21,6 +21,14 @@ pub struct Config {
     pub control: control::Config,
     pub certify: certify::Config,
     pub documents: Documents,
+
+    /// A statically-provisioned identity presented, in place of the proxy's
+    /// certified identity, on outbound connections to matching servers.
+    pub shadow: Option<ShadowConfig>,
+
+    /// The certificates to which server identities are pinned, so that servers
+    /// with self-signed certificates may be verified.
+    pub pinned_certs: tls::PinnedCerts,
 }
 
 #[derive(Clone)]
@@ -31,9 +39,25 @@ pub struct Documents {
     pub csr_der: Vec<u8>,
 }
 
+#[derive(Clone, Debug)]
+pub struct ShadowConfig {
+    /// The servers to which the shadow identity is presented.
+    pub servers: tls::ShadowServers,
+    pub documents: ShadowDocuments,
+}
+
+#[derive(Clone)]
+pub struct ShadowDocuments {
+    pub id: LocalId,
+    pub trust_anchors_pem: String,
+    pub key_pkcs8: Vec<u8>,
+    pub crt_der: Vec<u8>,
+}
+
 pub struct Identity {
     addr: control::ControlAddr,
     receiver: creds::Receiver,
+    shadow: Option<tls::Shadow<creds::Receiver>>,
     ready: watch::Receiver<bool>,
     metrics: IdentityMetrics,
     task: Task,
@@ -54,14 +78,26 @@ struct NotifyReady {
 // === impl Config ===
 
 impl Config {
-    pub fn build(self, dns: dns::Resolver, client_metrics: ClientMetrics) -> Result<Identity> {
+    pub fn build(
+        self,
+        dns: dns::Resolver,
+        client_metrics: ClientMetrics,
+        tls_metrics: TlsMetrics,
+    ) -> Result<Identity> {
         let (store, receiver) = Mode::default().watch(
             (*self.documents.id).clone(),
             &self.documents.trust_anchors_pem,
             &self.documents.key_pkcs8,
             &self.documents.csr_der,
+            self.pinned_certs.clone(),
+            tls_metrics.clone(),
         )?;
 
+        let shadow = self
+            .shadow
+            .map(|shadow| shadow.build(self.pinned_certs, tls_metrics))
+            .transpose()?;
+
         let certify = Certify::from(self.certify);
         let metrics = certify.metrics();
 
@@ -84,6 +120,7 @@ impl Config {
         Ok(Identity {
             addr,
             receiver,
+            shadow,
             metrics,
             ready,
             task,
@@ -114,6 +151,39 @@ impl Credentials for NotifyReady {
     }
 }
 
+// === impl ShadowConfig ===
+
+impl ShadowConfig {
+    fn build(
+        self,
+        pins: tls::PinnedCerts,
+        metrics: TlsMetrics,
+    ) -> Result<tls::Shadow<creds::Receiver>> {
+        let ShadowDocuments {
+            id,
+            trust_anchors_pem,
+            key_pkcs8,
+            crt_der,
+        } = self.documents;
+        // Shadow credentials are provisioned statically, so no CSR is needed.
+        let (mut store, receiver) = Mode::default().watch(
+            (*id).clone(),
+            &trust_anchors_pem,
+            &key_pkcs8,
+            &[],
+            pins,
+            metrics,
+        )?;
+        // The expiry is only used to schedule renewals, which static
+        // credentials never need.
+        store.set_certificate(DerX509(crt_der), vec![], SystemTime::now())?;
+        Ok(tls::Shadow {
+            servers: self.servers,
+            identity: receiver,
+        })
+    }
+}
+
 // === impl Documents ===
 
 impl std::fmt::Debug for Documents {
@@ -125,6 +195,17 @@ impl std::fmt::Debug for Documents {
     }
 }
 
+// === impl ShadowDocuments ===
+
+impl std::fmt::Debug for ShadowDocuments {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShadowDocuments")
+            .field("id", &self.id)
+            .field("trust_anchors_pem", &self.trust_anchors_pem)
+            .finish()
+    }
+}
+
 // === impl Identity ===
 
 impl Identity {
@@ -146,6 +227,11 @@ impl Identity {
         self.receiver.clone()
     }
 
+    /// Returns the shadow identity, if one is configured.
+    pub fn shadow(&self) -> Option<tls::Shadow<creds::Receiver>> {
+        self.shadow.clone()
+    }
+
     pub fn metrics(&self) -> IdentityMetrics {
         self.metrics.clone()
     }
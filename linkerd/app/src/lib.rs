@@ -18,6 +18,7 @@ use linkerd_app_core::{
     control::ControlAddr,
     dns, drain,
     metrics::FmtMetrics,
+    startup::{StartupGate, StartupMode},
     svc::Param,
     telemetry,
     transport::{listen::Bind, ClientAddr, Local, OrigDstAddr, Remote, ServerAddr},
@@ -63,10 +64,16 @@ pub struct Config {
     /// If the proxy does not shut down gracefully within this timeout, it will
     /// terminate forcefully, closing any remaining connections.
     pub shutdown_grace_period: time::Duration,
+
+    /// Determines how connections accepted before the proxy's identity is
+    /// certified are handled. When unset, connections are not accepted until
+    /// the proxy is ready.
+    pub startup_mode: Option<StartupMode>,
 }
 
 pub struct App {
     admin: admin::Task,
+    discovery_events: outbound::DiscoveryEvents,
     drain: drain::Signal,
     dst: ControlAddr,
     identity: identity::Identity,
@@ -115,16 +122,26 @@ impl Config {
             outbound,
             gateway,
             tap,
+            startup_mode,
             ..
         } = self;
         debug!("building app");
-        let (metrics, report) = Metrics::new(admin.metrics_retain_idle, start_time);
+        let (metrics, report) = Metrics::new(
+            admin.metrics_retain_idle,
+            admin.metrics_latency_sample_rate,
+            start_time,
+        );
 
         let dns = dns.build();
 
         // Ensure that we've obtained a valid identity before binding any servers.
-        let identity = info_span!("identity")
-            .in_scope(|| identity.build(dns.resolver.clone(), metrics.control.clone()))?;
+        let identity = info_span!("identity").in_scope(|| {
+            identity.build(
+                dns.resolver.clone(),
+                metrics.control.clone(),
+                metrics.tls.clone(),
+            )
+        })?;
 
         let report = identity.metrics().and_report(report);
 
@@ -151,12 +168,24 @@ impl Config {
                 .in_scope(|| oc_collector.build(identity, dns, metrics, client_metrics))
         }?;
 
+        // When a startup mode is configured, connections are accepted
+        // immediately and are gated until the proxy becomes ready.
+        let (startup, startup_latch) = match startup_mode {
+            Some(mode) => {
+                let (startup, latch) = StartupGate::new(mode);
+                (startup, Some(latch))
+            }
+            None => (StartupGate::ready(), None),
+        };
+
         let runtime = ProxyRuntime {
             identity: identity.receiver(),
+            shadow_identity: identity.shadow(),
             metrics: metrics.proxy.clone(),
             tap: tap.registry(),
             span_sink: oc_collector.span_sink(),
             drain: drain_rx.clone(),
+            startup,
         };
         let inbound = Inbound::new(inbound, runtime.clone());
         let outbound = Outbound::new(outbound, runtime);
@@ -171,6 +200,8 @@ impl Config {
             let identity = identity.receiver().server();
             let metrics = inbound.metrics();
             let policy = inbound_policies.clone();
+            let endpoint_pins = outbound.endpoint_pins();
+            let discovery_evictions = outbound.discovery_evictions();
             let report = inbound
                 .metrics()
                 .and_report(outbound.metrics())
@@ -185,11 +216,14 @@ impl Config {
                     log_level,
                     drain_rx,
                     shutdown_tx,
+                    endpoint_pins,
+                    discovery_evictions,
                 )
             })?
         };
 
         let dst_addr = dst.addr.clone();
+        let discovery_events = outbound.discovery_events();
         let gateway = gateway::Gateway::new(gateway, inbound.clone(), outbound.clone())
             .stack(dst.resolve.clone(), dst.profiles.clone());
 
@@ -211,7 +245,18 @@ impl Config {
             let resolve = dst.resolve;
 
             Box::pin(async move {
-                Self::await_identity(identity_ready).await;
+                match startup_latch {
+                    Some(latch) => {
+                        tokio::spawn(
+                            async move {
+                                Self::await_identity(identity_ready).await;
+                                latch.release();
+                            }
+                            .in_current_span(),
+                        );
+                    }
+                    None => Self::await_identity(identity_ready).await,
+                }
 
                 tokio::spawn(
                     outbound
@@ -235,6 +280,7 @@ impl Config {
 
         Ok(App {
             admin,
+            discovery_events,
             dst: dst_addr,
             drain: drain_tx,
             identity,
@@ -286,6 +332,12 @@ impl App {
         &self.dst
     }
 
+    /// Returns a handle for subscribing to the outbound proxy's profile and
+    /// endpoint discovery events.
+    pub fn discovery_events(&self) -> &outbound::DiscoveryEvents {
+        &self.discovery_events
+    }
+
     pub fn local_identity(&self) -> identity::Name {
         self.identity.receiver().name().clone()
     }
@@ -347,7 +399,7 @@ impl App {
                                 .instrument(info_span!("identity").or_current()),
                         );
 
-                        let latch = admin.latch;
+                        let latch = admin.identity_latch;
                         tokio::spawn(
                             ready
                                 .map(move |()| {
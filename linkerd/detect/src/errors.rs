@@ -0,0 +1,94 @@
+//! Counts connections that failed protocol detection with an error.
+//!
+//! Detection errors are distinct from detection timeouts: the detector
+//! encountered an error, e.g. because the peer sent data that could not be
+//! interpreted or because the connection was reset, before a protocol could be
+//! determined.
+
+use linkerd_error::Error;
+use std::{
+    fmt, io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// A coarse description of a protocol detection error.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DetectErrorReason {
+    /// The peer sent data that could not be interpreted.
+    InvalidData,
+    /// The peer closed or reset the connection.
+    ConnectionClosed,
+    /// Some other I/O error occurred.
+    Io,
+    /// The detector failed for a reason other than I/O.
+    Other,
+}
+
+/// Counts detection errors by reason.
+///
+/// Clones share the same counters, so a clone may be handed to each detect
+/// layer while the registry is read by the metrics report.
+#[derive(Clone, Debug, Default)]
+pub struct DetectMetrics(Arc<[AtomicU64; 4]>);
+
+// === impl DetectMetrics ===
+
+impl DetectMetrics {
+    /// Returns the total number of connections that failed protocol detection
+    /// for the given reason.
+    pub fn errors(&self, reason: DetectErrorReason) -> u64 {
+        self.0[reason as usize].load(Ordering::Relaxed)
+    }
+
+    /// Counts a detection error, returning its reason.
+    pub(crate) fn record(&self, error: &Error) -> DetectErrorReason {
+        let reason = DetectErrorReason::from_error(&**error);
+        self.0[reason as usize].fetch_add(1, Ordering::Relaxed);
+        reason
+    }
+}
+
+// === impl DetectErrorReason ===
+
+impl DetectErrorReason {
+    pub const ALL: [Self; 4] = [
+        Self::InvalidData,
+        Self::ConnectionClosed,
+        Self::Io,
+        Self::Other,
+    ];
+
+    /// Classifies an error returned by a detector.
+    pub fn from_error(error: &(dyn std::error::Error + 'static)) -> Self {
+        let error = match linkerd_error::cause_ref::<io::Error>(error) {
+            Some(error) => error,
+            None => return Self::Other,
+        };
+        match error.kind() {
+            io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => Self::InvalidData,
+            io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::UnexpectedEof => Self::ConnectionClosed,
+            _ => Self::Io,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidData => "invalid_data",
+            Self::ConnectionClosed => "connection_closed",
+            Self::Io => "io",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for DetectErrorReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -1,6 +1,9 @@
 #![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
 #![forbid(unsafe_code)]
 
+mod errors;
+
+pub use self::errors::{DetectErrorReason, DetectMetrics};
 use bytes::BytesMut;
 use linkerd_error::Error;
 use linkerd_io as io;
@@ -17,6 +20,9 @@ use tokio::time;
 use tower::util::ServiceExt;
 use tracing::{debug, info, trace};
 
+#[cfg(test)]
+mod tests;
+
 #[async_trait::async_trait]
 pub trait Detect<I>: Clone + Send + Sync + 'static {
     type Protocol: Send;
@@ -41,16 +47,18 @@ pub struct Config<D> {
     pub timeout: time::Duration,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct NewDetectService<P, D, N> {
     inner: N,
     params: P,
+    metrics: DetectMetrics,
     _detect: std::marker::PhantomData<fn() -> D>,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct DetectService<D, N> {
     config: Config<D>,
+    metrics: DetectMetrics,
     inner: N,
 }
 
@@ -81,19 +89,20 @@ impl<D: Default> Config<D> {
 // === impl NewDetectService ===
 
 impl<P, D, N> NewDetectService<P, D, N> {
-    pub fn new(params: P, inner: N) -> Self {
+    pub fn new(params: P, metrics: DetectMetrics, inner: N) -> Self {
         Self {
             inner,
             params,
+            metrics,
             _detect: std::marker::PhantomData,
         }
     }
 
-    pub fn layer(params: P) -> impl layer::Layer<N, Service = Self> + Clone
+    pub fn layer(params: P, metrics: DetectMetrics) -> impl layer::Layer<N, Service = Self> + Clone
     where
         P: Clone,
     {
-        layer::mk(move |inner| Self::new(params.clone(), inner))
+        layer::mk(move |inner| Self::new(params.clone(), metrics.clone(), inner))
     }
 }
 
@@ -108,6 +117,7 @@ where
         let config = self.params.extract_param(&target);
         DetectService {
             config,
+            metrics: self.metrics.clone(),
             inner: self.inner.new_service(target),
         }
     }
@@ -140,6 +150,7 @@ where
             capacity,
             timeout,
         } = self.config.clone();
+        let metrics = self.metrics.clone();
         let inner = self.inner.clone();
         Box::pin(async move {
             trace!(%capacity, ?timeout, "Starting protocol detection");
@@ -156,7 +167,11 @@ where
                     Ok(protocol)
                 }
                 Err(_) => Err(DetectTimeoutError(timeout, std::marker::PhantomData)),
-                Ok(Err(e)) => return Err(e),
+                Ok(Err(e)) => {
+                    let reason = metrics.record(&e);
+                    debug!(%reason, error = %e, "Protocol detection failed");
+                    return Err(e);
+                }
             };
 
             trace!("Dispatching connection");
@@ -0,0 +1,52 @@
+use super::*;
+use io::AsyncReadExt;
+use linkerd_stack::{service_fn, CloneParam, Service};
+
+/// Detects a protocol whose connections must start with a magic prefix,
+/// failing on any other data.
+#[derive(Clone, Debug)]
+struct DetectMagic;
+
+#[async_trait::async_trait]
+impl<I: io::AsyncRead + Send + Unpin + 'static> Detect<I> for DetectMagic {
+    type Protocol = ();
+
+    async fn detect(&self, io: &mut I, buf: &mut BytesMut) -> StdResult<Option<()>, Error> {
+        io.read_buf(buf).await?;
+        if buf.starts_with(b"MAGIC") {
+            return Ok(Some(()));
+        }
+        Err(io::Error::new(io::ErrorKind::InvalidData, "not magic").into())
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn counts_detection_errors() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let config = Config {
+        detect: DetectMagic,
+        capacity: 1024,
+        timeout: time::Duration::from_secs(10),
+    };
+    let metrics = DetectMetrics::default();
+    let mut svc = NewDetectService::new(CloneParam::from(config), metrics.clone(), |()| {
+        |_: Result<()>| service_fn(|_| std::future::ready(Ok::<_, Error>(())))
+    })
+    .new_service(());
+
+    let io = tokio_test::io::Builder::new()
+        .read(b"garbage garbage garbage")
+        .build();
+    let error = svc.call(io).await.expect_err("detection must fail");
+    assert_eq!(
+        DetectErrorReason::from_error(&*error),
+        DetectErrorReason::InvalidData
+    );
+    assert_eq!(metrics.errors(DetectErrorReason::InvalidData), 1);
+
+    // Successfully detected connections are not counted.
+    let io = tokio_test::io::Builder::new().read(b"MAGIC").build();
+    svc.call(io).await.expect("detection must succeed");
+    assert_eq!(metrics.errors(DetectErrorReason::InvalidData), 1);
+}
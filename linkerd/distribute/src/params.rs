@@ -23,7 +23,9 @@ pub enum Distribution<K> {
     RandomAvailable(Arc<WeightedKeys<K>>),
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+/// Weighted keys, followed by any fallback keys, which are unweighted and
+/// only used when none of the weighted keys are available.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct WeightedKeys<K> {
     keys: Vec<K>,
     weights: Vec<u32>,
@@ -87,6 +89,28 @@ impl<K> Distribution<K> {
         })))
     }
 
+    /// Returns a distribution that uses the `fallback` backend when none of
+    /// this distribution's backends are available.
+    pub fn with_fallback(self, fallback: K) -> Self
+    where
+        K: Clone + PartialEq,
+    {
+        if self.keys().contains(&fallback) {
+            return self;
+        }
+        match self {
+            Self::Empty => Self::from(fallback),
+            Self::FirstAvailable(keys) => {
+                Self::first_available(keys.iter().cloned().chain(Some(fallback)))
+            }
+            Self::RandomAvailable(keys) => {
+                let mut keys = (*keys).clone();
+                keys.keys.push(fallback);
+                Self::RandomAvailable(Arc::new(keys))
+            }
+        }
+    }
+
     pub(crate) fn keys(&self) -> &[K] {
         match self {
             Self::Empty => &[],
@@ -103,6 +127,11 @@ impl<K> WeightedKeys<K> {
         &self.keys
     }
 
+    /// Returns the number of weighted keys. Keys after these are fallbacks.
+    pub(crate) fn weighted_len(&self) -> usize {
+        self.weights.len()
+    }
+
     pub(crate) fn index(&self) -> WeightedIndex<u32> {
         WeightedIndex::new(self.weights.iter().copied()).expect("distribution must be valid")
     }
@@ -118,6 +118,16 @@ where
                         }
                     }
                 }
+
+                // Use the first available fallback backend, if any.
+                let weighted = keys.weighted_len();
+                for (idx, svc) in self.backends.values_mut().enumerate().skip(weighted) {
+                    if svc.poll_ready(cx)?.is_ready() {
+                        tracing::debug!("no weighted backends available; using fallback");
+                        self.ready_idx = Some(idx);
+                        return Poll::Ready(Ok(()));
+                    }
+                }
             }
         }
 
@@ -342,4 +352,31 @@ mod tests {
         }
         assert_ready_ok!(call.poll());
     }
+
+    #[test]
+    fn random_available_uses_fallback() {
+        let (mulder, mut mulder_ctl) = mock::pair();
+        let (scully, mut scully_ctl) = mock::pair();
+        let (skinner, mut skinner_ctl) = mock::pair();
+        let mut dist_svc = mock::Spawn::new(Distribute::new(
+            vec![("mulder", mulder), ("scully", scully), ("skinner", skinner)]
+                .into_iter()
+                .collect(),
+            Distribution::random_available([("mulder", 1), ("scully", 1)])
+                .unwrap()
+                .with_fallback("skinner"),
+        ));
+
+        mulder_ctl.allow(0);
+        scully_ctl.allow(0);
+        skinner_ctl.allow(1);
+        assert_ready_ok!(dist_svc.poll_ready());
+        assert_eq!(dist_svc.get_ref().ready_idx, Some(2));
+        let mut call = task::spawn(dist_svc.call(()));
+        match assert_ready!(skinner_ctl.poll_request()) {
+            Some(((), rsp)) => rsp.send_response(()),
+            _ => panic!("expected request"),
+        }
+        assert_ready_ok!(call.poll());
+    }
 }
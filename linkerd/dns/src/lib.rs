@@ -1,20 +1,44 @@
 #![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
 #![forbid(unsafe_code)]
 
+use futures::future;
 use linkerd_dns_name::NameRef;
 pub use linkerd_dns_name::{InvalidName, Name, Suffix};
-use std::{fmt, net};
+use std::{fmt, future::Future, net};
 use thiserror::Error;
 use tokio::time::{self, Instant};
 use tracing::{debug, trace};
 pub use trust_dns_resolver::config::ResolverOpts;
 use trust_dns_resolver::{
-    config::ResolverConfig, error, proto::rr::rdata, system_conf, AsyncResolver, TokioAsyncResolver,
+    config::ResolverConfig,
+    error,
+    proto::rr::{rdata, RecordType},
+    system_conf, AsyncResolver, TokioAsyncResolver,
 };
 
 #[derive(Clone)]
 pub struct Resolver {
     dns: TokioAsyncResolver,
+    query_strategy: Option<QueryStrategy>,
+}
+
+/// Determines which address records are queried when a name is resolved to
+/// A/AAAA records, and how their results are combined.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QueryStrategy {
+    /// Only A records are queried.
+    Ipv4Only,
+    /// Only AAAA records are queried.
+    Ipv6Only,
+    /// A and AAAA records are queried concurrently and all of their addresses
+    /// are returned.
+    Both,
+    /// A records are queried and AAAA records are only queried if no IPv4
+    /// addresses are found.
+    PreferIpv4,
+    /// AAAA records are queried and A records are only queried if no IPv6
+    /// addresses are found.
+    PreferIpv6,
 }
 
 pub trait ConfigureResolver {
@@ -69,7 +93,20 @@ impl Resolver {
         // This function is synchronous, but needs to be called within the Tokio
         // 0.2 runtime context, since it gets a handle.
         let dns = AsyncResolver::tokio(config, opts).expect("system DNS config must be valid");
-        Resolver { dns }
+        Resolver {
+            dns,
+            query_strategy: None,
+        }
+    }
+
+    /// Configures the records queried to resolve names to IP addresses.
+    ///
+    /// By default, the system resolver's lookup strategy is used.
+    pub fn with_query_strategy(self, query_strategy: QueryStrategy) -> Self {
+        Self {
+            query_strategy: Some(query_strategy),
+            ..self
+        }
     }
 
     /// Resolves a name to a set of addresses, preferring SRV records to normal A/AAAA
@@ -103,12 +140,34 @@ impl Resolver {
         name: NameRef<'_>,
     ) -> Result<(Vec<net::IpAddr>, time::Sleep), ARecordError> {
         debug!(%name, "Resolving an A/AAAA record");
-        let lookup = self.dns.lookup_ip(name.as_str()).await?;
-        let valid_until = Instant::from_std(lookup.valid_until());
-        let ips = lookup.iter().collect::<Vec<_>>();
+        let (ips, valid_until) = match self.query_strategy {
+            Some(strategy) => {
+                query_ips(strategy, |record_type| self.lookup_ips(name, record_type)).await?
+            }
+            None => {
+                let lookup = self.dns.lookup_ip(name.as_str()).await?;
+                let valid_until = Instant::from_std(lookup.valid_until());
+                (lookup.iter().collect(), valid_until)
+            }
+        };
         Ok((ips, time::sleep_until(valid_until)))
     }
 
+    async fn lookup_ips(
+        &self,
+        name: NameRef<'_>,
+        record_type: RecordType,
+    ) -> Result<(Vec<net::IpAddr>, Instant), error::ResolveError> {
+        trace!(%name, %record_type, "Querying address records");
+        let lookup = self.dns.lookup(name.as_str(), record_type).await?;
+        let valid_until = Instant::from_std(lookup.valid_until());
+        let ips = lookup
+            .iter()
+            .filter_map(|rdata| rdata.to_ip_addr())
+            .collect();
+        Ok((ips, valid_until))
+    }
+
     async fn resolve_srv(
         &self,
         name: NameRef<'_>,
@@ -145,6 +204,45 @@ impl Resolver {
     }
 }
 
+/// Queries the address records selected by a strategy, returning the resolved
+/// addresses and the time until which they are valid.
+async fn query_ips<E, F, Fut>(
+    strategy: QueryStrategy,
+    lookup: F,
+) -> Result<(Vec<net::IpAddr>, Instant), E>
+where
+    F: Fn(RecordType) -> Fut,
+    Fut: Future<Output = Result<(Vec<net::IpAddr>, Instant), E>>,
+{
+    let (preferred, fallback) = match strategy {
+        QueryStrategy::Ipv4Only => return lookup(RecordType::A).await,
+        QueryStrategy::Ipv6Only => return lookup(RecordType::AAAA).await,
+        QueryStrategy::Both => {
+            return match future::join(lookup(RecordType::A), lookup(RecordType::AAAA)).await {
+                (Ok((mut ips, a_valid_until)), Ok((aaaa_ips, aaaa_valid_until))) => {
+                    ips.extend(aaaa_ips);
+                    Ok((ips, a_valid_until.min(aaaa_valid_until)))
+                }
+                (Ok(res), Err(_)) | (Err(_), Ok(res)) => Ok(res),
+                (Err(error), Err(_)) => Err(error),
+            };
+        }
+        QueryStrategy::PreferIpv4 => (RecordType::A, RecordType::AAAA),
+        QueryStrategy::PreferIpv6 => (RecordType::AAAA, RecordType::A),
+    };
+
+    let res = lookup(preferred).await;
+    if matches!(&res, Ok((ips, _)) if !ips.is_empty()) {
+        return res;
+    }
+    debug!(%preferred, %fallback, "No preferred addresses found");
+    match (res, lookup(fallback).await) {
+        (_, Ok(res)) => Ok(res),
+        (Ok(res), Err(_)) => Ok(res),
+        (Err(error), Err(_)) => Err(error),
+    }
+}
+
 /// Note: `AsyncResolver` does not implement `Debug`, so we must manually
 ///       implement this.
 impl fmt::Debug for Resolver {
@@ -185,8 +283,14 @@ impl ResolveError {
 
 #[cfg(test)]
 mod tests {
-    use super::{Name, Suffix};
-    use std::str::FromStr;
+    use super::{query_ips, Name, QueryStrategy, RecordType, Suffix};
+    use futures::future;
+    use std::{
+        cell::RefCell,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr},
+        str::FromStr,
+    };
+    use tokio::time::{Duration, Instant};
 
     #[test]
     fn test_dns_name_parsing() {
@@ -289,6 +393,79 @@ mod tests {
 
         assert!(Suffix::from_str("").is_err(), "suffix must not be empty");
     }
+
+    const IPV4: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    const IPV6: IpAddr = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+
+    /// Resolves addresses with the given strategy against a name that has the
+    /// given A and AAAA records, returning the record types that were queried
+    /// and the addresses that were returned. A name without records of a type
+    /// fails lookups of that type.
+    async fn query(
+        strategy: QueryStrategy,
+        a: Option<IpAddr>,
+        aaaa: Option<IpAddr>,
+    ) -> (Vec<RecordType>, Result<Vec<IpAddr>, ()>) {
+        let queried = RefCell::new(Vec::new());
+        let valid_until = Instant::now() + Duration::from_secs(10);
+        let res = query_ips(strategy, |record_type| {
+            queried.borrow_mut().push(record_type);
+            let ip = match record_type {
+                RecordType::A => a,
+                RecordType::AAAA => aaaa,
+                _ => unreachable!("unexpected record type: {}", record_type),
+            };
+            future::ready(ip.map(|ip| (vec![ip], valid_until)).ok_or(()))
+        })
+        .await;
+        (queried.into_inner(), res.map(|(ips, _)| ips))
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn query_strategy_single_family() {
+        let (queried, ips) = query(QueryStrategy::Ipv4Only, Some(IPV4), Some(IPV6)).await;
+        assert_eq!(queried, vec![RecordType::A]);
+        assert_eq!(ips, Ok(vec![IPV4]));
+
+        let (queried, ips) = query(QueryStrategy::Ipv6Only, Some(IPV4), Some(IPV6)).await;
+        assert_eq!(queried, vec![RecordType::AAAA]);
+        assert_eq!(ips, Ok(vec![IPV6]));
+
+        let (_, ips) = query(QueryStrategy::Ipv4Only, None, Some(IPV6)).await;
+        assert_eq!(ips, Err(()), "IPv6 addresses must not be returned");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn query_strategy_both() {
+        let (queried, ips) = query(QueryStrategy::Both, Some(IPV4), Some(IPV6)).await;
+        assert_eq!(queried, vec![RecordType::A, RecordType::AAAA]);
+        assert_eq!(ips, Ok(vec![IPV4, IPV6]));
+
+        let (_, ips) = query(QueryStrategy::Both, None, Some(IPV6)).await;
+        assert_eq!(ips, Ok(vec![IPV6]));
+
+        let (_, ips) = query(QueryStrategy::Both, None, None).await;
+        assert_eq!(ips, Err(()));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn query_strategy_prefer() {
+        let (queried, ips) = query(QueryStrategy::PreferIpv4, Some(IPV4), Some(IPV6)).await;
+        assert_eq!(queried, vec![RecordType::A]);
+        assert_eq!(ips, Ok(vec![IPV4]));
+
+        let (queried, ips) = query(QueryStrategy::PreferIpv4, None, Some(IPV6)).await;
+        assert_eq!(queried, vec![RecordType::A, RecordType::AAAA]);
+        assert_eq!(ips, Ok(vec![IPV6]));
+
+        let (queried, ips) = query(QueryStrategy::PreferIpv6, Some(IPV4), Some(IPV6)).await;
+        assert_eq!(queried, vec![RecordType::AAAA]);
+        assert_eq!(ips, Ok(vec![IPV6]));
+
+        let (queried, ips) = query(QueryStrategy::PreferIpv6, Some(IPV4), None).await;
+        assert_eq!(queried, vec![RecordType::AAAA, RecordType::A]);
+        assert_eq!(ips, Ok(vec![IPV4]));
+    }
 }
 
 #[cfg(fuzzing)]
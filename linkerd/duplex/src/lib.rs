@@ -14,14 +14,26 @@ use futures::ready;
 use linkerd_io::{self as io, AsyncRead, AsyncWrite};
 use pin_project::pin_project;
 use std::task::{Context, Poll};
-use std::{future::Future, pin::Pin};
-use tracing::{error, trace};
+use std::{future::Future, pin::Pin, time::Duration};
+use tokio::time;
+use tracing::{debug, error, trace};
+
+/// The default capacity of each half's copy buffer.
+pub const DEFAULT_BUFFER_CAPACITY: usize = 8 * 1024;
 
 /// A future piping data bi-directionally to In and Out.
 #[pin_project]
 pub struct Duplex<In, Out> {
     half_in: HalfDuplex<In>,
     half_out: HalfDuplex<Out>,
+    idle: Option<IdleTimeout>,
+}
+
+/// Fails a `Duplex` when no data has been read in either direction for a
+/// timeout.
+struct IdleTimeout {
+    timeout: Duration,
+    sleep: Pin<Box<time::Sleep>>,
 }
 
 #[pin_project]
@@ -33,6 +45,8 @@ struct HalfDuplex<T> {
     io: T,
     direction: &'static str,
     flushing: bool,
+    // The total number of bytes read from `io`.
+    read_bytes: u64,
 }
 
 /// A buffer used to copy bytes from one IO to another.
@@ -66,9 +80,29 @@ where
     Out: AsyncRead + AsyncWrite + Unpin,
 {
     pub fn new(in_io: In, out_io: Out) -> Self {
+        Self::with_buffer_capacity(in_io, out_io, DEFAULT_BUFFER_CAPACITY)
+    }
+
+    /// Creates a `Duplex` that copies data in each direction through a buffer
+    /// of `capacity` bytes.
+    pub fn with_buffer_capacity(in_io: In, out_io: Out, capacity: usize) -> Self {
+        assert!(capacity > 0, "buffer capacity must be non-zero");
         Duplex {
-            half_in: HalfDuplex::new(in_io, "client->server"),
-            half_out: HalfDuplex::new(out_io, "server->client"),
+            half_in: HalfDuplex::new(in_io, "client->server", capacity),
+            half_out: HalfDuplex::new(out_io, "server->client", capacity),
+            idle: None,
+        }
+    }
+
+    /// Fails with a `TimedOut` error when no data is read in either direction
+    /// for `timeout`.
+    pub fn with_idle_timeout(self, timeout: Duration) -> Self {
+        Self {
+            idle: Some(IdleTimeout {
+                timeout,
+                sleep: Box::pin(time::sleep(timeout)),
+            }),
+            ..self
         }
     }
 }
@@ -86,13 +120,29 @@ where
         // return early if the first half isn't ready, but the other half
         // could make progress.
         trace!("poll");
+        let read_bytes = this.half_in.read_bytes + this.half_out.read_bytes;
         let _ = this.half_in.copy_into(this.half_out, cx)?;
         let _ = this.half_out.copy_into(this.half_in, cx)?;
         if this.half_in.is_done() && this.half_out.is_done() {
-            Poll::Ready(Ok(()))
-        } else {
-            Poll::Pending
+            return Poll::Ready(Ok(()));
+        }
+
+        if let Some(idle) = this.idle.as_mut() {
+            if this.half_in.read_bytes + this.half_out.read_bytes != read_bytes {
+                idle.sleep
+                    .as_mut()
+                    .reset(time::Instant::now() + idle.timeout);
+            }
+            if idle.sleep.as_mut().poll(cx).is_ready() {
+                debug!(timeout = ?idle.timeout, "Connection idle");
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "connection idle timeout",
+                )));
+            }
         }
+
+        Poll::Pending
     }
 }
 
@@ -100,13 +150,14 @@ impl<T> HalfDuplex<T>
 where
     T: AsyncRead + Unpin,
 {
-    fn new(io: T, direction: &'static str) -> Self {
+    fn new(io: T, direction: &'static str, capacity: usize) -> Self {
         Self {
-            buf: Some(CopyBuf::new()),
+            buf: Some(CopyBuf::new(capacity)),
             is_shutdown: false,
             io,
             direction,
             flushing: false,
+            read_bytes: 0,
         }
     }
 
@@ -224,6 +275,7 @@ where
 
             // If data was read, return the number of bytes read.
             if sz > 0 {
+                self.read_bytes += sz as u64;
                 return Poll::Ready(Ok(Buffered::Read(sz)));
             }
         }
@@ -283,6 +335,11 @@ where
     fn is_done(&self) -> bool {
         self.is_shutdown
     }
+
+    #[cfg(test)]
+    fn capacity(&self) -> usize {
+        self.buf.as_ref().map(|b| b.buf.len()).unwrap_or(0)
+    }
 }
 
 fn write_zero() -> io::Error {
@@ -290,9 +347,9 @@ fn write_zero() -> io::Error {
 }
 
 impl CopyBuf {
-    fn new() -> Self {
+    fn new(capacity: usize) -> Self {
         CopyBuf {
-            buf: Box::new([0; 8 * 1024]),
+            buf: vec![0; capacity].into_boxed_slice(),
             read_pos: 0,
             write_pos: 0,
         }
@@ -342,3 +399,101 @@ unsafe impl BufMut for CopyBuf {
         self.write_pos += cnt;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::time;
+
+    #[test]
+    fn default_buffer_capacity() {
+        let (a, _) = io::duplex(1024);
+        let (b, _) = io::duplex(1024);
+        let duplex = Duplex::new(a, b);
+        assert_eq!(duplex.half_in.capacity(), DEFAULT_BUFFER_CAPACITY);
+        assert_eq!(duplex.half_out.capacity(), DEFAULT_BUFFER_CAPACITY);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn copies_with_configured_buffer_capacity() {
+        const CAPACITY: usize = 64 * 1024;
+
+        let (mut client, client_proxy) = io::duplex(CAPACITY);
+        let (server_proxy, mut server) = io::duplex(CAPACITY);
+        let duplex = Duplex::with_buffer_capacity(client_proxy, server_proxy, CAPACITY);
+        assert_eq!(duplex.half_in.capacity(), CAPACITY);
+        assert_eq!(duplex.half_out.capacity(), CAPACITY);
+        let proxy = tokio::spawn(duplex);
+
+        // Send several buffers' worth of data in each direction.
+        let data = (0..4 * CAPACITY).map(|i| i as u8).collect::<Vec<u8>>();
+        let server = tokio::spawn({
+            let data = data.clone();
+            async move {
+                let mut req = Vec::new();
+                server.read_to_end(&mut req).await.unwrap();
+                assert_eq!(req, data);
+                server.write_all(&data).await.unwrap();
+                server.shutdown().await.unwrap();
+            }
+        });
+
+        client.write_all(&data).await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut rsp = Vec::new();
+        client.read_to_end(&mut rsp).await.unwrap();
+        assert_eq!(rsp, data);
+
+        server.await.unwrap();
+        proxy.await.unwrap().expect("duplex must complete");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn idle_timeout_closes_idle_connection() {
+        time::pause();
+        const TIMEOUT: Duration = Duration::from_secs(10);
+
+        let (_client, client_proxy) = io::duplex(1024);
+        let (server_proxy, _server) = io::duplex(1024);
+        let start = time::Instant::now();
+        let res = Duplex::new(client_proxy, server_proxy)
+            .with_idle_timeout(TIMEOUT)
+            .await;
+        let err = res.expect_err("idle connection must time out");
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(
+            time::Instant::now().saturating_duration_since(start),
+            TIMEOUT
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn idle_timeout_retains_active_connection() {
+        time::pause();
+        const TIMEOUT: Duration = Duration::from_secs(10);
+
+        let (mut client, client_proxy) = io::duplex(1024);
+        let (server_proxy, mut server) = io::duplex(1024);
+        let proxy =
+            tokio::spawn(Duplex::new(client_proxy, server_proxy).with_idle_timeout(TIMEOUT));
+
+        // Exchange data in alternating directions for several timeouts.
+        for i in 0..6 {
+            time::sleep(TIMEOUT / 2).await;
+            let mut buf = [0u8; 4];
+            if i % 2 == 0 {
+                client.write_all(b"ping").await.unwrap();
+                server.read_exact(&mut buf).await.unwrap();
+            } else {
+                server.write_all(b"pong").await.unwrap();
+                client.read_exact(&mut buf).await.unwrap();
+            }
+            assert!(!proxy.is_finished(), "active connection must not time out");
+        }
+
+        client.shutdown().await.unwrap();
+        server.shutdown().await.unwrap();
+        proxy.await.unwrap().expect("duplex must complete");
+    }
+}
@@ -0,0 +1,98 @@
+use bytes::{Buf, BytesMut};
+use http::{HeaderMap, HeaderValue};
+use http_body::Body;
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// A request body that copies up to a bounded number of its leading bytes so
+/// that they may be included in the access log.
+#[pin_project]
+#[derive(Debug)]
+pub struct CaptureBody<B> {
+    #[pin]
+    inner: B,
+    capture: Option<Capture>,
+}
+
+/// A handle to the bytes captured from a request body.
+#[derive(Clone, Debug)]
+pub(crate) struct Capture {
+    buf: Arc<Mutex<BytesMut>>,
+    limit: usize,
+}
+
+// === impl CaptureBody ===
+
+impl<B> CaptureBody<B> {
+    pub(crate) fn new(inner: B, capture: Option<Capture>) -> Self {
+        Self { inner, capture }
+    }
+}
+
+impl<B: Body> Body for CaptureBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        let data = futures_core::ready!(this.inner.poll_data(cx));
+        if let (Some(Ok(data)), Some(capture)) = (&data, this.capture.as_ref()) {
+            if !capture.extend(data.chunk()) {
+                // Stop copying once the limit has been reached.
+                *this.capture = None;
+            }
+        }
+        Poll::Ready(data)
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap<HeaderValue>>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// === impl Capture ===
+
+impl Capture {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            buf: Arc::new(Mutex::new(BytesMut::with_capacity(limit))),
+            limit,
+        }
+    }
+
+    /// Copies bytes from `chunk` until the limit is reached. Returns false if
+    /// no more bytes may be captured.
+    fn extend(&self, chunk: &[u8]) -> bool {
+        let mut buf = self.buf.lock();
+        let n = chunk.len().min(self.limit - buf.len());
+        buf.extend_from_slice(&chunk[..n]);
+        buf.len() < self.limit
+    }
+
+    /// Returns the bytes captured so far, lossily decoded as UTF-8.
+    pub(crate) fn snippet(&self) -> String {
+        String::from_utf8_lossy(&self.buf.lock()).into_owned()
+    }
+}
@@ -1,6 +1,12 @@
 #![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
 #![forbid(unsafe_code)]
 
+mod body;
+#[cfg(test)]
+mod tests;
+
+use self::body::Capture;
+pub use self::body::CaptureBody;
 use futures_core::TryFuture;
 use linkerd_identity as identity;
 use linkerd_proxy_transport::{ClientAddr, Remote};
@@ -22,6 +28,7 @@ use tracing::{field, span, Level, Span};
 #[derive(Clone, Debug)]
 pub struct NewAccessLog<N> {
     inner: N,
+    error_body_limit: Option<usize>,
 }
 
 #[derive(Clone, Debug)]
@@ -29,12 +36,14 @@ pub struct AccessLogContext<S> {
     inner: S,
     client_addr: SocketAddr,
     client_id: Option<identity::Name>,
+    error_body_limit: Option<usize>,
 }
 
 struct ResponseFutureInner {
     span: Span,
     start: Instant,
     processing: Duration,
+    capture: Option<Capture>,
 }
 
 #[pin_project]
@@ -58,9 +67,17 @@ impl<N> NewAccessLog<N> {
     /// Recording the access log will introduce additional overhead in the
     /// request path, but this is largely avoided when access logging is not
     /// enabled.
+    ///
+    /// When `error_body_limit` is set, up to that many bytes of each request
+    /// body are buffered so that they may be recorded, as `request_body`, in
+    /// the access log of requests that receive error responses.
     #[inline]
-    pub fn layer() -> impl svc::layer::Layer<N, Service = Self> {
-        svc::layer::mk(|inner| NewAccessLog { inner })
+    pub fn layer(error_body_limit: Option<usize>) -> impl svc::layer::Layer<N, Service = Self> {
+        let error_body_limit = error_body_limit.filter(|l| *l > 0);
+        svc::layer::mk(move |inner| NewAccessLog {
+            inner,
+            error_body_limit,
+        })
     }
 }
 
@@ -82,13 +99,14 @@ where
             inner,
             client_addr,
             client_id,
+            error_body_limit: self.error_body_limit,
         }
     }
 }
 
 impl<S, B1, B2> svc::Service<http::Request<B1>> for AccessLogContext<S>
 where
-    S: svc::Service<http::Request<B1>, Response = http::Response<B2>>,
+    S: svc::Service<http::Request<CaptureBody<B1>>, Response = http::Response<B2>>,
 {
     type Response = S::Response;
     type Error = S::Error;
@@ -133,6 +151,7 @@ where
             processing_ns = field::Empty,
             user_agent = get_header(http::header::USER_AGENT),
             host = get_header(http::header::HOST),
+            request_body = field::Empty,
         );
 
         // The access log span is only enabled by the `tracing` subscriber if
@@ -141,15 +160,18 @@ where
         if span.is_disabled() {
             return AccessLogFuture {
                 data: None,
-                inner: self.inner.call(request),
+                inner: self.inner.call(request.map(|b| CaptureBody::new(b, None))),
             };
         }
 
+        let capture = self.error_body_limit.map(Capture::new);
+        let request = request.map(|b| CaptureBody::new(b, capture.clone()));
         AccessLogFuture {
             data: Some(ResponseFutureInner {
                 span,
                 start: Instant::now(),
                 processing: Duration::from_secs(0),
+                capture,
             }),
             inner: self.inner.call(request),
         }
@@ -199,6 +221,13 @@ where
         span.record("total_ns", &field::display(total_ns));
         span.record("processing_ns", &field::display(processing_ns));
 
+        // Only the request bodies of failed requests are logged.
+        if response.status().is_client_error() || response.status().is_server_error() {
+            if let Some(capture) = data.capture.take() {
+                span.record("request_body", &field::debug(capture.snippet()));
+            }
+        }
+
         Poll::Ready(Ok(response))
     }
 }
@@ -0,0 +1,94 @@
+use super::*;
+use bytes::Bytes;
+use http_body::{Body, Full};
+use parking_lot::Mutex;
+use std::{fmt, sync::Arc};
+use svc::{layer::Layer as _, ServiceExt};
+use tracing_subscriber::{layer::SubscriberExt, Layer};
+
+#[derive(Clone, Debug)]
+struct Target;
+
+impl Param<tls::ConditionalServerTls> for Target {
+    fn param(&self) -> tls::ConditionalServerTls {
+        tls::ConditionalServerTls::None(tls::NoServerTls::Loopback)
+    }
+}
+
+impl Param<Remote<ClientAddr>> for Target {
+    fn param(&self) -> Remote<ClientAddr> {
+        Remote(ClientAddr(([127, 0, 0, 1], 12345).into()))
+    }
+}
+
+/// Records the `request_body` field of access log spans.
+#[derive(Clone, Default)]
+struct RequestBodies(Arc<Mutex<Vec<String>>>);
+
+impl<S: tracing::Subscriber> Layer<S> for RequestBodies {
+    fn on_record(
+        &self,
+        _: &span::Id,
+        values: &span::Record<'_>,
+        _: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        struct Visit<'a>(&'a mut Vec<String>);
+        impl field::Visit for Visit<'_> {
+            fn record_debug(&mut self, field: &field::Field, value: &dyn fmt::Debug) {
+                if field.name() == "request_body" {
+                    self.0.push(format!("{:?}", value));
+                }
+            }
+        }
+        values.record(&mut Visit(&mut *self.0.lock()));
+    }
+}
+
+async fn send(error_body_limit: Option<usize>, path: &str) {
+    let svc = NewAccessLog::layer(error_body_limit)
+        .layer(|_: Target| {
+            svc::service_fn(|req: http::Request<CaptureBody<Full<Bytes>>>| async move {
+                let status = match req.uri().path() {
+                    "/fail" => http::StatusCode::INTERNAL_SERVER_ERROR,
+                    _ => http::StatusCode::OK,
+                };
+                let mut body = req.into_body();
+                while body.data().await.is_some() {}
+                Ok::<_, std::convert::Infallible>(
+                    http::Response::builder().status(status).body(()).unwrap(),
+                )
+            })
+        })
+        .new_service(Target);
+
+    let req = http::Request::post(path)
+        .body(Full::new(Bytes::from_static(b"hello world")))
+        .unwrap();
+    svc.oneshot(req).await.unwrap();
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn captures_error_request_bodies() {
+    let bodies = RequestBodies::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(bodies.clone()));
+
+    send(Some(5), "/ok").await;
+    assert!(
+        bodies.0.lock().is_empty(),
+        "successful request bodies must not be logged"
+    );
+
+    send(Some(5), "/fail").await;
+    assert_eq!(*bodies.0.lock(), vec![format!("{:?}", "hello")]);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn disabled_by_default() {
+    let bodies = RequestBodies::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(bodies.clone()));
+
+    send(None, "/fail").await;
+    assert!(bodies.0.lock().is_empty());
+}
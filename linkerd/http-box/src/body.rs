@@ -72,6 +72,14 @@ impl Body for BoxBody {
     }
 }
 
+impl From<bytes::Bytes> for Data {
+    fn from(bytes: bytes::Bytes) -> Self {
+        Self {
+            inner: Box::new(bytes),
+        }
+    }
+}
+
 impl bytes::Buf for Data {
     fn remaining(&self) -> usize {
         self.inner.remaining()
@@ -4,19 +4,38 @@ mod service;
 pub use self::service::{NewHttpMetrics, ResponseBody};
 use super::Report;
 use linkerd_http_classify::ClassifyResponse;
-use linkerd_metrics::{latency, Counter, FmtMetrics, Histogram, LastUpdate, NewMetrics};
+use linkerd_metrics::{latency, Counter, FmtMetrics, Histogram, LastUpdate};
 use linkerd_stack::{self as svc, layer};
-use std::{collections::HashMap, fmt::Debug, hash::Hash};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    hash::Hash,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 use tokio::time::{Duration, Instant};
 
 type Registry<T, C> = super::Registry<T, Metrics<C>>;
 
 #[derive(Debug)]
-pub struct Requests<T, C>(Registry<T, C>)
+pub struct Requests<T, C>(Registry<T, C>, LatencySampling)
 where
     T: Hash + Eq,
     C: Hash + Eq;
 
+/// Determines which requests have their latencies recorded.
+///
+/// Updating latency histograms is comparatively expensive, so only a fraction
+/// of requests may be sampled. Sampling is deterministic, so that exactly the
+/// configured fraction of requests is observed.
+#[derive(Clone, Debug)]
+pub(crate) struct LatencySampling {
+    rate: f64,
+    requests: Arc<AtomicU64>,
+}
+
 #[derive(Debug)]
 pub struct Metrics<C>
 where
@@ -45,7 +64,7 @@ pub struct ClassMetrics {
 
 impl<T: Hash + Eq, C: Hash + Eq> Default for Requests<T, C> {
     fn default() -> Self {
-        Requests(Registry::default())
+        Requests(Registry::default(), LatencySampling::default())
     }
 }
 
@@ -57,21 +76,58 @@ impl<T: Hash + Eq, C: Hash + Eq> Requests<T, C> {
         Report::new(retain_idle, self.0)
     }
 
+    /// Records latencies for only the given fraction of requests. Request and
+    /// response counts are always recorded.
+    pub fn with_latency_sample_rate(self, rate: f64) -> Self {
+        Requests(self.0, LatencySampling::new(rate))
+    }
+
     pub fn to_layer<L, N, Tgt>(
         &self,
-    ) -> impl layer::Layer<N, Service = NewHttpMetrics<N, T, L, C, N::Service>> + Clone
+    ) -> impl layer::Layer<N, Service = NewHttpMetrics<N, T, L, N::Service>> + Clone
     where
         L: ClassifyResponse<Class = C> + Send + Sync + 'static,
         N: svc::NewService<Tgt>,
     {
-        let reg = self.0.clone();
-        NewMetrics::layer(reg)
+        NewHttpMetrics::layer(self.0.clone(), self.1.clone())
     }
 }
 
 impl<T: Hash + Eq, C: Hash + Eq> Clone for Requests<T, C> {
     fn clone(&self) -> Self {
-        Requests(self.0.clone())
+        Requests(self.0.clone(), self.1.clone())
+    }
+}
+
+// === impl LatencySampling ===
+
+impl LatencySampling {
+    fn new(rate: f64) -> Self {
+        Self {
+            rate: rate.clamp(0.0, 1.0),
+            requests: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Returns true if the next request's latency should be recorded.
+    pub(crate) fn sample(&self) -> bool {
+        if self.rate >= 1.0 {
+            return true;
+        }
+        if self.rate <= 0.0 {
+            return false;
+        }
+
+        // Sample a request each time the running count of requests, scaled by
+        // the sample rate, reaches a new integer.
+        let n = self.requests.fetch_add(1, Ordering::Relaxed) as f64;
+        ((n + 1.0) * self.rate).floor() > (n * self.rate).floor()
+    }
+}
+
+impl Default for LatencySampling {
+    fn default() -> Self {
+        Self::new(1.0)
     }
 }
 
@@ -107,6 +163,70 @@ where
 
 #[cfg(test)]
 mod tests {
+    #[tokio::test(flavor = "current_thread")]
+    async fn samples_latencies() {
+        use super::service::RequestBody;
+        use linkerd_error::Error;
+        use linkerd_http_classify::{ClassifyEos, ClassifyResponse};
+        use linkerd_stack::{layer::Layer, service_fn, NewService, Service, ServiceExt};
+
+        #[derive(Clone, Debug, Hash, Eq, PartialEq)]
+        struct Target;
+
+        #[derive(Clone, Debug, Default)]
+        struct Classify;
+
+        impl ClassifyResponse for Classify {
+            type Class = ();
+            type ClassifyEos = Self;
+
+            fn start<B>(self, _: &http::Response<B>) -> Self {
+                self
+            }
+
+            fn error(self, _: &Error) {}
+        }
+
+        impl ClassifyEos for Classify {
+            type Class = ();
+
+            fn eos(self, _: Option<&http::HeaderMap>) {}
+
+            fn error(self, _: &Error) {}
+        }
+
+        let requests = super::Requests::<Target, ()>::default().with_latency_sample_rate(0.25);
+        let mut svc = requests
+            .to_layer::<Classify, _, Target>()
+            .layer(|_: Target| {
+                service_fn(|_: http::Request<RequestBody<hyper::Body, ()>>| {
+                    futures::future::ok::<_, Error>(http::Response::new(hyper::Body::empty()))
+                })
+            })
+            .new_service(Target);
+
+        for _ in 0..100 {
+            svc.ready()
+                .await
+                .expect("service must be ready")
+                .call(http::Request::new(hyper::Body::empty()))
+                .await
+                .expect("request must succeed");
+        }
+
+        let metrics = requests.0.lock().get_or_default(Target).clone();
+        let metrics = metrics.lock();
+        assert_eq!(u64::from(&metrics.total), 100, "all requests are counted");
+        let status = metrics
+            .by_status
+            .get(&Some(http::StatusCode::OK))
+            .expect("responses must be recorded");
+        let responses: u64 = status.by_class.values().map(|c| u64::from(&c.total)).sum();
+        assert_eq!(responses, 100, "all responses are counted");
+        let latencies: u64 = status.latency.into_iter().map(|(_, c)| u64::from(c)).sum();
+        assert_eq!(latencies, 25, "only sampled latencies are recorded");
+    }
+
     #[test]
     fn expiry() {
         use linkerd_metrics::FmtLabels;
@@ -1,10 +1,10 @@
-use super::{ClassMetrics, Metrics, StatusMetrics};
+use super::{ClassMetrics, LatencySampling, Metrics, Registry, StatusMetrics};
 use futures::{ready, TryFuture};
 use http_body::Body;
 use linkerd_error::Error;
 use linkerd_http_classify::{ClassifyEos, ClassifyResponse};
 use linkerd_metrics::NewMetrics;
-use linkerd_stack::Proxy;
+use linkerd_stack::{self as svc, Proxy};
 use parking_lot::Mutex;
 use pin_project::{pin_project, pinned_drop};
 use std::{
@@ -18,8 +18,15 @@ use std::{
 use tokio::time::Instant;
 
 /// Wraps services to record metrics.
-pub type NewHttpMetrics<N, K, C, Class, S> =
-    NewMetrics<N, K, Mutex<Metrics<Class>>, HttpMetrics<S, C>>;
+pub struct NewHttpMetrics<N, K, C, S>
+where
+    K: Hash + Eq,
+    C: ClassifyResponse,
+    C::Class: Hash + Eq,
+{
+    inner: NewMetrics<N, K, Mutex<Metrics<C::Class>>, HttpMetrics<S, C>>,
+    sampling: LatencySampling,
+}
 
 /// A middleware that records HTTP metrics.
 #[pin_project]
@@ -30,6 +37,7 @@ where
     C::Class: Hash + Eq,
 {
     metrics: Option<Arc<Mutex<Metrics<C::Class>>>>,
+    sampling: LatencySampling,
     #[pin]
     inner: S,
     _p: PhantomData<fn() -> C>,
@@ -44,6 +52,7 @@ where
     classify: Option<C>,
     metrics: Option<Arc<Mutex<Metrics<C::Class>>>>,
     stream_open_at: Instant,
+    sample_latency: bool,
     #[pin]
     inner: F,
 }
@@ -77,6 +86,57 @@ where
     inner: B,
 }
 
+// === impl NewHttpMetrics ===
+
+impl<N, K, C, S> NewHttpMetrics<N, K, C, S>
+where
+    K: Hash + Eq,
+    C: ClassifyResponse,
+    C::Class: Hash + Eq,
+{
+    pub(super) fn layer(
+        registry: Registry<K, C::Class>,
+        sampling: LatencySampling,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            inner: svc::layer::Layer::layer(&NewMetrics::layer(registry.clone()), inner),
+            sampling: sampling.clone(),
+        })
+    }
+}
+
+impl<N, K, C, S, T> svc::NewService<T> for NewHttpMetrics<N, K, C, S>
+where
+    K: Hash + Eq,
+    C: ClassifyResponse,
+    C::Class: Hash + Eq,
+    NewMetrics<N, K, Mutex<Metrics<C::Class>>, HttpMetrics<S, C>>:
+        svc::NewService<T, Service = HttpMetrics<S, C>>,
+{
+    type Service = HttpMetrics<S, C>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let mut svc = self.inner.new_service(target);
+        svc.sampling = self.sampling.clone();
+        svc
+    }
+}
+
+impl<N, K, C, S> Clone for NewHttpMetrics<N, K, C, S>
+where
+    N: Clone,
+    K: Hash + Eq,
+    C: ClassifyResponse,
+    C::Class: Hash + Eq,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            sampling: self.sampling.clone(),
+        }
+    }
+}
+
 // === impl HttpMetrics ===
 
 impl<S, C> From<(S, Arc<Mutex<Metrics<C::Class>>>)> for HttpMetrics<S, C>
@@ -87,6 +147,7 @@ where
     fn from((inner, metrics): (S, Arc<Mutex<Metrics<C::Class>>>)) -> Self {
         Self {
             metrics: Some(metrics),
+            sampling: LatencySampling::default(),
             inner,
             _p: PhantomData,
         }
@@ -103,6 +164,7 @@ where
         Self {
             inner: self.inner.clone(),
             metrics: self.metrics.clone(),
+            sampling: self.sampling.clone(),
             _p: PhantomData,
         }
     }
@@ -149,6 +211,7 @@ where
             classify: Some(classify),
             metrics: self.metrics.clone(),
             stream_open_at: Instant::now(),
+            sample_latency: self.sampling.sample(),
             inner: self.inner.proxy(svc, req),
         }
     }
@@ -198,6 +261,7 @@ where
             classify: Some(classify),
             metrics: self.metrics.clone(),
             stream_open_at: Instant::now(),
+            sample_latency: self.sampling.sample(),
             inner: self.inner.call(req),
         }
     }
@@ -228,7 +292,9 @@ where
                     classify,
                     metrics,
                     stream_open_at: *this.stream_open_at,
-                    latency_recorded: false,
+                    // Unsampled responses are treated as though their latency
+                    // has already been recorded.
+                    latency_recorded: !*this.sample_latency,
                     inner,
                 };
                 Ok(http::Response::from_parts(head, body))
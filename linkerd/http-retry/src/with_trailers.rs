@@ -51,6 +51,15 @@ impl<B: Body> WithTrailers<B> {
             .and_then(|trls| trls.as_ref().ok()?.as_ref())
     }
 
+    /// Returns the error that occurred while polling for the body's first
+    /// frame, if any.
+    pub fn error(&self) -> Option<&B::Error> {
+        match self.first_data {
+            Some(Err(ref error)) => Some(error),
+            _ => None,
+        }
+    }
+
     pub fn map_response(rsp: http::Response<B>) -> WithTrailersFuture<B>
     where
         B: Send + Unpin + 'static,
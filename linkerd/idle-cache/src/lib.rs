@@ -2,6 +2,7 @@
 #![forbid(unsafe_code)]
 
 use parking_lot::RwLock;
+use rand::Rng;
 use std::{
     borrow::Borrow,
     collections::{
@@ -29,6 +30,15 @@ where
     /// evicted.
     idle: time::Duration,
 
+    /// The maximum amount of time by which each entry's idle timeout is
+    /// randomly extended, so that entries created together are not all evicted
+    /// at the same instant.
+    jitter: time::Duration,
+
+    /// The maximum amount of time an entry may be used before it is replaced,
+    /// regardless of whether it is idle.
+    max_age: Option<time::Duration>,
+
     inner: Arc<InnerMap<K, V, S>>,
 }
 
@@ -50,6 +60,8 @@ struct CacheEntry<V> {
     ///
     /// If this is unset, the entry is permanent and will not be evicted.
     handle: Option<Weak<Notify>>,
+    /// The time at which the entry was created.
+    created: time::Instant,
 }
 
 /// A locked cache map holding values and an optional handle. When the handle is
@@ -80,6 +92,8 @@ where
     pub fn with_capacity(idle: time::Duration, capacity: usize) -> Self {
         Self {
             idle,
+            jitter: time::Duration::ZERO,
+            max_age: None,
             inner: Arc::new(RwLock::new(HashMap::with_capacity_and_hasher(
                 capacity,
                 BuildHasherDefault::default(),
@@ -101,7 +115,12 @@ where
             .map(|(k, v)| (k, CacheEntry::permanent(v)))
             .collect();
         let inner = Arc::new(RwLock::new(entries));
-        Self { inner, idle }
+        Self {
+            inner,
+            idle,
+            jitter: time::Duration::ZERO,
+            max_age: None,
+        }
     }
 }
 
@@ -113,7 +132,28 @@ where
 {
     pub fn with_hasher(idle: time::Duration, hasher: S) -> Self {
         let inner = Arc::new(RwLock::new(HashMap::with_hasher(hasher)));
-        Self { inner, idle }
+        Self {
+            inner,
+            idle,
+            jitter: time::Duration::ZERO,
+            max_age: None,
+        }
+    }
+
+    /// Extends the idle timeout of each entry by a random duration of up to
+    /// `jitter`, chosen when the entry is created.
+    pub fn with_jitter(self, jitter: time::Duration) -> Self {
+        Self { jitter, ..self }
+    }
+
+    /// Replaces each entry with a new value once it is `max_age` old, even if
+    /// it is still in use. Handles to the prior value remain valid until they
+    /// are dropped.
+    pub fn with_max_age(self, max_age: time::Duration) -> Self {
+        Self {
+            max_age: Some(max_age),
+            ..self
+        }
     }
 
     pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<Cached<V>>
@@ -124,6 +164,10 @@ where
     {
         let cache = self.inner.read();
         let cache_entry = cache.get(key)?;
+        if cache_entry.is_expired(self.max_age) {
+            trace!(?key, "Cached value expired");
+            return None;
+        }
         let cached = cache_entry.cached();
 
         trace!(
@@ -151,17 +195,29 @@ where
                 debug!(key = ?entry.key(), "Caching new value");
                 let inner = f(entry.key());
                 let handle = self.spawn_idle(entry.key().clone());
-                entry.insert(CacheEntry {
-                    value: inner.clone(),
-                    handle: Some(Arc::downgrade(&handle)),
-                });
+                entry.insert(CacheEntry::new(inner.clone(), &handle));
                 Cached {
                     inner,
                     handle: Some(handle),
                 }
             }
 
-            Entry::Occupied(entry) => {
+            Entry::Occupied(mut entry) => {
+                if entry.get().is_expired(self.max_age) {
+                    // The entry has outlived its maximum age. Replace it
+                    // without disturbing the handles that still hold the prior
+                    // value; the prior entry's eviction task exits once they
+                    // are dropped.
+                    debug!(key = ?entry.key(), "Replacing expired value");
+                    let inner = f(entry.key());
+                    let handle = self.spawn_idle(entry.key().clone());
+                    entry.insert(CacheEntry::new(inner.clone(), &handle));
+                    return Cached {
+                        inner,
+                        handle: Some(handle),
+                    };
+                }
+
                 // Another thread raced us to create a value for this target.
                 trace!(key = ?entry.key(), "Using cached value");
                 entry.get().cached()
@@ -186,15 +242,35 @@ where
         }
     }
 
+    /// Removes a value from the cache, so that the next lookup creates a new
+    /// value. Handles to the removed value remain valid until they are
+    /// dropped. Returns `true` if a value was removed.
+    pub fn evict<Q: ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + fmt::Debug,
+    {
+        let evicted = self.inner.write().remove(key).is_some();
+        if evicted {
+            debug!(?key, "Evicted cache entry");
+        }
+        evicted
+    }
+
     fn spawn_idle(&self, key: K) -> Arc<Notify> {
         // Spawn a background task that holds the handle. Every time the handle
         // is notified, it resets the idle timeout. Every time teh idle timeout
         // expires, the handle is checked and the service is dropped if there
         // are no active handles.
         let handle = Arc::new(Notify::new());
-        tokio::spawn(Self::evict(
+        let idle = if self.jitter.is_zero() {
+            self.idle
+        } else {
+            self.idle + rand::thread_rng().gen_range(time::Duration::ZERO..=self.jitter)
+        };
+        tokio::spawn(Self::evict_idle(
             key,
-            self.idle,
+            idle,
             handle.clone(),
             Arc::downgrade(&self.inner),
         ));
@@ -202,12 +278,17 @@ where
     }
 
     #[instrument(level = "debug", skip(idle, reset, cache))]
-    async fn evict(
+    async fn evict_idle(
         key: K,
         idle: time::Duration,
         mut reset: Arc<Notify>,
         cache: Weak<InnerMap<K, V, S>>,
     ) {
+        // The entry may be replaced (e.g. once it reaches its maximum age), so
+        // the task only evicts the entry that holds its handle. The handle's
+        // address identifies it (as an integer, so that the task is `Send`).
+        let id = Arc::as_ptr(&reset) as usize;
+
         // Wait for the handle to be notified before starting to track idleness.
         reset.notified().await;
         debug!("Awaiting idleness");
@@ -252,16 +333,9 @@ where
             }
 
             // If this was the last handle, attempt to clear the key from the
-            // cache (unless it was replaced by a permanent value). There should
-            // be at most one task per key, so we expect the key to be in the
-            // cached.
-            let entry = cache.entry(key);
-            debug_assert!(
-                matches!(entry, Entry::Occupied(_)),
-                "Cache item must exist: {:?}",
-                entry.key()
-            );
-            if let Entry::Occupied(entry) = entry {
+            // cache (unless it was replaced by a permanent value or by a newer
+            // entry).
+            if let Entry::Occupied(entry) = cache.entry(key) {
                 if entry.get().is_permanent() {
                     // The key was updated with a permanent value that cannot be
                     // evicted.
@@ -269,6 +343,13 @@ where
                     return;
                 }
 
+                if !entry.get().is_held_by(id) {
+                    // The entry expired and was replaced by a new value, which
+                    // has its own eviction task.
+                    debug!(key = ?entry.key(), "Cache entry was replaced");
+                    return;
+                }
+
                 debug!(key = ?entry.key(), "Dropping cache entry");
                 entry.remove();
             }
@@ -280,10 +361,19 @@ where
 }
 
 impl<V> CacheEntry<V> {
+    fn new(value: V, handle: &Arc<Notify>) -> Self {
+        Self {
+            value,
+            handle: Some(Arc::downgrade(handle)),
+            created: time::Instant::now(),
+        }
+    }
+
     fn permanent(value: V) -> Self {
         Self {
             value,
             handle: None,
+            created: time::Instant::now(),
         }
     }
 
@@ -305,6 +395,23 @@ impl<V> CacheEntry<V> {
     fn is_permanent(&self) -> bool {
         self.handle.is_none()
     }
+
+    /// Permanent entries never expire.
+    fn is_expired(&self, max_age: Option<time::Duration>) -> bool {
+        match max_age {
+            Some(max_age) => {
+                !self.is_permanent()
+                    && time::Instant::now().saturating_duration_since(self.created) >= max_age
+            }
+            None => false,
+        }
+    }
+
+    fn is_held_by(&self, id: usize) -> bool {
+        self.handle
+            .as_ref()
+            .map_or(false, |handle| handle.as_ptr() as usize == id)
+    }
 }
 impl<K, V, S> Clone for IdleCache<K, V, S>
 where
@@ -314,6 +421,8 @@ where
         Self {
             inner: self.inner.clone(),
             idle: self.idle,
+            jitter: self.jitter,
+            max_age: self.max_age,
         }
     }
 }
@@ -417,6 +526,7 @@ async fn test_idle_retain() {
         CacheEntry {
             value: (),
             handle: Some(weak.clone()),
+            created: time::Instant::now(),
         },
     );
     let c0 = Cached {
@@ -458,3 +568,105 @@ async fn test_idle_retain() {
     assert!(weak.upgrade().is_none());
     assert!(!cache.inner.read().contains_key(&()));
 }
+
+#[cfg(test)]
+#[tokio::test(flavor = "current_thread")]
+async fn test_idle_jitter() {
+    use tokio::sync::mpsc;
+
+    /// Records the time at which it is evicted from the cache.
+    #[derive(Debug)]
+    struct Evicted(mpsc::UnboundedSender<time::Instant>);
+
+    impl Drop for Evicted {
+        fn drop(&mut self) {
+            let _ = self.0.send(time::Instant::now());
+        }
+    }
+
+    time::pause();
+
+    let idle = time::Duration::from_secs(10);
+    let jitter = time::Duration::from_secs(60 * 60);
+    let cache = IdleCache::new(idle).with_jitter(jitter);
+
+    // Create two entries at the same instant and drop their handles so that
+    // both begin idling together.
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let start = time::Instant::now();
+    let c0 = cache.get_or_insert_with(0, |_| Arc::new(Evicted(tx.clone())));
+    let c1 = cache.get_or_insert_with(1, |_| Arc::new(Evicted(tx)));
+    drop((c0, c1));
+
+    let evicted0 = rx.recv().await.expect("entry must be evicted");
+    let evicted1 = rx.recv().await.expect("entry must be evicted");
+    for evicted in [evicted0, evicted1] {
+        let elapsed = evicted.saturating_duration_since(start);
+        assert!(elapsed >= idle, "{:?}", elapsed);
+        assert!(
+            elapsed <= idle + jitter + time::Duration::from_secs(1),
+            "{:?}",
+            elapsed
+        );
+    }
+    assert_ne!(evicted0, evicted1, "entries must not be evicted together");
+    assert!(cache.inner.read().is_empty());
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "current_thread")]
+async fn test_max_age() {
+    time::pause();
+
+    let idle = time::Duration::from_secs(10);
+    let max_age = time::Duration::from_secs(60);
+    let cache = IdleCache::new(idle).with_max_age(max_age);
+
+    // Hold a handle to the first value for the duration of the test, so that
+    // the entry is never idle.
+    let c0 = cache.get_or_insert_with((), |_| 0);
+    time::sleep(max_age / 2).await;
+    assert_eq!(*cache.get_or_insert_with((), |_| 1), 0);
+
+    // Once the entry reaches its maximum age, a new value is created even
+    // though the prior value is still in use.
+    time::sleep(max_age / 2).await;
+    assert!(cache.get(&()).is_none(), "entry must be expired");
+    let c1 = cache.get_or_insert_with((), |_| 1);
+    assert_eq!(*c1, 1);
+    assert_eq!(*cache.get_or_insert_with((), |_| 2), 1);
+    assert_eq!(*c0, 0, "prior handles must retain the prior value");
+
+    // Dropping the prior value's handles must not evict its replacement.
+    drop(c0);
+    time::sleep(idle * 2).await;
+    assert_eq!(*cache.get(&()).expect("entry must be cached"), 1);
+
+    // The replacement is evicted once it idles out.
+    drop(c1);
+    time::sleep(idle * 2).await;
+    assert!(!cache.inner.read().contains_key(&()));
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "current_thread")]
+async fn test_evict() {
+    time::pause();
+
+    let idle = time::Duration::from_secs(10);
+    let cache = IdleCache::new(idle);
+
+    // Evicting a value that is still in use causes the next lookup to create
+    // a new value.
+    let c0 = cache.get_or_insert_with((), |_| 0);
+    assert!(cache.evict(&()), "entry must be evicted");
+    assert!(!cache.evict(&()), "entry must not be evicted twice");
+    let c1 = cache.get_or_insert_with((), |_| 1);
+    assert_eq!(*c1, 1);
+    assert_eq!(*c0, 0, "prior handles must retain the prior value");
+
+    // Dropping the evicted value's handles must not evict its replacement.
+    drop(c0);
+    time::sleep(idle * 2).await;
+    assert_eq!(*cache.get(&()).expect("entry must be cached"), 1);
+}
@@ -28,6 +28,30 @@ where
     pub fn layer(idle: time::Duration) -> impl layer::Layer<N, Service = Self> + Clone {
         layer::mk(move |new_svc| Self::new(new_svc, idle))
     }
+
+    /// Extends the idle timeout of each cached service by a random duration of
+    /// up to `jitter`.
+    pub fn with_jitter(self, jitter: time::Duration) -> Self {
+        Self {
+            cache: self.cache.with_jitter(jitter),
+            new_svc: self.new_svc,
+        }
+    }
+
+    /// Replaces each cached service with a new one once it is `max_age` old,
+    /// even if it is still in use.
+    pub fn with_max_age(self, max_age: time::Duration) -> Self {
+        Self {
+            cache: self.cache.with_max_age(max_age),
+            new_svc: self.new_svc,
+        }
+    }
+
+    /// Removes the cached service for `target`, so that the next service for
+    /// the target is newly built. Returns `true` if a service was removed.
+    pub fn evict(&self, target: &T) -> bool {
+        self.cache.evict(target)
+    }
 }
 
 impl<T, N> NewService<T> for NewIdleCached<T, N>
@@ -9,6 +9,7 @@ use boring::{
 };
 use linkerd_error::Result;
 use linkerd_identity as id;
+use linkerd_tls::PinnedCerts;
 use std::sync::Arc;
 use tokio::sync::watch;
 
@@ -17,7 +18,12 @@ pub fn watch(
     roots_pem: &str,
     key_pkcs8: &[u8],
     csr: &[u8],
+    pins: PinnedCerts,
 ) -> Result<(Store, Receiver)> {
+    if !pins.is_empty() {
+        return Err("certificate pinning is not supported by the boring TLS backend".into());
+    }
+
     let creds = {
         let roots = X509::stack_from_pem(roots_pem.as_bytes())?;
         let key = PKey::private_key_from_pkcs8(key_pkcs8)?;
@@ -9,6 +9,7 @@ fn load(ent: &Entity) -> crate::creds::Store {
         roots_pem,
         ent.key,
         b"fake CSR data",
+        Default::default(),
     )
     .expect("credentials must be readable");
     store
@@ -1,3 +1,7 @@
+use crate::{
+    record::{self, RecordIo},
+    Metrics,
+};
 use futures::prelude::*;
 use linkerd_io as io;
 use linkerd_stack::{NewService, Service};
@@ -10,6 +14,7 @@ use tokio_rustls::rustls::{self, ClientConfig};
 #[derive(Clone)]
 pub struct NewClient {
     config: watch::Receiver<Arc<ClientConfig>>,
+    metrics: Metrics,
 }
 
 /// A `Service` that initiates client-side TLS connections.
@@ -17,21 +22,22 @@ pub struct NewClient {
 pub struct Connect {
     server_id: rustls::ServerName,
     config: Arc<ClientConfig>,
+    metrics: Metrics,
 }
 
 pub type ConnectFuture<I> = futures::future::MapOk<
-    tokio_rustls::Connect<I>,
-    fn(tokio_rustls::client::TlsStream<I>) -> ClientIo<I>,
+    tokio_rustls::Connect<RecordIo<I>>,
+    fn(tokio_rustls::client::TlsStream<RecordIo<I>>) -> ClientIo<I>,
 >;
 
 #[derive(Debug)]
-pub struct ClientIo<I>(tokio_rustls::client::TlsStream<I>);
+pub struct ClientIo<I>(tokio_rustls::client::TlsStream<RecordIo<I>>);
 
 // === impl NewClient ===
 
 impl NewClient {
-    pub(crate) fn new(config: watch::Receiver<Arc<ClientConfig>>) -> Self {
-        Self { config }
+    pub(crate) fn new(config: watch::Receiver<Arc<ClientConfig>>, metrics: Metrics) -> Self {
+        Self { config, metrics }
     }
 }
 
@@ -39,7 +45,11 @@ impl NewService<ClientTls> for NewClient {
     type Service = Connect;
 
     fn new_service(&self, target: ClientTls) -> Self::Service {
-        Connect::new(target, (*self.config.borrow()).clone())
+        Connect::new(
+            target,
+            (*self.config.borrow()).clone(),
+            self.metrics.clone(),
+        )
     }
 }
 
@@ -52,7 +62,7 @@ impl std::fmt::Debug for NewClient {
 // === impl Connect ===
 
 impl Connect {
-    pub(crate) fn new(client_tls: ClientTls, config: Arc<ClientConfig>) -> Self {
+    pub(crate) fn new(client_tls: ClientTls, config: Arc<ClientConfig>, metrics: Metrics) -> Self {
         // If ALPN protocols are configured by the endpoint, we have to clone the entire
         // configuration and set the protocols. If there are no ALPN options, clone the Arc'd base
         // configuration without extra allocation.
@@ -71,7 +81,11 @@ impl Connect {
         let server_id = rustls::ServerName::try_from(client_tls.server_id.as_str())
             .expect("identity must be a valid DNS name");
 
-        Self { server_id, config }
+        Self {
+            server_id,
+            config,
+            metrics,
+        }
     }
 }
 
@@ -90,8 +104,15 @@ where
     fn call(&mut self, io: I) -> Self::Future {
         tokio_rustls::TlsConnector::from(self.config.clone())
             // XXX(eliza): it's a bummer that the server name has to be cloned here...
-            .connect(self.server_id.clone(), io)
-            .map_ok(ClientIo)
+            .connect(
+                self.server_id.clone(),
+                RecordIo::new(io, self.metrics.clone()),
+            )
+            .map_ok(|io| {
+                let (record, conn) = io.get_ref();
+                record.metrics().record_client_handshake(conn);
+                ClientIo(io)
+            })
     }
 }
 
@@ -104,7 +125,9 @@ impl<I: io::AsyncRead + io::AsyncWrite + Unpin> io::AsyncRead for ClientIo<I> {
         cx: &mut Context<'_>,
         buf: &mut io::ReadBuf<'_>,
     ) -> io::Poll<()> {
-        Pin::new(&mut self.0).poll_read(cx, buf)
+        let res = futures::ready!(Pin::new(&mut self.0).poll_read(cx, buf));
+        record::inspect_eof(&res, self.0.get_ref().0);
+        io::Poll::Ready(res)
     }
 }
 
@@ -153,6 +176,6 @@ impl<I> ClientIo<I> {
 impl<I: io::PeerAddr> io::PeerAddr for ClientIo<I> {
     #[inline]
     fn peer_addr(&self) -> io::Result<std::net::SocketAddr> {
-        self.0.get_ref().0.peer_addr()
+        self.0.get_ref().0.get_ref().peer_addr()
     }
 }
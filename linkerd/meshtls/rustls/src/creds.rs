@@ -1,9 +1,12 @@
 mod receiver;
 mod store;
+mod verify;
 
 pub use self::{receiver::Receiver, store::Store};
+use crate::Metrics;
 use linkerd_error::Result;
 use linkerd_identity as id;
+use linkerd_tls::PinnedCerts;
 use ring::{error::KeyRejected, signature::EcdsaKeyPair};
 use std::sync::Arc;
 use thiserror::Error;
@@ -24,6 +27,8 @@ pub fn watch(
     roots_pem: &str,
     key_pkcs8: &[u8],
     csr: &[u8],
+    pins: PinnedCerts,
+    metrics: Metrics,
 ) -> Result<(Store, Receiver)> {
     let mut roots = rustls::RootCertStore::empty();
     let certs = match rustls_pemfile::certs(&mut std::io::Cursor::new(roots_pem)) {
@@ -53,10 +58,10 @@ pub fn watch(
     // controlling the set of trusted signature algorithms), but they provide good enough
     // defaults for now.
     // TODO: lock down the verification further.
-    let server_cert_verifier = Arc::new(rustls::client::WebPkiVerifier::new(
-        roots.clone(),
-        None, // no certificate transparency policy
-    ));
+    //
+    // Servers whose identities are pinned to a certificate are verified by
+    // the certificate's fingerprint instead.
+    let server_cert_verifier = Arc::new(verify::PinningVerifier::new(roots.clone(), pins));
 
     let (client_tx, client_rx) = {
         // Since we don't have a certificate yet, build a client configuration
@@ -76,7 +81,7 @@ pub fn watch(
         watch::channel(store::server_config(roots.clone(), empty_resolver))
     };
 
-    let rx = Receiver::new(identity.clone(), client_rx, server_rx);
+    let rx = Receiver::new(identity.clone(), client_rx, server_rx, metrics);
     let store = Store::new(
         roots,
         server_cert_verifier,
@@ -97,6 +102,8 @@ pub fn for_test(ent: &linkerd_tls_test_util::Entity) -> (Store, Receiver) {
         std::str::from_utf8(ent.trust_anchors).expect("roots must be PEM"),
         ent.key,
         b"fake CSR",
+        PinnedCerts::default(),
+        Metrics::default(),
     )
     .expect("credentials must be valid")
 }
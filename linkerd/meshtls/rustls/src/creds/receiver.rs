@@ -1,4 +1,4 @@
-use crate::{NewClient, Server};
+use crate::{Metrics, NewClient, Server};
 use linkerd_identity::Name;
 use std::sync::Arc;
 use tokio::sync::watch;
@@ -10,6 +10,7 @@ pub struct Receiver {
     name: Name,
     client_rx: watch::Receiver<Arc<rustls::ClientConfig>>,
     server_rx: watch::Receiver<Arc<rustls::ServerConfig>>,
+    metrics: Metrics,
 }
 
 // === impl Receiver ===
@@ -19,11 +20,13 @@ impl Receiver {
         name: Name,
         client_rx: watch::Receiver<Arc<rustls::ClientConfig>>,
         server_rx: watch::Receiver<Arc<rustls::ServerConfig>>,
+        metrics: Metrics,
     ) -> Self {
         Self {
             name,
             client_rx,
             server_rx,
+            metrics,
         }
     }
 
@@ -34,12 +37,16 @@ impl Receiver {
 
     /// Returns a `NewClient` that can be used to establish TLS on client connections.
     pub fn new_client(&self) -> NewClient {
-        NewClient::new(self.client_rx.clone())
+        NewClient::new(self.client_rx.clone(), self.metrics.clone())
     }
 
     /// Returns a `Server` that can be used to terminate TLS on server connections.
     pub fn server(&self) -> Server {
-        Server::new(self.name.clone(), self.server_rx.clone())
+        Server::new(
+            self.name.clone(),
+            self.server_rx.clone(),
+            self.metrics.clone(),
+        )
     }
 }
 
@@ -88,6 +95,7 @@ mod tests {
             name: "example".parse().unwrap(),
             server_rx,
             client_rx,
+            metrics: Default::default(),
         };
 
         let server = receiver.server();
@@ -111,6 +119,7 @@ mod tests {
             name: "example".parse().unwrap(),
             server_rx,
             client_rx,
+            metrics: Default::default(),
         };
 
         let server = receiver
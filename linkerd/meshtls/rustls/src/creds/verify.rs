@@ -0,0 +1,78 @@
+use linkerd_identity as id;
+use linkerd_tls::{Fingerprint, PinnedCerts};
+use std::{str::FromStr, time::SystemTime};
+use tokio_rustls::rustls::{
+    self,
+    client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier},
+};
+use tracing::debug;
+
+#[cfg(test)]
+mod tests;
+
+/// Verifies servers whose identities are pinned to a certificate by the
+/// certificate's fingerprint, and all other servers against the trust roots.
+pub(crate) struct PinningVerifier {
+    pins: PinnedCerts,
+    roots: WebPkiVerifier,
+}
+
+// === impl PinningVerifier ===
+
+impl PinningVerifier {
+    pub(crate) fn new(roots: rustls::RootCertStore, pins: PinnedCerts) -> Self {
+        // no certificate transparency policy
+        let roots = WebPkiVerifier::new(roots, None);
+        Self { pins, roots }
+    }
+
+    fn pinned(&self, server_name: &rustls::ServerName) -> Option<&Fingerprint> {
+        if self.pins.is_empty() {
+            return None;
+        }
+        match server_name {
+            rustls::ServerName::DnsName(name) => {
+                let name = id::Name::from_str(name.as_ref()).ok()?;
+                self.pins.get(&name)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::Certificate,
+        intermediates: &[rustls::Certificate],
+        server_name: &rustls::ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let pinned = match self.pinned(server_name) {
+            Some(pinned) => pinned,
+            None => {
+                return self.roots.verify_server_cert(
+                    end_entity,
+                    intermediates,
+                    server_name,
+                    scts,
+                    ocsp_response,
+                    now,
+                )
+            }
+        };
+
+        // The pinned certificate is trusted as-is, so neither its issuer nor
+        // the names it is valid for are checked.
+        let digest = ring::digest::digest(&ring::digest::SHA256, &end_entity.0);
+        if digest.as_ref() != &pinned.0[..] {
+            debug!(expected = %pinned, "Certificate does not match pinned fingerprint");
+            return Err(rustls::Error::InvalidCertificateData(
+                "certificate does not match pinned fingerprint".to_string(),
+            ));
+        }
+        Ok(ServerCertVerified::assertion())
+    }
+}
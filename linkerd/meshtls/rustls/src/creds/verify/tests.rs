@@ -0,0 +1,70 @@
+use super::*;
+use linkerd_tls_test_util::*;
+use std::convert::TryFrom;
+
+/// Builds a verifier that pins each server's identity to another entity's
+/// certificate.
+fn verifier(pins: &[(&Entity, &Entity)]) -> PinningVerifier {
+    let mut roots = rustls::RootCertStore::empty();
+    let certs = rustls_pemfile::certs(&mut std::io::Cursor::new(FOO_NS1.trust_anchors))
+        .expect("roots must be PEM");
+    roots.add_parsable_certificates(&certs[..]);
+    let pins = pins
+        .iter()
+        .map(|(server, pinned)| (server.name.parse().unwrap(), fingerprint(pinned)))
+        .collect();
+    PinningVerifier::new(roots, pins)
+}
+
+fn fingerprint(ent: &Entity) -> Fingerprint {
+    let digest = ring::digest::digest(&ring::digest::SHA256, ent.crt);
+    let mut fingerprint = [0u8; 32];
+    fingerprint.copy_from_slice(digest.as_ref());
+    Fingerprint(fingerprint)
+}
+
+/// Verifies the certificate of `presented` for the name of `server`.
+fn verify(verifier: &PinningVerifier, server: &Entity, presented: &Entity) -> bool {
+    let name = rustls::ServerName::try_from(server.name).expect("name must be valid");
+    verifier
+        .verify_server_cert(
+            &rustls::Certificate(presented.crt.to_vec()),
+            &[],
+            &name,
+            &mut std::iter::empty(),
+            &[],
+            SystemTime::now(),
+        )
+        .is_ok()
+}
+
+#[test]
+fn accepts_pinned_self_signed_cert() {
+    let verifier = verifier(&[(&SELF_SIGNED, &SELF_SIGNED)]);
+    assert!(verify(&verifier, &SELF_SIGNED, &SELF_SIGNED));
+}
+
+#[test]
+fn rejects_cert_not_matching_pin() {
+    let pinned = verifier(&[(&SELF_SIGNED, &SELF_SIGNED)]);
+    assert!(!verify(&pinned, &SELF_SIGNED, &SELF_SIGNED_OTHER));
+
+    // A pinned server may not present a certificate issued by the trust
+    // roots, either.
+    let pinned = verifier(&[(&FOO_NS1, &SELF_SIGNED)]);
+    assert!(!verify(&pinned, &FOO_NS1, &FOO_NS1));
+}
+
+#[test]
+fn rejects_self_signed_cert_without_pin() {
+    let verifier = verifier(&[]);
+    assert!(!verify(&verifier, &SELF_SIGNED, &SELF_SIGNED));
+}
+
+#[test]
+fn verifies_unpinned_servers_against_roots() {
+    let verifier = verifier(&[(&SELF_SIGNED, &SELF_SIGNED)]);
+    assert!(verify(&verifier, &FOO_NS1, &FOO_NS1));
+    assert!(!verify(&verifier, &BAR_NS1, &FOO_NS1));
+    assert!(!verify(&verifier, &FOO_NS1_CA2, &FOO_NS1_CA2));
+}
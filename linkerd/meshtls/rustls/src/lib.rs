@@ -3,11 +3,16 @@
 
 mod client;
 pub mod creds;
+mod metrics;
+mod negotiated;
+mod record;
 mod server;
 #[cfg(test)]
 mod tests;
 
 pub use self::{
     client::{ClientIo, Connect, ConnectFuture, NewClient},
+    metrics::Metrics,
+    negotiated::Negotiated,
     server::{Server, ServerIo, TerminateFuture},
 };
@@ -0,0 +1,55 @@
+use crate::negotiated::{Handshakes, Negotiated};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use tokio_rustls::rustls;
+
+/// Counts the TLS handshakes completed by the clients and servers built from a
+/// credential `Receiver`, as well as their truncated connections.
+///
+/// Clones share the same counters, so the registry may be read by a metrics
+/// report while it's updated by each connection.
+#[derive(Clone, Debug, Default)]
+pub struct Metrics(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    client_handshakes: Handshakes,
+    server_handshakes: Handshakes,
+    truncated_records: AtomicU64,
+}
+
+// === impl Metrics ===
+
+impl Metrics {
+    /// Returns the number of TLS connections initiated by the proxy by the
+    /// parameters their handshakes negotiated.
+    pub fn client_handshakes(&self) -> Vec<(Negotiated, u64)> {
+        self.0.client_handshakes.get()
+    }
+
+    /// Returns the number of TLS connections accepted by the proxy by the
+    /// parameters their handshakes negotiated.
+    pub fn server_handshakes(&self) -> Vec<(Negotiated, u64)> {
+        self.0.server_handshakes.get()
+    }
+
+    /// Returns the total number of TLS connections that were closed by the
+    /// peer partway through a record.
+    pub fn truncated_records(&self) -> u64 {
+        self.0.truncated_records.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_client_handshake(&self, conn: &rustls::CommonState) {
+        self.0.client_handshakes.record(conn)
+    }
+
+    pub(crate) fn record_server_handshake(&self, conn: &rustls::CommonState) {
+        self.0.server_handshakes.record(conn)
+    }
+
+    pub(crate) fn record_truncated_record(&self) {
+        self.0.truncated_records.fetch_add(1, Ordering::Relaxed);
+    }
+}
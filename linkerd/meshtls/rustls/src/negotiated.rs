@@ -0,0 +1,42 @@
+//! Counts the protocol versions and cipher suites negotiated by successful TLS
+//! handshakes.
+
+use parking_lot::Mutex;
+use tokio_rustls::rustls::{self, CipherSuite, ProtocolVersion};
+
+/// The parameters negotiated by a TLS handshake.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Negotiated {
+    pub version: ProtocolVersion,
+    pub cipher: CipherSuite,
+}
+
+/// Counts handshakes by the parameters they negotiated. Few distinct
+/// parameters may be negotiated, so they are counted in a list.
+#[derive(Debug, Default)]
+pub(crate) struct Handshakes(Mutex<Vec<(Negotiated, u64)>>);
+
+// === impl Handshakes ===
+
+impl Handshakes {
+    pub(crate) fn get(&self) -> Vec<(Negotiated, u64)> {
+        self.0.lock().clone()
+    }
+
+    /// Records the parameters negotiated by a completed handshake.
+    pub(crate) fn record(&self, conn: &rustls::CommonState) {
+        let negotiated = match (conn.protocol_version(), conn.negotiated_cipher_suite()) {
+            (Some(version), Some(suite)) => Negotiated {
+                version,
+                cipher: suite.suite(),
+            },
+            _ => return,
+        };
+
+        let mut handshakes = self.0.lock();
+        match handshakes.iter_mut().find(|(n, _)| *n == negotiated) {
+            Some((_, count)) => *count += 1,
+            None => handshakes.push((negotiated, 1)),
+        }
+    }
+}
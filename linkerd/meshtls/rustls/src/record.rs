@@ -0,0 +1,149 @@
+//! Distinguishes TLS connections that were closed partway through a record.
+//!
+//! rustls returns an `UnexpectedEof` error whenever a peer closes its
+//! connection without first sending a `close_notify` alert. Many peers do this
+//! routinely once they are done writing. However, a connection that is closed
+//! partway through a TLS record has certainly been truncated.
+//!
+//! To tell the two apart, `RecordIo` tracks TLS record boundaries in the
+//! ciphertext read from the peer. The error is always returned, since the
+//! missing `close_notify` means a truncation can't be ruled out, but closes at
+//! a record boundary are only logged at the trace level. Closes partway through
+//! a record are counted as truncations.
+
+use crate::Metrics;
+use linkerd_io as io;
+use std::{pin::Pin, task::Context};
+use tracing::{debug, trace};
+
+#[cfg(test)]
+mod tests;
+
+/// The length of a TLS record header: a 1-byte content type, a 2-byte protocol
+/// version, and a 2-byte payload length.
+const HEADER_LEN: usize = 5;
+
+/// Wraps a transport to track TLS record boundaries in the bytes read from it.
+#[derive(Debug)]
+pub struct RecordIo<I> {
+    io: I,
+    metrics: Metrics,
+    header: [u8; HEADER_LEN],
+    header_len: usize,
+    remaining: usize,
+}
+
+/// Inspects the result of a read from a TLS stream wrapping a `RecordIo`,
+/// counting the error if the peer closed its connection partway through a
+/// record.
+pub(crate) fn inspect_eof<I>(res: &io::Result<()>, io: &RecordIo<I>) {
+    let is_eof = matches!(res, Err(e) if e.kind() == io::ErrorKind::UnexpectedEof);
+    if !is_eof {
+        return;
+    }
+
+    if io.at_record_boundary() {
+        trace!("Peer closed connection without close_notify");
+        return;
+    }
+    io.metrics.record_truncated_record();
+    debug!(
+        header = io.header_len,
+        remaining = io.remaining,
+        "Peer closed connection partway through a TLS record"
+    );
+}
+
+// === impl RecordIo ===
+
+impl<I> RecordIo<I> {
+    pub(crate) fn new(io: I, metrics: Metrics) -> Self {
+        Self {
+            io,
+            metrics,
+            header: [0; HEADER_LEN],
+            header_len: 0,
+            remaining: 0,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get_ref(&self) -> &I {
+        &self.io
+    }
+
+    #[inline]
+    pub(crate) fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Returns true if no part of a record has been read since the last
+    /// complete record.
+    fn at_record_boundary(&self) -> bool {
+        self.header_len == 0 && self.remaining == 0
+    }
+
+    fn observe(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            if self.remaining > 0 {
+                let n = self.remaining.min(bytes.len());
+                self.remaining -= n;
+                bytes = &bytes[n..];
+                continue;
+            }
+
+            let n = (HEADER_LEN - self.header_len).min(bytes.len());
+            self.header[self.header_len..self.header_len + n].copy_from_slice(&bytes[..n]);
+            self.header_len += n;
+            bytes = &bytes[n..];
+            if self.header_len == HEADER_LEN {
+                self.remaining = u16::from_be_bytes([self.header[3], self.header[4]]) as usize;
+                self.header_len = 0;
+            }
+        }
+    }
+}
+
+impl<I: io::AsyncRead + Unpin> io::AsyncRead for RecordIo<I> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut io::ReadBuf<'_>,
+    ) -> io::Poll<()> {
+        let filled = buf.filled().len();
+        futures::ready!(Pin::new(&mut self.io).poll_read(cx, buf))?;
+        self.observe(&buf.filled()[filled..]);
+        io::Poll::Ready(Ok(()))
+    }
+}
+
+impl<I: io::AsyncWrite + Unpin> io::AsyncWrite for RecordIo<I> {
+    #[inline]
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+
+    #[inline]
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> io::Poll<usize> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    #[inline]
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> io::Poll<usize> {
+        Pin::new(&mut self.io).poll_write_vectored(cx, bufs)
+    }
+
+    #[inline]
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+}
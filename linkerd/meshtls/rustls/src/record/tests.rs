@@ -0,0 +1,181 @@
+use super::*;
+use crate::creds;
+use linkerd_identity::{Credentials, DerX509};
+use linkerd_io::{AsyncReadExt, AsyncWriteExt};
+use linkerd_stack::{NewService, Service};
+use linkerd_tls::{ClientTls, ServerId};
+use linkerd_tls_test_util::FOO_NS1;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::Poll,
+    time::{Duration, SystemTime},
+};
+
+#[test]
+fn tracks_record_boundaries() {
+    let mut io = RecordIo::new((), Default::default());
+    assert!(io.at_record_boundary());
+
+    // A record header, split across reads.
+    io.observe(&[0x17, 0x03]);
+    assert!(!io.at_record_boundary());
+    io.observe(&[0x03, 0x00, 0x04]);
+    assert!(!io.at_record_boundary());
+
+    // The record's payload, followed by part of the next record's header.
+    io.observe(&[1, 2, 3, 4, 0x17]);
+    assert!(!io.at_record_boundary());
+    io.observe(&[0x03, 0x03, 0x00, 0x01, 5]);
+    assert!(io.at_record_boundary());
+
+    // An empty record.
+    io.observe(&[0x17, 0x03, 0x03, 0x00, 0x00]);
+    assert!(io.at_record_boundary());
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn close_at_record_boundary_is_not_counted() {
+    let metrics = Metrics::default();
+    let (mut client, mut server) = connect(metrics.clone()).await;
+
+    client.write(b"hello").await;
+    drop(client);
+
+    // The missing close_notify is still reported to the reader.
+    let mut buf = Vec::new();
+    let error = server
+        .read_to_end(&mut buf)
+        .await
+        .expect_err("a close without close_notify must fail");
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(&buf[..], b"hello");
+    assert_eq!(metrics.truncated_records(), 0);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn truncated_record_is_counted() {
+    let metrics = Metrics::default();
+    let (mut client, mut server) = connect(metrics.clone()).await;
+
+    client.truncate.store(true, Ordering::SeqCst);
+    client.write(b"hello").await;
+    drop(client);
+
+    let mut buf = Vec::new();
+    let error = server
+        .read_to_end(&mut buf)
+        .await
+        .expect_err("a truncated record must fail");
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(metrics.truncated_records(), 1);
+}
+
+struct Client {
+    io: crate::ClientIo<Truncating>,
+    truncate: Arc<AtomicBool>,
+}
+
+/// A transport that, once `truncate` is set, writes only part of the next
+/// write before closing.
+struct Truncating {
+    io: Option<io::DuplexStream>,
+    truncate: Arc<AtomicBool>,
+}
+
+async fn connect(metrics: Metrics) -> (Client, crate::ServerIo<io::DuplexStream>) {
+    let roots_pem = std::str::from_utf8(FOO_NS1.trust_anchors).expect("valid PEM");
+    let (mut store, rx) = creds::watch(
+        FOO_NS1.name.parse().unwrap(),
+        roots_pem,
+        FOO_NS1.key,
+        b"fake CSR data",
+        Default::default(),
+        metrics,
+    )
+    .expect("credentials must be readable");
+    store
+        .set_certificate(
+            DerX509(FOO_NS1.crt.to_vec()),
+            vec![],
+            SystemTime::now() + Duration::from_secs(600),
+        )
+        .expect("certificate must be valid");
+
+    let (client_io, server_io) = io::duplex(64 * 1024);
+    let truncate = Arc::new(AtomicBool::new(false));
+    let client_io = Truncating {
+        io: Some(client_io),
+        truncate: truncate.clone(),
+    };
+
+    let mut connect = rx.new_client().new_service(ClientTls {
+        server_id: ServerId(FOO_NS1.name.parse().unwrap()),
+        alpn: None,
+    });
+    let mut accept = rx.server();
+    let (client, server) = tokio::join!(connect.call(client_io), accept.call(server_io));
+    let client = Client {
+        io: client.expect("client must connect"),
+        truncate,
+    };
+    let (_, server) = server.expect("server must accept");
+    (client, server)
+}
+
+// === impl Client ===
+
+impl Client {
+    async fn write(&mut self, buf: &[u8]) {
+        self.io.write_all(buf).await.expect("write must succeed");
+        self.io.flush().await.expect("flush must succeed");
+    }
+}
+
+// === impl Truncating ===
+
+impl io::AsyncRead for Truncating {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut io::ReadBuf<'_>,
+    ) -> io::Poll<()> {
+        match self.io.as_mut() {
+            Some(io) => Pin::new(io).poll_read(cx, buf),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+impl io::AsyncWrite for Truncating {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> io::Poll<usize> {
+        let truncate = self.truncate.load(Ordering::SeqCst);
+        let io = match self.io.as_mut() {
+            Some(io) => io,
+            None => return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+        };
+        if !truncate {
+            return Pin::new(io).poll_write(cx, buf);
+        }
+
+        futures::ready!(Pin::new(io).poll_write(cx, &buf[..buf.len() / 2]))?;
+        self.io = None;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        match self.io.as_mut() {
+            Some(io) => Pin::new(io).poll_flush(cx),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        match self.io.as_mut() {
+            Some(io) => Pin::new(io).poll_shutdown(cx),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+}
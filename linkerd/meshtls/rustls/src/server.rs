@@ -1,3 +1,7 @@
+use crate::{
+    record::{self, RecordIo},
+    Metrics,
+};
 use futures::prelude::*;
 use linkerd_identity::{LocalId, Name};
 use linkerd_io as io;
@@ -14,23 +18,28 @@ use tracing::debug;
 pub struct Server {
     name: Name,
     rx: watch::Receiver<Arc<ServerConfig>>,
+    metrics: Metrics,
 }
 
 pub type TerminateFuture<I> = futures::future::MapOk<
-    tokio_rustls::Accept<I>,
-    fn(tokio_rustls::server::TlsStream<I>) -> (ServerTls, ServerIo<I>),
+    tokio_rustls::Accept<RecordIo<I>>,
+    fn(tokio_rustls::server::TlsStream<RecordIo<I>>) -> (ServerTls, ServerIo<I>),
 >;
 
 #[derive(Debug)]
-pub struct ServerIo<I>(tokio_rustls::server::TlsStream<I>);
+pub struct ServerIo<I>(tokio_rustls::server::TlsStream<RecordIo<I>>);
 
 #[derive(Debug, Error)]
 #[error("credential store lost")]
 pub struct LostStore(());
 
 impl Server {
-    pub(crate) fn new(name: Name, rx: watch::Receiver<Arc<ServerConfig>>) -> Self {
-        Self { name, rx }
+    pub(crate) fn new(
+        name: Name,
+        rx: watch::Receiver<Arc<ServerConfig>>,
+        metrics: Metrics,
+    ) -> Self {
+        Self { name, rx, metrics }
     }
 
     #[cfg(test)]
@@ -78,7 +87,7 @@ impl Server {
             }
         });
 
-        Ok(Self::new(self.name, rx))
+        Ok(Self::new(self.name, rx, self.metrics))
     }
 }
 
@@ -104,8 +113,11 @@ where
     #[inline]
     fn call(&mut self, io: I) -> Self::Future {
         tokio_rustls::TlsAcceptor::from((*self.rx.borrow()).clone())
-            .accept(io)
+            .accept(RecordIo::new(io, self.metrics.clone()))
             .map_ok(|io| {
+                let (record, conn) = io.get_ref();
+                record.metrics().record_server_handshake(conn);
+
                 // Determine the peer's identity, if it exist.
                 let client_id = client_identity(&io);
 
@@ -153,7 +165,9 @@ impl<I: io::AsyncRead + io::AsyncWrite + Unpin> io::AsyncRead for ServerIo<I> {
         cx: &mut Context<'_>,
         buf: &mut io::ReadBuf<'_>,
     ) -> io::Poll<()> {
-        Pin::new(&mut self.0).poll_read(cx, buf)
+        let res = futures::ready!(Pin::new(&mut self.0).poll_read(cx, buf));
+        record::inspect_eof(&res, self.0.get_ref().0);
+        io::Poll::Ready(res)
     }
 }
 
@@ -202,6 +216,6 @@ impl<I> ServerIo<I> {
 impl<I: io::PeerAddr> io::PeerAddr for ServerIo<I> {
     #[inline]
     fn peer_addr(&self) -> io::Result<std::net::SocketAddr> {
-        self.0.get_ref().0.peer_addr()
+        self.0.get_ref().0.get_ref().peer_addr()
     }
 }
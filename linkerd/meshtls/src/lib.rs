@@ -21,6 +21,7 @@ pub use self::{
 };
 use linkerd_error::{Error, Result};
 use linkerd_identity::Name;
+use linkerd_tls::PinnedCerts;
 use std::str::FromStr;
 
 #[cfg(feature = "boring")]
@@ -29,6 +30,17 @@ pub use linkerd_meshtls_boring as boring;
 #[cfg(feature = "rustls")]
 pub use linkerd_meshtls_rustls as rustls;
 
+/// Counts the TLS handshakes completed by the clients and servers built from
+/// credentials, as well as their truncated connections.
+///
+/// Clones share the same counters. Handshakes and truncations are currently
+/// only counted by the `rustls` implementation.
+#[derive(Clone, Debug, Default)]
+pub struct Metrics {
+    #[cfg(feature = "rustls")]
+    rustls: rustls::Metrics,
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum Mode {
     #[cfg(feature = "boring")]
@@ -54,6 +66,76 @@ macro_rules! no_tls {
     };
 }
 
+/// The number of successful TLS handshakes that negotiated a protocol version
+/// and cipher suite on one side of a connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Negotiated {
+    pub side: Side,
+    pub version: String,
+    pub cipher: String,
+    pub handshakes: u64,
+}
+
+/// The side of a TLS connection on which the proxy negotiated it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    /// The proxy initiated the connection.
+    Client,
+
+    /// The proxy accepted the connection.
+    Server,
+}
+
+// === impl Metrics ===
+
+impl Metrics {
+    /// Returns the total number of TLS connections that were closed by the peer
+    /// partway through a record.
+    pub fn truncated_records(&self) -> u64 {
+        #[cfg(feature = "rustls")]
+        {
+            self.rustls.truncated_records()
+        }
+
+        #[cfg(not(feature = "rustls"))]
+        {
+            0
+        }
+    }
+
+    /// Returns the number of successful TLS handshakes by the side of the
+    /// connection and the protocol version and cipher suite they negotiated.
+    pub fn negotiated(&self) -> Vec<Negotiated> {
+        #[cfg(feature = "rustls")]
+        {
+            let client = self
+                .rustls
+                .client_handshakes()
+                .into_iter()
+                .map(|h| (Side::Client, h));
+            let server = self
+                .rustls
+                .server_handshakes()
+                .into_iter()
+                .map(|h| (Side::Server, h));
+            client
+                .chain(server)
+                .map(|(side, (negotiated, handshakes))| Negotiated {
+                    side,
+                    version: format!("{:?}", negotiated.version),
+                    cipher: format!("{:?}", negotiated.cipher),
+                    handshakes,
+                })
+                .collect()
+        }
+
+        #[cfg(not(feature = "rustls"))]
+        {
+            Vec::new()
+        }
+    }
+}
+
 // === impl Mode ===
 
 #[cfg(feature = "rustls")]
@@ -85,11 +167,15 @@ impl Mode {
         roots_pem: &str,
         key_pkcs8: &[u8],
         csr: &[u8],
+        pins: PinnedCerts,
+        metrics: Metrics,
     ) -> Result<(creds::Store, creds::Receiver)> {
         match self {
             #[cfg(feature = "boring")]
             Self::Boring => {
-                let (store, receiver) = boring::creds::watch(identity, roots_pem, key_pkcs8, csr)?;
+                let _ = metrics;
+                let (store, receiver) =
+                    boring::creds::watch(identity, roots_pem, key_pkcs8, csr, pins)?;
                 Ok((
                     creds::Store::Boring(store),
                     creds::Receiver::Boring(receiver),
@@ -98,7 +184,14 @@ impl Mode {
 
             #[cfg(feature = "rustls")]
             Self::Rustls => {
-                let (store, receiver) = rustls::creds::watch(identity, roots_pem, key_pkcs8, csr)?;
+                let (store, receiver) = rustls::creds::watch(
+                    identity,
+                    roots_pem,
+                    key_pkcs8,
+                    csr,
+                    pins,
+                    metrics.rustls,
+                )?;
                 Ok((
                     creds::Store::Rustls(store),
                     creds::Receiver::Rustls(receiver),
@@ -106,7 +199,7 @@ impl Mode {
             }
 
             #[cfg(not(feature = "__has_any_tls_impls"))]
-            _ => no_tls!(identity, roots_pem, key_pkcs8, csr),
+            _ => no_tls!(identity, roots_pem, key_pkcs8, csr, pins, metrics),
         }
     }
 }
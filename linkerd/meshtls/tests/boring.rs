@@ -13,7 +13,7 @@ async fn plaintext() {
 
 #[tokio::test(flavor = "current_thread")]
 async fn proxy_to_proxy_tls_works() {
-    util::proxy_to_proxy_tls_works(Mode::Boring).await;
+    util::proxy_to_proxy_tls_works(Mode::Boring, Default::default()).await;
 }
 
 #[tokio::test(flavor = "current_thread")]
@@ -4,7 +4,7 @@
 
 mod util;
 
-use linkerd_meshtls::Mode;
+use linkerd_meshtls::{Metrics, Mode, Side};
 
 #[tokio::test(flavor = "current_thread")]
 async fn plaintext() {
@@ -13,10 +13,33 @@ async fn plaintext() {
 
 #[tokio::test(flavor = "current_thread")]
 async fn proxy_to_proxy_tls_works() {
-    util::proxy_to_proxy_tls_works(Mode::Rustls).await;
+    util::proxy_to_proxy_tls_works(Mode::Rustls, Default::default()).await;
 }
 
 #[tokio::test(flavor = "current_thread")]
 async fn proxy_to_proxy_tls_pass_through_when_identity_does_not_match() {
     util::proxy_to_proxy_tls_pass_through_when_identity_does_not_match(Mode::Rustls).await;
 }
+
+#[tokio::test(flavor = "current_thread")]
+async fn counts_negotiated_parameters() {
+    let metrics = Metrics::default();
+    util::proxy_to_proxy_tls_works(Mode::Rustls, metrics.clone()).await;
+
+    let negotiated = metrics.negotiated();
+    for side in [Side::Client, Side::Server] {
+        let handshakes = negotiated
+            .iter()
+            .find(|n| {
+                n.side == side
+                    && n.version == "TLSv1_3"
+                    && n.cipher == "TLS13_CHACHA20_POLY1305_SHA256"
+            })
+            .map_or(0, |n| n.handshakes);
+        assert_eq!(
+            handshakes, 1,
+            "{:?} handshakes must be counted: {:?}",
+            side, negotiated
+        );
+    }
+}
@@ -22,8 +22,8 @@ use tokio::net::TcpStream;
 use tracing::Instrument;
 
 pub async fn plaintext(mode: meshtls::Mode) {
-    let (_foo, _, server_tls) = load(mode, &test_util::FOO_NS1);
-    let (_bar, client_tls, _) = load(mode, &test_util::BAR_NS1);
+    let (_foo, _, server_tls) = load(mode, &test_util::FOO_NS1, Default::default());
+    let (_bar, client_tls, _) = load(mode, &test_util::BAR_NS1, Default::default());
     let (client_result, server_result) = run_test(
         client_tls,
         Conditional::None(tls::NoClientTls::NotProvidedByServiceDiscovery),
@@ -46,9 +46,9 @@ pub async fn plaintext(mode: meshtls::Mode) {
     assert_eq!(&server_result.result.expect("ping")[..], PING);
 }
 
-pub async fn proxy_to_proxy_tls_works(mode: meshtls::Mode) {
-    let (_foo, _, server_tls) = load(mode, &test_util::FOO_NS1);
-    let (_bar, client_tls, _) = load(mode, &test_util::BAR_NS1);
+pub async fn proxy_to_proxy_tls_works(mode: meshtls::Mode, metrics: meshtls::Metrics) {
+    let (_foo, _, server_tls) = load(mode, &test_util::FOO_NS1, metrics.clone());
+    let (_bar, client_tls, _) = load(mode, &test_util::BAR_NS1, metrics);
     let server_id = tls::ServerId(test_util::FOO_NS1.name.parse().unwrap());
     let (client_result, server_result) = run_test(
         client_tls.clone(),
@@ -77,11 +77,11 @@ pub async fn proxy_to_proxy_tls_works(mode: meshtls::Mode) {
 }
 
 pub async fn proxy_to_proxy_tls_pass_through_when_identity_does_not_match(mode: meshtls::Mode) {
-    let (_foo, _, server_tls) = load(mode, &test_util::FOO_NS1);
+    let (_foo, _, server_tls) = load(mode, &test_util::FOO_NS1, Default::default());
 
     // Misuse the client's identity instead of the server's identity. Any
     // identity other than `server_tls.server_identity` would work.
-    let (_bar, client_tls, _) = load(mode, &test_util::BAR_NS1);
+    let (_bar, client_tls, _) = load(mode, &test_util::BAR_NS1, Default::default());
     let sni = test_util::BAR_NS1.name.parse::<Name>().unwrap();
 
     let (client_result, server_result) = run_test(
@@ -114,6 +114,7 @@ type ServerConn<T, I> = (
 fn load(
     mode: meshtls::Mode,
     ent: &test_util::Entity,
+    metrics: meshtls::Metrics,
 ) -> (meshtls::creds::Store, meshtls::NewClient, meshtls::Server) {
     let roots_pem = std::str::from_utf8(ent.trust_anchors).expect("valid PEM");
     let (mut store, rx) = mode
@@ -122,6 +123,8 @@ fn load(
             roots_pem,
             ent.key,
             b"fake CSR data",
+            Default::default(),
+            metrics,
         )
         .expect("credentials must be readable");
 
@@ -241,7 +244,7 @@ where
                     sender
                         .send(Transported {
                             tls: None,
-                            result: Err(e),
+                            result: Err(io::Error::new(io::ErrorKind::Other, e)),
                         })
                         .expect("send result");
                 }
@@ -8,7 +8,7 @@ use super::{Counter, Factor, FmtLabels, FmtMetric};
 #[derive(Debug)]
 pub struct Histogram<V: Into<u64>, F = ()> {
     bounds: &'static Bounds,
-    buckets: Box<[Counter<F>]>,
+    buckets: Box<[Counter]>,
 
     /// The total sum of all observed latency values.
     ///
@@ -30,7 +30,7 @@ pub struct Histogram<V: Into<u64>, F = ()> {
     // TODO: Implement Prometheus reset semantics correctly, taking into consideration
     //       that Prometheus represents this as `f64` and so there are only 52 significant
     //       bits.
-    sum: Counter,
+    sum: Counter<F>,
 
     _p: PhantomData<V>,
 }
@@ -172,8 +172,8 @@ impl<V: Into<u64>, F: Factor + std::fmt::Debug> Histogram<V, F> {
 }
 
 impl<'a, V: Into<u64>, F> IntoIterator for &'a Histogram<V, F> {
-    type Item = (&'a Bucket, &'a Counter<F>);
-    type IntoIter = iter::Zip<slice::Iter<'a, Bucket>, slice::Iter<'a, Counter<F>>>;
+    type Item = (&'a Bucket, &'a Counter);
+    type IntoIter = iter::Zip<slice::Iter<'a, Bucket>, slice::Iter<'a, Counter>>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.bounds.0.iter().zip(self.buckets.iter())
@@ -184,7 +184,7 @@ impl<V: Into<u64>, F: Factor> FmtMetric for Histogram<V, F> {
     const KIND: &'static str = "histogram";
 
     fn fmt_metric<N: fmt::Display>(&self, f: &mut fmt::Formatter<'_>, name: N) -> fmt::Result {
-        let total = Counter::<F>::new();
+        let total = Counter::<()>::new();
         for (le, count) in self {
             total.add(count.into());
             total.fmt_metric_labeled(f, format_args!("{}_bucket", &name), Label("le", le))?;
@@ -204,7 +204,7 @@ impl<V: Into<u64>, F: Factor> FmtMetric for Histogram<V, F> {
         N: fmt::Display,
         L: FmtLabels,
     {
-        let total = Counter::<F>::new();
+        let total = Counter::<()>::new();
         for (le, count) in self {
             total.add(count.into());
             total.fmt_metric_labeled(
@@ -332,6 +332,34 @@ mod tests {
         Bucket::Inf,
     ]);
 
+    /// Formats a histogram's metrics as `hist_*` series.
+    struct Fmt<'a, F>(&'a Histogram<u64, F>);
+
+    impl<F: Factor> fmt::Display for Fmt<'_, F> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.fmt_metric(f, "hist")
+        }
+    }
+
+    #[test]
+    fn scales_sum_but_not_bucket_counts() {
+        let hist = Histogram::<u64, crate::MicrosAsSeconds>::new(BOUNDS);
+        hist.add(250_000u64);
+        hist.add(1_500_000u64);
+
+        // Bucket counts are numbers of observations, so they must not be
+        // scaled by the histogram's factor.
+        hist.assert_bucket_exactly(0.3, 1.0)
+            .assert_bucket_exactly(2.0, 1.0);
+        assert_eq!(hist.sum.value(), 1.75);
+
+        let text = Fmt(&hist).to_string();
+        assert!(text.contains("hist_bucket{le=\"0.3\"} 1\n"), "{}", text);
+        assert!(text.contains("hist_bucket{le=\"+Inf\"} 2\n"), "{}", text);
+        assert!(text.contains("hist_count 2\n"), "{}", text);
+        assert!(text.contains("hist_sum 1.75\n"), "{}", text);
+    }
+
     quickcheck! {
         fn bucket_incremented(obs: u64) -> bool {
             let hist = Histogram::<u64>::new(BOUNDS);
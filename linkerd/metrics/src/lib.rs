@@ -23,9 +23,9 @@ pub use self::summary::Summary;
 pub use self::{
     counter::Counter,
     gauge::Gauge,
-    histogram::Histogram,
+    histogram::{Bounds, Bucket, Histogram},
     prom::{FmtLabels, FmtMetric, FmtMetrics, Metric},
-    scopes::Scopes,
+    scopes::{Created, Scopes},
     serve::Serve,
     store::{LastUpdate, SharedStore, Store},
 };
@@ -49,8 +49,10 @@ pub trait Factor {
     fn factor(n: u64) -> f64;
 }
 
+#[derive(Debug)]
 pub struct MicrosAsSeconds;
 
+#[derive(Debug)]
 pub struct MillisAsSeconds;
 
 /// Largest `u64` that can fit without loss of precision in `f64` (2^53).
@@ -1,7 +1,19 @@
+use crate::scopes::Created;
 use std::fmt;
 use std::marker::{PhantomData, Sized};
+use std::time::UNIX_EPOCH;
+
+/// Metric name suffixes that are reported as a metric family's `UNIT` in
+/// OpenMetrics output.
+const UNITS: &[&str] = &["seconds", "bytes"];
 
 /// Writes a block of metrics in prometheus-formatted output.
+///
+/// When formatted with the alternate flag (i.e. `{:#}`), metrics are written
+/// in the OpenMetrics text format: counter families are described without
+/// their `_total` suffix, `# UNIT` metadata is emitted for metrics with a unit
+/// suffix, and the counter samples of [`Created`] scopes are followed by a
+/// `_created` sample.
 pub trait FmtMetrics {
     fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
 
@@ -84,6 +96,20 @@ impl<'a, N: fmt::Display, M: FmtMetric> Metric<'a, N, M> {
 
     /// Formats help messages for this metric.
     pub fn fmt_help(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            let name = self.name.to_string();
+            let family = Self::family(&name);
+            writeln!(f, "# HELP {} {}", family, self.help)?;
+            writeln!(f, "# TYPE {} {}", family, M::KIND)?;
+            if let Some(unit) = UNITS
+                .iter()
+                .find(|u| family.strip_suffix(*u).map_or(false, |n| n.ends_with('_')))
+            {
+                writeln!(f, "# UNIT {} {}", family, unit)?;
+            }
+            return Ok(());
+        }
+
         writeln!(f, "# HELP {} {}", self.name, self.help)?;
         writeln!(f, "# TYPE {} {}", self.name, M::KIND)?;
         Ok(())
@@ -91,6 +117,11 @@ impl<'a, N: fmt::Display, M: FmtMetric> Metric<'a, N, M> {
 
     /// Formats a single metric without labels.
     pub fn fmt_metric(&self, f: &mut fmt::Formatter<'_>, metric: &M) -> fmt::Result {
+        if f.alternate() && M::KIND == "counter" {
+            let name = self.name.to_string();
+            return metric.fmt_metric(f, format_args!("{}_total", Self::family(&name)));
+        }
+
         metric.fmt_metric(f, &self.name)
     }
 
@@ -101,6 +132,15 @@ impl<'a, N: fmt::Display, M: FmtMetric> Metric<'a, N, M> {
         metric: &M,
         labels: &L,
     ) -> fmt::Result {
+        if f.alternate() && M::KIND == "counter" {
+            let name = self.name.to_string();
+            return metric.fmt_metric_labeled(
+                f,
+                format_args!("{}_total", Self::family(&name)),
+                labels,
+            );
+        }
+
         metric.fmt_metric_labeled(f, &self.name, labels)
     }
 
@@ -116,12 +156,66 @@ impl<'a, N: fmt::Display, M: FmtMetric> Metric<'a, N, M> {
         I: IntoIterator<Item = (L, &'s S)>,
         F: Fn(&S) -> &M,
     {
+        if f.alternate() && M::KIND == "counter" {
+            let name = self.name.to_string();
+            let family = Self::family(&name);
+            for (labels, scope) in scopes {
+                to_metric(scope).fmt_metric_labeled(f, format_args!("{}_total", family), labels)?;
+            }
+            return Ok(());
+        }
+
         for (labels, scope) in scopes {
             to_metric(scope).fmt_metric_labeled(f, &self.name, labels)?;
         }
 
         Ok(())
     }
+
+    /// Formats a single metric across labeled scopes that track when they were
+    /// created.
+    ///
+    /// In OpenMetrics output, each counter sample is followed by a `_created`
+    /// sample with its scope's creation time.
+    pub fn fmt_created_scopes<'s, L, S: 's, I, F>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        scopes: I,
+        to_metric: F,
+    ) -> fmt::Result
+    where
+        L: FmtLabels,
+        I: IntoIterator<Item = (L, &'s Created<S>)>,
+        F: Fn(&S) -> &M,
+    {
+        if !(f.alternate() && M::KIND == "counter") {
+            return self.fmt_scopes(f, scopes, |scope| to_metric(scope));
+        }
+
+        let name = self.name.to_string();
+        let family = Self::family(&name);
+        for (labels, scope) in scopes {
+            to_metric(scope).fmt_metric_labeled(f, format_args!("{}_total", family), &labels)?;
+            if let Ok(created) = scope.created().duration_since(UNIX_EPOCH) {
+                write!(f, "{}_created{{", family)?;
+                labels.fmt_labels(f)?;
+                writeln!(f, "}} {}", created.as_secs_f64())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the OpenMetrics family name for a metric.
+    ///
+    /// Counter families are named without the `_total` suffix that is required
+    /// on their samples.
+    fn family(name: &str) -> &str {
+        if M::KIND == "counter" {
+            return name.strip_suffix("_total").unwrap_or(name);
+        }
+        name
+    }
 }
 
 impl<N: fmt::Display, M: FmtMetric> fmt::Debug for Metric<'_, N, M> {
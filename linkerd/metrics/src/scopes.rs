@@ -1,5 +1,5 @@
 use super::prom::FmtLabels;
-use std::{collections::HashMap, hash::Hash};
+use std::{collections::HashMap, hash::Hash, ops::Deref, time::SystemTime};
 
 /// Holds an `S`-typed scope for each `L`-typed label set.
 ///
@@ -7,6 +7,18 @@ use std::{collections::HashMap, hash::Hash};
 #[derive(Debug)]
 pub struct Scopes<L: FmtLabels + Hash + Eq, S>(HashMap<L, S>);
 
+/// Holds an `S`-typed scope along with the time at which it was created.
+///
+/// The scope's counters are reported with `_created` samples in OpenMetrics
+/// output when formatted with [`Metric::fmt_created_scopes`].
+///
+/// [`Metric::fmt_created_scopes`]: crate::Metric::fmt_created_scopes
+#[derive(Debug)]
+pub struct Created<S> {
+    scope: S,
+    created: SystemTime,
+}
+
 impl<L: FmtLabels + Hash + Eq, S> Default for Scopes<L, S> {
     fn default() -> Self {
         Scopes(HashMap::default())
@@ -48,3 +60,32 @@ impl<'a, L: FmtLabels + Hash + Eq, S> IntoIterator for &'a Scopes<L, S> {
         self.0.iter()
     }
 }
+
+// === impl Created ===
+
+impl<S> Created<S> {
+    pub fn new(scope: S) -> Self {
+        Self {
+            scope,
+            created: SystemTime::now(),
+        }
+    }
+
+    pub fn created(&self) -> SystemTime {
+        self.created
+    }
+}
+
+impl<S: Default> Default for Created<S> {
+    fn default() -> Self {
+        Self::new(S::default())
+    }
+}
+
+impl<S> Deref for Created<S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &self.scope
+    }
+}
@@ -5,7 +5,13 @@ use tracing::trace;
 
 use super::FmtMetrics;
 
+const TEXT_CONTENT_TYPE: &str = "text/plain";
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
 /// Serve Prometheues metrics.
+///
+/// Metrics are served in the OpenMetrics text format when the request's
+/// `Accept` header includes `application/openmetrics-text`.
 #[derive(Debug, Clone)]
 pub struct Serve<M> {
     metrics: M,
@@ -30,26 +36,155 @@ impl<M> Serve<M> {
                     .unwrap_or(false)
             })
     }
+
+    fn is_openmetrics<B>(req: &http::Request<B>) -> bool {
+        req.headers()
+            .get_all(http::header::ACCEPT)
+            .iter()
+            .any(|value| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| value.contains("application/openmetrics-text"))
+                    .unwrap_or(false)
+            })
+    }
 }
 
 impl<M: FmtMetrics> Serve<M> {
     pub fn serve<B>(&self, req: http::Request<B>) -> std::io::Result<http::Response<Body>> {
+        let openmetrics = Self::is_openmetrics(&req);
+        let content_type = if openmetrics {
+            OPENMETRICS_CONTENT_TYPE
+        } else {
+            TEXT_CONTENT_TYPE
+        };
+
         if Self::is_gzip(&req) {
-            trace!("gzipping metrics");
-            let mut writer = GzEncoder::new(Vec::<u8>::new(), CompressionOptions::fast());
-            write!(&mut writer, "{}", self.metrics.as_display())?;
+            trace!(openmetrics, "gzipping metrics");
+            let writer = GzEncoder::new(Vec::<u8>::new(), CompressionOptions::fast());
+            let writer = self.write_metrics(writer, openmetrics)?;
             Ok(http::Response::builder()
                 .header(http::header::CONTENT_ENCODING, "gzip")
-                .header(http::header::CONTENT_TYPE, "text/plain")
+                .header(http::header::CONTENT_TYPE, content_type)
                 .body(writer.finish()?.into())
                 .expect("Response must be valid"))
         } else {
-            let mut writer = Vec::<u8>::new();
-            write!(&mut writer, "{}", self.metrics.as_display())?;
+            let writer = self.write_metrics(Vec::<u8>::new(), openmetrics)?;
             Ok(http::Response::builder()
-                .header(http::header::CONTENT_TYPE, "text/plain")
+                .header(http::header::CONTENT_TYPE, content_type)
                 .body(Body::from(writer))
                 .expect("Response must be valid"))
         }
     }
+
+    fn write_metrics<W: Write>(&self, mut writer: W, openmetrics: bool) -> std::io::Result<W> {
+        if openmetrics {
+            write!(&mut writer, "{:#}", self.metrics.as_display())?;
+            writeln!(&mut writer, "# EOF")?;
+        } else {
+            write!(&mut writer, "{}", self.metrics.as_display())?;
+        }
+        Ok(writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Counter, Created, FmtLabels, Gauge, Metric};
+    use std::fmt;
+
+    struct Labels;
+
+    impl FmtLabels for Labels {
+        fn fmt_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "foo=\"bar\"")
+        }
+    }
+
+    struct Report {
+        requests: Created<Counter>,
+        errors: Counter,
+        uptime: Gauge,
+    }
+
+    impl FmtMetrics for Report {
+        fn fmt_metrics(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let requests = Metric::<_, Counter>::new("requests_total", "Total requests.");
+            requests.fmt_help(f)?;
+            requests.fmt_created_scopes(f, Some((Labels, &self.requests)), |c| c)?;
+
+            let errors = Metric::<_, Counter>::new("errors_total", "Total errors.");
+            errors.fmt_help(f)?;
+            errors.fmt_scopes(f, Some((Labels, &self.errors)), |c| c)?;
+
+            let uptime = Metric::<_, Gauge>::new("uptime_seconds", "Uptime.");
+            uptime.fmt_help(f)?;
+            uptime.fmt_metric(f, &self.uptime)
+        }
+    }
+
+    fn report() -> Serve<Report> {
+        Serve::new(Report {
+            requests: Created::new(Counter::from(3)),
+            errors: Counter::from(1),
+            uptime: Gauge::from(7),
+        })
+    }
+
+    fn body(serve: &Serve<Report>, openmetrics: bool) -> String {
+        let buf = serve.write_metrics(Vec::new(), openmetrics).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn prometheus_text() {
+        let serve = report();
+        let rsp = serve.serve(http::Request::new(())).unwrap();
+        assert_eq!(rsp.headers()[http::header::CONTENT_TYPE], TEXT_CONTENT_TYPE);
+
+        let body = body(&serve, false);
+        assert!(body.contains("# TYPE requests_total counter\n"), "{}", body);
+        assert!(body.contains("requests_total{foo=\"bar\"} 3\n"), "{}", body);
+        assert!(body.contains("errors_total{foo=\"bar\"} 1\n"), "{}", body);
+        assert!(!body.contains("_created"), "{}", body);
+        assert!(!body.contains("# UNIT"), "{}", body);
+        assert!(!body.contains("# EOF"), "{}", body);
+    }
+
+    #[test]
+    fn openmetrics_text() {
+        let serve = report();
+        let req = http::Request::builder()
+            .header(
+                http::header::ACCEPT,
+                "application/openmetrics-text; version=1.0.0,text/plain;q=0.5",
+            )
+            .body(())
+            .unwrap();
+        let rsp = serve.serve(req).unwrap();
+        assert_eq!(
+            rsp.headers()[http::header::CONTENT_TYPE],
+            OPENMETRICS_CONTENT_TYPE
+        );
+
+        let body = body(&serve, true);
+        assert!(
+            body.contains("# HELP requests Total requests.\n"),
+            "{}",
+            body
+        );
+        assert!(body.contains("# TYPE requests counter\n"), "{}", body);
+        assert!(body.contains("requests_total{foo=\"bar\"} 3\n"), "{}", body);
+        assert!(body.contains("requests_created{foo=\"bar\"} "), "{}", body);
+        assert!(body.contains("# TYPE errors counter\n"), "{}", body);
+        assert!(body.contains("errors_total{foo=\"bar\"} 1\n"), "{}", body);
+        assert!(!body.contains("errors_created"), "{}", body);
+        assert!(body.contains("# TYPE uptime_seconds gauge\n"), "{}", body);
+        assert!(body.contains("# UNIT uptime_seconds seconds\n"), "{}", body);
+        assert!(body.contains("uptime_seconds 7\n"), "{}", body);
+        assert!(!body.contains("uptime_seconds_created"), "{}", body);
+        assert!(body.ends_with("# EOF\n"), "{}", body);
+    }
 }
@@ -1,4 +1,6 @@
 use http::uri::Authority;
+use linkerd_proxy_core::EndpointWeight;
+use linkerd_stack::Param;
 use linkerd_tls::client::ServerId;
 use std::collections::BTreeMap;
 
@@ -22,6 +24,9 @@ pub struct Metadata {
 
     /// Used to override the the authority if needed
     authority_override: Option<Authority>,
+
+    /// The endpoint's weight relative to the other endpoints of its service.
+    weight: u32,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
@@ -43,11 +48,15 @@ impl Default for Metadata {
             authority_override: None,
             tagged_transport_port: None,
             protocol_hint: ProtocolHint::Unknown,
+            weight: Self::DEFAULT_WEIGHT,
         }
     }
 }
 
 impl Metadata {
+    /// The weight of endpoints whose weight is not set.
+    const DEFAULT_WEIGHT: u32 = 1;
+
     pub fn new(
         labels: impl IntoIterator<Item = (String, String)>,
         protocol_hint: ProtocolHint,
@@ -61,9 +70,27 @@ impl Metadata {
             tagged_transport_port,
             identity,
             authority_override,
+            weight: Self::DEFAULT_WEIGHT,
+        }
+    }
+
+    /// Sets the endpoint's weight. Endpoints without a weight have a weight
+    /// of 1.
+    pub fn with_weight(self, weight: u32) -> Self {
+        Self {
+            weight: if weight == 0 {
+                Self::DEFAULT_WEIGHT
+            } else {
+                weight
+            },
+            ..self
         }
     }
 
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+
     /// Returns the endpoint's labels from the destination service, if it has them.
     pub fn labels(&self) -> Labels {
         self.labels.clone()
@@ -85,3 +112,9 @@ impl Metadata {
         self.authority_override.as_ref()
     }
 }
+
+impl Param<EndpointWeight> for Metadata {
+    fn param(&self) -> EndpointWeight {
+        EndpointWeight(self.weight)
+    }
+}
@@ -51,7 +51,8 @@ pub fn to_addr_meta(
         tagged_transport_port,
         tls_id,
         authority_override,
-    );
+    )
+    .with_weight(pb.weight);
     Some((addr, meta))
 }
 
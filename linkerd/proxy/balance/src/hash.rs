@@ -0,0 +1,238 @@
+//! A balancer that selects endpoints by a hash of each request.
+//!
+//! Each request is hashed by a [`HashRequest`] key and dispatched to the ready
+//! endpoint with the highest rendezvous score for that hash, so that requests
+//! with the same key are sent to the same endpoint for as long as it is ready,
+//! and adding or removing an endpoint only moves the keys that it is selected
+//! for. Endpoint weights are not considered.
+
+use crate::discover;
+use futures::{future, TryFutureExt};
+use indexmap::IndexMap;
+use linkerd_error::Error;
+use linkerd_proxy_core::Resolve;
+use linkerd_stack::{layer, NewService, Param, Service};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tower::discover::{Change, Discover};
+use tracing::{debug, trace};
+
+#[cfg(test)]
+mod tests;
+
+/// Computes the hash by which a request is assigned to an endpoint.
+pub trait HashRequest<Req> {
+    fn hash_request(&self, req: &Req) -> u64;
+}
+
+/// Configures a stack to resolve targets to balance requests over `N`-typed
+/// endpoint stacks by the hash of each request's `K`-typed key, which is
+/// provided by the target.
+#[derive(Debug)]
+pub struct NewBalanceHashed<K, Req, R, N> {
+    update_queue_capacity: usize,
+    resolve: R,
+    inner: N,
+    _marker: PhantomData<fn(Req) -> K>,
+}
+
+pub type Balance<K, Req, S> = Hashed<discover::Buffer<S>, K, Req>;
+
+/// Balances requests over a discovered set of services by the hash of each
+/// request.
+pub struct Hashed<D: Discover, K, Req> {
+    discover: D,
+    key: K,
+    endpoints: IndexMap<D::Key, D::Service>,
+    /// The indices of the endpoints that are ready to receive the next
+    /// request.
+    ready: Vec<usize>,
+    _marker: PhantomData<fn(Req)>,
+}
+
+// === impl NewBalanceHashed ===
+
+impl<K, Req, R, N> NewBalanceHashed<K, Req, R, N> {
+    /// See [`crate::NewBalancePeakEwma`].
+    const UPDATE_QUEUE_CAPACITY: usize = 1_000;
+
+    pub fn new(inner: N, resolve: R) -> Self {
+        Self {
+            update_queue_capacity: Self::UPDATE_QUEUE_CAPACITY,
+            resolve,
+            inner,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn layer(resolve: R) -> impl layer::Layer<N, Service = Self> + Clone
+    where
+        R: Clone,
+    {
+        layer::mk(move |inner| Self::new(inner, resolve.clone()))
+    }
+}
+
+impl<T, K, Req, R, M, N, S> NewService<T> for NewBalanceHashed<K, Req, R, M>
+where
+    T: Param<K> + Clone + Send,
+    K: HashRequest<Req>,
+    R: Resolve<T>,
+    M: NewService<T, Service = N> + Clone,
+    N: NewService<(SocketAddr, R::Endpoint), Service = S> + Send + 'static,
+    S: Service<Req> + Send,
+    S::Error: Into<Error>,
+{
+    type Service = Balance<K, Req, S>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let key = target.param();
+        let disco = discover::spawn_new_from_resolve(
+            self.update_queue_capacity,
+            self.resolve.clone(),
+            self.inner.clone(),
+            target,
+        );
+        Hashed::new(disco, key)
+    }
+}
+
+impl<K, Req, R: Clone, N: Clone> Clone for NewBalanceHashed<K, Req, R, N> {
+    fn clone(&self) -> Self {
+        Self {
+            update_queue_capacity: self.update_queue_capacity,
+            resolve: self.resolve.clone(),
+            inner: self.inner.clone(),
+            _marker: self._marker,
+        }
+    }
+}
+
+// === impl Hashed ===
+
+impl<D: Discover, K, Req> Hashed<D, K, Req> {
+    pub fn new(discover: D, key: K) -> Self {
+        Self {
+            discover,
+            key,
+            endpoints: IndexMap::new(),
+            ready: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<D, K, S, Req> Hashed<D, K, Req>
+where
+    D: Discover<Service = S> + Unpin,
+    D::Key: Hash + Debug,
+    D::Error: Into<Error>,
+    S: Service<Req>,
+    S::Error: Into<Error>,
+{
+    /// Applies all pending discovery updates.
+    fn update_endpoints(&mut self, cx: &mut Context<'_>) -> Result<(), Error> {
+        while let Poll::Ready(change) = Pin::new(&mut self.discover).poll_discover(cx) {
+            match change.transpose().map_err(Into::into)? {
+                Some(Change::Insert(key, service)) => {
+                    trace!(?key, "Inserting endpoint");
+                    self.endpoints.insert(key, service);
+                }
+                Some(Change::Remove(key)) => {
+                    trace!(?key, "Removing endpoint");
+                    self.endpoints.shift_remove(&key);
+                }
+                // The resolution has ended; its endpoints remain in use.
+                None => return Ok(()),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<D, K, S, Req> Service<Req> for Hashed<D, K, Req>
+where
+    D: Discover<Service = S> + Unpin,
+    D::Key: Hash + Debug,
+    D::Error: Into<Error>,
+    K: HashRequest<Req>,
+    S: Service<Req>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::ErrInto<S::Future, Error>;
+
+    /// Polls all endpoints, since the endpoint that a request is dispatched to
+    /// is not known until the request is hashed.
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.update_endpoints(cx)?;
+
+        // Endpoint indices may have changed, so readiness is determined anew.
+        self.ready.clear();
+        let mut idx = 0;
+        while idx < self.endpoints.len() {
+            match self.endpoints[idx].poll_ready(cx) {
+                Poll::Ready(Ok(())) => self.ready.push(idx),
+                Poll::Pending => {}
+                Poll::Ready(Err(error)) => {
+                    let error = error.into();
+                    debug!(%error, "Removing failed endpoint");
+                    self.endpoints.shift_remove_index(idx);
+                    continue;
+                }
+            }
+            idx += 1;
+        }
+
+        if self.ready.is_empty() {
+            trace!(endpoints = self.endpoints.len(), "No ready endpoints");
+            return Poll::Pending;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let hash = self.key.hash_request(&req);
+        let idx = self
+            .ready
+            .drain(..)
+            .max_by_key(|&idx| {
+                let (key, _) = self
+                    .endpoints
+                    .get_index(idx)
+                    .expect("ready endpoints must exist");
+                let mut hasher = DefaultHasher::new();
+                hash.hash(&mut hasher);
+                key.hash(&mut hasher);
+                hasher.finish()
+            })
+            .expect("called before ready");
+        trace!(hash, endpoint = ?self.endpoints.get_index(idx).map(|(k, _)| k), "Selected endpoint");
+        self.endpoints[idx].call(req).err_into()
+    }
+}
+
+impl<D, K, Req> Debug for Hashed<D, K, Req>
+where
+    D: Discover + Debug,
+    D::Key: Debug,
+    D::Service: Debug,
+    K: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hashed")
+            .field("discover", &self.discover)
+            .field("key", &self.key)
+            .field("endpoints", &self.endpoints)
+            .field("ready", &self.ready)
+            .finish()
+    }
+}
@@ -0,0 +1,74 @@
+use super::*;
+use linkerd_stack::{service_fn, ServiceExt};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+type Svc = linkerd_stack::BoxService<u64, &'static str, Error>;
+
+/// Hashes each request by its value.
+#[derive(Clone, Debug)]
+struct Identity;
+
+impl HashRequest<u64> for Identity {
+    fn hash_request(&self, req: &u64) -> u64 {
+        *req
+    }
+}
+
+fn endpoint(name: &'static str) -> Change<&'static str, Svc> {
+    Change::Insert(
+        name,
+        linkerd_stack::BoxService::new(service_fn(move |_: u64| future::ok::<_, Error>(name))),
+    )
+}
+
+async fn send<D>(
+    balance: &mut Hashed<D, Identity, u64>,
+    keys: std::ops::Range<u64>,
+) -> Vec<&'static str>
+where
+    D: Discover<Key = &'static str, Service = Svc> + Unpin,
+    D::Error: Into<Error>,
+{
+    let mut selected = Vec::new();
+    for key in keys {
+        selected.push(balance.ready().await.unwrap().call(key).await.unwrap());
+    }
+    selected
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn same_key_selects_same_endpoint() {
+    let (tx, rx) = mpsc::unbounded_channel();
+    for name in ["a", "b", "c"] {
+        tx.send(Ok::<_, Error>(endpoint(name))).unwrap();
+    }
+    let mut balance = Hashed::new(UnboundedReceiverStream::new(rx), Identity);
+
+    let selected = send(&mut balance, 0..100).await;
+    assert_eq!(send(&mut balance, 0..100).await, selected);
+
+    // Keys are spread across all of the endpoints.
+    for name in ["a", "b", "c"] {
+        assert!(selected.contains(&name), "{} must be selected", name);
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn removing_endpoint_only_moves_its_keys() {
+    let (tx, rx) = mpsc::unbounded_channel();
+    for name in ["a", "b", "c"] {
+        tx.send(Ok::<_, Error>(endpoint(name))).unwrap();
+    }
+    let mut balance = Hashed::new(UnboundedReceiverStream::new(rx), Identity);
+    let before = send(&mut balance, 0..100).await;
+
+    tx.send(Ok(Change::Remove("b"))).unwrap();
+    let after = send(&mut balance, 0..100).await;
+    for (before, after) in before.into_iter().zip(after) {
+        assert_ne!(after, "b");
+        if before != "b" {
+            assert_eq!(after, before);
+        }
+    }
+}
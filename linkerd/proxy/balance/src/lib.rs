@@ -9,7 +9,13 @@ use tower::{
 };
 
 mod discover;
+mod hash;
+mod wrr;
 
+pub use self::{
+    hash::{HashRequest, Hashed, NewBalanceHashed},
+    wrr::{NewBalanceWeightedRoundRobin, WeightedRoundRobin},
+};
 pub use tower::load::peak_ewma::Handle;
 
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
@@ -0,0 +1,329 @@
+//! A deterministic weighted round-robin balancer.
+//!
+//! Endpoints are selected in a smooth weighted round-robin order: over each
+//! cycle, every endpoint is selected in proportion to its weight, and the
+//! selections of heavier endpoints are interleaved with those of lighter ones
+//! rather than being sent in bursts. When the selected endpoint is not ready,
+//! the next ready endpoint (in discovery order) is used instead, and is charged
+//! for the request in the schedule.
+
+use crate::discover;
+use futures::{future, TryFutureExt};
+use indexmap::IndexMap;
+use linkerd_error::Error;
+use linkerd_proxy_core::{EndpointWeight, Resolve};
+use linkerd_stack::{layer, NewService, Param, Service};
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    marker::PhantomData,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tower::discover::{Change, Discover};
+use tracing::{debug, trace};
+
+#[cfg(test)]
+mod tests;
+
+/// Configures a stack to resolve targets to balance requests over `N`-typed
+/// endpoint stacks in weighted round-robin order.
+#[derive(Debug)]
+pub struct NewBalanceWeightedRoundRobin<Req, R, N> {
+    update_queue_capacity: usize,
+    resolve: R,
+    inner: N,
+    _marker: PhantomData<fn(Req)>,
+}
+
+pub type Balance<Req, S> = WeightedRoundRobin<discover::Buffer<Weighted<S>>, Req>;
+
+/// Balances requests over a discovered set of [`Weighted`] services.
+pub struct WeightedRoundRobin<D: Discover, Req> {
+    discover: D,
+    endpoints: IndexMap<D::Key, Endpoint<D::Service>>,
+    /// The index of the endpoint that is ready to receive the next request.
+    ready: Option<usize>,
+    _marker: PhantomData<fn(Req)>,
+}
+
+/// An endpoint service with its weight.
+#[derive(Debug)]
+pub struct Weighted<S> {
+    inner: S,
+    weight: u32,
+}
+
+/// Wraps the inner stack in [`NewWeighted`] to produce [`Weighted`] services.
+#[derive(Debug)]
+pub struct NewNewWeighted<N> {
+    inner: N,
+}
+
+/// Wraps the inner services with the weight of their endpoints.
+#[derive(Debug)]
+pub struct NewWeighted<N> {
+    inner: N,
+}
+
+#[derive(Debug)]
+struct Endpoint<S> {
+    service: S,
+    weight: i64,
+    /// The endpoint's current weight in the smooth round-robin schedule.
+    current: i64,
+}
+
+// === impl NewBalanceWeightedRoundRobin ===
+
+impl<Req, R, N> NewBalanceWeightedRoundRobin<Req, R, N> {
+    /// See [`crate::NewBalancePeakEwma`].
+    const UPDATE_QUEUE_CAPACITY: usize = 1_000;
+
+    pub fn new(inner: N, resolve: R) -> Self {
+        Self {
+            update_queue_capacity: Self::UPDATE_QUEUE_CAPACITY,
+            resolve,
+            inner,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn layer(resolve: R) -> impl layer::Layer<N, Service = Self> + Clone
+    where
+        R: Clone,
+    {
+        layer::mk(move |inner| Self::new(inner, resolve.clone()))
+    }
+}
+
+impl<T, Req, R, M, N, S> NewService<T> for NewBalanceWeightedRoundRobin<Req, R, M>
+where
+    T: Clone + Send,
+    R: Resolve<T>,
+    R::Endpoint: Param<EndpointWeight>,
+    M: NewService<T, Service = N> + Clone,
+    N: NewService<(SocketAddr, R::Endpoint), Service = S> + Send + 'static,
+    S: Service<Req> + Send,
+    S::Error: Into<Error>,
+{
+    type Service = Balance<Req, S>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let new = NewNewWeighted {
+            inner: self.inner.clone(),
+        };
+        let disco = discover::spawn_new_from_resolve(
+            self.update_queue_capacity,
+            self.resolve.clone(),
+            new,
+            target,
+        );
+        WeightedRoundRobin::new(disco)
+    }
+}
+
+impl<Req, R: Clone, N: Clone> Clone for NewBalanceWeightedRoundRobin<Req, R, N> {
+    fn clone(&self) -> Self {
+        Self {
+            update_queue_capacity: self.update_queue_capacity,
+            resolve: self.resolve.clone(),
+            inner: self.inner.clone(),
+            _marker: self._marker,
+        }
+    }
+}
+
+// === impl NewNewWeighted ===
+
+impl<T, N: NewService<T>> NewService<T> for NewNewWeighted<N> {
+    type Service = NewWeighted<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        NewWeighted {
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl NewWeighted ===
+
+impl<E, N> NewService<(SocketAddr, E)> for NewWeighted<N>
+where
+    E: Param<EndpointWeight>,
+    N: NewService<(SocketAddr, E)>,
+{
+    type Service = Weighted<N::Service>;
+
+    fn new_service(&self, (addr, endpoint): (SocketAddr, E)) -> Self::Service {
+        let EndpointWeight(weight) = endpoint.param();
+        Weighted {
+            inner: self.inner.new_service((addr, endpoint)),
+            weight,
+        }
+    }
+}
+
+// === impl WeightedRoundRobin ===
+
+impl<D: Discover, Req> WeightedRoundRobin<D, Req> {
+    pub fn new(discover: D) -> Self {
+        Self {
+            discover,
+            endpoints: IndexMap::new(),
+            ready: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<D, S, Req> WeightedRoundRobin<D, Req>
+where
+    D: Discover<Service = Weighted<S>> + Unpin,
+    D::Key: Hash + Debug,
+    D::Error: Into<Error>,
+    S: Service<Req>,
+    S::Error: Into<Error>,
+{
+    /// Applies all pending discovery updates.
+    fn update_endpoints(&mut self, cx: &mut Context<'_>) -> Result<(), Error> {
+        while let Poll::Ready(change) = Pin::new(&mut self.discover).poll_discover(cx) {
+            match change.transpose().map_err(Into::into)? {
+                Some(Change::Insert(key, service)) => {
+                    trace!(?key, weight = service.weight, "Inserting endpoint");
+                    let endpoint = Endpoint {
+                        weight: i64::from(service.weight.max(1)),
+                        service,
+                        current: 0,
+                    };
+                    let (idx, replaced) = self.endpoints.insert_full(key, endpoint);
+                    if replaced.is_some() && self.ready == Some(idx) {
+                        self.ready = None;
+                    }
+                }
+                Some(Change::Remove(key)) => {
+                    trace!(?key, "Removing endpoint");
+                    // Removing an endpoint shifts the indices of later
+                    // endpoints.
+                    if self.endpoints.shift_remove(&key).is_some() {
+                        self.ready = None;
+                    }
+                }
+                // The resolution has ended; its endpoints remain in use.
+                None => return Ok(()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the index of the next endpoint in the smooth weighted
+    /// round-robin schedule, without advancing the schedule.
+    fn next_index(&self) -> usize {
+        let mut selected: Option<(usize, i64)> = None;
+        for (idx, ep) in self.endpoints.values().enumerate() {
+            let current = ep.current + ep.weight;
+            if selected.map_or(true, |(_, c)| current > c) {
+                selected = Some((idx, current));
+            }
+        }
+        let (idx, _) = selected.expect("endpoints must not be empty");
+        idx
+    }
+
+    /// Advances the smooth weighted round-robin schedule once the endpoint at
+    /// `idx` has been selected.
+    ///
+    /// The schedule only advances when an endpoint is ready, so that polling
+    /// endpoints that are not ready does not skew it.
+    fn advance(&mut self, idx: usize) {
+        let total = self.endpoints.values().map(|ep| ep.weight).sum::<i64>();
+        for ep in self.endpoints.values_mut() {
+            ep.current += ep.weight;
+        }
+        self.endpoints[idx].current -= total;
+    }
+}
+
+impl<D, S, Req> Service<Req> for WeightedRoundRobin<D, Req>
+where
+    D: Discover<Service = Weighted<S>> + Unpin,
+    D::Key: Hash + Debug,
+    D::Error: Into<Error>,
+    S: Service<Req>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::ErrInto<S::Future, Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.update_endpoints(cx)?;
+
+        'select: loop {
+            // An endpoint remains selected until a request is dispatched to
+            // it.
+            if let Some(idx) = self.ready {
+                match self.endpoints[idx].service.inner.poll_ready(cx) {
+                    Poll::Ready(Ok(())) => return Poll::Ready(Ok(())),
+                    Poll::Pending => self.ready = None,
+                    Poll::Ready(Err(error)) => {
+                        let error = error.into();
+                        debug!(%error, "Removing failed endpoint");
+                        self.endpoints.shift_remove_index(idx);
+                        self.ready = None;
+                    }
+                }
+            }
+
+            if self.endpoints.is_empty() {
+                trace!("No endpoints");
+                return Poll::Pending;
+            }
+
+            // Try the next endpoint in the schedule and then, if it is not
+            // ready, each of the others in order.
+            let next = self.next_index();
+            let len = self.endpoints.len();
+            for idx in (next..len).chain(0..next) {
+                match self.endpoints[idx].service.inner.poll_ready(cx) {
+                    Poll::Ready(Ok(())) => {
+                        self.advance(idx);
+                        self.ready = Some(idx);
+                        return Poll::Ready(Ok(()));
+                    }
+                    Poll::Pending => {}
+                    Poll::Ready(Err(error)) => {
+                        let error = error.into();
+                        debug!(%error, "Removing failed endpoint");
+                        self.endpoints.shift_remove_index(idx);
+                        continue 'select;
+                    }
+                }
+            }
+
+            trace!("No ready endpoints");
+            return Poll::Pending;
+        }
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let idx = self.ready.take().expect("called before ready");
+        self.endpoints[idx].service.inner.call(req).err_into()
+    }
+}
+
+impl<D, Req> Debug for WeightedRoundRobin<D, Req>
+where
+    D: Discover + Debug,
+    D::Key: Debug,
+    D::Service: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WeightedRoundRobin")
+            .field("discover", &self.discover)
+            .field("endpoints", &self.endpoints)
+            .field("ready", &self.ready)
+            .finish()
+    }
+}
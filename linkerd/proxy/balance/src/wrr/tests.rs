@@ -0,0 +1,137 @@
+use super::*;
+use futures::{stream, FutureExt};
+use linkerd_stack::{service_fn, ServiceExt};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+type Svc = Weighted<linkerd_stack::BoxService<(), &'static str, Error>>;
+
+fn endpoint(name: &'static str, weight: u32) -> Change<&'static str, Svc> {
+    Change::Insert(
+        name,
+        Weighted {
+            inner: linkerd_stack::BoxService::new(service_fn(move |()| {
+                future::ok::<_, Error>(name)
+            })),
+            weight,
+        },
+    )
+}
+
+/// An endpoint that is only ready while its gate is open.
+struct Gated {
+    name: &'static str,
+    open: Arc<AtomicBool>,
+}
+
+impl Service<()> for Gated {
+    type Response = &'static str;
+    type Error = Error;
+    type Future = future::Ready<Result<&'static str, Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        if self.open.load(Ordering::Acquire) {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn call(&mut self, (): ()) -> Self::Future {
+        future::ok(self.name)
+    }
+}
+
+fn gated(name: &'static str, weight: u32) -> (Change<&'static str, Svc>, Arc<AtomicBool>) {
+    let open = Arc::new(AtomicBool::new(false));
+    let svc = Gated {
+        name,
+        open: open.clone(),
+    };
+    let change = Change::Insert(
+        name,
+        Weighted {
+            inner: linkerd_stack::BoxService::new(svc),
+            weight,
+        },
+    );
+    (change, open)
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn cycles_endpoints_by_weight() {
+    let disco = stream::iter(vec![
+        Ok::<_, Error>(endpoint("a", 3)),
+        Ok(endpoint("b", 1)),
+        Ok(endpoint("c", 2)),
+    ]);
+    let mut balance = WeightedRoundRobin::<_, ()>::new(disco);
+
+    let mut selected = Vec::new();
+    for _ in 0..12 {
+        selected.push(balance.ready().await.unwrap().call(()).await.unwrap());
+    }
+
+    // Each cycle of 6 requests selects each endpoint in proportion to its
+    // weight, interleaving the heavier endpoints' selections.
+    let cycle = ["a", "c", "a", "b", "c", "a"];
+    assert_eq!(selected, [cycle, cycle].concat());
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn endpoints_without_weights_alternate() {
+    let disco = stream::iter(vec![Ok::<_, Error>(endpoint("a", 0)), Ok(endpoint("b", 0))]);
+    let mut balance = WeightedRoundRobin::<_, ()>::new(disco);
+
+    let mut selected = Vec::new();
+    for _ in 0..4 {
+        selected.push(balance.ready().await.unwrap().call(()).await.unwrap());
+    }
+    assert_eq!(selected, ["a", "b", "a", "b"]);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn pending_endpoints_do_not_advance_schedule() {
+    let (a, a_open) = gated("a", 3);
+    let (b, b_open) = gated("b", 1);
+    let disco = stream::iter(vec![Ok::<_, Error>(a), Ok(b)]);
+    let mut balance = WeightedRoundRobin::<_, ()>::new(disco);
+
+    // Polling while no endpoint is ready must not change the schedule.
+    for _ in 0..5 {
+        assert!(balance.ready().now_or_never().is_none());
+    }
+    a_open.store(true, Ordering::Release);
+    b_open.store(true, Ordering::Release);
+
+    let mut selected = Vec::new();
+    for _ in 0..4 {
+        selected.push(balance.ready().await.unwrap().call(()).await.unwrap());
+    }
+    assert_eq!(selected, ["a", "a", "b", "a"]);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn pending_scheduled_endpoint_is_skipped() {
+    let (a, a_open) = gated("a", 1);
+    let (b, b_open) = gated("b", 1);
+    let disco = stream::iter(vec![Ok::<_, Error>(a), Ok(b)]);
+    let mut balance = WeightedRoundRobin::<_, ()>::new(disco);
+
+    // `a` is scheduled first, but it is not ready, so `b` is selected in its
+    // place and charged for the request.
+    b_open.store(true, Ordering::Release);
+    let rsp = balance.ready().await.unwrap().call(()).await.unwrap();
+    assert_eq!(rsp, "b");
+
+    // Once `a` is ready, it catches up on the request that `b` served in its
+    // place.
+    a_open.store(true, Ordering::Release);
+    let mut selected = Vec::new();
+    for _ in 0..4 {
+        selected.push(balance.ready().await.unwrap().call(()).await.unwrap());
+    }
+    assert_eq!(selected, ["a", "a", "b", "a"]);
+}
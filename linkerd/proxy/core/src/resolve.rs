@@ -28,6 +28,10 @@ pub trait Resolve<T>: Clone + Send + Sync + Unpin + 'static {
 #[derive(Clone, Debug)]
 pub struct ResolveService<S>(S);
 
+/// The relative weight of a resolved endpoint, used by weighted balancers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EndpointWeight(pub u32);
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Update<T> {
     Reset(Vec<(SocketAddr, T)>),
@@ -1,5 +1,12 @@
 use hyper_balance::{PendingUntilFirstData, PendingUntilFirstDataBody};
 use linkerd_proxy_balance as balance;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+pub use balance::HashRequest;
 
 pub type Body<B> = PendingUntilFirstDataBody<balance::Handle, B>;
 
@@ -7,3 +14,48 @@ pub type EwmaConfig = balance::EwmaConfig;
 
 pub type NewBalancePeakEwma<B, R, N> =
     balance::NewBalancePeakEwma<PendingUntilFirstData, http::Request<B>, R, N>;
+
+pub type NewBalanceWeightedRoundRobin<B, R, N> =
+    balance::NewBalanceWeightedRoundRobin<http::Request<B>, R, N>;
+
+pub type NewBalanceHashed<B, R, N> =
+    balance::NewBalanceHashed<CompositeKey, http::Request<B>, R, N>;
+
+/// Hashes requests by their path and the values of a set of headers, so that
+/// requests for the same resource are balanced to the same endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CompositeKey {
+    headers: Arc<[http::header::HeaderName]>,
+}
+
+// === impl CompositeKey ===
+
+impl Default for CompositeKey {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl CompositeKey {
+    pub fn new(headers: impl IntoIterator<Item = http::header::HeaderName>) -> Self {
+        Self {
+            headers: headers.into_iter().collect(),
+        }
+    }
+}
+
+impl<B> HashRequest<http::Request<B>> for CompositeKey {
+    fn hash_request(&self, req: &http::Request<B>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        req.uri().path().hash(&mut hasher);
+        for header in self.headers.iter() {
+            for value in req.headers().get_all(header) {
+                value.as_bytes().hash(&mut hasher);
+            }
+            // Delimits each header's values so that a value cannot be
+            // mistaken for another header's.
+            0xffu8.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
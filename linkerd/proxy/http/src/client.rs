@@ -29,11 +29,13 @@ pub struct MakeClient<C, B> {
     connect: C,
     h1_pool: h1::PoolSettings,
     h2_settings: h2::Settings,
+    h2_pool: h2::PoolSettings,
     _marker: PhantomData<fn(B)>,
 }
 
 pub enum Client<C, T, B> {
     H2(h2::Connection<B>),
+    H2Pool(h2::Pool<C, T, B>),
     Http1(h1::Client<C, T, B>),
     OrigProtoUpgrade(orig_proto::Upgrade<C, T, B>),
 }
@@ -41,11 +43,13 @@ pub enum Client<C, T, B> {
 pub fn layer<C, B>(
     h1_pool: h1::PoolSettings,
     h2_settings: h2::Settings,
+    h2_pool: h2::PoolSettings,
 ) -> impl layer::Layer<C, Service = MakeClient<C, B>> + Copy {
     layer::mk(move |connect: C| MakeClient {
         connect,
         h1_pool,
         h2_settings,
+        h2_pool,
         _marker: PhantomData,
     })
 }
@@ -88,6 +92,7 @@ where
         let connect = self.connect.clone();
         let h1_pool = self.h1_pool;
         let h2_settings = self.h2_settings;
+        let h2_pool = self.h2_pool;
 
         Box::pin(async move {
             let settings = target.param();
@@ -95,10 +100,14 @@ where
 
             let client = match settings {
                 Settings::H2 => {
-                    let h2 = h2::Connect::new(connect, h2_settings)
-                        .oneshot(target)
-                        .await?;
-                    Client::H2(h2)
+                    let connect = h2::Connect::new(connect, h2_settings);
+                    let h2 = connect.clone().oneshot(target.clone()).await?;
+                    match h2_pool.max_connections {
+                        Some(max) if max > 1 => {
+                            Client::H2Pool(h2::Pool::new(connect, target, h2_pool, h2))
+                        }
+                        _ => Client::H2(h2),
+                    }
                 }
                 Settings::Http1 => Client::Http1(h1::Client::new(connect, target, h1_pool)),
                 Settings::OrigProtoUpgrade => {
@@ -121,6 +130,7 @@ impl<C: Clone, B> Clone for MakeClient<C, B> {
             connect: self.connect.clone(),
             h1_pool: self.h1_pool,
             h2_settings: self.h2_settings,
+            h2_pool: self.h2_pool,
             _marker: self._marker,
         }
     }
@@ -135,6 +145,7 @@ where
     T: Clone + Send + Sync + 'static,
     C: MakeConnection<(crate::Version, T)> + Clone + Send + Sync + 'static,
     C::Connection: Unpin + Send,
+    C::Metadata: Send,
     C::Future: Unpin + Send + 'static,
     C::Error: Into<Error>,
     B: hyper::body::HttpBody + Send + 'static,
@@ -149,6 +160,7 @@ where
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
         match self {
             Self::H2(ref mut svc) => svc.poll_ready(cx).map_err(Into::into),
+            Self::H2Pool(ref mut svc) => svc.poll_ready(cx),
             Self::OrigProtoUpgrade(ref mut svc) => svc.poll_ready(cx),
             Self::Http1(_) => Poll::Ready(Ok(())),
         }
@@ -156,7 +168,7 @@ where
 
     fn call(&mut self, req: http::Request<B>) -> Self::Future {
         let span = match self {
-            Self::H2(_) => debug_span!("h2"),
+            Self::H2(_) | Self::H2Pool(_) => debug_span!("h2"),
             Self::Http1(_) => debug_span!("http1"),
             Self::OrigProtoUpgrade { .. } => debug_span!("orig-proto-upgrade"),
         };
@@ -176,6 +188,7 @@ where
                         .err_into::<Error>()
                         .map_ok(|rsp| rsp.map(BoxBody::new)),
                 ) as RspFuture,
+                Self::H2Pool(ref mut svc) => svc.call(req),
             }
         })
         .instrument(span)
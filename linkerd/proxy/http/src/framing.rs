@@ -0,0 +1,218 @@
+//! Rejects HTTP/1 requests with ambiguous message framing.
+//!
+//! When a proxy and the server behind it disagree about where a request's body
+//! ends, a client may smuggle a second request past the proxy. Hyper's parser
+//! tolerates some forms of ambiguous framing--e.g. repeated, identical
+//! `Content-Length` headers, or a `Content-Length` header alongside a
+//! `Transfer-Encoding` header--so this middleware may be configured to fail
+//! such requests with an [`AmbiguousFraming`] error before they are forwarded.
+//!
+//! Alternatively, [`NormalizeTransferEncoding`] canonicalizes the
+//! `Transfer-Encoding` of chunked requests so that every HTTP/1 hop frames them
+//! identically.
+
+use futures::{future, TryFutureExt};
+use http::header::{self, HeaderMap, HeaderValue};
+use linkerd_error::Error;
+use linkerd_stack::layer;
+use std::task::{Context, Poll};
+use thiserror::Error;
+use tracing::debug;
+
+#[derive(Clone, Debug)]
+pub struct RejectAmbiguousFraming<S> {
+    inner: S,
+    enabled: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct NormalizeTransferEncoding<S> {
+    inner: S,
+    mode: Option<TransferEncodingMode>,
+}
+
+/// Determines how conflicting `Transfer-Encoding` values are handled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TransferEncodingMode {
+    /// Conflicting values are stripped: repeated `chunked` codings are
+    /// collapsed and `Content-Length` headers are removed.
+    Lenient,
+
+    /// Requests with conflicting values are rejected.
+    Strict,
+}
+
+#[derive(Debug, Error)]
+#[error("ambiguous HTTP/1 message framing: {0}")]
+pub struct AmbiguousFraming(&'static str);
+
+// === impl RejectAmbiguousFraming ===
+
+impl<S> RejectAmbiguousFraming<S> {
+    /// When `enabled` is false, requests are not validated.
+    pub fn layer(enabled: bool) -> impl layer::Layer<S, Service = Self> + Copy + Clone {
+        layer::mk(move |inner| Self { inner, enabled })
+    }
+}
+
+impl<S, B> tower::Service<http::Request<B>> for RejectAmbiguousFraming<S>
+where
+    S: tower::Service<http::Request<B>>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<
+        future::ErrInto<S::Future, Error>,
+        future::Ready<Result<S::Response, Error>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if self.enabled {
+            if let http::Version::HTTP_10 | http::Version::HTTP_11 = req.version() {
+                if let Err(error) = check_framing(req.headers()) {
+                    debug!(%error, "Rejecting request");
+                    return future::Either::Right(future::err(error.into()));
+                }
+            }
+        }
+
+        future::Either::Left(self.inner.call(req).err_into())
+    }
+}
+
+// === impl NormalizeTransferEncoding ===
+
+impl<S> NormalizeTransferEncoding<S> {
+    /// When `mode` is `None`, requests are not modified.
+    pub fn layer(
+        mode: Option<TransferEncodingMode>,
+    ) -> impl layer::Layer<S, Service = Self> + Copy + Clone {
+        layer::mk(move |inner| Self { inner, mode })
+    }
+}
+
+impl<S, B> tower::Service<http::Request<B>> for NormalizeTransferEncoding<S>
+where
+    S: tower::Service<http::Request<B>>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<
+        future::ErrInto<S::Future, Error>,
+        future::Ready<Result<S::Response, Error>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        if let Some(mode) = self.mode {
+            if let http::Version::HTTP_10 | http::Version::HTTP_11 = req.version() {
+                if let Err(error) = normalize_transfer_encoding(req.headers_mut(), mode) {
+                    debug!(%error, "Rejecting request");
+                    return future::Either::Right(future::err(error.into()));
+                }
+            }
+        }
+
+        future::Either::Left(self.inner.call(req).err_into())
+    }
+}
+
+fn check_framing(headers: &HeaderMap) -> Result<(), AmbiguousFraming> {
+    let content_lengths = headers.get_all(header::CONTENT_LENGTH);
+    let transfer_encodings = headers.get_all(header::TRANSFER_ENCODING);
+
+    let mut cls = content_lengths.iter();
+    if let Some(cl) = cls.next() {
+        if transfer_encodings.iter().next().is_some() {
+            return Err(AmbiguousFraming(
+                "both content-length and transfer-encoding are set",
+            ));
+        }
+        if cls.next().is_some() || cl.as_bytes().contains(&b',') {
+            return Err(AmbiguousFraming("multiple content-length values"));
+        }
+    }
+
+    // Transfer codings may be split across headers, but `chunked` must be
+    // the final coding and may only be applied once.
+    let mut codings = Vec::new();
+    for value in transfer_encodings.iter() {
+        let value = value
+            .to_str()
+            .map_err(|_| AmbiguousFraming("invalid transfer-encoding"))?;
+        codings.extend(value.split(',').map(str::trim).filter(|c| !c.is_empty()));
+    }
+    if let Some((last, rest)) = codings.split_last() {
+        let is_chunked = |c: &&str| c.eq_ignore_ascii_case("chunked");
+        if !is_chunked(last) || rest.iter().any(is_chunked) {
+            return Err(AmbiguousFraming(
+                "transfer-encoding must end with a single chunked coding",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Rewrites a request's `Transfer-Encoding` as a single, lowercased list of
+/// codings that ends with a single `chunked` coding.
+fn normalize_transfer_encoding(
+    headers: &mut HeaderMap,
+    mode: TransferEncodingMode,
+) -> Result<(), AmbiguousFraming> {
+    if !headers.contains_key(header::TRANSFER_ENCODING) {
+        return Ok(());
+    }
+
+    let mut codings = Vec::new();
+    for value in headers.get_all(header::TRANSFER_ENCODING).iter() {
+        let value = value
+            .to_str()
+            .map_err(|_| AmbiguousFraming("invalid transfer-encoding"))?;
+        codings.extend(
+            value
+                .split(',')
+                .map(|c| c.trim().to_ascii_lowercase())
+                .filter(|c| !c.is_empty()),
+        );
+    }
+
+    // The length of a request body is only known when it is chunked last, so
+    // other requests can't be made unambiguous.
+    if codings.last().map(String::as_str) != Some("chunked") {
+        return Err(AmbiguousFraming(
+            "transfer-encoding must end with a chunked coding",
+        ));
+    }
+    if codings.iter().filter(|c| *c == "chunked").count() > 1 {
+        if mode == TransferEncodingMode::Strict {
+            return Err(AmbiguousFraming("chunked coding applied more than once"));
+        }
+        codings.retain(|c| c != "chunked");
+        codings.push("chunked".to_string());
+    }
+    if headers.contains_key(header::CONTENT_LENGTH) {
+        if mode == TransferEncodingMode::Strict {
+            return Err(AmbiguousFraming(
+                "both content-length and transfer-encoding are set",
+            ));
+        }
+        headers.remove(header::CONTENT_LENGTH);
+    }
+
+    let value = HeaderValue::from_str(&codings.join(", "))
+        .map_err(|_| AmbiguousFraming("invalid transfer-encoding"))?;
+    headers.insert(header::TRANSFER_ENCODING, value);
+    Ok(())
+}
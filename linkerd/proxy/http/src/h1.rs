@@ -1,18 +1,24 @@
 use crate::{
     glue::HyperConnect,
     upgrade::{Http11Upgrade, HttpConnect},
+    ServerMetrics,
 };
+use bytes::BytesMut;
 use futures::prelude::*;
 use http::{
     header::{CONNECTION, CONTENT_LENGTH, HOST, TRANSFER_ENCODING, UPGRADE},
     uri::{Authority, Parts, Scheme, Uri},
 };
+use hyper::body::HttpBody;
 use linkerd_error::{Error, Result};
 use linkerd_http_box::BoxBody;
 use linkerd_stack::MakeConnection;
 use std::{future::Future, mem, pin::Pin, time::Duration};
 use tracing::{debug, trace};
 
+#[cfg(test)]
+mod tests;
+
 #[derive(Copy, Clone, Debug)]
 pub struct WasAbsoluteForm(pub(crate) ());
 
@@ -20,6 +26,110 @@ pub struct WasAbsoluteForm(pub(crate) ());
 pub struct PoolSettings {
     pub max_idle: usize,
     pub idle_timeout: Duration,
+    /// The maximum size of each connection's read and write buffers. When
+    /// unset, hyper's default is used.
+    pub max_buf_size: Option<usize>,
+
+    /// Determines how responses whose bodies are delimited by the server
+    /// closing the connection are framed.
+    pub close_delimited: CloseDelimitedResponses,
+}
+
+/// Determines how HTTP/1 responses without a `Content-Length` that are not
+/// chunked--i.e. whose bodies end when the server closes the connection--are
+/// framed to clients.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CloseDelimitedResponses {
+    /// Bodies are streamed to clients, which may continue to reuse their
+    /// connections.
+    #[default]
+    Stream,
+
+    /// Bodies are streamed to clients, and responses to HTTP/1 clients include
+    /// a `Connection: close` header so that the client's connection is closed
+    /// once the body completes.
+    Close,
+
+    /// Bodies of up to `max_bytes` are buffered so that the response is sent
+    /// with a `Content-Length`. Larger bodies are handled as with `Close`.
+    Buffer { max_bytes: usize },
+}
+
+/// Marks responses whose bodies are delimited by the connection closing, so
+/// that HTTP/1 servers close the client's connection once the body completes.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct CloseDelimited(());
+
+/// Configures HTTP/1 server connections.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ServerSettings {
+    /// The maximum size of each connection's read and write buffers. When
+    /// unset, hyper's default is used.
+    pub max_buf_size: Option<usize>,
+
+    /// Whether, once the server begins draining, responses on keep-alive
+    /// connections include a `Connection: close` header so that clients stop
+    /// reusing the connection before it is closed.
+    pub close_on_drain: bool,
+
+    /// Determines how pipelined requests are handled.
+    pub pipelining: Pipelining,
+
+    /// Determines how connections on which the client sends bytes that cannot
+    /// be parsed as HTTP/1 are closed.
+    pub parse_errors: ParseErrors,
+}
+
+/// Determines how HTTP/1 server connections handle pipelined requests--i.e.
+/// requests that the client sends before it has received the response to its
+/// prior request.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Pipelining {
+    /// Pipelined requests are processed one at a time, and their responses are
+    /// sent in the order in which the requests were received.
+    #[default]
+    Serialize,
+
+    /// Pipelined requests are not processed. They are failed with a 503
+    /// response with a `Connection: close` header, and the connection is
+    /// closed so that the client retries its remaining requests on a new
+    /// connection.
+    Reject,
+}
+
+/// Determines how HTTP/1 server connections are closed when the client sends
+/// bytes that cannot be parsed as HTTP/1--e.g. when a client that was
+/// detected as speaking HTTP later switches to another protocol.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ParseErrors {
+    /// The connection fails with the parse error.
+    #[default]
+    Fail,
+
+    /// The connection is closed cleanly, without an error.
+    Abort,
+}
+
+/// Counts the connection error if it was caused by unparseable bytes,
+/// returning true if so.
+pub(crate) fn record_parse_error(error: &hyper::Error, metrics: &ServerMetrics) -> bool {
+    if !error.is_parse() {
+        return false;
+    }
+    metrics.incr_http1_parse_errors();
+    true
+}
+
+// === impl PoolSettings ===
+
+impl PoolSettings {
+    fn builder(&self) -> hyper::client::Builder {
+        let mut builder = hyper::Client::builder();
+        if let Some(sz) = self.max_buf_size {
+            builder.http1_max_buf_size(sz);
+        }
+        builder
+    }
 }
 
 /// Communicates with HTTP/1.x servers.
@@ -77,6 +187,8 @@ where
         // Marked by `upgrade`.
         let upgrade = req.extensions_mut().remove::<Http11Upgrade>();
         let is_http_connect = req.method() == http::Method::CONNECT;
+        let is_head = req.method() == http::Method::HEAD;
+        let close_delimited = self.pool.close_delimited;
 
         // Configured by `normalize_uri` or `orig_proto::Downgrade`.
         let use_absolute_form = req.extensions_mut().remove::<WasAbsoluteForm>().is_some();
@@ -93,7 +205,8 @@ where
             // ish, so we just build a one-off client for the connection.
             // There's no real reason to hold the client for re-use.
             debug!(use_absolute_form, is_missing_host, "Using one-off client");
-            hyper::Client::builder()
+            self.pool
+                .builder()
                 .pool_max_idle_per_host(0)
                 .set_host(use_absolute_form)
                 .build(HyperConnect::new(
@@ -118,7 +231,8 @@ where
             if client.is_none() {
                 debug!(use_absolute_form, "Caching new client");
                 *client = Some(
-                    hyper::Client::builder()
+                    self.pool
+                        .builder()
                         .pool_max_idle_per_host(self.pool.max_idle)
                         .pool_idle_timeout(self.pool.idle_timeout)
                         .set_host(use_absolute_form)
@@ -133,7 +247,7 @@ where
             client.as_ref().unwrap().request(req)
         };
 
-        Box::pin(rsp_fut.err_into().map_ok(move |mut rsp| {
+        let rsp_fut = rsp_fut.err_into().map_ok(move |mut rsp| {
             if is_http_connect {
                 debug_assert!(
                     upgrade.is_some(),
@@ -157,15 +271,62 @@ where
                 if let Some(upgrade) = upgrade {
                     upgrade.insert_half(hyper::upgrade::on(&mut rsp));
                 }
-            } else {
-                strip_connection_headers(rsp.headers_mut());
+                return (rsp, false);
             }
 
-            rsp.map(BoxBody::new)
-        }))
+            strip_connection_headers(rsp.headers_mut());
+            let is_close_delimited = !is_http_connect && is_close_delimited(&rsp, is_head);
+            (rsp, is_close_delimited)
+        });
+
+        Box::pin(
+            rsp_fut.and_then(move |(rsp, is_close_delimited)| async move {
+                if !is_close_delimited {
+                    return Ok(rsp.map(BoxBody::new));
+                }
+                frame_close_delimited(rsp, close_delimited).await
+            }),
+        )
     }
 }
 
+/// Frames a response whose body is delimited by the server closing the
+/// connection.
+async fn frame_close_delimited(
+    mut rsp: http::Response<hyper::Body>,
+    mode: CloseDelimitedResponses,
+) -> Result<http::Response<BoxBody>> {
+    let max_bytes = match mode {
+        CloseDelimitedResponses::Stream => return Ok(rsp.map(BoxBody::new)),
+        CloseDelimitedResponses::Close => {
+            debug!("Closing client connection after close-delimited response");
+            rsp.extensions_mut().insert(CloseDelimited(()));
+            return Ok(rsp.map(BoxBody::new));
+        }
+        CloseDelimitedResponses::Buffer { max_bytes } => max_bytes,
+    };
+
+    let (mut head, mut body) = rsp.into_parts();
+    let mut buf = BytesMut::new();
+    while let Some(data) = body.data().await {
+        buf.extend_from_slice(&data?);
+        if buf.len() > max_bytes {
+            // The remainder of the body is streamed after the data that has
+            // already been read.
+            debug!(max_bytes, "Close-delimited response is too large to buffer");
+            head.extensions.insert(CloseDelimited(()));
+            let read = stream::once(future::ok::<_, hyper::Error>(buf.freeze()));
+            let body = hyper::Body::wrap_stream(read.chain(body));
+            return Ok(http::Response::from_parts(head, BoxBody::new(body)));
+        }
+    }
+
+    trace!(bytes = buf.len(), "Buffered close-delimited response");
+    head.headers.insert(CONTENT_LENGTH, buf.len().into());
+    let body = http_body::Full::new(buf.freeze());
+    Ok(http::Response::from_parts(head, BoxBody::new(body)))
+}
+
 // === HTTP/1 utils ===
 
 /// Returns an Authority from a request's Host header.
@@ -193,7 +354,9 @@ pub(crate) fn set_authority(uri: &mut http::Uri, auth: Authority) {
     *uri = new;
 }
 
-pub(crate) fn strip_connection_headers(headers: &mut http::HeaderMap) {
+/// Removes connection-level headers, which must not be forwarded to another
+/// connection.
+pub fn strip_connection_headers(headers: &mut http::HeaderMap) {
     if let Some(val) = headers.remove(CONNECTION) {
         if let Ok(conn_header) = val.to_str() {
             // A `Connection` header may have a comma-separated list of
@@ -215,6 +378,23 @@ pub(crate) fn strip_connection_headers(headers: &mut http::HeaderMap) {
     headers.remove("keep-alive");
 }
 
+/// Checks whether a response's body is delimited by the server closing the
+/// connection.
+fn is_close_delimited<B>(rsp: &http::Response<B>, is_head: bool) -> bool {
+    // Responses to HEAD requests and 1xx, 204, and 304 responses have no body.
+    let status = rsp.status();
+    if is_head
+        || status.is_informational()
+        || status == http::StatusCode::NO_CONTENT
+        || status == http::StatusCode::NOT_MODIFIED
+    {
+        return false;
+    }
+
+    let headers = rsp.headers();
+    !headers.contains_key(CONTENT_LENGTH) && !headers.contains_key(TRANSFER_ENCODING)
+}
+
 /// Checks requests to determine if they want to perform an HTTP upgrade.
 pub(crate) fn wants_upgrade<B>(req: &http::Request<B>) -> bool {
     // HTTP upgrades were added in 1.1, not 1.0.
@@ -0,0 +1,181 @@
+use super::*;
+use crate::{glue::UpgradeBody, server::NewServeHttp, ClientHandle};
+use bytes::Bytes;
+use linkerd_io::{self as io, AsyncReadExt, AsyncWriteExt};
+use linkerd_stack::{layer::Layer, service_fn, NewService};
+use tokio::task::JoinHandle;
+use tower::Service;
+
+const BODY: &[u8] = b"hello";
+
+/// Serves a response whose body is delimited by closing the connection.
+async fn serve_close_delimited(mut io: io::DuplexStream) {
+    let mut req = Vec::new();
+    while !req.ends_with(b"\r\n\r\n") {
+        let mut buf = [0u8; 1024];
+        let n = io.read(&mut buf).await.expect("read must succeed");
+        if n == 0 {
+            return;
+        }
+        req.extend_from_slice(&buf[..n]);
+    }
+    io.write_all(b"HTTP/1.1 200 OK\r\n\r\n")
+        .await
+        .expect("write must succeed");
+    io.write_all(BODY).await.expect("write must succeed");
+}
+
+/// Proxies a request from an HTTP/1 client to a server that responds with a
+/// close-delimited body, returning the response the client receives, its
+/// body, and the client's connection task. The returned drain signal must be
+/// held so that the proxy's server connection is not shut down.
+async fn proxy(
+    close_delimited: CloseDelimitedResponses,
+) -> (
+    http::Response<hyper::Body>,
+    Bytes,
+    JoinHandle<hyper::Result<()>>,
+    drain::Signal,
+) {
+    let connect = service_fn(|_: (crate::Version, ())| {
+        let (client_io, server_io) = io::duplex(4096);
+        tokio::spawn(serve_close_delimited(server_io));
+        future::ok::<_, Error>((client_io, ()))
+    });
+    let client = Client::<_, _, BoxBody>::new(
+        connect,
+        (),
+        PoolSettings {
+            max_idle: 1,
+            idle_timeout: Duration::from_secs(1),
+            max_buf_size: None,
+            close_delimited,
+        },
+    );
+    let inner = move |_: ClientHandle| {
+        let client = client.clone();
+        service_fn(move |_: http::Request<UpgradeBody>| {
+            let mut client = client.clone();
+            let req = http::Request::builder()
+                .uri("http://example.com/")
+                .header(HOST, "example.com")
+                .body(BoxBody::default())
+                .expect("request must be valid");
+            client.request(req)
+        })
+    };
+
+    let (drain_tx, drain) = drain::channel();
+    let mut serve = NewServeHttp::layer(
+        Default::default(),
+        Default::default(),
+        Default::default(),
+        drain,
+    )
+    .layer(move |_: crate::Version| inner.clone())
+    .new_service(crate::Version::Http1);
+    let (client_io, server_io) = io::duplex(4096);
+    tokio::spawn(serve.call(server_io));
+    let (mut client, conn) = hyper::client::conn::Builder::new()
+        .handshake::<_, hyper::Body>(client_io)
+        .await
+        .expect("client must connect");
+    let conn = tokio::spawn(conn);
+
+    future::poll_fn(|cx| client.poll_ready(cx))
+        .await
+        .expect("client must be ready");
+    let rsp = client
+        .send_request(
+            http::Request::builder()
+                .uri("/")
+                .header(HOST, "example.com")
+                .body(hyper::Body::empty())
+                .expect("request must be valid"),
+        )
+        .await
+        .expect("request must succeed");
+    let (parts, body) = rsp.into_parts();
+    let body = hyper::body::to_bytes(body)
+        .await
+        .expect("body must be read");
+    (
+        http::Response::from_parts(parts, hyper::Body::empty()),
+        body,
+        conn,
+        drain_tx,
+    )
+}
+
+/// Tests that close-delimited responses are streamed to clients by default,
+/// so that the client's connection may be reused.
+#[tokio::test(flavor = "current_thread")]
+async fn close_delimited_stream() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (rsp, body, _conn, _drain) = proxy(CloseDelimitedResponses::Stream).await;
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    assert_eq!(body, BODY);
+    assert_eq!(
+        rsp.headers().get(TRANSFER_ENCODING),
+        Some(&http::HeaderValue::from_static("chunked"))
+    );
+    assert!(rsp.headers().get(CONTENT_LENGTH).is_none());
+    assert!(rsp.headers().get(CONNECTION).is_none());
+}
+
+/// Tests that close-delimited responses are streamed with `Connection: close`
+/// and that the client's connection is closed once the body completes.
+#[tokio::test(flavor = "current_thread")]
+async fn close_delimited_close() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (rsp, body, conn, _drain) = proxy(CloseDelimitedResponses::Close).await;
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    assert_eq!(body, BODY);
+    assert_eq!(
+        rsp.headers().get(CONNECTION),
+        Some(&http::HeaderValue::from_static("close"))
+    );
+    assert!(rsp.headers().get(CONTENT_LENGTH).is_none());
+    conn.await
+        .unwrap()
+        .expect("client connection must close cleanly");
+}
+
+/// Tests that close-delimited responses are buffered so that they are sent
+/// with a `Content-Length`.
+#[tokio::test(flavor = "current_thread")]
+async fn close_delimited_buffer() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (rsp, body, _conn, _drain) =
+        proxy(CloseDelimitedResponses::Buffer { max_bytes: 1024 }).await;
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    assert_eq!(body, BODY);
+    assert_eq!(
+        rsp.headers().get(CONTENT_LENGTH),
+        Some(&http::HeaderValue::from(BODY.len()))
+    );
+    assert!(rsp.headers().get(TRANSFER_ENCODING).is_none());
+    assert!(rsp.headers().get(CONNECTION).is_none());
+}
+
+/// Tests that close-delimited responses that are too large to buffer are
+/// handled as in the `Close` mode.
+#[tokio::test(flavor = "current_thread")]
+async fn close_delimited_buffer_too_large() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (rsp, body, conn, _drain) = proxy(CloseDelimitedResponses::Buffer { max_bytes: 2 }).await;
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    assert_eq!(body, BODY);
+    assert_eq!(
+        rsp.headers().get(CONNECTION),
+        Some(&http::HeaderValue::from_static("close"))
+    );
+    assert!(rsp.headers().get(CONTENT_LENGTH).is_none());
+    conn.await
+        .unwrap()
+        .expect("client connection must close cleanly");
+}
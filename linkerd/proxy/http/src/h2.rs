@@ -1,4 +1,4 @@
-use crate::trace;
+use crate::{trace, ServerMetrics};
 use futures::prelude::*;
 pub use h2::{Error as H2Error, Reason};
 use hyper::{
@@ -17,11 +17,35 @@ use std::{
 use tracing::instrument::Instrument;
 use tracing::{debug, debug_span, trace_span};
 
+mod continuation;
+mod pool;
+mod window_update;
+
+pub use self::{
+    continuation::HeaderBlockTooLarge,
+    pool::{Pool, PoolSettings},
+    window_update::TooManyWindowUpdates,
+};
+pub(crate) use self::{continuation::LimitHeaderBlocks, window_update::LimitWindowUpdates};
+
 #[derive(Copy, Clone, Debug, Default)]
 pub struct Settings {
     pub initial_stream_window_size: Option<u32>,
     pub initial_connection_window_size: Option<u32>,
     pub keepalive_timeout: Option<Duration>,
+    /// The largest frame payload, in bytes, that peers may send. Connections
+    /// on which a larger frame is received are closed with a
+    /// `FRAME_SIZE_ERROR`. When unset, the protocol default of 16KB is used.
+    pub max_frame_size: Option<u32>,
+    /// The largest header block, in bytes, that clients may send across a
+    /// HEADERS frame and its CONTINUATION frames. Server connections on which
+    /// a larger header block is received are closed. When unset, header blocks
+    /// are limited only by the HTTP/2 implementation.
+    pub max_header_block_size: Option<u32>,
+    /// The most WINDOW_UPDATE frames that clients may send per second. Server
+    /// connections on which more are received are closed. When unset,
+    /// WINDOW_UPDATE frames are not limited.
+    pub max_window_updates_per_second: Option<u32>,
 }
 
 #[derive(Debug)]
@@ -36,6 +60,18 @@ pub struct Connection<B> {
     tx: SendRequest<B>,
 }
 
+/// Counts the connection error if it was caused by an oversized frame.
+pub(crate) fn record_connection_error(error: &hyper::Error, metrics: &ServerMetrics) {
+    let is_frame_size_error = std::error::Error::source(error)
+        .and_then(|e| e.downcast_ref::<H2Error>())
+        .and_then(H2Error::reason)
+        .map_or(false, |reason| reason == Reason::FRAME_SIZE_ERROR);
+    if is_frame_size_error {
+        debug!("Closed connection after receiving an oversized frame");
+        metrics.incr_h2_oversized_frame_closes();
+    }
+}
+
 // === impl Connect ===
 
 impl<C, B> Connect<C, B> {
@@ -84,6 +120,9 @@ where
             initial_connection_window_size,
             initial_stream_window_size,
             keepalive_timeout,
+            max_frame_size,
+            // Header block and WINDOW_UPDATE limits only apply to servers.
+            ..
         } = self.h2_settings;
 
         let connect = self
@@ -99,6 +138,7 @@ where
                     .http2_only(true)
                     .http2_initial_stream_window_size(initial_stream_window_size)
                     .http2_initial_connection_window_size(initial_connection_window_size)
+                    .http2_max_frame_size(max_frame_size)
                     .executor(trace::Executor::new());
 
                 // Configure HTTP/2 PING frames
@@ -0,0 +1,201 @@
+//! Bounds the size of the header blocks that HTTP/2 clients send.
+//!
+//! A header block begins with a HEADERS (or PUSH_PROMISE) frame and may be
+//! continued by any number of CONTINUATION frames until a frame sets the
+//! END_HEADERS flag. Since the frames' payloads are buffered until the block is
+//! complete, a client may flood the server with CONTINUATION frames. The frames
+//! read from a server connection are inspected so that the connection fails
+//! once a header block's payloads exceed the configured limit.
+
+use crate::ServerMetrics;
+use linkerd_io as io;
+use pin_project::pin_project;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tracing::debug;
+
+#[cfg(test)]
+mod tests;
+
+/// The client connection preface, which precedes the first frame.
+const PREFACE_LEN: usize = 24;
+
+const FRAME_HEADER_LEN: usize = 9;
+
+const HEADERS: u8 = 0x1;
+const PUSH_PROMISE: u8 = 0x5;
+const CONTINUATION: u8 = 0x9;
+const END_HEADERS: u8 = 0x4;
+
+/// Fails reads once a header block exceeds `max_bytes`.
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct LimitHeaderBlocks<I> {
+    #[pin]
+    io: I,
+    max_bytes: Option<u32>,
+    frames: Frames,
+    metrics: ServerMetrics,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("HTTP/2 header block exceeds {0} bytes")]
+pub struct HeaderBlockTooLarge(u32);
+
+/// Tracks the frames read from a connection.
+#[derive(Debug)]
+struct Frames {
+    /// The number of preface bytes that have not yet been read.
+    preface: usize,
+    /// The current frame's header, as it's read.
+    header: [u8; FRAME_HEADER_LEN],
+    header_len: usize,
+    /// The number of bytes of the current frame's payload that have not yet
+    /// been read.
+    payload: usize,
+    /// The size of the header block that is in progress, if any.
+    block: Option<u64>,
+}
+
+// === impl LimitHeaderBlocks ===
+
+impl<I> LimitHeaderBlocks<I> {
+    /// When `max_bytes` is `None`, header blocks are not limited.
+    pub(crate) fn new(io: I, max_bytes: Option<u32>, metrics: ServerMetrics) -> Self {
+        Self {
+            io,
+            max_bytes,
+            frames: Frames::new(),
+            metrics,
+        }
+    }
+}
+
+impl<I: io::AsyncRead> io::AsyncRead for LimitHeaderBlocks<I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let max_bytes = match *this.max_bytes {
+            Some(max_bytes) => max_bytes,
+            None => return this.io.poll_read(cx, buf),
+        };
+
+        let filled = buf.filled().len();
+        futures::ready!(this.io.poll_read(cx, buf))?;
+        if let Err(error) = this.frames.read(&buf.filled()[filled..], max_bytes) {
+            debug!(%error, "Closing connection");
+            this.metrics.incr_h2_continuation_flood_closes();
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, error)));
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<I: io::AsyncWrite> io::AsyncWrite for LimitHeaderBlocks<I> {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().io.poll_write(cx, buf)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().io.poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().io.poll_shutdown(cx)
+    }
+
+    #[inline]
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.project().io.poll_write_vectored(cx, bufs)
+    }
+
+    #[inline]
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+}
+
+// === impl Frames ===
+
+impl Frames {
+    fn new() -> Self {
+        Self {
+            preface: PREFACE_LEN,
+            header: [0; FRAME_HEADER_LEN],
+            header_len: 0,
+            payload: 0,
+            block: None,
+        }
+    }
+
+    /// Reads bytes from the connection, failing if a header block exceeds
+    /// `max_bytes`.
+    fn read(&mut self, mut bytes: &[u8], max_bytes: u32) -> Result<(), HeaderBlockTooLarge> {
+        while !bytes.is_empty() {
+            if self.preface > 0 {
+                let n = self.preface.min(bytes.len());
+                self.preface -= n;
+                bytes = &bytes[n..];
+                continue;
+            }
+
+            if self.payload > 0 {
+                let n = self.payload.min(bytes.len());
+                self.payload -= n;
+                bytes = &bytes[n..];
+                continue;
+            }
+
+            let n = (FRAME_HEADER_LEN - self.header_len).min(bytes.len());
+            self.header[self.header_len..self.header_len + n].copy_from_slice(&bytes[..n]);
+            self.header_len += n;
+            bytes = &bytes[n..];
+            if self.header_len == FRAME_HEADER_LEN {
+                self.header_len = 0;
+                self.frame(max_bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Accounts for a frame once its header has been read.
+    fn frame(&mut self, max_bytes: u32) -> Result<(), HeaderBlockTooLarge> {
+        let [l0, l1, l2, kind, flags, ..] = self.header;
+        let len = u32::from_be_bytes([0, l0, l1, l2]);
+        self.payload = len as usize;
+
+        self.block = match kind {
+            HEADERS | PUSH_PROMISE => Some(u64::from(len)),
+            // CONTINUATION frames outside of a header block are a protocol
+            // error that is handled by the HTTP/2 implementation.
+            CONTINUATION => self.block.map(|block| block + u64::from(len)),
+            _ => return Ok(()),
+        };
+        if self
+            .block
+            .map_or(false, |block| block > u64::from(max_bytes))
+        {
+            return Err(HeaderBlockTooLarge(max_bytes));
+        }
+        if flags & END_HEADERS == END_HEADERS {
+            self.block = None;
+        }
+        Ok(())
+    }
+}
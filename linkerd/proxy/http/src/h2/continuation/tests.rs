@@ -0,0 +1,44 @@
+use super::*;
+
+const MAX_BYTES: u32 = 1024;
+
+fn frame(kind: u8, flags: u8, len: u32) -> Vec<u8> {
+    let mut frame = len.to_be_bytes()[1..].to_vec();
+    frame.extend_from_slice(&[kind, flags, 0, 0, 0, 1]);
+    frame.resize(FRAME_HEADER_LEN + len as usize, 0);
+    frame
+}
+
+#[test]
+fn allows_header_blocks_within_limit() {
+    let mut bytes = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+    bytes.extend(frame(HEADERS, 0, 512));
+    bytes.extend(frame(CONTINUATION, END_HEADERS, 512));
+    // Each header block is limited independently, and other frames are not
+    // limited.
+    bytes.extend(frame(HEADERS, END_HEADERS, MAX_BYTES));
+    bytes.extend(frame(0x0, 0, MAX_BYTES * 4));
+
+    // Frames may be split across reads.
+    let mut frames = Frames::new();
+    for chunk in bytes.chunks(7) {
+        frames
+            .read(chunk, MAX_BYTES)
+            .expect("must not exceed limit");
+    }
+}
+
+#[test]
+fn fails_header_blocks_over_limit() {
+    let mut bytes = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+    bytes.extend(frame(HEADERS, 0, 512));
+    bytes.extend(frame(CONTINUATION, 0, 512));
+    let mut frames = Frames::new();
+    frames
+        .read(&bytes, MAX_BYTES)
+        .expect("must not exceed limit");
+
+    frames
+        .read(&frame(CONTINUATION, END_HEADERS, 1), MAX_BYTES)
+        .expect_err("must exceed limit");
+}
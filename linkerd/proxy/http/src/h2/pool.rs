@@ -0,0 +1,311 @@
+//! A pool of HTTP/2 connections to a single endpoint.
+//!
+//! Some HTTP/2 servers limit the number of streams that may be active on a
+//! connection, such that multiplexing all requests over a single connection
+//! causes requests to queue. A `Pool` opens additional connections to the
+//! endpoint--up to a configured maximum--when every connection in the pool has
+//! at least `max_concurrent_streams` streams in flight. Once the maximum number
+//! of connections has been reached, requests are multiplexed over the
+//! least-loaded connection. When an additional connection cannot be
+//! established, the pool backs off before trying to open another.
+
+use super::{Connect, Connection};
+use bytes::Bytes;
+use futures::prelude::*;
+use hyper::body::HttpBody;
+use linkerd_error::{Error, Result};
+use linkerd_exp_backoff::{ExponentialBackoff, ExponentialBackoffStream};
+use linkerd_http_box::BoxBody;
+use linkerd_stack::{MakeConnection, Service};
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+use tracing::{debug, trace};
+
+#[cfg(test)]
+mod tests;
+
+/// The default number of in-flight streams at which a pooled connection is
+/// considered saturated.
+const DEFAULT_MAX_CONCURRENT_STREAMS: usize = 100;
+
+/// Bounds the time between attempts to add a connection to the pool after an
+/// attempt fails.
+const CONNECT_BACKOFF: ExponentialBackoff =
+    ExponentialBackoff::new_unchecked(Duration::from_millis(100), Duration::from_secs(10), 0.1);
+
+#[derive(Copy, Clone, Debug)]
+pub struct PoolSettings {
+    /// The maximum number of connections a client may open to an endpoint.
+    /// When unset, all requests to an endpoint are multiplexed over a single
+    /// connection.
+    pub max_connections: Option<usize>,
+
+    /// The number of in-flight streams at which a connection is considered
+    /// saturated, so that another connection is opened if the pool allows it.
+    pub max_concurrent_streams: usize,
+}
+
+pub struct Pool<C, T, B> {
+    connect: Connect<C, B>,
+    target: T,
+    max_connections: usize,
+    max_concurrent_streams: usize,
+    connections: Vec<Pooled<B>>,
+    /// A task establishing a new connection for the pool.
+    connecting: Option<JoinHandle<Result<Connection<B>>>>,
+    /// Set when an attempt to add a connection fails, until a connection is
+    /// added.
+    backoff: Option<ExponentialBackoffStream>,
+    /// Whether the pool is waiting for its backoff to elapse before
+    /// connecting.
+    backing_off: bool,
+    ready: Option<usize>,
+}
+
+struct Pooled<B> {
+    conn: Connection<B>,
+    /// Cloned for each stream on the connection and held until the stream's
+    /// response body is dropped.
+    streams: Arc<()>,
+}
+
+/// A response body that holds its connection's stream count.
+#[derive(Debug)]
+struct StreamBody {
+    inner: hyper::Body,
+    _stream: Arc<()>,
+}
+
+type RspFuture = Pin<Box<dyn Future<Output = Result<http::Response<BoxBody>>> + Send + 'static>>;
+
+// === impl PoolSettings ===
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        Self {
+            max_connections: None,
+            max_concurrent_streams: DEFAULT_MAX_CONCURRENT_STREAMS,
+        }
+    }
+}
+
+// === impl Pool ===
+
+impl<C, T, B> Pool<C, T, B> {
+    /// Creates a pool from an established connection, which may open further
+    /// connections with `connect`.
+    pub fn new(
+        connect: Connect<C, B>,
+        target: T,
+        settings: PoolSettings,
+        conn: Connection<B>,
+    ) -> Self {
+        Self {
+            connect,
+            target,
+            max_connections: settings.max_connections.unwrap_or(1),
+            max_concurrent_streams: settings.max_concurrent_streams,
+            connections: vec![Pooled::new(conn)],
+            connecting: None,
+            backoff: None,
+            backing_off: false,
+            ready: None,
+        }
+    }
+}
+
+impl<C, T, B> Pool<C, T, B>
+where
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Error> + Send + Sync,
+{
+    /// Returns the index of the ready connection with the fewest streams in
+    /// flight, removing any connections that have failed.
+    fn poll_least_loaded(&mut self, cx: &mut Context<'_>) -> Option<usize> {
+        let mut ready = None::<usize>;
+        let mut i = 0;
+        while i < self.connections.len() {
+            match self.connections[i].conn.poll_ready(cx) {
+                Poll::Ready(Ok(())) => {
+                    let streams = self.connections[i].streams();
+                    if ready.map_or(true, |r| streams < self.connections[r].streams()) {
+                        ready = Some(i);
+                    }
+                    i += 1;
+                }
+                Poll::Ready(Err(error)) => {
+                    debug!(%error, "Removing failed connection");
+                    self.connections.remove(i);
+                }
+                Poll::Pending => {
+                    i += 1;
+                }
+            }
+        }
+        ready
+    }
+
+    /// Returns true while the pool is waiting to retry a failed connection.
+    fn poll_backoff(&mut self, cx: &mut Context<'_>) -> bool {
+        if !self.backing_off {
+            return false;
+        }
+        let backoff = self.backoff.get_or_insert_with(|| CONNECT_BACKOFF.stream());
+        if backoff.poll_next_unpin(cx).is_pending() {
+            return true;
+        }
+        self.backing_off = false;
+        false
+    }
+}
+
+impl<C, T, B> Service<http::Request<B>> for Pool<C, T, B>
+where
+    T: Clone,
+    C: MakeConnection<(crate::Version, T)>,
+    C::Connection: Send + Unpin + 'static,
+    C::Metadata: Send,
+    C::Future: Send + 'static,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Error> + Send + Sync,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Error;
+    type Future = RspFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        loop {
+            // Drive any pending connection so that it's added to the pool once
+            // it's established.
+            if let Some(connecting) = self.connecting.as_mut() {
+                if let Poll::Ready(res) = Pin::new(connecting).poll(cx) {
+                    self.connecting = None;
+                    match res.map_err(Error::from).and_then(|res| res) {
+                        Ok(conn) => {
+                            self.connections.push(Pooled::new(conn));
+                            self.backoff = None;
+                            debug!(connections = self.connections.len(), "Connected");
+                        }
+                        Err(error) => {
+                            if self.connections.is_empty() {
+                                return Poll::Ready(Err(error));
+                            }
+                            debug!(%error, "Failed to add a connection to the pool");
+                            self.backing_off = true;
+                        }
+                    }
+                }
+            }
+
+            // Connections that are not ready, e.g. while their handshakes
+            // complete, are not saturated.
+            let ready = self.poll_least_loaded(cx);
+            let saturated = self
+                .connections
+                .iter()
+                .all(|c| c.streams() >= self.max_concurrent_streams);
+            if saturated && self.connections.len() < self.max_connections && !self.poll_backoff(cx)
+            {
+                // Wait for the pending connection rather than adding streams
+                // to a saturated one.
+                if self.connecting.is_some() {
+                    return Poll::Pending;
+                }
+                if self.connect.poll_ready(cx)?.is_ready() {
+                    trace!(connections = self.connections.len(), "Connecting");
+                    let connect = self.connect.call(self.target.clone());
+                    self.connecting = Some(tokio::spawn(connect));
+                    continue;
+                }
+            }
+
+            return match ready {
+                Some(i) => {
+                    self.ready = Some(i);
+                    Poll::Ready(Ok(()))
+                }
+                None => Poll::Pending,
+            };
+        }
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let i = self
+            .ready
+            .take()
+            .expect("poll_ready must be called before call");
+        let Pooled { conn, streams } = &mut self.connections[i];
+        let stream = streams.clone();
+        Box::pin(conn.call(req).err_into::<Error>().map_ok(move |rsp| {
+            rsp.map(move |inner| {
+                BoxBody::new(StreamBody {
+                    inner,
+                    _stream: stream,
+                })
+            })
+        }))
+    }
+}
+
+impl<C, T, B> Drop for Pool<C, T, B> {
+    fn drop(&mut self) {
+        if let Some(connecting) = self.connecting.take() {
+            connecting.abort();
+        }
+    }
+}
+
+// === impl Pooled ===
+
+impl<B> Pooled<B> {
+    fn new(conn: Connection<B>) -> Self {
+        Self {
+            conn,
+            streams: Arc::new(()),
+        }
+    }
+
+    fn streams(&self) -> usize {
+        Arc::strong_count(&self.streams) - 1
+    }
+}
+
+// === impl StreamBody ===
+
+impl HttpBody for StreamBody {
+    type Data = Bytes;
+    type Error = hyper::Error;
+
+    #[inline]
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        Pin::new(&mut self.inner).poll_data(cx)
+    }
+
+    #[inline]
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        Pin::new(&mut self.inner).poll_trailers(cx)
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        HttpBody::size_hint(&self.inner)
+    }
+}
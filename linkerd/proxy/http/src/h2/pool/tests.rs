@@ -0,0 +1,203 @@
+use super::*;
+use crate::h2::Settings;
+use linkerd_io as io;
+use linkerd_stack::{service_fn, ServiceExt};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::{sync::Semaphore, time};
+
+/// Builds a connector to an HTTP/2 server that holds each request until
+/// `release` has a permit available.
+fn connect(
+    connections: Arc<AtomicUsize>,
+    received: Arc<AtomicUsize>,
+    release: Arc<Semaphore>,
+) -> impl MakeConnection<
+    (crate::Version, ()),
+    Connection = io::DuplexStream,
+    Metadata = (),
+    Error = io::Error,
+    Future = impl Future<Output = io::Result<(io::DuplexStream, ())>> + Send + 'static,
+> + Clone {
+    service_fn(move |_: (crate::Version, ())| {
+        connections.fetch_add(1, Ordering::SeqCst);
+        let (client_io, server_io) = io::duplex(64 * 1024);
+        let received = received.clone();
+        let release = release.clone();
+        let serve = hyper::server::conn::Http::new()
+            .http2_only(true)
+            .serve_connection(
+                server_io,
+                hyper::service::service_fn(move |_: http::Request<hyper::Body>| {
+                    received.fetch_add(1, Ordering::SeqCst);
+                    let release = release.clone();
+                    async move {
+                        release.acquire().await.unwrap().forget();
+                        Ok::<_, Error>(http::Response::new(hyper::Body::empty()))
+                    }
+                }),
+            );
+        tokio::spawn(serve);
+        future::ok::<_, io::Error>((client_io, ()))
+    })
+}
+
+fn req() -> http::Request<BoxBody> {
+    http::Request::builder()
+        .version(http::Version::HTTP_2)
+        .uri("http://example.com/")
+        .body(BoxBody::default())
+        .unwrap()
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn limits_connections_per_endpoint() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let connections = Arc::new(AtomicUsize::new(0));
+    let received = Arc::new(AtomicUsize::new(0));
+    let release = Arc::new(Semaphore::new(0));
+    let connect = Connect::new(
+        connect(connections.clone(), received.clone(), release.clone()),
+        Settings::default(),
+    );
+    let conn = connect.clone().oneshot(()).await.expect("must connect");
+    let mut pool = Pool::new(
+        connect,
+        (),
+        PoolSettings {
+            max_connections: Some(2),
+            max_concurrent_streams: 2,
+        },
+        conn,
+    );
+
+    // Send more concurrent requests than the pool's connections can carry
+    // without exceeding their stream limits.
+    let mut rsps = Vec::new();
+    for _ in 0..6 {
+        future::poll_fn(|cx| pool.poll_ready(cx))
+            .await
+            .expect("pool must be ready");
+        rsps.push(tokio::spawn(pool.call(req())));
+    }
+    while received.load(Ordering::SeqCst) < 6 {
+        tokio::task::yield_now().await;
+    }
+    assert_eq!(
+        connections.load(Ordering::SeqCst),
+        2,
+        "the pool must open a second connection once the first is saturated, but no more"
+    );
+
+    release.add_permits(6);
+    for rsp in rsps {
+        let rsp = rsp.await.unwrap().expect("request must succeed");
+        assert_eq!(rsp.status(), http::StatusCode::OK);
+    }
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn reuses_connections_that_are_not_saturated() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let connections = Arc::new(AtomicUsize::new(0));
+    let received = Arc::new(AtomicUsize::new(0));
+    let release = Arc::new(Semaphore::new(100));
+    let connect = Connect::new(
+        connect(connections.clone(), received, release),
+        Settings::default(),
+    );
+    let conn = connect.clone().oneshot(()).await.expect("must connect");
+    let mut pool = Pool::new(
+        connect,
+        (),
+        PoolSettings {
+            max_connections: Some(2),
+            max_concurrent_streams: 2,
+        },
+        conn,
+    );
+
+    // Requests that complete before the next is sent never saturate the
+    // connection.
+    for _ in 0..4 {
+        let rsp = pool
+            .ready()
+            .await
+            .expect("pool must be ready")
+            .call(req())
+            .await
+            .expect("request must succeed");
+        assert_eq!(rsp.status(), http::StatusCode::OK);
+    }
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn backs_off_failed_connections() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let received = Arc::new(AtomicUsize::new(0));
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let connect = {
+        let mut connect = connect(
+            Arc::new(AtomicUsize::new(0)),
+            received.clone(),
+            Arc::new(Semaphore::new(0)),
+        );
+        let attempts = attempts.clone();
+        // Only the pool's first connection can be established.
+        service_fn(move |target: (crate::Version, ())| {
+            let first = attempts.fetch_add(1, Ordering::SeqCst) == 0;
+            let connect = connect.connect(target);
+            async move {
+                if !first {
+                    return Err(io::Error::from(io::ErrorKind::ConnectionRefused));
+                }
+                connect.await
+            }
+        })
+    };
+    let connect = Connect::new(connect, Settings::default());
+    let conn = connect.clone().oneshot(()).await.expect("must connect");
+    let mut pool = Pool::new(
+        connect,
+        (),
+        PoolSettings {
+            max_connections: Some(2),
+            max_concurrent_streams: 1,
+        },
+        conn,
+    );
+
+    // Saturate the pool's connection.
+    future::poll_fn(|cx| pool.poll_ready(cx))
+        .await
+        .expect("pool must be ready");
+    let _rsp = tokio::spawn(pool.call(req()));
+    while received.load(Ordering::SeqCst) < 1 {
+        tokio::task::yield_now().await;
+    }
+
+    // Once a connection can't be added, requests use the saturated connection
+    // without further attempts until the backoff elapses.
+    for _ in 0..3 {
+        future::poll_fn(|cx| pool.poll_ready(cx))
+            .await
+            .expect("pool must be ready");
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        tokio::spawn(pool.call(req()));
+        time::sleep(Duration::from_millis(10)).await;
+    }
+
+    time::sleep(Duration::from_millis(100)).await;
+    future::poll_fn(|cx| pool.poll_ready(cx))
+        .await
+        .expect("pool must be ready");
+    assert_eq!(
+        attempts.load(Ordering::SeqCst),
+        3,
+        "the pool must retry once its backoff elapses"
+    );
+}
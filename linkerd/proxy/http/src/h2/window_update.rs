@@ -0,0 +1,208 @@
+//! Bounds the rate at which HTTP/2 clients send WINDOW_UPDATE frames.
+//!
+//! WINDOW_UPDATE frames are cheap to send but each must be processed by the
+//! server's flow control, so a client may churn the server's CPU by flooding
+//! the connection with them. The frames read from a server connection are
+//! inspected so that the connection fails once a client sends more
+//! WINDOW_UPDATE frames in a one-second interval than the configured limit.
+
+use crate::ServerMetrics;
+use linkerd_io as io;
+use pin_project::pin_project;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::Instant;
+use tracing::debug;
+
+#[cfg(test)]
+mod tests;
+
+/// The client connection preface, which precedes the first frame.
+const PREFACE_LEN: usize = 24;
+
+const FRAME_HEADER_LEN: usize = 9;
+
+const WINDOW_UPDATE: u8 = 0x8;
+
+/// The interval over which WINDOW_UPDATE frames are counted.
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// Fails reads once more than `max_per_sec` WINDOW_UPDATE frames are read in
+/// an interval.
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct LimitWindowUpdates<I> {
+    #[pin]
+    io: I,
+    max_per_sec: Option<u32>,
+    frames: Frames,
+    metrics: ServerMetrics,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("HTTP/2 client sent more than {0} WINDOW_UPDATE frames per second")]
+pub struct TooManyWindowUpdates(u32);
+
+/// Tracks the frames read from a connection.
+#[derive(Debug)]
+struct Frames {
+    /// The number of preface bytes that have not yet been read.
+    preface: usize,
+    /// The current frame's header, as it's read.
+    header: [u8; FRAME_HEADER_LEN],
+    header_len: usize,
+    /// The number of bytes of the current frame's payload that have not yet
+    /// been read.
+    payload: usize,
+    /// The start of the current interval and the number of WINDOW_UPDATE
+    /// frames read in it.
+    interval: Option<(Instant, u32)>,
+}
+
+// === impl LimitWindowUpdates ===
+
+impl<I> LimitWindowUpdates<I> {
+    /// When `max_per_sec` is `None`, WINDOW_UPDATE frames are not limited.
+    pub(crate) fn new(io: I, max_per_sec: Option<u32>, metrics: ServerMetrics) -> Self {
+        Self {
+            io,
+            max_per_sec,
+            frames: Frames::new(),
+            metrics,
+        }
+    }
+}
+
+impl<I: io::AsyncRead> io::AsyncRead for LimitWindowUpdates<I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let max_per_sec = match *this.max_per_sec {
+            Some(max_per_sec) => max_per_sec,
+            None => return this.io.poll_read(cx, buf),
+        };
+
+        let filled = buf.filled().len();
+        futures::ready!(this.io.poll_read(cx, buf))?;
+        if let Err(error) = this
+            .frames
+            .read(&buf.filled()[filled..], max_per_sec, Instant::now())
+        {
+            debug!(%error, "Closing connection");
+            this.metrics.incr_h2_window_update_flood_closes();
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, error)));
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<I: io::AsyncWrite> io::AsyncWrite for LimitWindowUpdates<I> {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().io.poll_write(cx, buf)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().io.poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().io.poll_shutdown(cx)
+    }
+
+    #[inline]
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.project().io.poll_write_vectored(cx, bufs)
+    }
+
+    #[inline]
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+}
+
+// === impl Frames ===
+
+impl Frames {
+    fn new() -> Self {
+        Self {
+            preface: PREFACE_LEN,
+            header: [0; FRAME_HEADER_LEN],
+            header_len: 0,
+            payload: 0,
+            interval: None,
+        }
+    }
+
+    /// Reads bytes from the connection at `now`, failing if more than
+    /// `max_per_sec` WINDOW_UPDATE frames have been read in the interval.
+    fn read(
+        &mut self,
+        mut bytes: &[u8],
+        max_per_sec: u32,
+        now: Instant,
+    ) -> Result<(), TooManyWindowUpdates> {
+        while !bytes.is_empty() {
+            if self.preface > 0 {
+                let n = self.preface.min(bytes.len());
+                self.preface -= n;
+                bytes = &bytes[n..];
+                continue;
+            }
+
+            if self.payload > 0 {
+                let n = self.payload.min(bytes.len());
+                self.payload -= n;
+                bytes = &bytes[n..];
+                continue;
+            }
+
+            let n = (FRAME_HEADER_LEN - self.header_len).min(bytes.len());
+            self.header[self.header_len..self.header_len + n].copy_from_slice(&bytes[..n]);
+            self.header_len += n;
+            bytes = &bytes[n..];
+            if self.header_len == FRAME_HEADER_LEN {
+                self.header_len = 0;
+                self.frame(max_per_sec, now)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Accounts for a frame once its header has been read.
+    fn frame(&mut self, max_per_sec: u32, now: Instant) -> Result<(), TooManyWindowUpdates> {
+        let [l0, l1, l2, kind, ..] = self.header;
+        self.payload = u32::from_be_bytes([0, l0, l1, l2]) as usize;
+        if kind != WINDOW_UPDATE {
+            return Ok(());
+        }
+
+        let (start, count) = match self.interval.take() {
+            Some((start, count)) if now.saturating_duration_since(start) < INTERVAL => {
+                (start, count + 1)
+            }
+            _ => (now, 1),
+        };
+        self.interval = Some((start, count));
+        if count > max_per_sec {
+            return Err(TooManyWindowUpdates(max_per_sec));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use super::*;
+
+const MAX_PER_SEC: u32 = 10;
+
+fn frame(kind: u8, len: u32) -> Vec<u8> {
+    let mut frame = len.to_be_bytes()[1..].to_vec();
+    frame.extend_from_slice(&[kind, 0, 0, 0, 0, 1]);
+    frame.resize(FRAME_HEADER_LEN + len as usize, 0);
+    frame
+}
+
+#[test]
+fn allows_window_updates_within_limit() {
+    let now = Instant::now();
+    let mut bytes = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+    for _ in 0..MAX_PER_SEC {
+        bytes.extend(frame(WINDOW_UPDATE, 4));
+        // Other frames are not limited.
+        bytes.extend(frame(0x0, 16));
+    }
+
+    // Frames may be split across reads.
+    let mut frames = Frames::new();
+    for chunk in bytes.chunks(7) {
+        frames
+            .read(chunk, MAX_PER_SEC, now)
+            .expect("must not exceed limit");
+    }
+
+    // The count resets once the interval has elapsed.
+    let later = now + INTERVAL;
+    for _ in 0..MAX_PER_SEC {
+        frames
+            .read(&frame(WINDOW_UPDATE, 4), MAX_PER_SEC, later)
+            .expect("must not exceed limit");
+    }
+}
+
+#[test]
+fn fails_window_updates_over_limit() {
+    let now = Instant::now();
+    let mut bytes = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+    for _ in 0..MAX_PER_SEC {
+        bytes.extend(frame(WINDOW_UPDATE, 4));
+    }
+    let mut frames = Frames::new();
+    frames
+        .read(&bytes, MAX_PER_SEC, now)
+        .expect("must not exceed limit");
+
+    frames
+        .read(&frame(WINDOW_UPDATE, 4), MAX_PER_SEC, now + INTERVAL / 2)
+        .expect_err("must exceed limit");
+}
@@ -0,0 +1,76 @@
+//! Rejects requests with oversized header values.
+//!
+//! The size of a request's header block may be limited by the server, but a
+//! single header value may still be large enough to overwhelm applications
+//! that buffer it. This middleware may be configured to fail requests with a
+//! header value that exceeds a maximum length with a [`HeaderValueTooLarge`]
+//! error before they are forwarded.
+
+use futures::{future, TryFutureExt};
+use http::header::{HeaderMap, HeaderName};
+use linkerd_error::Error;
+use linkerd_stack::layer;
+use std::task::{Context, Poll};
+use thiserror::Error;
+use tracing::debug;
+
+#[derive(Clone, Debug)]
+pub struct LimitHeaderValues<S> {
+    inner: S,
+    max_bytes: Option<usize>,
+}
+
+#[derive(Debug, Error)]
+#[error("header {name} exceeds the maximum value length of {max_bytes} bytes")]
+pub struct HeaderValueTooLarge {
+    name: HeaderName,
+    max_bytes: usize,
+}
+
+// === impl LimitHeaderValues ===
+
+impl<S> LimitHeaderValues<S> {
+    /// When `max_bytes` is `None`, requests are not validated.
+    pub fn layer(max_bytes: Option<usize>) -> impl layer::Layer<S, Service = Self> + Copy + Clone {
+        layer::mk(move |inner| Self { inner, max_bytes })
+    }
+}
+
+impl<S, B> tower::Service<http::Request<B>> for LimitHeaderValues<S>
+where
+    S: tower::Service<http::Request<B>>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<
+        future::ErrInto<S::Future, Error>,
+        future::Ready<Result<S::Response, Error>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if let Some(max_bytes) = self.max_bytes {
+            if let Err(error) = check_values(req.headers(), max_bytes) {
+                debug!(%error, "Rejecting request");
+                return future::Either::Right(future::err(error.into()));
+            }
+        }
+
+        future::Either::Left(self.inner.call(req).err_into())
+    }
+}
+
+fn check_values(headers: &HeaderMap, max_bytes: usize) -> Result<(), HeaderValueTooLarge> {
+    match headers.iter().find(|(_, value)| value.len() > max_bytes) {
+        Some((name, _)) => Err(HeaderValueTooLarge {
+            name: name.clone(),
+            max_bytes,
+        }),
+        None => Ok(()),
+    }
+}
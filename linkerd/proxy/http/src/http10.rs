@@ -0,0 +1,199 @@
+//! Bridges HTTP/1.0 clients to HTTP/1.1 servers.
+//!
+//! HTTP/1.0 requests are sent upstream as HTTP/1.1 so that they may be served
+//! over pooled, persistent connections:
+//!
+//! * The client's connection-level headers (e.g. `Connection: keep-alive`) are
+//!   stripped, after noting whether the client asked to keep its connection
+//!   alive;
+//! * A `Host` header is set from the request's authority, if one was not
+//!   provided.
+//!
+//! Responses are downgraded to HTTP/1.0 and are marked with a `Connection`
+//! header that preserves the client's keep-alive preference, since HTTP/1.0
+//! connections are closed by default.
+//!
+//! This must be below the `NormalizeUri` layer so that requests have an
+//! authority.
+
+use super::h1;
+use futures::{future, TryFutureExt};
+use http::header::{HeaderValue, CONNECTION, HOST};
+use linkerd_stack::layer;
+use std::task::{Context, Poll};
+use tracing::trace;
+
+#[derive(Clone, Debug)]
+pub struct BridgeHttp10<S> {
+    inner: S,
+    enabled: bool,
+}
+
+// === impl BridgeHttp10 ===
+
+impl<S> BridgeHttp10<S> {
+    pub fn new(inner: S, enabled: bool) -> Self {
+        Self { inner, enabled }
+    }
+
+    /// Bridges HTTP/1.0 requests to HTTP/1.1 when `enabled` is set. Otherwise,
+    /// requests are passed through unmodified.
+    pub fn layer(enabled: bool) -> impl layer::Layer<S, Service = Self> + Copy + Clone {
+        layer::mk(move |inner| Self::new(inner, enabled))
+    }
+}
+
+impl<S, B, RspB> tower::Service<http::Request<B>> for BridgeHttp10<S>
+where
+    S: tower::Service<http::Request<B>, Response = http::Response<RspB>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = future::Either<
+        S::Future,
+        future::MapOk<S::Future, fn(http::Response<RspB>) -> http::Response<RspB>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        if !self.enabled
+            || req.version() != http::Version::HTTP_10
+            || req.method() == http::Method::CONNECT
+        {
+            return future::Either::Left(self.inner.call(req));
+        }
+
+        let keep_alive = wants_keep_alive(req.headers());
+        h1::strip_connection_headers(req.headers_mut());
+        if !req.headers().contains_key(HOST) {
+            if let Some(host) = req
+                .uri()
+                .authority()
+                .and_then(|a| HeaderValue::from_str(a.as_str()).ok())
+            {
+                req.headers_mut().insert(HOST, host);
+            }
+        }
+        trace!(keep_alive, "Bridging HTTP/1.0 request to HTTP/1.1");
+        *req.version_mut() = http::Version::HTTP_11;
+
+        let downgrade: fn(http::Response<RspB>) -> http::Response<RspB> = if keep_alive {
+            downgrade_keep_alive
+        } else {
+            downgrade_close
+        };
+        future::Either::Right(self.inner.call(req).map_ok(downgrade))
+    }
+}
+
+fn downgrade_keep_alive<B>(rsp: http::Response<B>) -> http::Response<B> {
+    downgrade(rsp, HeaderValue::from_static("keep-alive"))
+}
+
+fn downgrade_close<B>(rsp: http::Response<B>) -> http::Response<B> {
+    downgrade(rsp, HeaderValue::from_static("close"))
+}
+
+fn downgrade<B>(mut rsp: http::Response<B>, connection: HeaderValue) -> http::Response<B> {
+    *rsp.version_mut() = http::Version::HTTP_10;
+    h1::strip_connection_headers(rsp.headers_mut());
+    rsp.headers_mut().insert(CONNECTION, connection);
+    rsp
+}
+
+/// Returns true if an HTTP/1.0 client asked for its connection to persist.
+fn wants_keep_alive(headers: &http::HeaderMap) -> bool {
+    headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|token| token.trim().eq_ignore_ascii_case("keep-alive"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_stack::{service_fn, ServiceExt};
+
+    fn upstream(
+        req: http::Request<()>,
+    ) -> future::Ready<Result<http::Response<()>, std::convert::Infallible>> {
+        assert_eq!(req.version(), http::Version::HTTP_11);
+        assert_eq!(req.headers().get(HOST).unwrap(), "foo.example.com");
+        assert!(req.headers().get(CONNECTION).is_none());
+        assert!(req.headers().get("keep-alive").is_none());
+        future::ok(
+            http::Response::builder()
+                .version(http::Version::HTTP_11)
+                .header("keep-alive", "timeout=5")
+                .body(())
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn bridges_http10_close() {
+        let req = http::Request::get("http://foo.example.com/")
+            .version(http::Version::HTTP_10)
+            .body(())
+            .unwrap();
+        let rsp = BridgeHttp10::new(service_fn(upstream), true)
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(rsp.version(), http::Version::HTTP_10);
+        assert_eq!(rsp.headers().get(CONNECTION).unwrap(), "close");
+        assert!(rsp.headers().get("keep-alive").is_none());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn bridges_http10_keep_alive() {
+        let req = http::Request::get("http://foo.example.com/")
+            .version(http::Version::HTTP_10)
+            .header(CONNECTION, "Keep-Alive")
+            .header("keep-alive", "timeout=10")
+            .body(())
+            .unwrap();
+        let rsp = BridgeHttp10::new(service_fn(upstream), true)
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(rsp.version(), http::Version::HTTP_10);
+        assert_eq!(rsp.headers().get(CONNECTION).unwrap(), "keep-alive");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn disabled() {
+        let svc = service_fn(|req: http::Request<()>| {
+            assert_eq!(req.version(), http::Version::HTTP_10);
+            future::ok::<_, std::convert::Infallible>(http::Response::new(()))
+        });
+        let req = http::Request::get("http://foo.example.com/")
+            .version(http::Version::HTTP_10)
+            .body(())
+            .unwrap();
+        let rsp = BridgeHttp10::new(svc, false).oneshot(req).await.unwrap();
+        assert!(rsp.headers().get(CONNECTION).is_none());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn ignores_http11() {
+        let svc = service_fn(|req: http::Request<()>| {
+            assert_eq!(req.version(), http::Version::HTTP_11);
+            assert_eq!(req.headers().get(CONNECTION).unwrap(), "upgrade");
+            future::ok::<_, std::convert::Infallible>(http::Response::new(()))
+        });
+        let req = http::Request::get("http://foo.example.com/")
+            .header(CONNECTION, "upgrade")
+            .body(())
+            .unwrap();
+        let rsp = BridgeHttp10::new(svc, true).oneshot(req).await.unwrap();
+        assert_eq!(rsp.version(), http::Version::HTTP_11);
+        assert!(rsp.headers().get(CONNECTION).is_none());
+    }
+}
@@ -8,10 +8,13 @@ pub mod balance;
 pub mod client;
 pub mod client_handle;
 pub mod detect;
+pub mod framing;
 mod glue;
 pub mod h1;
 pub mod h2;
 mod header_from_target;
+pub mod header_limit;
+pub mod http10;
 pub mod insert;
 pub mod normalize_uri;
 pub mod orig_proto;
@@ -25,15 +28,18 @@ pub mod upgrade;
 pub mod version;
 
 pub use self::{
-    balance::NewBalancePeakEwma,
+    balance::{NewBalanceHashed, NewBalancePeakEwma, NewBalanceWeightedRoundRobin},
     client_handle::{ClientHandle, SetClientHandle},
     detect::DetectHttp,
+    framing::{NormalizeTransferEncoding, RejectAmbiguousFraming},
     glue::{HyperServerSvc, UpgradeBody},
     header_from_target::NewHeaderFromTarget,
+    header_limit::LimitHeaderValues,
+    http10::BridgeHttp10,
     normalize_uri::{MarkAbsoluteForm, NewNormalizeUri},
     override_authority::{AuthorityOverride, NewOverrideAuthority},
     retain::Retain,
-    server::{NewServeHttp, ServeHttp},
+    server::{NewServeHttp, ServeHttp, ServerMetrics},
     strip_header::StripHeader,
     timeout::{NewTimeout, ResponseTimeout, ResponseTimeoutError},
     version::Version,
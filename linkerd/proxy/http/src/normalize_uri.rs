@@ -12,7 +12,12 @@
 //!   extension is added so that the `h1::Client` can differentiate the request
 //!   from modified requests;
 //! * Otherwise, if the request has a `Host` header, it is used as the authority;
-//! * Otherwise, the target's address is used (as provided by the target).
+//! * Otherwise, unless a `Host` header is required, the target's address is
+//!   used (as provided by the target).
+//!
+//! Requests for which no authority can be determined are left unmodified, so
+//! that they may be failed with a [`NoAuthority`] error by [`RequireAuthority`]
+//! further down the stack.
 
 use super::h1;
 use futures::{future, TryFutureExt};
@@ -26,6 +31,7 @@ use tracing::trace;
 #[derive(Clone, Debug)]
 pub struct NewNormalizeUri<N> {
     inner: N,
+    require_host: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -42,6 +48,13 @@ pub struct DefaultAuthority(pub Option<Authority>);
 #[error("failed to normalize URI because no authority could be determined")]
 pub struct NoAuthority(());
 
+/// Fails HTTP/1 requests whose URIs have no authority with a [`NoAuthority`]
+/// error.
+#[derive(Clone, Debug)]
+pub struct RequireAuthority<S> {
+    inner: S,
+}
+
 /// Detects the original form of a request URI and inserts a `WasAbsoluteForm`
 /// extension.
 #[derive(Clone, Debug)]
@@ -52,12 +65,20 @@ pub struct MarkAbsoluteForm<S> {
 // === impl NewNormalizeUri ===
 
 impl<N> NewNormalizeUri<N> {
-    pub fn layer() -> impl layer::Layer<N, Service = Self> + Copy + Clone {
-        layer::mk(Self::new)
+    /// Normalizes request URIs.
+    ///
+    /// When `require_host` is set, origin-form HTTP/1 requests without a
+    /// `Host` header are failed rather than using the target's default
+    /// authority.
+    pub fn layer(require_host: bool) -> impl layer::Layer<N, Service = Self> + Copy + Clone {
+        layer::mk(move |inner| Self::new(inner, require_host))
     }
 
-    fn new(inner: N) -> Self {
-        Self { inner }
+    fn new(inner: N, require_host: bool) -> Self {
+        Self {
+            inner,
+            require_host,
+        }
     }
 }
 
@@ -69,7 +90,12 @@ where
     type Service = NormalizeUri<N::Service>;
 
     fn new_service(&self, target: T) -> Self::Service {
-        let DefaultAuthority(default) = target.param();
+        let default = if self.require_host {
+            None
+        } else {
+            let DefaultAuthority(default) = target.param();
+            default
+        };
         let inner = self.inner.new_service(target);
         NormalizeUri::new(inner, default)
     }
@@ -84,6 +110,50 @@ impl<S> NormalizeUri<S> {
 }
 
 impl<S, B> tower::Service<http::Request<B>> for NormalizeUri<S>
+where
+    S: tower::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        if let http::Version::HTTP_10 | http::Version::HTTP_11 = req.version() {
+            if req.extensions().get::<h1::WasAbsoluteForm>().is_none()
+                && req.uri().authority().is_none()
+            {
+                match h1::authority_from_host(&req).or_else(|| self.default.clone()) {
+                    Some(authority) => {
+                        trace!(%authority, "Normalizing URI");
+                        h1::set_authority(req.uri_mut(), authority);
+                    }
+                    None => trace!("No authority could be determined"),
+                }
+            }
+        }
+
+        self.inner.call(req)
+    }
+}
+
+// === impl RequireAuthority ===
+
+impl<S> RequireAuthority<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    pub fn layer() -> impl layer::Layer<S, Service = Self> + Copy + Clone {
+        layer::mk(Self::new)
+    }
+}
+
+impl<S, B> tower::Service<http::Request<B>> for RequireAuthority<S>
 where
     S: tower::Service<http::Request<B>>,
     S::Error: Into<Error>,
@@ -100,19 +170,10 @@ where
         self.inner.poll_ready(cx).map_err(Into::into)
     }
 
-    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
         if let http::Version::HTTP_10 | http::Version::HTTP_11 = req.version() {
-            if req.extensions().get::<h1::WasAbsoluteForm>().is_none()
-                && req.uri().authority().is_none()
-            {
-                let authority = match h1::authority_from_host(&req).or_else(|| self.default.clone())
-                {
-                    Some(a) => a,
-                    None => return future::Either::Right(future::err(NoAuthority(()).into())),
-                };
-
-                trace!(%authority, "Normalizing URI");
-                h1::set_authority(req.uri_mut(), authority);
+            if req.uri().authority().is_none() {
+                return future::Either::Right(future::err(NoAuthority(()).into()));
             }
         }
 
@@ -2,19 +2,29 @@ use crate::{
     self as http,
     client_handle::SetClientHandle,
     glue::{HyperServerSvc, UpgradeBody},
+    h1::{CloseDelimited, ParseErrors, Pipelining, ServerSettings as H1Settings},
     h2::Settings as H2Settings,
     trace, upgrade, ClientHandle, Version,
 };
 use linkerd_error::Error;
 use linkerd_io::{self as io, PeerAddr};
 use linkerd_stack::{layer, NewService, Param};
+use pin_project::pin_project;
 use std::{
     future::Future,
     pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
 };
 use tower::Service;
-use tracing::{debug, Instrument};
+use tracing::{debug, trace, Instrument};
+
+mod pipeline;
+#[cfg(test)]
+mod tests;
 
 type Server = hyper::server::conn::Http<trace::Executor>;
 
@@ -22,6 +32,12 @@ type Server = hyper::server::conn::Http<trace::Executor>;
 pub struct NewServeHttp<N> {
     inner: N,
     server: Server,
+    metrics: ServerMetrics,
+    max_header_block_size: Option<u32>,
+    max_window_updates_per_second: Option<u32>,
+    close_on_drain: bool,
+    pipelining: Pipelining,
+    parse_errors: ParseErrors,
     drain: drain::Watch,
 }
 
@@ -29,26 +45,76 @@ pub struct NewServeHttp<N> {
 pub struct ServeHttp<N> {
     version: Version,
     server: Server,
+    metrics: ServerMetrics,
+    max_header_block_size: Option<u32>,
+    max_window_updates_per_second: Option<u32>,
+    close_on_drain: bool,
+    pipelining: Pipelining,
+    parse_errors: ParseErrors,
     inner: N,
     drain: drain::Watch,
 }
 
+/// Counts server connections that were closed because the client misbehaved.
+///
+/// Clones share the same counters, so the registry may be read by a metrics
+/// report while it's updated by each server.
+#[derive(Clone, Debug, Default)]
+pub struct ServerMetrics(Arc<Counts>);
+
+#[derive(Debug, Default)]
+struct Counts {
+    http1_parse_errors: AtomicU64,
+    h2_oversized_frame_closes: AtomicU64,
+    h2_continuation_flood_closes: AtomicU64,
+    h2_window_update_flood_closes: AtomicU64,
+}
+
+/// Sets a `Connection: close` header on HTTP/1 responses once the connection
+/// has begun draining, or when the response's body is delimited by the
+/// upstream connection closing.
+#[derive(Debug)]
+struct CloseOnDrain<S> {
+    inner: S,
+    draining: Option<Arc<AtomicBool>>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+struct CloseOnDrainFuture<F> {
+    #[pin]
+    inner: F,
+    draining: Option<Arc<AtomicBool>>,
+}
+
 // === impl NewServeHttp ===
 
 impl<N> NewServeHttp<N> {
     pub fn layer(
+        h1: H1Settings,
         h2: H2Settings,
+        metrics: ServerMetrics,
         drain: drain::Watch,
     ) -> impl layer::Layer<N, Service = Self> + Clone {
-        layer::mk(move |inner| Self::new(h2, inner, drain.clone()))
+        layer::mk(move |inner| Self::new(h1, h2, metrics.clone(), inner, drain.clone()))
     }
 
     /// Creates a new `ServeHttp`.
-    fn new(h2: H2Settings, inner: N, drain: drain::Watch) -> Self {
+    fn new(
+        h1: H1Settings,
+        h2: H2Settings,
+        metrics: ServerMetrics,
+        inner: N,
+        drain: drain::Watch,
+    ) -> Self {
         let mut server = hyper::server::conn::Http::new().with_executor(trace::Executor::new());
+        if let Some(sz) = h1.max_buf_size {
+            server.max_buf_size(sz);
+        }
         server
             .http2_initial_stream_window_size(h2.initial_stream_window_size)
-            .http2_initial_connection_window_size(h2.initial_connection_window_size);
+            .http2_initial_connection_window_size(h2.initial_connection_window_size)
+            .http2_max_frame_size(h2.max_frame_size);
 
         // Configure HTTP/2 PING frames
         if let Some(timeout) = h2.keepalive_timeout {
@@ -63,6 +129,12 @@ impl<N> NewServeHttp<N> {
         Self {
             inner,
             server,
+            metrics,
+            max_header_block_size: h2.max_header_block_size,
+            max_window_updates_per_second: h2.max_window_updates_per_second,
+            close_on_drain: h1.close_on_drain,
+            pipelining: h1.pipelining,
+            parse_errors: h1.parse_errors,
             drain,
         }
     }
@@ -83,6 +155,12 @@ where
             inner,
             version,
             server: self.server.clone(),
+            metrics: self.metrics.clone(),
+            max_header_block_size: self.max_header_block_size,
+            max_window_updates_per_second: self.max_window_updates_per_second,
+            close_on_drain: self.close_on_drain,
+            pipelining: self.pipelining,
+            parse_errors: self.parse_errors,
             drain: self.drain.clone(),
         }
     }
@@ -113,7 +191,13 @@ where
             version,
             inner,
             drain,
+            close_on_drain,
+            pipelining,
+            parse_errors,
             mut server,
+            metrics,
+            max_header_block_size,
+            max_window_updates_per_second,
         } = self.clone();
         debug!(?version, "Handling as HTTP");
 
@@ -127,18 +211,34 @@ where
 
                 match version {
                     Version::Http1 => {
+                        let draining = close_on_drain.then(Default::default);
+                        let svc = CloseOnDrain {
+                            inner: upgrade::Service::new(svc, drain.clone()),
+                            draining: draining.clone(),
+                        };
+                        let (io, svc) = pipeline::detect(pipelining, io, svc);
                         // Enable support for HTTP upgrades (CONNECT and websockets).
                         let mut conn = server
                             .http1_only(true)
-                            .serve_connection(io, upgrade::Service::new(svc, drain.clone()))
+                            .serve_connection(io, svc)
                             .with_upgrades();
                         tokio::select! {
                             res = &mut conn => {
                                 debug!(?res, "The client is shutting down the connection");
+                                if let Err(error) = &res {
+                                    let is_parse_error = crate::h1::record_parse_error(error, &metrics);
+                                    if is_parse_error && parse_errors == ParseErrors::Abort {
+                                        debug!(%error, "Closing connection after a parse error");
+                                        return Ok(());
+                                    }
+                                }
                                 res?
                             }
                             shutdown = drain.signaled() => {
                                 debug!("The process is shutting down the connection");
+                                if let Some(draining) = draining {
+                                    draining.store(true, Ordering::Release);
+                                }
                                 Pin::new(&mut conn).graceful_shutdown();
                                 shutdown.release_after(conn).await?;
                             }
@@ -151,12 +251,29 @@ where
                     }
 
                     Version::H2 => {
+                        // Fails the connection if the client floods it with
+                        // CONTINUATION frames.
+                        let io = crate::h2::LimitHeaderBlocks::new(
+                            io,
+                            max_header_block_size,
+                            metrics.clone(),
+                        );
+                        // Fails the connection if the client floods it with
+                        // WINDOW_UPDATE frames.
+                        let io = crate::h2::LimitWindowUpdates::new(
+                            io,
+                            max_window_updates_per_second,
+                            metrics.clone(),
+                        );
                         let mut conn = server
                             .http2_only(true)
                             .serve_connection(io, HyperServerSvc::new(svc));
                         tokio::select! {
                             res = &mut conn => {
                                 debug!(?res, "The client is shutting down the connection");
+                                if let Err(error) = &res {
+                                    crate::h2::record_connection_error(error, &metrics);
+                                }
                                 res?
                             }
                             shutdown = drain.signaled() => {
@@ -178,3 +295,105 @@ where
         )
     }
 }
+
+// === impl ServerMetrics ===
+
+impl ServerMetrics {
+    /// Returns the total number of HTTP/1 connections that were closed because
+    /// the client sent bytes that could not be parsed as HTTP/1.
+    pub fn http1_parse_errors(&self) -> u64 {
+        self.0.http1_parse_errors.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn incr_http1_parse_errors(&self) {
+        self.0.http1_parse_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the total number of HTTP/2 connections that were closed because
+    /// the client sent a frame larger than the configured maximum frame size.
+    pub fn h2_oversized_frame_closes(&self) -> u64 {
+        self.0.h2_oversized_frame_closes.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn incr_h2_oversized_frame_closes(&self) {
+        self.0
+            .h2_oversized_frame_closes
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the total number of HTTP/2 connections that were closed because
+    /// the client sent a header block larger than the configured maximum, e.g.
+    /// by flooding the connection with CONTINUATION frames.
+    pub fn h2_continuation_flood_closes(&self) -> u64 {
+        self.0.h2_continuation_flood_closes.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn incr_h2_continuation_flood_closes(&self) {
+        self.0
+            .h2_continuation_flood_closes
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the total number of HTTP/2 connections that were closed because
+    /// the client sent WINDOW_UPDATE frames faster than the configured maximum
+    /// rate.
+    pub fn h2_window_update_flood_closes(&self) -> u64 {
+        self.0.h2_window_update_flood_closes.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn incr_h2_window_update_flood_closes(&self) {
+        self.0
+            .h2_window_update_flood_closes
+            .fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// === impl CloseOnDrain ===
+
+impl<S, Req, B> tower::Service<Req> for CloseOnDrain<S>
+where
+    S: tower::Service<Req, Response = http::Response<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = CloseOnDrainFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        CloseOnDrainFuture {
+            inner: self.inner.call(req),
+            draining: self.draining.clone(),
+        }
+    }
+}
+
+impl<F, B, E> Future for CloseOnDrainFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut rsp = futures::ready!(this.inner.poll(cx))?;
+        let draining = this
+            .draining
+            .as_ref()
+            .map(|d| d.load(Ordering::Acquire))
+            .unwrap_or(false);
+        let close_delimited = rsp.extensions().get::<CloseDelimited>().is_some();
+        // Upgraded connections are not reused, so they are left alone.
+        if (draining || close_delimited) && rsp.status() != http::StatusCode::SWITCHING_PROTOCOLS {
+            trace!(draining, close_delimited, "Setting connection: close");
+            rsp.headers_mut().insert(
+                http::header::CONNECTION,
+                http::HeaderValue::from_static("close"),
+            );
+        }
+        Poll::Ready(Ok(rsp))
+    }
+}
@@ -0,0 +1,245 @@
+//! Rejects pipelined HTTP/1 requests.
+//!
+//! hyper processes the requests on an HTTP/1 connection one at a time, so
+//! pipelined requests are serialized by default. When pipelined requests are
+//! rejected instead, a request is considered pipelined if none of the
+//! connection's reads returned data after the response to the prior request
+//! was written, i.e. if all of the request's bytes had already been read (and
+//! buffered) by the time the prior response completed.
+
+use crate::{h1::Pipelining, BoxBody};
+use futures::future;
+use linkerd_io as io;
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+use tracing::debug;
+
+/// Records the reads of a connection so that pipelined requests are detected.
+#[pin_project]
+#[derive(Debug)]
+pub(super) struct DetectIo<I> {
+    #[pin]
+    io: I,
+    state: Option<Arc<State>>,
+}
+
+/// Fails pipelined requests detected by [`DetectIo`].
+#[derive(Debug)]
+pub(super) struct RejectPipelined<S> {
+    inner: S,
+    state: Option<Arc<State>>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub(super) struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    state: Option<Arc<State>>,
+}
+
+/// A response body that notes when the response has been written.
+#[pin_project]
+#[derive(Debug)]
+struct ResponseBody {
+    #[pin]
+    inner: BoxBody,
+    _end: ResponseEnd,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Set once a response has been written on the connection.
+    responded: AtomicBool,
+    /// Set when a read returns data, and cleared when a response is written.
+    read: AtomicBool,
+}
+
+#[derive(Debug)]
+struct ResponseEnd(Arc<State>);
+
+/// Wraps a connection's IO and service so that pipelined requests are handled
+/// according to `pipelining`.
+pub(super) fn detect<I, S>(
+    pipelining: Pipelining,
+    io: I,
+    inner: S,
+) -> (DetectIo<I>, RejectPipelined<S>) {
+    let state = match pipelining {
+        Pipelining::Serialize => None,
+        Pipelining::Reject => Some(Arc::new(State::default())),
+    };
+    let svc = RejectPipelined {
+        inner,
+        state: state.clone(),
+    };
+    (DetectIo { io, state }, svc)
+}
+
+// === impl DetectIo ===
+
+impl<I: io::AsyncRead> io::AsyncRead for DetectIo<I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let filled = buf.filled().len();
+        futures::ready!(this.io.poll_read(cx, buf))?;
+        if let Some(state) = this.state {
+            if buf.filled().len() > filled {
+                state.read.store(true, Ordering::Release);
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<I: io::AsyncWrite> io::AsyncWrite for DetectIo<I> {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().io.poll_write(cx, buf)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().io.poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().io.poll_shutdown(cx)
+    }
+
+    #[inline]
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.project().io.poll_write_vectored(cx, bufs)
+    }
+
+    #[inline]
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+}
+
+// === impl RejectPipelined ===
+
+impl<S, Req> tower::Service<Req> for RejectPipelined<S>
+where
+    S: tower::Service<Req, Response = http::Response<BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = future::Either<
+        future::Ready<Result<http::Response<BoxBody>, S::Error>>,
+        ResponseFuture<S::Future>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        if let Some(state) = self.state.as_ref() {
+            if state.responded.load(Ordering::Acquire) && !state.read.load(Ordering::Acquire) {
+                debug!("Rejecting pipelined request");
+                let rsp = http::Response::builder()
+                    .status(http::StatusCode::SERVICE_UNAVAILABLE)
+                    .header(http::header::CONNECTION, "close")
+                    .body(BoxBody::default())
+                    .expect("response must be valid");
+                return future::Either::Left(future::ok(rsp));
+            }
+        }
+
+        future::Either::Right(ResponseFuture {
+            inner: self.inner.call(req),
+            state: self.state.clone(),
+        })
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<BoxBody>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let rsp = futures::ready!(this.inner.poll(cx))?;
+        let rsp = match this.state.take() {
+            Some(state) => rsp.map(|inner| {
+                BoxBody::new(ResponseBody {
+                    inner,
+                    _end: ResponseEnd(state),
+                })
+            }),
+            None => rsp,
+        };
+        Poll::Ready(Ok(rsp))
+    }
+}
+
+// === impl ResponseBody ===
+
+impl http_body::Body for ResponseBody {
+    type Data = <BoxBody as http_body::Body>::Data;
+    type Error = <BoxBody as http_body::Body>::Error;
+
+    #[inline]
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        self.project().inner.poll_data(cx)
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// === impl ResponseEnd ===
+
+impl Drop for ResponseEnd {
+    /// The response body is dropped once it has been written.
+    fn drop(&mut self) {
+        self.0.read.store(false, Ordering::Release);
+        self.0.responded.store(true, Ordering::Release);
+    }
+}
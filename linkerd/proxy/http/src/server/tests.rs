@@ -0,0 +1,429 @@
+use super::*;
+use crate::BoxBody;
+use futures::future;
+use linkerd_stack::service_fn;
+use tokio::sync::Notify;
+
+/// Tests that, once draining begins, an in-flight HTTP/1 response on a
+/// keep-alive connection is sent with `Connection: close` and that the
+/// connection is then closed.
+#[tokio::test(flavor = "current_thread")]
+async fn http1_close_on_drain() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let received = Arc::new(Notify::new());
+    let release = Arc::new(Notify::new());
+    let inner = {
+        let received = received.clone();
+        let release = release.clone();
+        move |_: ClientHandle| {
+            let received = received.clone();
+            let release = release.clone();
+            service_fn(move |req: http::Request<UpgradeBody>| {
+                let received = received.clone();
+                let release = release.clone();
+                async move {
+                    if req.uri().path() == "/wait" {
+                        received.notify_one();
+                        release.notified().await;
+                    }
+                    Ok::<_, Error>(http::Response::new(BoxBody::default()))
+                }
+            })
+        }
+    };
+
+    let (drain_tx, drain) = drain::channel();
+    let h1 = H1Settings {
+        close_on_drain: true,
+        ..Default::default()
+    };
+    let mut serve = layer::Layer::layer(
+        &NewServeHttp::layer(h1, H2Settings::default(), Default::default(), drain),
+        move |_: Version| inner.clone(),
+    )
+    .new_service(Version::Http1);
+
+    let (client_io, server_io) = io::duplex(4096);
+    let server = tokio::spawn(serve.call(server_io));
+    drop(serve);
+    let (mut client, conn) = hyper::client::conn::Builder::new()
+        .handshake::<_, hyper::Body>(client_io)
+        .await
+        .expect("client must connect");
+    let conn = tokio::spawn(conn);
+
+    // Before draining, responses allow the connection to be reused.
+    future::poll_fn(|cx| client.poll_ready(cx)).await.unwrap();
+    let rsp = client
+        .send_request(req("/"))
+        .await
+        .expect("request must succeed");
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    assert!(rsp.headers().get(http::header::CONNECTION).is_none());
+
+    // Start draining while a request is in flight.
+    future::poll_fn(|cx| client.poll_ready(cx)).await.unwrap();
+    let rsp = tokio::spawn(client.send_request(req("/wait")));
+    received.notified().await;
+    let drained = tokio::spawn(drain_tx.drain());
+    for _ in 0..10 {
+        tokio::task::yield_now().await;
+    }
+    release.notify_one();
+
+    let rsp = rsp.await.unwrap().expect("request must succeed");
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    assert_eq!(
+        rsp.headers().get(http::header::CONNECTION),
+        Some(&http::HeaderValue::from_static("close"))
+    );
+
+    // The connection is closed once the in-flight response completes.
+    conn.await
+        .unwrap()
+        .expect("client connection must close cleanly");
+    server
+        .await
+        .unwrap()
+        .expect("server connection must close cleanly");
+    drained.await.unwrap();
+}
+
+/// Tests that an HTTP/2 connection on which the client sends a frame larger
+/// than the configured maximum frame size is closed and counted.
+#[tokio::test(flavor = "current_thread")]
+async fn h2_oversized_frame_closes_connection() {
+    use io::{AsyncReadExt, AsyncWriteExt};
+
+    let _trace = linkerd_tracing::test::trace_init();
+
+    const MAX_FRAME_SIZE: u32 = 16_384;
+    let inner = |_: ClientHandle| {
+        service_fn(|_: http::Request<UpgradeBody>| {
+            future::ok::<_, Error>(http::Response::new(BoxBody::default()))
+        })
+    };
+    let (_drain_tx, drain) = drain::channel();
+    let h2 = H2Settings {
+        max_frame_size: Some(MAX_FRAME_SIZE),
+        ..Default::default()
+    };
+    let metrics = ServerMetrics::default();
+    let mut serve = layer::Layer::layer(
+        &NewServeHttp::layer(H1Settings::default(), h2, metrics.clone(), drain),
+        move |_: Version| inner,
+    )
+    .new_service(Version::H2);
+
+    let (mut client_io, server_io) = io::duplex(64 * 1024);
+    let server = tokio::spawn(serve.call(server_io));
+
+    // Send the connection preface and an empty SETTINGS frame, followed by a
+    // DATA frame that is one byte larger than the maximum frame size.
+    let mut frames = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+    frames.extend_from_slice(&[0, 0, 0, 0x4, 0, 0, 0, 0, 0]);
+    let len = MAX_FRAME_SIZE + 1;
+    frames.extend_from_slice(&len.to_be_bytes()[1..]);
+    frames.extend_from_slice(&[0x0, 0, 0, 0, 0, 1]);
+    frames.resize(frames.len() + len as usize, 0);
+    client_io.write_all(&frames).await.unwrap();
+
+    // The server closes the connection.
+    let mut buf = Vec::new();
+    client_io.read_to_end(&mut buf).await.unwrap();
+    server
+        .await
+        .unwrap()
+        .expect_err("server connection must fail");
+    assert_eq!(metrics.h2_oversized_frame_closes(), 1);
+}
+
+/// Tests that an HTTP/2 connection on which the client floods a header block
+/// with CONTINUATION frames is closed and counted.
+#[tokio::test(flavor = "current_thread")]
+async fn h2_continuation_flood_closes_connection() {
+    use io::{AsyncReadExt, AsyncWriteExt};
+
+    let _trace = linkerd_tracing::test::trace_init();
+
+    const MAX_HEADER_BLOCK_SIZE: u32 = 8 * 1024;
+    let inner = |_: ClientHandle| {
+        service_fn(|_: http::Request<UpgradeBody>| {
+            future::ok::<_, Error>(http::Response::new(BoxBody::default()))
+        })
+    };
+    let (_drain_tx, drain) = drain::channel();
+    let h2 = H2Settings {
+        max_header_block_size: Some(MAX_HEADER_BLOCK_SIZE),
+        ..Default::default()
+    };
+    let metrics = ServerMetrics::default();
+    let mut serve = layer::Layer::layer(
+        &NewServeHttp::layer(H1Settings::default(), h2, metrics.clone(), drain),
+        move |_: Version| inner,
+    )
+    .new_service(Version::H2);
+
+    let (mut client_io, server_io) = io::duplex(64 * 1024);
+    let server = tokio::spawn(serve.call(server_io));
+
+    // Send the connection preface and an empty SETTINGS frame, followed by a
+    // HEADERS frame without END_HEADERS and a stream of CONTINUATION frames
+    // (none of which end the header block) that exceed the maximum size.
+    let mut frames = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+    frames.extend_from_slice(&[0, 0, 0, 0x4, 0, 0, 0, 0, 0]);
+    const LEN: usize = 1024;
+    frames.extend_from_slice(&[0, 0x4, 0, 0x1, 0, 0, 0, 0, 1]);
+    frames.resize(frames.len() + LEN, 0);
+    for _ in 0..(MAX_HEADER_BLOCK_SIZE as usize / LEN) {
+        frames.extend_from_slice(&[0, 0x4, 0, 0x9, 0, 0, 0, 0, 1]);
+        frames.resize(frames.len() + LEN, 0);
+    }
+    client_io.write_all(&frames).await.unwrap();
+
+    // The server closes the connection.
+    let mut buf = Vec::new();
+    let _ = client_io.read_to_end(&mut buf).await;
+    server
+        .await
+        .unwrap()
+        .expect_err("server connection must fail");
+    assert_eq!(metrics.h2_continuation_flood_closes(), 1);
+}
+
+/// Tests that an HTTP/2 connection on which the client floods WINDOW_UPDATE
+/// frames faster than the configured rate is closed and counted.
+#[tokio::test(flavor = "current_thread")]
+async fn h2_window_update_flood_closes_connection() {
+    use io::{AsyncReadExt, AsyncWriteExt};
+
+    let _trace = linkerd_tracing::test::trace_init();
+
+    const MAX_WINDOW_UPDATES_PER_SECOND: u32 = 100;
+    let inner = |_: ClientHandle| {
+        service_fn(|_: http::Request<UpgradeBody>| {
+            future::ok::<_, Error>(http::Response::new(BoxBody::default()))
+        })
+    };
+    let (_drain_tx, drain) = drain::channel();
+    let h2 = H2Settings {
+        max_window_updates_per_second: Some(MAX_WINDOW_UPDATES_PER_SECOND),
+        ..Default::default()
+    };
+    let metrics = ServerMetrics::default();
+    let mut serve = layer::Layer::layer(
+        &NewServeHttp::layer(H1Settings::default(), h2, metrics.clone(), drain),
+        move |_: Version| inner,
+    )
+    .new_service(Version::H2);
+
+    let (mut client_io, server_io) = io::duplex(64 * 1024);
+    let server = tokio::spawn(serve.call(server_io));
+
+    // Send the connection preface and an empty SETTINGS frame, followed by a
+    // stream of connection-level WINDOW_UPDATE frames that exceeds the rate.
+    let mut frames = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+    frames.extend_from_slice(&[0, 0, 0, 0x4, 0, 0, 0, 0, 0]);
+    for _ in 0..=MAX_WINDOW_UPDATES_PER_SECOND {
+        frames.extend_from_slice(&[0, 0, 0x4, 0x8, 0, 0, 0, 0, 0]);
+        frames.extend_from_slice(&1u32.to_be_bytes());
+    }
+    client_io.write_all(&frames).await.unwrap();
+
+    // The server closes the connection.
+    let mut buf = Vec::new();
+    let _ = client_io.read_to_end(&mut buf).await;
+    server
+        .await
+        .unwrap()
+        .expect_err("server connection must fail");
+    assert_eq!(metrics.h2_window_update_flood_closes(), 1);
+}
+
+/// Tests that pipelined HTTP/1 requests are served in order by default.
+#[tokio::test(flavor = "current_thread")]
+async fn http1_serializes_pipelined_requests() {
+    use io::AsyncWriteExt;
+
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (mut client, _drain, server) = serve_pipelined(Pipelining::Serialize);
+    client
+        .write_all(b"GET /a HTTP/1.1\r\nhost: example.com\r\n\r\nGET /b HTTP/1.1\r\nhost: example.com\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+
+    let rsps = read_to_close(&mut client).await;
+    let (a, b) = (rsps.find("\r\n\r\n/a"), rsps.find("\r\n\r\n/b"));
+    assert!(a.is_some() && b.is_some() && a < b, "{}", rsps);
+    assert_eq!(rsps.matches("HTTP/1.1 200 OK").count(), 2, "{}", rsps);
+    server
+        .await
+        .unwrap()
+        .expect("server connection must close cleanly");
+}
+
+/// Tests that, when pipelining is rejected, pipelined requests fail with
+/// `Connection: close` and that the connection is then closed.
+#[tokio::test(flavor = "current_thread")]
+async fn http1_rejects_pipelined_requests() {
+    use io::AsyncWriteExt;
+
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (mut client, _drain, server) = serve_pipelined(Pipelining::Reject);
+    client
+        .write_all(b"GET /a HTTP/1.1\r\nhost: example.com\r\n\r\nGET /b HTTP/1.1\r\nhost: example.com\r\n\r\nGET /c HTTP/1.1\r\nhost: example.com\r\n\r\n")
+        .await
+        .unwrap();
+
+    // The first request is served, and the connection is closed after the
+    // second request is rejected.
+    let rsps = read_to_close(&mut client).await;
+    // A response's status line directly follows the prior response's body.
+    let mut lines = rsps
+        .split("\r\n")
+        .filter_map(|l| l.find("HTTP/1.1").map(|i| &l[i..]));
+    assert_eq!(lines.next(), Some("HTTP/1.1 200 OK"), "{}", rsps);
+    assert_eq!(
+        lines.next(),
+        Some("HTTP/1.1 503 Service Unavailable"),
+        "{}",
+        rsps
+    );
+    assert_eq!(lines.next(), None, "{}", rsps);
+    assert!(rsps.contains("\r\n\r\n/a"), "{}", rsps);
+    assert!(rsps.contains("connection: close"), "{}", rsps);
+    server
+        .await
+        .unwrap()
+        .expect("server connection must close cleanly");
+}
+
+/// Tests that, when pipelining is rejected, requests that are sent after the
+/// prior response is received are served.
+#[tokio::test(flavor = "current_thread")]
+async fn http1_reject_pipelining_serves_sequential_requests() {
+    use io::{AsyncReadExt, AsyncWriteExt};
+
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (mut client, _drain, server) = serve_pipelined(Pipelining::Reject);
+    client
+        .write_all(b"GET /a HTTP/1.1\r\nhost: example.com\r\n\r\n")
+        .await
+        .unwrap();
+    let mut rsp = Vec::new();
+    while !rsp.ends_with(b"\r\n\r\n/a") {
+        let mut buf = [0; 1024];
+        let n = client.read(&mut buf).await.unwrap();
+        assert_ne!(n, 0, "connection must not close");
+        rsp.extend_from_slice(&buf[..n]);
+    }
+    assert!(rsp.starts_with(b"HTTP/1.1 200 OK"));
+
+    client
+        .write_all(b"GET /b HTTP/1.1\r\nhost: example.com\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let rsp = read_to_close(&mut client).await;
+    assert!(rsp.starts_with("HTTP/1.1 200 OK"), "{}", rsp);
+    assert!(rsp.ends_with("\r\n\r\n/b"), "{}", rsp);
+    server
+        .await
+        .unwrap()
+        .expect("server connection must close cleanly");
+}
+
+/// Tests that, when parse errors abort connections, a connection on which the
+/// client sends bytes that are not HTTP after a valid request is closed
+/// cleanly and counted.
+#[tokio::test(flavor = "current_thread")]
+async fn http1_parse_error_aborts_connection() {
+    use io::AsyncWriteExt;
+
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let metrics = ServerMetrics::default();
+    let h1 = H1Settings {
+        parse_errors: ParseErrors::Abort,
+        ..Default::default()
+    };
+    let (mut client, _drain, server) = serve_http1(h1, metrics.clone());
+    client
+        .write_all(b"GET /a HTTP/1.1\r\nhost: example.com\r\n\r\n\x16\x03\x01\x02\x00\x01\x00\x01\xfc\x03\x03\r\n\r\n")
+        .await
+        .unwrap();
+
+    // The valid request is served before the connection is closed.
+    let rsps = read_to_close(&mut client).await;
+    assert!(rsps.starts_with("HTTP/1.1 200 OK"), "{}", rsps);
+    assert!(rsps.contains("\r\n\r\n/a"), "{}", rsps);
+    server
+        .await
+        .unwrap()
+        .expect("server connection must close cleanly");
+    assert_eq!(metrics.http1_parse_errors(), 1);
+}
+
+/// Serves an HTTP/1 connection that handles pipelined requests as configured.
+fn serve_pipelined(
+    pipelining: Pipelining,
+) -> (
+    io::DuplexStream,
+    drain::Signal,
+    tokio::task::JoinHandle<Result<(), Error>>,
+) {
+    serve_http1(
+        H1Settings {
+            pipelining,
+            ..Default::default()
+        },
+        ServerMetrics::default(),
+    )
+}
+
+/// Serves an HTTP/1 connection whose responses' bodies are their requests'
+/// paths.
+fn serve_http1(
+    h1: H1Settings,
+    metrics: ServerMetrics,
+) -> (
+    io::DuplexStream,
+    drain::Signal,
+    tokio::task::JoinHandle<Result<(), Error>>,
+) {
+    let inner = |_: ClientHandle| {
+        service_fn(|req: http::Request<UpgradeBody>| {
+            let body = hyper::Body::from(req.uri().path().to_string());
+            future::ok::<_, Error>(http::Response::new(BoxBody::new(body)))
+        })
+    };
+    let (drain_tx, drain) = drain::channel();
+    let mut serve = layer::Layer::layer(
+        &NewServeHttp::layer(h1, H2Settings::default(), metrics, drain),
+        move |_: Version| inner,
+    )
+    .new_service(Version::Http1);
+
+    let (client_io, server_io) = io::duplex(4096);
+    let server = tokio::spawn(serve.call(server_io));
+    (client_io, drain_tx, server)
+}
+
+async fn read_to_close(io: &mut io::DuplexStream) -> String {
+    use io::AsyncReadExt;
+
+    let mut buf = String::new();
+    io.read_to_string(&mut buf).await.unwrap();
+    buf
+}
+
+fn req(path: &str) -> http::Request<hyper::Body> {
+    http::Request::builder()
+        .uri(path)
+        .header(http::header::HOST, "example.com")
+        .body(hyper::Body::empty())
+        .unwrap()
+}
@@ -10,6 +10,12 @@ pub struct ResponseTimeout(pub Option<Duration>);
 #[error("HTTP response timeout after {0:?}")]
 pub struct ResponseTimeoutError(Duration);
 
+impl ResponseTimeoutError {
+    pub fn new(timeout: Duration) -> Self {
+        Self(timeout)
+    }
+}
+
 /// An HTTP-specific optional timeout layer.
 ///
 /// The stack target must implement `HasTimeout`, and if a duration is
@@ -7,6 +7,7 @@ use futures::{
 };
 use hyper::upgrade::OnUpgrade;
 use linkerd_duplex::Duplex;
+use linkerd_io::{self as io, EitherIo};
 use std::fmt;
 use std::mem;
 use std::sync::Arc;
@@ -45,10 +46,22 @@ pub struct HttpConnect;
 
 struct Inner {
     server: TryLock<Option<OnUpgrade>>,
-    client: TryLock<Option<OnUpgrade>>,
+    client: TryLock<Option<ClientUpgrade>>,
     upgrade_drain_signal: Option<drain::Watch>,
 }
 
+/// The client half of an upgrade is either an upgraded HTTP client connection
+/// or a connection established directly by the proxy (e.g. to tunnel a
+/// `CONNECT` request).
+enum ClientUpgrade {
+    Http(OnUpgrade),
+    Tunnel(Box<dyn TunnelIo>),
+}
+
+trait TunnelIo: io::AsyncRead + io::AsyncWrite + Send + Unpin {}
+
+impl<I> TunnelIo for I where I: io::AsyncRead + io::AsyncWrite + Send + Unpin {}
+
 #[derive(Debug)]
 enum Half {
     Server,
@@ -106,10 +119,30 @@ impl Http11Upgrade {
                     .try_lock()
                     .expect("only Half::Client touches client TryLock");
                 debug_assert!(lock.is_none());
-                *lock = Some(upgrade);
+                *lock = Some(ClientUpgrade::Http(upgrade));
             }
         }
     }
+
+    /// Completes the client half of the upgrade with a connection established
+    /// by the proxy, so that the server's upgraded connection is tunneled to
+    /// it.
+    pub fn insert_tunnel<I>(self, io: I)
+    where
+        I: io::AsyncRead + io::AsyncWrite + Send + Unpin + 'static,
+    {
+        debug_assert!(
+            matches!(self.half, Half::Client),
+            "only the client half may be tunneled"
+        );
+        let mut lock = self
+            .inner
+            .client
+            .try_lock()
+            .expect("only Half::Client touches client TryLock");
+        debug_assert!(lock.is_none());
+        *lock = Some(ClientUpgrade::Tunnel(Box::new(io)));
+    }
 }
 
 impl fmt::Debug for Http11Upgrade {
@@ -133,7 +166,15 @@ impl Drop for Inner {
 
             let server_upgrade = server.map_err(|e| debug!("server HTTP upgrade error: {}", e));
 
-            let client_upgrade = client.map_err(|e| debug!("client HTTP upgrade error: {}", e));
+            let client_upgrade = async move {
+                match client {
+                    ClientUpgrade::Http(upgrade) => upgrade
+                        .await
+                        .map(EitherIo::Left)
+                        .map_err(|e| debug!("client HTTP upgrade error: {}", e)),
+                    ClientUpgrade::Tunnel(io) => Ok(EitherIo::Right(io)),
+                }
+            };
 
             let both_upgrades = async move {
                 let (server_conn, client_conn) = tokio::try_join!(server_upgrade, client_upgrade)?;
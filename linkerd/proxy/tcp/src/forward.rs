@@ -6,6 +6,7 @@ use std::{
     future::Future,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 use tokio::io::{AsyncRead, AsyncWrite};
 use tower::Service;
@@ -13,15 +14,29 @@ use tower::Service;
 #[derive(Clone, Debug)]
 pub struct Forward<C> {
     connect: C,
+    buffer_capacity: usize,
+    idle_timeout: Option<Duration>,
 }
 
 impl<C> Forward<C> {
-    fn new(connect: C) -> Self {
-        Self { connect }
+    fn new(connect: C, buffer_capacity: usize, idle_timeout: Option<Duration>) -> Self {
+        Self {
+            connect,
+            buffer_capacity,
+            idle_timeout,
+        }
     }
 
-    pub fn layer() -> impl layer::Layer<C, Service = Self> + Clone + Copy {
-        layer::mk(Self::new)
+    /// Forwards connections, copying data in each direction through buffers
+    /// of `buffer_capacity` bytes.
+    ///
+    /// If an `idle_timeout` is set, connections on which no data is read in
+    /// either direction for the timeout are closed.
+    pub fn layer(
+        buffer_capacity: usize,
+        idle_timeout: Option<Duration>,
+    ) -> impl layer::Layer<C, Service = Self> + Clone + Copy {
+        layer::mk(move |connect| Self::new(connect, buffer_capacity, idle_timeout))
     }
 }
 
@@ -43,11 +58,20 @@ where
     }
 
     fn call(&mut self, src_io: I) -> Self::Future {
+        let capacity = self.buffer_capacity;
+        let idle_timeout = self.idle_timeout;
         Box::pin(
             self.connect
                 .call(())
                 .err_into::<Error>()
-                .and_then(|dst_io| Duplex::new(src_io, dst_io).err_into::<Error>()),
+                .and_then(move |dst_io| {
+                    let duplex = Duplex::with_buffer_capacity(src_io, dst_io, capacity);
+                    match idle_timeout {
+                        Some(timeout) => duplex.with_idle_timeout(timeout),
+                        None => duplex,
+                    }
+                    .err_into::<Error>()
+                }),
         )
     }
 }
@@ -1,22 +1,56 @@
-use crate::{ClientAddr, Keepalive, Local, Remote, ServerAddr};
+use crate::{ClientAddr, Keepalive, KeepaliveProbes, Local, Remote, ServerAddr};
+use ipnet::IpNet;
 use linkerd_io as io;
 use linkerd_stack::{Param, Service};
 use std::{
     future::Future,
+    net::{IpAddr, SocketAddr},
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
-use tokio::net::TcpStream;
+use tokio::net::{TcpSocket, TcpStream};
 use tracing::debug;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct ConnectTcp {
     keepalive: Keepalive,
+    keepalive_probes: Option<KeepaliveProbes>,
+    source_addrs: SourceAddrs,
 }
 
+/// Configures the local addresses to which connections are bound, by the
+/// network of the server to which they connect.
+///
+/// Connections to servers that are not in any of the configured networks are
+/// bound to an address chosen by the operating system.
+#[derive(Clone, Debug)]
+pub struct SourceAddrs(Arc<[(IpNet, IpAddr)]>);
+
 impl ConnectTcp {
     pub fn new(keepalive: Keepalive) -> Self {
-        Self { keepalive }
+        Self {
+            keepalive,
+            keepalive_probes: None,
+            source_addrs: SourceAddrs::default(),
+        }
+    }
+
+    /// Configures keepalive probes on connections, overriding the keepalive
+    /// time.
+    pub fn with_keepalive_probes(self, keepalive_probes: KeepaliveProbes) -> Self {
+        Self {
+            keepalive_probes: Some(keepalive_probes),
+            ..self
+        }
+    }
+
+    /// Binds connections to the configured source addresses.
+    pub fn with_source_addrs(self, source_addrs: SourceAddrs) -> Self {
+        Self {
+            source_addrs,
+            ..self
+        }
     }
 }
 
@@ -31,19 +65,179 @@ impl<T: Param<Remote<ServerAddr>>> Service<T> for ConnectTcp {
 
     fn call(&mut self, t: T) -> Self::Future {
         let Keepalive(keepalive) = self.keepalive;
+        let keepalive_probes = self.keepalive_probes;
         let Remote(ServerAddr(addr)) = t.param();
-        debug!(server.addr = %addr, "Connecting");
+        let source_addr = self.source_addrs.select(addr);
+        debug!(server.addr = %addr, source.addr = ?source_addr, "Connecting");
         Box::pin(async move {
-            let io = TcpStream::connect(&addr).await?;
+            let io = match source_addr {
+                Some(ip) => {
+                    let socket = match ip {
+                        IpAddr::V4(_) => TcpSocket::new_v4()?,
+                        IpAddr::V6(_) => TcpSocket::new_v6()?,
+                    };
+                    socket.bind(SocketAddr::new(ip, 0))?;
+                    socket.connect(addr).await?
+                }
+                None => TcpStream::connect(&addr).await?,
+            };
             super::set_nodelay_or_warn(&io);
-            let io = super::set_keepalive_or_warn(io, keepalive)?;
+            let io = match keepalive_probes {
+                Some(probes) => super::set_keepalive_probes_or_warn(io, probes)?,
+                None => super::set_keepalive_or_warn(io, keepalive)?,
+            };
             let local_addr = io.local_addr()?;
             debug!(
                 local.addr = %local_addr,
                 ?keepalive,
+                ?keepalive_probes,
                 "Connected",
             );
             Ok((io::ScopedIo::client(io), Local(ClientAddr(local_addr))))
         })
     }
 }
+
+/// Returns true if a connect failed because the server's network or host is
+/// unreachable--i.e. because there is no route to it.
+pub fn is_unreachable(error: &io::Error) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        matches!(
+            error.raw_os_error(),
+            Some(libc::ENETUNREACH | libc::EHOSTUNREACH)
+        )
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = error;
+        false
+    }
+}
+
+/// Returns true if a connection failed because its peer stopped responding,
+/// e.g. to TCP keepalive probes.
+pub fn is_keepalive_timeout(error: &io::Error) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        error.raw_os_error() == Some(libc::ETIMEDOUT)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = error;
+        false
+    }
+}
+
+// === impl SourceAddrs ===
+
+impl Default for SourceAddrs {
+    fn default() -> Self {
+        Self(Arc::new([]))
+    }
+}
+
+impl SourceAddrs {
+    pub fn new(addrs: impl IntoIterator<Item = (IpNet, IpAddr)>) -> Self {
+        Self(addrs.into_iter().collect())
+    }
+
+    /// Returns the source address of the first network that contains the
+    /// server's address, ignoring source addresses of the other IP family.
+    fn select(&self, server: SocketAddr) -> Option<IpAddr> {
+        let server = server.ip();
+        self.0
+            .iter()
+            .find(|(net, source)| net.contains(&server) && source.is_ipv4() == server.is_ipv4())
+            .map(|(_, source)| *source)
+    }
+}
+
+impl FromIterator<(IpNet, IpAddr)> for SourceAddrs {
+    fn from_iter<I: IntoIterator<Item = (IpNet, IpAddr)>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_stack::ServiceExt;
+    use tokio::net::TcpListener;
+
+    #[derive(Clone, Debug)]
+    struct Target(SocketAddr);
+
+    impl Param<Remote<ServerAddr>> for Target {
+        fn param(&self) -> Remote<ServerAddr> {
+            Remote(ServerAddr(self.0))
+        }
+    }
+
+    #[test]
+    fn selects_source_addrs_by_network() {
+        let v4 = IpAddr::from([192, 0, 2, 10]);
+        let v6 = "2001:db8::10".parse::<IpAddr>().unwrap();
+        let addrs = SourceAddrs::new([
+            ("10.0.0.0/8".parse().unwrap(), v4),
+            ("::/0".parse().unwrap(), v6),
+            ("0.0.0.0/0".parse().unwrap(), v6),
+        ]);
+        assert_eq!(addrs.select(([10, 1, 2, 3], 80).into()), Some(v4));
+        assert_eq!(addrs.select("[2001:db8::1]:80".parse().unwrap()), Some(v6));
+        // Sources of the wrong IP family are ignored.
+        assert_eq!(addrs.select(([172, 16, 0, 1], 80).into()), None);
+    }
+
+    // Linux routes all of 127.0.0.0/8 to the loopback interface, so a
+    // connection may be bound to a loopback address other than 127.0.0.1.
+    #[cfg(target_os = "linux")]
+    #[tokio::test(flavor = "current_thread")]
+    async fn binds_to_source_addr() {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .await
+            .unwrap();
+        let server_addr = listener.local_addr().unwrap();
+        let source = IpAddr::from([127, 0, 0, 2]);
+
+        let connect = ConnectTcp::new(Keepalive(None))
+            .with_source_addrs(SourceAddrs::new([("127.0.0.0/8".parse().unwrap(), source)]));
+        let (_io, Local(ClientAddr(local_addr))) = connect
+            .oneshot(Target(server_addr))
+            .await
+            .expect("must connect");
+        assert_eq!(local_addr.ip(), source);
+
+        let (_, peer_addr) = listener.accept().await.unwrap();
+        assert_eq!(peer_addr, local_addr);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test(flavor = "current_thread")]
+    async fn sets_keepalive_probes() {
+        use std::time::Duration;
+
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .await
+            .unwrap();
+        let server_addr = listener.local_addr().unwrap();
+
+        let connect = ConnectTcp::new(Keepalive(None)).with_keepalive_probes(KeepaliveProbes {
+            time: Duration::from_secs(30),
+            interval: Some(Duration::from_secs(5)),
+            retries: Some(3),
+        });
+        let (io, _) = connect
+            .oneshot(Target(server_addr))
+            .await
+            .expect("must connect");
+
+        let sock = socket2::SockRef::from(io.get_ref());
+        assert!(sock.keepalive().unwrap());
+        assert_eq!(sock.keepalive_time().unwrap(), Duration::from_secs(30));
+        assert_eq!(sock.keepalive_interval().unwrap(), Duration::from_secs(5));
+        assert_eq!(sock.keepalive_retries().unwrap(), 3);
+    }
+}
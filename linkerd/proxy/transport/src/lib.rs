@@ -16,7 +16,7 @@ pub mod orig_dst;
 
 pub use self::{
     addrs::{ClientAddr, ListenAddr, Local, OrigDstAddr, Remote, ServerAddr},
-    connect::ConnectTcp,
+    connect::{is_keepalive_timeout, is_unreachable, ConnectTcp, SourceAddrs},
     listen::{Bind, BindTcp},
     orig_dst::BindWithOrigDst,
 };
@@ -28,6 +28,22 @@ use tokio::net::TcpStream;
 #[derive(Copy, Clone, Debug, Default)]
 pub struct Keepalive(pub Option<Duration>);
 
+/// Configures TCP keepalive probes so that connections to peers that stop
+/// responding are closed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct KeepaliveProbes {
+    /// The amount of time a connection may be idle before probes are sent.
+    pub time: Duration,
+
+    /// The interval between unacknowledged probes. When unset, the operating
+    /// system's default is used.
+    pub interval: Option<Duration>,
+
+    /// The number of unacknowledged probes after which the connection is
+    /// closed. When unset, the operating system's default is used.
+    pub retries: Option<u32>,
+}
+
 impl From<Keepalive> for Option<Duration> {
     fn from(Keepalive(duration): Keepalive) -> Option<Duration> {
         duration
@@ -46,14 +62,35 @@ fn set_keepalive_or_warn(
     tcp: TcpStream,
     keepalive_duration: Option<Duration>,
 ) -> io::Result<TcpStream> {
+    let ka = keepalive_duration
+        .into_iter()
+        .fold(TcpKeepalive::new(), |k, t| k.with_time(t));
+    set_tcp_keepalive_or_warn(tcp, &ka)
+}
+
+/// Probe intervals and retries are only configured on Linux.
+fn set_keepalive_probes_or_warn(tcp: TcpStream, probes: KeepaliveProbes) -> io::Result<TcpStream> {
+    let ka = TcpKeepalive::new().with_time(probes.time);
+    #[cfg(target_os = "linux")]
+    let ka = {
+        let ka = probes
+            .interval
+            .into_iter()
+            .fold(ka, |k, i| k.with_interval(i));
+        probes
+            .retries
+            .into_iter()
+            .fold(ka, |k, r| k.with_retries(r))
+    };
+    set_tcp_keepalive_or_warn(tcp, &ka)
+}
+
+fn set_tcp_keepalive_or_warn(tcp: TcpStream, ka: &TcpKeepalive) -> io::Result<TcpStream> {
     let sock = {
         let stream = tokio::net::TcpStream::into_std(tcp)?;
         socket2::Socket::from(stream)
     };
-    let ka = keepalive_duration
-        .into_iter()
-        .fold(TcpKeepalive::new(), |k, t| k.with_time(t));
-    if let Err(e) = sock.set_tcp_keepalive(&ka) {
+    if let Err(e) = sock.set_tcp_keepalive(ka) {
         tracing::warn!("failed to set keepalive: {}", e);
     }
     let stream: std::net::TcpStream = socket2::Socket::into(sock);
@@ -8,13 +8,18 @@ mod tests;
 use futures::{future, prelude::*, ready};
 use linkerd_error::{Error, Recover};
 use linkerd_stack::{layer, NewService, Service};
-use std::task::{Context, Poll};
+use std::{
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::Instant;
 use tracing::{debug, trace, warn};
 
 #[derive(Clone, Debug)]
 pub struct NewReconnect<R, N> {
     recover: R,
     inner: N,
+    max_elapsed: Option<Duration>,
 }
 
 #[derive(Debug)]
@@ -27,8 +32,22 @@ where
     recover: R,
     inner: N,
     state: State<R::Backoff, N::Service>,
+
+    /// The maximum amount of time the service may spend disconnected (i.e.
+    /// backing off and reconnecting) before it fails.
+    max_elapsed: Option<Duration>,
+
+    /// The time at which the service first failed, if it has not since
+    /// reconnected.
+    disconnected_at: Option<Instant>,
 }
 
+/// Indicates that a service failed to reconnect within its maximum backoff
+/// time.
+#[derive(Debug, thiserror::Error)]
+#[error("failed to reconnect after {0:?}")]
+pub struct ReconnectTimeout(Duration);
+
 #[derive(Debug)]
 enum State<B, S> {
     Disconnected {
@@ -45,11 +64,29 @@ enum State<B, S> {
 
 impl<R: Clone, N> NewReconnect<R, N> {
     pub fn new(recover: R, inner: N) -> Self {
-        Self { inner, recover }
+        Self {
+            inner,
+            recover,
+            max_elapsed: None,
+        }
     }
 
     pub fn layer(recover: R) -> impl layer::Layer<N, Service = Self> + Clone {
-        layer::mk(move |inner| Self::new(recover.clone(), inner))
+        Self::layer_with_max_elapsed(recover, None)
+    }
+
+    /// Returns a layer whose services fail once they have been unable to
+    /// reconnect for `max_elapsed`, if it is set, so that callers (such as load
+    /// balancers) may stop using them.
+    pub fn layer_with_max_elapsed(
+        recover: R,
+        max_elapsed: Option<Duration>,
+    ) -> impl layer::Layer<N, Service = Self> + Clone {
+        layer::mk(move |inner| Self {
+            inner,
+            recover: recover.clone(),
+            max_elapsed,
+        })
     }
 }
 
@@ -62,6 +99,7 @@ where
 
     fn new_service(&self, target: T) -> Self::Service {
         Reconnect::new(target, self.inner.clone(), self.recover.clone())
+            .with_max_elapsed(self.max_elapsed)
     }
 }
 
@@ -78,8 +116,36 @@ where
             inner,
             recover,
             state: State::Disconnected { backoff: None },
+            max_elapsed: None,
+            disconnected_at: None,
+        }
+    }
+
+    /// Fails the service once it has been unable to reconnect for
+    /// `max_elapsed`, if it is set.
+    pub fn with_max_elapsed(self, max_elapsed: Option<Duration>) -> Self {
+        Self {
+            max_elapsed,
+            ..self
+        }
+    }
+}
+
+/// Fails if the service has been disconnected for longer than the maximum
+/// allowed time. If the service has never connected, the disconnection time is
+/// recorded on its first failure.
+fn check_elapsed(
+    max_elapsed: Option<Duration>,
+    disconnected_at: &mut Option<Instant>,
+) -> Result<(), Error> {
+    if let Some(max) = max_elapsed {
+        let now = Instant::now();
+        let since = *disconnected_at.get_or_insert(now);
+        if now.saturating_duration_since(since) >= max {
+            return Err(ReconnectTimeout(max).into());
         }
     }
+    Ok(())
 }
 
 impl<T, Req, R, N, S> Service<Req> for Reconnect<T, R, N>
@@ -110,6 +176,7 @@ where
                         let error: Error = e.into();
                         warn!(error, "Service failed");
                         let backoff = self.recover.recover(error)?;
+                        self.disconnected_at = Some(Instant::now());
                         debug!("Recovering");
                         State::Disconnected {
                             backoff: Some(backoff),
@@ -126,6 +193,7 @@ where
                 } => match ready!(service.as_mut().unwrap().poll_ready(cx)) {
                     Ok(()) => {
                         debug!("Connected");
+                        self.disconnected_at = None;
                         State::Connected(service.take().unwrap())
                     }
                     Err(e) => {
@@ -134,6 +202,7 @@ where
                         let error: Error = e.into();
                         warn!(error, "Failed to connect");
                         let new_backoff = self.recover.recover(error)?;
+                        check_elapsed(self.max_elapsed, &mut self.disconnected_at)?;
                         debug!("Recovering");
                         State::Disconnected {
                             backoff: Some(backoff.take().unwrap_or(new_backoff)),
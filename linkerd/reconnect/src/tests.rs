@@ -148,3 +148,49 @@ async fn backoff() {
     assert_ready!(service.poll_ready()).unwrap();
     assert_eq!(news.load(Ordering::SeqCst), 4);
 }
+
+/// Tests that the reconnect layer fails once the service has been unable to
+/// reconnect for the maximum backoff time.
+#[tokio::test]
+async fn max_elapsed() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (service, mut handle) = mock::pair::<String, String>();
+
+    let mut service = Spawn::new(
+        Reconnect::new(
+            (),
+            move |()| service.clone(),
+            |_: Error| {
+                let backoff = time::interval_at(
+                    time::Instant::now() + time::Duration::from_secs(1),
+                    time::Duration::from_secs(1),
+                );
+                Ok(tokio_stream::wrappers::IntervalStream::new(backoff).map(|_| ()))
+            },
+        )
+        .with_max_elapsed(Some(time::Duration::from_secs(3))),
+    );
+
+    time::pause();
+
+    // Connect and then fail.
+    handle.allow(1);
+    assert_ready!(service.poll_ready()).unwrap();
+    handle.send_error("dead");
+    assert_pending!(service.poll_ready());
+
+    // Reconnects continue to fail within the ceiling.
+    for _ in 0..2 {
+        time::sleep(time::Duration::from_secs(1)).await;
+        handle.send_error("dead");
+        assert_pending!(service.poll_ready());
+    }
+
+    // Once the ceiling is reached, the service fails rather than backing off
+    // again.
+    time::sleep(time::Duration::from_secs(1)).await;
+    handle.send_error("dead");
+    let error = assert_ready!(service.poll_ready()).unwrap_err();
+    assert!(error.is::<ReconnectTimeout>(), "{}", error);
+}
@@ -0,0 +1,125 @@
+//! A retry budget that tracks its utilization.
+
+use parking_lot::Mutex;
+use std::{collections::VecDeque, fmt, time::Duration};
+use tokio::time::Instant;
+use tower::retry::budget::{self, Overdrawn};
+
+#[cfg(test)]
+mod tests;
+
+/// Limits the number of retries to a fraction of requests, plus a minimum
+/// number of retries per second, over a rolling window.
+///
+/// Unlike [`tower::retry::budget::Budget`], which this wraps, the budget
+/// reports how much of its allowance has been used over its window, so that
+/// the utilization can be exposed as a metric.
+pub struct Budget {
+    inner: budget::Budget,
+    ttl: Duration,
+    min_per_sec: u32,
+    retry_percent: f32,
+    buckets: Mutex<VecDeque<Bucket>>,
+}
+
+/// The number of buckets into which the budget's window is divided. Buckets
+/// expire whole, so the window advances in increments of `ttl / BUCKETS`.
+const BUCKETS: u32 = 10;
+
+#[derive(Debug)]
+struct Bucket {
+    start: Instant,
+    deposits: u64,
+    withdrawals: u64,
+}
+
+// === impl Budget ===
+
+impl Budget {
+    /// See [`tower::retry::budget::Budget::new`].
+    pub fn new(ttl: Duration, min_per_sec: u32, retry_percent: f32) -> Self {
+        Self {
+            inner: budget::Budget::new(ttl, min_per_sec, retry_percent),
+            ttl,
+            min_per_sec,
+            retry_percent,
+            buckets: Mutex::new(VecDeque::with_capacity(BUCKETS as usize + 1)),
+        }
+    }
+
+    /// Records a request that was not retried.
+    pub fn deposit(&self) {
+        self.inner.deposit();
+        self.bucket(|b| b.deposits += 1);
+    }
+
+    /// Withdraws a retry from the budget, failing if the budget is exhausted.
+    pub fn withdraw(&self) -> Result<(), Overdrawn> {
+        self.inner.withdraw()?;
+        self.bucket(|b| b.withdrawals += 1);
+        Ok(())
+    }
+
+    /// Returns the proportion of the budget's allowance, between 0 and 1, that
+    /// was withdrawn over its window.
+    ///
+    /// The allowance is estimated from the requests deposited in the window,
+    /// so this approximates the inner budget's own accounting.
+    pub fn utilization(&self) -> f64 {
+        let mut buckets = self.buckets.lock();
+        self.expire(&mut buckets, Instant::now());
+
+        let (deposits, withdrawals) = buckets
+            .iter()
+            .fold((0, 0), |(d, w), b| (d + b.deposits, w + b.withdrawals));
+        if withdrawals == 0 {
+            return 0.0;
+        }
+        let reserve = f64::from(self.min_per_sec) * self.ttl.as_secs_f64();
+        let allowance = reserve + f64::from(self.retry_percent) * deposits as f64;
+        if allowance <= 0.0 {
+            return 1.0;
+        }
+        (withdrawals as f64 / allowance).min(1.0)
+    }
+
+    fn bucket(&self, f: impl FnOnce(&mut Bucket)) {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock();
+        self.expire(&mut buckets, now);
+
+        let width = self.ttl / BUCKETS;
+        let is_stale = buckets
+            .back()
+            .map(|b| now.saturating_duration_since(b.start) >= width)
+            .unwrap_or(true);
+        if is_stale {
+            buckets.push_back(Bucket {
+                start: now,
+                deposits: 0,
+                withdrawals: 0,
+            });
+        }
+        f(buckets.back_mut().expect("bucket must be present"));
+    }
+
+    fn expire(&self, buckets: &mut VecDeque<Bucket>, now: Instant) {
+        while let Some(bucket) = buckets.front() {
+            if now.saturating_duration_since(bucket.start) < self.ttl {
+                break;
+            }
+            buckets.pop_front();
+        }
+    }
+}
+
+impl fmt::Debug for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Budget")
+            .field("inner", &self.inner)
+            .field("ttl", &self.ttl)
+            .field("min_per_sec", &self.min_per_sec)
+            .field("retry_percent", &self.retry_percent)
+            .finish()
+    }
+}
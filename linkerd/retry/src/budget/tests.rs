@@ -0,0 +1,55 @@
+use super::*;
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn utilization_increases_with_retries() {
+    // Allows 10 retries per 10s, plus one for every two requests.
+    let budget = Budget::new(Duration::from_secs(10), 1, 0.5);
+    assert_eq!(budget.utilization(), 0.0);
+
+    for _ in 0..4 {
+        budget.deposit();
+    }
+    let mut utilizations = Vec::new();
+    for _ in 0..6 {
+        budget.withdraw().expect("budget must not be exhausted");
+        budget.withdraw().expect("budget must not be exhausted");
+        utilizations.push(budget.utilization());
+    }
+    assert_eq!(
+        utilizations,
+        [
+            2.0 / 12.0,
+            4.0 / 12.0,
+            6.0 / 12.0,
+            8.0 / 12.0,
+            10.0 / 12.0,
+            1.0
+        ]
+    );
+
+    // The budget is exhausted.
+    assert!(budget.withdraw().is_err());
+    assert_eq!(budget.utilization(), 1.0);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn utilization_resets_as_budget_refills() {
+    let budget = Budget::new(Duration::from_secs(10), 1, 0.0);
+    for _ in 0..5 {
+        budget.withdraw().expect("budget must not be exhausted");
+    }
+    assert_eq!(budget.utilization(), 0.5);
+
+    // Withdrawals expire from the window in buckets.
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    for _ in 0..2 {
+        budget.withdraw().expect("budget must not be exhausted");
+    }
+    assert_eq!(budget.utilization(), 0.7);
+
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    assert_eq!(budget.utilization(), 0.2);
+
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    assert_eq!(budget.utilization(), 0.0);
+}
@@ -13,9 +13,13 @@ use std::{
     future::Future,
     task::{Context, Poll},
 };
-pub use tower::retry::{budget::Budget, Policy};
+pub use tower::retry::Policy;
 use tracing::trace;
 
+mod budget;
+
+pub use self::budget::Budget;
+
 /// A strategy for obtaining per-target retry polices.
 pub trait NewPolicy<T> {
     type Policy;
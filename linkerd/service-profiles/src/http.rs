@@ -1,5 +1,6 @@
 mod proxy;
 
+use linkerd_retry::Budget;
 use std::{
     fmt,
     hash::{Hash, Hasher},
@@ -7,7 +8,6 @@ use std::{
     sync::Arc,
     time::Duration,
 };
-use tower::retry::budget::Budget;
 
 pub use self::proxy::NewProxyRouter;
 
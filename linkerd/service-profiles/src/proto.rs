@@ -3,9 +3,9 @@ use linkerd2_proxy_api::destination as api;
 use linkerd_addr::NameAddr;
 use linkerd_dns_name::Name;
 use linkerd_proxy_api_resolve::pb as resolve;
+use linkerd_retry::Budget;
 use regex::Regex;
 use std::{str::FromStr, sync::Arc, time::Duration};
-use tower::retry::budget::Budget;
 use tracing::warn;
 
 pub(super) fn convert_profile(proto: api::DestinationProfile, port: u16) -> Profile {
@@ -1,6 +1,7 @@
 use crate::NegotiatedProtocol;
 use futures::prelude::*;
 use linkerd_conditional::Conditional;
+use linkerd_error::Error;
 use linkerd_identity as id;
 use linkerd_io as io;
 use linkerd_stack::{layer, MakeConnection, NewService, Oneshot, Param, Service, ServiceExt};
@@ -11,9 +12,20 @@ use std::{
     pin::Pin,
     str::FromStr,
     task::{Context, Poll},
+    time::Duration,
 };
+use thiserror::Error;
+use tokio::time;
 use tracing::debug;
 
+mod pinned;
+mod shadow;
+
+pub use self::{
+    pinned::{Fingerprint, InvalidFingerprint, PinnedCerts},
+    shadow::{NewShadowClient, Shadow, ShadowServers},
+};
+
 /// A newtype for target server identities.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct ServerId(pub id::Name);
@@ -54,15 +66,27 @@ pub type ConditionalClientTls = Conditional<ClientTls, NoClientTls>;
 pub struct Client<L, C> {
     identity: L,
     inner: C,
+    handshake_timeout: Option<Duration>,
 }
 
+#[derive(Clone, Debug, Error)]
+#[error("TLS handshake timed out after {0:?}")]
+pub struct HandshakeTimeout(Duration);
+
 #[pin_project::pin_project(project = ConnectProj)]
 #[derive(Debug)]
 pub enum Connect<F, I, H: Service<I>, M> {
-    Connect(#[pin] F, Option<Conditional<H, NoClientTls>>),
+    Connect(
+        #[pin] F,
+        Option<Conditional<H, NoClientTls>>,
+        Option<Duration>,
+    ),
     Handshake {
         #[pin]
         inner: Oneshot<H, I>,
+        #[pin]
+        sleep: Option<time::Sleep>,
+        timeout: Option<Duration>,
         state: Option<(Conditional<(), NoClientTls>, M)>,
     },
 }
@@ -88,9 +112,20 @@ impl From<ServerId> for ClientTls {
 
 impl<L: Clone, C> Client<L, C> {
     pub fn layer(identity: L) -> impl layer::Layer<C, Service = Self> + Clone {
+        Self::layer_with_handshake_timeout(identity, None)
+    }
+
+    /// Fails connections with a [`HandshakeTimeout`] error when a TLS
+    /// handshake does not complete within `handshake_timeout` of the
+    /// connection being established.
+    pub fn layer_with_handshake_timeout(
+        identity: L,
+        handshake_timeout: Option<Duration>,
+    ) -> impl layer::Layer<C, Service = Self> + Clone {
         layer::mk(move |inner| Self {
             inner,
             identity: identity.clone(),
+            handshake_timeout,
         })
     }
 }
@@ -99,7 +134,8 @@ impl<T, L, H, I, C> Service<T> for Client<L, C>
 where
     T: Param<ConditionalClientTls>,
     L: NewService<ClientTls, Service = H>,
-    C: MakeConnection<T, Error = io::Error>,
+    C: MakeConnection<T>,
+    C::Error: Into<Error>,
     C::Connection: io::AsyncRead + io::AsyncWrite + Send + Unpin,
     C::Metadata: Send + Unpin,
     C::Future: Send + 'static,
@@ -110,12 +146,12 @@ where
     I: io::AsyncRead + io::AsyncWrite + Send + Unpin,
 {
     type Response = (io::EitherIo<C::Connection, I>, ConnectMeta<C::Metadata>);
-    type Error = io::Error;
+    type Error = Error;
     type Future = Connect<C::Future, C::Connection, H, C::Metadata>;
 
     #[inline]
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        self.inner.poll_ready(cx)
+        self.inner.poll_ready(cx).map_err(Into::into)
     }
 
     fn call(&mut self, target: T) -> Self::Future {
@@ -128,25 +164,29 @@ where
         };
 
         let connect = self.inner.connect(target);
-        Connect::Connect(connect, Some(handshake))
+        Connect::Connect(connect, Some(handshake), self.handshake_timeout)
     }
 }
 
 impl<F, I, J, H, M> Future for Connect<F, I, H, M>
 where
-    F: TryFuture<Ok = (I, M), Error = io::Error>,
+    F: TryFuture<Ok = (I, M)>,
+    F::Error: Into<Error>,
     H: Service<I, Response = (J, Option<NegotiatedProtocol>), Error = io::Error>,
 {
-    type Output = io::Result<(io::EitherIo<I, J>, ConnectMeta<M>)>;
+    type Output = Result<(io::EitherIo<I, J>, ConnectMeta<M>), Error>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         loop {
             match self.as_mut().project() {
-                ConnectProj::Connect(fut, tls) => {
-                    let (io, socket) = futures::ready!(fut.try_poll(cx))?;
+                ConnectProj::Connect(fut, tls, timeout) => {
+                    let (io, socket) = futures::ready!(fut.try_poll(cx)).map_err(Into::into)?;
+                    let timeout = *timeout;
                     match tls.take().expect("tls handshake must be set") {
                         Conditional::Some(tls) => self.set(Connect::Handshake {
                             inner: tls.oneshot(io),
+                            sleep: timeout.map(time::sleep),
+                            timeout,
                             state: Some((Conditional::Some(()), socket)),
                         }),
                         Conditional::None(reason) => {
@@ -158,8 +198,23 @@ where
                         }
                     }
                 }
-                ConnectProj::Handshake { inner, state } => {
-                    let (io, alpn) = futures::ready!(inner.try_poll(cx))?;
+                ConnectProj::Handshake {
+                    inner,
+                    sleep,
+                    timeout,
+                    state,
+                } => {
+                    let (io, alpn) = match inner.try_poll(cx) {
+                        Poll::Ready(res) => res?,
+                        Poll::Pending => {
+                            if let (Some(sleep), Some(timeout)) = (sleep.as_pin_mut(), *timeout) {
+                                futures::ready!(sleep.poll(cx));
+                                debug!(?timeout, "TLS handshake timed out");
+                                return Poll::Ready(Err(HandshakeTimeout(timeout).into()));
+                            }
+                            return Poll::Pending;
+                        }
+                    };
                     debug!(
                         alpn = alpn
                             .as_ref()
@@ -245,3 +300,49 @@ impl fmt::Debug for AlpnProtocols {
         dbg.finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_stack::{layer::Layer, service_fn};
+
+    #[derive(Clone, Debug)]
+    struct Target(ConditionalClientTls);
+
+    impl Param<ConditionalClientTls> for Target {
+        fn param(&self) -> ConditionalClientTls {
+            self.0.clone()
+        }
+    }
+
+    /// Tests that a connection fails with a `HandshakeTimeout` error when the
+    /// TCP connection is established but the TLS handshake never completes.
+    #[tokio::test(flavor = "current_thread")]
+    async fn handshake_timeout() {
+        let _trace = linkerd_tracing::test::trace_init();
+        time::pause();
+
+        let (client_io, _server_io) = io::duplex(1024);
+        let mut client_io = Some(client_io);
+        let connect = service_fn(move |_: Target| {
+            let io = client_io.take().expect("must only connect once");
+            future::ok::<_, io::Error>((io, ()))
+        });
+        let identity = |_: ClientTls| {
+            service_fn(|_: io::DuplexStream| {
+                future::pending::<io::Result<(io::DuplexStream, Option<NegotiatedProtocol>)>>()
+            })
+        };
+
+        let server_id = "foo.ns1.serviceaccount.identity.linkerd.cluster.local"
+            .parse::<ServerId>()
+            .unwrap();
+        let target = Target(Conditional::Some(server_id.into()));
+        let error = Client::layer_with_handshake_timeout(identity, Some(Duration::from_secs(1)))
+            .layer(connect)
+            .oneshot(target)
+            .await
+            .expect_err("handshake must time out");
+        assert!(error.is::<HandshakeTimeout>(), "{}", error);
+    }
+}
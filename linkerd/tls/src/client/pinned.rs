@@ -0,0 +1,83 @@
+//! Pins server identities to specific certificates.
+//!
+//! Some trusted upstreams present self-signed certificates that cannot be
+//! verified against the proxy's trust roots. Such a server's identity may be
+//! pinned to the SHA-256 fingerprint of its certificate, in which case the
+//! server is verified by comparing its end-entity certificate against the
+//! fingerprint instead of verifying its chain of trust.
+
+use linkerd_identity as id;
+use std::{collections::HashMap, fmt, str::FromStr, sync::Arc};
+use thiserror::Error;
+
+/// The certificates to which server identities are pinned.
+#[derive(Clone, Debug, Default)]
+pub struct PinnedCerts(Arc<HashMap<id::Name, Fingerprint>>);
+
+/// The SHA-256 digest of a DER-encoded certificate.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Fingerprint(pub [u8; 32]);
+
+#[derive(Debug, Error)]
+#[error("a fingerprint must be 32 hex-encoded bytes")]
+pub struct InvalidFingerprint(());
+
+// === impl PinnedCerts ===
+
+impl PinnedCerts {
+    pub fn new(pins: impl IntoIterator<Item = (id::Name, Fingerprint)>) -> Self {
+        Self(Arc::new(pins.into_iter().collect()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the fingerprint of the certificate to which a server's identity
+    /// is pinned, if any.
+    pub fn get(&self, server: &id::Name) -> Option<&Fingerprint> {
+        self.0.get(server)
+    }
+}
+
+impl FromIterator<(id::Name, Fingerprint)> for PinnedCerts {
+    fn from_iter<I: IntoIterator<Item = (id::Name, Fingerprint)>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+// === impl Fingerprint ===
+
+impl FromStr for Fingerprint {
+    type Err = InvalidFingerprint;
+
+    /// Parses a hex-encoded fingerprint, optionally with the colon-separated
+    /// bytes that `openssl x509 -fingerprint` prints.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.trim().replace(':', "");
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(InvalidFingerprint(()));
+        }
+        let mut fingerprint = [0u8; 32];
+        for (i, byte) in fingerprint.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+                .map_err(|_| InvalidFingerprint(()))?;
+        }
+        Ok(Self(fingerprint))
+    }
+}
+
+impl fmt::Debug for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
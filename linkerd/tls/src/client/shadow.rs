@@ -0,0 +1,80 @@
+//! Selects a shadow identity for connections to matching servers.
+//!
+//! A shadow identity is a second set of client credentials--e.g. issued by a
+//! staging CA--that is presented, instead of the proxy's primary identity, on
+//! connections to servers whose identities match a set of suffixes. This allows
+//! a new CA to be validated against a subset of servers before it is rolled out
+//! to all of them.
+
+use super::ClientTls;
+use linkerd_dns_name::Suffix;
+use linkerd_stack::NewService;
+use std::sync::Arc;
+use tracing::debug;
+
+#[cfg(test)]
+mod tests;
+
+/// Selects between primary and shadow client credentials by server identity.
+#[derive(Clone, Debug)]
+pub struct NewShadowClient<N> {
+    primary: N,
+    shadow: Option<Shadow<N>>,
+}
+
+/// Shadow client credentials and the servers to which they are presented.
+#[derive(Clone, Debug)]
+pub struct Shadow<N> {
+    pub servers: ShadowServers,
+    pub identity: N,
+}
+
+/// Suffixes of the server identities to which a shadow identity is presented.
+#[derive(Clone, Debug)]
+pub struct ShadowServers(Arc<[Suffix]>);
+
+// === impl NewShadowClient ===
+
+impl<N> NewShadowClient<N> {
+    pub fn new(primary: N, shadow: Option<Shadow<N>>) -> Self {
+        Self { primary, shadow }
+    }
+}
+
+impl<N: NewService<ClientTls>> NewService<ClientTls> for NewShadowClient<N> {
+    type Service = N::Service;
+
+    fn new_service(&self, tls: ClientTls) -> Self::Service {
+        if let Some(shadow) = &self.shadow {
+            if shadow.servers.matches(&tls) {
+                debug!(server.id = %tls.server_id, "Using shadow identity");
+                return shadow.identity.new_service(tls);
+            }
+        }
+        self.primary.new_service(tls)
+    }
+}
+
+// === impl ShadowServers ===
+
+impl Default for ShadowServers {
+    fn default() -> Self {
+        Self(Arc::new([]))
+    }
+}
+
+impl ShadowServers {
+    pub fn new(suffixes: impl IntoIterator<Item = Suffix>) -> Self {
+        Self(suffixes.into_iter().collect())
+    }
+
+    fn matches(&self, tls: &ClientTls) -> bool {
+        self.0.iter().any(|sfx| sfx.contains(&tls.server_id.0))
+    }
+}
+
+impl FromIterator<Suffix> for ShadowServers {
+    fn from_iter<I: IntoIterator<Item = Suffix>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
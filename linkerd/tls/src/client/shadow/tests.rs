@@ -0,0 +1,62 @@
+use super::*;
+use crate::ServerId;
+
+/// Stands in for client credentials, identifying the identity presented.
+#[derive(Clone, Debug)]
+struct Identity(&'static str);
+
+impl NewService<ClientTls> for Identity {
+    type Service = &'static str;
+
+    fn new_service(&self, _: ClientTls) -> Self::Service {
+        self.0
+    }
+}
+
+fn tls(server_id: &str) -> ClientTls {
+    ServerId(server_id.parse().unwrap()).into()
+}
+
+#[test]
+fn presents_shadow_identity_to_matching_servers() {
+    let new_client = NewShadowClient::new(
+        Identity("primary"),
+        Some(Shadow {
+            servers: ["staging.svc.cluster.local".parse().unwrap()]
+                .into_iter()
+                .collect(),
+            identity: Identity("shadow"),
+        }),
+    );
+
+    assert_eq!(
+        new_client.new_service(tls(
+            "web.ns.serviceaccount.identity.linkerd.staging.svc.cluster.local"
+        )),
+        "shadow"
+    );
+    assert_eq!(
+        new_client.new_service(tls("staging.svc.cluster.local")),
+        "shadow"
+    );
+    assert_eq!(
+        new_client.new_service(tls("web.ns.serviceaccount.identity.linkerd.cluster.local")),
+        "primary"
+    );
+    // Suffixes only match whole labels.
+    assert_eq!(
+        new_client.new_service(tls("prestaging.svc.cluster.local")),
+        "primary"
+    );
+}
+
+#[test]
+fn presents_primary_identity_without_shadow() {
+    let new_client = NewShadowClient::new(Identity("primary"), None);
+    assert_eq!(
+        new_client.new_service(tls(
+            "web.ns.serviceaccount.identity.linkerd.staging.svc.cluster.local"
+        )),
+        "primary"
+    );
+}
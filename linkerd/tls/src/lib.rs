@@ -7,7 +7,10 @@ pub mod server;
 pub use linkerd_identity::LocalId;
 
 pub use self::{
-    client::{Client, ClientTls, ConditionalClientTls, ConnectMeta, NoClientTls, ServerId},
+    client::{
+        Client, ClientTls, ConditionalClientTls, ConnectMeta, Fingerprint, NewShadowClient,
+        NoClientTls, PinnedCerts, ServerId, Shadow, ShadowServers,
+    },
     server::{ClientId, ConditionalServerTls, NewDetectTls, NoServerTls, ServerTls},
 };
 
@@ -35,3 +35,19 @@ pub static BAR_NS1: Entity = Entity {
     crt: include_bytes!("testdata/bar-ns1-ca1/crt.der"),
     key: include_bytes!("testdata/bar-ns1-ca1/key.p8"),
 };
+
+/// A server with a self-signed certificate. Its trust anchors are those of the
+/// mesh, which did not issue its certificate.
+pub static SELF_SIGNED: Entity = Entity {
+    name: "upstream.example.com",
+    trust_anchors: include_bytes!("testdata/ca1.pem"),
+    crt: include_bytes!("testdata/self-signed/crt.der"),
+    key: include_bytes!("testdata/self-signed/key.p8"),
+};
+
+pub static SELF_SIGNED_OTHER: Entity = Entity {
+    name: "upstream.example.com",
+    trust_anchors: include_bytes!("testdata/ca1.pem"),
+    crt: include_bytes!("testdata/self-signed-other/crt.der"),
+    key: include_bytes!("testdata/self-signed-other/key.p8"),
+};
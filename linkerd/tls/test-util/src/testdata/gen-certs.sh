@@ -48,6 +48,28 @@ ee() {
   mv "${ee}.csr" "${ee}/csr.pem"
 }
 
+self_signed() {
+  hostname=$1
+  ee=$2
+
+  openssl req -x509 -newkey ec -pkeyopt ec_paramgen_curve:prime256v1 -nodes \
+    -days 36500 -subj "/CN=${hostname}" \
+    -addext "subjectAltName=DNS:${hostname}" \
+    -addext "basicConstraints=critical,CA:FALSE" \
+    -keyout "${ee}-key.pem" -out "${ee}.pem"
+  mkdir -p "${ee}"
+
+  openssl pkcs8 -topk8 -nocrypt -inform pem -outform der \
+    -in "${ee}-key.pem" \
+    -out "${ee}/key.p8"
+  rm "${ee}-key.pem"
+
+  openssl x509 -inform pem -outform der \
+    -in "${ee}.pem" \
+    -out "${ee}/crt.der"
+  rm "${ee}.pem"
+}
+
 ca 'Cluster-local CA 1' ca1
 ca 'Cluster-local CA 1' ca2 # Same name, different key pair.
 
@@ -58,3 +80,7 @@ ee ca1 default default linkerd
 ee ca1 foo ns1 linkerd
 ee ca2 foo ns1 linkerd # Same, but different CA
 ee ca1 bar ns1 linkerd # Different service.
+
+# A server outside of the mesh with a self-signed certificate.
+self_signed upstream.example.com self-signed
+self_signed upstream.example.com self-signed-other # Same, but different key pair.
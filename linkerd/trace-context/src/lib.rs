@@ -15,6 +15,7 @@ use std::time::SystemTime;
 use thiserror::Error;
 
 const SPAN_ID_LEN: usize = 8;
+const TRACE_ID_LEN: usize = 16;
 
 #[derive(Debug, Default)]
 pub struct Id(Vec<u8>);
@@ -58,11 +59,18 @@ impl<K: SpanSink> SpanSink for Option<K> {
 // === impl Id ===
 
 impl Id {
-    fn new_span_id<R: Rng>(rng: &mut R) -> Self {
+    pub fn new_span_id<R: Rng>(rng: &mut R) -> Self {
         let mut bytes = vec![0; SPAN_ID_LEN];
         rng.fill(bytes.as_mut_slice());
         Self(bytes)
     }
+
+    /// Generates an ID for a span that begins a new trace.
+    pub fn new_trace_id<R: Rng>(rng: &mut R) -> Self {
+        let mut bytes = vec![0; TRACE_ID_LEN];
+        rng.fill(bytes.as_mut_slice());
+        Self(bytes)
+    }
 }
 
 impl From<Id> for Vec<u8> {
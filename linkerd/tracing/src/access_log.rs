@@ -1,3 +1,4 @@
+pub use self::sink::{Sink, SinkConfig};
 use std::fmt;
 use tracing::{field, span, Id, Level, Metadata, Subscriber};
 use tracing_subscriber::{
@@ -8,14 +9,18 @@ use tracing_subscriber::{
     registry::LookupSpan,
 };
 
+mod sink;
+#[cfg(test)]
+mod tests;
+
 pub const TRACE_TARGET: &str = "_access_log";
 
 pub(super) type AccessLogLayer<S> =
     Filtered<Box<dyn Layer<S> + Send + Sync + 'static>, FilterFn, S>;
 
-#[derive(Default)]
 pub(super) struct Writer<F = ApacheCommon> {
     formatter: F,
+    sink: Box<dyn Sink>,
 }
 
 #[derive(Default)]
@@ -34,13 +39,13 @@ struct ApacheCommonVisitor<'writer> {
     writer: format::Writer<'writer>,
 }
 
-pub(super) fn build<S>(format: Format) -> AccessLogLayer<S>
+pub(super) fn build<S>(format: Format, sink: Box<dyn Sink>) -> AccessLogLayer<S>
 where
     S: Subscriber + for<'span> LookupSpan<'span>,
 {
     let writer: Box<dyn Layer<S> + Send + Sync + 'static> = match format {
-        Format::Apache => Box::new(Writer::<ApacheCommon>::default()),
-        Format::Json => Box::new(Writer::<format::JsonFields>::default()),
+        Format::Apache => Box::new(Writer::<ApacheCommon>::new(sink)),
+        Format::Json => Box::new(Writer::<format::JsonFields>::new(sink)),
     };
 
     writer.with_filter(
@@ -54,6 +59,15 @@ where
 
 // === impl Writer ===
 
+impl<F: Default> Writer<F> {
+    fn new(sink: Box<dyn Sink>) -> Self {
+        Self {
+            formatter: F::default(),
+            sink,
+        }
+    }
+}
+
 impl<S, F> Layer<S> for Writer<F>
 where
     S: Subscriber + for<'span> LookupSpan<'span>,
@@ -96,7 +110,7 @@ where
     fn on_close(&self, id: Id, ctx: Context<'_, S>) {
         if let Some(span) = ctx.span(&id) {
             if let Some(fields) = span.extensions().get::<FormattedFields<F>>() {
-                eprintln!("{}", fields.fields);
+                self.sink.write_record(&fields.fields);
             }
         }
     }
@@ -0,0 +1,310 @@
+//! Destinations to which access log records are written.
+//!
+//! By default, records are written to stderr. Records may instead be shipped
+//! directly to a network endpoint--over UDP or TCP, or to a syslog server--so
+//! that access logs may be collected without a file sidecar. Network records
+//! are written by a background thread so that logging never blocks the proxy;
+//! records are dropped while the thread falls behind. When the endpoint cannot
+//! be reached, records are dropped until the connection is retried after an
+//! exponential backoff.
+
+use std::{
+    fmt,
+    io::{self, Write},
+    net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+use tokio::time::Instant;
+
+#[cfg(test)]
+mod tests;
+
+/// Writes access log records.
+pub trait Sink: Send + Sync + 'static {
+    /// Writes a single formatted record, without a trailing newline.
+    fn write_record(&self, record: &str);
+}
+
+/// Configures where access log records are written.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SinkConfig {
+    #[default]
+    Stderr,
+
+    /// Each record is sent as a datagram.
+    Udp(String),
+
+    /// Records are sent as newline-delimited lines over a connection that is
+    /// re-established as needed.
+    Tcp(String),
+
+    /// Each record is sent as an RFC 5424 syslog message over UDP.
+    Syslog(String),
+}
+
+/// Writes records to stderr.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Stderr(());
+
+/// Writes records to a network endpoint from a background thread.
+#[derive(Debug)]
+pub struct NetworkSink {
+    tx: mpsc::SyncSender<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Protocol {
+    Udp,
+    Tcp,
+    Syslog,
+}
+
+enum Conn {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+}
+
+/// Delays reconnection attempts after failures, doubling the delay after each
+/// consecutive failure.
+#[derive(Debug)]
+struct Backoff {
+    delay: Duration,
+    retry_at: Option<Instant>,
+}
+
+/// Limits how often errors are written to stderr, so that an unreachable sink
+/// does not flood it.
+#[derive(Debug, Default)]
+struct ErrorLog {
+    last: Option<Instant>,
+    suppressed: usize,
+}
+
+/// The number of records that may be buffered for a network sink.
+const CAPACITY: usize = 10_000;
+
+/// Bounds the time spent connecting to, and writing to, a network sink, so
+/// that an unresponsive endpoint does not stall the sink indefinitely.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+const MIN_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// At most one error is written to stderr per interval.
+const ERROR_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The priority of syslog messages: the `local0` facility at the
+/// informational severity.
+const SYSLOG_PRIORITY: u8 = 16 * 8 + 6;
+
+// === impl SinkConfig ===
+
+impl SinkConfig {
+    /// Builds a sink, falling back to stderr if a network sink cannot be
+    /// started.
+    pub(crate) fn build(&self) -> Box<dyn Sink> {
+        let (protocol, addr) = match self {
+            Self::Stderr => return Box::new(Stderr(())),
+            Self::Udp(addr) => (Protocol::Udp, addr),
+            Self::Tcp(addr) => (Protocol::Tcp, addr),
+            Self::Syslog(addr) => (Protocol::Syslog, addr),
+        };
+        match NetworkSink::spawn(protocol, addr.clone()) {
+            Ok(sink) => Box::new(sink),
+            Err(error) => {
+                eprintln!("Failed to start access log sink {:?}: {}", self, error);
+                Box::new(Stderr(()))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for SinkConfig {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const ERR: &str = "expected 'stderr' or a 'udp://', 'tcp://', or 'syslog://' address";
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("stderr") {
+            return Ok(Self::Stderr);
+        }
+        let (scheme, addr) = s.split_once("://").ok_or(ERR)?;
+        // Addresses are resolved when records are sent, but they must include
+        // a port.
+        match addr.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {}
+            _ => return Err(ERR),
+        }
+        let addr = addr.to_string();
+        match scheme {
+            s if s.eq_ignore_ascii_case("udp") => Ok(Self::Udp(addr)),
+            s if s.eq_ignore_ascii_case("tcp") => Ok(Self::Tcp(addr)),
+            s if s.eq_ignore_ascii_case("syslog") => Ok(Self::Syslog(addr)),
+            _ => Err(ERR),
+        }
+    }
+}
+
+// === impl Stderr ===
+
+impl Sink for Stderr {
+    fn write_record(&self, record: &str) {
+        eprintln!("{}", record);
+    }
+}
+
+// === impl NetworkSink ===
+
+impl NetworkSink {
+    fn spawn(protocol: Protocol, addr: String) -> io::Result<Self> {
+        let (tx, rx) = mpsc::sync_channel(CAPACITY);
+        thread::Builder::new()
+            .name("accesslog".to_string())
+            .spawn(move || Self::run(protocol, addr, rx))?;
+        Ok(Self { tx })
+    }
+
+    /// Sends records until all of the sink's handles are dropped.
+    fn run(protocol: Protocol, addr: String, rx: mpsc::Receiver<String>) {
+        let mut conn = None;
+        let mut backoff = Backoff::default();
+        let mut errors = ErrorLog::default();
+        for record in rx {
+            if conn.is_none() {
+                // Records are dropped until the connection is retried.
+                if !backoff.is_ready(Instant::now()) {
+                    continue;
+                }
+                match Conn::connect(protocol, &addr) {
+                    Ok(c) => {
+                        backoff.reset();
+                        conn = Some(c);
+                    }
+                    Err(error) => {
+                        backoff.failed(Instant::now());
+                        errors.log(format_args!(
+                            "Failed to connect to access log sink {}: {}",
+                            addr, error
+                        ));
+                        continue;
+                    }
+                }
+            }
+            if let Some(c) = conn.as_mut() {
+                if let Err(error) = c.send(protocol, &record) {
+                    errors.log(format_args!(
+                        "Failed to write to access log sink {}: {}",
+                        addr, error
+                    ));
+                    conn = None;
+                }
+            }
+        }
+    }
+}
+
+impl Sink for NetworkSink {
+    fn write_record(&self, record: &str) {
+        // Records are dropped while the buffer is full.
+        let _ = self.tx.try_send(record.to_string());
+    }
+}
+
+// === impl Conn ===
+
+impl Conn {
+    fn connect(protocol: Protocol, addr: &str) -> io::Result<Self> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "address did not resolve"))?;
+        match protocol {
+            Protocol::Tcp => {
+                let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok(Self::Tcp(stream))
+            }
+            Protocol::Udp | Protocol::Syslog => {
+                let local: SocketAddr = if addr.is_ipv4() {
+                    ([0, 0, 0, 0], 0).into()
+                } else {
+                    ([0u16; 8], 0).into()
+                };
+                let socket = UdpSocket::bind(local)?;
+                socket.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                socket.connect(addr)?;
+                Ok(Self::Udp(socket))
+            }
+        }
+    }
+
+    fn send(&mut self, protocol: Protocol, record: &str) -> io::Result<()> {
+        match self {
+            Self::Tcp(stream) => {
+                stream.write_all(record.as_bytes())?;
+                stream.write_all(b"\n")
+            }
+            Self::Udp(socket) if protocol == Protocol::Syslog => {
+                // The timestamp, hostname, and other header fields are omitted
+                // so that the collector sets them.
+                let msg = format!("<{}>1 - - linkerd-proxy - - - {}", SYSLOG_PRIORITY, record);
+                socket.send(msg.as_bytes()).map(|_| ())
+            }
+            Self::Udp(socket) => socket.send(record.as_bytes()).map(|_| ()),
+        }
+    }
+}
+
+// === impl Backoff ===
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            delay: MIN_BACKOFF,
+            retry_at: None,
+        }
+    }
+}
+
+impl Backoff {
+    /// Returns true if a connection may be attempted.
+    fn is_ready(&self, now: Instant) -> bool {
+        self.retry_at.map_or(true, |at| now >= at)
+    }
+
+    fn failed(&mut self, now: Instant) {
+        self.retry_at = Some(now + self.delay);
+        self.delay = (self.delay * 2).min(MAX_BACKOFF);
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+// === impl ErrorLog ===
+
+impl ErrorLog {
+    fn log(&mut self, error: fmt::Arguments<'_>) {
+        match self.check(Instant::now()) {
+            Some(0) => eprintln!("{}", error),
+            Some(suppressed) => eprintln!("{} ({} similar errors suppressed)", error, suppressed),
+            None => {}
+        }
+    }
+
+    /// Returns the number of errors suppressed since the last error was
+    /// logged, if an error may be logged now.
+    fn check(&mut self, now: Instant) -> Option<usize> {
+        if let Some(last) = self.last {
+            if now.saturating_duration_since(last) < ERROR_LOG_INTERVAL {
+                self.suppressed += 1;
+                return None;
+            }
+        }
+        self.last = Some(now);
+        Some(std::mem::take(&mut self.suppressed))
+    }
+}
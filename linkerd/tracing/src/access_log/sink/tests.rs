@@ -0,0 +1,42 @@
+use super::*;
+
+#[test]
+fn backs_off_reconnects() {
+    let now = Instant::now();
+    let mut backoff = Backoff::default();
+    assert!(backoff.is_ready(now));
+
+    // Each consecutive failure doubles the delay before the next attempt.
+    backoff.failed(now);
+    assert!(!backoff.is_ready(now + MIN_BACKOFF / 2));
+    assert!(backoff.is_ready(now + MIN_BACKOFF));
+    backoff.failed(now);
+    assert!(!backoff.is_ready(now + MIN_BACKOFF));
+    assert!(backoff.is_ready(now + MIN_BACKOFF * 2));
+
+    for _ in 0..10 {
+        backoff.failed(now);
+    }
+    assert!(
+        backoff.is_ready(now + MAX_BACKOFF),
+        "backoff must be bounded"
+    );
+
+    backoff.reset();
+    assert!(backoff.is_ready(now));
+}
+
+#[test]
+fn rate_limits_errors() {
+    let now = Instant::now();
+    let mut errors = ErrorLog::default();
+    assert_eq!(errors.check(now), Some(0));
+    assert_eq!(errors.check(now), None);
+    assert_eq!(errors.check(now + ERROR_LOG_INTERVAL / 2), None);
+    assert_eq!(
+        errors.check(now + ERROR_LOG_INTERVAL),
+        Some(2),
+        "must report the number of suppressed errors"
+    );
+    assert_eq!(errors.check(now + ERROR_LOG_INTERVAL), None);
+}
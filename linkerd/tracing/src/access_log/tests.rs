@@ -0,0 +1,98 @@
+use super::*;
+use std::{
+    io::{BufRead, BufReader},
+    net::{TcpListener, UdpSocket},
+    time::Duration,
+};
+use tracing_subscriber::prelude::*;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Records an access log span with the given sink.
+fn record(sink: SinkConfig) {
+    let subscriber = tracing_subscriber::registry().with(build(Format::Json, sink.build()));
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::span!(
+            target: TRACE_TARGET,
+            Level::INFO,
+            "http",
+            client.addr = "192.0.2.10:40000",
+            status = field::Empty,
+        );
+        span.record("status", &200);
+    });
+}
+
+#[test]
+fn delivers_records_over_udp() {
+    let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    server.set_read_timeout(Some(TIMEOUT)).unwrap();
+    let addr = server.local_addr().unwrap();
+    record(SinkConfig::Udp(addr.to_string()));
+
+    let mut buf = [0; 1024];
+    let n = server.recv(&mut buf).expect("record must be delivered");
+    assert_eq!(
+        std::str::from_utf8(&buf[..n]).unwrap(),
+        r#"{"client.addr":"192.0.2.10:40000","status":200}"#
+    );
+}
+
+#[test]
+fn delivers_syslog_messages() {
+    let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    server.set_read_timeout(Some(TIMEOUT)).unwrap();
+    let addr = server.local_addr().unwrap();
+    record(SinkConfig::Syslog(addr.to_string()));
+
+    let mut buf = [0; 1024];
+    let n = server.recv(&mut buf).expect("record must be delivered");
+    assert_eq!(
+        std::str::from_utf8(&buf[..n]).unwrap(),
+        r#"<134>1 - - linkerd-proxy - - - {"client.addr":"192.0.2.10:40000","status":200}"#
+    );
+}
+
+#[test]
+fn delivers_records_over_tcp() {
+    let server = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    record(SinkConfig::Tcp(addr.to_string()));
+    record(SinkConfig::Tcp(addr.to_string()));
+
+    // Each sink connects once, delimiting its records by newlines.
+    for _ in 0..2 {
+        let (conn, _) = server.accept().expect("sink must connect");
+        conn.set_read_timeout(Some(TIMEOUT)).unwrap();
+        let mut line = String::new();
+        BufReader::new(conn)
+            .read_line(&mut line)
+            .expect("record must be delivered");
+        assert_eq!(
+            line,
+            "{\"client.addr\":\"192.0.2.10:40000\",\"status\":200}\n"
+        );
+    }
+}
+
+#[test]
+fn parses_sinks() {
+    assert_eq!("stderr".parse(), Ok(SinkConfig::Stderr));
+    assert_eq!(
+        "udp://collector.example.com:514".parse(),
+        Ok(SinkConfig::Udp("collector.example.com:514".to_string()))
+    );
+    assert_eq!(
+        "TCP://192.0.2.10:5140".parse(),
+        Ok(SinkConfig::Tcp("192.0.2.10:5140".to_string()))
+    );
+    assert_eq!(
+        "syslog://[2001:db8::1]:514".parse(),
+        Ok(SinkConfig::Syslog("[2001:db8::1]:514".to_string()))
+    );
+    assert!("collector.example.com:514".parse::<SinkConfig>().is_err());
+    assert!("udp://collector.example.com".parse::<SinkConfig>().is_err());
+    assert!("http://collector.example.com:80"
+        .parse::<SinkConfig>()
+        .is_err());
+}
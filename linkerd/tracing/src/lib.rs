@@ -25,6 +25,7 @@ pub use tracing_subscriber::{registry, EnvFilter};
 const ENV_LOG_LEVEL: &str = "LINKERD2_PROXY_LOG";
 const ENV_LOG_FORMAT: &str = "LINKERD2_PROXY_LOG_FORMAT";
 const ENV_ACCESS_LOG: &str = "LINKERD2_PROXY_ACCESS_LOG";
+const ENV_ACCESS_LOG_SINK: &str = "LINKERD2_PROXY_ACCESS_LOG_SINK";
 
 const DEFAULT_LOG_LEVEL: &str = "warn,linkerd=info";
 const DEFAULT_LOG_FORMAT: &str = "PLAIN";
@@ -36,6 +37,7 @@ pub struct Settings {
     format: String,
     start_time: Option<Instant>,
     access_log: Option<access_log::Format>,
+    access_log_sink: access_log::SinkConfig,
     is_test: bool,
 }
 
@@ -71,6 +73,7 @@ impl Settings {
                 .ok()
                 .unwrap_or_else(|| DEFAULT_LOG_FORMAT.to_string()),
             access_log: Self::access_log_format(),
+            access_log_sink: Self::access_log_sink(),
             start_time: Some(start_time),
             is_test: false,
         }
@@ -82,6 +85,7 @@ impl Settings {
             format,
             start_time: None,
             access_log: Self::access_log_format(),
+            access_log_sink: Self::access_log_sink(),
             is_test: true,
         }
     }
@@ -97,6 +101,21 @@ impl Settings {
         }
     }
 
+    /// Access logs are written to stderr unless another sink is configured.
+    fn access_log_sink() -> access_log::SinkConfig {
+        let env = match std::env::var(ENV_ACCESS_LOG_SINK) {
+            Ok(env) => env,
+            Err(_) => return access_log::SinkConfig::default(),
+        };
+        match env.parse() {
+            Ok(sink) => sink,
+            Err(err) => {
+                eprintln!("Invalid {}={:?}: {}", ENV_ACCESS_LOG_SINK, env, err);
+                access_log::SinkConfig::default()
+            }
+        }
+    }
+
     fn timer(&self) -> Uptime {
         self.start_time
             .map(Uptime::starting_at)
@@ -184,7 +203,7 @@ impl Settings {
     /// The log dispatcher handles:
     ///
     /// - process diagnostic logging to stdout;
-    /// - optional access logging to stderr;
+    /// - optional access logging to stderr or a network sink;
     /// - if the `stream` feature is enabled, on-demand log streaming via the
     ///   returned `Handle`
     pub fn build(self) -> (Dispatch, Handle) {
@@ -218,7 +237,11 @@ impl Settings {
         };
 
         // Access logging is optionally enabled process-wide.
-        let registry = registry.with(self.access_log.map(access_log::build));
+        let sink = &self.access_log_sink;
+        let registry = registry.with(
+            self.access_log
+                .map(|format| access_log::build(format, sink.build())),
+        );
 
         // The handle controls the logging system at runtime.
         let handle = Handle {